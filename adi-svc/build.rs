@@ -1,16 +1,76 @@
+use std::process::Command;
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Configure tonic-build to generate Rust code from protobuf
-    // The generated files will be placed in OUT_DIR by default
+    // The generated files will be placed in OUT_DIR by default.
+    // Library builds get the messages and client stubs; server stubs and the
+    // transport helpers follow the `server` and `client` features.
+    let feature = |name: &str| std::env::var_os(format!("CARGO_FEATURE_{}", name)).is_some();
     tonic_build::configure()
-        .build_server(true)
+        .build_server(feature("SERVER"))
         .build_client(true)
+        .build_transport(feature("CLIENT"))
+        // `bytes` fields share the received buffer instead of copying it
+        .bytes(["."])
         .compile(
             &["proto/document_intelligence.proto"],
             &["proto"],
         )?;
     
     println!("cargo:rerun-if-changed=proto/document_intelligence.proto");
+    // Migrations are embedded with `sqlx::migrate!`
+    println!("cargo:rerun-if-changed=migrations");
+    
+    emit_build_info();
     
     Ok(())
 }
 
+/// Git commit, build time and enabled features, read by `BuildInfo::current()`
+///
+/// `GIT_SHA` and `SOURCE_DATE_EPOCH` take precedence, for builds outside a
+/// checkout such as container images and for reproducible builds.
+fn emit_build_info() {
+    let git = |args: &[&str]| {
+        Command::new("git")
+            .args(args)
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|output| output.trim().to_string())
+            .filter(|output| !output.is_empty())
+    };
+    
+    let git_sha = std::env::var("GIT_SHA")
+        .ok()
+        .filter(|sha| !sha.trim().is_empty())
+        .or_else(|| git(&["rev-parse", "HEAD"]))
+        .unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=ADI_GIT_SHA={}", git_sha);
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    if let Some(head) = git(&["rev-parse", "--git-path", "HEAD"]) {
+        println!("cargo:rerun-if-changed={}", head);
+    }
+    if let Some(branch) = git(&["symbolic-ref", "-q", "HEAD"]).and_then(|reference| git(&["rev-parse", "--git-path", &reference])) {
+        println!("cargo:rerun-if-changed={}", branch);
+    }
+    
+    let build_epoch = std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.trim().parse::<u64>().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|elapsed| elapsed.as_secs())
+                .unwrap_or_default()
+        });
+    println!("cargo:rustc-env=ADI_BUILD_EPOCH={}", build_epoch);
+    println!("cargo:rerun-if-env-changed=SOURCE_DATE_EPOCH");
+    
+    let mut features: Vec<String> = std::env::vars()
+        .filter_map(|(name, _)| name.strip_prefix("CARGO_FEATURE_").map(|feature| feature.to_lowercase().replace('_', "-")))
+        .collect();
+    features.sort();
+    println!("cargo:rustc-env=ADI_FEATURES={}", features.join(","));
+}
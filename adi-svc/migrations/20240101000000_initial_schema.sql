@@ -0,0 +1,51 @@
+-- Operations, results and work leases
+--
+-- Idempotent so databases set up by the old `migrate` binary adopt it cleanly.
+
+CREATE TABLE IF NOT EXISTS operations (
+    operation_id VARCHAR(255) PRIMARY KEY,
+    status VARCHAR(50) NOT NULL,
+    model_type VARCHAR(100) NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    last_updated TIMESTAMPTZ NOT NULL
+);
+
+-- Uploaded document metadata
+ALTER TABLE operations
+    ADD COLUMN IF NOT EXISTS document_id VARCHAR(512),
+    ADD COLUMN IF NOT EXISTS filename VARCHAR(512),
+    ADD COLUMN IF NOT EXISTS content_type VARCHAR(255);
+
+-- Content hash for duplicate lookup
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS content_sha256 CHAR(64);
+CREATE INDEX IF NOT EXISTS idx_operations_content_sha256 ON operations(content_sha256);
+
+-- Malware scan verdict for uploaded documents
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS scan_verdict VARCHAR(255);
+
+CREATE TABLE IF NOT EXISTS results (
+    operation_id VARCHAR(255) PRIMARY KEY REFERENCES operations(operation_id) ON DELETE CASCADE,
+    model_id VARCHAR(255) NOT NULL,
+    api_version VARCHAR(50) NOT NULL,
+    content TEXT NOT NULL,
+    pages_data JSONB,
+    tables_data JSONB,
+    key_value_pairs_data JSONB,
+    documents_data JSONB,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+-- Worker leases on succeeded operations (review/export queues)
+CREATE TABLE IF NOT EXISTS work_leases (
+    queue VARCHAR(50) NOT NULL,
+    operation_id VARCHAR(255) NOT NULL REFERENCES operations(operation_id) ON DELETE CASCADE,
+    worker_id VARCHAR(255) NOT NULL,
+    claimed_at TIMESTAMPTZ NOT NULL,
+    lease_expires_at TIMESTAMPTZ NOT NULL,
+    completed_at TIMESTAMPTZ,
+    PRIMARY KEY (queue, operation_id)
+);
+
+CREATE INDEX IF NOT EXISTS idx_operations_status ON operations(status);
+CREATE INDEX IF NOT EXISTS idx_operations_created_at ON operations(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_operations_document_id ON operations(document_id);
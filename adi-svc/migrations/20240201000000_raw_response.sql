@@ -0,0 +1,2 @@
+-- Untouched provider response, kept when STORE_RAW_RESPONSES is enabled
+ALTER TABLE results ADD COLUMN IF NOT EXISTS raw_response JSONB;
@@ -0,0 +1,10 @@
+-- Status transitions of each operation, for support and auditing
+CREATE TABLE IF NOT EXISTS operation_events (
+    id BIGSERIAL PRIMARY KEY,
+    operation_id VARCHAR(255) NOT NULL REFERENCES operations(operation_id) ON DELETE CASCADE,
+    kind VARCHAR(50) NOT NULL,
+    detail TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_operation_events_operation ON operation_events(operation_id, id);
@@ -0,0 +1,16 @@
+-- Mutating API calls, kept for compliance; not pruned with results
+CREATE TABLE IF NOT EXISTS audit_log (
+    id BIGSERIAL PRIMARY KEY,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    principal VARCHAR(255) NOT NULL,
+    action VARCHAR(255) NOT NULL,
+    model VARCHAR(255),
+    document_sha256 CHAR(64),
+    operation_id VARCHAR(255),
+    outcome VARCHAR(20) NOT NULL,
+    status VARCHAR(50) NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_audit_log_created_at ON audit_log(created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_principal ON audit_log(principal, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_operation ON audit_log(operation_id) WHERE operation_id IS NOT NULL;
@@ -0,0 +1,5 @@
+-- Tenant that owns each operation; rows from before multi-tenancy belong to the default tenant
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS tenant_id VARCHAR(64) NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS idx_operations_tenant_created ON operations(tenant_id, created_at DESC);
+CREATE INDEX IF NOT EXISTS idx_operations_tenant_sha256 ON operations(tenant_id, content_sha256) WHERE content_sha256 IS NOT NULL;
@@ -0,0 +1,14 @@
+-- Pages each operation analyzed, filled in when its result arrives
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS page_count INTEGER;
+
+-- Daily usage per tenant and model for chargeback; not pruned with results
+CREATE TABLE IF NOT EXISTS usage (
+    day DATE NOT NULL,
+    tenant_id VARCHAR(64) NOT NULL,
+    model VARCHAR(255) NOT NULL,
+    operations BIGINT NOT NULL DEFAULT 0,
+    pages BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (day, tenant_id, model)
+);
+
+CREATE INDEX IF NOT EXISTS idx_usage_tenant_day ON usage(tenant_id, day);
@@ -0,0 +1,9 @@
+-- Per-tenant operation and page quotas, one per period
+CREATE TABLE IF NOT EXISTS quotas (
+    tenant_id VARCHAR(64) NOT NULL,
+    period VARCHAR(16) NOT NULL,
+    max_operations BIGINT,
+    max_pages BIGINT,
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (tenant_id, period)
+);
@@ -0,0 +1,23 @@
+-- Analysis requests queued with mode=async for the worker pool
+CREATE TABLE IF NOT EXISTS analysis_jobs (
+    job_id VARCHAR(255) PRIMARY KEY,
+    tenant_id VARCHAR(64) NOT NULL,
+    model_type VARCHAR(100) NOT NULL,
+    options JSONB NOT NULL,
+    document_url TEXT,
+    document_id VARCHAR(512),
+    filename VARCHAR(512),
+    content_type VARCHAR(255),
+    scan_verdict VARCHAR(255),
+    status VARCHAR(16) NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    operation_id VARCHAR(255),
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
+
+-- Workers scan pending jobs oldest first
+CREATE INDEX IF NOT EXISTS idx_analysis_jobs_pending
+    ON analysis_jobs (created_at)
+    WHERE status IN ('queued', 'running');
@@ -0,0 +1,7 @@
+-- Priority lanes: workers claim higher ranks first (interactive 10, batch 0)
+ALTER TABLE analysis_jobs ADD COLUMN IF NOT EXISTS priority SMALLINT NOT NULL DEFAULT 10;
+
+DROP INDEX IF EXISTS idx_analysis_jobs_pending;
+CREATE INDEX IF NOT EXISTS idx_analysis_jobs_pending
+    ON analysis_jobs (priority DESC, created_at)
+    WHERE status IN ('queued', 'running');
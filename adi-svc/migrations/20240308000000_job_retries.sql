@@ -0,0 +1,10 @@
+-- Retries with backoff: queued jobs wait until their next attempt is due
+ALTER TABLE analysis_jobs ADD COLUMN IF NOT EXISTS next_attempt_at TIMESTAMPTZ NOT NULL DEFAULT NOW();
+
+-- Failed jobs are now dead-lettered
+UPDATE analysis_jobs SET status = 'dead_lettered' WHERE status = 'failed';
+
+-- Failed operations are traced back to the job that started them
+CREATE INDEX IF NOT EXISTS idx_analysis_jobs_operation
+    ON analysis_jobs (operation_id)
+    WHERE operation_id IS NOT NULL;
@@ -0,0 +1,9 @@
+-- Items ingestion sources have submitted, so each version is analyzed once
+CREATE TABLE IF NOT EXISTS ingested_items (
+    source VARCHAR(1024) NOT NULL,
+    item VARCHAR(1024) NOT NULL,
+    version VARCHAR(255) NOT NULL,
+    operation_id VARCHAR(255),
+    ingested_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (source, item)
+);
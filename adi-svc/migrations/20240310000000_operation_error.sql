@@ -0,0 +1,2 @@
+-- Error Azure reported for a failed operation
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS error JSONB;
@@ -0,0 +1,2 @@
+-- How analyze/auto chose the model of an operation
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS routing JSONB;
@@ -0,0 +1,11 @@
+-- Progress of documents through configured pipelines, one row per run
+CREATE TABLE IF NOT EXISTS pipeline_runs (
+    run_id VARCHAR(255) PRIMARY KEY,
+    pipeline VARCHAR(255) NOT NULL,
+    tenant_id VARCHAR(64) NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    operation_id VARCHAR(255),
+    steps JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,15 @@
+-- Human review of low-confidence results
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS review JSONB;
+CREATE INDEX IF NOT EXISTS idx_operations_review ON operations (tenant_id, (review->>'status'), created_at DESC)
+    WHERE review IS NOT NULL;
+
+-- Every version of a corrected result; revision 0 is the extraction as analyzed
+CREATE TABLE IF NOT EXISTS result_revisions (
+    operation_id VARCHAR(255) NOT NULL REFERENCES operations(operation_id) ON DELETE CASCADE,
+    revision INTEGER NOT NULL,
+    corrections JSONB NOT NULL,
+    reviewer VARCHAR(255),
+    result JSONB NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (operation_id, revision)
+);
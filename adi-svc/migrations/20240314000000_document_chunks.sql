@@ -0,0 +1,19 @@
+-- Embedded content chunks for semantic retrieval. Needs the pgvector
+-- extension; skipped on servers without it, where embeddings cannot be stored
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_available_extensions WHERE name = 'vector') THEN
+        CREATE EXTENSION IF NOT EXISTS vector;
+        CREATE TABLE IF NOT EXISTS document_chunks (
+            operation_id VARCHAR(255) NOT NULL REFERENCES operations(operation_id) ON DELETE CASCADE,
+            chunk_index INTEGER NOT NULL,
+            tenant_id VARCHAR(64) NOT NULL,
+            content TEXT NOT NULL,
+            embedding vector NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL,
+            PRIMARY KEY (operation_id, chunk_index)
+        );
+        CREATE INDEX IF NOT EXISTS idx_document_chunks_tenant ON document_chunks (tenant_id);
+    END IF;
+END
+$$;
@@ -0,0 +1,3 @@
+-- Page each embedded chunk was taken from, for search results; null when the
+-- result had no lines to split by page
+ALTER TABLE IF EXISTS document_chunks ADD COLUMN IF NOT EXISTS page_number INTEGER;
@@ -0,0 +1,3 @@
+-- How the stored spans count characters; null for results stored before the
+-- option existed, which used Azure's default of textElements
+ALTER TABLE results ADD COLUMN IF NOT EXISTS string_index_type VARCHAR(32);
@@ -0,0 +1,12 @@
+-- Bulk NDJSON exports of results and the stored file each produced
+CREATE TABLE IF NOT EXISTS export_jobs (
+    export_id VARCHAR(255) PRIMARY KEY,
+    tenant_id VARCHAR(64) NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    filter JSONB NOT NULL,
+    results BIGINT NOT NULL DEFAULT 0,
+    document_id VARCHAR(512),
+    error TEXT,
+    created_at TIMESTAMPTZ NOT NULL,
+    updated_at TIMESTAMPTZ NOT NULL
+);
@@ -0,0 +1,3 @@
+-- Soft deletion: deleted operations are hidden until restored or purged
+ALTER TABLE operations ADD COLUMN IF NOT EXISTS deleted_at TIMESTAMPTZ;
+CREATE INDEX IF NOT EXISTS idx_operations_deleted_at ON operations(deleted_at) WHERE deleted_at IS NOT NULL;
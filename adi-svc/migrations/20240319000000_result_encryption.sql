@@ -0,0 +1,13 @@
+-- Envelope-encrypted results: a sealed row keeps its result, raw response or
+-- revision in the sealed columns and leaves the plaintext ones empty. The key
+-- ID columns name the KEK that wrapped each sealed value's data key.
+ALTER TABLE results ADD COLUMN IF NOT EXISTS sealed BYTEA;
+ALTER TABLE results ADD COLUMN IF NOT EXISTS encryption_key_id TEXT;
+ALTER TABLE results ADD COLUMN IF NOT EXISTS sealed_raw_response BYTEA;
+ALTER TABLE results ADD COLUMN IF NOT EXISTS raw_response_key_id TEXT;
+CREATE INDEX IF NOT EXISTS idx_results_encryption_key_id ON results(encryption_key_id) WHERE encryption_key_id IS NOT NULL;
+
+ALTER TABLE result_revisions ADD COLUMN IF NOT EXISTS sealed BYTEA;
+ALTER TABLE result_revisions ADD COLUMN IF NOT EXISTS encryption_key_id TEXT;
+ALTER TABLE result_revisions ALTER COLUMN corrections DROP NOT NULL;
+ALTER TABLE result_revisions ALTER COLUMN result DROP NOT NULL;
@@ -0,0 +1,5 @@
+-- Envelope-encrypted chunk content: a sealed chunk keeps its text in `sealed`
+-- and leaves `content` empty. Skipped where pgvector, and so the table, is missing.
+ALTER TABLE IF EXISTS document_chunks ADD COLUMN IF NOT EXISTS sealed BYTEA;
+ALTER TABLE IF EXISTS document_chunks ADD COLUMN IF NOT EXISTS encryption_key_id TEXT;
+ALTER TABLE IF EXISTS document_chunks ALTER COLUMN content DROP NOT NULL;
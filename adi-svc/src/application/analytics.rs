@@ -0,0 +1,137 @@
+/// Analytics export use case
+///
+/// Writes operations and their flattened fields as Parquet, one folder per
+/// day of creation (`date=2024-03-01/operations.parquet` next to
+/// `fields.parquet`), the layout lakehouse tables read as partitions.
+/// Exporting a day again replaces its files.
+
+use bytes::Bytes;
+use chrono::{Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::domain::{field_rows, fields_parquet, operations_parquet, DomainError, OperationListQuery, OperationStatus};
+use super::errors::ApplicationResult;
+use super::ports::DatasetWriterPort;
+use super::services::DocumentIntelligenceService;
+use super::training::SkippedDocument;
+
+/// Longest range one export covers
+pub const MAX_EXPORT_DAYS: i64 = 366;
+
+const PARQUET_CONTENT_TYPE: &str = "application/vnd.apache.parquet";
+
+/// Summary of one export
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct AnalyticsExportReport {
+    /// Folder of each exported day
+    pub folders: Vec<String>,
+    pub operations: u64,
+    pub fields: u64,
+    /// Succeeded operations whose fields were left out because their result could not be read
+    pub skipped: Vec<SkippedDocument>,
+}
+
+/// Parquet export service
+pub struct AnalyticsExportService {
+    service: Arc<DocumentIntelligenceService>,
+    writer: Arc<dyn DatasetWriterPort>,
+    prefix: String,
+}
+
+impl std::fmt::Debug for AnalyticsExportService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AnalyticsExportService")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AnalyticsExportService {
+    /// Service writing under `prefix` of the writer's destination
+    pub fn new(service: Arc<DocumentIntelligenceService>, writer: Arc<dyn DatasetWriterPort>, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/');
+        Self {
+            service,
+            writer,
+            prefix: if prefix.is_empty() { String::new() } else { format!("{}/", prefix) },
+        }
+    }
+
+    /// Export the operations of every tenant created from `from` to `to`, both included (UTC days)
+    pub async fn export(&self, from: NaiveDate, to: NaiveDate) -> ApplicationResult<AnalyticsExportReport> {
+        let days = (to - from).num_days() + 1;
+        if days < 1 {
+            return Err(DomainError::ValidationError(format!("export range ends on {}, before it starts on {}", to, from)).into());
+        }
+        if days > MAX_EXPORT_DAYS {
+            return Err(DomainError::ValidationError(format!("export ranges cover at most {} days", MAX_EXPORT_DAYS)).into());
+        }
+
+        let mut report = AnalyticsExportReport::default();
+        for day in from.iter_days().take(days as usize) {
+            self.export_day(day, &mut report).await?;
+        }
+        info!(
+            "Exported {} operations and {} fields created {} to {} ({} skipped)",
+            report.operations,
+            report.fields,
+            from,
+            to,
+            report.skipped.len()
+        );
+        Ok(report)
+    }
+
+    /// Export the last complete UTC day, as the scheduled export does
+    pub async fn export_yesterday(&self) -> ApplicationResult<AnalyticsExportReport> {
+        let yesterday = Utc::now().date_naive() - Duration::days(1);
+        self.export(yesterday, yesterday).await
+    }
+
+    async fn export_day(&self, day: NaiveDate, report: &mut AnalyticsExportReport) -> ApplicationResult<()> {
+        let start = day.and_time(chrono::NaiveTime::MIN).and_utc();
+        let mut operations = Vec::new();
+        let (mut before, mut before_id) = (None, None);
+        loop {
+            let page = self
+                .service
+                .operations_created(start, start + Duration::days(1), before, before_id.as_deref(), OperationListQuery::MAX_LIMIT)
+                .await?;
+            before = page.last().map(|last| last.created_at);
+            before_id = page.last().map(|last| last.operation_id.clone());
+            let more = page.len() == OperationListQuery::MAX_LIMIT as usize;
+            operations.extend(page);
+            if !more {
+                break;
+            }
+        }
+
+        let mut fields = Vec::new();
+        for operation in operations.iter().filter(|operation| operation.status == OperationStatus::Succeeded) {
+            match self.service.completed_result(&operation.tenant_id, &operation.operation_id).await {
+                Ok((_, result)) => fields.extend(field_rows(operation, &result)),
+                Err(e) => {
+                    warn!("Leaving fields of operation {} out of the analytics export: {}", operation.operation_id, e);
+                    report.skipped.push(SkippedDocument {
+                        operation_id: operation.operation_id.clone(),
+                        reason: e.to_string(),
+                    });
+                }
+            }
+        }
+
+        let folder = format!("{}date={}/", self.prefix, day.format("%Y-%m-%d"));
+        self.write(&format!("{}operations.parquet", folder), operations_parquet(&operations)?).await?;
+        self.write(&format!("{}fields.parquet", folder), fields_parquet(&fields)?).await?;
+        report.folders.push(folder);
+        report.operations += operations.len() as u64;
+        report.fields += fields.len() as u64;
+        Ok(())
+    }
+
+    async fn write(&self, path: &str, content: Vec<u8>) -> ApplicationResult<()> {
+        self.writer.put(path, PARQUET_CONTENT_TYPE, Bytes::from(content)).await
+    }
+}
@@ -0,0 +1,75 @@
+/// Caller deadlines
+///
+/// REST and gRPC callers may bound how long they wait for a response. The
+/// presentation layer runs the call inside `with_deadline`, which drops the
+/// work when the deadline passes; adapters read `remaining()` to stop their
+/// own outbound calls (Azure, the tracker) at the same moment.
+
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use super::errors::{ApplicationError, ApplicationResult};
+
+tokio::task_local! {
+    static DEADLINE: Instant;
+}
+
+/// Run `future`, failing with `DeadlineExceeded` if it is still running at `deadline`
+///
+/// Nested deadlines keep the earlier of the two. Tasks spawned from `future`
+/// do not inherit the deadline, so background work outlives the call.
+pub async fn with_deadline<T, F>(deadline: Option<Instant>, future: F) -> ApplicationResult<T>
+where
+    F: Future<Output = ApplicationResult<T>>,
+{
+    let Some(deadline) = deadline else {
+        return future.await;
+    };
+    let deadline = DEADLINE.try_with(|outer| deadline.min(*outer)).unwrap_or(deadline);
+    DEADLINE
+        .scope(deadline, async move {
+            tokio::time::timeout_at(deadline, future)
+                .await
+                .unwrap_or(Err(ApplicationError::DeadlineExceeded))
+        })
+        .await
+}
+
+/// Time left before the current call's deadline; `None` when it has none
+pub fn remaining() -> Option<Duration> {
+    DEADLINE
+        .try_with(|deadline| deadline.saturating_duration_since(Instant::now()))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_with_deadline() {
+        assert_eq!(remaining(), None);
+        let unbounded = with_deadline(None, async { Ok(remaining()) }).await.unwrap();
+        assert_eq!(unbounded, None);
+
+        let outer = Instant::now() + Duration::from_secs(60);
+        let inner = with_deadline(Some(outer), async {
+            let left = remaining().unwrap();
+            assert!(left <= Duration::from_secs(60));
+            // A later inner deadline does not extend the outer one
+            with_deadline(Some(outer + Duration::from_secs(60)), async { Ok(remaining()) }).await
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert!(inner <= Duration::from_secs(60));
+
+        let expired = with_deadline(Some(Instant::now() + Duration::from_millis(20)), async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        })
+        .await;
+        assert!(matches!(expired, Err(ApplicationError::DeadlineExceeded)));
+    }
+}
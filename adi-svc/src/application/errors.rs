@@ -1,5 +1,5 @@
 use thiserror::Error;
-use crate::domain::DomainError;
+use crate::domain::{AzureError, DomainError};
 
 /// Application-level errors
 #[derive(Error, Debug)]
@@ -10,9 +10,85 @@ pub enum ApplicationError {
     #[error("Azure service error: {0}")]
     AzureService(String),
     
+    #[error("Azure error: {0}")]
+    Azure(Box<AzureError>),
+    
+    #[error("Azure rate limit exceeded: {error}")]
+    AzureThrottled {
+        error: Box<AzureError>,
+        /// How long Azure asked callers to wait, when it said
+        retry_after_secs: Option<u64>,
+    },
+    
+    #[error("Azure service unavailable: {0}")]
+    AzureUnavailable(String),
+    
     #[error("Operation not found: {0}")]
     OperationNotFound(String),
     
+    #[error("Document not found: {0}")]
+    DocumentNotFound(String),
+    
+    #[error("Upload not found: {0}")]
+    UploadNotFound(String),
+    
+    #[error("Upload offset mismatch: expected {expected}")]
+    UploadOffsetMismatch { expected: u64 },
+    
+    #[error("Lease not held: {0}")]
+    LeaseNotHeld(String),
+    
+    #[error("Permission denied: {0}")]
+    PermissionDenied(String),
+    
+    #[error("Invalid signed URL: {0}")]
+    InvalidSignature(String),
+    
+    #[error("Malware detected: {0}")]
+    MalwareDetected(String),
+    
+    #[error("Quota exceeded: {detail}; resets at {resets_at}")]
+    QuotaExceeded {
+        detail: String,
+        resets_at: chrono::DateTime<chrono::Utc>,
+    },
+    
+    #[error("Job not found: {0}")]
+    JobNotFound(String),
+    
+    #[error("Job cannot be retried: {0}")]
+    JobNotRetryable(String),
+    
+    #[error("Quota not found: {0}")]
+    QuotaNotFound(String),
+    
+    #[error("Page not found: {0}")]
+    PageNotFound(String),
+    
+    #[error("Table not found: {0}")]
+    TableNotFound(String),
+    
+    #[error("Output template not found: {0}")]
+    TemplateNotFound(String),
+    
+    #[error("Pipeline not found: {0}")]
+    PipelineNotFound(String),
+    
+    #[error("Pipeline run not found: {0}")]
+    PipelineRunNotFound(String),
+    
+    #[error("Export not found: {0}")]
+    ExportNotFound(String),
+    
+    #[error("Result not available: {0}")]
+    ResultNotAvailable(String),
+    
+    #[error("Operation {operation_id} already has revision {revision}; reload it and correct again")]
+    RevisionConflict { operation_id: String, revision: u32 },
+    
+    #[error("Deadline exceeded before the call completed")]
+    DeadlineExceeded,
+    
     #[error("Analysis failed: {0}")]
     AnalysisFailed(String),
     
@@ -29,5 +105,24 @@ impl From<anyhow::Error> for ApplicationError {
     }
 }
 
+impl ApplicationError {
+    /// Whether the same request might succeed if tried again later
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Self::Azure(error) => error.status.is_some_and(|status| status >= 500),
+            Self::AzureService(_) | Self::AzureThrottled { .. } | Self::AzureUnavailable(_) | Self::Internal(_) => true,
+            _ => false,
+        }
+    }
+    
+    /// The error Azure reported, when it caused this one
+    pub fn azure_error(&self) -> Option<&AzureError> {
+        match self {
+            Self::Azure(error) | Self::AzureThrottled { error, .. } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
 pub type ApplicationResult<T> = Result<T, ApplicationError>;
 
@@ -0,0 +1,199 @@
+/// Bulk export use case
+///
+/// Exports run in the background, streaming each page of matching results
+/// into document storage as it is read, so an export of any size is never
+/// held in memory. The finished file is a stored document of the tenant,
+/// downloaded through a signed link and purged with other documents.
+///
+/// Progress is recorded after every page. An export whose progress stops
+/// for longer than the stale period was left behind by an instance that
+/// stopped, and is marked failed by [`ExportService::spawn_recovery`].
+
+use bytes::Bytes;
+use futures::stream::{self, Stream, StreamExt};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn, Instrument};
+
+use crate::domain::{export_line, ExportFilter, ExportJob, ExportStatus, OperationListQuery, OperationStatus, TenantId};
+use crate::infrastructure::TaskSupervisor;
+use super::errors::{ApplicationError, ApplicationResult};
+use super::ports::{ExportJobPort, SignedUrl};
+use super::services::DocumentIntelligenceService;
+
+/// Media type of export files
+pub const NDJSON_CONTENT_TYPE: &str = "application/x-ndjson";
+
+/// A running export without progress for this long is assumed abandoned
+const STALE_EXPORT_SECS: i64 = 900;
+
+/// Export service
+pub struct ExportService {
+    service: Arc<DocumentIntelligenceService>,
+    jobs: Arc<dyn ExportJobPort>,
+    supervisor: TaskSupervisor,
+}
+
+impl std::fmt::Debug for ExportService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ExportService").finish_non_exhaustive()
+    }
+}
+
+/// Where paging through the tenant's operations has got to
+struct Cursor {
+    before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Last operation read at `before`, so ties at a page boundary are not skipped
+    before_id: Option<String>,
+    done: bool,
+}
+
+impl ExportService {
+    pub fn new(service: Arc<DocumentIntelligenceService>, jobs: Arc<dyn ExportJobPort>) -> Self {
+        Self { service, jobs, supervisor: TaskSupervisor::new() }
+    }
+
+    /// Run exports under the server's supervisor, so shutdown stops them
+    pub fn with_supervisor(mut self, supervisor: TaskSupervisor) -> Self {
+        self.supervisor = supervisor;
+        self
+    }
+
+    /// Start exporting the tenant's results matching `filter`
+    ///
+    /// Returns the job as recorded before anything is written; its progress
+    /// is read back with [`Self::job`].
+    pub async fn start(self: &Arc<Self>, tenant: TenantId, filter: ExportFilter) -> ApplicationResult<ExportJob> {
+        let job = ExportJob::new(tenant, filter.normalize()?);
+        self.jobs.create_export(&job).await?;
+        info!("Started export {} for tenant {}", job.export_id, job.tenant_id);
+
+        let this = self.clone();
+        let started = job.clone();
+        let span = tracing::Span::current();
+        self.supervisor.spawn("export", move |shutdown| {
+            let this = this.clone();
+            let started = started.clone();
+            async move { this.execute(started, shutdown).await }.instrument(span.clone())
+        });
+        Ok(job)
+    }
+
+    /// Mark failed the running exports that stopped making progress
+    pub async fn recover_interrupted(&self) -> ApplicationResult<usize> {
+        let stale = self.jobs.stale_exports(chrono::Duration::seconds(STALE_EXPORT_SECS)).await?;
+        for mut job in stale.iter().cloned() {
+            warn!("Export {} was interrupted; marking it failed", job.export_id);
+            job.fail("Interrupted before it finished".to_string());
+            self.jobs.update_export(&job).await?;
+        }
+        Ok(stale.len())
+    }
+
+    /// Recover interrupted exports now and then once every stale period
+    pub fn spawn_recovery(self: &Arc<Self>) {
+        let this = self.clone();
+        self.supervisor.spawn("export-recovery", move |shutdown| {
+            let this = this.clone();
+            async move {
+                loop {
+                    if let Err(e) = this.recover_interrupted().await {
+                        error!("Failed to recover interrupted exports: {}", e);
+                    }
+                    tokio::select! {
+                        _ = shutdown.cancelled() => return,
+                        _ = tokio::time::sleep(std::time::Duration::from_secs(STALE_EXPORT_SECS as u64)) => {}
+                    }
+                }
+            }
+        });
+    }
+
+    /// An export belonging to `tenant`
+    pub async fn job(&self, tenant: &TenantId, export_id: &str) -> ApplicationResult<ExportJob> {
+        self.jobs
+            .get_export(export_id)
+            .await?
+            .filter(|job| &job.tenant_id == tenant)
+            .ok_or_else(|| ApplicationError::ExportNotFound(export_id.to_string()))
+    }
+
+    /// Signed link to a finished export's file, valid for `expires_in`
+    pub async fn download_url(&self, job: &ExportJob, expires_in: chrono::Duration) -> ApplicationResult<Option<SignedUrl>> {
+        match (&job.status, &job.document_id) {
+            (ExportStatus::Succeeded, Some(document_id)) => {
+                Ok(Some(self.service.document_url(&job.tenant_id, document_id, expires_in).await?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn execute(&self, mut job: ExportJob, shutdown: CancellationToken) {
+        let written = AtomicU64::new(0);
+        let lines = self.lines(&job, &written).boxed();
+        let filename = job.filename();
+        let stored = tokio::select! {
+            stored = self.service.store_document_stream(&job.tenant_id, &filename, NDJSON_CONTENT_TYPE, lines) => stored,
+            _ = shutdown.cancelled() => Err(ApplicationError::Internal("Interrupted by shutdown".to_string())),
+        };
+        match stored {
+            Ok(document_id) => {
+                job.succeed(document_id, written.into_inner());
+                info!("Export {} wrote {} results", job.export_id, job.results);
+            }
+            Err(e) => {
+                warn!("Export {} failed: {}", job.export_id, e);
+                job.fail(e.to_string());
+            }
+        }
+        if let Err(e) = self.jobs.update_export(&job).await {
+            error!("Failed to record export {}: {}", job.export_id, e);
+        }
+    }
+
+    /// The file's lines, one chunk per page of the tenant's succeeded operations
+    fn lines<'a>(
+        &'a self,
+        job: &'a ExportJob,
+        written: &'a AtomicU64,
+    ) -> impl Stream<Item = ApplicationResult<Bytes>> + Send + 'a {
+        let start = Cursor { before: job.filter.to, before_id: None, done: false };
+        stream::try_unfold(start, move |cursor| async move {
+            if cursor.done {
+                return Ok(None);
+            }
+            let query = OperationListQuery {
+                status: Some(OperationStatus::Succeeded),
+                before: cursor.before,
+                before_id: cursor.before_id,
+                limit: OperationListQuery::MAX_LIMIT,
+                ..OperationListQuery::default()
+            };
+            let page = self.service.list_operations(&job.tenant_id, &query).await?;
+
+            let mut chunk = Vec::new();
+            for operation in page.iter().filter(|operation| job.filter.matches_operation(operation)) {
+                let (operation, result) = self.service.completed_result(&job.tenant_id, &operation.operation_id).await?;
+                if job.filter.matches_result(&result) {
+                    chunk.extend(export_line(&operation, &result)?);
+                    written.fetch_add(1, Ordering::Relaxed);
+                }
+            }
+
+            let mut progress = job.clone();
+            progress.progress(written.load(Ordering::Relaxed));
+            if let Err(e) = self.jobs.update_export(&progress).await {
+                warn!("Failed to record progress of export {}: {}", job.export_id, e);
+            }
+
+            // Pages are newest first, so nothing older than the range can match
+            let last = page.last();
+            let before = last.map(|operation| operation.created_at);
+            let done = page.len() < OperationListQuery::MAX_LIMIT as usize
+                || job.filter.from.zip(before).is_some_and(|(from, last)| last < from);
+            let before_id = last.map(|operation| operation.operation_id.clone());
+            Ok(Some((Bytes::from(chunk), Cursor { before, before_id, done })))
+        })
+    }
+}
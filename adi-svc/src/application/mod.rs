@@ -6,8 +6,22 @@
 pub mod ports;
 pub mod services;
 pub mod errors;
+pub mod retention;
+pub mod pipelines;
+pub mod training;
+#[cfg(feature = "analytics")]
+pub mod analytics;
+pub mod exports;
+pub mod deadline;
+pub mod request_id;
 
 pub use ports::*;
 pub use services::*;
 pub use errors::*;
+pub use retention::*;
+pub use pipelines::*;
+pub use training::*;
+#[cfg(feature = "analytics")]
+pub use analytics::*;
+pub use exports::*;
 
@@ -0,0 +1,299 @@
+/// Pipeline use case
+///
+/// Runs configured pipelines for one document at a time, in the background,
+/// recording each step's progress through the [`PipelineRunPort`].
+///
+/// A run whose progress stops for longer than the analyze step may wait was
+/// left behind by an instance that stopped; [`PipelineService::spawn_recovery`]
+/// fails it at the step it had reached.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use serde_json::json;
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn, Instrument};
+
+use crate::domain::{
+    validate_result, AnalysisOperation, AnalysisResult, AnalyzeDocumentRequest, LifecycleEvent, LifecycleEventKind,
+    PipelineDefinition, PipelineRun, PipelineStatus, PipelineStep, RoutingDecision, TenantId,
+};
+use super::errors::{ApplicationError, ApplicationResult};
+use super::ports::{EventPublisherPort, PipelineRunPort, WebhookPort};
+use super::services::DocumentIntelligenceService;
+use crate::infrastructure::TaskSupervisor;
+
+/// How much longer than the analyze step's timeout a run may go without progress
+const STALE_RUN_MARGIN: Duration = Duration::from_secs(300);
+
+/// What earlier steps of a run produced for later ones
+#[derive(Default)]
+struct RunState {
+    decision: Option<RoutingDecision>,
+    analyzed: Option<(AnalysisOperation, AnalysisResult)>,
+}
+
+/// Pipeline service
+pub struct PipelineService {
+    service: Arc<DocumentIntelligenceService>,
+    runs: Arc<dyn PipelineRunPort>,
+    pipelines: HashMap<String, PipelineDefinition>,
+    event_publisher: Option<Arc<dyn EventPublisherPort>>,
+    webhooks: Option<Arc<dyn WebhookPort>>,
+    poll_interval: Duration,
+    timeout: Duration,
+    supervisor: TaskSupervisor,
+}
+
+impl std::fmt::Debug for PipelineService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PipelineService")
+            .field("pipelines", &self.pipeline_names())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PipelineService {
+    pub fn new(
+        service: Arc<DocumentIntelligenceService>,
+        runs: Arc<dyn PipelineRunPort>,
+        pipelines: Vec<PipelineDefinition>,
+    ) -> Self {
+        Self {
+            service,
+            runs,
+            pipelines: pipelines
+                .into_iter()
+                .map(|pipeline| (pipeline.name.clone(), pipeline))
+                .collect(),
+            event_publisher: None,
+            webhooks: None,
+            poll_interval: Duration::from_secs(1),
+            timeout: Duration::from_secs(600),
+            supervisor: TaskSupervisor::new(),
+        }
+    }
+
+    /// Run pipelines under the server's supervisor, so shutdown stops them
+    pub fn with_supervisor(mut self, supervisor: TaskSupervisor) -> Self {
+        self.supervisor = supervisor;
+        self
+    }
+
+    pub fn with_event_publisher(mut self, publisher: Arc<dyn EventPublisherPort>) -> Self {
+        self.event_publisher = Some(publisher);
+        self
+    }
+
+    pub fn with_webhooks(mut self, webhooks: Arc<dyn WebhookPort>) -> Self {
+        self.webhooks = Some(webhooks);
+        self
+    }
+
+    /// How often the analyze step polls its operation, and how long it waits in all
+    pub fn with_polling(mut self, poll_interval: Duration, timeout: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self.timeout = timeout;
+        self
+    }
+
+    /// Names of the configured pipelines, sorted
+    pub fn pipeline_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.pipelines.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Start running pipeline `name` for a document
+    ///
+    /// Returns the run as recorded before its first step; the steps run in
+    /// the background and their progress is read back with [`Self::run`].
+    pub async fn start(self: &Arc<Self>, name: &str, request: AnalyzeDocumentRequest) -> ApplicationResult<PipelineRun> {
+        let definition = self
+            .pipelines
+            .get(name)
+            .ok_or_else(|| ApplicationError::PipelineNotFound(name.to_string()))?;
+        request.source.validate().map_err(ApplicationError::Domain)?;
+
+        let run = PipelineRun::new(definition, request.tenant_id.clone());
+        self.runs.create_run(&run).await?;
+        info!("Started pipeline {} run {}", name, run.run_id);
+
+        let this = self.clone();
+        let started = run.clone();
+        let span = tracing::Span::current();
+        self.supervisor.spawn("pipeline-run", move |shutdown| {
+            let this = this.clone();
+            let started = started.clone();
+            let request = request.clone();
+            async move {
+                this.execute(started, request, shutdown).await;
+            }
+            .instrument(span.clone())
+        });
+        Ok(run)
+    }
+
+    /// Fail the running runs that stopped making progress
+    pub async fn recover_interrupted(&self) -> ApplicationResult<usize> {
+        let stale_after = chrono::Duration::from_std(self.stale_after())
+            .map_err(|e| ApplicationError::Configuration(format!("Invalid pipeline timeout: {}", e)))?;
+        let stale = self.runs.stale_runs(stale_after).await?;
+        for mut run in stale.iter().cloned() {
+            warn!("Pipeline {} run {} was interrupted; marking it failed", run.pipeline, run.run_id);
+            run.interrupt("Interrupted before it finished".to_string());
+            self.runs.update_run(&run).await?;
+        }
+        Ok(stale.len())
+    }
+
+    /// Recover interrupted runs now and then once every stale period
+    pub fn spawn_recovery(self: &Arc<Self>) {
+        let this = self.clone();
+        self.supervisor.spawn("pipeline-recovery", move |shutdown| {
+            let this = this.clone();
+            async move {
+                loop {
+                    if let Err(e) = this.recover_interrupted().await {
+                        error!("Failed to recover interrupted pipeline runs: {}", e);
+                    }
+                    tokio::select! {
+                        _ = shutdown.cancelled() => return,
+                        _ = tokio::time::sleep(this.stale_after()) => {}
+                    }
+                }
+            }
+        });
+    }
+
+    /// The longest a live run goes without recording progress, with a margin
+    fn stale_after(&self) -> Duration {
+        self.timeout + STALE_RUN_MARGIN
+    }
+
+    /// A run of pipeline `name` belonging to `tenant`
+    pub async fn run(&self, tenant: &TenantId, name: &str, run_id: &str) -> ApplicationResult<PipelineRun> {
+        self.runs
+            .get_run(run_id)
+            .await?
+            .filter(|run| &run.tenant_id == tenant && run.pipeline == name)
+            .ok_or_else(|| ApplicationError::PipelineRunNotFound(run_id.to_string()))
+    }
+
+    /// Run every step in order, stopping at the first that fails
+    async fn execute(&self, mut run: PipelineRun, request: AnalyzeDocumentRequest, shutdown: CancellationToken) -> PipelineRun {
+        let steps = match self.pipelines.get(&run.pipeline) {
+            Some(definition) => definition.steps.clone(),
+            None => return run,
+        };
+        let mut state = RunState::default();
+        for (index, step) in steps.iter().enumerate() {
+            run.start_step(index);
+            self.save(&run).await;
+            let outcome = tokio::select! {
+                outcome = self.run_step(step, &mut run, &mut state, &request) => outcome.map_err(|e| e.to_string()),
+                _ = shutdown.cancelled() => Err("Interrupted by shutdown".to_string()),
+            };
+            if let Err(reason) = &outcome {
+                warn!("Pipeline {} run {} failed at {}: {}", run.pipeline, run.run_id, step.as_str(), reason);
+            }
+            run.finish_step(index, outcome);
+            self.save(&run).await;
+            if run.status != PipelineStatus::Running {
+                break;
+            }
+        }
+        info!("Pipeline {} run {} {}", run.pipeline, run.run_id, run.status.as_str());
+        run
+    }
+
+    /// Run one step, returning what it did
+    async fn run_step(
+        &self,
+        step: &PipelineStep,
+        run: &mut PipelineRun,
+        state: &mut RunState,
+        request: &AnalyzeDocumentRequest,
+    ) -> ApplicationResult<Option<String>> {
+        match step {
+            PipelineStep::Classify => {
+                let decision = self.service.route_document(request).await?;
+                let detail = format!(
+                    "{} ({}) routes to {}",
+                    decision.doc_type.as_deref().unwrap_or("unclassified"),
+                    decision.method.as_str(),
+                    decision.model_id
+                );
+                state.decision = Some(decision);
+                Ok(Some(detail))
+            }
+            PipelineStep::Analyze { .. } => {
+                let request = request.clone();
+                let operation = match (step.model()?, state.decision.take()) {
+                    (Some(target), _) => self.service.analyze_with(request, &target).await?,
+                    (None, Some(decision)) => self.service.analyze_routed(request, decision).await?,
+                    (None, None) => self.service.analyze_document(request).await?,
+                };
+                run.operation_id = Some(operation.operation_id.clone());
+                self.save(run).await;
+
+                let analyzed = self
+                    .service
+                    .wait_for_result(&run.tenant_id, &operation.operation_id, self.poll_interval, self.timeout)
+                    .await?;
+                let detail = format!("operation {} succeeded with {}", operation.operation_id, analyzed.1.model_id);
+                state.analyzed = Some(analyzed);
+                Ok(Some(detail))
+            }
+            PipelineStep::Validate { required_fields, min_confidence } => {
+                let (_, result) = analyzed(state)?;
+                let problems = validate_result(result, required_fields, *min_confidence);
+                if !problems.is_empty() {
+                    return Err(ApplicationError::AnalysisFailed(problems.join("; ")));
+                }
+                Ok(Some(format!("{} required fields present", required_fields.len())))
+            }
+            PipelineStep::PublishEvent => {
+                let publisher = self.event_publisher.as_ref().ok_or_else(|| {
+                    ApplicationError::Configuration("Event publishing is not configured".to_string())
+                })?;
+                let (operation, result) = analyzed(state)?;
+                let event = LifecycleEvent::new(LifecycleEventKind::Processed, operation)
+                    .with_summary(result)
+                    .with_pipeline(&run.pipeline);
+                publisher.publish(&event).await?;
+                Ok(Some(format!("published event {}", event.event_id)))
+            }
+            PipelineStep::Webhook { url } => {
+                let webhooks = self.webhooks.as_ref().ok_or_else(|| {
+                    ApplicationError::Configuration("Webhook delivery is not configured".to_string())
+                })?;
+                let (operation, result) = analyzed(state)?;
+                let payload = json!({
+                    "run": run,
+                    "model": operation.model_type.as_str(),
+                    "routing": operation.routing,
+                    "summary": result.summary(),
+                });
+                webhooks.deliver(url, &payload).await?;
+                Ok(Some(format!("delivered to {}", url)))
+            }
+        }
+    }
+
+    /// Record a run's progress; a failed write is logged so the run carries on
+    async fn save(&self, run: &PipelineRun) {
+        if let Err(e) = self.runs.update_run(run).await {
+            error!("Failed to record pipeline run {}: {}", run.run_id, e);
+        }
+    }
+}
+
+/// The operation and result of the run's analyze step
+fn analyzed(state: &RunState) -> ApplicationResult<(&AnalysisOperation, &AnalysisResult)> {
+    state
+        .analyzed
+        .as_ref()
+        .map(|(operation, result)| (operation, result))
+        .ok_or_else(|| ApplicationError::Internal("Step needs the result of an analyze step".to_string()))
+}
@@ -5,10 +5,17 @@
 /// depends on abstractions, not concretions.
 
 use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use serde::{Deserialize, Serialize};
 use crate::domain::{
-    AnalyzeDocumentRequest, AnalysisOperation, AnalysisResult, ModelType,
+    AnalysisJob, AnalyzeDocumentRequest, AnalysisOperation, AnalysisResult, DocumentClassification, DocumentFormat,
+    DocumentMetadata, DocumentSource, AuditEntry, ExportJob, AuditQuery, ChunkMatch, DocumentPage, EmbeddedChunk, FieldMatch, FieldQuery, ImagePreprocessing,
+    LifecycleEvent, ModelType, OperationEvent, OperationEventKind, OperationListQuery, OperationStats, OperationStatsQuery, PipelineRun, Quota, QuotaPeriod, RedactionBox, ResultFields, ResultRevision, RoutingMethod, ScanVerdict, SearchDocument,
+    TenantId, UsageQuery, UsageRecord, WorkLease, WorkQueue,
 };
-use super::errors::ApplicationResult;
+use super::errors::{ApplicationError, ApplicationResult};
 
 /// Port for document intelligence operations
 #[async_trait]
@@ -25,29 +32,275 @@ pub trait DocumentIntelligencePort: Send + Sync {
         operation_id: &str,
     ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)>;
     
+    /// Like `get_analysis_result`, also returning the untouched
+    /// `analyzeResult` payload when the adapter has one
+    async fn get_analysis_result_raw(
+        &self,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>, Option<serde_json::Value>)> {
+        let (operation, result) = self.get_analysis_result(operation_id).await?;
+        Ok((operation, result, None))
+    }
+    
     /// Check if a custom model exists
     async fn validate_custom_model(&self, model_id: &str) -> ApplicationResult<bool>;
 }
 
+/// Inclusive byte range within a stored document, as in HTTP `Range`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+impl ByteRange {
+    /// Number of bytes covered by the range
+    pub fn length(&self) -> u64 {
+        self.end - self.start + 1
+    }
+    
+    /// Whether the range lies within a document of `total_size` bytes
+    pub fn fits(&self, total_size: u64) -> bool {
+        self.start <= self.end && self.end < total_size
+    }
+}
+
+/// Streaming body of a stored document (or a range of it)
+pub struct DocumentStream {
+    /// Size of the whole document
+    pub total_size: u64,
+    /// Range being served, `None` for the whole document
+    pub range: Option<ByteRange>,
+    pub body: BoxStream<'static, std::io::Result<Bytes>>,
+}
+
+impl DocumentStream {
+    /// Number of bytes `body` will yield
+    pub fn content_length(&self) -> u64 {
+        self.range.map_or(self.total_size, |range| range.length())
+    }
+}
+
+/// Progress of a resumable upload
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadState {
+    pub upload_id: String,
+    pub filename: String,
+    pub content_type: String,
+    /// Bytes received so far; the next chunk must start here
+    pub offset: u64,
+    /// Declared size, if the client announced it
+    pub total_size: Option<u64>,
+}
+
+/// Time-limited link to a stored document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SignedUrl {
+    pub url: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+fn resumable_uploads_unsupported<T>() -> ApplicationResult<T> {
+    Err(ApplicationError::Configuration(
+        "Resumable uploads are not supported by this storage backend".to_string(),
+    ))
+}
+
 /// Port for document storage (optional - for uploaded files)
+///
+/// Documents and uploads live in a per-tenant namespace: an identifier only
+/// resolves for the tenant it was created under.
 #[async_trait]
 pub trait DocumentStoragePort: Send + Sync {
     /// Store a document and return its identifier
     async fn store_document(
         &self,
+        tenant: &TenantId,
         filename: &str,
         content_type: &str,
-        data: Vec<u8>,
+        data: Bytes,
     ) -> ApplicationResult<String>;
     
+    /// Store a document written as a stream, e.g. an export, and return its identifier
+    ///
+    /// The default buffers the stream into `store_document`; adapters that
+    /// can write incrementally should override it. An error in the stream
+    /// fails the write and leaves nothing stored.
+    async fn store_stream(
+        &self,
+        tenant: &TenantId,
+        filename: &str,
+        content_type: &str,
+        body: BoxStream<'_, ApplicationResult<Bytes>>,
+    ) -> ApplicationResult<String> {
+        let data = body
+            .try_fold(Vec::new(), |mut data, chunk| async move {
+                data.extend_from_slice(&chunk);
+                Ok(data)
+            })
+            .await?;
+        self.store_document(tenant, filename, content_type, Bytes::from(data)).await
+    }
+    
     /// Retrieve a document by identifier
-    async fn retrieve_document(&self, document_id: &str) -> ApplicationResult<Vec<u8>>;
+    async fn retrieve_document(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<Bytes>;
+    
+    /// Size of a stored document in bytes
+    async fn document_size(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<u64> {
+        Ok(self.retrieve_document(tenant, document_id).await?.len() as u64)
+    }
+    
+    /// Stream a document, or a byte range of it
+    ///
+    /// The default buffers the whole document; adapters that can read
+    /// incrementally should override it so large downloads stay flat in memory.
+    async fn open_document(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        range: Option<ByteRange>,
+    ) -> ApplicationResult<DocumentStream> {
+        let data = self.retrieve_document(tenant, document_id).await?;
+        let total_size = data.len() as u64;
+        let body = match range {
+            Some(range) if range.fits(total_size) => {
+                data.slice(range.start as usize..=range.end as usize)
+            }
+            Some(range) => {
+                return Err(ApplicationError::Internal(format!(
+                    "Range {}-{} outside document of {} bytes",
+                    range.start, range.end, total_size
+                )))
+            }
+            None => data,
+        };
+        
+        Ok(DocumentStream {
+            total_size,
+            range,
+            body: stream::once(async move { Ok(body) }).boxed(),
+        })
+    }
+    
+    /// Begin a resumable upload
+    async fn create_upload(
+        &self,
+        _tenant: &TenantId,
+        _metadata: &DocumentMetadata,
+        _total_size: Option<u64>,
+    ) -> ApplicationResult<UploadState> {
+        resumable_uploads_unsupported()
+    }
+    
+    /// Current progress of a resumable upload
+    async fn upload_state(&self, _tenant: &TenantId, _upload_id: &str) -> ApplicationResult<UploadState> {
+        resumable_uploads_unsupported()
+    }
+    
+    /// Append `data` at `offset`, which must equal the bytes received so far
+    async fn append_upload(
+        &self,
+        _tenant: &TenantId,
+        _upload_id: &str,
+        _offset: u64,
+        _data: Bytes,
+    ) -> ApplicationResult<UploadState> {
+        resumable_uploads_unsupported()
+    }
+    
+    /// Turn a finished upload into a stored document, returning its identifier
+    async fn complete_upload(
+        &self,
+        _tenant: &TenantId,
+        _upload_id: &str,
+    ) -> ApplicationResult<(String, UploadState)> {
+        resumable_uploads_unsupported()
+    }
+    
+    /// Discard a resumable upload and any bytes received
+    async fn abort_upload(&self, _tenant: &TenantId, _upload_id: &str) -> ApplicationResult<()> {
+        resumable_uploads_unsupported()
+    }
     
     /// Delete a document by identifier
-    async fn delete_document(&self, document_id: &str) -> ApplicationResult<()>;
+    async fn delete_document(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<()>;
+    
+    /// Get a signed URL for the document that stops working after `expires_in`
+    async fn get_document_url(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        expires_in: chrono::Duration,
+    ) -> ApplicationResult<SignedUrl>;
     
-    /// Get a URL for accessing the document
-    async fn get_document_url(&self, document_id: &str) -> ApplicationResult<String>;
+    /// Check a link issued by `get_document_url`, for backends whose links
+    /// are served by this service rather than by the storage provider
+    async fn verify_document_url(
+        &self,
+        _tenant: &TenantId,
+        _document_id: &str,
+        _expires: i64,
+        _signature: &str,
+    ) -> ApplicationResult<()> {
+        Err(ApplicationError::InvalidSignature(
+            "Document links are served by the storage provider".to_string(),
+        ))
+    }
+    
+    /// Delete documents of every tenant stored before the cutoff, returning how many were removed
+    async fn purge_documents(&self, cutoff: DateTime<Utc>) -> ApplicationResult<u64>;
+}
+
+/// Row counts removed by a tracker prune
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PrunedRows {
+    pub operations: u64,
+    pub results: u64,
+}
+
+/// Port for scanning uploaded bytes for malware (optional)
+#[async_trait]
+pub trait MalwareScanPort: Send + Sync {
+    /// Scan a document's bytes
+    async fn scan(&self, data: &[u8]) -> ApplicationResult<ScanVerdict>;
+}
+
+/// Port for cleaning up image uploads before submission (optional)
+#[async_trait]
+pub trait ImagePreprocessPort: Send + Sync {
+    /// Apply `options` to an image of `format`, returning the bytes to submit
+    async fn preprocess(
+        &self,
+        data: Bytes,
+        format: DocumentFormat,
+        options: &ImagePreprocessing,
+    ) -> ApplicationResult<Vec<u8>>;
+}
+
+/// Port for burning redactions into a copy of a source document (optional)
+#[async_trait]
+pub trait RedactionRenderPort: Send + Sync {
+    /// Black out `boxes` in a document of `format`, returning the redacted copy as a PDF
+    async fn render_redacted(
+        &self,
+        data: Bytes,
+        format: DocumentFormat,
+        boxes: &[RedactionBox],
+    ) -> ApplicationResult<Vec<u8>>;
+}
+
+/// Port for classifying documents ahead of `analyze/auto` routing (optional)
+#[async_trait]
+pub trait DocumentClassifierPort: Send + Sync {
+    /// How decisions based on this classifier are recorded
+    fn method(&self) -> RoutingMethod;
+    
+    /// Most likely type of the document, or `None` when nothing matched
+    async fn classify(
+        &self,
+        source: &DocumentSource,
+        filename: Option<&str>,
+    ) -> ApplicationResult<Option<DocumentClassification>>;
 }
 
 /// Port for operation tracking (optional - for async operations)
@@ -59,9 +312,22 @@ pub trait OperationTrackerPort: Send + Sync {
     /// Retrieve an operation by ID
     async fn get_operation(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisOperation>>;
     
-    /// Update an operation
+    /// Update an operation; whether it is deleted is only changed by `set_deleted`
     async fn update_operation(&self, operation: &AnalysisOperation) -> ApplicationResult<()>;
     
+    /// Soft-delete an operation at `deleted_at`, or restore it with `None`
+    async fn set_deleted(
+        &self,
+        operation_id: &str,
+        deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+    ) -> ApplicationResult<()>;
+    
+    /// Append an entry to an operation's event history
+    async fn record_event(&self, event: &OperationEvent) -> ApplicationResult<()>;
+    
+    /// An operation's event history, oldest first
+    async fn list_events(&self, operation_id: &str) -> ApplicationResult<Vec<OperationEvent>>;
+    
     /// Store a result for an operation
     async fn store_result(
         &self,
@@ -71,12 +337,373 @@ pub trait OperationTrackerPort: Send + Sync {
     
     /// Retrieve a result by operation ID
     async fn get_result(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisResult>>;
+    
+    /// Store the provider's untouched response for a stored result
+    async fn store_raw_response(
+        &self,
+        operation_id: &str,
+        raw: &serde_json::Value,
+    ) -> ApplicationResult<()>;
+    
+    /// Retrieve the provider's untouched response, if one was stored
+    async fn get_raw_response(&self, operation_id: &str) -> ApplicationResult<Option<serde_json::Value>>;
+    
+    /// Retrieve only the selected sections of a result; adapters that can
+    /// skip loading unselected sections should override this
+    async fn get_result_fields(
+        &self,
+        operation_id: &str,
+        fields: &ResultFields,
+    ) -> ApplicationResult<Option<AnalysisResult>> {
+        Ok(self.get_result(operation_id).await?.map(|result| result.project(fields)))
+    }
+    
+    /// Retrieve a single page of a stored result; `None` when there is no
+    /// result or it has no such page
+    async fn get_result_page(
+        &self,
+        operation_id: &str,
+        page_number: i32,
+    ) -> ApplicationResult<Option<DocumentPage>> {
+        let fields = ResultFields {
+            pages: true,
+            ..ResultFields::none()
+        };
+        Ok(self
+            .get_result_fields(operation_id, &fields)
+            .await?
+            .and_then(|result| result.pages.into_iter().find(|page| page.page_number == page_number)))
+    }
+    
+    /// Key-value pairs and document fields of a stored result passing
+    /// `query`; `None` when there is no result
+    async fn query_result_fields(
+        &self,
+        operation_id: &str,
+        query: &FieldQuery,
+    ) -> ApplicationResult<Option<Vec<FieldMatch>>> {
+        let fields = ResultFields {
+            key_value_pairs: true,
+            documents: true,
+            ..ResultFields::none()
+        };
+        Ok(self
+            .get_result_fields(operation_id, &fields)
+            .await?
+            .map(|result| result.query_fields(query)))
+    }
+    
+    /// A tenant's operations passing `query`'s filters, newest first
+    async fn list_operations(
+        &self,
+        tenant: &TenantId,
+        query: &OperationListQuery,
+    ) -> ApplicationResult<Vec<AnalysisOperation>>;
+    
+    /// Operations of every tenant created in `[from, to)`, newest first; for paging,
+    /// only those sorting after `(before, before_id)` in that order when `before` is set
+    async fn list_operations_created(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        before: Option<DateTime<Utc>>,
+        before_id: Option<&str>,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>>;
+    
+    /// Aggregates of a tenant's operations passing `query`, ordered by group
+    async fn operation_stats(
+        &self,
+        tenant: &TenantId,
+        query: &OperationStatsQuery,
+    ) -> ApplicationResult<Vec<OperationStats>>;
+    
+    /// A tenant's operations whose uploaded content has this SHA-256, newest first
+    async fn find_operations_by_sha256(
+        &self,
+        tenant: &TenantId,
+        sha256: &str,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>>;
+    
+    /// The earliest operation of a tenant that analyzed a stored document,
+    /// skipping soft-deleted ones unless `include_deleted`
+    async fn find_operation_by_document(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        include_deleted: bool,
+    ) -> ApplicationResult<Option<AnalysisOperation>>;
+    
+    /// Delete operations (and their results) last updated before the cutoff
+    async fn prune_operations(&self, cutoff: DateTime<Utc>) -> ApplicationResult<PrunedRows>;
+    
+    /// Delete operations (and their results) soft-deleted before the cutoff,
+    /// returning them so their documents can go too
+    async fn purge_deleted_operations(&self, cutoff: DateTime<Utc>) -> ApplicationResult<Vec<AnalysisOperation>>;
+}
+
+/// Port for leasing succeeded operations to external workers (optional)
+///
+/// Claims are exclusive: an operation is held by at most one worker per queue
+/// until its lease expires, it is released, or it is completed.
+/// Workers only see the operations of the tenant they act for, and the
+/// review queue only hands out operations flagged `needs_review`.
+#[async_trait]
+pub trait WorkQueuePort: Send + Sync {
+    /// Lease up to `limit` of the tenant's succeeded operations that are unclaimed or whose lease expired
+    async fn claim(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        worker_id: &str,
+        lease: chrono::Duration,
+        limit: u32,
+    ) -> ApplicationResult<Vec<WorkLease>>;
+    
+    /// Extend a live lease held by `worker_id`, `None` if it is no longer held
+    async fn heartbeat(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+        lease: chrono::Duration,
+    ) -> ApplicationResult<Option<WorkLease>>;
+    
+    /// Mark a leased item done so it is never handed out again
+    async fn complete(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+    ) -> ApplicationResult<bool>;
+    
+    /// Give up a lease so another worker can claim the item
+    async fn release(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+    ) -> ApplicationResult<bool>;
+}
+
+/// Port for the queue of analysis jobs submitted with `mode=async` (optional)
+///
+/// Jobs are handed out highest priority first, oldest first within a priority,
+/// each to one worker at a time. Queued jobs wait until their next attempt is
+/// due. A job left running longer than the stale period is assumed abandoned
+/// and handed out again.
+#[async_trait]
+pub trait JobQueuePort: Send + Sync {
+    async fn enqueue_job(&self, job: &AnalysisJob) -> ApplicationResult<()>;
+    
+    /// Mark the next due queued or stale running job as running and return it
+    async fn claim_job(&self, stale_after: chrono::Duration) -> ApplicationResult<Option<AnalysisJob>>;
+    
+    /// Store a job's new status, attempts, schedule, operation and error
+    async fn update_job(&self, job: &AnalysisJob) -> ApplicationResult<()>;
+    
+    async fn get_job(&self, job_id: &str) -> ApplicationResult<Option<AnalysisJob>>;
+    
+    /// The job whose latest submission started `operation_id`
+    async fn job_for_operation(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisJob>>;
+}
+
+/// Port remembering what ingestion sources have already submitted (optional)
+///
+/// Items are keyed by source (e.g. a container URL) and name; the version
+/// (e.g. an ETag) tells a replaced item from one already analyzed.
+#[async_trait]
+pub trait IngestLedgerPort: Send + Sync {
+    /// Version of `item` last ingested from `source`
+    async fn ingested_version(&self, source: &str, item: &str) -> ApplicationResult<Option<String>>;
+    
+    /// Remember that `version` of `item` was ingested, with the operation it started if any
+    async fn record_ingested(
+        &self,
+        source: &str,
+        item: &str,
+        version: &str,
+        operation_id: Option<&str>,
+    ) -> ApplicationResult<()>;
+}
+
+/// Port for the compliance audit log of mutating API calls (optional)
+///
+/// Entries are append-only and are not removed by result retention.
+#[async_trait]
+pub trait AuditLogPort: Send + Sync {
+    async fn record(&self, entry: &AuditEntry) -> ApplicationResult<()>;
+    
+    /// Entries passing `query`'s filters, newest first, at most `query.limit`
+    async fn query(&self, query: &AuditQuery) -> ApplicationResult<Vec<AuditEntry>>;
+}
+
+/// Port for per-tenant usage metering (optional)
+///
+/// Usage is kept as daily totals and, like the audit log, outlives result retention.
+#[async_trait]
+pub trait UsagePort: Send + Sync {
+    /// Add one operation of `pages` pages to the tenant's total for `day` and `model`
+    async fn record_usage(
+        &self,
+        tenant: &TenantId,
+        day: chrono::NaiveDate,
+        model: ModelType,
+        pages: u32,
+    ) -> ApplicationResult<()>;
+    
+    /// Daily totals within the query's range, ordered by day, tenant and model
+    async fn usage(&self, query: &UsageQuery) -> ApplicationResult<Vec<UsageRecord>>;
+}
+
+/// Port for announcing operation lifecycle events to downstream systems (optional)
+///
+/// Delivery is at most once: events are published after the change is
+/// tracked, and a failed publish does not fail the call that caused it.
+#[async_trait]
+pub trait EventPublisherPort: Send + Sync {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()>;
+}
+
+/// Port for making succeeded operations searchable (optional)
+///
+/// Documents are pushed once the result is stored and again when it is
+/// corrected; a failed push is logged rather than failing the call.
+#[async_trait]
+pub trait SearchIndexPort: Send + Sync {
+    /// Add the operation's document to the index, replacing any earlier version
+    async fn index(&self, document: &SearchDocument) -> ApplicationResult<()>;
+}
+
+/// Port for turning text into embedding vectors (optional)
+#[async_trait]
+pub trait EmbeddingProviderPort: Send + Sync {
+    /// One vector per text, in the order given
+    async fn embed(&self, texts: &[String]) -> ApplicationResult<Vec<Vec<f32>>>;
+}
+
+/// Port for storing the embedded chunks of analyzed documents (optional)
+#[async_trait]
+pub trait EmbeddingStorePort: Send + Sync {
+    /// Replace the operation's chunks with `chunks`
+    async fn store_chunks(&self, operation_id: &str, tenant: &TenantId, chunks: &[EmbeddedChunk]) -> ApplicationResult<()>;
+    
+    /// The tenant's `limit` chunks closest to `embedding` by cosine distance, closest first
+    async fn search_chunks(&self, tenant: &TenantId, embedding: &[f32], limit: u32) -> ApplicationResult<Vec<ChunkMatch>>;
+}
+
+/// Port for POSTing pipeline notifications to webhook URLs (optional)
+#[async_trait]
+pub trait WebhookPort: Send + Sync {
+    /// Deliver `payload` as JSON, failing unless the receiver answers with success
+    async fn deliver(&self, url: &str, payload: &serde_json::Value) -> ApplicationResult<()>;
+}
+
+/// Port for the per-step progress of pipeline runs (optional)
+#[async_trait]
+pub trait PipelineRunPort: Send + Sync {
+    async fn create_run(&self, run: &PipelineRun) -> ApplicationResult<()>;
+    
+    /// Store a run's new status, operation and steps
+    async fn update_run(&self, run: &PipelineRun) -> ApplicationResult<()>;
+    
+    async fn get_run(&self, run_id: &str) -> ApplicationResult<Option<PipelineRun>>;
+    
+    /// Running runs not updated for longer than the stale period
+    async fn stale_runs(&self, stale_after: chrono::Duration) -> ApplicationResult<Vec<PipelineRun>>;
+}
+
+/// Port for per-model latency and outcome metrics (optional)
+///
+/// Calls are made on the request path, so implementations must not block.
+pub trait OperationMetricsPort: Send + Sync {
+    /// A submission to the analysis backend, how long it took and whether it was accepted
+    fn record_submit(&self, tenant: &TenantId, model: ModelType, latency: std::time::Duration, accepted: bool);
+    
+    /// An operation first seen finished as `outcome`, `elapsed` after it was submitted
+    fn record_completion(
+        &self,
+        tenant: &TenantId,
+        model: ModelType,
+        outcome: OperationEventKind,
+        elapsed: std::time::Duration,
+    );
+}
+
+/// Port for the progress of bulk export jobs (optional)
+#[async_trait]
+pub trait ExportJobPort: Send + Sync {
+    async fn create_export(&self, job: &ExportJob) -> ApplicationResult<()>;
+    
+    /// Store a job's new status, file and result count
+    async fn update_export(&self, job: &ExportJob) -> ApplicationResult<()>;
+    
+    async fn get_export(&self, export_id: &str) -> ApplicationResult<Option<ExportJob>>;
+    
+    /// Running exports not updated for longer than the stale period
+    async fn stale_exports(&self, stale_after: chrono::Duration) -> ApplicationResult<Vec<ExportJob>>;
+}
+
+/// Port for keeping every revision of corrected results (optional)
+#[async_trait]
+pub trait ResultRevisionPort: Send + Sync {
+    /// Store a new revision, failing with `RevisionConflict` if the operation already has one with its number
+    async fn store_revision(&self, revision: &ResultRevision) -> ApplicationResult<()>;
+    
+    /// An operation's revisions, oldest first
+    async fn list_revisions(&self, operation_id: &str) -> ApplicationResult<Vec<ResultRevision>>;
+}
+
+/// Port for writing files of a training dataset (optional)
+#[async_trait]
+pub trait DatasetWriterPort: Send + Sync {
+    /// Write `content` at `path`, replacing any file already there
+    async fn put(&self, path: &str, content_type: &str, content: Bytes) -> ApplicationResult<()>;
+}
+
+/// Port for storing per-tenant quotas (optional)
+///
+/// A tenant has at most one quota per period; enforcement reads usage from the [`UsagePort`].
+#[async_trait]
+pub trait QuotaPort: Send + Sync {
+    /// Create or replace the tenant's quota for `quota.period`
+    async fn set_quota(&self, quota: &Quota) -> ApplicationResult<()>;
+    
+    /// Remove the tenant's quota for `period`, returning whether there was one
+    async fn delete_quota(&self, tenant: &TenantId, period: QuotaPeriod) -> ApplicationResult<bool>;
+    
+    /// Quotas of one tenant, or of all tenants when unset, ordered by tenant and period
+    async fn list_quotas(&self, tenant: Option<&TenantId>) -> ApplicationResult<Vec<Quota>>;
+}
+
+/// Port for checking that a dependency answers (optional)
+#[async_trait]
+pub trait HealthCheckPort: Send + Sync {
+    /// Name the dependency is reported under, e.g. `postgres`
+    fn dependency(&self) -> &'static str;
+    
+    /// Succeed when the dependency answers
+    async fn check_health(&self) -> ApplicationResult<()>;
+}
+
+/// Outcome of checking one dependency
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyHealth {
+    pub name: &'static str,
+    pub healthy: bool,
+    pub latency_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{DocumentSource, AnalyzeOptions, OperationStatus};
+    use crate::domain::{AnalyzeOptions, OperationStatus};
 
     struct MockDocumentIntelligence;
 
@@ -110,6 +737,8 @@ mod tests {
             source: DocumentSource::Url("https://example.com/doc.pdf".to_string()),
             model_type: ModelType::Read,
             options: AnalyzeOptions::default(),
+            metadata: None,
+            tenant_id: TenantId::default(),
         };
         
         let result = port.analyze_document(request).await;
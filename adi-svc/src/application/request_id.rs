@@ -0,0 +1,55 @@
+/// Request ids
+///
+/// Every REST and gRPC call runs under an id, the caller's `x-request-id` or
+/// a fresh one, so its logs, error responses and Azure calls can be
+/// correlated. Adapters read it with `current()`.
+
+use std::future::Future;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Longest caller-supplied id that is kept
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// The caller's id when it is usable, otherwise a new one
+///
+/// Usable ids are 1 to 128 visible ASCII characters, so they are safe to
+/// echo in headers and logs.
+pub fn resolve(presented: Option<&str>) -> String {
+    presented
+        .map(str::trim)
+        .filter(|id| !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN)
+        .filter(|id| id.bytes().all(|b| b.is_ascii_graphic()))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string())
+}
+
+/// Run `future` under `id`
+pub async fn with_request_id<F: Future>(id: String, future: F) -> F::Output {
+    REQUEST_ID.scope(id, future).await
+}
+
+/// Id of the call being served; `None` outside one, e.g. in background jobs
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_request_id() {
+        assert_eq!(resolve(Some(" support-1234 ")), "support-1234");
+        for unusable in [None, Some(""), Some("two words"), Some("naïve")] {
+            assert!(uuid::Uuid::parse_str(&resolve(unusable)).is_ok(), "{:?}", unusable);
+        }
+        assert_ne!(resolve(Some(&"x".repeat(129))).len(), 129);
+
+        assert_eq!(current(), None);
+        let id = with_request_id("abc".to_string(), async { current() }).await;
+        assert_eq!(id.as_deref(), Some("abc"));
+    }
+}
@@ -0,0 +1,139 @@
+/// Result retention use case
+///
+/// Prunes operations, results and uploaded documents that are older than
+/// the configured time-to-live, and purges soft-deleted operations once they
+/// can no longer be restored.
+
+use std::sync::Arc;
+use chrono::Utc;
+use tracing::{info, warn};
+
+use super::errors::ApplicationResult;
+use super::ports::{DocumentStoragePort, OperationTrackerPort};
+
+/// Summary of a single retention pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionReport {
+    pub operations_pruned: u64,
+    pub results_pruned: u64,
+    pub documents_pruned: u64,
+    /// Soft-deleted operations removed for good
+    pub deleted_purged: u64,
+}
+
+/// Retention service
+pub struct RetentionService {
+    tracker_adapter: Option<Arc<dyn OperationTrackerPort>>,
+    storage_adapter: Option<Arc<dyn DocumentStoragePort>>,
+    ttl: Option<chrono::Duration>,
+    deleted_ttl: Option<chrono::Duration>,
+}
+
+impl RetentionService {
+    pub fn new(
+        tracker_adapter: Option<Arc<dyn OperationTrackerPort>>,
+        storage_adapter: Option<Arc<dyn DocumentStoragePort>>,
+        ttl_days: Option<u32>,
+    ) -> Self {
+        Self {
+            tracker_adapter,
+            storage_adapter,
+            ttl: ttl_days.map(|days| chrono::Duration::days(i64::from(days))),
+            deleted_ttl: None,
+        }
+    }
+
+    /// Keep soft-deleted operations restorable for this many days, then purge them
+    pub fn with_deleted_ttl(mut self, days: u32) -> Self {
+        self.deleted_ttl = Some(chrono::Duration::days(i64::from(days)));
+        self
+    }
+
+    /// Delete everything older than the retention window
+    pub async fn prune_expired(&self) -> ApplicationResult<RetentionReport> {
+        let mut report = RetentionReport::default();
+
+        if let Some(ttl) = self.ttl {
+            let cutoff = Utc::now() - ttl;
+            info!("Pruning data older than {}", cutoff);
+
+            if let Some(tracker) = &self.tracker_adapter {
+                let pruned = tracker.prune_operations(cutoff).await?;
+                report.operations_pruned = pruned.operations;
+                report.results_pruned = pruned.results;
+            }
+
+            if let Some(storage) = &self.storage_adapter {
+                report.documents_pruned = storage.purge_documents(cutoff).await?;
+            }
+        }
+
+        if let (Some(deleted_ttl), Some(tracker)) = (self.deleted_ttl, &self.tracker_adapter) {
+            let purged = tracker.purge_deleted_operations(Utc::now() - deleted_ttl).await?;
+            report.deleted_purged = purged.len() as u64;
+            if let Some(storage) = &self.storage_adapter {
+                // A document analyzed again by another operation stays, including
+                // one deleted but still restorable; its own purge removes it
+                for operation in &purged {
+                    let Some(document_id) = &operation.document_id else { continue };
+                    if tracker.find_operation_by_document(&operation.tenant_id, document_id, true).await?.is_some() {
+                        continue;
+                    }
+                    match storage.delete_document(&operation.tenant_id, document_id).await {
+                        Ok(()) => report.documents_pruned += 1,
+                        Err(e) => warn!("Failed to delete document {} of purged operation {}: {}", document_id, operation.operation_id, e),
+                    }
+                }
+            }
+        }
+
+        info!(
+            "Retention pass complete: {} operations, {} results, {} documents pruned, {} deleted operations purged",
+            report.operations_pruned, report.results_pruned, report.documents_pruned, report.deleted_purged
+        );
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AnalysisOperation, ModelType};
+    use crate::infrastructure::InMemoryOperationTracker;
+
+    #[tokio::test]
+    async fn test_prune_expired() {
+        let tracker = Arc::new(InMemoryOperationTracker::new());
+        let mut expired = AnalysisOperation::new(ModelType::Read);
+        expired.last_updated = Utc::now() - chrono::Duration::days(10);
+        tracker.store_operation(&expired).await.unwrap();
+        tracker.store_operation(&AnalysisOperation::new(ModelType::Read)).await.unwrap();
+
+        let service = RetentionService::new(Some(tracker), None, Some(7));
+        let report = service.prune_expired().await.unwrap();
+
+        assert_eq!(report.operations_pruned, 1);
+        assert_eq!(report.results_pruned, 0);
+        assert_eq!(report.documents_pruned, 0);
+    }
+
+    #[tokio::test]
+    async fn test_purge_deleted() {
+        let tracker = Arc::new(InMemoryOperationTracker::new());
+        let mut purged = AnalysisOperation::new(ModelType::Read);
+        purged.deleted_at = Some(Utc::now() - chrono::Duration::days(10));
+        let mut restorable = AnalysisOperation::new(ModelType::Read);
+        restorable.deleted_at = Some(Utc::now() - chrono::Duration::days(1));
+        for operation in [&purged, &restorable] {
+            tracker.store_operation(operation).await.unwrap();
+        }
+
+        let service = RetentionService::new(Some(tracker.clone()), None, None).with_deleted_ttl(7);
+        let report = service.prune_expired().await.unwrap();
+
+        assert_eq!(report.deleted_purged, 1);
+        assert_eq!(report.operations_pruned, 0);
+        assert!(tracker.get_operation(&purged.operation_id).await.unwrap().is_none());
+        assert!(tracker.get_operation(&restorable.operation_id).await.unwrap().is_some());
+    }
+}
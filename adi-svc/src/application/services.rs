@@ -6,19 +6,103 @@
 /// These services orchestrate domain objects and ports to implement
 /// the application's use cases.
 
-use std::sync::Arc;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::task::JoinHandle;
 use crate::domain::{
-    AnalyzeDocumentRequest, AnalysisOperation, AnalysisResult, DocumentSource, ModelType,
+    diff_results, redaction_boxes, AnalysisJob, ChunkMatch, ChunkingPolicy, EmbeddedChunk, AnalyzeDocumentRequest, AuditEntry, AuditQuery, AnalyzeOptions, AnalysisOperation, AnalysisResult,
+    DocumentFormat, DocumentMetadata, DocumentPage, DocumentSource, DocumentTable, DomainError, FieldCorrection, FieldMatch, FieldQuery, Invoice,
+    JobPriority, JobRetryPolicy, JobStatus, LifecycleEvent, LifecycleEventKind, ModelRoutes, ModelType, OperationEvent, OperationEventKind, OperationListQuery, OperationStats, OperationStatsQuery, OperationStatus, OutputTemplate, PdfInspection, Quota,
+    Principal, QuotaPeriod, QuotaUsage, RedactionRules, RenderedOutput, ResultDiff, ResultFields, ResultRevision, ReviewPolicy, ReviewState, ReviewStatus, Role, RouteTarget, RoutingDecision, ScanVerdict, SearchDocument, SemanticQuery, TenantId, UsageQuery, UsageRecord, WorkLease,
+    WorkQueue,
 };
 use super::errors::{ApplicationError, ApplicationResult};
-use super::ports::{DocumentIntelligencePort, DocumentStoragePort, OperationTrackerPort};
-use tracing::{info, warn, error};
+use super::ports::{
+    AuditLogPort, ByteRange, DependencyHealth, DocumentClassifierPort, DocumentIntelligencePort, DocumentStoragePort,
+    DocumentStream, EmbeddingProviderPort, EmbeddingStorePort, EventPublisherPort, HealthCheckPort, ImagePreprocessPort, JobQueuePort, MalwareScanPort,
+    OperationMetricsPort, OperationTrackerPort, QuotaPort, RedactionRenderPort, ResultRevisionPort, SearchIndexPort, SignedUrl, UploadState, UsagePort,
+    WorkQueuePort,
+};
+use tracing::{info, warn, error, Instrument};
+
+/// A job running this long is assumed abandoned by a worker that died mid-submission
+const STALE_JOB_SECS: i64 = 300;
+
+/// A dependency that has not answered a health check in this long is reported unhealthy
+const HEALTH_CHECK_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// The kept copy of a document being analyzed
+enum StoredDocument {
+    /// Already in storage under this id
+    Stored(String),
+    /// Being written while the document is submitted
+    Storing(JoinHandle<ApplicationResult<String>>),
+}
+
+impl StoredDocument {
+    /// Storage id of the copy
+    ///
+    /// A failed background write is logged rather than failing the analysis,
+    /// which Azure has already accepted; the operation is left without a
+    /// stored document.
+    async fn id(self) -> Option<String> {
+        let result = match self {
+            StoredDocument::Stored(document_id) => return Some(document_id),
+            StoredDocument::Storing(task) => task
+                .await
+                .unwrap_or_else(|e| Err(ApplicationError::Internal(format!("Storage task failed: {}", e)))),
+        };
+        result
+            .map_err(|e| error!("Failed to store uploaded document: {}", e))
+            .ok()
+    }
+    
+    /// Remove the copy written for an analysis that never started
+    ///
+    /// A write still in flight is waited for so its document can be deleted;
+    /// documents already stored before the analysis are left alone.
+    async fn discard(self, storage: &dyn DocumentStoragePort, tenant: &TenantId) {
+        let StoredDocument::Storing(task) = self else { return };
+        if let Ok(Ok(document_id)) = task.await {
+            if let Err(e) = storage.delete_document(tenant, &document_id).await {
+                warn!("Failed to delete document {} of a failed submission: {}", document_id, e);
+            }
+        }
+    }
+}
 
 /// Main document intelligence service
 pub struct DocumentIntelligenceService {
     intelligence_adapter: Arc<dyn DocumentIntelligencePort>,
     storage_adapter: Option<Arc<dyn DocumentStoragePort>>,
     tracker_adapter: Option<Arc<dyn OperationTrackerPort>>,
+    work_queue: Option<Arc<dyn WorkQueuePort>>,
+    job_queue: Option<Arc<dyn JobQueuePort>>,
+    job_retry: JobRetryPolicy,
+    audit_log: Option<Arc<dyn AuditLogPort>>,
+    usage_meter: Option<Arc<dyn UsagePort>>,
+    quotas: Option<Arc<dyn QuotaPort>>,
+    configured_quotas: RwLock<Vec<Quota>>,
+    event_publisher: Option<Arc<dyn EventPublisherPort>>,
+    search_index: Option<Arc<dyn SearchIndexPort>>,
+    embeddings: Option<(Arc<dyn EmbeddingProviderPort>, Arc<dyn EmbeddingStorePort>)>,
+    chunking: ChunkingPolicy,
+    malware_scanner: Option<Arc<dyn MalwareScanPort>>,
+    image_preprocessor: Option<Arc<dyn ImagePreprocessPort>>,
+    redaction_renderer: Option<Arc<dyn RedactionRenderPort>>,
+    document_classifier: Option<Arc<dyn DocumentClassifierPort>>,
+    model_routes: ModelRoutes,
+    review_policy: Option<ReviewPolicy>,
+    revisions: Option<Arc<dyn ResultRevisionPort>>,
+    health_checks: Vec<Arc<dyn HealthCheckPort>>,
+    operation_metrics: Option<Arc<dyn OperationMetricsPort>>,
+    validate_pdfs: bool,
+    max_pdf_pages: Option<u32>,
+    store_raw_responses: bool,
+    output_templates: HashMap<String, OutputTemplate>,
 }
 
 impl DocumentIntelligenceService {
@@ -31,7 +115,206 @@ impl DocumentIntelligenceService {
             intelligence_adapter,
             storage_adapter,
             tracker_adapter,
+            work_queue: None,
+            job_queue: None,
+            job_retry: JobRetryPolicy::default(),
+            audit_log: None,
+            usage_meter: None,
+            quotas: None,
+            configured_quotas: RwLock::new(Vec::new()),
+            event_publisher: None,
+            search_index: None,
+            embeddings: None,
+            chunking: ChunkingPolicy::default(),
+            malware_scanner: None,
+            image_preprocessor: None,
+            redaction_renderer: None,
+            document_classifier: None,
+            model_routes: ModelRoutes::default(),
+            review_policy: None,
+            revisions: None,
+            health_checks: Vec::new(),
+            operation_metrics: None,
+            validate_pdfs: false,
+            max_pdf_pages: None,
+            store_raw_responses: false,
+            output_templates: HashMap::new(),
+        }
+    }
+    
+    /// Enable the review/export work queues
+    pub fn with_work_queue(mut self, work_queue: Arc<dyn WorkQueuePort>) -> Self {
+        self.work_queue = Some(work_queue);
+        self
+    }
+    
+    /// Accept `mode=async` submissions, queued for the worker pool
+    pub fn with_job_queue(mut self, job_queue: Arc<dyn JobQueuePort>) -> Self {
+        self.job_queue = Some(job_queue);
+        self
+    }
+    
+    /// How failed jobs are retried before they are dead-lettered
+    pub fn with_job_retry(mut self, policy: JobRetryPolicy) -> Self {
+        self.job_retry = policy;
+        self
+    }
+    
+    /// Record mutating API calls in an audit log
+    pub fn with_audit_log(mut self, audit_log: Arc<dyn AuditLogPort>) -> Self {
+        self.audit_log = Some(audit_log);
+        self
+    }
+    
+    /// Record submit latency, completion time and outcomes per tenant and model
+    pub fn with_operation_metrics(mut self, metrics: Arc<dyn OperationMetricsPort>) -> Self {
+        self.operation_metrics = Some(metrics);
+        self
+    }
+    
+    /// Meter pages analyzed per tenant, day and model
+    pub fn with_usage_meter(mut self, usage_meter: Arc<dyn UsagePort>) -> Self {
+        self.usage_meter = Some(usage_meter);
+        self
+    }
+    
+    /// Refuse submissions from tenants over their quota; needs a usage meter to count against
+    pub fn with_quotas(mut self, quotas: Arc<dyn QuotaPort>) -> Self {
+        self.quotas = Some(quotas);
+        self
+    }
+    
+    /// Also enforce quotas from configuration, which are not stored
+    pub fn with_configured_quotas(self, quotas: Vec<Quota>) -> Self {
+        self.set_configured_quotas(quotas);
+        self
+    }
+    
+    /// Replace the quotas from configuration, e.g. on reload; `false` when they are unchanged
+    pub fn set_configured_quotas(&self, quotas: Vec<Quota>) -> bool {
+        let limits = |quotas: &[Quota]| {
+            quotas
+                .iter()
+                .map(|quota| (quota.tenant_id.clone(), quota.period, quota.max_operations, quota.max_pages))
+                .collect::<Vec<_>>()
+        };
+        let mut current = self.configured_quotas.write().unwrap_or_else(|e| e.into_inner());
+        if limits(&current) == limits(&quotas) {
+            return false;
         }
+        *current = quotas;
+        true
+    }
+    
+    /// Flag succeeded results falling short of `policy` for human review
+    pub fn with_review_policy(mut self, policy: ReviewPolicy) -> Self {
+        self.review_policy = Some(policy);
+        self
+    }
+    
+    /// Accept reviewers' corrections, keeping every revision of a corrected result
+    pub fn with_result_revisions(mut self, revisions: Arc<dyn ResultRevisionPort>) -> Self {
+        self.revisions = Some(revisions);
+        self
+    }
+    
+    /// Report `check`'s dependency in `check_health`
+    pub fn with_health_check(mut self, check: Arc<dyn HealthCheckPort>) -> Self {
+        self.health_checks.push(check);
+        self
+    }
+    
+    /// Check every dependency concurrently, each bounded by a timeout
+    pub async fn check_health(&self) -> Vec<DependencyHealth> {
+        futures::future::join_all(self.health_checks.iter().map(|check| async move {
+            let started = std::time::Instant::now();
+            let outcome = tokio::time::timeout(HEALTH_CHECK_TIMEOUT, check.check_health())
+                .await
+                .unwrap_or_else(|_| {
+                    Err(ApplicationError::Internal(format!(
+                        "No answer within {}s",
+                        HEALTH_CHECK_TIMEOUT.as_secs()
+                    )))
+                });
+            if let Err(ref e) = outcome {
+                warn!("Health check of {} failed: {}", check.dependency(), e);
+            }
+            DependencyHealth {
+                name: check.dependency(),
+                healthy: outcome.is_ok(),
+                latency_ms: started.elapsed().as_millis() as u64,
+                error: outcome.err().map(|e| e.to_string()),
+            }
+        }))
+        .await
+    }
+    
+    /// Announce operations being created, succeeding and failing
+    pub fn with_event_publisher(mut self, publisher: Arc<dyn EventPublisherPort>) -> Self {
+        self.event_publisher = Some(publisher);
+        self
+    }
+    
+    /// Push succeeded results to a search index
+    pub fn with_search_index(mut self, index: Arc<dyn SearchIndexPort>) -> Self {
+        self.search_index = Some(index);
+        self
+    }
+    
+    /// Embed succeeded results' content in chunks split by `chunking`, storing them for semantic retrieval
+    pub fn with_embeddings(
+        mut self,
+        provider: Arc<dyn EmbeddingProviderPort>,
+        store: Arc<dyn EmbeddingStorePort>,
+        chunking: ChunkingPolicy,
+    ) -> Self {
+        self.embeddings = Some((provider, store));
+        self.chunking = chunking;
+        self
+    }
+    
+    /// Scan uploaded bytes before storing or submitting them
+    pub fn with_malware_scanner(mut self, scanner: Arc<dyn MalwareScanPort>) -> Self {
+        self.malware_scanner = Some(scanner);
+        self
+    }
+    
+    /// Enable per-request image preprocessing
+    pub fn with_image_preprocessor(mut self, preprocessor: Arc<dyn ImagePreprocessPort>) -> Self {
+        self.image_preprocessor = Some(preprocessor);
+        self
+    }
+    
+    /// Enable rendering redacted copies of analyzed documents
+    pub fn with_redaction_renderer(mut self, renderer: Arc<dyn RedactionRenderPort>) -> Self {
+        self.redaction_renderer = Some(renderer);
+        self
+    }
+    
+    /// Enable `analyze_auto`, classifying documents with `classifier` and routing them by `routes`
+    pub fn with_model_routing(mut self, classifier: Arc<dyn DocumentClassifierPort>, routes: ModelRoutes) -> Self {
+        self.document_classifier = Some(classifier);
+        self.model_routes = routes;
+        self
+    }
+    
+    /// Reject corrupt, encrypted and (if `max_pages` is set) oversized PDFs before submission
+    pub fn with_pdf_validation(mut self, max_pages: Option<u32>) -> Self {
+        self.validate_pdfs = true;
+        self.max_pdf_pages = max_pages;
+        self
+    }
+    
+    /// Keep the provider's untouched response alongside each stored result
+    pub fn with_raw_responses(mut self) -> Self {
+        self.store_raw_responses = true;
+        self
+    }
+    
+    /// Offer `templates` for rendering results with [`Self::render_result`]
+    pub fn with_output_templates(mut self, templates: Vec<OutputTemplate>) -> Self {
+        self.output_templates = templates.into_iter().map(|template| (template.name.clone(), template)).collect();
+        self
     }
     
     /// Analyze a document using the specified model
@@ -43,55 +326,727 @@ impl DocumentIntelligenceService {
         
         // Validate the request
         request.source.validate().map_err(ApplicationError::Domain)?;
+        validate_options(&request.options)?;
+        self.check_quotas(&request.tenant_id).await?;
         
         // If document is provided as bytes and storage is available, store it for record-keeping
-        // but keep the bytes for Azure API call
+        // while it is submitted to Azure, which still gets the bytes rather than a file:// URL
+        let mut document = None;
+        let mut scan_verdict = None;
         if let DocumentSource::Bytes(ref bytes) = request.source {
+            let (format, verdict) = self.screen(bytes).await?;
+            scan_verdict = verdict;
+            let metadata = request.metadata.get_or_insert_with(DocumentMetadata::default);
+            metadata.content_type = format.mime_type().to_string();
             if let Some(storage) = &self.storage_adapter {
-                info!("Storing document bytes for record-keeping");
-                let _doc_id = storage
-                    .store_document(
-                        "uploaded_document",
-                        "application/octet-stream",
-                        bytes.clone(),
-                    )
-                    .await?;
-                // Note: We keep request.source as Bytes - don't convert to file:// URL
-                // Azure needs the base64-encoded bytes, not a local file path
+                info!("Storing document bytes for record-keeping: {}", metadata.filename);
+                let storage = storage.clone();
+                let (tenant, metadata, bytes) = (request.tenant_id.clone(), metadata.clone(), bytes.clone());
+                let task = tokio::spawn(
+                    async move {
+                        storage
+                            .store_document(&tenant, &metadata.filename, &metadata.content_type, bytes)
+                            .await
+                    }
+                    .in_current_span(),
+                );
+                document = Some(StoredDocument::Storing(task));
             }
         }
         
+        self.start_analysis(request, document, scan_verdict).await
+    }
+    
+    /// Classify a document, then analyze it with the model its type routes to
+    ///
+    /// The routing decision is recorded on the operation. Documents that can't
+    /// be classified, or only with low confidence, take the fallback route.
+    pub async fn analyze_auto(&self, request: AnalyzeDocumentRequest) -> ApplicationResult<AnalysisOperation> {
+        let decision = self.route_document(&request).await?;
+        self.analyze_routed(request, decision).await
+    }
+    
+    /// Classify a document and choose the model its type routes to
+    pub async fn route_document(&self, request: &AnalyzeDocumentRequest) -> ApplicationResult<RoutingDecision> {
+        request.source.validate().map_err(ApplicationError::Domain)?;
+        let classifier = self.document_classifier.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Document classification is not configured".to_string())
+        })?;
+        let filename = request.metadata.as_ref().map(|metadata| metadata.filename.as_str());
+        let classification = classifier.classify(&request.source, filename).await?;
+        let decision = self.model_routes.route(classification, classifier.method());
+        info!(
+            "Routing {} document ({}, confidence {:?}) to {}",
+            decision.doc_type.as_deref().unwrap_or("unclassified"),
+            decision.method.as_str(),
+            decision.confidence,
+            decision.model_id
+        );
+        Ok(decision)
+    }
+    
+    /// Analyze a document with the model `decision` chose, recording the decision on the operation
+    pub async fn analyze_routed(
+        &self,
+        request: AnalyzeDocumentRequest,
+        decision: RoutingDecision,
+    ) -> ApplicationResult<AnalysisOperation> {
+        let mut operation = self.analyze_with(request, &decision.target()).await?;
+        operation.routing = Some(decision);
+        if let Some(tracker) = &self.tracker_adapter {
+            tracker.update_operation(&operation).await?;
+        }
+        Ok(operation)
+    }
+    
+    /// Analyze a document with a prebuilt model or a custom model that exists
+    pub async fn analyze_with(
+        &self,
+        mut request: AnalyzeDocumentRequest,
+        target: &RouteTarget,
+    ) -> ApplicationResult<AnalysisOperation> {
+        request.model_type = match target {
+            RouteTarget::Prebuilt(model_type) => *model_type,
+            RouteTarget::Custom(model_id) => {
+                if !self.intelligence_adapter.validate_custom_model(model_id).await? {
+                    return Err(ApplicationError::AnalysisFailed(format!("Custom model not found: {}", model_id)));
+                }
+                ModelType::Custom
+            }
+        };
+        self.analyze_document(request).await
+    }
+    
+    /// Identify the document format and scan it, rejecting unsupported or infected documents
+    async fn screen(&self, bytes: &[u8]) -> ApplicationResult<(DocumentFormat, Option<ScanVerdict>)> {
+        let format = DocumentFormat::detect(bytes)?;
+        if format == DocumentFormat::Pdf && self.validate_pdfs {
+            self.validate_pdf(bytes)?;
+        }
+        Ok((format, self.scan(bytes).await?))
+    }
+    
+    /// Check a PDF's structure and page count without calling Azure
+    fn validate_pdf(&self, bytes: &[u8]) -> ApplicationResult<()> {
+        let inspection = PdfInspection::inspect(bytes)?;
+        if let (Some(pages), Some(max)) = (inspection.page_count, self.max_pdf_pages) {
+            if pages > max {
+                return Err(DomainError::TooManyPages { pages, max }.into());
+            }
+        }
+        Ok(())
+    }
+    
+    /// Scan bytes if a scanner is configured, rejecting infected documents
+    async fn scan(&self, bytes: &[u8]) -> ApplicationResult<Option<ScanVerdict>> {
+        let scanner = match &self.malware_scanner {
+            Some(scanner) => scanner,
+            None => return Ok(None),
+        };
+        
+        match scanner.scan(bytes).await? {
+            ScanVerdict::Infected { signature } => {
+                warn!("Rejected infected upload: {}", signature);
+                Err(ApplicationError::MalwareDetected(signature))
+            }
+            verdict => Ok(Some(verdict)),
+        }
+    }
+    
+    /// Apply requested image preprocessing to the bytes about to be submitted
+    async fn preprocess(&self, request: &mut AnalyzeDocumentRequest) -> ApplicationResult<()> {
+        let options = match &request.options.preprocess {
+            Some(options) if !options.is_noop() => options,
+            _ => return Ok(()),
+        };
+        let bytes = match &mut request.source {
+            DocumentSource::Bytes(bytes) => bytes,
+            DocumentSource::Url(_) => return Ok(()),
+        };
+        let format = DocumentFormat::detect(bytes)?;
+        if !matches!(format, DocumentFormat::Jpeg | DocumentFormat::Png | DocumentFormat::Tiff) {
+            return Ok(());
+        }
+        
+        let preprocessor = self.image_preprocessor.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Image preprocessing is not configured".to_string())
+        })?;
+        let original_len = bytes.len();
+        *bytes = preprocessor.preprocess(std::mem::take(bytes), format, options).await?.into();
+        info!("Preprocessed {:?} image: {} -> {} bytes", format, original_len, bytes.len());
+        Ok(())
+    }
+    
+    /// Submit an already-stored request to the adapter and track it
+    async fn start_analysis(
+        &self,
+        mut request: AnalyzeDocumentRequest,
+        document: Option<StoredDocument>,
+        scan_verdict: Option<ScanVerdict>,
+    ) -> ApplicationResult<AnalysisOperation> {
+        // Hash and metadata describe the document as uploaded, before preprocessing
+        let content_sha256 = match &request.source {
+            DocumentSource::Bytes(bytes) => Some(hex::encode(Sha256::digest(bytes))),
+            DocumentSource::Url(_) => None,
+        };
+        let metadata = request.metadata.clone();
+        let tenant_id = request.tenant_id.clone();
+        
         // Start analysis
-        let operation = self.intelligence_adapter.analyze_document(request).await?;
+        let model_type = request.model_type;
+        let submitted = async {
+            self.preprocess(&mut request).await?;
+            let submit_started = std::time::Instant::now();
+            let submitted = self.intelligence_adapter.analyze_document(request).await;
+            if let Some(metrics) = &self.operation_metrics {
+                metrics.record_submit(&tenant_id, model_type, submit_started.elapsed(), submitted.is_ok());
+            }
+            submitted
+        }
+        .await;
+        let mut operation = match submitted {
+            Ok(operation) => operation,
+            Err(e) => {
+                // Nothing will reference the copy stored alongside the submission
+                if let (Some(document), Some(storage)) = (document, &self.storage_adapter) {
+                    document.discard(storage.as_ref(), &tenant_id).await;
+                }
+                return Err(e);
+            }
+        };
+        let document_id = match document {
+            Some(document) => document.id().await,
+            None => None,
+        };
+        operation.tenant_id = tenant_id;
+        if let Some(ref metadata) = metadata {
+            operation.set_document(document_id, metadata);
+        }
+        operation.content_sha256 = content_sha256;
+        operation.scan_verdict = scan_verdict;
         
         // Track operation if tracker is available
         if let Some(tracker) = &self.tracker_adapter {
             tracker.store_operation(&operation).await?;
+            let detail = Some(operation.model_type.as_str().to_string());
+            tracker
+                .record_event(&OperationEvent::new(&operation.operation_id, OperationEventKind::Submitted, detail))
+                .await?;
+            if let Some(kind) = OperationEventKind::for_status(operation.status) {
+                tracker.record_event(&OperationEvent::new(&operation.operation_id, kind, None)).await?;
+            }
         }
         
+        self.publish_event(LifecycleEvent::new(LifecycleEventKind::Created, &operation)).await;
+        
         info!("Document analysis started: operation_id={}", operation.operation_id);
         Ok(operation)
     }
     
+    /// Result of an operation that has succeeded
+    pub async fn completed_result(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, AnalysisResult)> {
+        self.completed_result_fields(tenant, operation_id, &ResultFields::all()).await
+    }
+    
+    /// Selected sections of the result of an operation that has succeeded
+    pub async fn completed_result_fields(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+        fields: &ResultFields,
+    ) -> ApplicationResult<(AnalysisOperation, AnalysisResult)> {
+        match self.get_analysis_result_fields(tenant, operation_id, fields).await? {
+            (operation, Some(result)) if operation.status == OperationStatus::Succeeded => {
+                Ok((operation, result))
+            }
+            (operation, _) => Err(ApplicationError::ResultNotAvailable(format!(
+                "operation {} is {}",
+                operation_id,
+                format!("{:?}", operation.status).to_lowercase()
+            ))),
+        }
+    }
+    
+    /// One page of the result of an operation that has succeeded
+    pub async fn result_page(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+        page_number: i32,
+    ) -> ApplicationResult<DocumentPage> {
+        // Stored results are sliced by the tracker without loading other pages
+        if let Some(tracker) = &self.tracker_adapter {
+            let stored = self.tenant_operation(tenant, operation_id).await?;
+            if stored.is_some_and(|op| op.status == OperationStatus::Succeeded) {
+                if let Some(page) = tracker.get_result_page(operation_id, page_number).await? {
+                    return Ok(page);
+                }
+            }
+        }
+        
+        let fields = ResultFields {
+            pages: true,
+            ..ResultFields::none()
+        };
+        let (_, result) = self.completed_result_fields(tenant, operation_id, &fields).await?;
+        result
+            .pages
+            .into_iter()
+            .find(|page| page.page_number == page_number)
+            .ok_or_else(|| {
+                ApplicationError::PageNotFound(format!(
+                    "operation {} has no page {}",
+                    operation_id, page_number
+                ))
+            })
+    }
+    
+    /// The result of an operation that has succeeded, mapped by the output template `template`
+    pub async fn render_result(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+        template: &str,
+    ) -> ApplicationResult<RenderedOutput> {
+        let template = self
+            .output_templates
+            .get(template)
+            .ok_or_else(|| ApplicationError::TemplateNotFound(template.to_string()))?;
+        let (_, result) = self.completed_result(tenant, operation_id).await?;
+        Ok(template.render(operation_id, &result)?)
+    }
+    
+    /// The invoice read from the result of an operation that has succeeded
+    pub async fn result_invoice(&self, tenant: &TenantId, operation_id: &str) -> ApplicationResult<Invoice> {
+        let (_, result) = self.completed_result(tenant, operation_id).await?;
+        Ok(Invoice::from_result(&result)?)
+    }
+    
+    /// One table, by its position from 0, of the result of an operation that has succeeded
+    pub async fn result_table(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+        index: usize,
+    ) -> ApplicationResult<DocumentTable> {
+        let fields = ResultFields {
+            tables: true,
+            ..ResultFields::none()
+        };
+        let (_, result) = self.completed_result_fields(tenant, operation_id, &fields).await?;
+        let count = result.tables.len();
+        result.tables.into_iter().nth(index).ok_or_else(|| {
+            ApplicationError::TableNotFound(format!(
+                "operation {} has {} tables, no table {}",
+                operation_id, count, index
+            ))
+        })
+    }
+    
+    /// Key-value pairs and document fields of a succeeded result passing `query`
+    pub async fn query_result_fields(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+        query: &FieldQuery,
+    ) -> ApplicationResult<Vec<FieldMatch>> {
+        // Stored results are filtered by the tracker
+        if let Some(tracker) = &self.tracker_adapter {
+            let stored = self.tenant_operation(tenant, operation_id).await?;
+            if stored.is_some_and(|op| op.status == OperationStatus::Succeeded) {
+                if let Some(matches) = tracker.query_result_fields(operation_id, query).await? {
+                    return Ok(matches);
+                }
+            }
+        }
+        
+        let fields = ResultFields {
+            key_value_pairs: true,
+            documents: true,
+            ..ResultFields::none()
+        };
+        let (_, result) = self.completed_result_fields(tenant, operation_id, &fields).await?;
+        Ok(result.query_fields(query))
+    }
+    
+    /// Differences between the results of two succeeded operations
+    pub async fn diff_results(
+        &self,
+        tenant: &TenantId,
+        left_id: &str,
+        right_id: &str,
+    ) -> ApplicationResult<ResultDiff> {
+        let (_, left) = self.completed_result(tenant, left_id).await?;
+        let (_, right) = self.completed_result(tenant, right_id).await?;
+        Ok(diff_results(&left, &right))
+    }
+    
+    /// Render a copy of an operation's document with `rules` burned in and store it
+    ///
+    /// Returns the stored copy's document id and its bytes, always a PDF.
+    pub async fn redact_document(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+        rules: &RedactionRules,
+    ) -> ApplicationResult<(String, Bytes)> {
+        rules.validate()?;
+        let renderer = self.redaction_renderer.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Redaction rendering is not configured".to_string())
+        })?;
+        let storage = self.storage()?;
+        let (operation, result) = self.completed_result(tenant, operation_id).await?;
+        let Some(document_id) = operation.document_id.as_deref() else {
+            return Err(ApplicationError::ResultNotAvailable(format!(
+                "operation {} has no stored document to redact",
+                operation_id
+            )));
+        };
+        
+        let document = storage.retrieve_document(tenant, document_id).await?;
+        let format = DocumentFormat::detect(&document)?;
+        let boxes = redaction_boxes(&result, rules);
+        info!("Redacting {} areas of document {}", boxes.len(), document_id);
+        let redacted = Bytes::from(renderer.render_redacted(document, format, &boxes).await?);
+        
+        let stem = operation
+            .filename
+            .as_deref()
+            .map(|filename| filename.rsplit_once('.').map_or(filename, |(stem, _)| stem))
+            .filter(|stem| !stem.is_empty())
+            .unwrap_or("document");
+        let filename = format!("{}-redacted.pdf", stem);
+        let redacted_id = storage
+            .store_document(tenant, &filename, "application/pdf", redacted.clone())
+            .await?;
+        Ok((redacted_id, redacted))
+    }
+    
+    /// Begin a resumable upload
+    pub async fn create_upload(
+        &self,
+        tenant: &TenantId,
+        metadata: &DocumentMetadata,
+        total_size: Option<u64>,
+    ) -> ApplicationResult<UploadState> {
+        self.storage()?.create_upload(tenant, metadata, total_size).await
+    }
+    
+    /// Current progress of a resumable upload
+    pub async fn upload_state(&self, tenant: &TenantId, upload_id: &str) -> ApplicationResult<UploadState> {
+        self.storage()?.upload_state(tenant, upload_id).await
+    }
+    
+    /// Append a chunk at `offset` to a resumable upload
+    pub async fn append_upload(
+        &self,
+        tenant: &TenantId,
+        upload_id: &str,
+        offset: u64,
+        data: Bytes,
+    ) -> ApplicationResult<UploadState> {
+        self.storage()?.append_upload(tenant, upload_id, offset, data).await
+    }
+    
+    /// Discard a resumable upload
+    pub async fn abort_upload(&self, tenant: &TenantId, upload_id: &str) -> ApplicationResult<()> {
+        self.storage()?.abort_upload(tenant, upload_id).await
+    }
+    
+    /// Finish a resumable upload and start analyzing it
+    pub async fn analyze_upload(
+        &self,
+        tenant: &TenantId,
+        upload_id: &str,
+        model_type: ModelType,
+        options: AnalyzeOptions,
+    ) -> ApplicationResult<AnalysisOperation> {
+        validate_options(&options)?;
+        self.check_quotas(tenant).await?;
+        let storage = self.storage()?;
+        let (document_id, upload) = storage.complete_upload(tenant, upload_id).await?;
+        let bytes = storage.retrieve_document(tenant, &document_id).await?;
+        info!("Resumable upload {} completed as {}", upload_id, document_id);
+        
+        // Rejected documents are not kept
+        let (format, scan_verdict) = match self.screen(&bytes).await {
+            Err(e @ (ApplicationError::MalwareDetected(_) | ApplicationError::Domain(_))) => {
+                storage.delete_document(tenant, &document_id).await?;
+                return Err(e);
+            }
+            other => other?,
+        };
+        
+        let request = AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(bytes),
+            model_type,
+            options,
+            metadata: Some(DocumentMetadata::new(upload.filename, format.mime_type())),
+            tenant_id: tenant.clone(),
+        };
+        request.source.validate().map_err(ApplicationError::Domain)?;
+        
+        self.start_analysis(request, Some(StoredDocument::Stored(document_id)), scan_verdict).await
+    }
+    
+    /// Queue a request for the worker pool instead of submitting it now
+    ///
+    /// Validation, quotas and malware scanning apply when the job is queued,
+    /// so a request that would be refused is refused straight away. Workers
+    /// take interactive jobs before batch ones.
+    pub async fn enqueue_analysis(
+        &self,
+        request: AnalyzeDocumentRequest,
+        priority: JobPriority,
+    ) -> ApplicationResult<AnalysisJob> {
+        let job_queue = self.job_queue()?;
+        request.source.validate().map_err(ApplicationError::Domain)?;
+        validate_options(&request.options)?;
+        self.check_quotas(&request.tenant_id).await?;
+        
+        let AnalyzeDocumentRequest { source, model_type, options, metadata, tenant_id } = request;
+        let mut job = AnalysisJob::new(tenant_id, model_type, options).with_priority(priority);
+        job.metadata = metadata;
+        match source {
+            DocumentSource::Url(url) => job.document_url = Some(url),
+            DocumentSource::Bytes(bytes) => {
+                // Workers read the document back from storage
+                let storage = self.storage()?;
+                let (format, verdict) = self.screen(&bytes).await?;
+                let metadata = job.metadata.get_or_insert_with(DocumentMetadata::default);
+                metadata.content_type = format.mime_type().to_string();
+                let document_id = storage
+                    .store_document(&job.tenant_id, &metadata.filename, &metadata.content_type, bytes)
+                    .await?;
+                job.document_id = Some(document_id);
+                job.scan_verdict = verdict;
+            }
+        }
+        
+        job_queue.enqueue_job(&job).await?;
+        info!(
+            "Queued {} analysis job {} for tenant {}",
+            job.priority.as_str(),
+            job.job_id,
+            job.tenant_id
+        );
+        Ok(job)
+    }
+    
+    /// Claim the next queued job and submit it; `false` when there was none
+    pub async fn run_next_job(&self) -> ApplicationResult<bool> {
+        let job_queue = self.job_queue()?;
+        let Some(mut job) = job_queue.claim_job(chrono::Duration::seconds(STALE_JOB_SECS)).await? else {
+            return Ok(false);
+        };
+        
+        // Workers that keep dying on a job must not retry it forever
+        if job.attempts > self.job_retry.max_attempts {
+            error!("Analysis job {} abandoned after {} attempts", job.job_id, job.attempts - 1);
+            job.dead_letter(format!("Abandoned by workers after {} attempts", job.attempts - 1));
+            job_queue.update_job(&job).await?;
+            return Ok(true);
+        }
+        
+        info!("Running analysis job {} (attempt {})", job.job_id, job.attempts);
+        match self.submit_job(&job).await {
+            Ok(operation) => {
+                info!("Analysis job {} started operation {}", job.job_id, operation.operation_id);
+                job.submitted(&operation.operation_id);
+            }
+            Err(e) => self.fail_job(&mut job, e.to_string(), e.is_transient()),
+        }
+        job_queue.update_job(&job).await?;
+        Ok(true)
+    }
+    
+    /// Schedule a failed job's next attempt, or dead-letter it when it may not have one
+    fn fail_job(&self, job: &mut AnalysisJob, error: String, transient: bool) {
+        if transient && self.job_retry.allows_retry(job.attempts) {
+            let delay = self.job_retry.backoff(job.attempts);
+            warn!(
+                "Analysis job {} failed on attempt {}, retrying in {}s: {}",
+                job.job_id,
+                job.attempts,
+                delay.num_seconds(),
+                error
+            );
+            job.retry_after(error, delay);
+        } else {
+            error!("Analysis job {} dead-lettered after {} attempts: {}", job.job_id, job.attempts, error);
+            job.dead_letter(error);
+        }
+    }
+    
+    /// Retry or dead-letter the job behind an operation the provider failed
+    ///
+    /// Runs when a poll first sees the failure; errors are logged rather than failing the poll.
+    async fn fail_operation_job(&self, operation_id: &str) {
+        let Some(job_queue) = &self.job_queue else {
+            return;
+        };
+        let mut job = match job_queue.job_for_operation(operation_id).await {
+            Ok(Some(job)) if job.status == JobStatus::Submitted => job,
+            Ok(_) => return,
+            Err(e) => {
+                error!("Failed to find job for operation {}: {}", operation_id, e);
+                return;
+            }
+        };
+        self.fail_job(&mut job, format!("Operation {} failed", operation_id), true);
+        if let Err(e) = job_queue.update_job(&job).await {
+            error!("Failed to update job {}: {}", job.job_id, e);
+        }
+    }
+    
+    /// Give a dead-lettered or backing-off job a fresh set of attempts, due now
+    pub async fn retry_job(&self, tenant: &TenantId, job_id: &str) -> ApplicationResult<AnalysisJob> {
+        let (job, _) = self.job(tenant, job_id).await?;
+        self.requeue_job(job).await
+    }
+    
+    /// Retry the job that started a failed operation
+    pub async fn retry_operation(&self, tenant: &TenantId, operation_id: &str) -> ApplicationResult<AnalysisJob> {
+        let operation = self
+            .tenant_operation(tenant, operation_id)
+            .await?
+            .ok_or_else(|| ApplicationError::OperationNotFound(operation_id.to_string()))?;
+        if operation.status != OperationStatus::Failed {
+            return Err(ApplicationError::JobNotRetryable(format!(
+                "Operation {} has not failed",
+                operation_id
+            )));
+        }
+        let job = self.job_queue()?.job_for_operation(operation_id).await?.ok_or_else(|| {
+            ApplicationError::JobNotRetryable(format!(
+                "Operation {} was not started by a queued job; submit it again",
+                operation_id
+            ))
+        })?;
+        self.requeue_job(job).await
+    }
+    
+    async fn requeue_job(&self, mut job: AnalysisJob) -> ApplicationResult<AnalysisJob> {
+        // Running and submitted jobs still have an attempt in flight
+        if !matches!(job.status, JobStatus::Queued | JobStatus::DeadLettered) {
+            return Err(ApplicationError::JobNotRetryable(format!(
+                "Job {} is {}",
+                job.job_id,
+                job.status.as_str()
+            )));
+        }
+        self.check_quotas(&job.tenant_id).await?;
+        job.requeue();
+        self.job_queue()?.update_job(&job).await?;
+        info!("Analysis job {} queued again by request", job.job_id);
+        Ok(job)
+    }
+    
+    /// Rebuild a job's request and start its analysis
+    async fn submit_job(&self, job: &AnalysisJob) -> ApplicationResult<AnalysisOperation> {
+        let source = match (&job.document_id, &job.document_url) {
+            (Some(document_id), _) => {
+                DocumentSource::Bytes(self.storage()?.retrieve_document(&job.tenant_id, document_id).await?)
+            }
+            (None, Some(url)) => DocumentSource::Url(url.clone()),
+            (None, None) => {
+                return Err(ApplicationError::Internal(format!("Job {} has no document", job.job_id)));
+            }
+        };
+        let request = AnalyzeDocumentRequest {
+            source,
+            model_type: job.model_type,
+            options: job.options.clone(),
+            metadata: job.metadata.clone(),
+            tenant_id: job.tenant_id.clone(),
+        };
+        let document = job.document_id.clone().map(StoredDocument::Stored);
+        self.start_analysis(request, document, job.scan_verdict.clone()).await
+    }
+    
+    /// A tenant's job and, once submitted, the operation it started
+    pub async fn job(
+        &self,
+        tenant: &TenantId,
+        job_id: &str,
+    ) -> ApplicationResult<(AnalysisJob, Option<AnalysisOperation>)> {
+        let job = self
+            .job_queue()?
+            .get_job(job_id)
+            .await?
+            .filter(|job| job.tenant_id == *tenant)
+            .ok_or_else(|| ApplicationError::JobNotFound(job_id.to_string()))?;
+        let operation = match &job.operation_id {
+            Some(operation_id) => self.tenant_operation(tenant, operation_id).await?,
+            None => None,
+        };
+        Ok((job, operation))
+    }
+    
     /// Get the result of an analysis operation
     pub async fn get_analysis_result(
         &self,
+        tenant: &TenantId,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)> {
+        self.get_analysis_result_fields(tenant, operation_id, &ResultFields::all()).await
+    }
+    
+    /// Poll an operation every `poll_interval` until it finishes, giving up after `timeout`
+    ///
+    /// Used by ingestion sources that have no client to poll for them; each
+    /// poll is what records the result and publishes the completion event.
+    pub async fn wait_for_result(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+        poll_interval: std::time::Duration,
+        timeout: std::time::Duration,
+    ) -> ApplicationResult<(AnalysisOperation, AnalysisResult)> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let (operation, result) = self.get_analysis_result(tenant, operation_id).await?;
+            match (operation.status, result) {
+                (OperationStatus::Succeeded, Some(result)) => return Ok((operation, result)),
+                (status, _) if status.is_terminal() => {
+                    return Err(ApplicationError::AnalysisFailed(format!(
+                        "Operation {} ended {:?}",
+                        operation_id, status
+                    )));
+                }
+                _ => {}
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return Err(ApplicationError::AnalysisFailed(format!(
+                    "Operation {} did not finish within {:?}",
+                    operation_id, timeout
+                )));
+            }
+            tokio::time::sleep(poll_interval).await;
+        }
+    }
+    
+    /// Get the selected sections of an analysis operation's result
+    pub async fn get_analysis_result_fields(
+        &self,
+        tenant: &TenantId,
         operation_id: &str,
+        fields: &ResultFields,
     ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)> {
         info!("Retrieving analysis result: operation_id={}", operation_id);
         
         // ALWAYS check tracker first
-        let stored_operation = if let Some(tracker) = &self.tracker_adapter {
-            tracker.get_operation(operation_id).await?
-        } else {
-            None
-        };
+        let stored_operation = self.tenant_operation(tenant, operation_id).await?;
         
         // If we have a stored operation with terminal status and result, return from cache
         if let Some(ref op) = stored_operation {
             if op.status.is_terminal() {
                 if let Some(tracker) = &self.tracker_adapter {
-                    if let Some(result) = tracker.get_result(operation_id).await? {
+                    if let Some(result) = tracker.get_result_fields(operation_id, fields).await? {
                         info!("Returning cached result for operation: {}", operation_id);
                         return Ok((op.clone(), Some(result)));
                     }
@@ -100,36 +1055,682 @@ impl DocumentIntelligenceService {
         }
         
         // Query Azure
-        let (mut operation, result) = self
-            .intelligence_adapter
-            .get_analysis_result(operation_id)
-            .await?;
+        let (mut operation, result, raw) = if self.store_raw_responses {
+            self.intelligence_adapter.get_analysis_result_raw(operation_id).await?
+        } else {
+            let (operation, result) = self.intelligence_adapter.get_analysis_result(operation_id).await?;
+            (operation, result, None)
+        };
+        
+        // Azure only has news of the status; everything else stays as stored
+        let mut transition = None;
+        if let Some(stored_op) = stored_operation {
+            if stored_op.status != operation.status {
+                transition = OperationEventKind::for_status(operation.status);
+            }
+            operation = AnalysisOperation {
+                status: operation.status,
+                last_updated: operation.last_updated,
+                error: operation.error.or(stored_op.error),
+                page_count: operation.page_count.or(stored_op.page_count),
+                ..stored_op
+            };
+        }
+        if let Some(ref result) = result {
+            operation.page_count = Some(result.pages.len() as u32);
+        }
+        if let (Some(policy), Some(result), Some(OperationEventKind::Succeeded)) = (&self.review_policy, &result, transition) {
+            let reasons = policy.assess(result);
+            if !reasons.is_empty() {
+                info!("Operation {} needs review: {}", operation_id, reasons.join("; "));
+                operation.review = Some(ReviewState::needs_review(reasons));
+            }
+        }
         
         // Update tracker if available
         if let Some(tracker) = &self.tracker_adapter {
             tracker.update_operation(&operation).await?;
+            if let Some(kind) = transition {
+                tracker.record_event(&OperationEvent::new(operation_id, kind, None)).await?;
+            }
+            if transition == Some(OperationEventKind::Succeeded) {
+                self.meter_usage(&operation).await;
+            }
+            if transition == Some(OperationEventKind::Failed) {
+                self.fail_operation_job(operation_id).await;
+            }
+            if let (Some(metrics), Some(kind)) = (&self.operation_metrics, transition) {
+                if operation.status.is_terminal() {
+                    // As observed by polling, so a late poll overstates it
+                    let elapsed = (chrono::Utc::now() - operation.created_at).to_std().unwrap_or_default();
+                    metrics.record_completion(&operation.tenant_id, operation.model_type, kind, elapsed);
+                }
+            }
             if let Some(ref result) = result {
                 tracker.store_result(operation_id, result).await?;
+                if let Some(ref raw) = raw {
+                    tracker.store_raw_response(operation_id, raw).await?;
+                }
             }
         }
+        if let (Some(OperationEventKind::Succeeded), Some(ref result)) = (transition, &result) {
+            self.index_result(&operation, result).await;
+            self.embed_result(&operation, result).await;
+        }
         
-        // Use stored model_type if available
-        if let Some(stored_op) = stored_operation {
-            operation.model_type = stored_op.model_type;
+        // Announced once stored, so consumers can fetch the result straight away
+        if let Some(kind) = transition.and(LifecycleEventKind::for_status(operation.status)) {
+            let mut event = LifecycleEvent::new(kind, &operation);
+            if let Some(ref result) = result {
+                event = event.with_summary(result);
+            }
+            self.publish_event(event).await;
         }
         
-        Ok((operation, result))
+        Ok((operation, result.map(|result| result.project(fields))))
+    }
+    
+    /// Count a newly succeeded operation's pages; failures are logged rather than failing the poll
+    async fn meter_usage(&self, operation: &AnalysisOperation) {
+        let Some(usage_meter) = &self.usage_meter else {
+            return;
+        };
+        let pages = operation.page_count.unwrap_or_default();
+        let day = chrono::Utc::now().date_naive();
+        if let Err(e) = usage_meter
+            .record_usage(&operation.tenant_id, day, operation.model_type, pages)
+            .await
+        {
+            error!("Failed to record usage for operation {}: {}", operation.operation_id, e);
+        }
+    }
+    
+    /// Publish a lifecycle event; failures are logged rather than failing the call
+    async fn publish_event(&self, event: LifecycleEvent) {
+        if let Some(publisher) = &self.event_publisher {
+            if let Err(e) = publisher.publish(&event).await {
+                error!(
+                    "Failed to publish {} event for operation {}: {}",
+                    event.event_type.as_str(),
+                    event.operation_id,
+                    e
+                );
+            }
+        }
+    }
+    
+    /// Push a succeeded result to the search index; failures are logged rather than failing the call
+    async fn index_result(&self, operation: &AnalysisOperation, result: &AnalysisResult) {
+        if let Some(index) = &self.search_index {
+            if let Err(e) = index.index(&SearchDocument::new(operation, result)).await {
+                error!("Failed to index operation {}: {}", operation.operation_id, e);
+            }
+        }
+    }
+    
+    /// Embed a succeeded result's content chunks and store them; failures are logged rather than failing the call
+    async fn embed_result(&self, operation: &AnalysisOperation, result: &AnalysisResult) {
+        let Some((provider, store)) = &self.embeddings else {
+            return;
+        };
+        let chunks = self.chunking.chunk_result(result);
+        if chunks.is_empty() {
+            return;
+        }
+        let embedded = async {
+            let texts: Vec<String> = chunks.iter().map(|chunk| chunk.text.clone()).collect();
+            let vectors = provider.embed(&texts).await?;
+            if vectors.len() != chunks.len() {
+                return Err(ApplicationError::Internal(format!(
+                    "Embeddings provider returned {} vectors for {} chunks",
+                    vectors.len(),
+                    chunks.len()
+                )));
+            }
+            let chunks: Vec<EmbeddedChunk> = chunks
+                .into_iter()
+                .zip(vectors)
+                .enumerate()
+                .map(|(index, (chunk, embedding))| EmbeddedChunk {
+                    index: index as u32,
+                    page_number: chunk.page_number,
+                    text: chunk.text,
+                    embedding,
+                })
+                .collect();
+            store.store_chunks(&operation.operation_id, &operation.tenant_id, &chunks).await?;
+            Ok(chunks.len())
+        }
+        .await;
+        match embedded {
+            Ok(count) => info!("Embedded {} chunks of operation {}", count, operation.operation_id),
+            Err(e) => error!("Failed to embed operation {}: {}", operation.operation_id, e),
+        }
+    }
+    
+    /// The tenant's stored chunks closest in meaning to the query, closest first
+    pub async fn semantic_search(&self, tenant: &TenantId, query: &SemanticQuery) -> ApplicationResult<Vec<ChunkMatch>> {
+        let (provider, store) = self.embeddings.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Embeddings are not configured".to_string())
+        })?;
+        let embedding = provider
+            .embed(std::slice::from_ref(&query.query))
+            .await?
+            .pop()
+            .ok_or_else(|| ApplicationError::Internal("Embeddings provider returned no vector for the query".to_string()))?;
+        store.search_chunks(tenant, &embedding, query.top_k).await
+    }
+    
+    /// Daily usage totals in a date range
+    pub async fn usage_report(&self, query: &UsageQuery) -> ApplicationResult<Vec<UsageRecord>> {
+        let usage_meter = self.usage_meter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Usage metering is not configured".to_string())
+        })?;
+        query.validate()?;
+        usage_meter.usage(query).await
+    }
+    
+    /// Refuse to submit for a tenant that has reached any of its quotas
+    async fn check_quotas(&self, tenant: &TenantId) -> ApplicationResult<()> {
+        if self.usage_meter.is_none() {
+            return Ok(());
+        }
+        let mut quotas = match &self.quotas {
+            Some(quotas) => quotas.list_quotas(Some(tenant)).await?,
+            None => Vec::new(),
+        };
+        quotas.extend(
+            self.configured_quotas
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .filter(|quota| &quota.tenant_id == tenant)
+                .cloned(),
+        );
+        for quota in quotas {
+            let usage = self.quota_usage(&quota).await?;
+            if let Some(detail) = quota.exceeded_by(usage.operations, usage.pages) {
+                warn!("Tenant {} is over quota: {}", tenant, detail);
+                return Err(ApplicationError::QuotaExceeded {
+                    detail,
+                    resets_at: usage.resets_at,
+                });
+            }
+        }
+        Ok(())
+    }
+    
+    /// Metered usage in the quota's current window
+    async fn quota_usage(&self, quota: &Quota) -> ApplicationResult<QuotaUsage> {
+        let usage_meter = self.usage_meter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Quotas require usage metering".to_string())
+        })?;
+        let today = chrono::Utc::now().date_naive();
+        let query = UsageQuery {
+            tenant: Some(quota.tenant_id.clone()),
+            from: quota.period.start(today),
+            to: today,
+        };
+        let records = usage_meter.usage(&query).await?;
+        Ok(QuotaUsage {
+            operations: records.iter().map(|record| record.operations).sum(),
+            pages: records.iter().map(|record| record.pages).sum(),
+            resets_at: quota.period.resets_at(today),
+        })
+    }
+    
+    fn quota_store(&self) -> ApplicationResult<&Arc<dyn QuotaPort>> {
+        self.quotas
+            .as_ref()
+            .ok_or_else(|| ApplicationError::Configuration("Quotas are not configured".to_string()))
+    }
+    
+    /// Quotas of one tenant, or of every tenant, with usage in their current windows
+    pub async fn quotas(&self, tenant: Option<&TenantId>) -> ApplicationResult<Vec<(Quota, QuotaUsage)>> {
+        let mut quotas = Vec::new();
+        for quota in self.quota_store()?.list_quotas(tenant).await? {
+            let usage = self.quota_usage(&quota).await?;
+            quotas.push((quota, usage));
+        }
+        Ok(quotas)
+    }
+    
+    /// Create or replace a tenant's quota for its period
+    pub async fn set_quota(&self, quota: &Quota) -> ApplicationResult<()> {
+        self.quota_store()?.set_quota(quota).await?;
+        info!(
+            "Quota set for tenant {}: {} operations={:?} pages={:?}",
+            quota.tenant_id,
+            quota.period.as_str(),
+            quota.max_operations,
+            quota.max_pages
+        );
+        Ok(())
+    }
+    
+    /// Remove a tenant's quota for `period`
+    pub async fn delete_quota(&self, tenant: &TenantId, period: QuotaPeriod) -> ApplicationResult<()> {
+        if !self.quota_store()?.delete_quota(tenant, period).await? {
+            return Err(ApplicationError::QuotaNotFound(format!("{} {}", tenant, period.as_str())));
+        }
+        Ok(())
+    }
+    
+    /// The tracked operation, if it belongs to `tenant`
+    ///
+    /// Another tenant's operation is reported as not found. Operations the
+    /// tracker doesn't know about are only passed through to the provider for
+    /// the default tenant, since nothing records who submitted them.
+    async fn tenant_operation(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+    ) -> ApplicationResult<Option<AnalysisOperation>> {
+        let Some(tracker) = &self.tracker_adapter else {
+            return Ok(None);
+        };
+        match tracker.get_operation(operation_id).await? {
+            // Deleted operations are not found, rather than fetched from Azure again
+            Some(op) if op.tenant_id == *tenant && !op.is_deleted() => Ok(Some(op)),
+            None if tenant.is_default() => Ok(None),
+            _ => Err(ApplicationError::OperationNotFound(operation_id.to_string())),
+        }
+    }
+    
+    /// Hide an operation and its result until it is restored or purged
+    pub async fn delete_operation(&self, tenant: &TenantId, operation_id: &str) -> ApplicationResult<AnalysisOperation> {
+        let tracker = self.tracker_adapter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Deleting operations requires an operation tracker".to_string())
+        })?;
+        let mut operation = self
+            .tenant_operation(tenant, operation_id)
+            .await?
+            .ok_or_else(|| ApplicationError::OperationNotFound(operation_id.to_string()))?;
+        operation.deleted_at = Some(chrono::Utc::now());
+        tracker.set_deleted(operation_id, operation.deleted_at).await?;
+        info!("Deleted operation {} of tenant {}", operation_id, tenant);
+        Ok(operation)
+    }
+    
+    /// Bring back a deleted operation that has not been purged yet
+    pub async fn restore_operation(&self, tenant: &TenantId, operation_id: &str) -> ApplicationResult<AnalysisOperation> {
+        let tracker = self.tracker_adapter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Restoring operations requires an operation tracker".to_string())
+        })?;
+        let mut operation = tracker
+            .get_operation(operation_id)
+            .await?
+            .filter(|op| op.tenant_id == *tenant)
+            .ok_or_else(|| ApplicationError::OperationNotFound(operation_id.to_string()))?;
+        if operation.deleted_at.take().is_some() {
+            tracker.set_deleted(operation_id, None).await?;
+            info!("Restored operation {} of tenant {}", operation_id, tenant);
+        }
+        Ok(operation)
+    }
+    
+    /// A tenant's operations, newest first
+    pub async fn list_operations(
+        &self,
+        tenant: &TenantId,
+        query: &OperationListQuery,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let tracker = self.tracker_adapter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Listing operations requires an operation tracker".to_string())
+        })?;
+        query.validate()?;
+        tracker.list_operations(tenant, query).await
+    }
+    
+    /// Counts, median completion time and failure ratio of a tenant's operations, by group
+    pub async fn operation_stats(
+        &self,
+        tenant: &TenantId,
+        query: &OperationStatsQuery,
+    ) -> ApplicationResult<Vec<OperationStats>> {
+        let tracker = self.tracker_adapter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Operation statistics require an operation tracker".to_string())
+        })?;
+        query.validate()?;
+        tracker.operation_stats(tenant, query).await
+    }
+    
+    /// Operations of every tenant created in `[from, to)`, newest first, paged by `before` and `before_id`
+    pub async fn operations_created(
+        &self,
+        from: chrono::DateTime<chrono::Utc>,
+        to: chrono::DateTime<chrono::Utc>,
+        before: Option<chrono::DateTime<chrono::Utc>>,
+        before_id: Option<&str>,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let tracker = self.tracker_adapter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Listing operations requires an operation tracker".to_string())
+        })?;
+        tracker.list_operations_created(from, to, before, before_id, limit).await
+    }
+    
+    /// A tenant's operations waiting for review, newest first
+    pub async fn review_queue(
+        &self,
+        tenant: &TenantId,
+        before: Option<chrono::DateTime<chrono::Utc>>,
+        before_id: Option<String>,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let query = OperationListQuery {
+            review: Some(ReviewStatus::NeedsReview),
+            before,
+            before_id,
+            limit,
+            ..Default::default()
+        };
+        self.list_operations(tenant, &query).await
+    }
+    
+    /// Apply a reviewer's corrections to a succeeded result as its next revision
+    ///
+    /// The corrected result replaces the stored one, so every read of the
+    /// result sees it; the extraction it replaces is kept as the previous
+    /// revision. The operation leaves the review queue.
+    pub async fn correct_result(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+        corrections: Vec<FieldCorrection>,
+        reviewer: Option<String>,
+    ) -> ApplicationResult<ResultRevision> {
+        let (revisions, tracker) = match (&self.revisions, &self.tracker_adapter) {
+            (Some(revisions), Some(tracker)) => (revisions, tracker),
+            _ => {
+                return Err(ApplicationError::Configuration(
+                    "Corrections require an operation tracker".to_string(),
+                ))
+            }
+        };
+        crate::domain::validate_corrections(&corrections)?;
+        let (mut operation, result) = self.completed_result(tenant, operation_id).await?;
+        
+        // The first correction also keeps the extraction as analyzed
+        let latest = match revisions.list_revisions(operation_id).await?.pop() {
+            Some(latest) => latest,
+            None => {
+                let original = ResultRevision::original(operation_id, result);
+                revisions.store_revision(&original).await?;
+                original
+            }
+        };
+        let revision = latest.correct(corrections, reviewer)?;
+        revisions.store_revision(&revision).await?;
+        tracker.store_result(operation_id, &revision.result).await?;
+        
+        operation
+            .review
+            .get_or_insert_with(|| ReviewState::needs_review(Vec::new()))
+            .reviewed(&revision);
+        tracker.update_operation(&operation).await?;
+        self.index_result(&operation, &revision.result).await;
+        info!(
+            "Operation {} corrected as revision {} ({} fields)",
+            operation_id,
+            revision.revision,
+            revision.corrections.len()
+        );
+        Ok(revision)
+    }
+    
+    /// Every revision of a corrected result, oldest first; empty for uncorrected results
+    pub async fn result_revisions(&self, tenant: &TenantId, operation_id: &str) -> ApplicationResult<Vec<ResultRevision>> {
+        let revisions = self.revisions.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Result revisions require an operation tracker".to_string())
+        })?;
+        self.completed_result_fields(tenant, operation_id, &ResultFields::none()).await?;
+        revisions.list_revisions(operation_id).await
+    }
+    
+    /// The provider's untouched response for a succeeded operation
+    pub async fn raw_result(&self, tenant: &TenantId, operation_id: &str) -> ApplicationResult<serde_json::Value> {
+        let tracker = self.tracker_adapter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Raw responses require an operation tracker".to_string())
+        })?;
+        // Polls the operation first so a just-finished result gets stored
+        self.completed_result_fields(tenant, operation_id, &ResultFields::none()).await?;
+        tracker.get_raw_response(operation_id).await?.ok_or_else(|| {
+            ApplicationError::ResultNotAvailable(format!("no raw response stored for operation {}", operation_id))
+        })
+    }
+    
+    /// Status transitions recorded for an operation, oldest first
+    pub async fn operation_events(
+        &self,
+        tenant: &TenantId,
+        operation_id: &str,
+    ) -> ApplicationResult<Vec<OperationEvent>> {
+        let tracker = self.tracker_adapter.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Operation events require an operation tracker".to_string())
+        })?;
+        if self.tenant_operation(tenant, operation_id).await?.is_none() {
+            return Err(ApplicationError::OperationNotFound(operation_id.to_string()));
+        }
+        tracker.list_events(operation_id).await
+    }
+    
+    /// Prior operations for uploaded content with this SHA-256, newest first
+    pub async fn lookup_by_sha256(
+        &self,
+        tenant: &TenantId,
+        sha256: &str,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        match &self.tracker_adapter {
+            Some(tracker) => {
+                tracker
+                    .find_operations_by_sha256(tenant, &sha256.to_ascii_lowercase(), limit)
+                    .await
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+    
+    /// Size in bytes of a stored document
+    pub async fn document_size(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<u64> {
+        self.storage()?.document_size(tenant, document_id).await
+    }
+    
+    /// Store a document written as a stream, returning its identifier
+    pub async fn store_document_stream(
+        &self,
+        tenant: &TenantId,
+        filename: &str,
+        content_type: &str,
+        body: BoxStream<'_, ApplicationResult<Bytes>>,
+    ) -> ApplicationResult<String> {
+        self.storage()?.store_stream(tenant, filename, content_type, body).await
+    }
+    
+    /// Bytes of a stored document
+    pub async fn retrieve_document(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<Bytes> {
+        self.storage()?.retrieve_document(tenant, document_id).await
+    }
+    
+    /// Stream a stored document, or a byte range of it, without buffering it
+    pub async fn open_document(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        range: Option<ByteRange>,
+    ) -> ApplicationResult<DocumentStream> {
+        self.storage()?.open_document(tenant, document_id, range).await
+    }
+    
+    /// Signed link to a stored document, valid for `expires_in`
+    pub async fn document_url(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        expires_in: chrono::Duration,
+    ) -> ApplicationResult<SignedUrl> {
+        self.storage()?.get_document_url(tenant, document_id, expires_in).await
+    }
+    
+    /// Check the expiry and signature of a document link
+    pub async fn verify_document_url(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        expires: i64,
+        signature: &str,
+    ) -> ApplicationResult<()> {
+        self.storage()?.verify_document_url(tenant, document_id, expires, signature).await
+    }
+    
+    /// Filename and content type a stored document was uploaded with
+    ///
+    /// Taken from the operation that analyzed it; documents without a tracked
+    /// operation fall back to the generic metadata defaults.
+    pub async fn document_metadata(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+    ) -> ApplicationResult<DocumentMetadata> {
+        let operation = match &self.tracker_adapter {
+            Some(tracker) => tracker.find_operation_by_document(tenant, document_id, false).await?,
+            None => None,
+        };
+        
+        Ok(match operation {
+            Some(op) => DocumentMetadata::new(
+                op.filename.unwrap_or_default(),
+                op.content_type.unwrap_or_default(),
+            ),
+            None => DocumentMetadata::new("", ""),
+        })
+    }
+    
+    fn storage(&self) -> ApplicationResult<&Arc<dyn DocumentStoragePort>> {
+        self.storage_adapter
+            .as_ref()
+            .ok_or_else(|| ApplicationError::Configuration("Document storage is not configured".to_string()))
+    }
+    
+    /// Lease up to `limit` of the tenant's succeeded operations from a work queue to `worker_id`
+    pub async fn claim_work(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        worker_id: &str,
+        lease: chrono::Duration,
+        limit: u32,
+    ) -> ApplicationResult<Vec<WorkLease>> {
+        let leases = self.work_queue()?.claim(tenant, queue, worker_id, lease, limit).await?;
+        info!("Worker {} claimed {} {} items", worker_id, leases.len(), queue.as_str());
+        Ok(leases)
+    }
+    
+    /// Extend a lease; fails with `LeaseNotHeld` if the worker lost it
+    pub async fn heartbeat_work(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+        lease: chrono::Duration,
+    ) -> ApplicationResult<WorkLease> {
+        self.work_queue()?
+            .heartbeat(tenant, queue, operation_id, worker_id, lease)
+            .await?
+            .ok_or_else(|| ApplicationError::LeaseNotHeld(operation_id.to_string()))
+    }
+    
+    /// Mark a leased item done
+    pub async fn complete_work(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+    ) -> ApplicationResult<()> {
+        if !self.work_queue()?.complete(tenant, queue, operation_id, worker_id).await? {
+            return Err(ApplicationError::LeaseNotHeld(operation_id.to_string()));
+        }
+        info!("Worker {} completed {} item {}", worker_id, queue.as_str(), operation_id);
+        Ok(())
+    }
+    
+    /// Hand a leased item back to the queue
+    pub async fn release_work(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+    ) -> ApplicationResult<()> {
+        if !self.work_queue()?.release(tenant, queue, operation_id, worker_id).await? {
+            return Err(ApplicationError::LeaseNotHeld(operation_id.to_string()));
+        }
+        Ok(())
+    }
+    
+    /// Refuse callers that do not hold `role`
+    pub fn authorize(&self, principal: &Principal, role: Role) -> ApplicationResult<()> {
+        if principal.has_role(role) {
+            Ok(())
+        } else {
+            Err(ApplicationError::PermissionDenied(format!(
+                "{} lacks the {} role",
+                principal.id,
+                role.as_str()
+            )))
+        }
+    }
+    
+    /// Whether mutating calls should be reported to `record_audit`
+    pub fn audit_enabled(&self) -> bool {
+        self.audit_log.is_some()
+    }
+    
+    /// Append to the audit log; failures are logged rather than failing the audited call
+    pub async fn record_audit(&self, entry: &AuditEntry) {
+        if let Some(audit_log) = &self.audit_log {
+            if let Err(e) = audit_log.record(entry).await {
+                error!("Failed to record audit entry for {} by {}: {}", entry.action, entry.principal, e);
+            }
+        }
+    }
+    
+    /// Audit log entries matching `query`, newest first
+    pub async fn audit_entries(&self, query: &AuditQuery) -> ApplicationResult<Vec<AuditEntry>> {
+        let audit_log = self.audit_log.as_ref().ok_or_else(|| {
+            ApplicationError::Configuration("Audit log is not configured".to_string())
+        })?;
+        query.validate()?;
+        audit_log.query(query).await
+    }
+    
+    fn work_queue(&self) -> ApplicationResult<&Arc<dyn WorkQueuePort>> {
+        self.work_queue
+            .as_ref()
+            .ok_or_else(|| ApplicationError::Configuration("Work queue is not configured".to_string()))
+    }
+    
+    fn job_queue(&self) -> ApplicationResult<&Arc<dyn JobQueuePort>> {
+        self.job_queue
+            .as_ref()
+            .ok_or_else(|| ApplicationError::Configuration("Asynchronous jobs are not enabled".to_string()))
     }
     
     /// Analyze with Read model
     pub async fn analyze_read(
         &self,
+        tenant: &TenantId,
         source: DocumentSource,
     ) -> ApplicationResult<AnalysisOperation> {
         let request = AnalyzeDocumentRequest {
             source,
             model_type: ModelType::Read,
             options: Default::default(),
+            metadata: None,
+            tenant_id: tenant.clone(),
         };
         self.analyze_document(request).await
     }
@@ -137,12 +1738,15 @@ impl DocumentIntelligenceService {
     /// Analyze with Layout model
     pub async fn analyze_layout(
         &self,
+        tenant: &TenantId,
         source: DocumentSource,
     ) -> ApplicationResult<AnalysisOperation> {
         let request = AnalyzeDocumentRequest {
             source,
             model_type: ModelType::Layout,
             options: Default::default(),
+            metadata: None,
+            tenant_id: tenant.clone(),
         };
         self.analyze_document(request).await
     }
@@ -150,12 +1754,15 @@ impl DocumentIntelligenceService {
     /// Analyze invoice
     pub async fn analyze_invoice(
         &self,
+        tenant: &TenantId,
         source: DocumentSource,
     ) -> ApplicationResult<AnalysisOperation> {
         let request = AnalyzeDocumentRequest {
             source,
             model_type: ModelType::Invoice,
             options: Default::default(),
+            metadata: None,
+            tenant_id: tenant.clone(),
         };
         self.analyze_document(request).await
     }
@@ -163,12 +1770,15 @@ impl DocumentIntelligenceService {
     /// Analyze receipt
     pub async fn analyze_receipt(
         &self,
+        tenant: &TenantId,
         source: DocumentSource,
     ) -> ApplicationResult<AnalysisOperation> {
         let request = AnalyzeDocumentRequest {
             source,
             model_type: ModelType::Receipt,
             options: Default::default(),
+            metadata: None,
+            tenant_id: tenant.clone(),
         };
         self.analyze_document(request).await
     }
@@ -176,12 +1786,15 @@ impl DocumentIntelligenceService {
     /// Analyze ID document
     pub async fn analyze_id_document(
         &self,
+        tenant: &TenantId,
         source: DocumentSource,
     ) -> ApplicationResult<AnalysisOperation> {
         let request = AnalyzeDocumentRequest {
             source,
             model_type: ModelType::IdDocument,
             options: Default::default(),
+            metadata: None,
+            tenant_id: tenant.clone(),
         };
         self.analyze_document(request).await
     }
@@ -189,12 +1802,15 @@ impl DocumentIntelligenceService {
     /// Analyze business card
     pub async fn analyze_business_card(
         &self,
+        tenant: &TenantId,
         source: DocumentSource,
     ) -> ApplicationResult<AnalysisOperation> {
         let request = AnalyzeDocumentRequest {
             source,
             model_type: ModelType::BusinessCard,
             options: Default::default(),
+            metadata: None,
+            tenant_id: tenant.clone(),
         };
         self.analyze_document(request).await
     }
@@ -202,12 +1818,15 @@ impl DocumentIntelligenceService {
     /// Analyze W-2 tax form
     pub async fn analyze_w2(
         &self,
+        tenant: &TenantId,
         source: DocumentSource,
     ) -> ApplicationResult<AnalysisOperation> {
         let request = AnalyzeDocumentRequest {
             source,
             model_type: ModelType::W2,
             options: Default::default(),
+            metadata: None,
+            tenant_id: tenant.clone(),
         };
         self.analyze_document(request).await
     }
@@ -215,6 +1834,7 @@ impl DocumentIntelligenceService {
     /// Analyze with custom model
     pub async fn analyze_custom(
         &self,
+        tenant: &TenantId,
         source: DocumentSource,
         model_id: &str,
     ) -> ApplicationResult<AnalysisOperation> {
@@ -236,12 +1856,22 @@ impl DocumentIntelligenceService {
             source,
             model_type: ModelType::Custom,
             options: Default::default(),
+            metadata: None,
+            tenant_id: tenant.clone(),
         };
         
         self.analyze_document(request).await
     }
 }
 
+/// Reject analysis options that cannot be honoured
+fn validate_options(options: &AnalyzeOptions) -> ApplicationResult<()> {
+    if let Some(preprocess) = &options.preprocess {
+        preprocess.validate()?;
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -279,13 +1909,165 @@ mod tests {
         let adapter = Arc::new(MockIntelligenceAdapter);
         let service = DocumentIntelligenceService::new(adapter, None, None);
         
+        let tenant = TenantId::new("acme").unwrap();
         let result = service
-            .analyze_read(DocumentSource::Url("https://example.com/doc.pdf".to_string()))
+            .analyze_read(&tenant, DocumentSource::Url("https://example.com/doc.pdf".to_string()))
             .await;
         
         assert!(result.is_ok());
         let operation = result.unwrap();
         assert_eq!(operation.model_type, ModelType::Read);
+        assert_eq!(operation.tenant_id, tenant);
+    }
+
+    /// Adapter whose submissions Azure refuses
+    struct RejectingIntelligenceAdapter;
+
+    #[async_trait]
+    impl DocumentIntelligencePort for RejectingIntelligenceAdapter {
+        async fn analyze_document(
+            &self,
+            _request: AnalyzeDocumentRequest,
+        ) -> ApplicationResult<AnalysisOperation> {
+            Err(ApplicationError::AnalysisFailed("Service unavailable".to_string()))
+        }
+
+        async fn get_analysis_result(
+            &self,
+            operation_id: &str,
+        ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)> {
+            Err(ApplicationError::OperationNotFound(operation_id.to_string()))
+        }
+
+        async fn validate_custom_model(&self, _model_id: &str) -> ApplicationResult<bool> {
+            Ok(false)
+        }
+    }
+
+    /// Storage whose writes succeed or fail as configured
+    #[derive(Default)]
+    struct MockStorage {
+        fail: bool,
+        deleted: std::sync::Mutex<Vec<String>>,
+    }
+
+    #[async_trait]
+    impl DocumentStoragePort for MockStorage {
+        async fn store_document(
+            &self,
+            _tenant: &TenantId,
+            _filename: &str,
+            _content_type: &str,
+            _data: Bytes,
+        ) -> ApplicationResult<String> {
+            if self.fail {
+                return Err(ApplicationError::Internal("Disk full".to_string()));
+            }
+            Ok("doc-1".to_string())
+        }
+
+        async fn retrieve_document(&self, _tenant: &TenantId, document_id: &str) -> ApplicationResult<Bytes> {
+            Err(ApplicationError::DocumentNotFound(document_id.to_string()))
+        }
+
+        async fn delete_document(&self, _tenant: &TenantId, document_id: &str) -> ApplicationResult<()> {
+            self.deleted.lock().unwrap().push(document_id.to_string());
+            Ok(())
+        }
+
+        async fn get_document_url(
+            &self,
+            _tenant: &TenantId,
+            _document_id: &str,
+            _expires_in: chrono::Duration,
+        ) -> ApplicationResult<SignedUrl> {
+            Err(ApplicationError::Configuration("Document links are not supported".to_string()))
+        }
+
+        async fn purge_documents(&self, _cutoff: chrono::DateTime<chrono::Utc>) -> ApplicationResult<u64> {
+            Ok(0)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_uploads_are_stored_alongside_analysis() {
+        for (fail, document_id) in [(false, Some("doc-1")), (true, None)] {
+            let storage = Arc::new(MockStorage { fail, ..Default::default() });
+            let service = DocumentIntelligenceService::new(Arc::new(MockIntelligenceAdapter), Some(storage), None);
+
+            let operation = service.analyze_document(upload()).await.unwrap();
+            assert_eq!(operation.document_id.as_deref(), document_id);
+            assert_eq!(operation.filename.as_deref(), Some("a.pdf"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failed_submission_discards_stored_upload() {
+        let storage = Arc::new(MockStorage::default());
+        let service = DocumentIntelligenceService::new(Arc::new(RejectingIntelligenceAdapter), Some(storage.clone()), None);
+
+        assert!(service.analyze_document(upload()).await.is_err());
+        assert_eq!(*storage.deleted.lock().unwrap(), vec!["doc-1".to_string()]);
     }
-}
 
+    /// Adapter reporting operations as running, deleting them while Azure is asked
+    struct DeletingIntelligenceAdapter {
+        tracker: Arc<crate::infrastructure::InMemoryOperationTracker>,
+    }
+
+    #[async_trait]
+    impl DocumentIntelligencePort for DeletingIntelligenceAdapter {
+        async fn analyze_document(
+            &self,
+            _request: AnalyzeDocumentRequest,
+        ) -> ApplicationResult<AnalysisOperation> {
+            Ok(AnalysisOperation::new(ModelType::Read))
+        }
+
+        async fn get_analysis_result(
+            &self,
+            operation_id: &str,
+        ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)> {
+            self.tracker.set_deleted(operation_id, Some(chrono::Utc::now())).await?;
+            let mut op = AnalysisOperation::new(ModelType::Read);
+            op.operation_id = operation_id.to_string();
+            op.update_status(OperationStatus::Running);
+            Ok((op, None))
+        }
+
+        async fn validate_custom_model(&self, _model_id: &str) -> ApplicationResult<bool> {
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_poll_keeps_deletion_made_while_polling() {
+        let tracker = Arc::new(crate::infrastructure::InMemoryOperationTracker::new());
+        let operation = AnalysisOperation {
+            filename: Some("a.pdf".to_string()),
+            ..AnalysisOperation::new(ModelType::Invoice)
+        };
+        tracker.store_operation(&operation).await.unwrap();
+        let adapter = Arc::new(DeletingIntelligenceAdapter { tracker: tracker.clone() });
+        let service = DocumentIntelligenceService::new(adapter, None, Some(tracker.clone()));
+
+        let (polled, _) = service.get_analysis_result(&TenantId::default(), &operation.operation_id).await.unwrap();
+        assert_eq!(polled.status, OperationStatus::Running);
+        assert_eq!(polled.model_type, ModelType::Invoice);
+
+        let stored = tracker.get_operation(&operation.operation_id).await.unwrap().unwrap();
+        assert_eq!(stored.status, OperationStatus::Running);
+        assert_eq!(stored.filename.as_deref(), Some("a.pdf"));
+        assert!(stored.is_deleted(), "a poll in flight must not undelete the operation");
+    }
+
+    fn upload() -> AnalyzeDocumentRequest {
+        AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(Bytes::from_static(b"%PDF-1.7\n%%EOF")),
+            model_type: ModelType::Read,
+            options: AnalyzeOptions::default(),
+            metadata: Some(DocumentMetadata::new("a.pdf", "application/pdf")),
+            tenant_id: TenantId::default(),
+        }
+    }
+}
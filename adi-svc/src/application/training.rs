@@ -0,0 +1,184 @@
+/// Training dataset export use case
+///
+/// Writes a tenant's reviewed results as an Azure custom-model labeling
+/// dataset: each source document next to its `.ocr.json` layout and its
+/// `.labels.json` built from the latest corrected revision, plus a
+/// `fields.json` declaring every label. A custom model trained on the folder
+/// learns from what reviewers fixed.
+
+use bytes::Bytes;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use tracing::{info, warn};
+
+use crate::domain::{
+    fields_json, label_document, ocr_json, AnalysisOperation, DocumentFormat, DocumentLabels, OperationListQuery,
+    ReviewStatus, TenantId,
+};
+use super::errors::{ApplicationError, ApplicationResult};
+use super::ports::DatasetWriterPort;
+use super::services::DocumentIntelligenceService;
+
+/// Reviewed operation left out of an export
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SkippedDocument {
+    pub operation_id: String,
+    pub reason: String,
+}
+
+/// Field whose value was found nowhere on its document's pages
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct UnlocatedField {
+    pub operation_id: String,
+    pub field: String,
+}
+
+/// Summary of one export
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct TrainingExportReport {
+    /// Folder of the container the dataset was written to
+    pub folder: String,
+    /// Documents written with their labels
+    pub documents: u32,
+    /// Field names declared in `fields.json`
+    pub fields: Vec<String>,
+    pub skipped: Vec<SkippedDocument>,
+    /// Fields left unlabeled
+    pub unlocated: Vec<UnlocatedField>,
+}
+
+/// Files of one document in the dataset
+struct DatasetDocument {
+    /// File name of the document, which its other files are named after
+    name: String,
+    format: DocumentFormat,
+    content: Bytes,
+    ocr: serde_json::Value,
+    labels: DocumentLabels,
+    unlocated: Vec<String>,
+}
+
+/// Training dataset export service
+pub struct TrainingExportService {
+    service: Arc<DocumentIntelligenceService>,
+    writer: Arc<dyn DatasetWriterPort>,
+    prefix: String,
+}
+
+impl std::fmt::Debug for TrainingExportService {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TrainingExportService")
+            .field("prefix", &self.prefix)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TrainingExportService {
+    /// Service writing datasets under `prefix` of the writer's destination
+    pub fn new(service: Arc<DocumentIntelligenceService>, writer: Arc<dyn DatasetWriterPort>, prefix: &str) -> Self {
+        let prefix = prefix.trim_matches('/');
+        Self {
+            service,
+            writer,
+            prefix: if prefix.is_empty() { String::new() } else { format!("{}/", prefix) },
+        }
+    }
+
+    /// Export every reviewed result of `tenant` to `<prefix><tenant>/`
+    ///
+    /// Files of documents exported before are overwritten, so repeated
+    /// exports leave one complete, current dataset per tenant.
+    pub async fn export(&self, tenant: &TenantId) -> ApplicationResult<TrainingExportReport> {
+        let folder = format!("{}{}/", self.prefix, tenant.as_str());
+        let mut report = TrainingExportReport { folder: folder.clone(), ..Default::default() };
+        let mut fields = BTreeSet::new();
+        let mut query = OperationListQuery {
+            review: Some(ReviewStatus::Reviewed),
+            limit: OperationListQuery::MAX_LIMIT,
+            ..Default::default()
+        };
+        loop {
+            let operations = self.service.list_operations(tenant, &query).await?;
+            for operation in &operations {
+                match self.prepare(tenant, operation).await {
+                    Ok(document) => {
+                        self.write(&folder, &document).await?;
+                        report.documents += 1;
+                        fields.extend(document.labels.labels.iter().map(|label| label.label.clone()));
+                        report.unlocated.extend(document.unlocated.into_iter().map(|field| UnlocatedField {
+                            operation_id: operation.operation_id.clone(),
+                            field,
+                        }));
+                    }
+                    Err(reason) => {
+                        warn!("Skipping operation {} in training export: {}", operation.operation_id, reason);
+                        report.skipped.push(SkippedDocument {
+                            operation_id: operation.operation_id.clone(),
+                            reason: reason.to_string(),
+                        });
+                    }
+                }
+            }
+            match operations.last() {
+                Some(last) if operations.len() == query.limit as usize => {
+                    query.before = Some(last.created_at);
+                    query.before_id = Some(last.operation_id.clone());
+                }
+                _ => break,
+            }
+        }
+
+        report.fields = fields.into_iter().collect();
+        if report.documents > 0 {
+            let content = fields_json(report.fields.iter().map(String::as_str));
+            self.write_json(&format!("{}fields.json", folder), &content).await?;
+        }
+        info!(
+            "Exported {} reviewed documents of tenant {} to {} ({} skipped)",
+            report.documents,
+            tenant.as_str(),
+            folder,
+            report.skipped.len()
+        );
+        Ok(report)
+    }
+
+    /// Gather an operation's dataset files, failing when it can't be labeled
+    async fn prepare(&self, tenant: &TenantId, operation: &AnalysisOperation) -> ApplicationResult<DatasetDocument> {
+        let operation_id = &operation.operation_id;
+        let Some(document_id) = operation.document_id.as_deref() else {
+            return Err(ApplicationError::ResultNotAvailable("no stored document".to_string()));
+        };
+        let revisions = self.service.result_revisions(tenant, operation_id).await?;
+        let (Some(original), Some(latest)) = (revisions.first(), revisions.last()) else {
+            return Err(ApplicationError::ResultNotAvailable("no corrected revision".to_string()));
+        };
+        let content = self.service.retrieve_document(tenant, document_id).await?;
+        let format = DocumentFormat::detect(&content)?;
+
+        let name = format!("{}.{}", operation_id, format.extension());
+        let (labels, unlocated) = label_document(&name, &latest.result, &original.result);
+        // The provider's own response carries the full layout; rebuild it when it wasn't kept
+        let ocr = match self.service.raw_result(tenant, operation_id).await {
+            Ok(raw) => raw,
+            Err(_) => ocr_json(&original.result),
+        };
+        Ok(DatasetDocument { name, format, content, ocr, labels, unlocated })
+    }
+
+    async fn write(&self, folder: &str, document: &DatasetDocument) -> ApplicationResult<()> {
+        let path = format!("{}{}", folder, document.name);
+        self.writer.put(&path, document.format.mime_type(), document.content.clone()).await?;
+        self.write_json(&format!("{}.ocr.json", path), &document.ocr).await?;
+        let labels = serde_json::to_value(&document.labels)
+            .map_err(|e| ApplicationError::Internal(format!("Failed to encode labels: {}", e)))?;
+        self.write_json(&format!("{}.labels.json", path), &labels).await
+    }
+
+    async fn write_json(&self, path: &str, content: &serde_json::Value) -> ApplicationResult<()> {
+        let body = serde_json::to_vec_pretty(content)
+            .map_err(|e| ApplicationError::Internal(format!("Failed to encode {}: {}", path, e)))?;
+        self.writer.put(path, "application/json", Bytes::from(body)).await
+    }
+}
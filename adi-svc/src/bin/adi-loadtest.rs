@@ -0,0 +1,408 @@
+/// Load generator
+///
+/// Fires concurrent analyze-and-poll workloads at a running instance over
+/// REST or gRPC and reports latency percentiles and error rates. Point it at
+/// an instance started with `AZURE_MODE=mock` to size the service itself,
+/// without Azure's latency and rate limits.
+///
+/// Usage: adi-loadtest [--target URL] [--protocol rest|grpc] [--model MODEL]
+///                     [--document-url URL] [--concurrency N]
+///                     [--requests N | --duration SECS] [--poll-interval-ms N]
+///                     [--timeout-secs N] [--api-key KEY] [--tenant TENANT]
+///                     [--json]
+
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use adi_svc::client::{AdiClient, ClientError};
+use adi_svc::domain::{DocumentSource, ModelType, TenantId};
+use adi_svc::generated as pb;
+use adi_svc::presentation::audit::API_KEY_HEADER;
+use adi_svc::presentation::tenancy::TENANT_HEADER;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Protocol {
+    Rest,
+    Grpc,
+}
+
+struct Options {
+    target: Option<String>,
+    protocol: Protocol,
+    model: ModelType,
+    document_url: String,
+    concurrency: usize,
+    requests: usize,
+    duration: Option<Duration>,
+    poll_interval: Duration,
+    timeout: Duration,
+    api_key: Option<String>,
+    tenant: Option<TenantId>,
+    json: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            target: None,
+            protocol: Protocol::Rest,
+            model: ModelType::Read,
+            document_url: "https://example.com/sample.pdf".to_string(),
+            concurrency: 10,
+            requests: 100,
+            duration: None,
+            poll_interval: Duration::from_millis(500),
+            timeout: Duration::from_secs(300),
+            api_key: None,
+            tenant: None,
+            json: false,
+        }
+    }
+}
+
+/// State of a polled operation
+enum Poll {
+    Running,
+    Succeeded,
+    Failed(String),
+}
+
+/// Instance under test
+enum Target {
+    Rest {
+        http: reqwest::Client,
+        base_url: String,
+        route: &'static str,
+        api_key: Option<String>,
+        tenant: Option<TenantId>,
+    },
+    Grpc(Box<AdiClient>),
+}
+
+impl Target {
+    /// Submit the document, returning the operation ID
+    async fn submit(&self, options: &Options) -> Result<String, String> {
+        match self {
+            Self::Rest { http, base_url, route, .. } => {
+                let response = self
+                    .rest_request(http.post(format!("{}/api/v1/analyze/{}", base_url, route)))
+                    .json(&serde_json::json!({ "document_url": options.document_url }))
+                    .send()
+                    .await
+                    .map_err(rest_error)?;
+                let body = rest_body(response).await?;
+                body["operation_id"]
+                    .as_str()
+                    .map(str::to_string)
+                    .ok_or_else(|| "response without operation_id".to_string())
+            }
+            Self::Grpc(client) => client
+                .analyze(DocumentSource::Url(options.document_url.clone()), options.model)
+                .await
+                .map(|response| response.operation_id)
+                .map_err(grpc_error),
+        }
+    }
+
+    async fn poll(&self, operation_id: &str) -> Result<Poll, String> {
+        match self {
+            Self::Rest { http, base_url, .. } => {
+                let response = self
+                    .rest_request(http.get(format!("{}/api/v1/results/{}", base_url, operation_id)))
+                    .send()
+                    .await
+                    .map_err(rest_error)?;
+                let body = rest_body(response).await?;
+                Ok(match body["status"].as_str() {
+                    Some("succeeded") => Poll::Succeeded,
+                    Some("failed") | Some("canceled") => Poll::Failed(format!("operation {}", body["status"])),
+                    _ => Poll::Running,
+                })
+            }
+            Self::Grpc(client) => {
+                let response = client.get_result(operation_id).await.map_err(grpc_error)?;
+                Ok(match pb::AnalysisStatus::try_from(response.status) {
+                    Ok(pb::AnalysisStatus::StatusSucceeded) => Poll::Succeeded,
+                    Ok(pb::AnalysisStatus::StatusFailed) => Poll::Failed("operation failed".to_string()),
+                    _ => Poll::Running,
+                })
+            }
+        }
+    }
+
+    fn rest_request(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        let Self::Rest { api_key, tenant, .. } = self else {
+            return request;
+        };
+        let mut request = request;
+        if let Some(api_key) = api_key {
+            request = request.header(API_KEY_HEADER, api_key);
+        }
+        if let Some(tenant) = tenant {
+            request = request.header(TENANT_HEADER, tenant.as_str());
+        }
+        request
+    }
+}
+
+fn rest_error(e: reqwest::Error) -> String {
+    if e.is_timeout() {
+        "HTTP timeout".to_string()
+    } else if e.is_connect() {
+        "connection failed".to_string()
+    } else {
+        "HTTP request failed".to_string()
+    }
+}
+
+async fn rest_body(response: reqwest::Response) -> Result<serde_json::Value, String> {
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("HTTP {}", status.as_u16()));
+    }
+    response.json().await.map_err(|_| "invalid JSON response".to_string())
+}
+
+fn grpc_error(e: ClientError) -> String {
+    match e {
+        ClientError::Status(status) => format!("gRPC {:?}", status.code()),
+        ClientError::Connect(_) => "connection failed".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// Outcome of one analyze-and-poll round trip
+struct Sample {
+    submit: Option<Duration>,
+    total: Option<Duration>,
+    error: Option<String>,
+}
+
+async fn run_one(target: &Target, options: &Options) -> Sample {
+    let started = Instant::now();
+    let operation_id = match target.submit(options).await {
+        Ok(operation_id) => operation_id,
+        Err(error) => return Sample { submit: None, total: None, error: Some(error) },
+    };
+    let submit = Some(started.elapsed());
+
+    loop {
+        if started.elapsed() >= options.timeout {
+            return Sample { submit, total: None, error: Some("timeout".to_string()) };
+        }
+        tokio::time::sleep(options.poll_interval).await;
+        match target.poll(&operation_id).await {
+            Ok(Poll::Running) => {}
+            Ok(Poll::Succeeded) => return Sample { submit, total: Some(started.elapsed()), error: None },
+            Ok(Poll::Failed(error)) | Err(error) => return Sample { submit, total: None, error: Some(error) },
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted durations
+fn percentile(sorted: &[Duration], p: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn latency_summary(mut durations: Vec<Duration>) -> BTreeMap<&'static str, f64> {
+    durations.sort();
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    BTreeMap::from([
+        ("p50_ms", ms(percentile(&durations, 50.0))),
+        ("p90_ms", ms(percentile(&durations, 90.0))),
+        ("p95_ms", ms(percentile(&durations, 95.0))),
+        ("p99_ms", ms(percentile(&durations, 99.0))),
+        ("max_ms", ms(durations.last().copied().unwrap_or_default())),
+    ])
+}
+
+fn parse_args() -> Result<Options, Box<dyn std::error::Error>> {
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        if flag == "--json" {
+            options.json = true;
+            continue;
+        }
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--target" => options.target = Some(value.trim_end_matches('/').to_string()),
+            "--protocol" => {
+                options.protocol = match value.as_str() {
+                    "rest" => Protocol::Rest,
+                    "grpc" => Protocol::Grpc,
+                    _ => return Err(format!("Unknown protocol: {}; use rest or grpc", value).into()),
+                }
+            }
+            "--model" => options.model = ModelType::from_string(&value)?,
+            "--document-url" => options.document_url = value,
+            "--concurrency" => options.concurrency = value.parse()?,
+            "--requests" => options.requests = value.parse()?,
+            "--duration" => options.duration = Some(Duration::from_secs(value.parse()?)),
+            "--poll-interval-ms" => options.poll_interval = Duration::from_millis(value.parse()?),
+            "--timeout-secs" => options.timeout = Duration::from_secs(value.parse()?),
+            "--api-key" => options.api_key = Some(value),
+            "--tenant" => options.tenant = Some(TenantId::new(value)?),
+            _ => return Err(format!("Unknown argument: {}", flag).into()),
+        }
+    }
+    if options.model == ModelType::Custom {
+        return Err("Custom models are not supported; pick a prebuilt model".into());
+    }
+    if options.concurrency == 0 {
+        return Err("--concurrency must be at least 1".into());
+    }
+    Ok(options)
+}
+
+/// REST route segment for a prebuilt model
+fn rest_route(model: ModelType) -> &'static str {
+    match model {
+        ModelType::Read => "read",
+        ModelType::Layout => "layout",
+        ModelType::Invoice => "invoice",
+        ModelType::Receipt => "receipt",
+        ModelType::IdDocument => "id-document",
+        ModelType::BusinessCard => "business-card",
+        ModelType::W2 => "w2",
+        ModelType::Custom => "custom",
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Arc::new(parse_args()?);
+
+    let target = match options.protocol {
+        Protocol::Rest => Target::Rest {
+            http: reqwest::Client::builder()
+                .pool_max_idle_per_host(options.concurrency)
+                .timeout(Duration::from_secs(60))
+                .build()?,
+            base_url: options.target.clone().unwrap_or_else(|| "http://localhost:8080".to_string()),
+            route: rest_route(options.model),
+            api_key: options.api_key.clone(),
+            tenant: options.tenant.clone(),
+        },
+        Protocol::Grpc => {
+            let endpoint = options.target.clone().unwrap_or_else(|| "http://localhost:50051".to_string());
+            let mut client = AdiClient::connect(endpoint).await?;
+            if let Some(api_key) = &options.api_key {
+                client = client.with_api_key(api_key)?;
+            }
+            if let Some(tenant) = &options.tenant {
+                client = client.with_tenant(tenant)?;
+            }
+            Target::Grpc(Box::new(client))
+        }
+    };
+    let target = Arc::new(target);
+
+    match options.duration {
+        Some(duration) => eprintln!(
+            "Running {} {} workers for {:?}...",
+            options.concurrency,
+            if options.protocol == Protocol::Rest { "REST" } else { "gRPC" },
+            duration
+        ),
+        None => eprintln!(
+            "Running {} requests on {} {} workers...",
+            options.requests,
+            options.concurrency,
+            if options.protocol == Protocol::Rest { "REST" } else { "gRPC" }
+        ),
+    }
+
+    // Workers take requests until the count is used up or the duration has passed
+    let started = Instant::now();
+    let deadline = options.duration.map(|duration| started + duration);
+    let issued = Arc::new(AtomicUsize::new(0));
+    let workers: Vec<_> = (0..options.concurrency)
+        .map(|_| {
+            let target = target.clone();
+            let options = options.clone();
+            let issued = issued.clone();
+            tokio::spawn(async move {
+                let mut samples = Vec::new();
+                loop {
+                    let more = match deadline {
+                        Some(deadline) => Instant::now() < deadline,
+                        None => issued.fetch_add(1, Ordering::Relaxed) < options.requests,
+                    };
+                    if !more {
+                        return samples;
+                    }
+                    samples.push(run_one(&target, &options).await);
+                }
+            })
+        })
+        .collect();
+
+    let mut samples = Vec::new();
+    for worker in workers {
+        samples.extend(worker.await?);
+    }
+    let elapsed = started.elapsed();
+
+    let mut errors: BTreeMap<String, usize> = BTreeMap::new();
+    for error in samples.iter().filter_map(|sample| sample.error.clone()) {
+        *errors.entry(error).or_default() += 1;
+    }
+    let total = samples.len();
+    let failed: usize = errors.values().sum();
+    let error_rate = if total == 0 { 0.0 } else { failed as f64 / total as f64 };
+    let throughput = total as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+    let submit = latency_summary(samples.iter().filter_map(|sample| sample.submit).collect());
+    let end_to_end = latency_summary(samples.iter().filter_map(|sample| sample.total).collect());
+
+    if options.json {
+        let report = serde_json::json!({
+            "requests": total,
+            "succeeded": total - failed,
+            "failed": failed,
+            "error_rate": error_rate,
+            "elapsed_secs": elapsed.as_secs_f64(),
+            "throughput_rps": throughput,
+            "submit": submit,
+            "end_to_end": end_to_end,
+            "errors": errors,
+        });
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let line = |summary: &BTreeMap<&str, f64>| {
+        format!(
+            "p50 {:.1}ms  p90 {:.1}ms  p95 {:.1}ms  p99 {:.1}ms  max {:.1}ms",
+            summary["p50_ms"], summary["p90_ms"], summary["p95_ms"], summary["p99_ms"], summary["max_ms"]
+        )
+    };
+    println!(
+        "Requests:    {} ({} succeeded, {} failed, {:.2}% errors)",
+        total,
+        total - failed,
+        failed,
+        error_rate * 100.0
+    );
+    println!("Throughput:  {:.1} req/s over {:.1}s", throughput, elapsed.as_secs_f64());
+    println!("Submit:      {}", line(&submit));
+    println!("End to end:  {}", line(&end_to_end));
+    if !errors.is_empty() {
+        println!("Errors:");
+        for (error, count) in &errors {
+            println!("  {}: {}", error, count);
+        }
+    }
+
+    Ok(())
+}
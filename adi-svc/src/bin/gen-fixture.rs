@@ -0,0 +1,39 @@
+/// Synthetic fixture generator
+///
+/// Writes a deterministic `AnalysisResult` as JSON to stdout.
+///
+/// Usage: gen-fixture [--pages N] [--words-per-page N] [--words-per-line N]
+///                    [--tables N] [--table-rows N] [--table-columns N]
+///                    [--key-value-pairs N] [--model MODEL] [--seed N]
+
+use adi_svc::domain::ModelType;
+use adi_svc::fixtures::{synthetic_result, SyntheticResultSpec};
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut spec = SyntheticResultSpec::default();
+    let mut args = std::env::args().skip(1);
+
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("Missing value for {}", flag))?;
+        match flag.as_str() {
+            "--pages" => spec.pages = value.parse()?,
+            "--words-per-page" => spec.words_per_page = value.parse()?,
+            "--words-per-line" => spec.words_per_line = value.parse()?,
+            "--tables" => spec.tables = value.parse()?,
+            "--table-rows" => spec.table_rows = value.parse()?,
+            "--table-columns" => spec.table_columns = value.parse()?,
+            "--key-value-pairs" => spec.key_value_pairs = value.parse()?,
+            "--model" => spec.model_type = ModelType::from_string(&value)?,
+            "--seed" => spec.seed = value.parse()?,
+            _ => return Err(format!("Unknown argument: {}", flag).into()),
+        }
+    }
+
+    let result = synthetic_result(&spec);
+    serde_json::to_writer(std::io::stdout().lock(), &result)?;
+    println!();
+
+    Ok(())
+}
@@ -0,0 +1,253 @@
+/// Rust client for the adi-svc gRPC API
+///
+/// Wraps the generated tonic stubs so Rust services can submit documents with
+/// domain types and get protobuf results back without building requests by
+/// hand. `analyze_and_wait` submits a document and polls its operation with
+/// capped exponential backoff until it finishes. Needs the `client` feature.
+
+use std::time::Duration;
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::transport::{Channel, Endpoint};
+
+use crate::domain::{AnalyzeOptions, DocumentSource, ModelType, TenantId};
+use crate::generated as pb;
+use crate::generated::document_intelligence_service_client::DocumentIntelligenceServiceClient;
+use crate::presentation::audit::API_KEY_HEADER;
+use crate::presentation::converters::options_to_pb;
+use crate::presentation::tenancy::TENANT_HEADER;
+
+/// Client errors
+#[derive(Debug, thiserror::Error)]
+pub enum ClientError {
+    #[error("Failed to connect: {0}")]
+    Connect(#[from] tonic::transport::Error),
+
+    #[error("Request failed: {0}")]
+    Status(Box<tonic::Status>),
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("Operation {operation_id} failed: {message}")]
+    AnalysisFailed { operation_id: String, message: String },
+
+    #[error("Operation {operation_id} did not finish within {timeout:?}")]
+    Timeout { operation_id: String, timeout: Duration },
+}
+
+pub type ClientResult<T> = Result<T, ClientError>;
+
+impl From<tonic::Status> for ClientError {
+    fn from(status: tonic::Status) -> Self {
+        Self::Status(Box::new(status))
+    }
+}
+
+/// How `wait` polls an operation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PollPolicy {
+    /// Delay before the second poll; doubles after each poll
+    pub initial_delay: Duration,
+    /// Longest delay between polls
+    pub max_delay: Duration,
+    /// Give up once the operation has been polled for this long
+    pub timeout: Duration,
+}
+
+impl Default for PollPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(10),
+            timeout: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+/// Typed client for one adi-svc instance
+///
+/// Cheap to clone; clones share the underlying channel.
+#[derive(Debug, Clone)]
+pub struct AdiClient {
+    inner: DocumentIntelligenceServiceClient<Channel>,
+    api_key: Option<MetadataValue<Ascii>>,
+    tenant: Option<MetadataValue<Ascii>>,
+    poll: PollPolicy,
+}
+
+impl AdiClient {
+    /// Connect to an instance, e.g. `http://adi-svc:50051`
+    pub async fn connect(endpoint: impl Into<String>) -> ClientResult<Self> {
+        let channel = Endpoint::from_shared(endpoint.into())?.connect().await?;
+        Ok(Self::new(channel))
+    }
+
+    pub fn new(channel: Channel) -> Self {
+        Self {
+            inner: DocumentIntelligenceServiceClient::new(channel),
+            api_key: None,
+            tenant: None,
+            poll: PollPolicy::default(),
+        }
+    }
+
+    /// Send `key` as `x-api-key` with every request
+    pub fn with_api_key(mut self, key: &str) -> ClientResult<Self> {
+        self.api_key = Some(
+            key.parse()
+                .map_err(|_| ClientError::InvalidRequest("API key is not valid metadata".to_string()))?,
+        );
+        Ok(self)
+    }
+
+    /// Send `tenant` as `x-tenant-id` with every request, for instances that don't assign tenants by key
+    pub fn with_tenant(mut self, tenant: &TenantId) -> ClientResult<Self> {
+        self.tenant = Some(
+            tenant
+                .as_str()
+                .parse()
+                .map_err(|_| ClientError::InvalidRequest("Tenant is not valid metadata".to_string()))?,
+        );
+        Ok(self)
+    }
+
+    pub fn with_poll_policy(mut self, poll: PollPolicy) -> Self {
+        self.poll = poll;
+        self
+    }
+
+    /// The generated stub, for RPCs this client does not wrap
+    pub fn raw(&self) -> DocumentIntelligenceServiceClient<Channel> {
+        self.inner.clone()
+    }
+
+    /// Submit a document to a prebuilt model
+    pub async fn analyze(&self, source: DocumentSource, model: ModelType) -> ClientResult<pb::AnalyzeResponse> {
+        self.analyze_with_options(source, model, AnalyzeOptions::default()).await
+    }
+
+    pub async fn analyze_with_options(
+        &self,
+        source: DocumentSource,
+        model: ModelType,
+        options: AnalyzeOptions,
+    ) -> ClientResult<pb::AnalyzeResponse> {
+        let request = self.request(pb::AnalyzeRequest {
+            source: Some(match source {
+                DocumentSource::Url(url) => pb::analyze_request::Source::DocumentUrl(url),
+                DocumentSource::Bytes(bytes) => pb::analyze_request::Source::DocumentBytes(bytes),
+            }),
+            options: Some(options_to_pb(options)),
+        });
+        let mut client = self.inner.clone();
+        let response = match model {
+            ModelType::Read => client.analyze_read(request).await,
+            ModelType::Layout => client.analyze_layout(request).await,
+            ModelType::Invoice => client.analyze_invoice(request).await,
+            ModelType::Receipt => client.analyze_receipt(request).await,
+            ModelType::IdDocument => client.analyze_id_document(request).await,
+            ModelType::BusinessCard => client.analyze_business_card(request).await,
+            ModelType::W2 => client.analyze_w2(request).await,
+            ModelType::Custom => {
+                return Err(ClientError::InvalidRequest(
+                    "Custom models need a model ID; use analyze_custom".to_string(),
+                ));
+            }
+        };
+        Ok(response?.into_inner())
+    }
+
+    /// Submit a document to a custom model
+    pub async fn analyze_custom(
+        &self,
+        source: DocumentSource,
+        model_id: &str,
+        options: AnalyzeOptions,
+    ) -> ClientResult<pb::AnalyzeResponse> {
+        let request = self.request(pb::AnalyzeCustomRequest {
+            source: Some(match source {
+                DocumentSource::Url(url) => pb::analyze_custom_request::Source::DocumentUrl(url),
+                DocumentSource::Bytes(bytes) => pb::analyze_custom_request::Source::DocumentBytes(bytes),
+            }),
+            model_id: model_id.to_string(),
+            options: Some(options_to_pb(options)),
+        });
+        Ok(self.inner.clone().analyze_custom(request).await?.into_inner())
+    }
+
+    /// Current state of an operation, with its result once it has succeeded
+    pub async fn get_result(&self, operation_id: &str) -> ClientResult<pb::AnalyzeResponse> {
+        let request = self.request(pb::GetAnalysisResultRequest {
+            operation_id: operation_id.to_string(),
+            field_mask: None,
+            min_confidence: 0.0,
+            normalize_coordinates: false,
+        });
+        Ok(self.inner.clone().get_analysis_result(request).await?.into_inner())
+    }
+
+    /// Poll an operation until it finishes, returning its result
+    pub async fn wait(&self, operation_id: &str) -> ClientResult<pb::AnalysisResult> {
+        let deadline = tokio::time::Instant::now() + self.poll.timeout;
+        let mut delay = self.poll.initial_delay;
+        loop {
+            let response = self.get_result(operation_id).await?;
+            if let Some(result) = finished(operation_id, response)? {
+                return Ok(result);
+            }
+            let now = tokio::time::Instant::now();
+            if now >= deadline {
+                return Err(ClientError::Timeout {
+                    operation_id: operation_id.to_string(),
+                    timeout: self.poll.timeout,
+                });
+            }
+            tokio::time::sleep(delay.min(deadline - now)).await;
+            delay = (delay * 2).min(self.poll.max_delay);
+        }
+    }
+
+    /// Submit a document to a prebuilt model and wait for its result
+    pub async fn analyze_and_wait(&self, source: DocumentSource, model: ModelType) -> ClientResult<pb::AnalysisResult> {
+        let submitted = self.analyze(source, model).await?;
+        let operation_id = submitted.operation_id.clone();
+        match finished(&operation_id, submitted)? {
+            Some(result) => Ok(result),
+            None => self.wait(&operation_id).await,
+        }
+    }
+
+    /// Wrap a message with the configured metadata
+    fn request<T>(&self, message: T) -> tonic::Request<T> {
+        let mut request = tonic::Request::new(message);
+        if let Some(api_key) = &self.api_key {
+            request.metadata_mut().insert(API_KEY_HEADER, api_key.clone());
+        }
+        if let Some(tenant) = &self.tenant {
+            request.metadata_mut().insert(TENANT_HEADER, tenant.clone());
+        }
+        request
+    }
+}
+
+/// The result of a finished operation, `None` while it is running, or its failure
+fn finished(operation_id: &str, response: pb::AnalyzeResponse) -> ClientResult<Option<pb::AnalysisResult>> {
+    match pb::AnalysisStatus::try_from(response.status) {
+        Ok(pb::AnalysisStatus::StatusSucceeded) => match response.result {
+            Some(result) => Ok(Some(result)),
+            None => Err(ClientError::AnalysisFailed {
+                operation_id: operation_id.to_string(),
+                message: "Operation succeeded without a result".to_string(),
+            }),
+        },
+        Ok(pb::AnalysisStatus::StatusFailed) => Err(ClientError::AnalysisFailed {
+            operation_id: operation_id.to_string(),
+            message: response
+                .error
+                .map(|error| error.message)
+                .filter(|message| !message.is_empty())
+                .unwrap_or_else(|| "Operation failed".to_string()),
+        }),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,340 @@
+/// Operations and extracted fields as Parquet tables
+///
+/// The analytics export writes two tables: one row per operation and one row
+/// per extracted field. Document fields holding arrays or objects are
+/// flattened into one row per leaf, named by its path such as
+/// `Items[0].Amount`. Columns are strings, numbers and UTC timestamps, so
+/// lakehouse engines read the files without a schema of their own.
+
+use chrono::{DateTime, Utc};
+use parquet::basic::Compression;
+use parquet::data_type::{ByteArray, ByteArrayType, DataType, DoubleType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::{SerializedColumnWriter, SerializedFileWriter};
+use parquet::schema::parser::parse_message_type;
+use std::sync::Arc;
+
+use super::errors::{DomainError, DomainResult};
+use super::models::{AnalysisOperation, AnalysisResult, DocumentField, FieldMatch};
+use super::normalization::{normalize_field, normalize_value, NormalizedValue};
+use super::value_objects::FieldQuery;
+
+/// One extracted value of a result, flattened
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldRow {
+    pub operation_id: String,
+    pub tenant_id: String,
+    pub model_id: String,
+    /// `key_value_pair` or `document_field`
+    pub source: &'static str,
+    pub doc_type: Option<String>,
+    /// Pair key, or field name with the path to a nested value
+    pub name: String,
+    pub value: String,
+    /// `string`, `number`, `integer`, `date`, `time` or `boolean`
+    pub value_type: &'static str,
+    /// The value of numbers and integers
+    pub number: Option<f64>,
+    pub confidence: f32,
+    /// Canonical form, as served under `normalized_fields`
+    pub normalized: Option<String>,
+    /// ISO 4217 code of a normalized amount
+    pub currency: Option<String>,
+}
+
+/// Rows for every key-value pair and document field of `result`
+pub fn field_rows(operation: &AnalysisOperation, result: &AnalysisResult) -> Vec<FieldRow> {
+    let mut rows = Vec::new();
+    for field in result.query_fields(&FieldQuery::default()) {
+        let row = |source, doc_type: Option<&String>, name: String, confidence| FieldRow {
+            operation_id: operation.operation_id.clone(),
+            tenant_id: operation.tenant_id.as_str().to_string(),
+            model_id: result.model_id.clone(),
+            source,
+            doc_type: doc_type.cloned(),
+            name,
+            value: String::new(),
+            value_type: "string",
+            number: None,
+            confidence,
+            normalized: None,
+            currency: None,
+        };
+        match &field {
+            FieldMatch::KeyValuePair { value, confidence, .. } => {
+                let name = field.name().to_string();
+                let (normalized, currency) = split(normalize_value(&name, value));
+                rows.push(FieldRow {
+                    value: value.clone(),
+                    normalized,
+                    currency,
+                    ..row("key_value_pair", None, name, *confidence)
+                });
+            }
+            FieldMatch::DocumentField { doc_type, name, value, confidence } => {
+                let mut leaves = Vec::new();
+                flatten(name.clone(), name, value, &mut leaves);
+                rows.extend(leaves.into_iter().map(|(path, leaf_name, leaf)| {
+                    let (normalized, currency) = split(normalize_field(leaf_name, leaf));
+                    let (value, value_type, number) = scalar(leaf);
+                    FieldRow {
+                        value,
+                        value_type,
+                        number,
+                        normalized,
+                        currency,
+                        ..row("document_field", Some(doc_type), path, *confidence)
+                    }
+                }));
+            }
+        }
+    }
+    rows
+}
+
+/// Leaves of `field` with their paths and the name of their innermost field
+fn flatten<'a>(path: String, name: &'a str, field: &'a DocumentField, leaves: &mut Vec<(String, &'a str, &'a DocumentField)>) {
+    match field {
+        DocumentField::Array(items) => {
+            for (index, item) in items.iter().enumerate() {
+                flatten(format!("{}[{}]", path, index), name, item, leaves);
+            }
+        }
+        DocumentField::Object(fields) => {
+            let mut names: Vec<&String> = fields.keys().collect();
+            names.sort();
+            for child in names {
+                flatten(format!("{}.{}", path, child), child, &fields[child], leaves);
+            }
+        }
+        _ => leaves.push((path, name, field)),
+    }
+}
+
+fn scalar(field: &DocumentField) -> (String, &'static str, Option<f64>) {
+    match field {
+        DocumentField::String(text) => (text.clone(), "string", None),
+        DocumentField::Number(number) => (number.to_string(), "number", Some(*number)),
+        DocumentField::Integer(number) => (number.to_string(), "integer", Some(f64::from(*number))),
+        DocumentField::Date(date) => (date.to_string(), "date", None),
+        DocumentField::Time(time) => (time.to_string(), "time", None),
+        DocumentField::Boolean(flag) => (flag.to_string(), "boolean", None),
+        DocumentField::Array(_) | DocumentField::Object(_) => (String::new(), "string", None),
+    }
+}
+
+fn split(normalized: Option<NormalizedValue>) -> (Option<String>, Option<String>) {
+    match normalized {
+        Some(NormalizedValue::Date { value }) => (Some(value.to_string()), None),
+        Some(NormalizedValue::PhoneNumber { value }) | Some(NormalizedValue::CountryCode { value }) => (Some(value), None),
+        Some(NormalizedValue::Amount { value, currency }) => (Some(value), currency),
+        None => (None, None),
+    }
+}
+
+/// The operations table
+pub fn operations_parquet(operations: &[AnalysisOperation]) -> DomainResult<Vec<u8>> {
+    let text = |value: fn(&AnalysisOperation) -> Option<String>| Values::Text(operations.iter().map(value).collect());
+    write_table(
+        "operations",
+        vec![
+            Column::required("operation_id", text(|op| Some(op.operation_id.clone()))),
+            Column::required("tenant_id", text(|op| Some(op.tenant_id.as_str().to_string()))),
+            Column::required("model_id", text(|op| Some(op.model_type.as_str().to_string()))),
+            Column::required("status", text(|op| Some(format!("{:?}", op.status).to_lowercase()))),
+            Column::required("created_at", Values::Timestamp(operations.iter().map(|op| Some(op.created_at)).collect())),
+            Column::required("last_updated", Values::Timestamp(operations.iter().map(|op| Some(op.last_updated)).collect())),
+            Column::optional(
+                "page_count",
+                Values::Integer(operations.iter().map(|op| op.page_count.map(i64::from)).collect()),
+            ),
+            Column::optional("filename", text(|op| op.filename.clone())),
+            Column::optional("content_type", text(|op| op.content_type.clone())),
+            Column::optional("content_sha256", text(|op| op.content_sha256.clone())),
+            Column::optional("document_id", text(|op| op.document_id.clone())),
+            Column::optional("error_code", text(|op| op.error.as_ref().map(|error| error.code.clone()))),
+            Column::optional("review_status", text(|op| op.review.as_ref().map(|review| review.status.as_str().to_string()))),
+        ],
+    )
+}
+
+/// The fields table
+pub fn fields_parquet(rows: &[FieldRow]) -> DomainResult<Vec<u8>> {
+    let text = |value: fn(&FieldRow) -> Option<String>| Values::Text(rows.iter().map(value).collect());
+    write_table(
+        "fields",
+        vec![
+            Column::required("operation_id", text(|row| Some(row.operation_id.clone()))),
+            Column::required("tenant_id", text(|row| Some(row.tenant_id.clone()))),
+            Column::required("model_id", text(|row| Some(row.model_id.clone()))),
+            Column::required("source", text(|row| Some(row.source.to_string()))),
+            Column::optional("doc_type", text(|row| row.doc_type.clone())),
+            Column::required("name", text(|row| Some(row.name.clone()))),
+            Column::required("value", text(|row| Some(row.value.clone()))),
+            Column::required("value_type", text(|row| Some(row.value_type.to_string()))),
+            Column::optional("number", Values::Double(rows.iter().map(|row| row.number).collect())),
+            Column::required("confidence", Values::Double(rows.iter().map(|row| Some(f64::from(row.confidence))).collect())),
+            Column::optional("normalized", text(|row| row.normalized.clone())),
+            Column::optional("currency", text(|row| row.currency.clone())),
+        ],
+    )
+}
+
+/// A column's values, one per row
+enum Values {
+    Text(Vec<Option<String>>),
+    Integer(Vec<Option<i64>>),
+    Double(Vec<Option<f64>>),
+    Timestamp(Vec<Option<DateTime<Utc>>>),
+}
+
+struct Column {
+    name: &'static str,
+    /// Required columns have a value in every row
+    required: bool,
+    values: Values,
+}
+
+impl Column {
+    fn required(name: &'static str, values: Values) -> Self {
+        Self { name, required: true, values }
+    }
+
+    fn optional(name: &'static str, values: Values) -> Self {
+        Self { name, required: false, values }
+    }
+
+    /// Declaration in a Parquet message type
+    fn schema(&self) -> String {
+        let repetition = if self.required { "REQUIRED" } else { "OPTIONAL" };
+        let kind = match self.values {
+            Values::Text(_) => "BYTE_ARRAY {} (UTF8)",
+            Values::Integer(_) => "INT64 {}",
+            Values::Double(_) => "DOUBLE {}",
+            Values::Timestamp(_) => "INT64 {} (TIMESTAMP(MICROS,true))",
+        };
+        format!("{} {};", repetition, kind.replace("{}", self.name))
+    }
+
+    fn write(self, writer: &mut SerializedColumnWriter<'_>) -> parquet::errors::Result<()> {
+        match self.values {
+            Values::Text(values) => write_values::<ByteArrayType, _>(writer, self.required, values, |text| ByteArray::from(text.into_bytes())),
+            Values::Integer(values) => write_values::<Int64Type, _>(writer, self.required, values, |value| value),
+            Values::Double(values) => write_values::<DoubleType, _>(writer, self.required, values, |value| value),
+            Values::Timestamp(values) => write_values::<Int64Type, _>(writer, self.required, values, |at| at.timestamp_micros()),
+        }
+    }
+}
+
+/// Write a column's values, with definition levels marking the missing ones of optional columns
+fn write_values<T: DataType, V>(
+    writer: &mut SerializedColumnWriter<'_>,
+    required: bool,
+    values: Vec<Option<V>>,
+    convert: impl Fn(V) -> T::T,
+) -> parquet::errors::Result<()> {
+    let levels: Vec<i16> = values.iter().map(|value| i16::from(value.is_some())).collect();
+    let present: Vec<T::T> = values.into_iter().flatten().map(convert).collect();
+    writer.typed::<T>().write_batch(&present, (!required).then_some(&levels[..]), None)?;
+    Ok(())
+}
+
+/// A Snappy-compressed Parquet file holding `columns` as one row group
+fn write_table(name: &str, columns: Vec<Column>) -> DomainResult<Vec<u8>> {
+    let failed = |e: parquet::errors::ParquetError| DomainError::ValidationError(format!("failed to write {} table: {}", name, e));
+    let declarations: Vec<String> = columns.iter().map(Column::schema).collect();
+    let schema = parse_message_type(&format!("message {} {{ {} }}", name, declarations.join(" "))).map_err(failed)?;
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+
+    let mut writer = SerializedFileWriter::new(Vec::new(), Arc::new(schema), Arc::new(properties)).map_err(failed)?;
+    let mut row_group = writer.next_row_group().map_err(failed)?;
+    for column in columns {
+        let Some(mut column_writer) = row_group.next_column().map_err(failed)? else {
+            break;
+        };
+        column.write(&mut column_writer).map_err(failed)?;
+        column_writer.close().map_err(failed)?;
+    }
+    row_group.close().map_err(failed)?;
+    writer.into_inner().map_err(failed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ExtractedDocument, KeyValuePair, ModelType, OperationStatus};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use parquet::record::Field;
+    use std::collections::HashMap;
+
+    fn operation() -> AnalysisOperation {
+        let mut operation = AnalysisOperation::new(ModelType::Invoice);
+        operation.status = OperationStatus::Succeeded;
+        operation.page_count = Some(2);
+        operation
+    }
+
+    fn result() -> AnalysisResult {
+        AnalysisResult {
+            model_id: "prebuilt-invoice".to_string(),
+            key_value_pairs: vec![KeyValuePair { key: "Total:".to_string(), value: "$12.50".to_string(), confidence: 0.8 }],
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                fields: HashMap::from([
+                    ("InvoiceDate".to_string(), DocumentField::String("03/01/2024".to_string())),
+                    (
+                        "Items".to_string(),
+                        DocumentField::Array(vec![DocumentField::Object(HashMap::from([
+                            ("Amount".to_string(), DocumentField::Number(10.0)),
+                            ("Description".to_string(), DocumentField::String("Widget".to_string())),
+                        ]))]),
+                    ),
+                ]),
+                confidence: 0.9,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn read(bytes: Vec<u8>) -> Vec<HashMap<String, Field>> {
+        let reader = SerializedFileReader::new(bytes::Bytes::from(bytes)).unwrap();
+        reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap().get_column_iter().map(|(name, field)| (name.clone(), field.clone())).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_field_rows_flatten_nested_fields() {
+        let rows = field_rows(&operation(), &result());
+        let names: Vec<&str> = rows.iter().map(|row| row.name.as_str()).collect();
+        assert_eq!(names, ["Total", "InvoiceDate", "Items[0].Amount", "Items[0].Description"]);
+        assert_eq!((rows[0].normalized.as_deref(), rows[0].currency.as_deref()), (Some("12.50"), Some("USD")));
+        assert_eq!(rows[1].normalized.as_deref(), Some("2024-03-01"));
+        assert_eq!((rows[2].value_type, rows[2].number, rows[2].normalized.as_deref()), ("number", Some(10.0), Some("10")));
+        assert_eq!(rows[3].doc_type.as_deref(), Some("invoice"));
+        assert_eq!(rows[3].confidence, 0.9);
+    }
+
+    #[test]
+    fn test_parquet_tables_round_trip() {
+        let operation = operation();
+        let operations = read(operations_parquet(&[operation.clone(), AnalysisOperation::new(ModelType::Read)]).unwrap());
+        assert_eq!(operations.len(), 2);
+        assert_eq!(operations[0]["operation_id"], Field::Str(operation.operation_id.clone()));
+        assert_eq!(operations[0]["status"], Field::Str("succeeded".to_string()));
+        assert_eq!(operations[0]["page_count"], Field::Long(2));
+        assert_eq!(operations[0]["created_at"], Field::TimestampMicros(operation.created_at.timestamp_micros()));
+        assert_eq!(operations[1]["page_count"], Field::Null);
+
+        let fields = read(fields_parquet(&field_rows(&operation, &result())).unwrap());
+        assert_eq!(fields.len(), 4);
+        assert_eq!(fields[2]["name"], Field::Str("Items[0].Amount".to_string()));
+        assert_eq!(fields[2]["number"], Field::Double(10.0));
+        assert_eq!(fields[0]["doc_type"], Field::Null);
+        assert_eq!(fields[0]["currency"], Field::Str("USD".to_string()));
+
+        assert!(!operations_parquet(&[]).unwrap().is_empty(), "empty tables are still valid files");
+    }
+}
@@ -0,0 +1,309 @@
+/// Comparison of two analysis results
+///
+/// Reports which key-value pairs and document fields were added, removed or
+/// changed between two operations, how their tables differ, and which lines
+/// of content appear in only one of them.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use super::models::{AnalysisResult, DocumentField, DocumentTable};
+
+/// Differences between a left and a right result
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultDiff {
+    pub fields: Vec<FieldDiff>,
+    pub tables: Vec<TableDiff>,
+    pub content: ContentDiff,
+}
+
+impl ResultDiff {
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty() && self.tables.is_empty() && self.content.identical
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// Value of a key-value pair or document field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum DiffValue {
+    Text(String),
+    Field(DocumentField),
+}
+
+/// A key-value pair (`key_value_pairs[Key]`) or document field
+/// (`documents[0].Name`) that differs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldDiff {
+    pub path: String,
+    pub kind: DiffKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left: Option<DiffValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right: Option<DiffValue>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left_confidence: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right_confidence: Option<f32>,
+}
+
+/// A table, by position, that differs
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableDiff {
+    pub index: usize,
+    pub kind: DiffKind,
+    /// `[rows, columns]` of each side, when present
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left_shape: Option<[i32; 2]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right_shape: Option<[i32; 2]>,
+    /// Cell positions whose content differs
+    pub changed_cells: usize,
+}
+
+/// Lines present on only one side, ignoring order
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ContentDiff {
+    pub identical: bool,
+    pub added_lines: Vec<String>,
+    pub removed_lines: Vec<String>,
+}
+
+/// Compare `left` with `right`
+pub fn diff_results(left: &AnalysisResult, right: &AnalysisResult) -> ResultDiff {
+    ResultDiff {
+        fields: diff_fields(left, right),
+        tables: diff_tables(&left.tables, &right.tables),
+        content: diff_content(&left.content, &right.content),
+    }
+}
+
+/// Fields by path, with their confidence
+fn field_map(result: &AnalysisResult) -> BTreeMap<String, (DiffValue, f32)> {
+    let mut fields = BTreeMap::new();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for pair in &result.key_value_pairs {
+        // Repeated keys are told apart by occurrence
+        let count = seen.entry(pair.key.as_str()).or_default();
+        *count += 1;
+        let path = if *count == 1 {
+            format!("key_value_pairs[{}]", pair.key)
+        } else {
+            format!("key_value_pairs[{}#{}]", pair.key, count)
+        };
+        fields.insert(path, (DiffValue::Text(pair.value.clone()), pair.confidence));
+    }
+    for (index, document) in result.documents.iter().enumerate() {
+        for (name, value) in &document.fields {
+            fields.insert(
+                format!("documents[{}].{}", index, name),
+                (DiffValue::Field(value.clone()), document.confidence),
+            );
+        }
+    }
+    fields
+}
+
+fn diff_fields(left: &AnalysisResult, right: &AnalysisResult) -> Vec<FieldDiff> {
+    let mut left = field_map(left);
+    let right = field_map(right);
+    let mut diffs = Vec::new();
+
+    for (path, (value, confidence)) in right {
+        let diff = match left.remove(&path) {
+            None => FieldDiff {
+                path,
+                kind: DiffKind::Added,
+                left: None,
+                right: Some(value),
+                left_confidence: None,
+                right_confidence: Some(confidence),
+            },
+            Some((old, _)) if old == value => continue,
+            Some((old, old_confidence)) => FieldDiff {
+                path,
+                kind: DiffKind::Changed,
+                left: Some(old),
+                right: Some(value),
+                left_confidence: Some(old_confidence),
+                right_confidence: Some(confidence),
+            },
+        };
+        diffs.push(diff);
+    }
+    diffs.extend(left.into_iter().map(|(path, (value, confidence))| FieldDiff {
+        path,
+        kind: DiffKind::Removed,
+        left: Some(value),
+        right: None,
+        left_confidence: Some(confidence),
+        right_confidence: None,
+    }));
+
+    diffs.sort_by(|a, b| a.path.cmp(&b.path));
+    diffs
+}
+
+fn diff_tables(left: &[DocumentTable], right: &[DocumentTable]) -> Vec<TableDiff> {
+    let shape = |table: &DocumentTable| [table.row_count, table.column_count];
+    (0..left.len().max(right.len()))
+        .filter_map(|index| match (left.get(index), right.get(index)) {
+            (Some(old), Some(new)) => {
+                let changed_cells = changed_cells(old, new);
+                (shape(old) != shape(new) || changed_cells > 0).then(|| TableDiff {
+                    index,
+                    kind: DiffKind::Changed,
+                    left_shape: Some(shape(old)),
+                    right_shape: Some(shape(new)),
+                    changed_cells,
+                })
+            }
+            (None, Some(new)) => Some(TableDiff {
+                index,
+                kind: DiffKind::Added,
+                left_shape: None,
+                right_shape: Some(shape(new)),
+                changed_cells: new.cells.len(),
+            }),
+            (Some(old), None) => Some(TableDiff {
+                index,
+                kind: DiffKind::Removed,
+                left_shape: Some(shape(old)),
+                right_shape: None,
+                changed_cells: old.cells.len(),
+            }),
+            (None, None) => None,
+        })
+        .collect()
+}
+
+/// Positions whose cell content differs, counting cells present on one side only
+fn changed_cells(left: &DocumentTable, right: &DocumentTable) -> usize {
+    let cells = |table: &DocumentTable| -> HashMap<(i32, i32), String> {
+        table
+            .cells
+            .iter()
+            .map(|cell| ((cell.row_index, cell.column_index), cell.content.trim().to_string()))
+            .collect()
+    };
+    let (left, right) = (cells(left), cells(right));
+    let mut changed = left
+        .iter()
+        .filter(|(position, content)| right.get(position) != Some(content))
+        .count();
+    changed += right.keys().filter(|position| !left.contains_key(position)).count();
+    changed
+}
+
+fn diff_content(left: &str, right: &str) -> ContentDiff {
+    if left == right {
+        return ContentDiff {
+            identical: true,
+            ..Default::default()
+        };
+    }
+
+    let mut counts: HashMap<&str, i64> = HashMap::new();
+    for line in left.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        *counts.entry(line).or_default() += 1;
+    }
+    let mut added_lines = Vec::new();
+    for line in right.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        match counts.get_mut(line) {
+            Some(count) if *count > 0 => *count -= 1,
+            _ => added_lines.push(line.to_string()),
+        }
+    }
+    // Whatever is left unmatched on the left was removed, in left order
+    let mut removed_lines = Vec::new();
+    for line in left.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        if let Some(count) = counts.get_mut(line).filter(|count| **count > 0) {
+            *count -= 1;
+            removed_lines.push(line.to_string());
+        }
+    }
+
+    ContentDiff {
+        identical: false,
+        added_lines,
+        removed_lines,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{CellKind, ExtractedDocument, KeyValuePair, TableCell};
+
+    fn result(content: &str, total: f64, vendor_kvp: &str, cells: &[&str]) -> AnalysisResult {
+        AnalysisResult {
+            content: content.to_string(),
+            key_value_pairs: vec![KeyValuePair {
+                key: "Vendor".to_string(),
+                value: vendor_kvp.to_string(),
+                confidence: 0.9,
+            }],
+            documents: vec![ExtractedDocument {
+                doc_type: "contract".to_string(),
+                fields: HashMap::from([("Total".to_string(), DocumentField::Number(total))]),
+                confidence: 0.8,
+            }],
+            tables: vec![DocumentTable {
+                row_count: 1,
+                column_count: cells.len() as i32,
+                cells: cells
+                    .iter()
+                    .enumerate()
+                    .map(|(column, content)| TableCell {
+                        kind: CellKind::Content,
+                        row_index: 0,
+                        column_index: column as i32,
+                        row_span: 1,
+                        column_span: 1,
+                        content: content.to_string(),
+                    })
+                    .collect(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_diff_results() {
+        let left = result("Term: 12 months\nTotal 100", 100.0, "Contoso", &["a", "b"]);
+        let mut right = result("Term: 24 months\nTotal 100", 150.0, "Contoso", &["a", "c"]);
+        right.key_value_pairs.push(KeyValuePair {
+            key: "Signed".to_string(),
+            value: "yes".to_string(),
+            confidence: 0.7,
+        });
+
+        let diff = diff_results(&left, &right);
+        assert_eq!(diff.fields.len(), 2);
+        assert_eq!(diff.fields[0].path, "documents[0].Total");
+        assert_eq!(diff.fields[0].kind, DiffKind::Changed);
+        assert_eq!(diff.fields[0].left, Some(DiffValue::Field(DocumentField::Number(100.0))));
+        assert_eq!(diff.fields[0].right_confidence, Some(0.8));
+        assert_eq!(diff.fields[1].path, "key_value_pairs[Signed]");
+        assert_eq!(diff.fields[1].kind, DiffKind::Added);
+
+        assert_eq!(diff.tables.len(), 1);
+        assert_eq!(diff.tables[0].changed_cells, 1);
+
+        assert!(!diff.content.identical);
+        assert_eq!(diff.content.added_lines, vec!["Term: 24 months"]);
+        assert_eq!(diff.content.removed_lines, vec!["Term: 12 months"]);
+
+        let removed = diff_results(&right, &left);
+        assert_eq!(removed.fields[1].kind, DiffKind::Removed);
+        assert!(diff_results(&left, &left).is_empty());
+    }
+}
@@ -0,0 +1,609 @@
+/// E-invoice XML export
+///
+/// Reads the invoice document of a prebuilt-invoice result into a typed
+/// `Invoice`, then writes it as UBL 2.1 or as the UN/CEFACT Cross Industry
+/// Invoice that ZUGFeRD 2 and Factur-X carry. Both follow the EN 16931 core
+/// model: one document currency, amounts with two decimals, and line amounts
+/// that add up to the invoice's net total.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use super::errors::{DomainError, DomainResult};
+use super::models::{AnalysisResult, DocumentField};
+use super::normalization::{normalize_field, normalize_value, NormalizedValue};
+use super::ocr_xml::escape;
+
+/// UN/ECE recommendation 20 code for "one", used when no unit was printed
+const UNIT_CODE: &str = "C62";
+
+/// EN 16931 specification identifier
+const EN16931: &str = "urn:cen.eu:en16931:2017";
+
+/// XML syntax an invoice is written in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EInvoiceFormat {
+    /// OASIS UBL 2.1 `Invoice`
+    #[default]
+    Ubl,
+    /// UN/CEFACT `CrossIndustryInvoice`, the XML of ZUGFeRD 2 and Factur-X
+    #[serde(alias = "factur-x", alias = "facturx", alias = "cii")]
+    Zugferd,
+}
+
+/// A seller or buyer
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct InvoiceParty {
+    pub name: String,
+    /// Address lines as printed, without the country
+    pub address: Vec<String>,
+    /// ISO 3166-1 alpha-2 code
+    pub country: Option<String>,
+    /// VAT or other tax registration number
+    pub tax_id: Option<String>,
+}
+
+/// One invoiced item; amounts are in the invoice's currency
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvoiceLine {
+    pub description: String,
+    pub quantity: f64,
+    pub unit_price: f64,
+    /// Net amount of the line
+    pub amount: f64,
+}
+
+/// An invoice as its extracted fields describe it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invoice {
+    pub id: String,
+    pub issue_date: NaiveDate,
+    pub due_date: Option<NaiveDate>,
+    /// ISO 4217 code
+    pub currency: String,
+    pub purchase_order: Option<String>,
+    pub seller: InvoiceParty,
+    pub buyer: InvoiceParty,
+    pub lines: Vec<InvoiceLine>,
+    /// Sum of the line amounts, before tax
+    pub net_total: f64,
+    pub tax_total: f64,
+    pub total: f64,
+    pub amount_due: f64,
+}
+
+impl Invoice {
+    /// Read the first invoice document of `result`
+    ///
+    /// Needs the invoice id and date, vendor and customer names, a total and a
+    /// currency printed with one of the amounts. Items that don't add up to
+    /// the subtotal are replaced by a single line for it, so the export stays
+    /// consistent with the totals the document states.
+    pub fn from_result(result: &AnalysisResult) -> DomainResult<Self> {
+        let document = result
+            .documents
+            .iter()
+            .find(|document| document.doc_type.starts_with("invoice"))
+            .ok_or_else(|| DomainError::ValidationError(format!("result of {} has no invoice document", result.model_id)))?;
+        let fields = &document.fields;
+
+        let mut missing: Vec<&str> = Vec::new();
+        let mut required = |name: &'static str, value: Option<String>| {
+            if value.is_none() {
+                missing.push(name);
+            }
+            value.unwrap_or_default()
+        };
+        let id = required("InvoiceId", text(fields, "InvoiceId"));
+        let seller_name = required("VendorName", text(fields, "VendorName"));
+        let buyer_name = required("CustomerName", text(fields, "CustomerName"));
+        let issue_date = date(fields, "InvoiceDate");
+        if issue_date.is_none() {
+            missing.push("InvoiceDate");
+        }
+
+        let mut currencies: Vec<String> = Vec::new();
+        let mut money = |name: &str| {
+            let (amount, currency) = amount(fields, name)?;
+            currencies.extend(currency);
+            Some(amount)
+        };
+        let stated_total = money("InvoiceTotal");
+        let subtotal = money("SubTotal");
+        let tax_total = money("TotalTax").unwrap_or_default();
+        let amount_due = money("AmountDue");
+        let Some(total) = stated_total.or(subtotal.map(|subtotal| subtotal + tax_total)).or(amount_due) else {
+            missing.push("InvoiceTotal");
+            return Err(missing_fields(&missing));
+        };
+        if !missing.is_empty() {
+            return Err(missing_fields(&missing));
+        }
+        currencies.sort();
+        currencies.dedup();
+        let currency = match currencies.as_slice() {
+            [currency] => currency.clone(),
+            [] => return Err(DomainError::ValidationError("no currency is printed with the invoice amounts".to_string())),
+            _ => return Err(DomainError::ValidationError(format!("invoice amounts mix currencies {}", currencies.join(", ")))),
+        };
+
+        let net_total = round(subtotal.unwrap_or(total - tax_total));
+        let mut lines = items(fields);
+        let lines_total: f64 = lines.iter().map(|line| line.amount).sum();
+        if lines.is_empty() || (round(lines_total) - net_total).abs() > 0.005 {
+            lines = vec![InvoiceLine {
+                description: format!("Invoice {}", id),
+                quantity: 1.0,
+                unit_price: net_total,
+                amount: net_total,
+            }];
+        }
+
+        Ok(Self {
+            id,
+            issue_date: issue_date.unwrap_or_default(),
+            due_date: date(fields, "DueDate"),
+            currency,
+            purchase_order: text(fields, "PurchaseOrder"),
+            seller: party(fields, seller_name, "VendorAddress", "VendorTaxId"),
+            buyer: party(fields, buyer_name, "CustomerAddress", "CustomerTaxId"),
+            lines,
+            net_total,
+            tax_total: round(tax_total),
+            total: round(total),
+            amount_due: round(amount_due.unwrap_or(total)),
+        })
+    }
+
+    /// Render in `format`
+    pub fn to_xml(&self, format: EInvoiceFormat) -> String {
+        match format {
+            EInvoiceFormat::Ubl => render_ubl(self),
+            EInvoiceFormat::Zugferd => render_cii(self),
+        }
+    }
+
+    /// Amount paid before this invoice, when less is due than its total
+    fn prepaid(&self) -> Option<f64> {
+        Some(round(self.total - self.amount_due)).filter(|prepaid| *prepaid > 0.0)
+    }
+
+    /// VAT category and rate, derived from the stated totals: standard rated
+    /// when tax is charged, zero rated otherwise
+    fn tax_category(&self) -> (&'static str, f64) {
+        if self.tax_total > 0.0 && self.net_total > 0.0 {
+            ("S", round(self.tax_total / self.net_total * 100.0))
+        } else {
+            ("Z", 0.0)
+        }
+    }
+}
+
+/// Render an invoice as a UBL 2.1 `Invoice`
+pub fn render_ubl(invoice: &Invoice) -> String {
+    let currency = escape(&invoice.currency);
+    let money = |amount: f64| format!("currencyID=\"{}\">{:.2}", currency, amount);
+    let (category, percent) = invoice.tax_category();
+
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <Invoice xmlns=\"urn:oasis:names:specification:ubl:schema:xsd:Invoice-2\" \
+         xmlns:cac=\"urn:oasis:names:specification:ubl:schema:xsd:CommonAggregateComponents-2\" \
+         xmlns:cbc=\"urn:oasis:names:specification:ubl:schema:xsd:CommonBasicComponents-2\">\n  \
+         <cbc:UBLVersionID>2.1</cbc:UBLVersionID>\n",
+    );
+    let _ = writeln!(out, "  <cbc:CustomizationID>{}</cbc:CustomizationID>", EN16931);
+    let _ = writeln!(out, "  <cbc:ID>{}</cbc:ID>", escape(&invoice.id));
+    let _ = writeln!(out, "  <cbc:IssueDate>{}</cbc:IssueDate>", invoice.issue_date);
+    if let Some(due_date) = invoice.due_date {
+        let _ = writeln!(out, "  <cbc:DueDate>{}</cbc:DueDate>", due_date);
+    }
+    out.push_str("  <cbc:InvoiceTypeCode>380</cbc:InvoiceTypeCode>\n");
+    let _ = writeln!(out, "  <cbc:DocumentCurrencyCode>{}</cbc:DocumentCurrencyCode>", currency);
+    if let Some(order) = &invoice.purchase_order {
+        let _ = writeln!(out, "  <cac:OrderReference><cbc:ID>{}</cbc:ID></cac:OrderReference>", escape(order));
+    }
+    for (role, party) in [("AccountingSupplierParty", &invoice.seller), ("AccountingCustomerParty", &invoice.buyer)] {
+        let name = escape(&party.name);
+        let _ = writeln!(out, "  <cac:{}>\n    <cac:Party>", role);
+        let _ = writeln!(out, "      <cac:PartyName><cbc:Name>{}</cbc:Name></cac:PartyName>", name);
+        out.push_str("      <cac:PostalAddress>\n");
+        for line in &party.address {
+            let _ = writeln!(out, "        <cac:AddressLine><cbc:Line>{}</cbc:Line></cac:AddressLine>", escape(line));
+        }
+        if let Some(country) = &party.country {
+            let _ = writeln!(out, "        <cac:Country><cbc:IdentificationCode>{}</cbc:IdentificationCode></cac:Country>", country);
+        }
+        out.push_str("      </cac:PostalAddress>\n");
+        if let Some(tax_id) = &party.tax_id {
+            let _ = writeln!(
+                out,
+                "      <cac:PartyTaxScheme><cbc:CompanyID>{}</cbc:CompanyID><cac:TaxScheme><cbc:ID>VAT</cbc:ID></cac:TaxScheme></cac:PartyTaxScheme>",
+                escape(tax_id)
+            );
+        }
+        let _ = writeln!(
+            out,
+            "      <cac:PartyLegalEntity><cbc:RegistrationName>{}</cbc:RegistrationName></cac:PartyLegalEntity>",
+            name
+        );
+        let _ = writeln!(out, "    </cac:Party>\n  </cac:{}>", role);
+    }
+
+    let tax_category = format!(
+        "<cbc:ID>{}</cbc:ID><cbc:Percent>{:.2}</cbc:Percent><cac:TaxScheme><cbc:ID>VAT</cbc:ID></cac:TaxScheme>",
+        category, percent
+    );
+    let _ = writeln!(out, "  <cac:TaxTotal>\n    <cbc:TaxAmount {}</cbc:TaxAmount>", money(invoice.tax_total));
+    let _ = writeln!(
+        out,
+        "    <cac:TaxSubtotal><cbc:TaxableAmount {}</cbc:TaxableAmount><cbc:TaxAmount {}</cbc:TaxAmount><cac:TaxCategory>{}</cac:TaxCategory></cac:TaxSubtotal>",
+        money(invoice.net_total),
+        money(invoice.tax_total),
+        tax_category
+    );
+    out.push_str("  </cac:TaxTotal>\n  <cac:LegalMonetaryTotal>\n");
+    let _ = writeln!(out, "    <cbc:LineExtensionAmount {}</cbc:LineExtensionAmount>", money(invoice.net_total));
+    let _ = writeln!(out, "    <cbc:TaxExclusiveAmount {}</cbc:TaxExclusiveAmount>", money(invoice.net_total));
+    let _ = writeln!(out, "    <cbc:TaxInclusiveAmount {}</cbc:TaxInclusiveAmount>", money(invoice.total));
+    if let Some(prepaid) = invoice.prepaid() {
+        let _ = writeln!(out, "    <cbc:PrepaidAmount {}</cbc:PrepaidAmount>", money(prepaid));
+    }
+    let _ = writeln!(out, "    <cbc:PayableAmount {}</cbc:PayableAmount>", money(invoice.amount_due));
+    out.push_str("  </cac:LegalMonetaryTotal>\n");
+
+    for (index, line) in invoice.lines.iter().enumerate() {
+        let _ = writeln!(out, "  <cac:InvoiceLine>\n    <cbc:ID>{}</cbc:ID>", index + 1);
+        let _ = writeln!(out, "    <cbc:InvoicedQuantity unitCode=\"{}\">{}</cbc:InvoicedQuantity>", UNIT_CODE, line.quantity);
+        let _ = writeln!(out, "    <cbc:LineExtensionAmount {}</cbc:LineExtensionAmount>", money(line.amount));
+        let _ = writeln!(
+            out,
+            "    <cac:Item><cbc:Name>{}</cbc:Name><cac:ClassifiedTaxCategory>{}</cac:ClassifiedTaxCategory></cac:Item>",
+            escape(&line.description),
+            tax_category
+        );
+        let _ = writeln!(out, "    <cac:Price><cbc:PriceAmount {}</cbc:PriceAmount></cac:Price>", money(line.unit_price));
+        out.push_str("  </cac:InvoiceLine>\n");
+    }
+    out.push_str("</Invoice>\n");
+    out
+}
+
+/// Render an invoice as a UN/CEFACT `CrossIndustryInvoice` in the EN 16931
+/// profile of ZUGFeRD 2 and Factur-X
+pub fn render_cii(invoice: &Invoice) -> String {
+    let date = |date: NaiveDate| format!("<udt:DateTimeString format=\"102\">{}</udt:DateTimeString>", date.format("%Y%m%d"));
+    let (category, percent) = invoice.tax_category();
+
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <rsm:CrossIndustryInvoice xmlns:rsm=\"urn:un:unece:uncefact:data:standard:CrossIndustryInvoice:100\" \
+         xmlns:ram=\"urn:un:unece:uncefact:data:standard:ReusableAggregateBusinessInformationEntity:100\" \
+         xmlns:udt=\"urn:un:unece:uncefact:data:standard:UnqualifiedDataType:100\">\n",
+    );
+    let _ = writeln!(
+        out,
+        "  <rsm:ExchangedDocumentContext>\n    \
+         <ram:GuidelineSpecifiedDocumentContextParameter><ram:ID>{}</ram:ID></ram:GuidelineSpecifiedDocumentContextParameter>\n  \
+         </rsm:ExchangedDocumentContext>",
+        EN16931
+    );
+    let _ = writeln!(
+        out,
+        "  <rsm:ExchangedDocument>\n    <ram:ID>{}</ram:ID>\n    <ram:TypeCode>380</ram:TypeCode>\n    \
+         <ram:IssueDateTime>{}</ram:IssueDateTime>\n  </rsm:ExchangedDocument>",
+        escape(&invoice.id),
+        date(invoice.issue_date)
+    );
+    out.push_str("  <rsm:SupplyChainTradeTransaction>\n");
+
+    let tax = format!(
+        "<ram:TypeCode>VAT</ram:TypeCode><ram:CategoryCode>{}</ram:CategoryCode><ram:RateApplicablePercent>{:.2}</ram:RateApplicablePercent>",
+        category, percent
+    );
+    for (index, line) in invoice.lines.iter().enumerate() {
+        out.push_str("    <ram:IncludedSupplyChainTradeLineItem>\n");
+        let _ = writeln!(
+            out,
+            "      <ram:AssociatedDocumentLineDocument><ram:LineID>{}</ram:LineID></ram:AssociatedDocumentLineDocument>",
+            index + 1
+        );
+        let _ = writeln!(out, "      <ram:SpecifiedTradeProduct><ram:Name>{}</ram:Name></ram:SpecifiedTradeProduct>", escape(&line.description));
+        let _ = writeln!(
+            out,
+            "      <ram:SpecifiedLineTradeAgreement><ram:NetPriceProductTradePrice><ram:ChargeAmount>{:.2}</ram:ChargeAmount></ram:NetPriceProductTradePrice></ram:SpecifiedLineTradeAgreement>",
+            line.unit_price
+        );
+        let _ = writeln!(
+            out,
+            "      <ram:SpecifiedLineTradeDelivery><ram:BilledQuantity unitCode=\"{}\">{}</ram:BilledQuantity></ram:SpecifiedLineTradeDelivery>",
+            UNIT_CODE, line.quantity
+        );
+        let _ = writeln!(
+            out,
+            "      <ram:SpecifiedLineTradeSettlement>\n        <ram:ApplicableTradeTax>{}</ram:ApplicableTradeTax>\n        \
+             <ram:SpecifiedTradeSettlementLineMonetarySummation><ram:LineTotalAmount>{:.2}</ram:LineTotalAmount></ram:SpecifiedTradeSettlementLineMonetarySummation>\n      \
+             </ram:SpecifiedLineTradeSettlement>",
+            tax, line.amount
+        );
+        out.push_str("    </ram:IncludedSupplyChainTradeLineItem>\n");
+    }
+
+    out.push_str("    <ram:ApplicableHeaderTradeAgreement>\n");
+    for (role, party) in [("SellerTradeParty", &invoice.seller), ("BuyerTradeParty", &invoice.buyer)] {
+        let _ = writeln!(out, "      <ram:{}>\n        <ram:Name>{}</ram:Name>", role, escape(&party.name));
+        out.push_str("        <ram:PostalTradeAddress>");
+        // CII has three address lines; any further lines join the third
+        let (first, rest) = party.address.split_at(party.address.len().min(2));
+        for (element, line) in ["LineOne", "LineTwo"].iter().zip(first) {
+            let _ = write!(out, "<ram:{0}>{1}</ram:{0}>", element, escape(line));
+        }
+        if !rest.is_empty() {
+            let _ = write!(out, "<ram:LineThree>{}</ram:LineThree>", escape(&rest.join(", ")));
+        }
+        if let Some(country) = &party.country {
+            let _ = write!(out, "<ram:CountryID>{}</ram:CountryID>", country);
+        }
+        out.push_str("</ram:PostalTradeAddress>\n");
+        if let Some(tax_id) = &party.tax_id {
+            let _ = writeln!(
+                out,
+                "        <ram:SpecifiedTaxRegistration><ram:ID schemeID=\"VA\">{}</ram:ID></ram:SpecifiedTaxRegistration>",
+                escape(tax_id)
+            );
+        }
+        let _ = writeln!(out, "      </ram:{}>", role);
+    }
+    if let Some(order) = &invoice.purchase_order {
+        let _ = writeln!(
+            out,
+            "      <ram:BuyerOrderReferencedDocument><ram:IssuerAssignedID>{}</ram:IssuerAssignedID></ram:BuyerOrderReferencedDocument>",
+            escape(order)
+        );
+    }
+    out.push_str("    </ram:ApplicableHeaderTradeAgreement>\n    <ram:ApplicableHeaderTradeDelivery/>\n");
+
+    let currency = escape(&invoice.currency);
+    out.push_str("    <ram:ApplicableHeaderTradeSettlement>\n");
+    let _ = writeln!(out, "      <ram:InvoiceCurrencyCode>{}</ram:InvoiceCurrencyCode>", currency);
+    let _ = writeln!(
+        out,
+        "      <ram:ApplicableTradeTax><ram:CalculatedAmount>{:.2}</ram:CalculatedAmount><ram:TypeCode>VAT</ram:TypeCode>\
+         <ram:BasisAmount>{:.2}</ram:BasisAmount><ram:CategoryCode>{}</ram:CategoryCode><ram:RateApplicablePercent>{:.2}</ram:RateApplicablePercent></ram:ApplicableTradeTax>",
+        invoice.tax_total, invoice.net_total, category, percent
+    );
+    if let Some(due_date) = invoice.due_date {
+        let _ = writeln!(
+            out,
+            "      <ram:SpecifiedTradePaymentTerms><ram:DueDateDateTime>{}</ram:DueDateDateTime></ram:SpecifiedTradePaymentTerms>",
+            date(due_date)
+        );
+    }
+    out.push_str("      <ram:SpecifiedTradeSettlementHeaderMonetarySummation>\n");
+    let _ = writeln!(out, "        <ram:LineTotalAmount>{:.2}</ram:LineTotalAmount>", invoice.net_total);
+    let _ = writeln!(out, "        <ram:TaxBasisTotalAmount>{:.2}</ram:TaxBasisTotalAmount>", invoice.net_total);
+    let _ = writeln!(out, "        <ram:TaxTotalAmount currencyID=\"{}\">{:.2}</ram:TaxTotalAmount>", currency, invoice.tax_total);
+    let _ = writeln!(out, "        <ram:GrandTotalAmount>{:.2}</ram:GrandTotalAmount>", invoice.total);
+    if let Some(prepaid) = invoice.prepaid() {
+        let _ = writeln!(out, "        <ram:TotalPrepaidAmount>{:.2}</ram:TotalPrepaidAmount>", prepaid);
+    }
+    let _ = writeln!(out, "        <ram:DuePayableAmount>{:.2}</ram:DuePayableAmount>", invoice.amount_due);
+    out.push_str(
+        "      </ram:SpecifiedTradeSettlementHeaderMonetarySummation>\n    </ram:ApplicableHeaderTradeSettlement>\n  \
+         </rsm:SupplyChainTradeTransaction>\n</rsm:CrossIndustryInvoice>\n",
+    );
+    out
+}
+
+fn missing_fields(missing: &[&str]) -> DomainError {
+    DomainError::ValidationError(format!("invoice is missing {}", missing.join(", ")))
+}
+
+/// Round to cents, as EN 16931 amounts have two decimals
+fn round(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+fn text(fields: &HashMap<String, DocumentField>, name: &str) -> Option<String> {
+    let text = match fields.get(name)? {
+        DocumentField::String(text) => text.trim().to_string(),
+        DocumentField::Number(number) => number.to_string(),
+        DocumentField::Integer(number) => number.to_string(),
+        DocumentField::Date(date) => date.to_string(),
+        _ => return None,
+    };
+    Some(text).filter(|text| !text.is_empty())
+}
+
+fn date(fields: &HashMap<String, DocumentField>, name: &str) -> Option<NaiveDate> {
+    match normalize_field(name, fields.get(name)?)? {
+        NormalizedValue::Date { value } => Some(value),
+        _ => None,
+    }
+}
+
+/// An amount and the currency printed with it
+fn amount(fields: &HashMap<String, DocumentField>, name: &str) -> Option<(f64, Option<String>)> {
+    match normalize_field(name, fields.get(name)?)? {
+        NormalizedValue::Amount { value, currency } => Some((value.parse().ok()?, currency)),
+        _ => None,
+    }
+}
+
+/// A number field, read as an amount when it was printed as text
+fn number(fields: &HashMap<String, DocumentField>, name: &str) -> Option<f64> {
+    match fields.get(name)? {
+        DocumentField::Number(number) => Some(*number),
+        DocumentField::Integer(number) => Some(f64::from(*number)),
+        DocumentField::String(text) => match normalize_value("Amount", text)? {
+            NormalizedValue::Amount { value, .. } => value.parse().ok(),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// Line items with a description and enough amounts to price them
+fn items(fields: &HashMap<String, DocumentField>) -> Vec<InvoiceLine> {
+    let Some(DocumentField::Array(items)) = fields.get("Items") else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| {
+            let DocumentField::Object(item) = item else {
+                return None;
+            };
+            let description = text(item, "Description").or_else(|| text(item, "ProductCode"))?;
+            let quantity = number(item, "Quantity").filter(|quantity| *quantity > 0.0).unwrap_or(1.0);
+            let (unit_price, amount) = match (number(item, "UnitPrice"), number(item, "Amount")) {
+                (Some(price), Some(amount)) => (price, amount),
+                (Some(price), None) => (price, price * quantity),
+                (None, Some(amount)) => (amount / quantity, amount),
+                (None, None) => return None,
+            };
+            Some(InvoiceLine { description, quantity, unit_price: round(unit_price), amount: round(amount) })
+        })
+        .collect()
+}
+
+/// A party from its name and the address and tax id fields, reading a last
+/// address line naming a country as the country
+fn party(fields: &HashMap<String, DocumentField>, name: String, address: &str, tax_id: &str) -> InvoiceParty {
+    let mut lines: Vec<String> = text(fields, address)
+        .map(|address| {
+            address
+                .split(['\n', ','])
+                .map(str::trim)
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let country = lines.last().and_then(|line| match normalize_value("Country", line)? {
+        NormalizedValue::CountryCode { value } => Some(value),
+        _ => None,
+    });
+    if country.is_some() {
+        lines.pop();
+    }
+    InvoiceParty { name, address: lines, country, tax_id: text(fields, tax_id) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ExtractedDocument;
+
+    fn string(text: &str) -> DocumentField {
+        DocumentField::String(text.to_string())
+    }
+
+    fn item(description: &str, quantity: &str, unit_price: &str, amount: &str) -> DocumentField {
+        DocumentField::Object(HashMap::from([
+            ("Description".to_string(), string(description)),
+            ("Quantity".to_string(), string(quantity)),
+            ("UnitPrice".to_string(), string(unit_price)),
+            ("Amount".to_string(), string(amount)),
+        ]))
+    }
+
+    fn result(fields: &[(&str, DocumentField)]) -> AnalysisResult {
+        AnalysisResult {
+            model_id: "prebuilt-invoice".to_string(),
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                fields: fields.iter().map(|(name, field)| (name.to_string(), field.clone())).collect(),
+                confidence: 0.9,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn invoice_fields() -> Vec<(&'static str, DocumentField)> {
+        vec![
+            ("InvoiceId", string("INV-7")),
+            ("InvoiceDate", string("March 1, 2024")),
+            ("DueDate", string("2024-03-31")),
+            ("VendorName", string("Müller & Söhne GmbH")),
+            ("VendorAddress", string("Hauptstr. 1\n10115 Berlin\nGermany")),
+            ("VendorTaxId", string("DE123456789")),
+            ("CustomerName", string("Contoso Ltd.")),
+            ("CustomerAddress", string("1 Main St, Redmond WA")),
+            ("PurchaseOrder", string("PO-9")),
+            ("SubTotal", string("€100.00")),
+            ("TotalTax", string("€19.00")),
+            ("InvoiceTotal", string("€119.00")),
+            ("AmountDue", string("€100.00")),
+            (
+                "Items",
+                DocumentField::Array(vec![item("Widget", "2", "30.00", "60.00"), item("Gadget", "4", "10", "40")]),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_invoice_from_result() {
+        let invoice = Invoice::from_result(&result(&invoice_fields())).unwrap();
+        assert_eq!(invoice.id, "INV-7");
+        assert_eq!(invoice.issue_date, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(invoice.currency, "EUR");
+        assert_eq!(invoice.seller.address, ["Hauptstr. 1", "10115 Berlin"]);
+        assert_eq!(invoice.seller.country.as_deref(), Some("DE"));
+        assert_eq!(invoice.buyer.country, None);
+        assert_eq!(invoice.lines.len(), 2);
+        assert_eq!((invoice.net_total, invoice.tax_total, invoice.total, invoice.amount_due), (100.0, 19.0, 119.0, 100.0));
+        assert_eq!(invoice.prepaid(), Some(19.0));
+        assert_eq!(invoice.tax_category(), ("S", 19.0));
+
+        // Items that don't add up to the subtotal give way to one line for it
+        let mut fields = invoice_fields();
+        fields.retain(|(name, _)| *name != "Items");
+        fields.push(("Items", DocumentField::Array(vec![item("Widget", "2", "30.00", "60.00")])));
+        let invoice = Invoice::from_result(&result(&fields)).unwrap();
+        assert_eq!(invoice.lines, [InvoiceLine { description: "Invoice INV-7".to_string(), quantity: 1.0, unit_price: 100.0, amount: 100.0 }]);
+    }
+
+    #[test]
+    fn test_invoice_needs_core_fields() {
+        let error = Invoice::from_result(&result(&[("InvoiceTotal", string("$5.00"))])).unwrap_err();
+        assert_eq!(error.to_string(), "Document validation failed: invoice is missing InvoiceId, VendorName, CustomerName, InvoiceDate");
+
+        let mut fields = invoice_fields();
+        fields.retain(|(name, _)| !["SubTotal", "TotalTax", "InvoiceTotal", "AmountDue"].contains(name));
+        fields.push(("InvoiceTotal", string("119.00")));
+        assert!(Invoice::from_result(&result(&fields)).unwrap_err().to_string().contains("no currency"));
+
+        let read = AnalysisResult { model_id: "prebuilt-read".to_string(), ..Default::default() };
+        assert!(Invoice::from_result(&read).is_err());
+    }
+
+    #[test]
+    fn test_render_ubl_and_cii() {
+        let invoice = Invoice::from_result(&result(&invoice_fields())).unwrap();
+
+        let ubl = invoice.to_xml(EInvoiceFormat::Ubl);
+        assert!(ubl.contains("<cbc:ID>INV-7</cbc:ID>"));
+        assert!(ubl.contains("<cbc:IssueDate>2024-03-01</cbc:IssueDate>"));
+        assert!(ubl.contains("<cbc:Name>Müller &amp; Söhne GmbH</cbc:Name>"));
+        assert!(ubl.contains("<cbc:IdentificationCode>DE</cbc:IdentificationCode>"));
+        assert!(ubl.contains("<cbc:PrepaidAmount currencyID=\"EUR\">19.00</cbc:PrepaidAmount>"));
+        assert!(ubl.contains("<cbc:InvoicedQuantity unitCode=\"C62\">4</cbc:InvoicedQuantity>"));
+        assert!(ubl.contains("<cbc:Percent>19.00</cbc:Percent>"));
+        assert_eq!(ubl.matches("<cac:InvoiceLine>").count(), 2);
+
+        let cii = invoice.to_xml(EInvoiceFormat::Zugferd);
+        assert!(cii.contains("<ram:IssueDateTime><udt:DateTimeString format=\"102\">20240301</udt:DateTimeString></ram:IssueDateTime>"));
+        assert!(cii.contains("<ram:LineOne>Hauptstr. 1</ram:LineOne><ram:LineTwo>10115 Berlin</ram:LineTwo><ram:CountryID>DE</ram:CountryID>"));
+        assert!(cii.contains("<ram:ID schemeID=\"VA\">DE123456789</ram:ID>"));
+        assert!(cii.contains("<ram:GrandTotalAmount>119.00</ram:GrandTotalAmount>"));
+        assert!(cii.contains("<ram:DuePayableAmount>100.00</ram:DuePayableAmount>"));
+        assert_eq!(cii.matches("<ram:IncludedSupplyChainTradeLineItem>").count(), 2);
+
+        let format: EInvoiceFormat = serde_json::from_str("\"factur-x\"").unwrap();
+        assert_eq!(format, EInvoiceFormat::Zugferd);
+    }
+}
@@ -0,0 +1,299 @@
+/// Content chunks and their embeddings
+///
+/// A succeeded operation's content is split into overlapping chunks small
+/// enough for an embeddings model; each chunk is stored with its vector so
+/// analyzed documents can be retrieved by meaning rather than by keyword.
+
+use serde::{Deserialize, Serialize};
+
+use super::errors::{DomainError, DomainResult};
+use super::models::AnalysisResult;
+
+/// Longest snippet of a matching chunk returned by a search
+const SNIPPET_CHARS: usize = 300;
+
+/// How content is split into chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChunkingPolicy {
+    /// Longest chunk, in characters; a single longer word is split
+    pub max_chars: usize,
+    /// Characters of whole words repeated from the end of the previous chunk
+    pub overlap_chars: usize,
+}
+
+impl Default for ChunkingPolicy {
+    fn default() -> Self {
+        Self { max_chars: 2000, overlap_chars: 200 }
+    }
+}
+
+impl ChunkingPolicy {
+    pub fn new(max_chars: usize, overlap_chars: usize) -> DomainResult<Self> {
+        if max_chars == 0 {
+            return Err(DomainError::ValidationError("chunk size must be positive".to_string()));
+        }
+        if overlap_chars >= max_chars {
+            return Err(DomainError::ValidationError(format!(
+                "chunk overlap {} must be smaller than the chunk size {}",
+                overlap_chars, max_chars
+            )));
+        }
+        Ok(Self { max_chars, overlap_chars })
+    }
+
+    /// A result's content as chunks, each within one page when the result has lines
+    ///
+    /// Without lines, as from models that return no layout, the whole content
+    /// is chunked and the chunks carry no page.
+    pub fn chunk_result(&self, result: &AnalysisResult) -> Vec<ContentChunk> {
+        if result.pages.iter().all(|page| page.lines.is_empty()) {
+            return self
+                .chunk(&result.content)
+                .into_iter()
+                .map(|text| ContentChunk { page_number: None, text })
+                .collect();
+        }
+        result
+            .pages
+            .iter()
+            .flat_map(|page| {
+                let text: Vec<&str> = page.lines.iter().map(|line| line.content.as_str()).collect();
+                self.chunk(&text.join("\n")).into_iter().map(move |text| ContentChunk {
+                    page_number: u32::try_from(page.page_number).ok(),
+                    text,
+                })
+            })
+            .collect()
+    }
+
+    /// `content` as chunks of whitespace-separated words, in order
+    pub fn chunk(&self, content: &str) -> Vec<String> {
+        let mut words: Vec<&str> = Vec::new();
+        for word in content.split_whitespace() {
+            let mut rest = word;
+            while rest.chars().count() > self.max_chars {
+                let split = rest.char_indices().nth(self.max_chars).map_or(rest.len(), |(at, _)| at);
+                words.push(&rest[..split]);
+                rest = &rest[split..];
+            }
+            words.push(rest);
+        }
+
+        let mut chunks = Vec::new();
+        let mut current: Vec<&str> = Vec::new();
+        let mut length = 0;
+        for word in words {
+            let word_length = word.chars().count();
+            let added = if current.is_empty() { word_length } else { word_length + 1 };
+            if length + added > self.max_chars && !current.is_empty() {
+                chunks.push(current.join(" "));
+                // Carry whole trailing words into the next chunk, as long as the word still fits
+                let mut carried = 0;
+                let mut keep = current.len();
+                while keep > 0 {
+                    let next = current[keep - 1].chars().count() + usize::from(carried > 0);
+                    if carried + next > self.overlap_chars || carried + next + word_length + 1 > self.max_chars {
+                        break;
+                    }
+                    carried += next;
+                    keep -= 1;
+                }
+                current.drain(..keep);
+                length = carried;
+            }
+            length += if current.is_empty() { word_length } else { word_length + 1 };
+            current.push(word);
+        }
+        if !current.is_empty() {
+            chunks.push(current.join(" "));
+        }
+        chunks
+    }
+}
+
+/// A chunk of content and the page it is on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContentChunk {
+    pub page_number: Option<u32>,
+    pub text: String,
+}
+
+/// One chunk of an operation's content with its embedding
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EmbeddedChunk {
+    /// Position of the chunk in the content, from 0
+    pub index: u32,
+    pub page_number: Option<u32>,
+    pub text: String,
+    pub embedding: Vec<f32>,
+}
+
+/// A semantic search over a tenant's embedded chunks
+#[derive(Debug, Clone, PartialEq)]
+pub struct SemanticQuery {
+    pub query: String,
+    /// Most chunks returned
+    pub top_k: u32,
+}
+
+impl SemanticQuery {
+    pub const DEFAULT_TOP_K: u32 = 10;
+    pub const MAX_TOP_K: u32 = 50;
+
+    pub fn new(query: &str, top_k: Option<u32>) -> DomainResult<Self> {
+        let query = query.trim();
+        if query.is_empty() {
+            return Err(DomainError::ValidationError("query must not be empty".to_string()));
+        }
+        let top_k = top_k.unwrap_or(Self::DEFAULT_TOP_K);
+        if top_k == 0 || top_k > Self::MAX_TOP_K {
+            return Err(DomainError::ValidationError(format!(
+                "top_k must be between 1 and {}",
+                Self::MAX_TOP_K
+            )));
+        }
+        Ok(Self { query: query.to_string(), top_k })
+    }
+}
+
+/// A stored chunk close to a semantic query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChunkMatch {
+    pub operation_id: String,
+    pub chunk_index: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub page_number: Option<u32>,
+    /// Start of the chunk's text, cut at a word
+    pub snippet: String,
+    /// Cosine similarity to the query, higher is closer
+    pub score: f32,
+}
+
+impl ChunkMatch {
+    pub fn new(operation_id: &str, chunk_index: u32, page_number: Option<u32>, text: &str, score: f32) -> Self {
+        Self {
+            operation_id: operation_id.to_string(),
+            chunk_index,
+            page_number,
+            snippet: snippet(text),
+            score,
+        }
+    }
+}
+
+/// `text` cut to [`SNIPPET_CHARS`] at a word boundary, with an ellipsis when cut
+fn snippet(text: &str) -> String {
+    if text.chars().count() <= SNIPPET_CHARS {
+        return text.to_string();
+    }
+    let end = text.char_indices().nth(SNIPPET_CHARS).map_or(text.len(), |(at, _)| at);
+    let cut = &text[..end];
+    let cut = cut.rfind(char::is_whitespace).map_or(cut, |at| &cut[..at]);
+    format!("{}…", cut.trim_end())
+}
+
+/// Cosine similarity of two vectors, 0 when either is zero or their lengths differ
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = |v: &[f32]| v.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_with_overlap() {
+        let policy = ChunkingPolicy::new(11, 4).unwrap();
+        assert_eq!(
+            policy.chunk("one two three four five six"),
+            ["one two", "two three", "four five", "five six"]
+        );
+        assert_eq!(policy.chunk("  short \n"), ["short"]);
+        assert!(policy.chunk(" \n\t").is_empty());
+    }
+
+    #[test]
+    fn test_chunk_long_words() {
+        let policy = ChunkingPolicy::new(4, 0).unwrap();
+        assert_eq!(policy.chunk("abcdefghij ab"), ["abcd", "efgh", "ij", "ab"]);
+        assert_eq!(policy.chunk("ééééé"), ["éééé", "é"]);
+    }
+
+    #[test]
+    fn test_chunk_result_by_page() {
+        use crate::domain::{DocumentLine, DocumentPage};
+        let page = |page_number, lines: &[&str]| DocumentPage {
+            page_number,
+            angle: 0.0,
+            width: 8.5,
+            height: 11.0,
+            unit: "inch".to_string(),
+            words: Vec::new(),
+            lines: lines
+                .iter()
+                .map(|content| DocumentLine { content: content.to_string(), polygon: Vec::new(), spans: Vec::new() })
+                .collect(),
+            selection_marks: Vec::new(),
+        };
+        let mut result = AnalysisResult {
+            content: "alpha beta gamma".to_string(),
+            ..Default::default()
+        };
+        let policy = ChunkingPolicy::new(11, 0).unwrap();
+        assert_eq!(
+            policy.chunk_result(&result),
+            [
+                ContentChunk { page_number: None, text: "alpha beta".to_string() },
+                ContentChunk { page_number: None, text: "gamma".to_string() },
+            ]
+        );
+
+        result.pages = vec![page(1, &["alpha", "beta"]), page(2, &[]), page(3, &["gamma"])];
+        assert_eq!(
+            policy.chunk_result(&result),
+            [
+                ContentChunk { page_number: Some(1), text: "alpha beta".to_string() },
+                ContentChunk { page_number: Some(3), text: "gamma".to_string() },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_semantic_query() {
+        assert_eq!(SemanticQuery::new(" late fees ", None).unwrap().query, "late fees");
+        assert_eq!(SemanticQuery::new("fees", None).unwrap().top_k, SemanticQuery::DEFAULT_TOP_K);
+        assert!(SemanticQuery::new(" ", None).is_err());
+        assert!(SemanticQuery::new("fees", Some(0)).is_err());
+        assert!(SemanticQuery::new("fees", Some(51)).is_err());
+    }
+
+    #[test]
+    fn test_snippet_and_similarity() {
+        assert_eq!(snippet("short text"), "short text");
+        let long = "word ".repeat(100);
+        let cut = snippet(&long);
+        assert!(cut.ends_with("word…"));
+        assert!(cut.chars().count() <= SNIPPET_CHARS + 1);
+
+        assert!((cosine_similarity(&[1.0, 0.0], &[2.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 1.0]), 0.0);
+    }
+
+    #[test]
+    fn test_invalid_policy() {
+        assert!(ChunkingPolicy::new(0, 0).is_err());
+        assert!(ChunkingPolicy::new(100, 100).is_err());
+    }
+}
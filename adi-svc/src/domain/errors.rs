@@ -18,9 +18,18 @@ pub enum DomainError {
     #[error("Invalid locale: {0}")]
     InvalidLocale(String),
     
+    #[error("Invalid tenant id: {0}")]
+    InvalidTenant(String),
+    
     #[error("Invalid page range: {0}")]
     InvalidPageRange(String),
     
+    #[error("Document is encrypted or password protected")]
+    EncryptedDocument,
+    
+    #[error("Document has too many pages: {pages} (max: {max})")]
+    TooManyPages { pages: u32, max: u32 },
+    
     #[error("Document validation failed: {0}")]
     ValidationError(String),
 }
@@ -0,0 +1,215 @@
+/// Bulk result exports
+///
+/// An export job writes every succeeded result of a tenant that matches its
+/// filter to storage as NDJSON, one operation per line, newest first, and
+/// records its progress until the file can be downloaded.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use super::errors::{DomainError, DomainResult};
+use super::models::{AnalysisOperation, AnalysisResult};
+use super::value_objects::{ModelType, TenantId};
+
+/// Progress of an export job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportStatus {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+impl ExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "running" => Some(Self::Running),
+            "succeeded" => Some(Self::Succeeded),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Which of a tenant's results an export covers
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportFilter {
+    /// Only operations created at or after this instant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub from: Option<DateTime<Utc>>,
+    /// Only operations created before this instant
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub to: Option<DateTime<Utc>>,
+    /// Only results of this model: a prebuilt model, named as in the API routes, or a custom model ID
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+impl ExportFilter {
+    /// Check the range and spell prebuilt models as Azure does
+    pub fn normalize(mut self) -> DomainResult<Self> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from >= to {
+                return Err(DomainError::ValidationError(format!("export range ends at {}, before it starts at {}", to, from)));
+            }
+        }
+        self.model = match self.model.as_deref().map(str::trim) {
+            None => None,
+            Some("") => return Err(DomainError::ValidationError("model must not be empty".to_string())),
+            Some(model) => Some(match ModelType::from_string(model) {
+                Ok(model_type) if model_type != ModelType::Custom => model_type.as_str().to_string(),
+                _ => model.to_string(),
+            }),
+        };
+        Ok(self)
+    }
+
+    /// Whether an operation may match, before its result is read
+    pub fn matches_operation(&self, operation: &AnalysisOperation) -> bool {
+        let model_type = match self.model.as_deref().map(ModelType::from_string) {
+            Some(Ok(model_type)) if model_type != ModelType::Custom => model_type,
+            Some(_) => ModelType::Custom,
+            None => operation.model_type,
+        };
+        self.from.is_none_or(|from| operation.created_at >= from)
+            && self.to.is_none_or(|to| operation.created_at < to)
+            && operation.model_type == model_type
+    }
+
+    pub fn matches_result(&self, result: &AnalysisResult) -> bool {
+        self.model.as_ref().is_none_or(|model| &result.model_id == model)
+    }
+}
+
+/// A bulk export of results to an NDJSON file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportJob {
+    pub export_id: String,
+    pub tenant_id: TenantId,
+    pub status: ExportStatus,
+    pub filter: ExportFilter,
+    /// Results written to the file
+    pub results: u64,
+    /// Stored file, once written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<String>,
+    /// Why the export failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ExportJob {
+    pub fn new(tenant_id: TenantId, filter: ExportFilter) -> Self {
+        let now = Utc::now();
+        Self {
+            export_id: Uuid::new_v4().to_string(),
+            tenant_id,
+            status: ExportStatus::Running,
+            filter,
+            results: 0,
+            document_id: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Name the file is stored and downloaded under
+    pub fn filename(&self) -> String {
+        format!("export-{}.ndjson", self.export_id)
+    }
+
+    /// Record results written so far, while still running
+    pub fn progress(&mut self, results: u64) {
+        self.results = results;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn succeed(&mut self, document_id: String, results: u64) {
+        self.status = ExportStatus::Succeeded;
+        self.document_id = Some(document_id);
+        self.results = results;
+        self.updated_at = Utc::now();
+    }
+
+    pub fn fail(&mut self, reason: String) {
+        self.status = ExportStatus::Failed;
+        self.error = Some(reason);
+        self.updated_at = Utc::now();
+    }
+}
+
+/// One line of an export file
+#[derive(Debug, Serialize)]
+struct ExportLine<'a> {
+    operation_id: &'a str,
+    created_at: DateTime<Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<&'a str>,
+    result: &'a AnalysisResult,
+}
+
+/// An operation's result as a line of an export file, newline included
+pub fn export_line(operation: &AnalysisOperation, result: &AnalysisResult) -> DomainResult<Vec<u8>> {
+    let line = ExportLine {
+        operation_id: &operation.operation_id,
+        created_at: operation.created_at,
+        filename: operation.filename.as_deref(),
+        result,
+    };
+    let mut bytes = serde_json::to_vec(&line)
+        .map_err(|e| DomainError::ValidationError(format!("result of {} cannot be exported: {}", operation.operation_id, e)))?;
+    bytes.push(b'\n');
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_filter_matches() {
+        let mut invoice = AnalysisOperation::new(ModelType::Invoice);
+        invoice.operation_id = "op-1".to_string();
+        let custom = AnalysisOperation::new(ModelType::Custom);
+        let now = invoice.created_at;
+
+        let filter = ExportFilter { model: Some("invoice".to_string()), ..Default::default() }.normalize().unwrap();
+        assert_eq!(filter.model.as_deref(), Some("prebuilt-invoice"));
+        assert!(filter.matches_operation(&invoice));
+        assert!(!filter.matches_operation(&custom));
+
+        let filter = ExportFilter { model: Some("my-model".to_string()), ..Default::default() }.normalize().unwrap();
+        assert!(!filter.matches_operation(&invoice));
+        assert!(filter.matches_operation(&custom));
+        assert!(filter.matches_result(&AnalysisResult { model_id: "my-model".to_string(), ..Default::default() }));
+        assert!(!filter.matches_result(&AnalysisResult { model_id: "other".to_string(), ..Default::default() }));
+
+        let filter = ExportFilter { from: Some(now), to: Some(now + Duration::seconds(1)), model: None };
+        assert!(filter.matches_operation(&invoice) && filter.matches_operation(&custom));
+        let filter = ExportFilter { to: Some(now), ..Default::default() };
+        assert!(!filter.matches_operation(&invoice));
+
+        let backwards = ExportFilter { from: Some(now), to: Some(now), model: None };
+        assert!(backwards.normalize().is_err());
+        assert!(ExportFilter { model: Some(" ".to_string()), ..Default::default() }.normalize().is_err());
+
+        let line = export_line(&invoice, &AnalysisResult::default()).unwrap();
+        assert_eq!(line.last(), Some(&b'\n'));
+        let value: serde_json::Value = serde_json::from_slice(&line).unwrap();
+        assert_eq!(value["operation_id"], "op-1");
+        assert!(value["result"].is_object());
+    }
+}
@@ -0,0 +1,417 @@
+/// Geometry over the polygons Azure returns for words, lines and marks
+///
+/// Polygons are the corner points of a region in page units, clockwise from
+/// the top-left as Azure reports them. Azure's regions are convex
+/// quadrilaterals, which the intersection-based measures rely on.
+
+use serde::{Deserialize, Serialize};
+
+use super::errors::{DomainError, DomainResult};
+use super::models::{AnalysisResult, DocumentPage, Point};
+
+impl Point {
+    pub fn new(x: f32, y: f32) -> Self {
+        Self { x, y }
+    }
+
+    pub fn distance_to(&self, other: Point) -> f32 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// Axis-aligned rectangle in the units of its page
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BoundingBox {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl BoundingBox {
+    pub fn width(&self) -> f32 {
+        self.right - self.left
+    }
+
+    pub fn height(&self) -> f32 {
+        self.bottom - self.top
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width() * self.height()
+    }
+
+    pub fn center(&self) -> Point {
+        Point::new((self.left + self.right) / 2.0, (self.top + self.bottom) / 2.0)
+    }
+
+    /// Whether `point` is inside or on the edge
+    pub fn contains(&self, point: Point) -> bool {
+        (self.left..=self.right).contains(&point.x) && (self.top..=self.bottom).contains(&point.y)
+    }
+
+    /// The overlap of the two boxes, `None` when they do not overlap
+    pub fn intersection(&self, other: &BoundingBox) -> Option<BoundingBox> {
+        let overlap = BoundingBox {
+            left: self.left.max(other.left),
+            top: self.top.max(other.top),
+            right: self.right.min(other.right),
+            bottom: self.bottom.min(other.bottom),
+        };
+        (overlap.left < overlap.right && overlap.top < overlap.bottom).then_some(overlap)
+    }
+
+    /// Intersection over union, from 0 for disjoint boxes to 1 for equal ones
+    pub fn iou(&self, other: &BoundingBox) -> f32 {
+        let overlap = self.intersection(other).map_or(0.0, |overlap| overlap.area());
+        let union = self.area() + other.area() - overlap;
+        if union > 0.0 {
+            overlap / union
+        } else {
+            0.0
+        }
+    }
+
+    /// The corners, clockwise from the top-left
+    pub fn to_polygon(&self) -> Vec<Point> {
+        vec![
+            Point::new(self.left, self.top),
+            Point::new(self.right, self.top),
+            Point::new(self.right, self.bottom),
+            Point::new(self.left, self.bottom),
+        ]
+    }
+}
+
+/// Measures of a region given as its corner points
+///
+/// Implemented for `[Point]`, so `word.polygon.bounding_box()` works on the
+/// polygons of the result types.
+pub trait Polygon {
+    /// Smallest axis-aligned box holding every point, `None` without points
+    fn bounding_box(&self) -> Option<BoundingBox>;
+
+    /// Center of mass of the enclosed area, or of the points when they
+    /// enclose none
+    fn centroid(&self) -> Option<Point>;
+
+    /// Enclosed area, whichever direction the points wind
+    fn area(&self) -> f32;
+
+    /// Whether `point` is inside the region or on its edge
+    fn contains_point(&self, point: Point) -> bool;
+
+    /// Intersection over union of two convex regions
+    fn iou(&self, other: &[Point]) -> f32;
+}
+
+impl Polygon for [Point] {
+    fn bounding_box(&self) -> Option<BoundingBox> {
+        let first = self.first()?;
+        Some(self[1..].iter().fold(
+            BoundingBox { left: first.x, top: first.y, right: first.x, bottom: first.y },
+            |bounds, point| BoundingBox {
+                left: bounds.left.min(point.x),
+                top: bounds.top.min(point.y),
+                right: bounds.right.max(point.x),
+                bottom: bounds.bottom.max(point.y),
+            },
+        ))
+    }
+
+    fn centroid(&self) -> Option<Point> {
+        if self.is_empty() {
+            return None;
+        }
+        let signed = signed_area(self);
+        if signed.abs() <= f32::EPSILON {
+            let count = self.len() as f32;
+            let (x, y) = self.iter().fold((0.0, 0.0), |(x, y), point| (x + point.x, y + point.y));
+            return Some(Point::new(x / count, y / count));
+        }
+        let (x, y) = edges(self).fold((0.0, 0.0), |(x, y), (a, b)| {
+            let cross = a.x * b.y - b.x * a.y;
+            (x + (a.x + b.x) * cross, y + (a.y + b.y) * cross)
+        });
+        Some(Point::new(x / (6.0 * signed), y / (6.0 * signed)))
+    }
+
+    fn area(&self) -> f32 {
+        signed_area(self).abs()
+    }
+
+    fn contains_point(&self, point: Point) -> bool {
+        if self.len() < 3 {
+            return false;
+        }
+        let mut inside = false;
+        for (a, b) in edges(self) {
+            if on_segment(a, b, point) {
+                return true;
+            }
+            if (a.y > point.y) != (b.y > point.y) && point.x < (b.x - a.x) * (point.y - a.y) / (b.y - a.y) + a.x {
+                inside = !inside;
+            }
+        }
+        inside
+    }
+
+    fn iou(&self, other: &[Point]) -> f32 {
+        let overlap = clip(self, other).area();
+        let union = self.area() + other.area() - overlap;
+        if union > 0.0 {
+            overlap / union
+        } else {
+            0.0
+        }
+    }
+}
+
+/// Unit a page's dimensions and polygons are measured in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CoordinateUnit {
+    /// PDFs and office documents
+    Inch,
+    /// Images
+    Pixel,
+    /// Fractions of the page from its top-left corner
+    Normalized,
+}
+
+impl CoordinateUnit {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Inch => "inch",
+            Self::Pixel => "pixel",
+            Self::Normalized => "normalized",
+        }
+    }
+
+    pub fn parse(value: &str) -> DomainResult<Self> {
+        match value {
+            "inch" => Ok(Self::Inch),
+            "pixel" => Ok(Self::Pixel),
+            "normalized" => Ok(Self::Normalized),
+            other => Err(DomainError::ValidationError(format!(
+                "unknown coordinate unit '{}', expected inch, pixel or normalized",
+                other
+            ))),
+        }
+    }
+}
+
+impl DocumentPage {
+    pub fn coordinate_unit(&self) -> DomainResult<CoordinateUnit> {
+        CoordinateUnit::parse(&self.unit)
+    }
+
+    /// Multiply the page size and every polygon by `x` and `y`
+    pub fn scale_coordinates(&mut self, x: f32, y: f32) {
+        self.width *= x;
+        self.height *= y;
+        let polygons = self
+            .words
+            .iter_mut()
+            .map(|word| &mut word.polygon)
+            .chain(self.lines.iter_mut().map(|line| &mut line.polygon))
+            .chain(self.selection_marks.iter_mut().map(|mark| &mut mark.polygon));
+        for point in polygons.flatten() {
+            point.x *= x;
+            point.y *= y;
+        }
+    }
+
+    /// Measure the page in `unit`, at `dpi` between inches and pixels
+    ///
+    /// Normalized pages cannot be converted back, their size being gone.
+    pub fn convert_coordinates(&mut self, unit: CoordinateUnit, dpi: f32) -> DomainResult<()> {
+        let from = self.coordinate_unit()?;
+        match (from, unit) {
+            (from, to) if from == to => {}
+            (_, CoordinateUnit::Normalized) => {
+                if self.width <= 0.0 || self.height <= 0.0 {
+                    return Err(DomainError::ValidationError(format!(
+                        "page {} has no size to normalize by",
+                        self.page_number
+                    )));
+                }
+                self.scale_coordinates(1.0 / self.width, 1.0 / self.height);
+                // Exactly 1 x 1, whatever the rounding of the reciprocals
+                (self.width, self.height) = (1.0, 1.0);
+            }
+            (CoordinateUnit::Normalized, to) => {
+                return Err(DomainError::ValidationError(format!(
+                    "page {} is normalized and cannot be converted to {}",
+                    self.page_number,
+                    to.as_str()
+                )));
+            }
+            _ if dpi <= 0.0 => {
+                return Err(DomainError::ValidationError(format!("DPI must be positive, got {}", dpi)));
+            }
+            (_, CoordinateUnit::Pixel) => self.scale_coordinates(dpi, dpi),
+            (_, CoordinateUnit::Inch) => self.scale_coordinates(1.0 / dpi, 1.0 / dpi),
+        }
+        self.unit = unit.as_str().to_string();
+        Ok(())
+    }
+
+    /// Measure the page in fractions of its size, as a 1 x 1 `normalized` page;
+    /// pages without a size are left as they are
+    pub fn normalize_coordinates(&mut self) {
+        let _ = self.convert_coordinates(CoordinateUnit::Normalized, 0.0);
+    }
+}
+
+impl AnalysisResult {
+    /// Measure every page in fractions of its size, see [`DocumentPage::normalize_coordinates`]
+    pub fn normalize_coordinates(&mut self) {
+        self.pages.iter_mut().for_each(DocumentPage::normalize_coordinates);
+    }
+}
+
+/// Consecutive corner pairs, closing back to the first
+fn edges(polygon: &[Point]) -> impl Iterator<Item = (Point, Point)> + '_ {
+    polygon.iter().zip(polygon.iter().cycle().skip(1)).map(|(a, b)| (*a, *b))
+}
+
+/// Shoelace area, positive when the points wind clockwise in page space
+fn signed_area(polygon: &[Point]) -> f32 {
+    edges(polygon).map(|(a, b)| a.x * b.y - b.x * a.y).sum::<f32>() / 2.0
+}
+
+fn cross(origin: Point, a: Point, b: Point) -> f32 {
+    (a.x - origin.x) * (b.y - origin.y) - (a.y - origin.y) * (b.x - origin.x)
+}
+
+fn on_segment(a: Point, b: Point, point: Point) -> bool {
+    cross(a, b, point).abs() <= f32::EPSILON * a.distance_to(b).max(1.0)
+        && point.x >= a.x.min(b.x)
+        && point.x <= a.x.max(b.x)
+        && point.y >= a.y.min(b.y)
+        && point.y <= a.y.max(b.y)
+}
+
+/// The part of `subject` inside the convex `clipper` (Sutherland-Hodgman)
+fn clip(subject: &[Point], clipper: &[Point]) -> Vec<Point> {
+    if subject.len() < 3 || clipper.len() < 3 {
+        return Vec::new();
+    }
+    // Keep points on the interior side of each clipper edge, whichever way it winds
+    let winding = signed_area(clipper).signum();
+    let mut output = subject.to_vec();
+    for (a, b) in edges(clipper) {
+        let input = std::mem::take(&mut output);
+        let inside = |point: Point| cross(a, b, point) * winding >= 0.0;
+        for (current, next) in edges(&input) {
+            match (inside(current), inside(next)) {
+                (true, true) => output.push(next),
+                (true, false) => output.push(crossing(current, next, a, b)),
+                (false, true) => {
+                    output.push(crossing(current, next, a, b));
+                    output.push(next);
+                }
+                (false, false) => {}
+            }
+        }
+        if output.is_empty() {
+            break;
+        }
+    }
+    output
+}
+
+/// Where segment `p`-`q` crosses the line through `a` and `b`
+fn crossing(p: Point, q: Point, a: Point, b: Point) -> Point {
+    let (from, to) = (cross(a, b, p), cross(a, b, q));
+    let t = from / (from - to);
+    Point::new(p.x + (q.x - p.x) * t, p.y + (q.y - p.y) * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(left: f32, top: f32, right: f32, bottom: f32) -> Vec<Point> {
+        BoundingBox { left, top, right, bottom }.to_polygon()
+    }
+
+    #[test]
+    fn test_bounding_box_and_area() {
+        let diamond = [Point::new(1.0, 0.0), Point::new(2.0, 1.0), Point::new(1.0, 2.0), Point::new(0.0, 1.0)];
+        assert_eq!(
+            diamond.bounding_box(),
+            Some(BoundingBox { left: 0.0, top: 0.0, right: 2.0, bottom: 2.0 })
+        );
+        assert_eq!(diamond.area(), 2.0);
+        let mut reversed = diamond;
+        reversed.reverse();
+        assert_eq!(reversed.area(), 2.0);
+        assert_eq!(diamond.centroid(), Some(Point::new(1.0, 1.0)));
+        assert!([].bounding_box().is_none() && [].centroid().is_none());
+        assert_eq!([Point::new(0.0, 0.0), Point::new(2.0, 4.0)].centroid(), Some(Point::new(1.0, 2.0)));
+    }
+
+    #[test]
+    fn test_contains() {
+        let diamond = [Point::new(1.0, 0.0), Point::new(2.0, 1.0), Point::new(1.0, 2.0), Point::new(0.0, 1.0)];
+        assert!(diamond.contains_point(Point::new(1.0, 1.0)));
+        assert!(diamond.contains_point(Point::new(1.5, 0.5)));
+        assert!(!diamond.contains_point(Point::new(0.2, 0.2)));
+        let bounds = diamond.bounding_box().unwrap();
+        assert!(bounds.contains(Point::new(0.2, 0.2)) && !bounds.contains(Point::new(2.1, 1.0)));
+    }
+
+    #[test]
+    fn test_iou() {
+        let a = rect(0.0, 0.0, 2.0, 2.0);
+        let b = rect(1.0, 0.0, 3.0, 2.0);
+        assert!((a.iou(&b) - 1.0 / 3.0).abs() < 1e-6);
+        assert!((a.iou(&a) - 1.0).abs() < 1e-6);
+        assert_eq!(a.iou(&rect(5.0, 5.0, 6.0, 6.0)), 0.0);
+        let boxes = (a.bounding_box().unwrap(), b.bounding_box().unwrap());
+        assert!((boxes.0.iou(&boxes.1) - 1.0 / 3.0).abs() < 1e-6);
+        assert!(boxes.0.intersection(&rect(2.0, 0.0, 3.0, 1.0).bounding_box().unwrap()).is_none());
+
+        // A square turned 45 degrees inside a 2 x 2 square covers half of it
+        let diamond = [Point::new(1.0, 0.0), Point::new(2.0, 1.0), Point::new(1.0, 2.0), Point::new(0.0, 1.0)];
+        assert!((diamond.iou(&a) - 0.5).abs() < 1e-6);
+        assert!((a.iou(&diamond) - 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_convert_coordinates() {
+        use crate::domain::DocumentWord;
+        use crate::domain::Span;
+        let mut page = DocumentPage {
+            page_number: 1,
+            angle: 0.0,
+            width: 8.5,
+            height: 11.0,
+            unit: "inch".to_string(),
+            words: vec![DocumentWord {
+                content: "Total".to_string(),
+                polygon: rect(1.7, 2.2, 3.4, 4.4),
+                confidence: 0.9,
+                span: Span { offset: 0, length: 5 },
+            }],
+            lines: Vec::new(),
+            selection_marks: Vec::new(),
+        };
+
+        page.convert_coordinates(CoordinateUnit::Pixel, 100.0).unwrap();
+        assert_eq!((page.width, page.height, page.unit.as_str()), (850.0, 1100.0, "pixel"));
+        assert_eq!(page.words[0].polygon[0], Point::new(170.0, 220.0));
+        assert!(page.convert_coordinates(CoordinateUnit::Inch, 0.0).is_err());
+
+        page.normalize_coordinates();
+        assert_eq!((page.width, page.height, page.unit.as_str()), (1.0, 1.0, "normalized"));
+        let bounds = page.words[0].polygon.bounding_box().unwrap();
+        assert!((bounds.left - 0.2).abs() < 1e-6 && (bounds.bottom - 0.4).abs() < 1e-6);
+        assert!(page.convert_coordinates(CoordinateUnit::Inch, 72.0).is_err());
+
+        page.unit = "furlong".to_string();
+        assert!(page.convert_coordinates(CoordinateUnit::Pixel, 72.0).is_err());
+    }
+}
@@ -0,0 +1,288 @@
+/// Labeling datasets for custom model training
+///
+/// Turns a reviewed result into the files Azure Document Intelligence Studio
+/// trains custom models from: `<document>.ocr.json` holding the layout and
+/// `<document>.labels.json` pointing each field at the words that hold its
+/// value. Boxes are 8-number polygons in fractions of the page, as the
+/// labeling format expects.
+
+use serde::Serialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+
+use super::models::{AnalysisResult, DocumentField, DocumentPage, FieldMatch};
+use super::value_objects::FieldQuery;
+
+pub const LABELS_SCHEMA: &str = "https://schema.cognitiveservices.azure.com/formrecognizer/2021-03-01/labels.json";
+pub const FIELDS_SCHEMA: &str = "https://schema.cognitiveservices.azure.com/formrecognizer/2021-03-01/fields.json";
+
+/// Where a field's value appears on one page
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LabelValue {
+    pub page: i32,
+    pub text: String,
+    /// One polygon per word, as `x1, y1, ..., x4, y4` page fractions
+    pub bounding_boxes: Vec<Vec<f32>>,
+}
+
+/// Label of one field
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct FieldLabel {
+    pub label: String,
+    pub value: Vec<LabelValue>,
+}
+
+/// Contents of a `labels.json` file
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DocumentLabels {
+    #[serde(rename = "$schema")]
+    pub schema: String,
+    /// Name of the labeled document next to this file
+    pub document: String,
+    pub labels: Vec<FieldLabel>,
+}
+
+/// Label the fields of a reviewed result
+///
+/// `corrected` is the latest revision and `original` the extraction as
+/// analyzed. A corrected value found nowhere on the pages is labeled where
+/// the original value was, since a reviewer usually fixes the reading of
+/// words rather than pointing at others. Returns the labels and the names of
+/// fields that could not be located, which are left out.
+pub fn label_document(
+    document: &str,
+    corrected: &AnalysisResult,
+    original: &AnalysisResult,
+) -> (DocumentLabels, Vec<String>) {
+    let original_values = label_values(original);
+    let mut labels = Vec::new();
+    let mut unlocated = Vec::new();
+    for (name, text) in label_values(corrected) {
+        let located = locate(&corrected.pages, &text).or_else(|| {
+            let before = original_values.get(&name)?;
+            let mut found = locate(&corrected.pages, before)?;
+            found.text = text.clone();
+            Some(found)
+        });
+        match located {
+            Some(value) => labels.push(FieldLabel { label: name, value: vec![value] }),
+            None => unlocated.push(name),
+        }
+    }
+    let labels = DocumentLabels { schema: LABELS_SCHEMA.to_string(), document: document.to_string(), labels };
+    (labels, unlocated)
+}
+
+/// Contents of the dataset's `fields.json`, declaring every label as a string field
+pub fn fields_json<'a>(names: impl IntoIterator<Item = &'a str>) -> serde_json::Value {
+    let fields: Vec<serde_json::Value> = names
+        .into_iter()
+        .map(|name| json!({ "fieldKey": name, "fieldType": "string", "fieldFormat": "not-specified" }))
+        .collect();
+    json!({ "$schema": FIELDS_SCHEMA, "fields": fields, "definitions": {} })
+}
+
+/// Contents of an `ocr.json` file, for results whose raw response was not kept
+///
+/// Mirrors the shape of a succeeded analyze response, carrying only the page
+/// layout labels refer to.
+pub fn ocr_json(result: &AnalysisResult) -> serde_json::Value {
+    let polygon = |points: &[super::models::Point]| -> Vec<f32> {
+        points.iter().flat_map(|point| [point.x, point.y]).collect()
+    };
+    let pages: Vec<serde_json::Value> = result
+        .pages
+        .iter()
+        .map(|page| {
+            json!({
+                "pageNumber": page.page_number,
+                "angle": page.angle,
+                "width": page.width,
+                "height": page.height,
+                "unit": page.unit,
+                "words": page.words.iter().map(|word| json!({
+                    "content": word.content,
+                    "polygon": polygon(&word.polygon),
+                    "confidence": word.confidence,
+                    "span": { "offset": word.span.offset, "length": word.span.length },
+                })).collect::<Vec<_>>(),
+                "lines": page.lines.iter().map(|line| json!({
+                    "content": line.content,
+                    "polygon": polygon(&line.polygon),
+                    "spans": line.spans.iter().map(|span| json!({
+                        "offset": span.offset,
+                        "length": span.length,
+                    })).collect::<Vec<_>>(),
+                })).collect::<Vec<_>>(),
+            })
+        })
+        .collect();
+    json!({
+        "status": "succeeded",
+        "analyzeResult": {
+            "apiVersion": result.api_version,
+            "modelId": result.model_id,
+            "content": result.content,
+            "pages": pages,
+        },
+    })
+}
+
+/// Text of each labelable field by name; document fields win over pairs of the same name
+fn label_values(result: &AnalysisResult) -> BTreeMap<String, String> {
+    let mut values = BTreeMap::new();
+    let mut fields = result.query_fields(&FieldQuery::default());
+    fields.sort_by_key(|field| matches!(field, FieldMatch::KeyValuePair { .. }));
+    for field in &fields {
+        let text = match field {
+            FieldMatch::KeyValuePair { value, .. } => Some(value.clone()),
+            FieldMatch::DocumentField { value, .. } => match value {
+                DocumentField::String(text) => Some(text.clone()),
+                DocumentField::Integer(number) => Some(number.to_string()),
+                DocumentField::Number(number) => Some(number.to_string()),
+                _ => None,
+            },
+        };
+        if let Some(text) = text.filter(|text| !text.trim().is_empty()) {
+            values.entry(field.name().to_string()).or_insert(text);
+        }
+    }
+    values
+}
+
+/// First run of words spelling out `text`, ignoring case and spacing
+fn locate(pages: &[DocumentPage], text: &str) -> Option<LabelValue> {
+    let wanted = squash(text);
+    if wanted.is_empty() {
+        return None;
+    }
+    for page in pages.iter().filter(|page| page.width > 0.0 && page.height > 0.0) {
+        for start in 0..page.words.len() {
+            let mut spelled = String::new();
+            for (end, word) in page.words.iter().enumerate().skip(start) {
+                spelled.push_str(&squash(&word.content));
+                if !wanted.starts_with(&spelled) {
+                    break;
+                }
+                if spelled == wanted {
+                    let bounding_boxes = page.words[start..=end]
+                        .iter()
+                        .map(|word| {
+                            word.polygon
+                                .iter()
+                                .flat_map(|point| {
+                                    [(point.x / page.width).clamp(0.0, 1.0), (point.y / page.height).clamp(0.0, 1.0)]
+                                })
+                                .collect()
+                        })
+                        .collect();
+                    return Some(LabelValue { page: page.page_number, text: text.to_string(), bounding_boxes });
+                }
+            }
+        }
+    }
+    None
+}
+
+fn squash(text: &str) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).flat_map(char::to_lowercase).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{DocumentWord, ExtractedDocument, KeyValuePair, Point, Span};
+    use std::collections::HashMap;
+
+    /// Page of 10 x 10 units with one word per unit-wide cell, left to right
+    fn page(words: &[&str]) -> DocumentPage {
+        DocumentPage {
+            page_number: 1,
+            angle: 0.0,
+            width: 10.0,
+            height: 10.0,
+            unit: "inch".to_string(),
+            words: words
+                .iter()
+                .enumerate()
+                .map(|(i, content)| {
+                    let x = i as f32;
+                    DocumentWord {
+                        content: content.to_string(),
+                        polygon: vec![
+                            Point { x, y: 1.0 },
+                            Point { x: x + 1.0, y: 1.0 },
+                            Point { x: x + 1.0, y: 2.0 },
+                            Point { x, y: 2.0 },
+                        ],
+                        confidence: 0.9,
+                        span: Span { offset: 0, length: content.len() as i32 },
+                    }
+                })
+                .collect(),
+            lines: Vec::new(),
+            selection_marks: Vec::new(),
+        }
+    }
+
+    fn invoice(vendor: &str, total: &str) -> AnalysisResult {
+        AnalysisResult {
+            pages: vec![page(&["Contoso", "Ltd", "Total:", "$1", "0.00", "PO-1"])],
+            key_value_pairs: vec![KeyValuePair {
+                key: "PO Number:".to_string(),
+                value: "PO-1".to_string(),
+                confidence: 0.4,
+            }],
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                confidence: 0.6,
+                fields: HashMap::from([
+                    ("VendorName".to_string(), DocumentField::String(vendor.to_string())),
+                    ("InvoiceTotal".to_string(), DocumentField::String(total.to_string())),
+                ]),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_label_document() {
+        let original = invoice("Contoso Ltd", "$10.00");
+        // The reviewer fixed the total's reading and named a vendor nowhere on the page
+        let corrected = invoice("Fabrikam", "$100.00");
+        let (labels, unlocated) = label_document("op-1.pdf", &corrected, &original);
+        assert_eq!(labels.document, "op-1.pdf");
+        assert_eq!(unlocated, Vec::<String>::new());
+
+        let names: Vec<&str> = labels.labels.iter().map(|label| label.label.as_str()).collect();
+        assert_eq!(names, vec!["InvoiceTotal", "PO Number", "VendorName"]);
+        let total = &labels.labels[0].value[0];
+        assert_eq!(total.text, "$100.00");
+        assert_eq!(total.bounding_boxes.len(), 2);
+        assert_eq!(total.bounding_boxes[0], vec![0.3, 0.1, 0.4, 0.1, 0.4, 0.2, 0.3, 0.2]);
+        let vendor = &labels.labels[2].value[0];
+        assert_eq!(vendor.text, "Fabrikam");
+        assert_eq!(vendor.bounding_boxes.len(), 2);
+
+        let mut missing = corrected.clone();
+        missing.documents[0].fields.insert("Notes".to_string(), DocumentField::String("late".to_string()));
+        let (_, unlocated) = label_document("op-1.pdf", &missing, &original);
+        assert_eq!(unlocated, vec!["Notes"]);
+    }
+
+    #[test]
+    fn test_dataset_files() {
+        let result = invoice("Contoso Ltd", "$10.00");
+        let ocr = ocr_json(&result);
+        assert_eq!(ocr["status"], "succeeded");
+        let word = &ocr["analyzeResult"]["pages"][0]["words"][0];
+        assert_eq!(word["content"], "Contoso");
+        assert_eq!(word["polygon"].as_array().unwrap().len(), 8);
+
+        let fields = fields_json(["InvoiceTotal", "VendorName"]);
+        assert_eq!(fields["$schema"], FIELDS_SCHEMA);
+        assert_eq!(fields["fields"][1]["fieldKey"], "VendorName");
+        assert_eq!(fields["fields"][1]["fieldType"], "string");
+    }
+}
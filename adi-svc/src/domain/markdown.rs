@@ -0,0 +1,176 @@
+/// Markdown rendering of analysis results
+///
+/// Renders content, tables, key-value pairs and extracted fields into one
+/// Markdown document, independent of Azure's own markdown output format.
+
+use std::fmt::Write;
+
+use super::models::{AnalysisResult, DocumentField, DocumentTable};
+
+/// Render a result as Markdown, omitting empty sections
+pub fn render_markdown(result: &AnalysisResult) -> String {
+    let mut sections = Vec::new();
+
+    if !result.content.trim().is_empty() {
+        sections.push(format!("## Content\n\n{}\n", result.content.trim_end()));
+    }
+
+    if !result.tables.is_empty() {
+        let mut section = String::from("## Tables\n");
+        for (index, table) in result.tables.iter().enumerate() {
+            let _ = write!(
+                section,
+                "\n### Table {} ({} × {})\n\n{}",
+                index + 1,
+                table.row_count,
+                table.column_count,
+                table_markdown(table)
+            );
+        }
+        sections.push(section);
+    }
+
+    if !result.key_value_pairs.is_empty() {
+        let mut section = String::from("## Key-value pairs\n\n| Key | Value |\n| --- | --- |\n");
+        for pair in &result.key_value_pairs {
+            let _ = writeln!(section, "| {} | {} |", escape_cell(&pair.key), escape_cell(&pair.value));
+        }
+        sections.push(section);
+    }
+
+    for document in result.documents.iter().filter(|document| !document.fields.is_empty()) {
+        let mut section = format!("## Fields: {}\n\n", document.doc_type);
+        let mut names: Vec<&String> = document.fields.keys().collect();
+        names.sort();
+        for name in names {
+            field_markdown(&mut section, name, &document.fields[name], 0);
+        }
+        sections.push(section);
+    }
+
+    sections.join("\n")
+}
+
+/// Table as a pipe table, using the first row as the header row
+fn table_markdown(table: &DocumentTable) -> String {
+    let rows = table.row_count.max(1) as usize;
+    let columns = table.column_count.max(1) as usize;
+    let mut grid = vec![vec![String::new(); columns]; rows];
+    for cell in &table.cells {
+        let (row, column) = (cell.row_index as usize, cell.column_index as usize);
+        if row < rows && column < columns {
+            grid[row][column] = escape_cell(&cell.content);
+        }
+    }
+
+    let mut markdown = String::new();
+    for (index, row) in grid.iter().enumerate() {
+        let _ = writeln!(markdown, "| {} |", row.join(" | "));
+        if index == 0 {
+            let _ = writeln!(markdown, "|{}", " --- |".repeat(columns));
+        }
+    }
+    markdown
+}
+
+/// Bullet for a field, nesting arrays and objects
+fn field_markdown(out: &mut String, name: &str, field: &DocumentField, depth: usize) {
+    let indent = "  ".repeat(depth);
+    match field {
+        DocumentField::Array(items) => {
+            let _ = writeln!(out, "{}- **{}**:", indent, name);
+            for (index, item) in items.iter().enumerate() {
+                field_markdown(out, &(index + 1).to_string(), item, depth + 1);
+            }
+        }
+        DocumentField::Object(fields) => {
+            let _ = writeln!(out, "{}- **{}**:", indent, name);
+            let mut names: Vec<&String> = fields.keys().collect();
+            names.sort();
+            for child in names {
+                field_markdown(out, child, &fields[child], depth + 1);
+            }
+        }
+        scalar => {
+            let _ = writeln!(out, "{}- **{}**: {}", indent, name, scalar_text(scalar));
+        }
+    }
+}
+
+fn scalar_text(field: &DocumentField) -> String {
+    match field {
+        DocumentField::String(value) => value.replace('\n', " "),
+        DocumentField::Number(value) => value.to_string(),
+        DocumentField::Integer(value) => value.to_string(),
+        DocumentField::Date(value) => value.to_string(),
+        DocumentField::Time(value) => value.to_string(),
+        DocumentField::Boolean(value) => value.to_string(),
+        DocumentField::Array(_) | DocumentField::Object(_) => String::new(),
+    }
+}
+
+/// Keep cell text on one line and away from column separators
+fn escape_cell(text: &str) -> String {
+    text.trim().replace('|', "\\|").replace('\n', "<br>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{CellKind, ExtractedDocument, KeyValuePair, TableCell};
+    use std::collections::HashMap;
+
+    fn cell(row: i32, column: i32, content: &str) -> TableCell {
+        TableCell {
+            kind: if row == 0 { CellKind::ColumnHeader } else { CellKind::Content },
+            row_index: row,
+            column_index: column,
+            row_span: 1,
+            column_span: 1,
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let result = AnalysisResult {
+            content: "Invoice 42\nTotal due".to_string(),
+            tables: vec![DocumentTable {
+                row_count: 2,
+                column_count: 2,
+                cells: vec![cell(0, 0, "Item"), cell(0, 1, "Price"), cell(1, 0, "A|B"), cell(1, 1, "1.00")],
+            }],
+            key_value_pairs: vec![KeyValuePair {
+                key: "Due".to_string(),
+                value: "2024-06-01".to_string(),
+                confidence: 0.9,
+            }],
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                fields: HashMap::from([
+                    ("VendorName".to_string(), DocumentField::String("Contoso".to_string())),
+                    (
+                        "Items".to_string(),
+                        DocumentField::Array(vec![DocumentField::Object(HashMap::from([(
+                            "Amount".to_string(),
+                            DocumentField::Number(1.5),
+                        )]))]),
+                    ),
+                ]),
+                confidence: 0.95,
+            }],
+            ..Default::default()
+        };
+
+        let markdown = render_markdown(&result);
+        assert_eq!(
+            markdown,
+            "## Content\n\nInvoice 42\nTotal due\n\n\
+             ## Tables\n\n### Table 1 (2 × 2)\n\n| Item | Price |\n| --- | --- |\n| A\\|B | 1.00 |\n\n\
+             ## Key-value pairs\n\n| Key | Value |\n| --- | --- |\n| Due | 2024-06-01 |\n\n\
+             ## Fields: invoice\n\n- **Items**:\n  - **1**:\n    - **Amount**: 1.5\n- **VendorName**: Contoso\n"
+        );
+
+        assert_eq!(render_markdown(&AnalysisResult::default()), "");
+    }
+}
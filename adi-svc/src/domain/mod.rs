@@ -6,8 +6,50 @@
 pub mod models;
 pub mod errors;
 pub mod value_objects;
+pub mod pdf;
+pub mod markdown;
+pub mod ocr_xml;
+pub mod diff;
+pub mod redaction;
+pub mod routing;
+pub mod pipeline;
+pub mod normalization;
+pub mod review;
+pub mod labeling;
+pub mod search;
+pub mod embedding;
+pub mod geometry;
+pub mod reading_order;
+pub mod tables;
+pub mod output_template;
+pub mod einvoice;
+#[cfg(feature = "analytics")]
+pub mod analytics_rows;
+pub mod export;
+pub mod stats;
 
 pub use models::*;
 pub use errors::*;
 pub use value_objects::*;
+pub use pdf::*;
+pub use markdown::*;
+pub use ocr_xml::*;
+pub use diff::*;
+pub use redaction::*;
+pub use routing::*;
+pub use pipeline::*;
+pub use normalization::*;
+pub use review::*;
+pub use labeling::*;
+pub use search::*;
+pub use embedding::*;
+pub use geometry::*;
+pub use reading_order::*;
+pub use tables::*;
+pub use output_template::*;
+pub use einvoice::*;
+#[cfg(feature = "analytics")]
+pub use analytics_rows::*;
+pub use export::*;
+pub use stats::*;
 
@@ -1,3 +1,6 @@
+use super::errors::{DomainError, DomainResult};
+use super::review::ReviewState;
+use super::routing::RoutingDecision;
 use super::value_objects::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
@@ -9,6 +12,11 @@ pub struct AnalyzeDocumentRequest {
     pub source: DocumentSource,
     pub model_type: ModelType,
     pub options: AnalyzeOptions,
+    #[serde(default)]
+    pub metadata: Option<DocumentMetadata>,
+    /// Tenant the operation and any stored document belong to
+    #[serde(default)]
+    pub tenant_id: TenantId,
 }
 
 /// Options for document analysis
@@ -17,6 +25,12 @@ pub struct AnalyzeOptions {
     pub locale: Option<Locale>,
     pub pages: Option<PageRange>,
     pub features: Vec<AnalysisFeature>,
+    /// Image clean-up before submission; ignored for PDFs and office documents
+    #[serde(default)]
+    pub preprocess: Option<ImagePreprocessing>,
+    /// How spans in the result count characters; Azure's default when unset
+    #[serde(default)]
+    pub string_index_type: Option<StringIndexType>,
 }
 
 /// Analysis operation
@@ -27,6 +41,36 @@ pub struct AnalysisOperation {
     pub created_at: chrono::DateTime<chrono::Utc>,
     pub last_updated: chrono::DateTime<chrono::Utc>,
     pub model_type: ModelType,
+    /// Storage identifier of the uploaded document, if it was stored
+    #[serde(default)]
+    pub document_id: Option<String>,
+    #[serde(default)]
+    pub filename: Option<String>,
+    #[serde(default)]
+    pub content_type: Option<String>,
+    /// Hex SHA-256 of the uploaded bytes, for duplicate lookup
+    #[serde(default)]
+    pub content_sha256: Option<String>,
+    /// Malware scan result for uploaded bytes, when scanning is enabled
+    #[serde(default)]
+    pub scan_verdict: Option<ScanVerdict>,
+    #[serde(default)]
+    pub tenant_id: TenantId,
+    /// Pages Azure analyzed, known once the result is in
+    #[serde(default)]
+    pub page_count: Option<u32>,
+    /// Why Azure failed the operation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<AzureError>,
+    /// How the model was chosen, for operations started by `analyze/auto`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub routing: Option<RoutingDecision>,
+    /// Human review, for results flagged by the review policy
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<ReviewState>,
+    /// When the operation was deleted; it is hidden until restored or purged
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl AnalysisOperation {
@@ -38,13 +82,405 @@ impl AnalysisOperation {
             created_at: now,
             last_updated: now,
             model_type,
+            document_id: None,
+            filename: None,
+            content_type: None,
+            content_sha256: None,
+            scan_verdict: None,
+            tenant_id: TenantId::default(),
+            page_count: None,
+            error: None,
+            routing: None,
+            review: None,
+            deleted_at: None,
         }
     }
     
+    /// Attach the uploaded document's metadata to the operation
+    pub fn set_document(&mut self, document_id: Option<String>, metadata: &DocumentMetadata) {
+        self.document_id = document_id;
+        self.filename = Some(metadata.filename.clone());
+        self.content_type = Some(metadata.content_type.clone());
+    }
+    
     pub fn update_status(&mut self, status: OperationStatus) {
         self.status = status;
         self.last_updated = chrono::Utc::now();
     }
+    
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
+}
+
+/// Error Azure reported, for a failed call or a failed operation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AzureError {
+    /// HTTP status of the failed call; absent when an operation failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// Azure's `error.code`, e.g. `InvalidRequest`
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub target: Option<String>,
+    /// Innermost `innererror.code`, the most specific reason, e.g. `InvalidContentLength`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub inner_code: Option<String>,
+    /// Azure's `apim-request-id`, to quote to Azure support
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl std::fmt::Display for AzureError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(status) = self.status {
+            write!(f, "status {}: ", status)?;
+        }
+        write!(f, "{}", self.code)?;
+        if let Some(inner_code) = &self.inner_code {
+            write!(f, "/{}", inner_code)?;
+        }
+        write!(f, ": {}", self.message)?;
+        if let Some(request_id) = &self.request_id {
+            write!(f, " (Azure request {})", request_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// Operation lifecycle event published for downstream systems
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifecycleEvent {
+    /// Unique per event, for consumers that deduplicate
+    pub event_id: String,
+    pub event_type: LifecycleEventKind,
+    pub occurred_at: chrono::DateTime<chrono::Utc>,
+    pub operation_id: String,
+    pub tenant_id: TenantId,
+    /// Azure model ID, e.g. `prebuilt-invoice`
+    pub model: String,
+    pub status: OperationStatus,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_id: Option<String>,
+    /// Set on `succeeded` and `processed` events
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<ResultSummary>,
+    /// Pipeline that published a `processed` event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pipeline: Option<String>,
+}
+
+impl LifecycleEvent {
+    pub fn new(event_type: LifecycleEventKind, operation: &AnalysisOperation) -> Self {
+        Self {
+            event_id: Uuid::new_v4().to_string(),
+            event_type,
+            occurred_at: chrono::Utc::now(),
+            operation_id: operation.operation_id.clone(),
+            tenant_id: operation.tenant_id.clone(),
+            model: operation.model_type.as_str().to_string(),
+            status: operation.status,
+            document_id: operation.document_id.clone(),
+            summary: None,
+            pipeline: None,
+        }
+    }
+    
+    pub fn with_summary(mut self, result: &AnalysisResult) -> Self {
+        self.summary = Some(result.summary());
+        self
+    }
+    
+    pub fn with_pipeline(mut self, pipeline: impl Into<String>) -> Self {
+        self.pipeline = Some(pipeline.into());
+        self
+    }
+}
+
+/// Counts describing a result without its content
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ResultSummary {
+    pub pages: u32,
+    pub tables: u32,
+    pub key_value_pairs: u32,
+    /// Types of the documents the model extracted, e.g. `invoice`
+    pub document_types: Vec<String>,
+    /// Characters of extracted text
+    pub content_length: u64,
+}
+
+/// A worker's time-limited claim on an operation in a work queue
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WorkLease {
+    pub queue: WorkQueue,
+    pub operation_id: String,
+    pub worker_id: String,
+    pub claimed_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Entry in an operation's state-transition history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationEvent {
+    pub operation_id: String,
+    pub kind: OperationEventKind,
+    pub at: chrono::DateTime<chrono::Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl OperationEvent {
+    pub fn new(operation_id: &str, kind: OperationEventKind, detail: Option<String>) -> Self {
+        Self {
+            operation_id: operation_id.to_string(),
+            kind,
+            at: chrono::Utc::now(),
+            detail,
+        }
+    }
+}
+
+/// An authenticated caller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Principal {
+    /// Audit identity: an API key fingerprint (`key:…`) or `anonymous`
+    pub id: String,
+    /// Tenant the caller acts for
+    pub tenant: TenantId,
+    pub roles: Vec<Role>,
+}
+
+impl Principal {
+    pub fn new(id: impl Into<String>, tenant: TenantId, roles: Vec<Role>) -> Self {
+        Self { id: id.into(), tenant, roles }
+    }
+    
+    /// Whether the caller holds `role`; admins hold every role
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.iter().any(|held| *held == role || *held == Role::Admin)
+    }
+}
+
+/// One mutating API call, as recorded for compliance
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub at: chrono::DateTime<chrono::Utc>,
+    /// Caller: an API key fingerprint (`key:…`) or `anonymous`
+    pub principal: String,
+    /// Route or RPC, e.g. `POST /api/v1/analyze/invoice` or `grpc AnalyzeInvoice`
+    pub action: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub document_sha256: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    pub outcome: AuditOutcome,
+    /// HTTP status or gRPC code
+    pub status: String,
+}
+
+impl AuditEntry {
+    pub fn new(principal: &str, action: &str, outcome: AuditOutcome, status: impl Into<String>) -> Self {
+        Self {
+            at: chrono::Utc::now(),
+            principal: principal.to_string(),
+            action: action.to_string(),
+            model: None,
+            document_sha256: None,
+            operation_id: None,
+            outcome,
+            status: status.into(),
+        }
+    }
+    
+    /// Attribute the call to the operation it started
+    pub fn with_operation(mut self, operation: &AnalysisOperation) -> Self {
+        self.model = Some(operation.model_type.as_str().to_string());
+        self.document_sha256 = operation.content_sha256.clone();
+        self.operation_id = Some(operation.operation_id.clone());
+        self
+    }
+    
+    /// Whether the entry passes `query`'s filters (the limit is not applied)
+    pub fn matches(&self, query: &AuditQuery) -> bool {
+        query.principal.iter().all(|principal| *principal == self.principal)
+            && query.action.iter().all(|action| *action == self.action)
+            && query.operation_id.iter().all(|id| Some(id) == self.operation_id.as_ref())
+            && query.since.iter().all(|since| self.at >= *since)
+            && query.until.iter().all(|until| self.at <= *until)
+    }
+}
+
+/// Operations and pages one tenant analyzed with one model on one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageRecord {
+    pub tenant_id: TenantId,
+    pub day: chrono::NaiveDate,
+    pub model: ModelType,
+    pub operations: u64,
+    pub pages: u64,
+}
+
+impl UsageRecord {
+    /// Cost of the pages at the model's Azure list price
+    pub fn estimated_cost_usd(&self) -> f64 {
+        self.pages as f64 * self.model.list_price_per_1000_pages() / 1000.0
+    }
+}
+
+/// Limits on what a tenant may submit per day or month
+///
+/// Unset limits are unlimited. Usage is counted as metered, so operations
+/// still running when the limit is reached are not refused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quota {
+    pub tenant_id: TenantId,
+    pub period: QuotaPeriod,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_operations: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_pages: Option<u64>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl Quota {
+    pub fn new(
+        tenant_id: TenantId,
+        period: QuotaPeriod,
+        max_operations: Option<u64>,
+        max_pages: Option<u64>,
+    ) -> DomainResult<Self> {
+        if max_operations.is_none() && max_pages.is_none() {
+            return Err(DomainError::ValidationError(
+                "a quota needs max_operations, max_pages or both".to_string(),
+            ));
+        }
+        Ok(Self {
+            tenant_id,
+            period,
+            max_operations,
+            max_pages,
+            updated_at: chrono::Utc::now(),
+        })
+    }
+    
+    /// The limit already reached by `operations` and `pages`, if any
+    pub fn exceeded_by(&self, operations: u64, pages: u64) -> Option<String> {
+        if let Some(max) = self.max_operations.filter(|max| operations >= *max) {
+            return Some(format!("{} limit of {} operations reached", self.period.as_str(), max));
+        }
+        if let Some(max) = self.max_pages.filter(|max| pages >= *max) {
+            return Some(format!("{} limit of {} pages reached", self.period.as_str(), max));
+        }
+        None
+    }
+}
+
+/// Usage counted against a quota in its current window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub operations: u64,
+    pub pages: u64,
+    pub resets_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Analysis request queued for the worker pool instead of submitted inline
+///
+/// Uploaded bytes are stored before the job is queued, so a job refers to
+/// either a URL or a stored document.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisJob {
+    pub job_id: String,
+    pub tenant_id: TenantId,
+    pub model_type: ModelType,
+    pub options: AnalyzeOptions,
+    #[serde(default)]
+    pub document_url: Option<String>,
+    #[serde(default)]
+    pub document_id: Option<String>,
+    #[serde(default)]
+    pub metadata: Option<DocumentMetadata>,
+    #[serde(default)]
+    pub scan_verdict: Option<ScanVerdict>,
+    #[serde(default)]
+    pub priority: JobPriority,
+    pub status: JobStatus,
+    /// Times a worker has claimed the job
+    pub attempts: u32,
+    /// Workers leave a queued job alone until then, so retries back off
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    /// Operation started by the job, once submitted
+    #[serde(default)]
+    pub operation_id: Option<String>,
+    /// Why the last attempt failed
+    #[serde(default)]
+    pub error: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl AnalysisJob {
+    pub fn new(tenant_id: TenantId, model_type: ModelType, options: AnalyzeOptions) -> Self {
+        let now = chrono::Utc::now();
+        Self {
+            job_id: Uuid::new_v4().to_string(),
+            tenant_id,
+            model_type,
+            options,
+            document_url: None,
+            document_id: None,
+            metadata: None,
+            scan_verdict: None,
+            priority: JobPriority::default(),
+            status: JobStatus::Queued,
+            attempts: 0,
+            next_attempt_at: now,
+            operation_id: None,
+            error: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+    
+    pub fn with_priority(mut self, priority: JobPriority) -> Self {
+        self.priority = priority;
+        self
+    }
+    
+    /// Record that the job started `operation_id`
+    pub fn submitted(&mut self, operation_id: &str) {
+        self.status = JobStatus::Submitted;
+        self.operation_id = Some(operation_id.to_string());
+        self.error = None;
+        self.updated_at = chrono::Utc::now();
+    }
+    
+    /// Queue the job again once `delay` has passed
+    pub fn retry_after(&mut self, error: impl Into<String>, delay: chrono::Duration) {
+        let now = chrono::Utc::now();
+        self.status = JobStatus::Queued;
+        self.error = Some(error.into());
+        self.next_attempt_at = now + delay;
+        self.updated_at = now;
+    }
+    
+    /// Give up on the job until someone retries it by hand
+    pub fn dead_letter(&mut self, error: impl Into<String>) {
+        self.status = JobStatus::DeadLettered;
+        self.error = Some(error.into());
+        self.updated_at = chrono::Utc::now();
+    }
+    
+    /// Start over with a fresh set of attempts, keeping the last error for reference
+    pub fn requeue(&mut self) {
+        let now = chrono::Utc::now();
+        self.status = JobStatus::Queued;
+        self.attempts = 0;
+        self.next_attempt_at = now;
+        self.updated_at = now;
+    }
 }
 
 /// Complete analysis result
@@ -57,6 +493,9 @@ pub struct AnalysisResult {
     pub tables: Vec<DocumentTable>,
     pub key_value_pairs: Vec<KeyValuePair>,
     pub documents: Vec<ExtractedDocument>,
+    /// How the spans' offsets and lengths count characters
+    #[serde(default)]
+    pub string_index_type: StringIndexType,
 }
 
 impl Default for AnalysisResult {
@@ -69,6 +508,156 @@ impl Default for AnalysisResult {
             tables: Vec::new(),
             key_value_pairs: Vec::new(),
             documents: Vec::new(),
+            string_index_type: StringIndexType::default(),
+        }
+    }
+}
+
+impl AnalysisResult {
+    /// Counts for notifications, without the content itself
+    pub fn summary(&self) -> ResultSummary {
+        ResultSummary {
+            pages: self.pages.len() as u32,
+            tables: self.tables.len() as u32,
+            key_value_pairs: self.key_value_pairs.len() as u32,
+            document_types: self.documents.iter().map(|document| document.doc_type.clone()).collect(),
+            content_length: self.content.chars().count() as u64,
+        }
+    }
+    
+    /// Drop the sections not selected by `fields`
+    pub fn project(mut self, fields: &ResultFields) -> Self {
+        if !fields.content {
+            self.content.clear();
+        }
+        if !fields.pages {
+            self.pages.clear();
+        }
+        if !fields.tables {
+            self.tables.clear();
+        }
+        if !fields.key_value_pairs {
+            self.key_value_pairs.clear();
+        }
+        if !fields.documents {
+            self.documents.clear();
+        }
+        self
+    }
+    
+    /// Drop words, key-value pairs and documents (with their fields) whose
+    /// confidence is below `min_confidence`
+    pub fn retain_confident(&mut self, min_confidence: f32) -> ConfidenceFiltered {
+        let mut filtered = ConfidenceFiltered::default();
+        for page in &mut self.pages {
+            let before = page.words.len();
+            page.words.retain(|word| word.confidence >= min_confidence);
+            filtered.words += before - page.words.len();
+        }
+        
+        let before = self.key_value_pairs.len();
+        self.key_value_pairs.retain(|pair| pair.confidence >= min_confidence);
+        filtered.key_value_pairs = before - self.key_value_pairs.len();
+        
+        self.documents.retain(|document| {
+            let keep = document.confidence >= min_confidence;
+            if !keep {
+                filtered.document_fields += document.fields.len();
+            }
+            keep
+        });
+        filtered
+    }
+    
+    /// Key-value pairs and document fields passing `query`, pairs first,
+    /// then fields by document and name; fields carry their document's confidence
+    pub fn query_fields(&self, query: &FieldQuery) -> Vec<FieldMatch> {
+        let pairs = self
+            .key_value_pairs
+            .iter()
+            .filter(|pair| query.matches(&pair.key, pair.confidence))
+            .map(|pair| FieldMatch::KeyValuePair {
+                key: pair.key.clone(),
+                value: pair.value.clone(),
+                confidence: pair.confidence,
+            });
+        let fields = self.documents.iter().flat_map(|document| {
+            let mut names: Vec<&String> = document
+                .fields
+                .keys()
+                .filter(|name| query.matches(name, document.confidence))
+                .collect();
+            names.sort();
+            names.into_iter().map(move |name| FieldMatch::DocumentField {
+                doc_type: document.doc_type.clone(),
+                name: name.clone(),
+                value: document.fields[name].clone(),
+                confidence: document.confidence,
+            })
+        });
+        pairs.chain(fields).collect()
+    }
+    
+    /// Plain text of the result: the full `content`, or the lines of the
+    /// selected pages (one per line, pages separated by a blank line)
+    pub fn text(&self, pages: Option<&PageRange>) -> String {
+        let pages = match pages.filter(|pages| !pages.is_empty()) {
+            Some(pages) => pages,
+            None => return self.content.clone(),
+        };
+        
+        self.pages
+            .iter()
+            .filter(|page| u32::try_from(page.page_number).is_ok_and(|number| pages.contains(number)))
+            .map(|page| {
+                page.lines
+                    .iter()
+                    .map(|line| line.content.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+/// How many entries `AnalysisResult::retain_confident` dropped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConfidenceFiltered {
+    pub words: usize,
+    pub key_value_pairs: usize,
+    pub document_fields: usize,
+}
+
+/// Key-value pair or document field selected by a [`FieldQuery`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "source", rename_all = "snake_case")]
+pub enum FieldMatch {
+    KeyValuePair {
+        key: String,
+        value: String,
+        confidence: f32,
+    },
+    DocumentField {
+        doc_type: String,
+        name: String,
+        value: DocumentField,
+        confidence: f32,
+    },
+}
+
+impl FieldMatch {
+    /// Document field name, or pair key without surrounding space and a trailing colon
+    pub fn name(&self) -> &str {
+        match self {
+            Self::KeyValuePair { key, .. } => key.trim().trim_end_matches(':').trim_end(),
+            Self::DocumentField { name, .. } => name,
+        }
+    }
+    
+    pub fn confidence(&self) -> f32 {
+        match self {
+            Self::KeyValuePair { confidence, .. } | Self::DocumentField { confidence, .. } => *confidence,
         }
     }
 }
@@ -119,7 +708,7 @@ pub enum SelectionMarkState {
 }
 
 /// Point in 2D space
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub struct Point {
     pub x: f32,
     pub y: f32,
@@ -178,7 +767,7 @@ pub struct ExtractedDocument {
 }
 
 /// Document field with typed value
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type", content = "value")]
 pub enum DocumentField {
     #[serde(rename = "string")]
@@ -241,6 +830,170 @@ mod tests {
         assert!(op.last_updated > initial_time);
     }
 
+    #[test]
+    fn test_result_text_by_page() {
+        let page = |page_number: i32, lines: &[&str]| DocumentPage {
+            page_number,
+            angle: 0.0,
+            width: 8.5,
+            height: 11.0,
+            unit: "inch".to_string(),
+            words: Vec::new(),
+            lines: lines
+                .iter()
+                .map(|content| DocumentLine {
+                    content: content.to_string(),
+                    polygon: Vec::new(),
+                    spans: Vec::new(),
+                })
+                .collect(),
+            selection_marks: Vec::new(),
+        };
+        let result = AnalysisResult {
+            content: "a\nb\nc".to_string(),
+            pages: vec![page(1, &["a", "b"]), page(2, &["c"]), page(3, &["d"])],
+            ..Default::default()
+        };
+        
+        assert_eq!(result.text(None), "a\nb\nc");
+        assert_eq!(result.text(Some(&PageRange::all())), "a\nb\nc");
+        let range = PageRange::new(vec!["1,3".to_string()]).unwrap();
+        assert_eq!(result.text(Some(&range)), "a\nb\n\nd");
+    }
+
+    #[test]
+    fn test_query_fields() {
+        let result = AnalysisResult {
+            key_value_pairs: vec![
+                KeyValuePair {
+                    key: "InvoiceTotal".to_string(),
+                    value: "$10".to_string(),
+                    confidence: 0.9,
+                },
+                KeyValuePair {
+                    key: "InvoiceTotal".to_string(),
+                    value: "$1O".to_string(),
+                    confidence: 0.4,
+                },
+            ],
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                fields: HashMap::from([
+                    ("InvoiceTotal".to_string(), DocumentField::Number(10.0)),
+                    ("VendorName".to_string(), DocumentField::String("Contoso".to_string())),
+                ]),
+                confidence: 0.85,
+            }],
+            ..Default::default()
+        };
+        
+        let query = FieldQuery::new(Some("invoicetotal".to_string()), Some(0.8)).unwrap();
+        let matches = result.query_fields(&query);
+        assert_eq!(matches.len(), 2);
+        assert!(matches!(&matches[0], FieldMatch::KeyValuePair { value, .. } if value == "$10"));
+        assert!(matches!(
+            &matches[1],
+            FieldMatch::DocumentField { name, value: DocumentField::Number(total), .. }
+                if name == "InvoiceTotal" && *total == 10.0
+        ));
+        
+        assert_eq!(result.query_fields(&FieldQuery::default()).len(), 4);
+    }
+
+    #[test]
+    fn test_retain_confident() {
+        let word = |confidence: f32| DocumentWord {
+            content: "word".to_string(),
+            polygon: Vec::new(),
+            confidence,
+            span: Span { offset: 0, length: 4 },
+        };
+        let mut result = AnalysisResult {
+            pages: vec![DocumentPage {
+                page_number: 1,
+                angle: 0.0,
+                width: 8.5,
+                height: 11.0,
+                unit: "inch".to_string(),
+                words: vec![word(0.99), word(0.5), word(0.8)],
+                lines: Vec::new(),
+                selection_marks: Vec::new(),
+            }],
+            key_value_pairs: vec![KeyValuePair {
+                key: "Total".to_string(),
+                value: "10".to_string(),
+                confidence: 0.6,
+            }],
+            documents: vec![ExtractedDocument {
+                doc_type: "receipt".to_string(),
+                fields: HashMap::from([("Total".to_string(), DocumentField::Number(10.0))]),
+                confidence: 0.7,
+            }],
+            ..Default::default()
+        };
+        
+        let filtered = result.retain_confident(0.8);
+        assert_eq!(
+            filtered,
+            ConfidenceFiltered {
+                words: 1,
+                key_value_pairs: 1,
+                document_fields: 1,
+            }
+        );
+        assert_eq!(result.pages[0].words.len(), 2);
+        assert!(result.key_value_pairs.is_empty() && result.documents.is_empty());
+    }
+
+    #[test]
+    fn test_audit_entry_matches() {
+        let mut operation = AnalysisOperation::new(ModelType::Invoice);
+        operation.content_sha256 = Some("ab".repeat(32));
+        let entry = AuditEntry::new("key:0123", "POST /api/v1/analyze/invoice", AuditOutcome::Success, "200")
+            .with_operation(&operation);
+        assert_eq!(entry.model.as_deref(), Some("prebuilt-invoice"));
+        assert_eq!(entry.document_sha256, operation.content_sha256);
+
+        assert!(entry.matches(&AuditQuery::default()));
+        assert!(entry.matches(&AuditQuery {
+            principal: Some("key:0123".to_string()),
+            operation_id: Some(operation.operation_id.clone()),
+            ..Default::default()
+        }));
+        assert!(!entry.matches(&AuditQuery {
+            action: Some("DELETE /api/v1/uploads/:upload_id".to_string()),
+            ..Default::default()
+        }));
+        assert!(!entry.matches(&AuditQuery {
+            since: Some(entry.at + chrono::Duration::seconds(1)),
+            ..Default::default()
+        }));
+    }
+
+    #[test]
+    fn test_usage_record_cost() {
+        let record = UsageRecord {
+            tenant_id: TenantId::default(),
+            day: chrono::NaiveDate::from_ymd_opt(2024, 5, 1).unwrap(),
+            model: ModelType::Read,
+            operations: 3,
+            pages: 2000,
+        };
+        assert!((record.estimated_cost_usd() - 3.0).abs() < 1e-9);
+        let layout = UsageRecord { model: ModelType::Layout, ..record };
+        assert!((layout.estimated_cost_usd() - 20.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_quota_limits() {
+        let tenant = TenantId::default();
+        assert!(Quota::new(tenant.clone(), QuotaPeriod::Daily, None, None).is_err());
+        let quota = Quota::new(tenant, QuotaPeriod::Monthly, Some(10), Some(100)).unwrap();
+        assert_eq!(quota.exceeded_by(9, 99), None);
+        assert_eq!(quota.exceeded_by(10, 0).unwrap(), "monthly limit of 10 operations reached");
+        assert_eq!(quota.exceeded_by(0, 250).unwrap(), "monthly limit of 100 pages reached");
+    }
+
     #[test]
     fn test_document_field_accessors() {
         let string_field = DocumentField::String("test".to_string());
@@ -0,0 +1,483 @@
+/// Canonical forms of extracted values
+///
+/// Azure returns most fields as the text printed on the page. Normalization
+/// reads that text as a date, phone number, amount or country, guided by the
+/// field's name, and gives it one canonical spelling: ISO-8601 dates, E.164
+/// phone numbers, decimal amounts with ISO 4217 currency codes and ISO 3166-1
+/// alpha-2 country codes. Text that can't be read confidently is left out
+/// rather than guessed at.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::models::{DocumentField, FieldMatch};
+
+/// Currency symbols and prefixes, longest first so `US$` wins over `$`
+const CURRENCY_SYMBOLS: &[(&str, &str)] = &[
+    ("US$", "USD"),
+    ("CA$", "CAD"),
+    ("C$", "CAD"),
+    ("AU$", "AUD"),
+    ("A$", "AUD"),
+    ("NZ$", "NZD"),
+    ("HK$", "HKD"),
+    ("R$", "BRL"),
+    ("$", "USD"),
+    ("€", "EUR"),
+    ("£", "GBP"),
+    ("¥", "JPY"),
+    ("₹", "INR"),
+    ("₩", "KRW"),
+    ("₽", "RUB"),
+    ("₺", "TRY"),
+    ("₦", "NGN"),
+];
+
+/// ISO 4217 codes recognized next to an amount
+const CURRENCY_CODES: &[&str] = &[
+    "USD", "EUR", "GBP", "JPY", "CHF", "CAD", "AUD", "NZD", "CNY", "HKD", "SGD", "INR", "KRW", "SEK", "NOK", "DKK",
+    "PLN", "CZK", "HUF", "MXN", "BRL", "ZAR", "AED", "SAR", "ILS", "TRY", "RUB", "NGN", "XAF", "XOF",
+];
+
+/// Countries by alpha-2 code, alpha-3 code and common names
+const COUNTRIES: &[(&str, &str, &[&str])] = &[
+    ("US", "USA", &["united states", "united states of america", "u.s.", "u.s.a.", "america"]),
+    ("GB", "GBR", &["united kingdom", "great britain", "uk", "u.k.", "england", "scotland", "wales"]),
+    ("CA", "CAN", &["canada"]),
+    ("MX", "MEX", &["mexico"]),
+    ("BR", "BRA", &["brazil", "brasil"]),
+    ("AR", "ARG", &["argentina"]),
+    ("DE", "DEU", &["germany", "deutschland"]),
+    ("FR", "FRA", &["france"]),
+    ("ES", "ESP", &["spain", "españa", "espana"]),
+    ("IT", "ITA", &["italy", "italia"]),
+    ("PT", "PRT", &["portugal"]),
+    ("NL", "NLD", &["netherlands", "the netherlands", "holland"]),
+    ("BE", "BEL", &["belgium"]),
+    ("LU", "LUX", &["luxembourg"]),
+    ("CH", "CHE", &["switzerland"]),
+    ("AT", "AUT", &["austria"]),
+    ("IE", "IRL", &["ireland"]),
+    ("SE", "SWE", &["sweden"]),
+    ("NO", "NOR", &["norway"]),
+    ("DK", "DNK", &["denmark"]),
+    ("FI", "FIN", &["finland"]),
+    ("PL", "POL", &["poland"]),
+    ("CZ", "CZE", &["czech republic", "czechia"]),
+    ("GR", "GRC", &["greece"]),
+    ("TR", "TUR", &["turkey", "türkiye", "turkiye"]),
+    ("RU", "RUS", &["russia", "russian federation"]),
+    ("UA", "UKR", &["ukraine"]),
+    ("IL", "ISR", &["israel"]),
+    ("AE", "ARE", &["united arab emirates", "uae"]),
+    ("SA", "SAU", &["saudi arabia"]),
+    ("IN", "IND", &["india"]),
+    ("CN", "CHN", &["china", "people's republic of china"]),
+    ("HK", "HKG", &["hong kong"]),
+    ("JP", "JPN", &["japan"]),
+    ("KR", "KOR", &["south korea", "korea", "republic of korea"]),
+    ("SG", "SGP", &["singapore"]),
+    ("AU", "AUS", &["australia"]),
+    ("NZ", "NZL", &["new zealand"]),
+    ("ZA", "ZAF", &["south africa"]),
+    ("NG", "NGA", &["nigeria"]),
+    ("KE", "KEN", &["kenya"]),
+    ("EG", "EGY", &["egypt"]),
+    ("MA", "MAR", &["morocco"]),
+    ("CM", "CMR", &["cameroon", "cameroun"]),
+];
+
+/// Words in a field name suggesting a money amount
+const AMOUNT_HINTS: &[&str] = &[
+    "amount", "total", "price", "tax", "balance", "cost", "fee", "charge", "payment", "wage", "salary", "tip",
+    "discount", "due", "paid", "rate",
+];
+
+/// An extracted value in canonical form
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum NormalizedValue {
+    /// ISO-8601 calendar date
+    Date { value: NaiveDate },
+    /// E.164, e.g. `+14255550100`
+    PhoneNumber { value: String },
+    /// Decimal amount, kept as text to keep its precision, with the ISO 4217
+    /// currency when one was printed
+    Amount {
+        value: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        currency: Option<String>,
+    },
+    /// ISO 3166-1 alpha-2 code
+    CountryCode { value: String },
+}
+
+/// What a field's name says its value is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Date,
+    Phone,
+    Country,
+    Amount,
+}
+
+impl FieldKind {
+    fn of(name: &str) -> Option<Self> {
+        let name: String = name.chars().filter(|c| c.is_alphanumeric()).collect::<String>().to_lowercase();
+        if name.contains("date") || name.ends_with("dob") {
+            Some(Self::Date)
+        } else if ["phone", "fax", "tel", "mobile"].iter().any(|hint| name.contains(hint)) {
+            Some(Self::Phone)
+        } else if name.contains("country") {
+            Some(Self::Country)
+        } else if AMOUNT_HINTS.iter().any(|hint| name.contains(hint)) {
+            Some(Self::Amount)
+        } else {
+            None
+        }
+    }
+}
+
+/// Canonical form of `raw`, the text of field `name`
+///
+/// Without a telling name only amounts printed with a currency are read, as
+/// a bare number or slash-separated digits could be anything.
+pub fn normalize_value(name: &str, raw: &str) -> Option<NormalizedValue> {
+    let raw = raw.trim();
+    if raw.is_empty() {
+        return None;
+    }
+    match FieldKind::of(name) {
+        Some(FieldKind::Date) => parse_date(raw).map(|value| NormalizedValue::Date { value }),
+        Some(FieldKind::Phone) => parse_phone(raw).map(|value| NormalizedValue::PhoneNumber { value }),
+        Some(FieldKind::Country) => parse_country(raw).map(|value| NormalizedValue::CountryCode { value }),
+        Some(FieldKind::Amount) => parse_amount(raw).map(|(value, currency)| NormalizedValue::Amount { value, currency }),
+        None => parse_amount(raw)
+            .filter(|(_, currency)| currency.is_some())
+            .map(|(value, currency)| NormalizedValue::Amount { value, currency }),
+    }
+}
+
+/// Canonical form of a typed document field
+pub fn normalize_field(name: &str, field: &DocumentField) -> Option<NormalizedValue> {
+    match field {
+        DocumentField::String(raw) => normalize_value(name, raw),
+        DocumentField::Date(value) => Some(NormalizedValue::Date { value: *value }),
+        DocumentField::Number(number) if FieldKind::of(name) == Some(FieldKind::Amount) && number.is_finite() => {
+            Some(NormalizedValue::Amount { value: number.to_string(), currency: None })
+        }
+        DocumentField::Integer(number) if FieldKind::of(name) == Some(FieldKind::Amount) => {
+            Some(NormalizedValue::Amount { value: number.to_string(), currency: None })
+        }
+        _ => None,
+    }
+}
+
+/// Canonical forms of the fields that have one, keyed by name
+///
+/// Key-value pair keys lose a trailing colon. When a name occurs more than
+/// once, its first readable value is kept.
+pub fn normalized_fields(fields: &[FieldMatch]) -> BTreeMap<String, NormalizedValue> {
+    let mut normalized = BTreeMap::new();
+    for field in fields {
+        let name = field.name();
+        let value = match field {
+            FieldMatch::KeyValuePair { value, .. } => normalize_value(name, value),
+            FieldMatch::DocumentField { value, .. } => normalize_field(name, value),
+        };
+        if let Some(value) = value {
+            normalized.entry(name.to_string()).or_insert(value);
+        }
+    }
+    normalized
+}
+
+/// Read a date written with digits (`2024-03-01`, `03/01/2024`, `01.03.2024`)
+/// or with a month name (`March 1, 2024`, `1 Mar 2024`)
+///
+/// Slash and dash dates are read month first unless the first number can't
+/// be a month; dotted dates are read day first, as they are written in Europe.
+fn parse_date(raw: &str) -> Option<NaiveDate> {
+    let raw = raw.trim_end_matches(['.', ',']);
+    let separator = raw.chars().find(|c| matches!(c, '-' | '/' | '.'));
+    let numeric: Vec<&str> = raw.split(['-', '/', '.']).map(str::trim).collect();
+    if numeric.len() == 3 && numeric.iter().all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_digit())) {
+        let numbers: Vec<u32> = numeric.iter().filter_map(|part| part.parse().ok()).collect();
+        let (year, month, day) = if numeric[0].len() == 4 {
+            (numbers[0], numbers[1], numbers[2])
+        } else {
+            let year = match numeric[2].len() {
+                4 => numbers[2],
+                2 if numbers[2] < 70 => 2000 + numbers[2],
+                2 => 1900 + numbers[2],
+                _ => return None,
+            };
+            if separator == Some('.') || numbers[0] > 12 {
+                (year, numbers[1], numbers[0])
+            } else {
+                (year, numbers[0], numbers[1])
+            }
+        };
+        return NaiveDate::from_ymd_opt(year as i32, month, day);
+    }
+
+    // Month names, with the day and year in either order around it
+    let lowered = raw.to_lowercase();
+    let mut month = None;
+    let mut day = None;
+    let mut year = None;
+    for token in lowered.split(|c: char| c.is_whitespace() || c == ',' || c == '-').filter(|t| !t.is_empty()) {
+        let token = token.trim_end_matches('.');
+        let digits = token.trim_end_matches(|c: char| c.is_ascii_alphabetic());
+        if !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()) {
+            match digits.len() {
+                1 | 2 if day.is_none() => day = digits.parse().ok(),
+                4 if year.is_none() => year = digits.parse().ok(),
+                _ => return None,
+            }
+        } else if month.is_none() {
+            month = month_number(token);
+            month?;
+        } else {
+            return None;
+        }
+    }
+    NaiveDate::from_ymd_opt(year?, month?, day?)
+}
+
+/// Number of a month given by its name or a three-letter (or longer) prefix
+fn month_number(token: &str) -> Option<u32> {
+    const MONTHS: [&str; 12] = [
+        "january", "february", "march", "april", "may", "june", "july", "august", "september", "october", "november",
+        "december",
+    ];
+    if token.len() < 3 {
+        return None;
+    }
+    // `sept` is a common abbreviation that isn't a prefix
+    let token = if token == "sept" { "sep" } else { token };
+    MONTHS
+        .iter()
+        .position(|month| month.starts_with(token))
+        .map(|index| index as u32 + 1)
+}
+
+/// Read a phone number as E.164
+///
+/// Numbers without a country code are read as North American when they have
+/// ten digits; other national numbers are left out.
+fn parse_phone(raw: &str) -> Option<String> {
+    let lowered = raw.to_lowercase();
+    let number = lowered
+        .split("ext")
+        .next()
+        .and_then(|number| number.split(['x', '#']).next())
+        .unwrap_or_default()
+        .trim();
+    if number.chars().any(|c| c.is_alphabetic()) {
+        return None;
+    }
+    let digits: String = number.chars().filter(char::is_ascii_digit).collect();
+    let international = if number.starts_with('+') {
+        digits
+    } else if let Some(rest) = digits.strip_prefix("00") {
+        rest.to_string()
+    } else if digits.len() == 10 && !digits.starts_with(['0', '1']) {
+        format!("1{}", digits)
+    } else if digits.len() == 11 && digits.starts_with('1') {
+        digits
+    } else {
+        return None;
+    };
+    ((8..=15).contains(&international.len()) && !international.starts_with('0')).then(|| format!("+{}", international))
+}
+
+/// Read an amount as a decimal with its currency, if one was printed
+///
+/// The last `.` or `,` is the decimal separator unless it is followed by
+/// exactly three digits and is the only one, as in `1,250`; the others group
+/// thousands. Parentheses and a minus sign mark negative amounts.
+fn parse_amount(raw: &str) -> Option<(String, Option<String>)> {
+    let mut text = raw.trim().to_string();
+    let mut currency = None;
+    for (symbol, code) in CURRENCY_SYMBOLS {
+        if text.contains(symbol) {
+            text = text.replacen(symbol, " ", 1);
+            currency = Some(code.to_string());
+            break;
+        }
+    }
+    let upper = text.to_uppercase();
+    for code in CURRENCY_CODES {
+        let found = upper.match_indices(code).find(|(start, _)| {
+            let before = upper[..*start].chars().next_back();
+            let after = upper[start + code.len()..].chars().next();
+            !before.is_some_and(char::is_alphabetic) && !after.is_some_and(char::is_alphabetic)
+        });
+        if let Some((start, _)) = found {
+            text.replace_range(start..start + code.len(), " ");
+            currency = Some(code.to_string());
+            break;
+        }
+    }
+
+    let mut negative = false;
+    let mut number = String::new();
+    let trimmed = text.trim();
+    let trimmed = match trimmed.strip_prefix('(').and_then(|inner| inner.strip_suffix(')')) {
+        Some(inner) => {
+            negative = true;
+            inner
+        }
+        None => trimmed,
+    };
+    for c in trimmed.chars() {
+        match c {
+            '0'..='9' | '.' | ',' => number.push(c),
+            '-' | '−' if number.is_empty() && !negative => negative = true,
+            '-' if number.chars().last().is_some_and(|last| last.is_ascii_digit()) && negative => return None,
+            // Thousands spaced with regular, no-break and thin spaces or apostrophes
+            ' ' | '\u{a0}' | '\u{202f}' | '\'' | '’' => {}
+            _ => return None,
+        }
+    }
+    if !number.chars().any(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let decimal_at = number.rfind(['.', ',']).filter(|&at| {
+        let separator = number.as_bytes()[at];
+        let fraction = &number[at + 1..];
+        let single = number.bytes().filter(|&b| b == separator).count() == 1;
+        let other = number.bytes().any(|b| (b == b'.' || b == b',') && b != separator);
+        single && (other || fraction.len() != 3)
+    });
+    let (whole, fraction) = match decimal_at {
+        Some(at) => (&number[..at], Some(&number[at + 1..])),
+        None => (number.as_str(), None),
+    };
+    let whole: String = whole.chars().filter(char::is_ascii_digit).collect();
+    let whole = whole.trim_start_matches('0');
+    let whole = if whole.is_empty() { "0" } else { whole };
+    if fraction.is_some_and(|fraction| !fraction.chars().all(|c| c.is_ascii_digit())) {
+        return None;
+    }
+    let value = match fraction.filter(|fraction| !fraction.is_empty()) {
+        Some(fraction) => format!("{}{}.{}", if negative { "-" } else { "" }, whole, fraction),
+        None => format!("{}{}", if negative { "-" } else { "" }, whole),
+    };
+    Some((value, currency))
+}
+
+/// Read a country name or code as ISO 3166-1 alpha-2
+fn parse_country(raw: &str) -> Option<String> {
+    let lowered = raw.trim().trim_end_matches('.').to_lowercase();
+    let upper = lowered.to_uppercase();
+    COUNTRIES
+        .iter()
+        .find(|(alpha2, alpha3, names)| {
+            upper == *alpha2 || upper == *alpha3 || names.iter().any(|name| name.trim_end_matches('.') == lowered)
+        })
+        .map(|(alpha2, _, _)| alpha2.to_string())
+        .or_else(|| (upper.len() == 2 && upper.chars().all(|c| c.is_ascii_alphabetic())).then_some(upper))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> Option<NormalizedValue> {
+        Some(NormalizedValue::Date { value: NaiveDate::from_ymd_opt(y, m, d).unwrap() })
+    }
+
+    fn amount(value: &str, currency: Option<&str>) -> Option<NormalizedValue> {
+        Some(NormalizedValue::Amount { value: value.to_string(), currency: currency.map(str::to_string) })
+    }
+
+    #[test]
+    fn test_dates() {
+        assert_eq!(normalize_value("InvoiceDate", "2024-03-01"), date(2024, 3, 1));
+        assert_eq!(normalize_value("InvoiceDate", "03/01/2024"), date(2024, 3, 1));
+        assert_eq!(normalize_value("InvoiceDate", "25/12/2023"), date(2023, 12, 25));
+        assert_eq!(normalize_value("Due Date:", "01.03.24"), date(2024, 3, 1));
+        assert_eq!(normalize_value("DueDate", "March 1, 2024"), date(2024, 3, 1));
+        assert_eq!(normalize_value("DueDate", "1st Sept. 2024"), date(2024, 9, 1));
+        assert_eq!(normalize_value("DateOfBirth", "Feb 30, 2024"), None);
+        assert_eq!(normalize_value("ServiceDate", "Q1 2024"), None);
+    }
+
+    #[test]
+    fn test_phone_numbers() {
+        assert_eq!(
+            normalize_value("VendorPhone", "(425) 555-0100 ext. 12"),
+            Some(NormalizedValue::PhoneNumber { value: "+14255550100".to_string() })
+        );
+        assert_eq!(
+            normalize_value("Fax", "+44 20 7946 0958"),
+            Some(NormalizedValue::PhoneNumber { value: "+442079460958".to_string() })
+        );
+        assert_eq!(
+            normalize_value("Tel.", "0049 30 901820"),
+            Some(NormalizedValue::PhoneNumber { value: "+4930901820".to_string() })
+        );
+        // National numbers outside North America have no known country code
+        assert_eq!(normalize_value("Phone", "020 7946 0958"), None);
+        assert_eq!(normalize_value("Phone", "call us"), None);
+    }
+
+    #[test]
+    fn test_amounts() {
+        assert_eq!(normalize_value("InvoiceTotal", "$1,234.50"), amount("1234.50", Some("USD")));
+        assert_eq!(normalize_value("Total", "1.234,50 €"), amount("1234.50", Some("EUR")));
+        assert_eq!(normalize_value("Amount Due", "EUR 1 250"), amount("1250", Some("EUR")));
+        assert_eq!(normalize_value("Tax", "(12.00)"), amount("-12.00", None));
+        assert_eq!(normalize_value("Balance", "CHF 1'000.05"), amount("1000.05", Some("CHF")));
+        assert_eq!(normalize_value("Balance", "1,250"), amount("1250", None));
+        // Unhinted values are read only with a currency
+        assert_eq!(normalize_value("Reference", "12.50"), None);
+        assert_eq!(normalize_value("Reference", "12.50 GBP"), amount("12.50", Some("GBP")));
+        assert_eq!(normalize_value("Total", "see attached"), None);
+        assert_eq!(
+            normalize_field("SubTotal", &DocumentField::Number(99.5)),
+            amount("99.5", None)
+        );
+    }
+
+    #[test]
+    fn test_countries() {
+        let country = |code: &str| Some(NormalizedValue::CountryCode { value: code.to_string() });
+        assert_eq!(normalize_value("CountryRegion", "United States of America"), country("US"));
+        assert_eq!(normalize_value("Country", "deu"), country("DE"));
+        assert_eq!(normalize_value("Country", "fr"), country("FR"));
+        assert_eq!(normalize_value("Country", "U.K."), country("GB"));
+        assert_eq!(normalize_value("Country", "Atlantis"), None);
+    }
+
+    #[test]
+    fn test_normalized_fields() {
+        let fields = vec![
+            FieldMatch::KeyValuePair {
+                key: "Invoice Date:".to_string(),
+                value: "Jan 5, 2024".to_string(),
+                confidence: 0.9,
+            },
+            FieldMatch::DocumentField {
+                doc_type: "invoice".to_string(),
+                name: "VendorName".to_string(),
+                value: DocumentField::String("Contoso".to_string()),
+                confidence: 0.9,
+            },
+            FieldMatch::DocumentField {
+                doc_type: "invoice".to_string(),
+                name: "InvoiceTotal".to_string(),
+                value: DocumentField::String("$110.00".to_string()),
+                confidence: 0.9,
+            },
+        ];
+        let normalized = normalized_fields(&fields);
+        assert_eq!(normalized.len(), 2);
+        assert_eq!(normalized["Invoice Date"], date(2024, 1, 5).unwrap());
+        assert_eq!(normalized["InvoiceTotal"], amount("110.00", Some("USD")).unwrap());
+    }
+}
@@ -0,0 +1,299 @@
+/// hOCR and ALTO XML rendering of OCR results
+///
+/// Converts pages, lines and words with their polygons and confidences into
+/// the two XML formats archival and digitization systems commonly ingest.
+/// Words are attached to the line whose span contains them.
+
+use std::fmt::Write;
+
+use super::geometry::Polygon;
+use super::models::{AnalysisResult, DocumentLine, DocumentPage, DocumentWord, Point};
+
+/// hOCR coordinates per inch for inch-based (PDF) pages, i.e. PDF points
+const HOCR_UNITS_PER_INCH: f32 = 72.0;
+
+/// ALTO `inch1200` measurement unit
+const ALTO_UNITS_PER_INCH: f32 = 1200.0;
+
+/// Render a result as an hOCR (XHTML) document
+pub fn render_hocr(result: &AnalysisResult) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <!DOCTYPE html PUBLIC \"-//W3C//DTD XHTML 1.0 Transitional//EN\" \
+         \"http://www.w3.org/TR/xhtml1/DTD/xhtml1-transitional.dtd\">\n\
+         <html xmlns=\"http://www.w3.org/1999/xhtml\" xml:lang=\"en\" lang=\"en\">\n\
+         <head>\n  <title></title>\n  \
+         <meta http-equiv=\"Content-Type\" content=\"text/html;charset=utf-8\"/>\n",
+    );
+    let _ = write!(
+        out,
+        "  <meta name=\"ocr-system\" content=\"Azure AI Document Intelligence {}\"/>\n  \
+         <meta name=\"ocr-capabilities\" content=\"ocr_page ocr_line ocrx_word\"/>\n\
+         </head>\n<body>\n",
+        escape(&result.model_id)
+    );
+
+    for page in &result.pages {
+        let scale = scale(page, HOCR_UNITS_PER_INCH);
+        let n = page.page_number;
+        let _ = writeln!(
+            out,
+            "  <div class=\"ocr_page\" id=\"page_{}\" title=\"bbox 0 0 {} {}; ppageno {}\">",
+            n,
+            (page.width * scale).round() as i64,
+            (page.height * scale).round() as i64,
+            n - 1
+        );
+        for (l, (line, words)) in page_lines(page).into_iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "    <span class=\"ocr_line\" id=\"line_{}_{}\" title=\"bbox {}\">",
+                n,
+                l + 1,
+                hocr_bbox(&line.polygon, scale)
+            );
+            if words.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "      <span class=\"ocrx_word\" id=\"word_{}_{}_1\" title=\"bbox {}\">{}</span>",
+                    n,
+                    l + 1,
+                    hocr_bbox(&line.polygon, scale),
+                    escape(&line.content)
+                );
+            }
+            for (w, word) in words.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "      <span class=\"ocrx_word\" id=\"word_{}_{}_{}\" title=\"bbox {}; x_wconf {}\">{}</span>",
+                    n,
+                    l + 1,
+                    w + 1,
+                    hocr_bbox(&word.polygon, scale),
+                    (word.confidence * 100.0).round() as i64,
+                    escape(&word.content)
+                );
+            }
+            out.push_str("    </span>\n");
+        }
+        out.push_str("  </div>\n");
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+/// Render a result as an ALTO v4 document
+///
+/// ALTO has one measurement unit per file, taken from the first page.
+pub fn render_alto(result: &AnalysisResult) -> String {
+    let inches = result.pages.first().map_or("inch", |page| page.unit.as_str()) == "inch";
+    let unit = if inches { "inch1200" } else { "pixel" };
+
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+         <alto xmlns=\"http://www.loc.gov/standards/alto/ns-v4#\" \
+         xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" \
+         xsi:schemaLocation=\"http://www.loc.gov/standards/alto/ns-v4# \
+         http://www.loc.gov/standards/alto/v4/alto-4-2.xsd\">\n",
+    );
+    let _ = write!(
+        out,
+        "  <Description>\n    <MeasurementUnit>{}</MeasurementUnit>\n    \
+         <OCRProcessing ID=\"OCR_0\">\n      <ocrProcessingStep>\n        <processingSoftware>\n          \
+         <softwareName>Azure AI Document Intelligence {}</softwareName>\n        \
+         </processingSoftware>\n      </ocrProcessingStep>\n    </OCRProcessing>\n  \
+         </Description>\n  <Layout>\n",
+        unit,
+        escape(&result.model_id)
+    );
+
+    for page in &result.pages {
+        let scale = if inches { ALTO_UNITS_PER_INCH } else { 1.0 };
+        let n = page.page_number;
+        let (width, height) = ((page.width * scale).round() as i64, (page.height * scale).round() as i64);
+        let _ = write!(
+            out,
+            "    <Page ID=\"page_{n}\" PHYSICAL_IMG_NR=\"{n}\" WIDTH=\"{w}\" HEIGHT=\"{h}\">\n      \
+             <PrintSpace HPOS=\"0\" VPOS=\"0\" WIDTH=\"{w}\" HEIGHT=\"{h}\">\n        \
+             <TextBlock ID=\"block_{n}\">\n",
+            n = n,
+            w = width,
+            h = height
+        );
+        for (l, (line, words)) in page_lines(page).into_iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "          <TextLine ID=\"line_{}_{}\" {}>",
+                n,
+                l + 1,
+                alto_box(&line.polygon, scale)
+            );
+            if words.is_empty() {
+                let _ = writeln!(
+                    out,
+                    "            <String ID=\"string_{}_{}_1\" CONTENT=\"{}\" {}/>",
+                    n,
+                    l + 1,
+                    escape(&line.content),
+                    alto_box(&line.polygon, scale)
+                );
+            }
+            for (w, word) in words.iter().enumerate() {
+                if w > 0 {
+                    out.push_str("            <SP/>\n");
+                }
+                let _ = writeln!(
+                    out,
+                    "            <String ID=\"string_{}_{}_{}\" CONTENT=\"{}\" {} WC=\"{:.2}\"/>",
+                    n,
+                    l + 1,
+                    w + 1,
+                    escape(&word.content),
+                    alto_box(&word.polygon, scale),
+                    word.confidence.clamp(0.0, 1.0)
+                );
+            }
+            out.push_str("          </TextLine>\n");
+        }
+        out.push_str("        </TextBlock>\n      </PrintSpace>\n    </Page>\n");
+    }
+
+    out.push_str("  </Layout>\n</alto>\n");
+    out
+}
+
+/// Lines of a page with the words whose span falls inside each line
+fn page_lines(page: &DocumentPage) -> Vec<(&DocumentLine, Vec<&DocumentWord>)> {
+    page.lines
+        .iter()
+        .map(|line| {
+            let words = page
+                .words
+                .iter()
+                .filter(|word| {
+                    line.spans.iter().any(|span| {
+                        word.span.offset >= span.offset && word.span.offset < span.offset + span.length
+                    })
+                })
+                .collect();
+            (line, words)
+        })
+        .collect()
+}
+
+/// Output units per page unit
+fn scale(page: &DocumentPage, per_inch: f32) -> f32 {
+    if page.unit == "inch" {
+        per_inch
+    } else {
+        1.0
+    }
+}
+
+/// Axis-aligned bounds of a polygon as `(left, top, right, bottom)` in output units
+fn bounds(polygon: &[Point], scale: f32) -> (i64, i64, i64, i64) {
+    let Some(bounds) = polygon.bounding_box() else {
+        return (0, 0, 0, 0);
+    };
+    let round = |value: f32| (value * scale).round() as i64;
+    (round(bounds.left), round(bounds.top), round(bounds.right), round(bounds.bottom))
+}
+
+fn hocr_bbox(polygon: &[Point], scale: f32) -> String {
+    let (left, top, right, bottom) = bounds(polygon, scale);
+    format!("{} {} {} {}", left, top, right, bottom)
+}
+
+fn alto_box(polygon: &[Point], scale: f32) -> String {
+    let (left, top, right, bottom) = bounds(polygon, scale);
+    format!(
+        "HPOS=\"{}\" VPOS=\"{}\" WIDTH=\"{}\" HEIGHT=\"{}\"",
+        left,
+        top,
+        right - left,
+        bottom - top
+    )
+}
+
+pub(super) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Span;
+
+    fn rect(left: f32, top: f32, right: f32, bottom: f32) -> Vec<Point> {
+        vec![
+            Point { x: left, y: top },
+            Point { x: right, y: top },
+            Point { x: right, y: bottom },
+            Point { x: left, y: bottom },
+        ]
+    }
+
+    fn word(content: &str, offset: i32, left: f32, confidence: f32) -> DocumentWord {
+        DocumentWord {
+            content: content.to_string(),
+            polygon: rect(left, 1.0, left + 0.5, 1.25),
+            confidence,
+            span: Span {
+                offset,
+                length: content.len() as i32,
+            },
+        }
+    }
+
+    fn result() -> AnalysisResult {
+        AnalysisResult {
+            model_id: "prebuilt-read".to_string(),
+            content: "Fish & <Chips>".to_string(),
+            pages: vec![DocumentPage {
+                page_number: 1,
+                angle: 0.0,
+                width: 8.5,
+                height: 11.0,
+                unit: "inch".to_string(),
+                words: vec![word("Fish", 0, 1.0, 0.99), word("&", 5, 1.5, 0.5), word("<Chips>", 7, 2.0, 0.875)],
+                lines: vec![DocumentLine {
+                    content: "Fish & <Chips>".to_string(),
+                    polygon: rect(1.0, 1.0, 2.5, 1.25),
+                    spans: vec![Span { offset: 0, length: 14 }],
+                }],
+                selection_marks: Vec::new(),
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_render_hocr() {
+        let hocr = render_hocr(&result());
+        assert!(hocr.contains("<div class=\"ocr_page\" id=\"page_1\" title=\"bbox 0 0 612 792; ppageno 0\">"));
+        assert!(hocr.contains("<span class=\"ocr_line\" id=\"line_1_1\" title=\"bbox 72 72 180 90\">"));
+        assert!(hocr.contains(
+            "<span class=\"ocrx_word\" id=\"word_1_1_1\" title=\"bbox 72 72 108 90; x_wconf 99\">Fish</span>"
+        ));
+        assert!(hocr.contains("title=\"bbox 144 72 180 90; x_wconf 88\">&lt;Chips&gt;</span>"));
+        assert!(hocr.contains(">&amp;</span>"));
+    }
+
+    #[test]
+    fn test_render_alto() {
+        let alto = render_alto(&result());
+        assert!(alto.contains("<MeasurementUnit>inch1200</MeasurementUnit>"));
+        assert!(alto.contains("<Page ID=\"page_1\" PHYSICAL_IMG_NR=\"1\" WIDTH=\"10200\" HEIGHT=\"13200\">"));
+        assert!(alto.contains("<TextLine ID=\"line_1_1\" HPOS=\"1200\" VPOS=\"1200\" WIDTH=\"1800\" HEIGHT=\"300\">"));
+        assert!(alto.contains(
+            "<String ID=\"string_1_1_1\" CONTENT=\"Fish\" HPOS=\"1200\" VPOS=\"1200\" WIDTH=\"600\" HEIGHT=\"300\" WC=\"0.99\"/>"
+        ));
+        assert!(alto.contains("CONTENT=\"&lt;Chips&gt;\""));
+        assert_eq!(alto.matches("<SP/>").count(), 2);
+    }
+}
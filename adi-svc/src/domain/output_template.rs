@@ -0,0 +1,320 @@
+/// Output mapping templates
+///
+/// A template turns a result into the schema an integration expects, so the
+/// glue lives in configuration instead of in every consumer. JSONPath
+/// templates are a JSON document whose `$` strings are replaced by what the
+/// path selects from the result; Handlebars templates render text such as XML
+/// or CSV. Both see the result as the API serializes it, with the operation
+/// id added as `operation_id`. Rendering needs the `templates` feature;
+/// without it every template is rejected when it is loaded.
+
+#[cfg(feature = "templates")]
+use handlebars::Handlebars;
+use serde::Deserialize;
+use serde_json::Value;
+#[cfg(feature = "templates")]
+use serde_json_path::JsonPath;
+use std::collections::BTreeMap;
+
+use super::errors::{DomainError, DomainResult};
+use super::models::AnalysisResult;
+use super::value_objects::ModelType;
+
+/// A named template and the results it applies to
+#[derive(Debug, Clone)]
+pub struct OutputTemplate {
+    pub name: String,
+    /// Model ids the template applies to; any model when empty
+    pub models: Vec<String>,
+    /// Document types one of the result's documents must have; any when empty
+    pub doc_types: Vec<String>,
+    body: TemplateBody,
+}
+
+#[derive(Debug, Clone)]
+enum TemplateBody {
+    #[cfg(feature = "templates")]
+    JsonPath(Mapping),
+    #[cfg(feature = "templates")]
+    Handlebars { registry: Box<Handlebars<'static>>, content_type: String },
+}
+
+/// Part of a JSONPath template
+#[cfg(feature = "templates")]
+#[derive(Debug, Clone)]
+enum Mapping {
+    /// The single match, or null without one; an array of every match when several match
+    Path(JsonPath),
+    /// Every match as an array, written as a path alone in an array
+    All(JsonPath),
+    Object(Vec<(String, Mapping)>),
+    Array(Vec<Mapping>),
+    Literal(Value),
+}
+
+/// A rendered template and the media type it is served as
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RenderedOutput {
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+/// A template as written in the templates file
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TemplateDefinition {
+    #[serde(default)]
+    models: Vec<String>,
+    #[serde(default)]
+    doc_types: Vec<String>,
+    json_path: Option<Value>,
+    handlebars: Option<String>,
+    content_type: Option<String>,
+}
+
+/// Parse a JSON object of templates by name
+pub fn parse_output_templates(json: &str) -> DomainResult<Vec<OutputTemplate>> {
+    let definitions: BTreeMap<String, TemplateDefinition> = serde_json::from_str(json)
+        .map_err(|e| DomainError::ValidationError(format!("invalid output templates: {}", e)))?;
+    definitions
+        .into_iter()
+        .map(|(name, definition)| OutputTemplate::new(name, definition))
+        .collect()
+}
+
+impl OutputTemplate {
+    fn new(name: String, definition: TemplateDefinition) -> DomainResult<Self> {
+        let invalid = |reason: String| DomainError::ValidationError(format!("output template '{}': {}", name, reason));
+        let valid_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(invalid("names use letters, digits, '-' and '_'".to_string()));
+        }
+
+        let body = TemplateBody::compile(&name, definition.json_path, definition.handlebars, definition.content_type)
+            .map_err(invalid)?;
+
+        // Prebuilt models may be named as in the API routes
+        let models = definition
+            .models
+            .into_iter()
+            .map(|model| match ModelType::from_string(&model) {
+                Ok(model_type) if model_type != ModelType::Custom => model_type.as_str().to_string(),
+                _ => model,
+            })
+            .collect();
+        Ok(Self { name, models, doc_types: definition.doc_types, body })
+    }
+
+    pub fn applies_to(&self, result: &AnalysisResult) -> bool {
+        (self.models.is_empty() || self.models.contains(&result.model_id))
+            && (self.doc_types.is_empty()
+                || result.documents.iter().any(|document| self.doc_types.contains(&document.doc_type)))
+    }
+
+    /// Render `result` of `operation_id`, failing when the template does not apply to it
+    pub fn render(&self, operation_id: &str, result: &AnalysisResult) -> DomainResult<RenderedOutput> {
+        let failed = |reason: String| DomainError::ValidationError(format!("output template '{}': {}", self.name, reason));
+        if !self.applies_to(result) {
+            return Err(failed(format!("does not apply to results of {}", result.model_id)));
+        }
+        let mut input = serde_json::to_value(result).map_err(|e| failed(e.to_string()))?;
+        if let Value::Object(fields) = &mut input {
+            fields.insert("operation_id".to_string(), Value::String(operation_id.to_string()));
+        }
+
+        self.body.render(&self.name, &input).map_err(failed)
+    }
+}
+
+impl TemplateBody {
+    #[cfg(feature = "templates")]
+    fn compile(
+        name: &str,
+        json_path: Option<Value>,
+        handlebars: Option<String>,
+        content_type: Option<String>,
+    ) -> Result<Self, String> {
+        match (json_path, handlebars) {
+            (Some(mapping), None) => {
+                if content_type.is_some() {
+                    return Err("JSONPath templates are always JSON, content_type is for Handlebars".to_string());
+                }
+                Ok(Self::JsonPath(Mapping::compile(mapping)?))
+            }
+            (None, Some(template)) => {
+                let content_type = content_type.unwrap_or_else(|| "text/plain; charset=utf-8".to_string());
+                let mut registry = Handlebars::new();
+                registry.set_strict_mode(true);
+                // Only markup needs escaping; CSV or JSON would be mangled by it
+                if !(content_type.contains("xml") || content_type.contains("html")) {
+                    registry.register_escape_fn(handlebars::no_escape);
+                }
+                registry.register_template_string(name, template).map_err(|e| e.to_string())?;
+                Ok(Self::Handlebars { registry: Box::new(registry), content_type })
+            }
+            _ => Err("needs exactly one of json_path and handlebars".to_string()),
+        }
+    }
+
+    #[cfg(not(feature = "templates"))]
+    fn compile(_: &str, _: Option<Value>, _: Option<String>, _: Option<String>) -> Result<Self, String> {
+        Err("this build lacks the `templates` feature".to_string())
+    }
+
+    #[cfg(feature = "templates")]
+    fn render(&self, name: &str, input: &Value) -> Result<RenderedOutput, String> {
+        match self {
+            Self::JsonPath(mapping) => Ok(RenderedOutput {
+                content_type: "application/json".to_string(),
+                body: serde_json::to_vec(&mapping.apply(input)).map_err(|e| e.to_string())?,
+            }),
+            Self::Handlebars { registry, content_type } => Ok(RenderedOutput {
+                content_type: content_type.clone(),
+                body: registry.render(name, input).map_err(|e| e.to_string())?.into_bytes(),
+            }),
+        }
+    }
+
+    #[cfg(not(feature = "templates"))]
+    fn render(&self, _: &str, _: &Value) -> Result<RenderedOutput, String> {
+        match *self {}
+    }
+}
+
+#[cfg(feature = "templates")]
+impl Mapping {
+    fn compile(value: Value) -> Result<Self, String> {
+        let path = |path: &str| JsonPath::parse(path).map_err(|e| format!("invalid JSONPath '{}': {}", path, e));
+        Ok(match value {
+            Value::String(text) if text.starts_with('$') => Self::Path(path(&text)?),
+            Value::Array(items) => match items.as_slice() {
+                [Value::String(text)] if text.starts_with('$') => Self::All(path(text)?),
+                _ => Self::Array(items.into_iter().map(Self::compile).collect::<Result<_, _>>()?),
+            },
+            Value::Object(fields) => Self::Object(
+                fields
+                    .into_iter()
+                    .map(|(name, value)| Ok((name, Self::compile(value)?)))
+                    .collect::<Result<_, String>>()?,
+            ),
+            literal => Self::Literal(literal),
+        })
+    }
+
+    fn apply(&self, input: &Value) -> Value {
+        match self {
+            Self::Path(path) => match path.query(input).all().as_slice() {
+                [] => Value::Null,
+                [single] => (*single).clone(),
+                many => Value::Array(many.iter().map(|value| (*value).clone()).collect()),
+            },
+            Self::All(path) => Value::Array(path.query(input).all().into_iter().cloned().collect()),
+            Self::Object(fields) => Value::Object(
+                fields.iter().map(|(name, mapping)| (name.clone(), mapping.apply(input))).collect(),
+            ),
+            Self::Array(items) => Value::Array(items.iter().map(|mapping| mapping.apply(input)).collect()),
+            Self::Literal(value) => value.clone(),
+        }
+    }
+}
+
+#[cfg(all(test, feature = "templates"))]
+mod tests {
+    use super::*;
+    use crate::domain::{DocumentField, ExtractedDocument, KeyValuePair};
+    use serde_json::json;
+    use std::collections::HashMap;
+
+    fn invoice() -> AnalysisResult {
+        AnalysisResult {
+            model_id: "prebuilt-invoice".to_string(),
+            key_value_pairs: vec![
+                KeyValuePair { key: "PO".to_string(), value: "77".to_string(), confidence: 0.9 },
+                KeyValuePair { key: "Terms".to_string(), value: "Net 30".to_string(), confidence: 0.8 },
+            ],
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                fields: HashMap::from([
+                    ("VendorName".to_string(), DocumentField::String("Contoso & Co".to_string())),
+                    ("InvoiceTotal".to_string(), DocumentField::Number(110.0)),
+                ]),
+                confidence: 0.95,
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn template(definition: Value) -> DomainResult<OutputTemplate> {
+        let templates = parse_output_templates(&json!({ "erp-v1": definition }).to_string())?;
+        Ok(templates.into_iter().next().unwrap())
+    }
+
+    #[test]
+    fn test_json_path_template() {
+        let template = template(json!({
+            "models": ["invoice"],
+            "json_path": {
+                "id": "$.operation_id",
+                "supplier": { "name": "$.documents[0].fields.VendorName.value", "country": "US" },
+                "total": "$.documents[0].fields.InvoiceTotal.value",
+                "keys": "$.key_value_pairs[*].key",
+                "terms": ["$.key_value_pairs[?@.key == 'Terms'].value"],
+                "missing": "$.documents[0].fields.DueDate.value"
+            }
+        }))
+        .unwrap();
+        assert_eq!(template.models, ["prebuilt-invoice"]);
+
+        let rendered = template.render("op-1", &invoice()).unwrap();
+        assert_eq!(rendered.content_type, "application/json");
+        let output: Value = serde_json::from_slice(&rendered.body).unwrap();
+        assert_eq!(
+            output,
+            json!({
+                "id": "op-1",
+                "supplier": { "name": "Contoso & Co", "country": "US" },
+                "total": 110.0,
+                "keys": ["PO", "Terms"],
+                "terms": ["Net 30"],
+                "missing": null
+            })
+        );
+
+        let read = AnalysisResult { model_id: "prebuilt-read".to_string(), ..Default::default() };
+        assert!(!template.applies_to(&read));
+        assert!(template.render("op-2", &read).is_err());
+    }
+
+    #[test]
+    fn test_handlebars_template() {
+        let xml = template(json!({
+            "doc_types": ["invoice"],
+            "handlebars": "<Invoice id=\"{{operation_id}}\">{{documents.0.fields.VendorName.value}}</Invoice>",
+            "content_type": "application/xml"
+        }))
+        .unwrap();
+        let rendered = xml.render("op-1", &invoice()).unwrap();
+        assert_eq!(rendered.content_type, "application/xml");
+        assert_eq!(String::from_utf8(rendered.body).unwrap(), "<Invoice id=\"op-1\">Contoso &amp; Co</Invoice>");
+
+        let csv = template(json!({ "handlebars": "{{#each key_value_pairs}}{{key}},{{value}}\n{{/each}}" })).unwrap();
+        let rendered = csv.render("op-1", &invoice()).unwrap();
+        assert_eq!(rendered.content_type, "text/plain; charset=utf-8");
+        assert_eq!(String::from_utf8(rendered.body).unwrap(), "PO,77\nTerms,Net 30\n");
+
+        // Strict mode turns a misspelled field into an error rather than a blank
+        let typo = template(json!({ "handlebars": "{{documents.0.fields.VendorNam.value}}" })).unwrap();
+        assert!(typo.render("op-1", &invoice()).is_err());
+    }
+
+    #[test]
+    fn test_invalid_templates() {
+        assert!(template(json!({})).is_err());
+        assert!(template(json!({ "json_path": {}, "handlebars": "" })).is_err());
+        assert!(template(json!({ "json_path": { "a": "$[" } })).is_err());
+        assert!(template(json!({ "handlebars": "{{#each}}" })).is_err());
+        assert!(template(json!({ "json_path": {}, "content_type": "text/csv" })).is_err());
+        assert!(template(json!({ "json_path": {}, "model": "typo" })).is_err());
+        assert!(parse_output_templates(r#"{ "bad name": { "json_path": {} } }"#).is_err());
+    }
+}
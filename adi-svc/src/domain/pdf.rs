@@ -0,0 +1,216 @@
+/// PDF pre-validation
+///
+/// A lightweight structural check run before a PDF is sent for analysis:
+/// the header, trailer and cross-reference pointer must be intact, the file
+/// must not be encrypted, and the page count is read from the page tree.
+
+use super::errors::{DomainError, DomainResult};
+
+/// How far from the end of the file the trailer keywords are searched for
+const TAIL_BYTES: usize = 2048;
+
+/// Structural facts about a PDF
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PdfInspection {
+    /// Version from the `%PDF-x.y` header
+    pub version: String,
+    /// Pages in the document, when the page tree is readable without
+    /// decompressing object streams
+    pub page_count: Option<u32>,
+}
+
+impl PdfInspection {
+    /// Validate a PDF's structure, rejecting corrupt and encrypted files
+    pub fn inspect(bytes: &[u8]) -> DomainResult<Self> {
+        let version = bytes
+            .strip_prefix(b"%PDF-")
+            .map(|rest| {
+                rest.iter()
+                    .take_while(|b| b.is_ascii_digit() || **b == b'.')
+                    .map(|b| *b as char)
+                    .collect::<String>()
+            })
+            .filter(|version| !version.is_empty())
+            .ok_or_else(|| corrupt("missing %PDF- header"))?;
+
+        let tail_start = bytes.len().saturating_sub(TAIL_BYTES);
+        let tail = &bytes[tail_start..];
+        if find(tail, b"%%EOF").is_none() {
+            return Err(corrupt("missing %%EOF marker; the file may be truncated"));
+        }
+
+        let startxref = rfind(tail, b"startxref")
+            .map(|pos| tail_start + pos)
+            .ok_or_else(|| corrupt("missing startxref"))?;
+        let xref_offset = parse_uint(&bytes[startxref + b"startxref".len()..])
+            .map(|offset| offset as usize)
+            .filter(|offset| *offset < startxref)
+            .ok_or_else(|| corrupt("startxref does not point into the file"))?;
+
+        let trailer = trailer_dictionary(bytes, xref_offset, startxref)?;
+        if find(trailer, b"/Encrypt").is_some() {
+            return Err(DomainError::EncryptedDocument);
+        }
+
+        Ok(Self {
+            version,
+            page_count: page_count(bytes),
+        })
+    }
+}
+
+fn corrupt(reason: &str) -> DomainError {
+    DomainError::InvalidDocumentFormat(format!("PDF is corrupt: {}", reason))
+}
+
+/// Bytes holding the trailer dictionary: after a classic `xref` table, or the
+/// dictionary of a cross-reference stream object
+fn trailer_dictionary(bytes: &[u8], xref_offset: usize, startxref: usize) -> DomainResult<&[u8]> {
+    let section = &bytes[xref_offset..startxref];
+    let section = trim_start(section);
+
+    if section.starts_with(b"xref") {
+        let trailer = find(section, b"trailer").ok_or_else(|| corrupt("xref table has no trailer"))?;
+        return Ok(&section[trailer..]);
+    }
+
+    // Cross-reference stream: `N G obj << ... >> stream`
+    let header_end = find(section, b"obj").ok_or_else(|| corrupt("startxref does not point at a cross-reference section"))?;
+    let header = &section[..header_end];
+    let is_object_header = header
+        .split(|b| b.is_ascii_whitespace())
+        .filter(|token| !token.is_empty())
+        .all(|token| token.iter().all(u8::is_ascii_digit));
+    if !is_object_header {
+        return Err(corrupt("startxref does not point at a cross-reference section"));
+    }
+    let dictionary_end = find(section, b"stream").unwrap_or(section.len());
+    Ok(&section[header_end..dictionary_end])
+}
+
+/// Page count from the root of the page tree, falling back to counting page objects
+fn page_count(bytes: &[u8]) -> Option<u32> {
+    let mut root_count = None;
+    let mut pages = 0u32;
+
+    for type_pos in find_all(bytes, b"/Type") {
+        let value = trim_start(&bytes[type_pos + b"/Type".len()..]);
+        if value.starts_with(b"/Pages") {
+            // The root /Pages node holds the largest /Count
+            let object = enclosing_object(bytes, type_pos);
+            if let Some(count) = find(object, b"/Count").and_then(|pos| parse_uint(&object[pos + b"/Count".len()..])) {
+                root_count = root_count.max(Some(count as u32));
+            }
+        } else if value.starts_with(b"/Page")
+            && !value.get(b"/Page".len()).is_some_and(|b| b.is_ascii_alphanumeric())
+        {
+            pages += 1;
+        }
+    }
+
+    root_count.or(Some(pages).filter(|pages| *pages > 0))
+}
+
+/// The `obj ... endobj` span around `pos`
+fn enclosing_object(bytes: &[u8], pos: usize) -> &[u8] {
+    let start = rfind(&bytes[..pos], b"obj").unwrap_or(0);
+    let end = find(&bytes[pos..], b"endobj").map(|end| pos + end).unwrap_or(bytes.len());
+    &bytes[start..end]
+}
+
+fn parse_uint(bytes: &[u8]) -> Option<u64> {
+    let digits: String = trim_start(bytes)
+        .iter()
+        .take_while(|b| b.is_ascii_digit())
+        .map(|b| *b as char)
+        .collect();
+    digits.parse().ok()
+}
+
+fn trim_start(bytes: &[u8]) -> &[u8] {
+    let start = bytes.iter().position(|b| !b.is_ascii_whitespace()).unwrap_or(bytes.len());
+    &bytes[start..]
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|window| window == needle)
+}
+
+fn find_all<'a>(haystack: &'a [u8], needle: &'a [u8]) -> impl Iterator<Item = usize> + 'a {
+    haystack
+        .windows(needle.len())
+        .enumerate()
+        .filter(move |(_, window)| *window == needle)
+        .map(|(pos, _)| pos)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Minimal well-formed PDF with `pages` pages and an optional extra trailer entry
+    fn build_pdf(pages: u32, trailer_extra: &str) -> Vec<u8> {
+        let kids: Vec<String> = (0..pages).map(|i| format!("{} 0 R", i + 3)).collect();
+        let mut pdf = format!(
+            "%PDF-1.7\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n\
+             2 0 obj\n<< /Type /Pages /Kids [{}] /Count {} >>\nendobj\n",
+            kids.join(" "),
+            pages
+        );
+        for i in 0..pages {
+            pdf.push_str(&format!("{} 0 obj\n<< /Type /Page /Parent 2 0 R >>\nendobj\n", i + 3));
+        }
+        let xref = pdf.len();
+        pdf.push_str(&format!(
+            "xref\n0 {}\ntrailer\n<< /Size {} /Root 1 0 R{} >>\nstartxref\n{}\n%%EOF\n",
+            pages + 3,
+            pages + 3,
+            trailer_extra,
+            xref
+        ));
+        pdf.into_bytes()
+    }
+
+    #[test]
+    fn test_inspect_valid_pdf() {
+        let inspection = PdfInspection::inspect(&build_pdf(3, "")).unwrap();
+        assert_eq!(inspection.version, "1.7");
+        assert_eq!(inspection.page_count, Some(3));
+    }
+
+    #[test]
+    fn test_inspect_rejects_encrypted_pdf() {
+        let pdf = build_pdf(1, " /Encrypt 9 0 R");
+        assert!(matches!(PdfInspection::inspect(&pdf), Err(DomainError::EncryptedDocument)));
+    }
+
+    #[test]
+    fn test_inspect_rejects_corrupt_pdf() {
+        let pdf = build_pdf(2, "");
+
+        // Truncated download
+        let truncated = &pdf[..pdf.len() / 2];
+        assert!(matches!(
+            PdfInspection::inspect(truncated),
+            Err(DomainError::InvalidDocumentFormat(_))
+        ));
+
+        // startxref pointing past the cross-reference table
+        let text = String::from_utf8(pdf.clone()).unwrap();
+        let broken = text.replace(&format!("startxref\n{}", text.find("xref\n0").unwrap()), "startxref\n99999");
+        assert!(PdfInspection::inspect(broken.as_bytes()).is_err());
+
+        assert!(PdfInspection::inspect(b"%PDF-1.4 test").is_err());
+    }
+
+    #[test]
+    fn test_page_count_falls_back_to_page_objects() {
+        let pdf = b"1 0 obj << /Type /Page >> endobj 2 0 obj << /Type/Page >> endobj";
+        assert_eq!(page_count(pdf), Some(2));
+        assert_eq!(page_count(b"no pages here"), None);
+    }
+}
@@ -0,0 +1,411 @@
+/// Declarative processing pipelines
+///
+/// A pipeline is a named list of steps run in order for each document:
+/// classify it, analyze it, check the result and announce it by event or
+/// webhook. Every run records the status of each step, so callers can see how
+/// far a document got and why it stopped.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+use super::errors::{DomainError, DomainResult};
+use super::models::AnalysisResult;
+use super::routing::RouteTarget;
+use super::value_objects::TenantId;
+
+/// One step of a pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "step", rename_all = "snake_case")]
+pub enum PipelineStep {
+    /// Choose the model by classification, as `analyze/auto` does
+    Classify,
+    /// Analyze with `model`, a prebuilt name or `custom:<id>`, or else the
+    /// model classification chose, and wait for the result
+    Analyze {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        model: Option<String>,
+    },
+    /// Fail the run unless the result has `required_fields` and every
+    /// extracted document is at least `min_confidence` sure
+    Validate {
+        #[serde(default)]
+        required_fields: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        min_confidence: Option<f32>,
+    },
+    /// Publish a `processed` lifecycle event
+    PublishEvent,
+    /// POST the run and a result summary to `url`
+    Webhook { url: String },
+}
+
+impl PipelineStep {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Classify => "classify",
+            Self::Analyze { .. } => "analyze",
+            Self::Validate { .. } => "validate",
+            Self::PublishEvent => "publish_event",
+            Self::Webhook { .. } => "webhook",
+        }
+    }
+
+    /// Model an analyze step names, if it names one
+    pub fn model(&self) -> DomainResult<Option<RouteTarget>> {
+        match self {
+            Self::Analyze { model: Some(model) } => model.parse().map(Some),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// A named pipeline from configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineDefinition {
+    pub name: String,
+    pub steps: Vec<PipelineStep>,
+}
+
+impl PipelineDefinition {
+    /// Check the steps can run in order
+    ///
+    /// A pipeline analyzes exactly once; classification comes before the
+    /// analysis and every other step after it, when there is a result to use.
+    pub fn validate(&self) -> DomainResult<()> {
+        let invalid = |reason: String| DomainError::ValidationError(format!("pipeline '{}': {}", self.name, reason));
+        let valid_name = !self.name.is_empty()
+            && self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_name {
+            return Err(invalid("names may only use letters, digits, '-' and '_'".to_string()));
+        }
+
+        let analyze_steps: Vec<usize> = self
+            .steps
+            .iter()
+            .enumerate()
+            .filter(|(_, step)| matches!(step, PipelineStep::Analyze { .. }))
+            .map(|(index, _)| index)
+            .collect();
+        let analyze_at = match analyze_steps[..] {
+            [index] => index,
+            _ => return Err(invalid("needs exactly one analyze step".to_string())),
+        };
+        let named_model = self.steps[analyze_at].model()?;
+
+        for (index, step) in self.steps.iter().enumerate() {
+            match step {
+                PipelineStep::Classify if index > analyze_at => {
+                    return Err(invalid("classify must come before analyze".to_string()));
+                }
+                PipelineStep::Classify if named_model.is_some() => {
+                    return Err(invalid("classify has no effect when analyze names a model".to_string()));
+                }
+                PipelineStep::Validate { .. } | PipelineStep::PublishEvent | PipelineStep::Webhook { .. }
+                    if index < analyze_at =>
+                {
+                    return Err(invalid(format!("{} must come after analyze", step.as_str())));
+                }
+                PipelineStep::Validate { min_confidence: Some(confidence), .. }
+                    if !(0.0..=1.0).contains(confidence) =>
+                {
+                    return Err(invalid(format!("min_confidence must be between 0 and 1, got {}", confidence)));
+                }
+                PipelineStep::Webhook { url } if !url.starts_with("https://") && !url.starts_with("http://") => {
+                    return Err(invalid(format!("webhook URL must be http or https: {}", url)));
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parse and validate pipelines given as a JSON object of name to steps
+pub fn parse_pipelines(json: &str) -> DomainResult<Vec<PipelineDefinition>> {
+    let pipelines: BTreeMap<String, Vec<PipelineStep>> = serde_json::from_str(json)
+        .map_err(|e| DomainError::ValidationError(format!("invalid pipeline definitions: {}", e)))?;
+    pipelines
+        .into_iter()
+        .map(|(name, steps)| {
+            let definition = PipelineDefinition { name, steps };
+            definition.validate()?;
+            Ok(definition)
+        })
+        .collect()
+}
+
+/// Problems with a result against required fields and a confidence floor; empty when it passes
+///
+/// A field is present when an extracted document has it or a key-value pair
+/// is keyed by it, ignoring case and a trailing colon.
+pub fn validate_result(result: &AnalysisResult, required_fields: &[String], min_confidence: Option<f32>) -> Vec<String> {
+    let mut problems: Vec<String> = required_fields
+        .iter()
+        .filter(|field| {
+            let in_documents = result.documents.iter().any(|document| document.fields.contains_key(field.as_str()));
+            let in_pairs = result.key_value_pairs.iter().any(|pair| {
+                !pair.value.trim().is_empty()
+                    && pair.key.trim().trim_end_matches(':').eq_ignore_ascii_case(field)
+            });
+            !in_documents && !in_pairs
+        })
+        .map(|field| format!("missing field {}", field))
+        .collect();
+    if let Some(min_confidence) = min_confidence {
+        problems.extend(
+            result
+                .documents
+                .iter()
+                .filter(|document| document.confidence < min_confidence)
+                .map(|document| {
+                    format!("{} confidence {:.2} is below {:.2}", document.doc_type, document.confidence, min_confidence)
+                }),
+        );
+    }
+    problems
+}
+
+/// Progress of a pipeline run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PipelineStatus {
+    Running,
+    Succeeded,
+    /// A step failed; the steps after it were skipped
+    Failed,
+}
+
+impl PipelineStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "running" => Some(Self::Running),
+            "succeeded" => Some(Self::Succeeded),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+}
+
+/// Progress of one step of a run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepStatus {
+    Pending,
+    Running,
+    Succeeded,
+    Failed,
+    /// Not run because an earlier step failed
+    Skipped,
+}
+
+/// A step of a run and how it went
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StepRun {
+    /// Step kind, e.g. `analyze`
+    pub step: String,
+    pub status: StepStatus,
+    /// What the step did, or why it failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<DateTime<Utc>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// One document's way through a pipeline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipelineRun {
+    pub run_id: String,
+    pub pipeline: String,
+    pub tenant_id: TenantId,
+    pub status: PipelineStatus,
+    /// Operation the analyze step started
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub operation_id: Option<String>,
+    pub steps: Vec<StepRun>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PipelineRun {
+    /// A run of `definition` with every step pending
+    pub fn new(definition: &PipelineDefinition, tenant_id: TenantId) -> Self {
+        let now = Utc::now();
+        Self {
+            run_id: Uuid::new_v4().to_string(),
+            pipeline: definition.name.clone(),
+            tenant_id,
+            status: PipelineStatus::Running,
+            operation_id: None,
+            steps: definition
+                .steps
+                .iter()
+                .map(|step| StepRun {
+                    step: step.as_str().to_string(),
+                    status: StepStatus::Pending,
+                    detail: None,
+                    started_at: None,
+                    finished_at: None,
+                })
+                .collect(),
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn start_step(&mut self, index: usize) {
+        let now = Utc::now();
+        let step = &mut self.steps[index];
+        step.status = StepStatus::Running;
+        step.started_at = Some(now);
+        self.updated_at = now;
+    }
+
+    /// Record a step's outcome; a failure fails the run and skips the steps after it
+    pub fn finish_step(&mut self, index: usize, outcome: Result<Option<String>, String>) {
+        let now = Utc::now();
+        let step = &mut self.steps[index];
+        step.finished_at = Some(now);
+        match outcome {
+            Ok(detail) => {
+                step.status = StepStatus::Succeeded;
+                step.detail = detail;
+                if index + 1 == self.steps.len() {
+                    self.status = PipelineStatus::Succeeded;
+                }
+            }
+            Err(reason) => {
+                step.status = StepStatus::Failed;
+                step.detail = Some(reason);
+                for later in &mut self.steps[index + 1..] {
+                    later.status = StepStatus::Skipped;
+                }
+                self.status = PipelineStatus::Failed;
+            }
+        }
+        self.updated_at = now;
+    }
+
+    /// Fail a run that stopped before it finished, at the step it had reached
+    pub fn interrupt(&mut self, reason: String) {
+        let reached = self
+            .steps
+            .iter()
+            .position(|step| matches!(step.status, StepStatus::Running | StepStatus::Pending));
+        if let Some(index) = reached {
+            self.finish_step(index, Err(reason));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{DocumentField, ExtractedDocument, KeyValuePair};
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_pipelines() {
+        let pipelines = parse_pipelines(
+            r#"{
+                "invoices": [
+                    {"step": "classify"},
+                    {"step": "analyze"},
+                    {"step": "validate", "required_fields": ["InvoiceTotal"], "min_confidence": 0.8},
+                    {"step": "publish_event"},
+                    {"step": "webhook", "url": "https://hooks.example.com/invoices"}
+                ],
+                "receipts": [{"step": "analyze", "model": "prebuilt-receipt"}]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(pipelines.len(), 2);
+        assert_eq!(pipelines[0].name, "invoices");
+        assert_eq!(pipelines[0].steps[4].as_str(), "webhook");
+        assert_eq!(
+            pipelines[1].steps[0].model().unwrap(),
+            Some(RouteTarget::Prebuilt(crate::domain::ModelType::Receipt))
+        );
+
+        for invalid in [
+            r#"{"empty": []}"#,
+            r#"{"twice": [{"step": "analyze"}, {"step": "analyze"}]}"#,
+            r#"{"late": [{"step": "analyze"}, {"step": "classify"}]}"#,
+            r#"{"early": [{"step": "publish_event"}, {"step": "analyze"}]}"#,
+            r#"{"pointless": [{"step": "classify"}, {"step": "analyze", "model": "prebuilt-read"}]}"#,
+            r#"{"model": [{"step": "analyze", "model": "prebuilt-contract"}]}"#,
+            r#"{"hook": [{"step": "analyze"}, {"step": "webhook", "url": "ftp://example.com"}]}"#,
+            r#"{"bad name": [{"step": "analyze"}]}"#,
+            r#"{"unknown": [{"step": "translate"}]}"#,
+        ] {
+            assert!(parse_pipelines(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_validate_result() {
+        let result = AnalysisResult {
+            key_value_pairs: vec![KeyValuePair {
+                key: "PO Number:".to_string(),
+                value: "4711".to_string(),
+                confidence: 0.9,
+            }],
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                fields: HashMap::from([("InvoiceTotal".to_string(), DocumentField::Number(10.0))]),
+                confidence: 0.7,
+            }],
+            ..AnalysisResult::default()
+        };
+        let fields = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+
+        assert!(validate_result(&result, &fields(&["InvoiceTotal", "po number"]), Some(0.7)).is_empty());
+        assert_eq!(
+            validate_result(&result, &fields(&["VendorName"]), Some(0.8)),
+            vec!["missing field VendorName", "invoice confidence 0.70 is below 0.80"]
+        );
+    }
+
+    #[test]
+    fn test_run_progress() {
+        let definition = PipelineDefinition {
+            name: "invoices".to_string(),
+            steps: vec![
+                PipelineStep::Analyze { model: None },
+                PipelineStep::Validate { required_fields: Vec::new(), min_confidence: None },
+                PipelineStep::PublishEvent,
+            ],
+        };
+        let mut run = PipelineRun::new(&definition, TenantId::default());
+        run.start_step(0);
+        run.finish_step(0, Ok(Some("operation 1".to_string())));
+        assert_eq!(run.status, PipelineStatus::Running);
+
+        run.start_step(1);
+        run.finish_step(1, Err("missing field InvoiceTotal".to_string()));
+        assert_eq!(run.status, PipelineStatus::Failed);
+        let statuses: Vec<StepStatus> = run.steps.iter().map(|step| step.status).collect();
+        assert_eq!(statuses, vec![StepStatus::Succeeded, StepStatus::Failed, StepStatus::Skipped]);
+
+        let mut run = PipelineRun::new(&definition, TenantId::default());
+        run.start_step(0);
+        run.finish_step(0, Ok(None));
+        run.start_step(1);
+        run.interrupt("Interrupted by shutdown".to_string());
+        assert_eq!(run.status, PipelineStatus::Failed);
+        assert_eq!(run.steps[1].detail.as_deref(), Some("Interrupted by shutdown"));
+        let statuses: Vec<StepStatus> = run.steps.iter().map(|step| step.status).collect();
+        assert_eq!(statuses, vec![StepStatus::Succeeded, StepStatus::Failed, StepStatus::Skipped]);
+    }
+}
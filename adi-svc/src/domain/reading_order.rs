@@ -0,0 +1,282 @@
+/// Paragraphs rebuilt from line geometry
+///
+/// Read and prebuilt results carry lines but no paragraphs. Lines are split
+/// into columns at the vertical gutters between them, lines spanning several
+/// columns break the page into sections read top to bottom, and within a
+/// column a wide gap, an indent or a change of type size starts a paragraph.
+
+use serde::{Deserialize, Serialize};
+
+use super::geometry::{BoundingBox, Polygon};
+use super::models::{AnalysisResult, DocumentPage};
+
+/// Lines narrower than this share of the text width can form columns
+const COLUMN_LINE_SHARE: f32 = 0.55;
+
+/// Gaps between lines above this share of the line height start a paragraph
+const PARAGRAPH_GAP: f32 = 0.6;
+
+/// Lines this many times taller or shorter than the last start a paragraph
+const HEIGHT_CHANGE: f32 = 1.5;
+
+/// Consecutive lines of a page read as one block of text
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Paragraph {
+    pub page_number: i32,
+    /// The lines joined by spaces, with words hyphenated across lines rejoined
+    pub content: String,
+    /// Indexes into the page's `lines`, in reading order
+    pub lines: Vec<usize>,
+    /// Bounds of the lines in the page's unit, `None` for lines without polygons
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bounding_box: Option<BoundingBox>,
+}
+
+impl AnalysisResult {
+    /// Paragraphs of every page, in reading order
+    pub fn paragraphs(&self) -> Vec<Paragraph> {
+        self.pages.iter().flat_map(page_paragraphs).collect()
+    }
+}
+
+/// A page's lines grouped into paragraphs, in reading order
+///
+/// Lines without a polygon cannot be placed and follow as a paragraph each.
+pub fn page_paragraphs(page: &DocumentPage) -> Vec<Paragraph> {
+    let mut placed: Vec<(usize, BoundingBox)> = page
+        .lines
+        .iter()
+        .enumerate()
+        .filter_map(|(index, line)| Some((index, line.polygon.bounding_box()?)))
+        .collect();
+    placed.sort_by(|(_, a), (_, b)| a.top.total_cmp(&b.top).then(a.left.total_cmp(&b.left)));
+
+    let mut heights: Vec<f32> = placed.iter().map(|(_, bounds)| bounds.height()).collect();
+    heights.sort_by(f32::total_cmp);
+    let line_height = heights.get(heights.len() / 2).copied().unwrap_or_default().max(f32::EPSILON);
+
+    let columns = columns(&placed, line_height);
+    let mut flows: Vec<Vec<(usize, BoundingBox)>> = Vec::new();
+    let mut section: Vec<Vec<(usize, BoundingBox)>> = vec![Vec::new(); columns.len()];
+    let mut spanning: Vec<(usize, BoundingBox)> = Vec::new();
+    for (index, bounds) in placed {
+        let overlapped: Vec<usize> = columns
+            .iter()
+            .enumerate()
+            .filter(|(_, (left, right))| bounds.left < *right && bounds.right > *left)
+            .map(|(column, _)| column)
+            .collect();
+        if overlapped.len() > 1 {
+            // A heading or full-width line ends the columns above it
+            flows.extend(section.iter_mut().map(std::mem::take));
+            spanning.push((index, bounds));
+            continue;
+        }
+        flows.push(std::mem::take(&mut spanning));
+        let column = overlapped.first().copied().unwrap_or_else(|| nearest(&columns, &bounds));
+        section[column].push((index, bounds));
+    }
+    flows.push(spanning);
+    flows.extend(section);
+
+    let mut paragraphs: Vec<Paragraph> = Vec::new();
+    for flow in flows {
+        let mut previous: Option<BoundingBox> = None;
+        for (index, bounds) in flow {
+            let starts = previous.is_none_or(|previous| {
+                let gap = bounds.top - previous.bottom;
+                let ratio = bounds.height().max(f32::EPSILON) / previous.height().max(f32::EPSILON);
+                gap > PARAGRAPH_GAP * line_height
+                    || bounds.left - previous.left > line_height
+                    || !(1.0 / HEIGHT_CHANGE..=HEIGHT_CHANGE).contains(&ratio)
+            });
+            if starts {
+                paragraphs.push(paragraph(page, index, Some(bounds)));
+            } else if let Some(paragraph) = paragraphs.last_mut() {
+                append(paragraph, &page.lines[index].content, index);
+                paragraph.bounding_box = paragraph.bounding_box.map(|merged| BoundingBox {
+                    left: merged.left.min(bounds.left),
+                    top: merged.top.min(bounds.top),
+                    right: merged.right.max(bounds.right),
+                    bottom: merged.bottom.max(bounds.bottom),
+                });
+            }
+            previous = Some(bounds);
+        }
+    }
+
+    paragraphs.extend(
+        page.lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| line.polygon.is_empty())
+            .map(|(index, _)| paragraph(page, index, None)),
+    );
+    paragraphs
+}
+
+/// Horizontal extents of the page's columns, left to right
+fn columns(placed: &[(usize, BoundingBox)], line_height: f32) -> Vec<(f32, f32)> {
+    let left = placed.iter().map(|(_, bounds)| bounds.left).fold(f32::MAX, f32::min);
+    let right = placed.iter().map(|(_, bounds)| bounds.right).fold(f32::MIN, f32::max);
+    let mut extents: Vec<(f32, f32)> = placed
+        .iter()
+        .filter(|(_, bounds)| bounds.width() <= COLUMN_LINE_SHARE * (right - left))
+        .map(|(_, bounds)| (bounds.left, bounds.right))
+        .collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut columns: Vec<(f32, f32)> = Vec::new();
+    for (start, end) in extents {
+        match columns.last_mut() {
+            // Gutters narrower than half a line are spaces between words
+            Some(column) if start <= column.1 + line_height / 2.0 => column.1 = column.1.max(end),
+            _ => columns.push((start, end)),
+        }
+    }
+    if columns.is_empty() {
+        columns.push((left, right));
+    }
+    columns
+}
+
+/// Column whose extent is closest to the middle of `bounds`
+fn nearest(columns: &[(f32, f32)], bounds: &BoundingBox) -> usize {
+    let middle = bounds.center().x;
+    let distance = |(left, right): &(f32, f32)| (left - middle).max(middle - right).max(0.0);
+    (0..columns.len())
+        .min_by(|a, b| distance(&columns[*a]).total_cmp(&distance(&columns[*b])))
+        .unwrap_or_default()
+}
+
+fn paragraph(page: &DocumentPage, index: usize, bounding_box: Option<BoundingBox>) -> Paragraph {
+    Paragraph {
+        page_number: page.page_number,
+        content: page.lines[index].content.trim().to_string(),
+        lines: vec![index],
+        bounding_box,
+    }
+}
+
+/// Add a line to a paragraph, rejoining a word hyphenated across the break
+fn append(paragraph: &mut Paragraph, line: &str, index: usize) {
+    let line = line.trim();
+    let hyphenated = paragraph
+        .content
+        .strip_suffix('-')
+        .filter(|head| head.ends_with(char::is_alphabetic) && line.starts_with(char::is_lowercase));
+    match hyphenated {
+        Some(head) => paragraph.content = format!("{}{}", head, line),
+        None if paragraph.content.is_empty() => paragraph.content = line.to_string(),
+        None => {
+            paragraph.content.push(' ');
+            paragraph.content.push_str(line);
+        }
+    }
+    paragraph.lines.push(index);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::DocumentLine;
+
+    fn page(lines: &[(&str, [f32; 4])]) -> DocumentPage {
+        DocumentPage {
+            page_number: 1,
+            angle: 0.0,
+            width: 8.5,
+            height: 11.0,
+            unit: "inch".to_string(),
+            words: Vec::new(),
+            lines: lines
+                .iter()
+                .map(|(content, [left, top, right, bottom])| DocumentLine {
+                    content: content.to_string(),
+                    polygon: if left == right {
+                        Vec::new()
+                    } else {
+                        BoundingBox { left: *left, top: *top, right: *right, bottom: *bottom }.to_polygon()
+                    },
+                    spans: Vec::new(),
+                })
+                .collect(),
+            selection_marks: Vec::new(),
+        }
+    }
+
+    fn contents(paragraphs: &[Paragraph]) -> Vec<&str> {
+        paragraphs.iter().map(|paragraph| paragraph.content.as_str()).collect()
+    }
+
+    #[test]
+    fn test_single_column_paragraphs() {
+        let page = page(&[
+            ("Quarterly Report", [1.0, 1.0, 4.0, 1.4]),
+            ("Revenue grew in every", [1.0, 1.6, 7.0, 1.8]),
+            ("region this quarter, as", [1.0, 1.85, 7.0, 2.05]),
+            ("expected.", [1.0, 2.1, 2.0, 2.3]),
+            ("Costs were flat and the mar-", [1.3, 2.6, 7.0, 2.8]),
+            ("gin improved.", [1.0, 2.85, 3.0, 3.05]),
+            ("    Indented again", [1.3, 3.1, 4.0, 3.3]),
+        ]);
+        let paragraphs = page_paragraphs(&page);
+        assert_eq!(
+            contents(&paragraphs),
+            [
+                "Quarterly Report",
+                "Revenue grew in every region this quarter, as expected.",
+                "Costs were flat and the margin improved.",
+                "Indented again",
+            ]
+        );
+        assert_eq!(paragraphs[1].lines, [1, 2, 3]);
+        assert_eq!(
+            paragraphs[1].bounding_box,
+            Some(BoundingBox { left: 1.0, top: 1.6, right: 7.0, bottom: 2.3 })
+        );
+    }
+
+    #[test]
+    fn test_columns_read_in_order() {
+        // Two columns under a full-width title, with a footer across both,
+        // listed in the order Azure might return them: row by row
+        let page = page(&[
+            ("Annual Summary of Results", [1.0, 1.0, 7.5, 1.3]),
+            ("Left one", [1.0, 1.6, 4.0, 1.8]),
+            ("Right one", [4.5, 1.6, 7.5, 1.8]),
+            ("left two", [1.0, 1.85, 4.0, 2.05]),
+            ("right two", [4.5, 1.85, 7.5, 2.05]),
+            ("Left three", [1.0, 2.6, 3.0, 2.8]),
+            ("Footer across the whole page width", [1.0, 9.0, 7.5, 9.2]),
+            ("Unplaced", [0.0, 0.0, 0.0, 0.0]),
+        ]);
+        let paragraphs = page_paragraphs(&page);
+        assert_eq!(
+            contents(&paragraphs),
+            [
+                "Annual Summary of Results",
+                "Left one left two",
+                "Left three",
+                "Right one right two",
+                "Footer across the whole page width",
+                "Unplaced",
+            ]
+        );
+        assert_eq!(paragraphs[3].lines, [2, 4]);
+        assert!(paragraphs[5].bounding_box.is_none());
+    }
+
+    #[test]
+    fn test_result_paragraphs() {
+        let mut second = page(&[("Only line", [1.0, 1.0, 2.0, 1.2])]);
+        second.page_number = 2;
+        let result = AnalysisResult {
+            pages: vec![page(&[]), second],
+            ..Default::default()
+        };
+        let paragraphs = result.paragraphs();
+        assert_eq!(paragraphs.len(), 1);
+        assert_eq!(paragraphs[0].page_number, 2);
+    }
+}
@@ -0,0 +1,471 @@
+/// Redaction of source documents
+///
+/// Turns a rule set (field names, PII categories and explicit regions) into
+/// boxes over the analyzed document's pages. Boxes are fractions of the page
+/// measured from its top-left corner, so renderers need not know whether
+/// Azure measured the page in inches or pixels.
+
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+use super::errors::{DomainError, DomainResult};
+use super::geometry::Polygon;
+use super::models::{AnalysisResult, DocumentField, DocumentPage, Point};
+
+/// Personal data recognized by its shape in the page text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PiiCategory {
+    Email,
+    Phone,
+    /// US social security numbers written `123-45-6789`
+    Ssn,
+    /// Card numbers passing the Luhn check
+    CreditCard,
+}
+
+impl PiiCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Email => "email",
+            Self::Phone => "phone",
+            Self::Ssn => "ssn",
+            Self::CreditCard => "credit_card",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "email" => Some(Self::Email),
+            "phone" => Some(Self::Phone),
+            "ssn" => Some(Self::Ssn),
+            "credit_card" => Some(Self::CreditCard),
+            _ => None,
+        }
+    }
+}
+
+/// Area of a page to redact, in the page's unit from its top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RedactionRegion {
+    pub page_number: i32,
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+/// Parses `page:left,top,right,bottom`, e.g. `1:0.5,1,4,1.5`
+impl FromStr for RedactionRegion {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            DomainError::ValidationError(format!(
+                "invalid redaction region '{}': expected page:left,top,right,bottom",
+                s
+            ))
+        };
+        let (page, corners) = s.split_once(':').ok_or_else(invalid)?;
+        let page_number = page.trim().parse::<i32>().ok().filter(|page| *page > 0).ok_or_else(invalid)?;
+        let corners = corners
+            .split(',')
+            .map(|value| value.trim().parse::<f32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| invalid())?;
+        match corners[..] {
+            [left, top, right, bottom]
+                if corners.iter().all(|value| value.is_finite() && *value >= 0.0) && left < right && top < bottom =>
+            {
+                Ok(Self {
+                    page_number,
+                    left,
+                    top,
+                    right,
+                    bottom,
+                })
+            }
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// What to black out of a document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RedactionRules {
+    /// Key-value pair keys and document field names, matched case-insensitively;
+    /// their values are redacted wherever the text appears on a page
+    pub fields: Vec<String>,
+    pub pii: Vec<PiiCategory>,
+    pub regions: Vec<RedactionRegion>,
+}
+
+impl RedactionRules {
+    pub fn validate(&self) -> DomainResult<()> {
+        if self.fields.is_empty() && self.pii.is_empty() && self.regions.is_empty() {
+            return Err(DomainError::ValidationError(
+                "redaction needs at least one field, PII category or region".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Box to black out, as fractions of the page's width and height from its top-left corner
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RedactionBox {
+    pub page_number: i32,
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+/// Boxes over every word `rules` selects, and its regions, page by page
+///
+/// Field values are located by matching their text against runs of words,
+/// so values Azure normalized (dates, amounts) are only found when a key-value
+/// pair carries them as written.
+pub fn redaction_boxes(result: &AnalysisResult, rules: &RedactionRules) -> Vec<RedactionBox> {
+    let values = field_values(result, &rules.fields);
+    let mut boxes = Vec::new();
+    for page in &result.pages {
+        if page.width <= 0.0 || page.height <= 0.0 {
+            continue;
+        }
+
+        let mut redacted = vec![false; page.words.len()];
+        for value in &values {
+            mark_value(page, value, &mut redacted);
+        }
+        if !rules.pii.is_empty() {
+            mark_pii(page, &rules.pii, &mut redacted);
+        }
+        boxes.extend(
+            page.words
+                .iter()
+                .zip(redacted)
+                .filter(|(_, redacted)| *redacted)
+                .filter_map(|(word, _)| polygon_box(page, &word.polygon)),
+        );
+
+        boxes.extend(
+            rules
+                .regions
+                .iter()
+                .filter(|region| region.page_number == page.page_number)
+                .map(|region| RedactionBox {
+                    page_number: page.page_number,
+                    left: (region.left / page.width).clamp(0.0, 1.0),
+                    top: (region.top / page.height).clamp(0.0, 1.0),
+                    right: (region.right / page.width).clamp(0.0, 1.0),
+                    bottom: (region.bottom / page.height).clamp(0.0, 1.0),
+                }),
+        );
+    }
+    boxes
+}
+
+/// Text of the key-value pairs and document fields named in `names`
+fn field_values(result: &AnalysisResult, names: &[String]) -> Vec<String> {
+    let selected = |name: &str| names.iter().any(|selected| selected.eq_ignore_ascii_case(name));
+    let mut values: Vec<String> = result
+        .key_value_pairs
+        .iter()
+        .filter(|pair| selected(&pair.key))
+        .map(|pair| pair.value.clone())
+        .collect();
+    for document in &result.documents {
+        for (name, field) in &document.fields {
+            if selected(name) {
+                field_text(field, &mut values);
+            }
+        }
+    }
+    values.retain(|value| !value.trim().is_empty());
+    values
+}
+
+fn field_text(field: &DocumentField, values: &mut Vec<String>) {
+    match field {
+        DocumentField::String(text) => values.push(text.clone()),
+        DocumentField::Integer(number) => values.push(number.to_string()),
+        DocumentField::Number(number) => values.push(number.to_string()),
+        DocumentField::Array(items) => items.iter().for_each(|item| field_text(item, values)),
+        DocumentField::Object(fields) => fields.values().for_each(|item| field_text(item, values)),
+        DocumentField::Date(_) | DocumentField::Time(_) | DocumentField::Boolean(_) => {}
+    }
+}
+
+/// Mark every run of words on `page` spelling out `value`
+fn mark_value(page: &DocumentPage, value: &str, redacted: &mut [bool]) {
+    let tokens: Vec<&str> = value.split_whitespace().collect();
+    if tokens.len() > page.words.len() {
+        return;
+    }
+    for start in 0..=page.words.len() - tokens.len() {
+        let matches = tokens
+            .iter()
+            .zip(&page.words[start..])
+            .all(|(token, word)| word.content == *token);
+        if matches {
+            redacted[start..start + tokens.len()].fill(true);
+        }
+    }
+}
+
+/// Mark the words on `page` holding personal data of `categories`
+///
+/// The words are joined with single spaces so numbers Azure split into
+/// several words, like `(425) 555-0100`, are recognized as one.
+fn mark_pii(page: &DocumentPage, categories: &[PiiCategory], redacted: &mut [bool]) {
+    let mut text = String::new();
+    let mut word_ranges = Vec::with_capacity(page.words.len());
+    for word in &page.words {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        word_ranges.push(text.len()..text.len() + word.content.len());
+        text.push_str(&word.content);
+    }
+
+    let mut found = Vec::new();
+    for category in categories {
+        match category {
+            PiiCategory::Email => found.extend(email_spans(&text)),
+            PiiCategory::Ssn => found.extend(ssn_spans(&text)),
+            PiiCategory::Phone => found.extend(
+                number_spans(&text)
+                    .filter(|span| (10..=15).contains(&digit_count(&text[span.clone()]))),
+            ),
+            PiiCategory::CreditCard => found.extend(number_spans(&text).filter(|span| is_card_number(&text[span.clone()]))),
+        }
+    }
+    for (range, redacted) in word_ranges.iter().zip(redacted.iter_mut()) {
+        if found.iter().any(|span| span.start < range.end && range.start < span.end) {
+            *redacted = true;
+        }
+    }
+}
+
+/// Byte spans of whitespace-separated tokens shaped like `name@domain.tld`
+fn email_spans(text: &str) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+    let mut offset = 0;
+    text.split(' ').filter_map(move |token| {
+        let start = offset;
+        offset += token.len() + 1;
+        let address = token.trim_matches(|c: char| !c.is_alphanumeric());
+        let (local, domain) = address.split_once('@')?;
+        let valid = !local.is_empty()
+            && !domain.contains('@')
+            && domain.split('.').count() >= 2
+            && domain.split('.').all(|label| !label.is_empty());
+        valid.then(|| start..start + token.len())
+    })
+}
+
+/// Byte spans of `ddd-dd-dddd` not embedded in a longer number
+fn ssn_spans(text: &str) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+    const SHAPE: &[u8; 11] = b"ddd-dd-dddd";
+    let bytes = text.as_bytes();
+    let is_number = |b: &u8| b.is_ascii_digit() || *b == b'-';
+    (0..bytes.len().saturating_sub(SHAPE.len() - 1))
+        .filter(move |&start| {
+            let window = &bytes[start..start + SHAPE.len()];
+            let shaped = window
+                .iter()
+                .zip(SHAPE)
+                .all(|(b, shape)| if *shape == b'd' { b.is_ascii_digit() } else { b == shape });
+            shaped
+                && !bytes[..start].last().is_some_and(is_number)
+                && !bytes.get(start + SHAPE.len()).is_some_and(is_number)
+        })
+        .map(|start| start..start + SHAPE.len())
+}
+
+/// Byte spans of digit runs with the separators phone and card numbers are written with
+fn number_spans(text: &str) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+    let bytes = text.as_bytes();
+    let is_part = |b: u8| b.is_ascii_digit() || b" -.()+".contains(&b);
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        while pos < bytes.len() {
+            let start = pos;
+            while pos < bytes.len() && is_part(bytes[pos]) {
+                pos += 1;
+            }
+            // Trim separators that belong to the surrounding text
+            let run = &bytes[start..pos];
+            pos += 1;
+            let first = run.iter().position(|b| b.is_ascii_digit() || b"(+".contains(b));
+            let last = run.iter().rposition(u8::is_ascii_digit);
+            if let (Some(first), Some(last)) = (first, last) {
+                return Some(start + first..start + last + 1);
+            }
+        }
+        None
+    })
+}
+
+fn digit_count(text: &str) -> usize {
+    text.bytes().filter(u8::is_ascii_digit).count()
+}
+
+/// Whether `text` is 13 to 19 digits, grouped by spaces or dashes, passing the Luhn check
+fn is_card_number(text: &str) -> bool {
+    if !text.bytes().all(|b| b.is_ascii_digit() || b == b' ' || b == b'-') {
+        return false;
+    }
+    let digits: Vec<u32> = text.chars().filter_map(|c| c.to_digit(10)).collect();
+    if !(13..=19).contains(&digits.len()) {
+        return false;
+    }
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(index, digit)| match (index % 2, digit * 2) {
+            (1, doubled) if doubled > 9 => doubled - 9,
+            (1, doubled) => doubled,
+            _ => *digit,
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+/// Bounds of `polygon` as fractions of the page
+fn polygon_box(page: &DocumentPage, polygon: &[Point]) -> Option<RedactionBox> {
+    let bounds = polygon.bounding_box()?;
+    Some(RedactionBox {
+        page_number: page.page_number,
+        left: (bounds.left / page.width).clamp(0.0, 1.0),
+        top: (bounds.top / page.height).clamp(0.0, 1.0),
+        right: (bounds.right / page.width).clamp(0.0, 1.0),
+        bottom: (bounds.bottom / page.height).clamp(0.0, 1.0),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{DocumentWord, KeyValuePair, Span};
+    use std::collections::HashMap;
+
+    /// Page of 10 x 10 units with one word per unit-wide cell, left to right
+    fn page(words: &[&str]) -> DocumentPage {
+        DocumentPage {
+            page_number: 1,
+            angle: 0.0,
+            width: 10.0,
+            height: 10.0,
+            unit: "inch".to_string(),
+            words: words
+                .iter()
+                .enumerate()
+                .map(|(index, content)| {
+                    let (x, y) = ((index % 10) as f32, (index / 10) as f32);
+                    DocumentWord {
+                        content: content.to_string(),
+                        polygon: vec![
+                            Point { x, y },
+                            Point { x: x + 1.0, y },
+                            Point { x: x + 1.0, y: y + 1.0 },
+                            Point { x, y: y + 1.0 },
+                        ],
+                        confidence: 0.99,
+                        span: Span { offset: 0, length: 0 },
+                    }
+                })
+                .collect(),
+            lines: Vec::new(),
+            selection_marks: Vec::new(),
+        }
+    }
+
+    /// Indexes of the words covered by `boxes`
+    fn redacted_words(boxes: &[RedactionBox]) -> Vec<usize> {
+        boxes
+            .iter()
+            .map(|b| (b.top * 10.0).round() as usize * 10 + (b.left * 10.0).round() as usize)
+            .collect()
+    }
+
+    #[test]
+    fn test_redact_fields() {
+        let result = AnalysisResult {
+            pages: vec![page(&["Vendor:", "Contoso", "Ltd.", "Total:", "10", "Contoso", "Inc."])],
+            key_value_pairs: vec![KeyValuePair {
+                key: "Vendor".to_string(),
+                value: "Contoso Ltd.".to_string(),
+                confidence: 0.9,
+            }],
+            documents: vec![crate::domain::ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                fields: HashMap::from([("InvoiceTotal".to_string(), DocumentField::Integer(10))]),
+                confidence: 0.9,
+            }],
+            ..Default::default()
+        };
+        let rules = RedactionRules {
+            fields: vec!["vendor".to_string(), "InvoiceTotal".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(redacted_words(&redaction_boxes(&result, &rules)), vec![1, 2, 4]);
+
+        let rules = RedactionRules {
+            fields: vec!["VendorName".to_string()],
+            ..Default::default()
+        };
+        assert!(redaction_boxes(&result, &rules).is_empty());
+    }
+
+    #[test]
+    fn test_redact_pii() {
+        let result = AnalysisResult {
+            pages: vec![page(&[
+                "Call", "(425)", "555-0100", "or", "<jane@contoso.com>", "SSN", "123-45-6789", "card",
+                "4111", "1111", "1111", "1111", "on", "2024-01-05",
+            ])],
+            ..Default::default()
+        };
+        let redact = |pii: Vec<PiiCategory>| {
+            redacted_words(&redaction_boxes(&result, &RedactionRules { pii, ..Default::default() }))
+        };
+        assert_eq!(redact(vec![PiiCategory::Phone]), vec![1, 2]);
+        assert_eq!(redact(vec![PiiCategory::Email]), vec![4]);
+        assert_eq!(redact(vec![PiiCategory::Ssn]), vec![6]);
+        assert_eq!(redact(vec![PiiCategory::CreditCard]), vec![8, 9, 10, 11]);
+
+        // Parts of longer numbers are not social security numbers
+        assert_eq!(ssn_spans("ref 1123-45-6789").count(), 0);
+        assert_eq!(ssn_spans("ref 123-45-6789-0").count(), 0);
+        assert!(!is_card_number("4111 1111 1111 1112"));
+    }
+
+    #[test]
+    fn test_redact_regions() {
+        let result = AnalysisResult {
+            pages: vec![page(&[]), DocumentPage { page_number: 2, ..page(&[]) }],
+            ..Default::default()
+        };
+        let rules = RedactionRules {
+            regions: vec!["2:1,2,5,12".parse().unwrap()],
+            ..Default::default()
+        };
+        assert_eq!(
+            redaction_boxes(&result, &rules),
+            vec![RedactionBox {
+                page_number: 2,
+                left: 0.1,
+                top: 0.2,
+                right: 0.5,
+                bottom: 1.0,
+            }]
+        );
+
+        assert!("1:4,2,1,3".parse::<RedactionRegion>().is_err());
+        assert!("0:1,2,3,4".parse::<RedactionRegion>().is_err());
+        assert!("1:1,2,3".parse::<RedactionRegion>().is_err());
+        assert!(RedactionRules::default().validate().is_err());
+    }
+}
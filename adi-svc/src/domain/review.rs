@@ -0,0 +1,353 @@
+/// Human review of low-confidence extractions
+///
+/// A [`ReviewPolicy`] names the fields that matter and the confidence they
+/// must reach. Succeeded operations whose key fields fall short are flagged
+/// for review; a reviewer's corrections are applied to the result as a new
+/// [`ResultRevision`], keeping the extraction they replace.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::errors::{DomainError, DomainResult};
+use super::models::{AnalysisResult, DocumentField, FieldMatch, KeyValuePair};
+use super::value_objects::{validate_min_confidence, FieldQuery};
+
+/// Most corrections accepted in one request
+pub const MAX_CORRECTIONS: usize = 100;
+
+/// When a succeeded result needs a human to look at it
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReviewPolicy {
+    /// Lowest confidence a key field may have without review
+    pub min_confidence: f32,
+    /// Fields checked, matched like field queries; every field when empty
+    pub key_fields: Vec<String>,
+}
+
+impl ReviewPolicy {
+    pub fn new(min_confidence: f32, key_fields: Vec<String>) -> DomainResult<Self> {
+        validate_min_confidence(min_confidence)?;
+        Ok(Self { min_confidence, key_fields })
+    }
+
+    /// Why `result` needs review; empty when it doesn't
+    ///
+    /// Document fields carry their document's confidence. A key field found
+    /// more than once is judged by its most confident occurrence, and a key
+    /// field not found at all needs review too.
+    pub fn assess(&self, result: &AnalysisResult) -> Vec<String> {
+        let fields = result.query_fields(&FieldQuery::default());
+        let below = |name: &str, confidence: f32| {
+            format!("{} confidence {:.2} is below {:.2}", name, confidence, self.min_confidence)
+        };
+        if self.key_fields.is_empty() {
+            let mut reasons: Vec<String> = Vec::new();
+            for field in fields.iter().filter(|field| field.confidence() < self.min_confidence) {
+                let reason = below(field.name(), field.confidence());
+                if !reasons.contains(&reason) {
+                    reasons.push(reason);
+                }
+            }
+            return reasons;
+        }
+        self.key_fields
+            .iter()
+            .filter_map(|key| {
+                let best = fields
+                    .iter()
+                    .filter(|field| field.name().eq_ignore_ascii_case(key.trim()))
+                    .map(FieldMatch::confidence)
+                    .reduce(f32::max);
+                match best {
+                    None => Some(format!("missing field {}", key)),
+                    Some(confidence) if confidence < self.min_confidence => Some(below(key, confidence)),
+                    Some(_) => None,
+                }
+            })
+            .collect()
+    }
+}
+
+/// Where an operation is in review
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewStatus {
+    /// Waiting in the review queue
+    NeedsReview,
+    /// Corrected by a reviewer
+    Reviewed,
+}
+
+impl ReviewStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::NeedsReview => "needs_review",
+            Self::Reviewed => "reviewed",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "needs_review" => Some(Self::NeedsReview),
+            "reviewed" => Some(Self::Reviewed),
+            _ => None,
+        }
+    }
+}
+
+/// Review of one operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReviewState {
+    pub status: ReviewStatus,
+    /// Why the operation was flagged
+    #[serde(default)]
+    pub reasons: Vec<String>,
+    pub flagged_at: DateTime<Utc>,
+    /// Latest result revision, 0 until corrected
+    #[serde(default)]
+    pub revision: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_by: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewed_at: Option<DateTime<Utc>>,
+}
+
+impl ReviewState {
+    /// A review waiting on a human, for `reasons`
+    pub fn needs_review(reasons: Vec<String>) -> Self {
+        Self {
+            status: ReviewStatus::NeedsReview,
+            reasons,
+            flagged_at: Utc::now(),
+            revision: 0,
+            reviewed_by: None,
+            reviewed_at: None,
+        }
+    }
+
+    /// Mark reviewed by `revision`, keeping why it was flagged
+    pub fn reviewed(&mut self, revision: &ResultRevision) {
+        self.status = ReviewStatus::Reviewed;
+        self.revision = revision.revision;
+        self.reviewed_by = revision.reviewer.clone();
+        self.reviewed_at = Some(revision.created_at);
+    }
+}
+
+/// Value a reviewer gives a field
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FieldCorrection {
+    /// Document field name or key-value pair key, matched ignoring case
+    pub field: String,
+    /// Only correct the field in documents of this type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_type: Option<String>,
+    pub value: String,
+}
+
+/// Check a batch of corrections before applying any
+pub fn validate_corrections(corrections: &[FieldCorrection]) -> DomainResult<()> {
+    if corrections.is_empty() {
+        return Err(DomainError::ValidationError("at least one correction is required".to_string()));
+    }
+    if corrections.len() > MAX_CORRECTIONS {
+        return Err(DomainError::ValidationError(format!(
+            "at most {} corrections are accepted at once",
+            MAX_CORRECTIONS
+        )));
+    }
+    if corrections.iter().any(|correction| correction.field.trim().is_empty()) {
+        return Err(DomainError::ValidationError("correction field must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+/// Apply corrections to a result
+///
+/// A correction replaces the field in every matching document, or else the
+/// value of a key-value pair with that key, which becomes fully confident.
+/// A field found nowhere is added to the first matching document, or as a
+/// key-value pair when there is none.
+pub fn apply_corrections(result: &mut AnalysisResult, corrections: &[FieldCorrection]) -> DomainResult<()> {
+    validate_corrections(corrections)?;
+    for correction in corrections {
+        let field = correction.field.trim();
+        let wanted = |doc_type: &str| correction.doc_type.as_deref().is_none_or(|wanted| wanted == doc_type);
+        let mut applied = false;
+        for document in result.documents.iter_mut().filter(|document| wanted(&document.doc_type)) {
+            let names: Vec<String> = document
+                .fields
+                .keys()
+                .filter(|name| name.eq_ignore_ascii_case(field))
+                .cloned()
+                .collect();
+            for name in names {
+                document.fields.insert(name, DocumentField::String(correction.value.clone()));
+                applied = true;
+            }
+        }
+        if applied {
+            continue;
+        }
+        if correction.doc_type.is_none() {
+            for pair in result
+                .key_value_pairs
+                .iter_mut()
+                .filter(|pair| pair.key.trim().trim_end_matches(':').trim_end().eq_ignore_ascii_case(field))
+            {
+                pair.value = correction.value.clone();
+                pair.confidence = 1.0;
+                applied = true;
+            }
+        }
+        if applied {
+            continue;
+        }
+        match result.documents.iter_mut().find(|document| wanted(&document.doc_type)) {
+            Some(document) => {
+                document.fields.insert(field.to_string(), DocumentField::String(correction.value.clone()));
+            }
+            None if correction.doc_type.is_none() => result.key_value_pairs.push(KeyValuePair {
+                key: field.to_string(),
+                value: correction.value.clone(),
+                confidence: 1.0,
+            }),
+            None => {
+                return Err(DomainError::ValidationError(format!(
+                    "result has no {} document to correct",
+                    correction.doc_type.as_deref().unwrap_or_default()
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// One version of an operation's result
+///
+/// Revision 0 is the extraction as analyzed; each later revision is the
+/// previous one with a reviewer's corrections applied.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultRevision {
+    pub operation_id: String,
+    pub revision: u32,
+    /// Corrections made by this revision, empty for revision 0
+    pub corrections: Vec<FieldCorrection>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reviewer: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub result: AnalysisResult,
+}
+
+impl ResultRevision {
+    /// Revision 0, the extraction as analyzed
+    pub fn original(operation_id: &str, result: AnalysisResult) -> Self {
+        Self {
+            operation_id: operation_id.to_string(),
+            revision: 0,
+            corrections: Vec::new(),
+            reviewer: None,
+            created_at: Utc::now(),
+            result,
+        }
+    }
+
+    /// The revision after `self` with `corrections` applied
+    pub fn correct(&self, corrections: Vec<FieldCorrection>, reviewer: Option<String>) -> DomainResult<Self> {
+        let mut result = self.result.clone();
+        apply_corrections(&mut result, &corrections)?;
+        Ok(Self {
+            operation_id: self.operation_id.clone(),
+            revision: self.revision + 1,
+            corrections,
+            reviewer,
+            created_at: Utc::now(),
+            result,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ExtractedDocument;
+    use std::collections::HashMap;
+
+    fn invoice(confidence: f32) -> AnalysisResult {
+        AnalysisResult {
+            key_value_pairs: vec![KeyValuePair {
+                key: "PO Number:".to_string(),
+                value: "PO-1".to_string(),
+                confidence: 0.4,
+            }],
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                confidence,
+                fields: HashMap::from([("InvoiceTotal".to_string(), DocumentField::String("$10.00".to_string()))]),
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn correction(field: &str, value: &str) -> FieldCorrection {
+        FieldCorrection { field: field.to_string(), doc_type: None, value: value.to_string() }
+    }
+
+    #[test]
+    fn test_assess() {
+        let policy = ReviewPolicy::new(0.8, vec!["invoicetotal".to_string(), "VendorName".to_string()]).unwrap();
+        assert_eq!(
+            policy.assess(&invoice(0.6)),
+            vec!["invoicetotal confidence 0.60 is below 0.80", "missing field VendorName"]
+        );
+        let policy = ReviewPolicy::new(0.8, vec!["InvoiceTotal".to_string()]).unwrap();
+        assert!(policy.assess(&invoice(0.9)).is_empty());
+
+        // Without key fields every field counts
+        let policy = ReviewPolicy::new(0.5, Vec::new()).unwrap();
+        assert_eq!(policy.assess(&invoice(0.9)), vec!["PO Number confidence 0.40 is below 0.50"]);
+        assert!(ReviewPolicy::new(1.5, Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_corrections_make_revisions() {
+        let original = ResultRevision::original("op-1", invoice(0.6));
+        let corrected = original
+            .correct(
+                vec![
+                    correction("invoicetotal", "$12.00"),
+                    correction("po number", "PO-2"),
+                    correction("VendorName", "Contoso"),
+                ],
+                Some("alice".to_string()),
+            )
+            .unwrap();
+        assert_eq!(corrected.revision, 1);
+        let document = &corrected.result.documents[0];
+        assert!(matches!(&document.fields["InvoiceTotal"], DocumentField::String(v) if v == "$12.00"));
+        assert!(matches!(&document.fields["VendorName"], DocumentField::String(v) if v == "Contoso"));
+        assert_eq!(corrected.result.key_value_pairs[0].value, "PO-2");
+        assert_eq!(corrected.result.key_value_pairs[0].confidence, 1.0);
+        // The revision corrected from is untouched
+        assert!(matches!(&original.result.documents[0].fields["InvoiceTotal"], DocumentField::String(v) if v == "$10.00"));
+
+        let receipt = FieldCorrection { doc_type: Some("receipt".to_string()), ..correction("Total", "1") };
+        assert!(corrected.correct(vec![receipt], None).is_err());
+        assert!(corrected.correct(Vec::new(), None).is_err());
+        assert!(corrected.correct(vec![correction(" ", "x")], None).is_err());
+    }
+
+    #[test]
+    fn test_review_state() {
+        let mut state = ReviewState::needs_review(vec!["missing field VendorName".to_string()]);
+        assert_eq!(state.status, ReviewStatus::NeedsReview);
+        let revision = ResultRevision::original("op-1", invoice(0.6))
+            .correct(vec![correction("VendorName", "Contoso")], Some("alice".to_string()))
+            .unwrap();
+        state.reviewed(&revision);
+        assert_eq!(state.status, ReviewStatus::Reviewed);
+        assert_eq!(state.revision, 1);
+        assert_eq!(state.reviewed_by.as_deref(), Some("alice"));
+        assert_eq!(ReviewStatus::parse(state.status.as_str()), Some(ReviewStatus::Reviewed));
+    }
+}
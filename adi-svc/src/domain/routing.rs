@@ -0,0 +1,298 @@
+/// Model routing for `analyze/auto`
+///
+/// A document is first classified, by a custom Azure classifier or by
+/// keywords in its filename and text, and its document type is then looked up
+/// in a route table naming the prebuilt or custom model to analyze it with.
+/// Unrecognized types and low-confidence classifications take the fallback
+/// route, `prebuilt-layout` unless configured otherwise.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use super::errors::{DomainError, DomainResult};
+use super::value_objects::ModelType;
+
+/// Document types the keyword heuristics recognize, with the words that suggest each
+const KEYWORDS: &[(&str, &[&str])] = &[
+    ("invoice", &["invoice", "bill to", "amount due", "due date", "remit to", "purchase order"]),
+    ("receipt", &["receipt", "subtotal", "cashier", "change due", "total paid"]),
+    ("w2", &["w-2", "w2", "wage and tax statement", "employer identification number", "federal income tax withheld"]),
+    ("idDocument", &["driver license", "driver's license", "passport", "identity card", "date of birth"]),
+    ("businessCard", &["business card", "businesscard", "business-card"]),
+];
+
+/// Document type and how sure the classifier is of it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DocumentClassification {
+    pub doc_type: String,
+    pub confidence: f32,
+}
+
+/// Where a routing decision came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RoutingMethod {
+    /// A custom Azure classification model
+    Classifier,
+    /// Keywords in the filename and text layer
+    Heuristic,
+}
+
+impl RoutingMethod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Classifier => "classifier",
+            Self::Heuristic => "heuristic",
+        }
+    }
+}
+
+/// Model a document type is analyzed with
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RouteTarget {
+    Prebuilt(ModelType),
+    /// A custom model, by id
+    Custom(String),
+}
+
+impl RouteTarget {
+    pub fn model_id(&self) -> &str {
+        match self {
+            Self::Prebuilt(model_type) => model_type.as_str(),
+            Self::Custom(model_id) => model_id,
+        }
+    }
+}
+
+/// Parses a prebuilt model name such as `prebuilt-invoice` or `custom:<model id>`
+impl FromStr for RouteTarget {
+    type Err = DomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if let Some(model_id) = s.strip_prefix("custom:") {
+            let model_id = model_id.trim();
+            if model_id.is_empty() {
+                return Err(DomainError::InvalidModelType(s.to_string()));
+            }
+            return Ok(Self::Custom(model_id.to_string()));
+        }
+        match ModelType::from_string(s)? {
+            ModelType::Custom => Err(DomainError::InvalidModelType(format!("{}: name the model as custom:<id>", s))),
+            model_type => Ok(Self::Prebuilt(model_type)),
+        }
+    }
+}
+
+/// Which model each document type goes to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModelRoutes {
+    /// Keyed by lowercased document type
+    routes: HashMap<String, RouteTarget>,
+    fallback: RouteTarget,
+    /// Classifications below this confidence take the fallback route
+    min_confidence: f32,
+}
+
+impl Default for ModelRoutes {
+    /// The heuristics' document types routed to the matching prebuilt models
+    fn default() -> Self {
+        let routes = [
+            ("invoice", ModelType::Invoice),
+            ("receipt", ModelType::Receipt),
+            ("w2", ModelType::W2),
+            ("iddocument", ModelType::IdDocument),
+            ("businesscard", ModelType::BusinessCard),
+        ]
+        .into_iter()
+        .map(|(doc_type, model_type)| (doc_type.to_string(), RouteTarget::Prebuilt(model_type)))
+        .collect();
+        Self {
+            routes,
+            fallback: RouteTarget::Prebuilt(ModelType::Layout),
+            min_confidence: 0.5,
+        }
+    }
+}
+
+impl ModelRoutes {
+    /// Default routes overridden by `docType=model,...`, where `*` names the fallback
+    pub fn parse(spec: &str, min_confidence: f32) -> DomainResult<Self> {
+        if !(0.0..=1.0).contains(&min_confidence) {
+            return Err(DomainError::ValidationError(format!(
+                "routing confidence must be between 0 and 1, got {}",
+                min_confidence
+            )));
+        }
+        let mut routes = Self {
+            min_confidence,
+            ..Self::default()
+        };
+        for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (doc_type, target) = entry.split_once('=').ok_or_else(|| {
+                DomainError::ValidationError(format!("invalid route '{}': expected docType=model", entry))
+            })?;
+            let target = target.parse()?;
+            match doc_type.trim() {
+                "*" => routes.fallback = target,
+                "" => return Err(DomainError::ValidationError(format!("invalid route '{}': missing document type", entry))),
+                doc_type => {
+                    routes.routes.insert(doc_type.to_lowercase(), target);
+                }
+            }
+        }
+        Ok(routes)
+    }
+
+    /// Route a document classified by `method`, if it could be classified at all
+    pub fn route(&self, classification: Option<DocumentClassification>, method: RoutingMethod) -> RoutingDecision {
+        let target = classification
+            .as_ref()
+            .filter(|classification| classification.confidence >= self.min_confidence)
+            .and_then(|classification| self.routes.get(&classification.doc_type.to_lowercase()));
+        RoutingDecision {
+            method,
+            fallback: target.is_none(),
+            model_id: target.unwrap_or(&self.fallback).model_id().to_string(),
+            doc_type: classification.as_ref().map(|classification| classification.doc_type.clone()),
+            confidence: classification.map(|classification| classification.confidence),
+        }
+    }
+}
+
+/// How `analyze/auto` chose a document's model, recorded on its operation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RoutingDecision {
+    pub method: RoutingMethod,
+    /// Document type the classifier reported, if any
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doc_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+    /// Prebuilt model name or custom model id the document was sent to
+    pub model_id: String,
+    /// Whether the fallback route was taken
+    #[serde(default)]
+    pub fallback: bool,
+}
+
+impl RoutingDecision {
+    /// Model type to submit under; custom model ids map to `Custom`
+    pub fn model_type(&self) -> ModelType {
+        match ModelType::from_string(&self.model_id) {
+            Ok(model_type) if self.model_id.starts_with("prebuilt-") => model_type,
+            _ => ModelType::Custom,
+        }
+    }
+
+    /// The custom model chosen, if one was
+    pub fn custom_model_id(&self) -> Option<&str> {
+        (self.model_type() == ModelType::Custom).then_some(self.model_id.as_str())
+    }
+
+    /// The model chosen
+    pub fn target(&self) -> RouteTarget {
+        match self.custom_model_id() {
+            Some(model_id) => RouteTarget::Custom(model_id.to_string()),
+            None => RouteTarget::Prebuilt(self.model_type()),
+        }
+    }
+}
+
+/// Guess a document's type from keywords in its filename and text
+///
+/// Each distinct keyword found counts once; the type with the most wins, with
+/// a confidence growing with the number of hits. Ties go to the type listed
+/// first.
+pub fn classify_by_keywords(filename: Option<&str>, text: &str) -> Option<DocumentClassification> {
+    let haystack = format!("{}\n{}", filename.unwrap_or_default(), text).to_lowercase();
+    let (doc_type, hits) = KEYWORDS
+        .iter()
+        .map(|(doc_type, keywords)| {
+            let hits = keywords.iter().filter(|keyword| contains_word(&haystack, keyword)).count();
+            (*doc_type, hits)
+        })
+        .fold(("", 0), |best, candidate| if candidate.1 > best.1 { candidate } else { best });
+    (hits > 0).then(|| DocumentClassification {
+        doc_type: doc_type.to_string(),
+        confidence: (0.5 + 0.15 * hits as f32).min(0.95),
+    })
+}
+
+/// Whether `keyword` occurs in `haystack` not as part of a longer word
+fn contains_word(haystack: &str, keyword: &str) -> bool {
+    haystack.match_indices(keyword).any(|(start, _)| {
+        let before = haystack[..start].chars().next_back();
+        let after = haystack[start + keyword.len()..].chars().next();
+        !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_by_keywords() {
+        let invoice = classify_by_keywords(Some("scan-0042.pdf"), "INVOICE\nBill To: Contoso\nAmount Due: $10.00").unwrap();
+        assert_eq!(invoice.doc_type, "invoice");
+        assert!((invoice.confidence - 0.95).abs() < 1e-6);
+
+        let w2 = classify_by_keywords(Some("2023_W2.pdf"), "").unwrap();
+        assert_eq!(w2.doc_type, "w2");
+        assert!((w2.confidence - 0.65).abs() < 1e-6);
+
+        // Keywords inside longer words don't count
+        assert_eq!(classify_by_keywords(Some("receipts-archive.zip"), "reinvoiced"), None);
+        assert_eq!(classify_by_keywords(None, "Quarterly report"), None);
+    }
+
+    #[test]
+    fn test_route_targets() {
+        assert_eq!("prebuilt-invoice".parse::<RouteTarget>().unwrap(), RouteTarget::Prebuilt(ModelType::Invoice));
+        assert_eq!(
+            " custom: contracts-v2 ".parse::<RouteTarget>().unwrap(),
+            RouteTarget::Custom("contracts-v2".to_string())
+        );
+        assert!("custom".parse::<RouteTarget>().is_err());
+        assert!("custom:".parse::<RouteTarget>().is_err());
+        assert!("prebuilt-contract".parse::<RouteTarget>().is_err());
+    }
+
+    #[test]
+    fn test_routing() {
+        let routes = ModelRoutes::parse("contract=custom:contracts-v2, *=prebuilt-read", 0.6).unwrap();
+        let classified = |doc_type: &str, confidence| {
+            Some(DocumentClassification {
+                doc_type: doc_type.to_string(),
+                confidence,
+            })
+        };
+
+        let contract = routes.route(classified("Contract", 0.9), RoutingMethod::Classifier);
+        assert_eq!(contract.model_id, "contracts-v2");
+        assert_eq!(contract.model_type(), ModelType::Custom);
+        assert_eq!(contract.custom_model_id(), Some("contracts-v2"));
+        assert!(!contract.fallback);
+
+        // Default routes stay in place
+        let invoice = routes.route(classified("invoice", 0.8), RoutingMethod::Heuristic);
+        assert_eq!(invoice.model_type(), ModelType::Invoice);
+        assert_eq!(invoice.custom_model_id(), None);
+
+        for decision in [
+            routes.route(classified("invoice", 0.5), RoutingMethod::Heuristic),
+            routes.route(classified("letter", 0.9), RoutingMethod::Classifier),
+            routes.route(None, RoutingMethod::Heuristic),
+        ] {
+            assert_eq!(decision.model_type(), ModelType::Read);
+            assert!(decision.fallback);
+        }
+        assert_eq!(ModelRoutes::default().route(None, RoutingMethod::Heuristic).model_id, "prebuilt-layout");
+
+        assert!(ModelRoutes::parse("invoice", 0.5).is_err());
+        assert!(ModelRoutes::parse("=prebuilt-read", 0.5).is_err());
+        assert!(ModelRoutes::parse("", 1.5).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+/// Search index documents
+///
+/// A succeeded operation is pushed to a search index as one flat document:
+/// the extracted content, its key fields and the operation's metadata. An
+/// index mapping names the index field each value lands in, so documents fit
+/// an existing index schema rather than one of ours.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+use super::errors::{DomainError, DomainResult};
+use super::models::{AnalysisOperation, AnalysisResult, DocumentField};
+
+/// Mapping used when none is configured
+const DEFAULT_MAPPING: &str = "id=operation_id,tenant_id=tenant_id,model=model,filename=filename,\
+content_type=content_type,created_at=created_at,page_count=page_count,doc_types=doc_types,\
+content=content,fields=fields";
+
+/// What a succeeded operation contributes to the search index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SearchDocument {
+    pub operation_id: String,
+    pub tenant_id: String,
+    pub model: String,
+    pub filename: Option<String>,
+    pub content_type: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub page_count: Option<u32>,
+    pub content: String,
+    /// Types of the documents a prebuilt or custom model extracted
+    pub doc_types: Vec<String>,
+    /// Key-value pairs and document fields by name; document fields win over pairs with the same key
+    pub fields: BTreeMap<String, Value>,
+}
+
+impl SearchDocument {
+    pub fn new(operation: &AnalysisOperation, result: &AnalysisResult) -> Self {
+        let mut fields: BTreeMap<String, Value> = result
+            .key_value_pairs
+            .iter()
+            .map(|pair| (pair.key.clone(), Value::String(pair.value.clone())))
+            .collect();
+        for document in &result.documents {
+            for (name, field) in &document.fields {
+                fields.insert(name.clone(), field_json(field));
+            }
+        }
+        Self {
+            operation_id: operation.operation_id.clone(),
+            tenant_id: operation.tenant_id.to_string(),
+            model: operation.model_type.as_str().to_string(),
+            filename: operation.filename.clone(),
+            content_type: operation.content_type.clone(),
+            created_at: operation.created_at,
+            page_count: operation.page_count.or(Some(result.pages.len() as u32)),
+            content: result.content.clone(),
+            doc_types: result.documents.iter().map(|document| document.doc_type.clone()).collect(),
+            fields,
+        }
+    }
+}
+
+/// A value of a [`SearchDocument`] that can be mapped to an index field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SearchSource {
+    OperationId,
+    TenantId,
+    Model,
+    Filename,
+    ContentType,
+    /// RFC 3339 timestamp
+    CreatedAt,
+    PageCount,
+    Content,
+    DocTypes,
+    /// Every field as `name: value` text, for a string collection
+    Fields,
+    /// One field's value, `null` when the result lacks it
+    Field(String),
+}
+
+impl SearchSource {
+    fn parse(source: &str) -> DomainResult<Self> {
+        let source = source.trim();
+        if let Some(name) = source.strip_prefix("field:") {
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(DomainError::ValidationError("index mapping 'field:' needs a field name".to_string()));
+            }
+            return Ok(Self::Field(name.to_string()));
+        }
+        match source {
+            "operation_id" => Ok(Self::OperationId),
+            "tenant_id" => Ok(Self::TenantId),
+            "model" => Ok(Self::Model),
+            "filename" => Ok(Self::Filename),
+            "content_type" => Ok(Self::ContentType),
+            "created_at" => Ok(Self::CreatedAt),
+            "page_count" => Ok(Self::PageCount),
+            "content" => Ok(Self::Content),
+            "doc_types" => Ok(Self::DocTypes),
+            "fields" => Ok(Self::Fields),
+            other => Err(DomainError::ValidationError(format!("unknown index mapping source '{}'", other))),
+        }
+    }
+
+    fn value(&self, document: &SearchDocument) -> Value {
+        match self {
+            Self::OperationId => Value::String(document.operation_id.clone()),
+            Self::TenantId => Value::String(document.tenant_id.clone()),
+            Self::Model => Value::String(document.model.clone()),
+            Self::Filename => document.filename.clone().map_or(Value::Null, Value::String),
+            Self::ContentType => document.content_type.clone().map_or(Value::Null, Value::String),
+            Self::CreatedAt => Value::String(document.created_at.to_rfc3339()),
+            Self::PageCount => document.page_count.map_or(Value::Null, Value::from),
+            Self::Content => Value::String(document.content.clone()),
+            Self::DocTypes => Value::from(document.doc_types.clone()),
+            Self::Fields => Value::from(
+                document
+                    .fields
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", name, value_text(value)))
+                    .collect::<Vec<_>>(),
+            ),
+            Self::Field(name) => document.fields.get(name).cloned().unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// Index field each [`SearchSource`] is written to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexMapping {
+    entries: Vec<(String, SearchSource)>,
+}
+
+impl Default for IndexMapping {
+    /// Every metadata value under its own name, the content, and all fields as text
+    fn default() -> Self {
+        Self::parse(DEFAULT_MAPPING).expect("default index mapping is valid")
+    }
+}
+
+impl IndexMapping {
+    /// `indexField=source,...`, where a source is a [`SearchDocument`] value
+    /// such as `content` or `field:<name>`; the default mapping when `spec` is empty
+    ///
+    /// The first entry is the index's key and must map `operation_id`.
+    pub fn parse(spec: &str) -> DomainResult<Self> {
+        if spec.trim().is_empty() {
+            return Ok(Self::default());
+        }
+        let mut entries: Vec<(String, SearchSource)> = Vec::new();
+        for entry in spec.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            let (index_field, source) = entry.split_once('=').ok_or_else(|| {
+                DomainError::ValidationError(format!("invalid index mapping '{}': expected indexField=source", entry))
+            })?;
+            let index_field = index_field.trim();
+            if index_field.is_empty() {
+                return Err(DomainError::ValidationError(format!(
+                    "invalid index mapping '{}': missing index field",
+                    entry
+                )));
+            }
+            if entries.iter().any(|(existing, _)| existing == index_field) {
+                return Err(DomainError::ValidationError(format!(
+                    "index field '{}' is mapped twice",
+                    index_field
+                )));
+            }
+            entries.push((index_field.to_string(), SearchSource::parse(source)?));
+        }
+        match entries.first() {
+            Some((_, SearchSource::OperationId)) => Ok(Self { entries }),
+            _ => Err(DomainError::ValidationError(
+                "the first index mapping entry is the key and must map operation_id".to_string(),
+            )),
+        }
+    }
+
+    /// Index field holding the operation id
+    pub fn key_field(&self) -> &str {
+        &self.entries[0].0
+    }
+
+    /// Index fields and their sources, key first
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &SearchSource)> {
+        self.entries.iter().map(|(index_field, source)| (index_field.as_str(), source))
+    }
+
+    /// The index document for `document`
+    pub fn apply(&self, document: &SearchDocument) -> Map<String, Value> {
+        self.entries
+            .iter()
+            .map(|(index_field, source)| (index_field.clone(), source.value(document)))
+            .collect()
+    }
+}
+
+fn field_json(field: &DocumentField) -> Value {
+    match field {
+        DocumentField::String(value) => Value::String(value.clone()),
+        DocumentField::Number(value) => Value::from(*value),
+        DocumentField::Integer(value) => Value::from(*value),
+        DocumentField::Date(value) => Value::String(value.to_string()),
+        DocumentField::Time(value) => Value::String(value.to_string()),
+        DocumentField::Boolean(value) => Value::Bool(*value),
+        DocumentField::Array(items) => Value::Array(items.iter().map(field_json).collect()),
+        DocumentField::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, field)| (name.clone(), field_json(field)))
+                .collect(),
+        ),
+    }
+}
+
+fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ExtractedDocument, KeyValuePair, ModelType, TenantId};
+    use std::collections::HashMap;
+
+    fn invoice() -> (AnalysisOperation, AnalysisResult) {
+        let mut operation = AnalysisOperation::new(ModelType::Invoice);
+        operation.tenant_id = TenantId::new("acme").unwrap();
+        operation.filename = Some("invoice.pdf".to_string());
+        let result = AnalysisResult {
+            content: "Invoice INV-1 Total $120.00".to_string(),
+            key_value_pairs: vec![
+                KeyValuePair { key: "PO Number".to_string(), value: "PO-7".to_string(), confidence: 0.9 },
+                KeyValuePair { key: "InvoiceTotal".to_string(), value: "$120".to_string(), confidence: 0.9 },
+            ],
+            documents: vec![ExtractedDocument {
+                doc_type: "invoice".to_string(),
+                fields: HashMap::from([
+                    ("InvoiceId".to_string(), DocumentField::String("INV-1".to_string())),
+                    ("InvoiceTotal".to_string(), DocumentField::Number(120.0)),
+                ]),
+                confidence: 0.95,
+            }],
+            ..Default::default()
+        };
+        (operation, result)
+    }
+
+    #[test]
+    fn test_default_mapping() {
+        let (operation, result) = invoice();
+        let document = SearchDocument::new(&operation, &result);
+        assert_eq!(document.fields["InvoiceTotal"], Value::from(120.0));
+
+        let mapping = IndexMapping::default();
+        assert_eq!(mapping.key_field(), "id");
+        let indexed = mapping.apply(&document);
+        assert_eq!(indexed["id"], operation.operation_id);
+        assert_eq!(indexed["tenant_id"], "acme");
+        assert_eq!(indexed["model"], "prebuilt-invoice");
+        assert_eq!(indexed["content_type"], Value::Null);
+        assert_eq!(indexed["page_count"], 0);
+        assert_eq!(indexed["doc_types"], serde_json::json!(["invoice"]));
+        assert_eq!(
+            indexed["fields"],
+            serde_json::json!(["InvoiceId: INV-1", "InvoiceTotal: 120.0", "PO Number: PO-7"])
+        );
+    }
+
+    #[test]
+    fn test_custom_mapping() {
+        let (operation, result) = invoice();
+        let mapping = IndexMapping::parse("docId=operation_id, body=content, total=field:InvoiceTotal, due=field:DueDate").unwrap();
+        let indexed = mapping.apply(&SearchDocument::new(&operation, &result));
+        assert_eq!(indexed.len(), 4);
+        assert_eq!(indexed["body"], result.content);
+        assert_eq!(indexed["total"], Value::from(120.0));
+        assert_eq!(indexed["due"], Value::Null);
+
+        assert!(IndexMapping::parse("body=content,id=operation_id").is_err());
+        assert!(IndexMapping::parse("id=operation_id,body=text").is_err());
+        assert!(IndexMapping::parse("id=operation_id,id=content").is_err());
+        assert!(IndexMapping::parse("id=operation_id,total=field:").is_err());
+        assert_eq!(IndexMapping::parse(" ").unwrap(), IndexMapping::default());
+    }
+}
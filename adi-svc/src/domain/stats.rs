@@ -0,0 +1,180 @@
+/// Operation statistics for dashboards
+///
+/// A tenant's operations are grouped by UTC day of creation, model or
+/// status, and each group is counted by outcome. Completion time runs from
+/// creation to the last update of operations that finished.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::errors::{DomainError, DomainResult};
+use super::models::AnalysisOperation;
+use super::value_objects::OperationStatus;
+
+/// What operation statistics are grouped by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationStatsGroup {
+    /// UTC day of creation, as `2024-03-01`
+    #[default]
+    Day,
+    /// Model, named as Azure does, e.g. `prebuilt-invoice`
+    Model,
+    Status,
+}
+
+impl OperationStatsGroup {
+    /// The group `operation` falls in
+    pub fn key(&self, operation: &AnalysisOperation) -> String {
+        match self {
+            Self::Day => operation.created_at.format("%Y-%m-%d").to_string(),
+            Self::Model => operation.model_type.as_str().to_string(),
+            Self::Status => format!("{:?}", operation.status).to_lowercase(),
+        }
+    }
+}
+
+/// Which of a tenant's operations statistics cover
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OperationStatsQuery {
+    pub group_by: OperationStatsGroup,
+    /// Only operations created at or after this instant
+    pub from: Option<DateTime<Utc>>,
+    /// Only operations created before this instant
+    pub to: Option<DateTime<Utc>>,
+}
+
+impl OperationStatsQuery {
+    pub fn validate(&self) -> DomainResult<()> {
+        if let (Some(from), Some(to)) = (self.from, self.to) {
+            if from >= to {
+                return Err(DomainError::ValidationError("from must be before to".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn matches(&self, operation: &AnalysisOperation) -> bool {
+        self.from.is_none_or(|from| operation.created_at >= from) && self.to.is_none_or(|to| operation.created_at < to)
+    }
+}
+
+/// Aggregates of one group of operations
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OperationStats {
+    /// Day, model or status the group covers
+    pub key: String,
+    pub operations: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+    pub canceled: u64,
+    /// Median seconds from creation to completion of finished operations
+    pub median_completion_secs: Option<f64>,
+    /// Share of finished operations that failed
+    pub failure_ratio: Option<f64>,
+}
+
+impl OperationStats {
+    /// Failed operations over finished ones, `None` before any finished
+    pub fn ratio(failed: u64, finished: u64) -> Option<f64> {
+        (finished > 0).then(|| failed as f64 / finished as f64)
+    }
+}
+
+/// Statistics of `operations` passing `query`, ordered by key
+///
+/// For trackers that cannot aggregate where the operations are stored.
+pub fn operation_stats<'a>(
+    operations: impl IntoIterator<Item = &'a AnalysisOperation>,
+    query: &OperationStatsQuery,
+) -> Vec<OperationStats> {
+    let mut groups: BTreeMap<String, Vec<&AnalysisOperation>> = BTreeMap::new();
+    for operation in operations.into_iter().filter(|operation| query.matches(operation)) {
+        groups.entry(query.group_by.key(operation)).or_default().push(operation);
+    }
+    groups
+        .into_iter()
+        .map(|(key, operations)| {
+            let count = |status| operations.iter().filter(|operation| operation.status == status).count() as u64;
+            let (succeeded, failed, canceled) =
+                (count(OperationStatus::Succeeded), count(OperationStatus::Failed), count(OperationStatus::Canceled));
+            let mut completion: Vec<f64> = operations
+                .iter()
+                .filter(|operation| operation.status.is_terminal())
+                .map(|operation| (operation.last_updated - operation.created_at).num_milliseconds() as f64 / 1000.0)
+                .collect();
+            completion.sort_by(f64::total_cmp);
+            OperationStats {
+                key,
+                operations: operations.len() as u64,
+                succeeded,
+                failed,
+                canceled,
+                median_completion_secs: median(&completion),
+                failure_ratio: OperationStats::ratio(failed, succeeded + failed + canceled),
+            }
+        })
+        .collect()
+}
+
+/// Median of sorted values, interpolated as PostgreSQL's `percentile_cont(0.5)`
+fn median(sorted: &[f64]) -> Option<f64> {
+    let middle = sorted.len() / 2;
+    match sorted.len() {
+        0 => None,
+        len if len % 2 == 1 => Some(sorted[middle]),
+        _ => Some((sorted[middle - 1] + sorted[middle]) / 2.0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::ModelType;
+    use chrono::{Duration, TimeZone};
+
+    fn operation(model_type: ModelType, status: OperationStatus, day: u32, secs: i64) -> AnalysisOperation {
+        let mut operation = AnalysisOperation::new(model_type);
+        operation.status = status;
+        operation.created_at = Utc.with_ymd_and_hms(2024, 3, day, 12, 0, 0).unwrap();
+        operation.last_updated = operation.created_at + Duration::seconds(secs);
+        operation
+    }
+
+    #[test]
+    fn test_operation_stats() {
+        let operations = vec![
+            operation(ModelType::Invoice, OperationStatus::Succeeded, 1, 4),
+            operation(ModelType::Invoice, OperationStatus::Failed, 1, 10),
+            operation(ModelType::Invoice, OperationStatus::Succeeded, 1, 6),
+            operation(ModelType::Read, OperationStatus::Running, 1, 100),
+            operation(ModelType::Read, OperationStatus::Succeeded, 2, 2),
+        ];
+
+        let by_day = operation_stats(&operations, &OperationStatsQuery::default());
+        assert_eq!(by_day.iter().map(|stats| stats.key.as_str()).collect::<Vec<_>>(), vec!["2024-03-01", "2024-03-02"]);
+        let first = &by_day[0];
+        assert_eq!((first.operations, first.succeeded, first.failed), (4, 2, 1));
+        assert_eq!(first.median_completion_secs, Some(6.0), "running operations are not timed");
+        assert_eq!(first.failure_ratio, Some(1.0 / 3.0));
+
+        let query = OperationStatsQuery { group_by: OperationStatsGroup::Model, ..Default::default() };
+        let by_model = operation_stats(&operations, &query);
+        assert_eq!(by_model[0].key, "prebuilt-invoice");
+        assert_eq!(by_model[1].median_completion_secs, Some(2.0));
+
+        let query = OperationStatsQuery {
+            group_by: OperationStatsGroup::Status,
+            from: Some(Utc.with_ymd_and_hms(2024, 3, 2, 0, 0, 0).unwrap()),
+            to: None,
+        };
+        let by_status = operation_stats(&operations, &query);
+        assert_eq!(by_status.len(), 1);
+        assert_eq!((by_status[0].key.as_str(), by_status[0].failure_ratio), ("succeeded", Some(0.0)));
+
+        assert_eq!(median(&[1.0, 2.0, 4.0, 8.0]), Some(3.0));
+        let backwards = OperationStatsQuery { from: query.from, to: query.from, ..Default::default() };
+        assert!(backwards.validate().is_err());
+    }
+}
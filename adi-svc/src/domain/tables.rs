@@ -0,0 +1,181 @@
+/// Tables as records
+///
+/// Turns a table's cells into one record per data row, keyed by the column
+/// headers. Spanned cells repeat their content in every row and column they
+/// cover; header rows stacked over one another combine into one name.
+
+use serde::{Serialize, Serializer};
+
+use super::models::{CellKind, DocumentTable};
+
+/// One data row of a table, as header and value pairs in column order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRecord(pub Vec<(String, String)>);
+
+impl TableRecord {
+    pub fn get(&self, header: &str) -> Option<&str> {
+        self.0.iter().find(|(name, _)| name == header).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Serialized as a JSON object keeping the columns in order
+impl Serialize for TableRecord {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.0.iter().map(|(name, value)| (name, value)))
+    }
+}
+
+/// A table's column headers and its data rows as records
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct TableRecords {
+    pub headers: Vec<String>,
+    pub records: Vec<TableRecord>,
+}
+
+impl DocumentTable {
+    /// One record per data row, keyed by the column headers
+    ///
+    /// Header rows are the leading rows holding only column header cells, or
+    /// the first row when the table marks none. Columns without a header are
+    /// named `column_<n>` and repeated names get a `_<n>` suffix, counted
+    /// from 1. Rows with no content are skipped.
+    pub fn records(&self) -> TableRecords {
+        let rows = self.row_count.max(0) as usize;
+        let columns = self.column_count.max(0) as usize;
+        let mut grid: Vec<Vec<Option<(CellKind, &str)>>> = vec![vec![None; columns]; rows];
+        for cell in &self.cells {
+            let (row, column) = (cell.row_index.max(0) as usize, cell.column_index.max(0) as usize);
+            let row_span = cell.row_span.max(1) as usize;
+            let column_span = cell.column_span.max(1) as usize;
+            for spanned in grid.iter_mut().skip(row).take(row_span) {
+                for slot in spanned.iter_mut().skip(column).take(column_span) {
+                    *slot = Some((cell.kind, cell.content.trim()));
+                }
+            }
+        }
+
+        let is_header = |row: &Vec<Option<(CellKind, &str)>>| {
+            row.iter().flatten().next().is_some()
+                && row
+                    .iter()
+                    .flatten()
+                    .all(|(kind, _)| matches!(kind, CellKind::ColumnHeader | CellKind::StubHead))
+        };
+        let header_rows = match grid.iter().take_while(|row| is_header(row)).count() {
+            0 => rows.min(1),
+            count => count,
+        };
+
+        let mut headers: Vec<String> = (0..columns)
+            .map(|column| {
+                let mut parts: Vec<&str> = Vec::new();
+                for row in &grid[..header_rows] {
+                    if let Some((_, content)) = row[column] {
+                        // A header spanning several rows is named once
+                        if !content.is_empty() && parts.last() != Some(&content) {
+                            parts.push(content);
+                        }
+                    }
+                }
+                match parts.join(" ") {
+                    name if name.is_empty() => format!("column_{}", column + 1),
+                    name => name,
+                }
+            })
+            .collect();
+        let names = headers.clone();
+        for (column, header) in headers.iter_mut().enumerate() {
+            let earlier = names[..column].iter().filter(|name| *name == header).count();
+            if earlier > 0 || names[column + 1..].contains(header) {
+                *header = format!("{}_{}", header, earlier + 1);
+            }
+        }
+
+        let records = grid[header_rows..]
+            .iter()
+            .filter(|row| row.iter().flatten().any(|(_, content)| !content.is_empty()))
+            .map(|row| {
+                TableRecord(
+                    headers
+                        .iter()
+                        .zip(row)
+                        .map(|(header, cell)| (header.clone(), cell.map(|(_, content)| content).unwrap_or_default().to_string()))
+                        .collect(),
+                )
+            })
+            .collect();
+        TableRecords { headers, records }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::TableCell;
+
+    fn cell(kind: CellKind, row_index: i32, column_index: i32, spans: (i32, i32), content: &str) -> TableCell {
+        TableCell {
+            kind,
+            row_index,
+            column_index,
+            row_span: spans.0,
+            column_span: spans.1,
+            content: content.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_records_with_spans_and_stacked_headers() {
+        use CellKind::*;
+        // | Item      | Amount       |
+        // |           | Net | Gross  |
+        // | Widget    | 10  | 12     |
+        // | Gadget    | 5 (both)     |
+        let table = DocumentTable {
+            row_count: 5,
+            column_count: 3,
+            cells: vec![
+                cell(ColumnHeader, 0, 0, (2, 1), "Item"),
+                cell(ColumnHeader, 0, 1, (1, 2), "Amount"),
+                cell(ColumnHeader, 1, 1, (1, 1), "Net"),
+                cell(ColumnHeader, 1, 2, (1, 1), "Gross"),
+                cell(RowHeader, 2, 0, (1, 1), "Widget"),
+                cell(Content, 2, 1, (1, 1), "10"),
+                cell(Content, 2, 2, (1, 1), "12"),
+                cell(RowHeader, 3, 0, (1, 1), "Gadget"),
+                cell(Content, 3, 1, (1, 2), " 5 "),
+                cell(Content, 4, 0, (1, 1), ""),
+            ],
+        };
+        let records = table.records();
+        assert_eq!(records.headers, ["Item", "Amount Net", "Amount Gross"]);
+        assert_eq!(records.records.len(), 2);
+        assert_eq!(records.records[1].get("Amount Gross"), Some("5"));
+        assert_eq!(
+            serde_json::to_string(&records.records[0]).unwrap(),
+            r#"{"Item":"Widget","Amount Net":"10","Amount Gross":"12"}"#
+        );
+    }
+
+    #[test]
+    fn test_records_without_header_cells() {
+        use CellKind::*;
+        let table = DocumentTable {
+            row_count: 2,
+            column_count: 3,
+            cells: vec![
+                cell(Content, 0, 0, (1, 1), "Name"),
+                cell(Content, 0, 1, (1, 1), "Name"),
+                cell(Content, 1, 0, (1, 1), "a"),
+                cell(Content, 1, 1, (1, 1), "b"),
+                cell(Content, 1, 2, (1, 1), "c"),
+            ],
+        };
+        let records = table.records();
+        assert_eq!(records.headers, ["Name_1", "Name_2", "column_3"]);
+        assert_eq!(records.records[0].0[2], ("column_3".to_string(), "c".to_string()));
+
+        let empty = DocumentTable { row_count: 0, column_count: 0, cells: Vec::new() };
+        assert_eq!(empty.records(), TableRecords { headers: Vec::new(), records: Vec::new() });
+    }
+}
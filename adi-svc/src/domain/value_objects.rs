@@ -1,9 +1,12 @@
 use super::errors::{DomainError, DomainResult};
+use super::review::ReviewStatus;
+use bytes::Bytes;
+use chrono::Datelike;
 use serde::{Deserialize, Serialize};
 use std::str::FromStr;
 
 /// Model type for document analysis
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ModelType {
     Read,
@@ -43,6 +46,20 @@ impl ModelType {
             _ => Err(DomainError::InvalidModelType(s.to_string())),
         }
     }
+    
+    /// Azure pay-as-you-go list price in USD per 1,000 pages, for cost estimates
+    pub fn list_price_per_1000_pages(&self) -> f64 {
+        match self {
+            Self::Read => 1.5,
+            Self::Layout
+            | Self::Invoice
+            | Self::Receipt
+            | Self::IdDocument
+            | Self::BusinessCard
+            | Self::W2 => 10.0,
+            Self::Custom => 30.0,
+        }
+    }
 }
 
 impl FromStr for ModelType {
@@ -63,7 +80,8 @@ impl std::fmt::Display for ModelType {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum DocumentSource {
     Url(String),
-    Bytes(Vec<u8>),
+    /// Shared rather than copied as it moves from upload to storage and Azure
+    Bytes(Bytes),
 }
 
 impl DocumentSource {
@@ -97,6 +115,160 @@ impl DocumentSource {
     }
 }
 
+/// Document file format, identified from its leading bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    Pdf,
+    Jpeg,
+    Png,
+    Tiff,
+    Heif,
+    Docx,
+}
+
+impl DocumentFormat {
+    /// Identify a supported format from magic bytes, ignoring any declared content type
+    pub fn detect(bytes: &[u8]) -> DomainResult<Self> {
+        const HEIF_BRANDS: [&[u8]; 8] = [
+            b"heic", b"heix", b"heim", b"heis", b"hevc", b"hevx", b"mif1", b"msf1",
+        ];
+        
+        let format = if bytes.starts_with(b"%PDF-") {
+            Self::Pdf
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Self::Jpeg
+        } else if bytes.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+            Self::Png
+        } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+            Self::Tiff
+        } else if bytes.len() >= 12 && &bytes[4..8] == b"ftyp" && HEIF_BRANDS.contains(&&bytes[8..12]) {
+            Self::Heif
+        } else if bytes.starts_with(b"PK\x03\x04") && contains(bytes, b"word/") {
+            // OOXML is a zip; Word documents carry entries under word/
+            Self::Docx
+        } else {
+            let prefix: Vec<String> = bytes.iter().take(8).map(|b| format!("{:02x}", b)).collect();
+            return Err(DomainError::UnsupportedDocumentType(format!(
+                "unrecognized content starting with {}",
+                if prefix.is_empty() { "nothing".to_string() } else { prefix.join(" ") }
+            )));
+        };
+        Ok(format)
+    }
+    
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            Self::Pdf => "application/pdf",
+            Self::Jpeg => "image/jpeg",
+            Self::Png => "image/png",
+            Self::Tiff => "image/tiff",
+            Self::Heif => "image/heif",
+            Self::Docx => "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        }
+    }
+    
+    /// Usual file extension, without the dot
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Pdf => "pdf",
+            Self::Jpeg => "jpg",
+            Self::Png => "png",
+            Self::Tiff => "tiff",
+            Self::Heif => "heif",
+            Self::Docx => "docx",
+        }
+    }
+}
+
+/// Clean-up applied to image uploads before submission
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImagePreprocessing {
+    /// Straighten text rotated by up to a few degrees
+    pub deskew: bool,
+    /// Drop colour, keeping luminance only
+    pub grayscale: bool,
+    /// Downscale so a page-sized capture is at most this many dots per inch
+    pub max_dpi: Option<u32>,
+    /// Re-encode as JPEG at this quality (1-100)
+    pub jpeg_quality: Option<u8>,
+}
+
+impl ImagePreprocessing {
+    pub const MIN_DPI: u32 = 72;
+    pub const MAX_DPI: u32 = 1200;
+    
+    pub fn validate(&self) -> DomainResult<()> {
+        if let Some(dpi) = self.max_dpi {
+            if !(Self::MIN_DPI..=Self::MAX_DPI).contains(&dpi) {
+                return Err(DomainError::ValidationError(format!(
+                    "max_dpi must be between {} and {}",
+                    Self::MIN_DPI,
+                    Self::MAX_DPI
+                )));
+            }
+        }
+        if let Some(quality) = self.jpeg_quality {
+            if !(1..=100).contains(&quality) {
+                return Err(DomainError::ValidationError(
+                    "jpeg_quality must be between 1 and 100".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Whether no step is enabled
+    pub fn is_noop(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack.windows(needle.len()).any(|window| window == needle)
+}
+
+/// Filename and content type of an uploaded document
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DocumentMetadata {
+    pub filename: String,
+    pub content_type: String,
+}
+
+impl DocumentMetadata {
+    pub const DEFAULT_FILENAME: &'static str = "uploaded_document";
+    pub const DEFAULT_CONTENT_TYPE: &'static str = "application/octet-stream";
+    
+    /// Create metadata, stripping any directory components from the filename
+    /// and falling back to defaults for empty values
+    pub fn new(filename: impl Into<String>, content_type: impl Into<String>) -> Self {
+        let filename = filename.into();
+        let filename = filename
+            .rsplit(['/', '\\'])
+            .next()
+            .map(str::trim)
+            .filter(|name| !name.is_empty() && *name != "." && *name != "..")
+            .unwrap_or(Self::DEFAULT_FILENAME)
+            .to_string();
+        
+        let content_type = content_type.into();
+        let content_type = if content_type.trim().is_empty() {
+            Self::DEFAULT_CONTENT_TYPE.to_string()
+        } else {
+            content_type.trim().to_string()
+        };
+        
+        Self { filename, content_type }
+    }
+}
+
+impl Default for DocumentMetadata {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_FILENAME, Self::DEFAULT_CONTENT_TYPE)
+    }
+}
+
 /// Locale for document analysis
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Locale(String);
@@ -123,31 +295,193 @@ impl Default for Locale {
     }
 }
 
+/// Tenant owning operations, results and stored documents
+///
+/// Up to 64 ASCII letters, digits, `-` or `_`, so it is safe as a storage
+/// path segment. Callers that name no tenant belong to `default`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TenantId(String);
+
+impl TenantId {
+    pub const DEFAULT: &'static str = "default";
+    pub const MAX_LEN: usize = 64;
+    
+    pub fn new(tenant_id: impl Into<String>) -> DomainResult<Self> {
+        let tenant_id = tenant_id.into();
+        let valid = !tenant_id.is_empty()
+            && tenant_id.len() <= Self::MAX_LEN
+            && tenant_id.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_');
+        if valid {
+            Ok(Self(tenant_id))
+        } else {
+            Err(DomainError::InvalidTenant(tenant_id))
+        }
+    }
+    
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+    
+    pub fn is_default(&self) -> bool {
+        self.0 == Self::DEFAULT
+    }
+}
+
+impl Default for TenantId {
+    fn default() -> Self {
+        Self(Self::DEFAULT.to_string())
+    }
+}
+
+impl TryFrom<String> for TenantId {
+    type Error = DomainError;
+    
+    fn try_from(value: String) -> DomainResult<Self> {
+        Self::new(value)
+    }
+}
+
+impl From<TenantId> for String {
+    fn from(tenant_id: TenantId) -> Self {
+        tenant_id.0
+    }
+}
+
+impl std::fmt::Display for TenantId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
 /// Page range for document analysis
+///
+/// Comma-separated terms of a single page `N`, a span `A-B` or an open-ended
+/// `A-`, in any order but never overlapping; no terms selects every page.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
-pub struct PageRange(Vec<String>);
+#[serde(try_from = "Vec<String>", into = "Vec<String>")]
+pub struct PageRange {
+    terms: Vec<String>,
+    spans: Vec<PageSpan>,
+}
+
+/// One term of a [`PageRange`], with 1-based inclusive bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PageSpan {
+    pub start: u32,
+    /// `None` runs to the last page
+    pub end: Option<u32>,
+}
+
+impl PageSpan {
+    fn parse(term: &str) -> DomainResult<Self> {
+        let page = |number: &str| -> DomainResult<u32> {
+            let number = number.trim();
+            if number.is_empty() || !number.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(DomainError::InvalidPageRange(format!("'{}' is not a page number in '{}'", number, term)));
+            }
+            match number.parse::<u32>() {
+                Ok(0) => Err(DomainError::InvalidPageRange(format!("pages start at 1, got 0 in '{}'", term))),
+                Ok(page) => Ok(page),
+                Err(_) => Err(DomainError::InvalidPageRange(format!("page {} in '{}' is too large", number, term))),
+            }
+        };
+        match term.split_once('-') {
+            Some((start, end)) if end.trim().is_empty() => Ok(Self { start: page(start)?, end: None }),
+            Some((start, end)) => {
+                let (start, end) = (page(start)?, page(end)?);
+                if end < start {
+                    return Err(DomainError::InvalidPageRange(format!("'{}' is reversed", term)));
+                }
+                Ok(Self { start, end: Some(end) })
+            }
+            None => {
+                let page = page(term)?;
+                Ok(Self { start: page, end: Some(page) })
+            }
+        }
+    }
+
+    pub fn contains(&self, page: u32) -> bool {
+        page >= self.start && self.end.is_none_or(|end| page <= end)
+    }
+}
+
+impl std::fmt::Display for PageSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.end {
+            Some(end) if end == self.start => write!(f, "{}", self.start),
+            Some(end) => write!(f, "{}-{}", self.start, end),
+            None => write!(f, "{}-", self.start),
+        }
+    }
+}
 
 impl PageRange {
+    /// Parse `pages`, each holding one or more comma-separated terms
     pub fn new(pages: Vec<String>) -> DomainResult<Self> {
-        // Validate page ranges (e.g., "1", "1-3", "1,3,5-7")
+        let mut spans = Vec::new();
         for page in &pages {
-            if page.is_empty() {
-                return Err(DomainError::InvalidPageRange("Empty page range".to_string()));
+            for term in page.split(',') {
+                let term = term.trim();
+                if term.is_empty() {
+                    return Err(DomainError::InvalidPageRange(format!("empty term in '{}'", page)));
+                }
+                spans.push(PageSpan::parse(term)?);
             }
         }
-        Ok(Self(pages))
+
+        let mut sorted = spans.clone();
+        sorted.sort_by_key(|span| span.start);
+        for pair in sorted.windows(2) {
+            if pair[0].contains(pair[1].start) {
+                return Err(DomainError::InvalidPageRange(format!("'{}' overlaps '{}'", pair[0], pair[1])));
+            }
+        }
+
+        Ok(Self { terms: spans.iter().map(PageSpan::to_string).collect(), spans })
     }
     
     pub fn all() -> Self {
-        Self(vec![])
+        Self { terms: Vec::new(), spans: Vec::new() }
     }
     
+    /// The terms in their normalized form, as Azure's `pages` parameter takes them
     pub fn as_vec(&self) -> &[String] {
-        &self.0
+        &self.terms
+    }
+
+    /// The terms in the order given
+    pub fn spans(&self) -> &[PageSpan] {
+        &self.spans
     }
     
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.spans.is_empty()
+    }
+    
+    /// Whether `page` (1-based) is selected; an empty range selects every page
+    pub fn contains(&self, page: u32) -> bool {
+        self.is_empty() || self.spans.iter().any(|span| span.contains(page))
+    }
+
+    /// Selected pages of a `page_count`-page document, in ascending order
+    pub fn pages(&self, page_count: u32) -> impl Iterator<Item = u32> + '_ {
+        (1..=page_count).filter(move |page| self.contains(*page))
+    }
+}
+
+impl TryFrom<Vec<String>> for PageRange {
+    type Error = DomainError;
+
+    fn try_from(pages: Vec<String>) -> DomainResult<Self> {
+        Self::new(pages)
+    }
+}
+
+impl From<PageRange> for Vec<String> {
+    fn from(range: PageRange) -> Self {
+        range.terms
     }
 }
 
@@ -157,6 +491,122 @@ impl Default for PageRange {
     }
 }
 
+/// Sections of an analysis result a client asked for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ResultFields {
+    pub content: bool,
+    pub pages: bool,
+    pub tables: bool,
+    pub key_value_pairs: bool,
+    pub documents: bool,
+}
+
+impl ResultFields {
+    pub const NAMES: [&'static str; 5] = ["content", "pages", "tables", "key_value_pairs", "documents"];
+    
+    pub fn all() -> Self {
+        Self {
+            content: true,
+            pages: true,
+            tables: true,
+            key_value_pairs: true,
+            documents: true,
+        }
+    }
+    
+    /// Selection of no sections, to be filled in field by field
+    pub fn none() -> Self {
+        Self {
+            content: false,
+            pages: false,
+            tables: false,
+            key_value_pairs: false,
+            documents: false,
+        }
+    }
+    
+    /// Selection of the named sections, e.g. `["content", "tables"]`
+    pub fn from_names<I, S>(names: I) -> DomainResult<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut fields = Self::none();
+        for name in names {
+            match name.as_ref().trim() {
+                "content" => fields.content = true,
+                "pages" => fields.pages = true,
+                "tables" => fields.tables = true,
+                "key_value_pairs" => fields.key_value_pairs = true,
+                "documents" => fields.documents = true,
+                "" => {}
+                other => {
+                    return Err(DomainError::ValidationError(format!(
+                        "Unknown result field '{}' (expected one of: {})",
+                        other,
+                        Self::NAMES.join(", ")
+                    )))
+                }
+            }
+        }
+        Ok(fields)
+    }
+    
+    pub fn is_all(&self) -> bool {
+        *self == Self::all()
+    }
+}
+
+impl Default for ResultFields {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+/// Check a `min_confidence` threshold lies between 0 and 1
+pub fn validate_min_confidence(confidence: f32) -> DomainResult<()> {
+    if (0.0..=1.0).contains(&confidence) {
+        Ok(())
+    } else {
+        Err(DomainError::ValidationError(format!(
+            "min_confidence must be between 0 and 1, got {}",
+            confidence
+        )))
+    }
+}
+
+/// Filter over a result's key-value pairs and extracted document fields
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FieldQuery {
+    /// Key or field name, matched case-insensitively
+    pub key: Option<String>,
+    /// Lowest confidence kept, between 0 and 1
+    pub min_confidence: Option<f32>,
+}
+
+impl FieldQuery {
+    pub fn new(key: Option<String>, min_confidence: Option<f32>) -> DomainResult<Self> {
+        if let Some(confidence) = min_confidence {
+            validate_min_confidence(confidence)?;
+        }
+        let key = key.map(|key| key.trim().to_string()).filter(|key| !key.is_empty());
+        Ok(Self { key, min_confidence })
+    }
+    
+    /// Whether an entry with this key and confidence passes the filter
+    pub fn matches(&self, key: &str, confidence: f32) -> bool {
+        let key_matches = match &self.key {
+            Some(wanted) => key.trim().to_lowercase() == wanted.to_lowercase(),
+            None => true,
+        };
+        let confident = match self.min_confidence {
+            Some(min) => confidence >= min,
+            None => true,
+        };
+        key_matches && confident
+    }
+}
+
 /// Additional features that can be enabled
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -182,6 +632,40 @@ impl AnalysisFeature {
     }
 }
 
+/// How span offsets and lengths into the content are counted
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum StringIndexType {
+    /// User-perceived characters (grapheme clusters), Azure's default
+    #[default]
+    TextElements,
+    UnicodeCodePoint,
+    /// UTF-16 code units, as JavaScript strings index
+    Utf16CodeUnit,
+}
+
+impl StringIndexType {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::TextElements => "textElements",
+            Self::UnicodeCodePoint => "unicodeCodePoint",
+            Self::Utf16CodeUnit => "utf16CodeUnit",
+        }
+    }
+
+    pub fn parse(value: &str) -> DomainResult<Self> {
+        match value {
+            "textElements" => Ok(Self::TextElements),
+            "unicodeCodePoint" => Ok(Self::UnicodeCodePoint),
+            "utf16CodeUnit" => Ok(Self::Utf16CodeUnit),
+            other => Err(DomainError::ValidationError(format!(
+                "unknown string index type '{}', expected textElements, unicodeCodePoint or utf16CodeUnit",
+                other
+            ))),
+        }
+    }
+}
+
 /// Operation status
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
@@ -199,6 +683,470 @@ impl OperationStatus {
     }
 }
 
+/// Kind of entry in an operation's event history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationEventKind {
+    Submitted,
+    Running,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+impl OperationEventKind {
+    /// Event recorded when an operation moves into `status`
+    pub fn for_status(status: OperationStatus) -> Option<Self> {
+        match status {
+            OperationStatus::NotStarted => None,
+            OperationStatus::Running => Some(Self::Running),
+            OperationStatus::Succeeded => Some(Self::Succeeded),
+            OperationStatus::Failed => Some(Self::Failed),
+            OperationStatus::Canceled => Some(Self::Canceled),
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Submitted => "submitted",
+            Self::Running => "running",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Canceled => "canceled",
+        }
+    }
+    
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "submitted" => Some(Self::Submitted),
+            "running" => Some(Self::Running),
+            "succeeded" => Some(Self::Succeeded),
+            "failed" => Some(Self::Failed),
+            "canceled" => Some(Self::Canceled),
+            _ => None,
+        }
+    }
+}
+
+/// Lifecycle change announced to downstream systems
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LifecycleEventKind {
+    Created,
+    Succeeded,
+    /// Failed or canceled; the event's status tells which
+    Failed,
+    /// A pipeline's `publish_event` step reached the succeeded operation
+    Processed,
+}
+
+impl LifecycleEventKind {
+    /// Event announcing that an operation finished in `status`
+    pub fn for_status(status: OperationStatus) -> Option<Self> {
+        match status {
+            OperationStatus::Succeeded => Some(Self::Succeeded),
+            OperationStatus::Failed | OperationStatus::Canceled => Some(Self::Failed),
+            OperationStatus::NotStarted | OperationStatus::Running => None,
+        }
+    }
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Created => "created",
+            Self::Succeeded => "succeeded",
+            Self::Failed => "failed",
+            Self::Processed => "processed",
+        }
+    }
+}
+
+/// Page of a tenant's operations, newest first
+#[derive(Debug, Clone, PartialEq)]
+pub struct OperationListQuery {
+    pub status: Option<OperationStatus>,
+    /// Only operations created strictly before this instant, for paging
+    pub before: Option<chrono::DateTime<chrono::Utc>>,
+    /// With `before`, also operations created at that same instant whose ID
+    /// sorts before this one, so pages never skip timestamp ties
+    pub before_id: Option<String>,
+    /// Only operations at this stage of human review
+    pub review: Option<ReviewStatus>,
+    /// Also list soft-deleted operations
+    pub include_deleted: bool,
+    pub limit: u32,
+}
+
+impl OperationListQuery {
+    pub const DEFAULT_LIMIT: u32 = 50;
+    pub const MAX_LIMIT: u32 = 500;
+    
+    pub fn validate(&self) -> DomainResult<()> {
+        if self.limit == 0 || self.limit > Self::MAX_LIMIT {
+            return Err(DomainError::ValidationError(format!(
+                "limit must be between 1 and {}",
+                Self::MAX_LIMIT
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl Default for OperationListQuery {
+    fn default() -> Self {
+        Self {
+            status: None,
+            before: None,
+            before_id: None,
+            review: None,
+            include_deleted: false,
+            limit: Self::DEFAULT_LIMIT,
+        }
+    }
+}
+
+/// Whether an audited call succeeded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOutcome {
+    Success,
+    Failure,
+}
+
+impl AuditOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Success => "success",
+            Self::Failure => "failure",
+        }
+    }
+    
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "success" => Some(Self::Success),
+            "failure" => Some(Self::Failure),
+            _ => None,
+        }
+    }
+}
+
+/// What a caller is allowed to do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Submit documents and read results within the caller's tenant
+    Analyst,
+    /// Everything, for any tenant
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Analyst => "analyst",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+/// Filter for reading the audit log, newest entries first
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditQuery {
+    pub principal: Option<String>,
+    /// Exact action, e.g. `POST /api/v1/analyze/invoice`
+    pub action: Option<String>,
+    pub operation_id: Option<String>,
+    pub since: Option<chrono::DateTime<chrono::Utc>>,
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+    pub limit: u32,
+}
+
+impl AuditQuery {
+    pub const DEFAULT_LIMIT: u32 = 100;
+    pub const MAX_LIMIT: u32 = 1000;
+    
+    /// Validate the time window and limit
+    pub fn validate(&self) -> DomainResult<()> {
+        if self.limit == 0 || self.limit > Self::MAX_LIMIT {
+            return Err(DomainError::ValidationError(format!(
+                "limit must be between 1 and {}",
+                Self::MAX_LIMIT
+            )));
+        }
+        if let (Some(since), Some(until)) = (self.since, self.until) {
+            if since > until {
+                return Err(DomainError::ValidationError("since must not be after until".to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for AuditQuery {
+    fn default() -> Self {
+        Self {
+            principal: None,
+            action: None,
+            operation_id: None,
+            since: None,
+            until: None,
+            limit: Self::DEFAULT_LIMIT,
+        }
+    }
+}
+
+/// Date range of a usage report, both ends inclusive
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageQuery {
+    /// Only this tenant; all tenants when unset
+    pub tenant: Option<TenantId>,
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+}
+
+impl UsageQuery {
+    /// Longest range one report covers
+    pub const MAX_DAYS: i64 = 366;
+    
+    pub fn validate(&self) -> DomainResult<()> {
+        if self.from > self.to {
+            return Err(DomainError::ValidationError("from must not be after to".to_string()));
+        }
+        if (self.to - self.from).num_days() >= Self::MAX_DAYS {
+            return Err(DomainError::ValidationError(format!(
+                "usage reports cover at most {} days",
+                Self::MAX_DAYS
+            )));
+        }
+        Ok(())
+    }
+    
+    /// Whether `day` falls in the range
+    pub fn contains(&self, day: chrono::NaiveDate) -> bool {
+        self.from <= day && day <= self.to
+    }
+}
+
+/// Calendar window, in UTC, over which a quota is counted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaPeriod {
+    Daily,
+    Monthly,
+}
+
+impl QuotaPeriod {
+    pub const ALL: [QuotaPeriod; 2] = [Self::Daily, Self::Monthly];
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Monthly => "monthly",
+        }
+    }
+    
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "daily" => Some(Self::Daily),
+            "monthly" => Some(Self::Monthly),
+            _ => None,
+        }
+    }
+    
+    /// First day of the window containing `day`
+    pub fn start(&self, day: chrono::NaiveDate) -> chrono::NaiveDate {
+        match self {
+            Self::Daily => day,
+            Self::Monthly => day.with_day(1).unwrap_or(day),
+        }
+    }
+    
+    /// When the window containing `day` ends and the quota resets
+    pub fn resets_at(&self, day: chrono::NaiveDate) -> chrono::DateTime<chrono::Utc> {
+        let next = match self {
+            Self::Daily => day.succ_opt(),
+            Self::Monthly => self.start(day).checked_add_months(chrono::Months::new(1)),
+        };
+        next.unwrap_or(chrono::NaiveDate::MAX)
+            .and_time(chrono::NaiveTime::MIN)
+            .and_utc()
+    }
+}
+
+/// Queue through which succeeded operations are handed to external workers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WorkQueue {
+    Review,
+    Export,
+}
+
+impl WorkQueue {
+    pub const ALL: [WorkQueue; 2] = [Self::Review, Self::Export];
+    
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Review => "review",
+            Self::Export => "export",
+        }
+    }
+    
+    /// Review status an operation must be in to be handed out, if any
+    pub fn review_status(&self) -> Option<ReviewStatus> {
+        match self {
+            Self::Review => Some(ReviewStatus::NeedsReview),
+            Self::Export => None,
+        }
+    }
+}
+
+/// Progress of a queued analysis job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    /// Waiting for a worker, possibly until a retry is due
+    Queued,
+    /// Claimed by a worker that is submitting it
+    Running,
+    /// Handed to the provider; the job's operation tracks it from here
+    Submitted,
+    /// Gave up after a permanent failure or too many attempts; needs a manual retry
+    DeadLettered,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Queued => "queued",
+            Self::Running => "running",
+            Self::Submitted => "submitted",
+            Self::DeadLettered => "dead_lettered",
+        }
+    }
+    
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "queued" => Some(Self::Queued),
+            "running" => Some(Self::Running),
+            "submitted" => Some(Self::Submitted),
+            // Jobs that failed before retries existed
+            "dead_lettered" | "failed" => Some(Self::DeadLettered),
+            _ => None,
+        }
+    }
+    
+    /// Whether a worker will still act on the job
+    pub fn is_pending(&self) -> bool {
+        matches!(self, Self::Queued | Self::Running)
+    }
+}
+
+/// Lane a queued job waits in; workers drain higher lanes first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JobPriority {
+    /// Bulk work such as backfills, run when nothing interactive is waiting
+    Batch,
+    /// Someone is waiting on the result
+    #[default]
+    Interactive,
+}
+
+impl JobPriority {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Batch => "batch",
+            Self::Interactive => "interactive",
+        }
+    }
+    
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "batch" => Some(Self::Batch),
+            "interactive" => Some(Self::Interactive),
+            _ => None,
+        }
+    }
+    
+    /// Stored rank; higher ranks are claimed first
+    pub fn rank(&self) -> i16 {
+        match self {
+            Self::Batch => 0,
+            Self::Interactive => 10,
+        }
+    }
+    
+    pub fn from_rank(rank: i16) -> Self {
+        if rank >= Self::Interactive.rank() {
+            Self::Interactive
+        } else {
+            Self::Batch
+        }
+    }
+}
+
+/// How failed jobs are retried before they are dead-lettered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobRetryPolicy {
+    /// Attempts before a job is dead-lettered, the first included
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for each one after
+    pub base_delay_secs: u64,
+    /// Longest delay between attempts
+    pub max_delay_secs: u64,
+}
+
+impl JobRetryPolicy {
+    /// Delay before the attempt after `attempts` failed ones
+    pub fn backoff(&self, attempts: u32) -> chrono::Duration {
+        let exponent = attempts.saturating_sub(1).min(32);
+        let delay = self.base_delay_secs.saturating_mul(1 << exponent).min(self.max_delay_secs);
+        chrono::Duration::seconds(delay as i64)
+    }
+    
+    /// Whether a job that has made `attempts` attempts may try again
+    pub fn allows_retry(&self, attempts: u32) -> bool {
+        attempts < self.max_attempts
+    }
+}
+
+impl Default for JobRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            base_delay_secs: 10,
+            max_delay_secs: 600,
+        }
+    }
+}
+
+/// Outcome of a malware scan of uploaded bytes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "verdict", rename_all = "lowercase")]
+pub enum ScanVerdict {
+    Clean,
+    Infected { signature: String },
+}
+
+impl ScanVerdict {
+    /// Compact form for storage: `clean` or `infected:<signature>`
+    pub fn to_storage_string(&self) -> String {
+        match self {
+            Self::Clean => "clean".to_string(),
+            Self::Infected { signature } => format!("infected:{}", signature),
+        }
+    }
+    
+    pub fn from_storage_string(value: &str) -> Option<Self> {
+        match value.split_once(':') {
+            Some(("infected", signature)) => Some(Self::Infected {
+                signature: signature.to_string(),
+            }),
+            None if value == "clean" => Some(Self::Clean),
+            _ => None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -218,13 +1166,23 @@ mod tests {
         let invalid_url = DocumentSource::Url("".to_string());
         assert!(invalid_url.validate().is_err());
 
-        let valid_bytes = DocumentSource::Bytes(vec![1, 2, 3]);
+        let valid_bytes = DocumentSource::Bytes(Bytes::from_static(&[1, 2, 3]));
         assert!(valid_bytes.validate().is_ok());
 
-        let empty_bytes = DocumentSource::Bytes(vec![]);
+        let empty_bytes = DocumentSource::Bytes(Bytes::new());
         assert!(empty_bytes.validate().is_err());
     }
 
+    #[test]
+    fn test_document_metadata() {
+        let metadata = DocumentMetadata::new("../../etc/invoice.pdf", "application/pdf");
+        assert_eq!(metadata.filename, "invoice.pdf");
+        assert_eq!(metadata.content_type, "application/pdf");
+
+        let metadata = DocumentMetadata::new("", " ");
+        assert_eq!(metadata, DocumentMetadata::default());
+    }
+
     #[test]
     fn test_locale() {
         let locale = Locale::new("en-US").unwrap();
@@ -233,11 +1191,174 @@ mod tests {
         assert!(Locale::new("").is_err());
     }
 
+    #[test]
+    fn test_page_range_contains() {
+        let range = PageRange::new(vec!["1-3,5".to_string(), "9-".to_string()]).unwrap();
+        assert!(range.contains(1) && range.contains(3) && range.contains(5) && range.contains(12));
+        assert!(!range.contains(4) && !range.contains(8));
+        assert!(PageRange::all().contains(7));
+    }
+
+    #[test]
+    fn test_page_range_parsing() {
+        let range = PageRange::new(vec![" 7- , 1-3".to_string(), "5".to_string()]).unwrap();
+        assert_eq!(range.as_vec(), ["7-", "1-3", "5"]);
+        assert_eq!(range.spans()[0], PageSpan { start: 7, end: None });
+        assert_eq!(range.pages(8).collect::<Vec<_>>(), [1, 2, 3, 5, 7, 8]);
+        assert_eq!(PageRange::all().pages(3).collect::<Vec<_>>(), [1, 2, 3]);
+        assert!(PageRange::new(vec!["1-3,4".to_string()]).is_ok());
+
+        let error = |pages: &str| PageRange::new(vec![pages.to_string()]).unwrap_err().to_string();
+        assert_eq!(error("3-1"), "Invalid page range: '3-1' is reversed");
+        assert_eq!(error("1-3,2-5"), "Invalid page range: '1-3' overlaps '2-5'");
+        assert_eq!(error("4-,9"), "Invalid page range: '4-' overlaps '9'");
+        assert_eq!(error("2,2"), "Invalid page range: '2' overlaps '2'");
+        assert_eq!(error("1,a"), "Invalid page range: 'a' is not a page number in 'a'");
+        assert_eq!(error("-3"), "Invalid page range: '' is not a page number in '-3'");
+        assert_eq!(error("1-2-3"), "Invalid page range: '2-3' is not a page number in '1-2-3'");
+        assert_eq!(error("0-2"), "Invalid page range: pages start at 1, got 0 in '0-2'");
+        assert_eq!(error("1,,2"), "Invalid page range: empty term in '1,,2'");
+        assert_eq!(error(""), "Invalid page range: empty term in ''");
+
+        let stored: PageRange = serde_json::from_value(serde_json::json!(["1-2", "4"])).unwrap();
+        assert_eq!(serde_json::to_value(&stored).unwrap(), serde_json::json!(["1-2", "4"]));
+        assert!(serde_json::from_value::<PageRange>(serde_json::json!(["2-1"])).is_err());
+    }
+
+    #[test]
+    fn test_result_fields() {
+        let fields = ResultFields::from_names(["content", " tables"]).unwrap();
+        assert!(fields.content && fields.tables);
+        assert!(!fields.pages && !fields.key_value_pairs && !fields.documents);
+        assert!(ResultFields::from_names(ResultFields::NAMES).unwrap().is_all());
+        assert!(ResultFields::from_names(["words"]).is_err());
+    }
+
+    #[test]
+    fn test_field_query() {
+        let query = FieldQuery::new(Some(" InvoiceTotal ".to_string()), Some(0.8)).unwrap();
+        assert!(query.matches("invoicetotal", 0.8));
+        assert!(!query.matches("InvoiceTotal", 0.79));
+        assert!(!query.matches("InvoiceDate", 0.99));
+        assert!(FieldQuery::new(Some(String::new()), None).unwrap().matches("anything", 0.0));
+        assert!(FieldQuery::new(None, Some(1.5)).is_err());
+    }
+
     #[test]
     fn test_operation_status() {
         assert!(!OperationStatus::Running.is_terminal());
         assert!(OperationStatus::Succeeded.is_terminal());
         assert!(OperationStatus::Failed.is_terminal());
     }
-}
 
+    #[test]
+    fn test_tenant_id() {
+        assert!(TenantId::default().is_default());
+        assert_eq!(TenantId::new("acme-corp_2").unwrap().as_str(), "acme-corp_2");
+        assert!(TenantId::new("").is_err());
+        assert!(TenantId::new("..").is_err());
+        assert!(TenantId::new("a/b").is_err());
+        assert!(TenantId::new("x".repeat(TenantId::MAX_LEN + 1)).is_err());
+        assert!(serde_json::from_str::<TenantId>("\"../etc\"").is_err());
+        assert_eq!(serde_json::to_string(&TenantId::default()).unwrap(), "\"default\"");
+    }
+
+    #[test]
+    fn test_usage_query_validation() {
+        let day = |d: &str| d.parse::<chrono::NaiveDate>().unwrap();
+        let query = UsageQuery { tenant: None, from: day("2024-05-01"), to: day("2024-05-31") };
+        assert!(query.validate().is_ok());
+        assert!(query.contains(day("2024-05-31")) && !query.contains(day("2024-06-01")));
+        assert!(UsageQuery { from: day("2024-06-01"), ..query.clone() }.validate().is_err());
+        assert!(UsageQuery { from: day("2023-01-01"), ..query }.validate().is_err());
+    }
+
+    #[test]
+    fn test_quota_period_windows() {
+        let day = "2024-02-29".parse::<chrono::NaiveDate>().unwrap();
+        assert_eq!(QuotaPeriod::Daily.start(day), day);
+        assert_eq!(QuotaPeriod::Monthly.start(day).to_string(), "2024-02-01");
+        assert_eq!(QuotaPeriod::Daily.resets_at(day).to_rfc3339(), "2024-03-01T00:00:00+00:00");
+        assert_eq!(QuotaPeriod::Monthly.resets_at(day).to_rfc3339(), "2024-03-01T00:00:00+00:00");
+        let new_year = "2024-12-31".parse::<chrono::NaiveDate>().unwrap();
+        assert_eq!(QuotaPeriod::Monthly.resets_at(new_year).to_rfc3339(), "2025-01-01T00:00:00+00:00");
+        assert_eq!(QuotaPeriod::parse("monthly"), Some(QuotaPeriod::Monthly));
+        assert_eq!(QuotaPeriod::parse("weekly"), None);
+    }
+
+    #[test]
+    fn test_audit_query_validation() {
+        assert!(AuditQuery::default().validate().is_ok());
+        assert!(AuditQuery { limit: 0, ..Default::default() }.validate().is_err());
+        assert!(AuditQuery { limit: AuditQuery::MAX_LIMIT + 1, ..Default::default() }.validate().is_err());
+        let now = chrono::Utc::now();
+        let backwards = AuditQuery {
+            since: Some(now),
+            until: Some(now - chrono::Duration::hours(1)),
+            ..Default::default()
+        };
+        assert!(backwards.validate().is_err());
+    }
+
+    #[test]
+    fn test_operation_event_kind() {
+        assert_eq!(OperationEventKind::for_status(OperationStatus::NotStarted), None);
+        assert_eq!(
+            OperationEventKind::for_status(OperationStatus::Failed),
+            Some(OperationEventKind::Failed)
+        );
+        let kind = OperationEventKind::Submitted;
+        assert_eq!(OperationEventKind::parse(kind.as_str()), Some(kind));
+        assert_eq!(OperationEventKind::parse("queued"), None);
+    }
+
+    #[test]
+    fn test_scan_verdict_storage_string() {
+        let infected = ScanVerdict::Infected {
+            signature: "Eicar-Test-Signature".to_string(),
+        };
+        assert_eq!(infected.to_storage_string(), "infected:Eicar-Test-Signature");
+        assert_eq!(ScanVerdict::from_storage_string("infected:Eicar-Test-Signature"), Some(infected));
+        assert_eq!(ScanVerdict::from_storage_string("clean"), Some(ScanVerdict::Clean));
+        assert_eq!(ScanVerdict::from_storage_string("unknown"), None);
+    }
+
+    #[test]
+    fn test_document_format_detection() {
+        assert_eq!(DocumentFormat::detect(b"%PDF-1.7\n").unwrap(), DocumentFormat::Pdf);
+        assert_eq!(DocumentFormat::detect(&[0xFF, 0xD8, 0xFF, 0xE0]).unwrap(), DocumentFormat::Jpeg);
+        assert_eq!(
+            DocumentFormat::detect(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0]).unwrap(),
+            DocumentFormat::Png
+        );
+        assert_eq!(DocumentFormat::detect(b"MM\0*rest").unwrap(), DocumentFormat::Tiff);
+        assert_eq!(
+            DocumentFormat::detect(b"\0\0\0\x18ftypheic\0\0\0\0").unwrap(),
+            DocumentFormat::Heif
+        );
+        assert_eq!(
+            DocumentFormat::detect(b"PK\x03\x04....[Content_Types].xml word/document.xml").unwrap(),
+            DocumentFormat::Docx
+        );
+        assert_eq!(DocumentFormat::Docx.mime_type().split('/').next(), Some("application"));
+        
+        // Plain zips and unknown content are rejected
+        assert!(matches!(
+            DocumentFormat::detect(b"PK\x03\x04 xl/workbook.xml"),
+            Err(DomainError::UnsupportedDocumentType(_))
+        ));
+        assert!(DocumentFormat::detect(b"hello world").is_err());
+        assert!(DocumentFormat::detect(b"").is_err());
+    }
+    
+    #[test]
+    fn test_job_retry_backoff() {
+        let policy = JobRetryPolicy::default();
+        let delays: Vec<i64> = (1..=8).map(|attempts| policy.backoff(attempts).num_seconds()).collect();
+        assert_eq!(delays, vec![10, 20, 40, 80, 160, 320, 600, 600]);
+        assert_eq!(policy.backoff(u32::MAX).num_seconds(), 600);
+        assert!(policy.allows_retry(4));
+        assert!(!policy.allows_retry(5));
+        assert_eq!(JobStatus::parse("failed"), Some(JobStatus::DeadLettered));
+    }
+}
@@ -0,0 +1,248 @@
+/// Synthetic analysis result generator
+///
+/// Produces deterministic `AnalysisResult` fixtures of configurable size for
+/// benchmarks, pagination tests and the mock adapter. The same spec (including
+/// seed) always yields the same result.
+
+use std::collections::HashMap;
+
+use crate::domain::*;
+
+const VOCABULARY: &[&str] = &[
+    "invoice", "total", "amount", "due", "date", "customer", "vendor", "address",
+    "quantity", "price", "tax", "subtotal", "payment", "terms", "order", "number",
+    "shipping", "contoso", "fabrikam", "account", "balance", "description", "item", "unit",
+];
+
+/// Size and shape of a synthetic result
+#[derive(Debug, Clone)]
+pub struct SyntheticResultSpec {
+    pub pages: usize,
+    pub words_per_page: usize,
+    pub words_per_line: usize,
+    pub tables: usize,
+    pub table_rows: usize,
+    pub table_columns: usize,
+    pub key_value_pairs: usize,
+    pub model_type: ModelType,
+    pub seed: u64,
+}
+
+impl Default for SyntheticResultSpec {
+    fn default() -> Self {
+        Self {
+            pages: 1,
+            words_per_page: 200,
+            words_per_line: 10,
+            tables: 1,
+            table_rows: 5,
+            table_columns: 4,
+            key_value_pairs: 10,
+            model_type: ModelType::Layout,
+            seed: 42,
+        }
+    }
+}
+
+/// Small deterministic PRNG (SplitMix64), so fixtures don't depend on `rand`
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    fn confidence(&mut self) -> f32 {
+        0.5 + (self.next_u64() % 500) as f32 / 1000.0
+    }
+}
+
+/// Accumulates `content` and hands out spans into it
+struct ContentBuilder {
+    content: String,
+}
+
+impl ContentBuilder {
+    fn push(&mut self, text: &str, separator: char) -> Span {
+        if !self.content.is_empty() {
+            self.content.push(separator);
+        }
+        let offset = self.content.len() as i32;
+        self.content.push_str(text);
+        Span { offset, length: text.len() as i32 }
+    }
+}
+
+fn rectangle(x: f32, y: f32, width: f32, height: f32) -> Vec<Point> {
+    vec![
+        Point { x, y },
+        Point { x: x + width, y },
+        Point { x: x + width, y: y + height },
+        Point { x, y: y + height },
+    ]
+}
+
+/// Generate a synthetic result matching `spec`
+pub fn synthetic_result(spec: &SyntheticResultSpec) -> AnalysisResult {
+    let mut rng = SplitMix64(spec.seed);
+    let mut content = ContentBuilder { content: String::new() };
+    let words_per_line = spec.words_per_line.max(1);
+
+    let mut pages = Vec::with_capacity(spec.pages);
+    for page_index in 0..spec.pages {
+        let mut words = Vec::with_capacity(spec.words_per_page);
+        let mut lines = Vec::new();
+
+        for line_words in (0..spec.words_per_page).collect::<Vec<_>>().chunks(words_per_line) {
+            let line_index = lines.len();
+            let y = 1.0 + line_index as f32 * 0.25;
+            let mut line_text = Vec::with_capacity(line_words.len());
+            let mut line_offset = None;
+
+            for (column, _) in line_words.iter().enumerate() {
+                let text = VOCABULARY[rng.below(VOCABULARY.len())];
+                let separator = if column == 0 { '\n' } else { ' ' };
+                let span = content.push(text, separator);
+                line_offset.get_or_insert(span.offset);
+                line_text.push(text);
+
+                words.push(DocumentWord {
+                    content: text.to_string(),
+                    polygon: rectangle(0.5 + column as f32 * 0.75, y, 0.7, 0.2),
+                    confidence: rng.confidence(),
+                    span,
+                });
+            }
+
+            let line_content = line_text.join(" ");
+            lines.push(DocumentLine {
+                polygon: rectangle(0.5, y, line_words.len() as f32 * 0.75, 0.2),
+                spans: vec![Span {
+                    offset: line_offset.unwrap_or_default(),
+                    length: line_content.len() as i32,
+                }],
+                content: line_content,
+            });
+        }
+
+        pages.push(DocumentPage {
+            page_number: page_index as i32 + 1,
+            angle: 0.0,
+            width: 8.5,
+            height: 11.0,
+            unit: "inch".to_string(),
+            words,
+            lines,
+            selection_marks: Vec::new(),
+        });
+    }
+
+    let tables = (0..spec.tables)
+        .map(|_| {
+            let mut cells = Vec::with_capacity(spec.table_rows * spec.table_columns);
+            for row in 0..spec.table_rows {
+                for column in 0..spec.table_columns {
+                    let text = if row == 0 {
+                        format!("Column {}", column + 1)
+                    } else {
+                        format!("{}", rng.below(10_000))
+                    };
+                    content.push(&text, ' ');
+                    cells.push(TableCell {
+                        kind: if row == 0 { CellKind::ColumnHeader } else { CellKind::Content },
+                        row_index: row as i32,
+                        column_index: column as i32,
+                        row_span: 1,
+                        column_span: 1,
+                        content: text,
+                    });
+                }
+            }
+            DocumentTable {
+                row_count: spec.table_rows as i32,
+                column_count: spec.table_columns as i32,
+                cells,
+            }
+        })
+        .collect();
+
+    let key_value_pairs = (0..spec.key_value_pairs)
+        .map(|index| KeyValuePair {
+            key: format!("Field{}", index + 1),
+            value: VOCABULARY[rng.below(VOCABULARY.len())].to_string(),
+            confidence: rng.confidence(),
+        })
+        .collect();
+
+    let documents = match spec.model_type {
+        ModelType::Read | ModelType::Layout => Vec::new(),
+        model_type => {
+            let mut fields = HashMap::new();
+            fields.insert("Total".to_string(), DocumentField::Number(rng.below(100_000) as f64 / 100.0));
+            fields.insert("Reference".to_string(), DocumentField::String(format!("REF-{}", rng.below(1_000_000))));
+            vec![ExtractedDocument {
+                doc_type: model_type.as_str().trim_start_matches("prebuilt-").to_string(),
+                fields,
+                confidence: rng.confidence(),
+            }]
+        }
+    };
+
+    AnalysisResult {
+        model_id: spec.model_type.as_str().to_string(),
+        content: content.content,
+        pages,
+        tables,
+        key_value_pairs,
+        documents,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_synthetic_result_is_deterministic() {
+        let spec = SyntheticResultSpec { pages: 3, ..Default::default() };
+        let first = serde_json::to_string(&synthetic_result(&spec)).unwrap();
+        let second = serde_json::to_string(&synthetic_result(&spec)).unwrap();
+        assert_eq!(first, second);
+
+        let other_seed = SyntheticResultSpec { seed: 7, ..spec };
+        assert_ne!(first, serde_json::to_string(&synthetic_result(&other_seed)).unwrap());
+    }
+
+    #[test]
+    fn test_synthetic_result_shape() {
+        let spec = SyntheticResultSpec {
+            pages: 4,
+            words_per_page: 25,
+            words_per_line: 10,
+            tables: 2,
+            model_type: ModelType::Invoice,
+            ..Default::default()
+        };
+        let result = synthetic_result(&spec);
+
+        assert_eq!(result.pages.len(), 4);
+        assert_eq!(result.pages[3].words.len(), 25);
+        assert_eq!(result.pages[3].lines.len(), 3);
+        assert_eq!(result.tables.len(), 2);
+        assert_eq!(result.tables[0].cells.len(), 20);
+        assert_eq!(result.documents.len(), 1);
+
+        let word = &result.pages[2].words[7];
+        let start = word.span.offset as usize;
+        assert_eq!(&result.content[start..start + word.span.length as usize], word.content);
+    }
+}
@@ -0,0 +1,361 @@
+/// Alerts on elevated Azure error and throttling rates
+///
+/// Every call to the Document Intelligence adapter is counted over a rolling
+/// window. When enough calls were made and the share of failed or throttled
+/// ones crosses its threshold, a Slack-compatible payload is POSTed to each
+/// configured webhook and, with a routing key, a PagerDuty event is
+/// triggered. An alert is not raised again until its cool-down has passed.
+
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{info, warn, Instrument};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::{DocumentIntelligencePort, WebhookPort};
+use crate::domain::{AnalysisOperation, AnalysisResult, AnalyzeDocumentRequest};
+use crate::infrastructure::config::AlertingConfig;
+
+/// How an Azure call ended, as far as alerting is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CallOutcome {
+    Ok,
+    /// Azure answered 429
+    Throttled,
+    /// A failure worth retrying: 5xx, timeouts, unreachable
+    Error,
+}
+
+impl CallOutcome {
+    pub fn of<T>(result: &ApplicationResult<T>) -> Self {
+        match result {
+            Err(ApplicationError::AzureThrottled { .. }) => Self::Throttled,
+            Err(e) if e.is_transient() => Self::Error,
+            _ => Self::Ok,
+        }
+    }
+}
+
+/// Which rate crossed its threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AlertKind {
+    ErrorRate,
+    Throttling,
+}
+
+impl AlertKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::ErrorRate => "azure_error_rate",
+            Self::Throttling => "azure_throttling",
+        }
+    }
+}
+
+/// A threshold crossed within the window
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alert {
+    pub kind: AlertKind,
+    /// Share of calls in the window that failed or were throttled
+    pub rate: f64,
+    pub threshold: f64,
+    /// Calls in the window
+    pub requests: usize,
+    pub window: Duration,
+}
+
+impl Alert {
+    pub fn summary(&self) -> String {
+        let what = match self.kind {
+            AlertKind::ErrorRate => "failed",
+            AlertKind::Throttling => "were throttled (429), quota may be exhausted",
+        };
+        format!(
+            "adi-svc: {:.0}% of {} Azure Document Intelligence calls in the last {}s {} (threshold {:.0}%)",
+            self.rate * 100.0,
+            self.requests,
+            self.window.as_secs(),
+            what,
+            self.threshold * 100.0
+        )
+    }
+
+    /// Slack-compatible body: `text` is shown, the other fields are for other receivers
+    pub fn webhook_payload(&self) -> Value {
+        json!({
+            "text": self.summary(),
+            "alert": self.kind.as_str(),
+            "rate": self.rate,
+            "threshold": self.threshold,
+            "requests": self.requests,
+            "window_secs": self.window.as_secs(),
+        })
+    }
+
+    /// PagerDuty Events API v2 trigger, deduplicated per kind of alert
+    pub fn pagerduty_payload(&self, routing_key: &str) -> Value {
+        json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": format!("adi-svc-{}", self.kind.as_str()),
+            "payload": {
+                "summary": self.summary(),
+                "source": "adi-svc",
+                "severity": match self.kind {
+                    AlertKind::ErrorRate => "error",
+                    AlertKind::Throttling => "warning",
+                },
+                "custom_details": {
+                    "rate": self.rate,
+                    "threshold": self.threshold,
+                    "requests": self.requests,
+                    "window_secs": self.window.as_secs(),
+                },
+            },
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Window {
+    calls: VecDeque<(Instant, CallOutcome)>,
+    last_fired: HashMap<AlertKind, Instant>,
+}
+
+/// Rolling rates of Azure calls, raising alerts when thresholds are crossed
+#[derive(Debug)]
+pub struct AzureErrorMonitor {
+    window: Duration,
+    min_requests: usize,
+    error_rate: f64,
+    throttle_rate: f64,
+    cooldown: Duration,
+    state: Mutex<Window>,
+}
+
+impl AzureErrorMonitor {
+    pub fn new(config: &AlertingConfig) -> Self {
+        Self {
+            window: Duration::from_secs(config.window_secs),
+            min_requests: config.min_requests,
+            error_rate: config.error_rate,
+            throttle_rate: config.throttle_rate,
+            cooldown: Duration::from_secs(config.cooldown_secs),
+            state: Mutex::new(Window::default()),
+        }
+    }
+
+    /// Count a call, returning the alerts it raised
+    pub fn record(&self, outcome: CallOutcome) -> Vec<Alert> {
+        self.record_at(outcome, Instant::now())
+    }
+
+    pub fn record_at(&self, outcome: CallOutcome, now: Instant) -> Vec<Alert> {
+        let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        state.calls.push_back((now, outcome));
+        while state.calls.front().is_some_and(|(at, _)| now.duration_since(*at) > self.window) {
+            state.calls.pop_front();
+        }
+        let requests = state.calls.len();
+        if requests < self.min_requests {
+            return Vec::new();
+        }
+
+        let share = |wanted: CallOutcome| {
+            state.calls.iter().filter(|(_, outcome)| *outcome == wanted).count() as f64 / requests as f64
+        };
+        let rates = [
+            (AlertKind::ErrorRate, share(CallOutcome::Error), self.error_rate),
+            (AlertKind::Throttling, share(CallOutcome::Throttled), self.throttle_rate),
+        ];
+        let mut alerts = Vec::new();
+        for (kind, rate, threshold) in rates {
+            let cooling = state.last_fired.get(&kind).is_some_and(|at| now.duration_since(*at) < self.cooldown);
+            if rate >= threshold && !cooling {
+                state.last_fired.insert(kind, now);
+                alerts.push(Alert { kind, rate, threshold, requests, window: self.window });
+            }
+        }
+        alerts
+    }
+}
+
+/// Where alerts are delivered
+pub struct AlertNotifier {
+    webhooks: Arc<dyn WebhookPort>,
+    urls: Vec<String>,
+    pagerduty: Option<(String, String)>,
+}
+
+impl AlertNotifier {
+    pub fn new(webhooks: Arc<dyn WebhookPort>, config: &AlertingConfig) -> Self {
+        Self {
+            webhooks,
+            urls: config.webhook_urls.clone(),
+            pagerduty: config
+                .pagerduty_routing_key
+                .as_ref()
+                .map(|key| (config.pagerduty_url.clone(), key.expose().to_string())),
+        }
+    }
+
+    /// Deliver to every target, logging the ones that fail
+    pub async fn notify(&self, alert: &Alert) {
+        warn!("{}", alert.summary());
+        let payload = alert.webhook_payload();
+        let mut deliveries: Vec<(&str, Value)> = self.urls.iter().map(|url| (url.as_str(), payload.clone())).collect();
+        if let Some((url, routing_key)) = &self.pagerduty {
+            deliveries.push((url.as_str(), alert.pagerduty_payload(routing_key)));
+        }
+        for (url, payload) in deliveries {
+            match self.webhooks.deliver(url, &payload).await {
+                Ok(()) => info!("Sent {} alert", alert.kind.as_str()),
+                Err(e) => warn!("Failed to send {} alert: {}", alert.kind.as_str(), e),
+            }
+        }
+    }
+}
+
+/// Adapter counting the calls of another for [`AzureErrorMonitor`]
+///
+/// Alerts are delivered in the background, so callers never wait on them.
+pub struct AlertingIntelligenceAdapter {
+    inner: Arc<dyn DocumentIntelligencePort>,
+    monitor: AzureErrorMonitor,
+    notifier: Arc<AlertNotifier>,
+}
+
+impl AlertingIntelligenceAdapter {
+    pub fn new(inner: Arc<dyn DocumentIntelligencePort>, monitor: AzureErrorMonitor, notifier: AlertNotifier) -> Self {
+        Self { inner, monitor, notifier: Arc::new(notifier) }
+    }
+
+    fn observe<T>(&self, result: ApplicationResult<T>) -> ApplicationResult<T> {
+        for alert in self.monitor.record(CallOutcome::of(&result)) {
+            let notifier = self.notifier.clone();
+            tokio::spawn(async move { notifier.notify(&alert).await }.in_current_span());
+        }
+        result
+    }
+}
+
+#[async_trait]
+impl DocumentIntelligencePort for AlertingIntelligenceAdapter {
+    async fn analyze_document(&self, request: AnalyzeDocumentRequest) -> ApplicationResult<AnalysisOperation> {
+        self.observe(self.inner.analyze_document(request).await)
+    }
+
+    async fn get_analysis_result(
+        &self,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)> {
+        self.observe(self.inner.get_analysis_result(operation_id).await)
+    }
+
+    async fn get_analysis_result_raw(
+        &self,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>, Option<Value>)> {
+        self.observe(self.inner.get_analysis_result_raw(operation_id).await)
+    }
+
+    async fn validate_custom_model(&self, model_id: &str) -> ApplicationResult<bool> {
+        self.observe(self.inner.validate_custom_model(model_id).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::infrastructure::config::Secret;
+    use crate::infrastructure::webhooks::HttpWebhookSender;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn config() -> AlertingConfig {
+        AlertingConfig {
+            webhook_urls: Vec::new(),
+            pagerduty_routing_key: None,
+            pagerduty_url: "https://events.pagerduty.com/v2/enqueue".to_string(),
+            window_secs: 60,
+            min_requests: 4,
+            error_rate: 0.35,
+            throttle_rate: 0.25,
+            cooldown_secs: 600,
+        }
+    }
+
+    #[test]
+    fn test_thresholds_and_cooldown() {
+        let monitor = AzureErrorMonitor::new(&config());
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+
+        // Too few calls to judge, however many fail
+        assert!(monitor.record_at(CallOutcome::Throttled, at(0)).is_empty());
+        assert!(monitor.record_at(CallOutcome::Ok, at(1)).is_empty());
+        assert!(monitor.record_at(CallOutcome::Ok, at(2)).is_empty());
+        let alerts = monitor.record_at(CallOutcome::Ok, at(3));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].kind, AlertKind::Throttling);
+        assert_eq!(alerts[0].requests, 4);
+        assert!((alerts[0].rate - 0.25).abs() < f64::EPSILON);
+
+        // Cooling down, while other kinds still fire
+        assert!(monitor.record_at(CallOutcome::Throttled, at(4)).is_empty());
+        assert!(monitor.record_at(CallOutcome::Error, at(5)).is_empty());
+        assert!(monitor.record_at(CallOutcome::Error, at(6)).is_empty());
+        let alerts = monitor.record_at(CallOutcome::Error, at(7));
+        assert_eq!(alerts.iter().map(|alert| alert.kind).collect::<Vec<_>>(), vec![AlertKind::ErrorRate]);
+
+        // Old calls leave the window; after the cool-down the alert fires again
+        let throttled: Vec<_> = (0..4).flat_map(|i| monitor.record_at(CallOutcome::Throttled, at(700 + i))).collect();
+        assert_eq!(throttled.len(), 1);
+        assert_eq!(throttled[0].requests, 4, "only calls of the last minute count");
+    }
+
+    #[tokio::test]
+    async fn test_notify_webhooks_and_pagerduty() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/slack"))
+            .and(body_partial_json(json!({ "alert": "azure_throttling", "requests": 40 })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/v2/enqueue"))
+            .and(body_partial_json(json!({
+                "routing_key": "rk-1",
+                "event_action": "trigger",
+                "dedup_key": "adi-svc-azure_throttling",
+                "payload": { "source": "adi-svc", "severity": "warning" },
+            })))
+            .respond_with(ResponseTemplate::new(202))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = AlertingConfig {
+            webhook_urls: vec![format!("{}/slack", server.uri()), format!("{}/down", server.uri())],
+            pagerduty_routing_key: Some(Secret::from("rk-1".to_string())),
+            pagerduty_url: format!("{}/v2/enqueue", server.uri()),
+            ..config()
+        };
+        let sender = HttpWebhookSender::new(Duration::from_secs(5), None).unwrap();
+        let notifier = AlertNotifier::new(Arc::new(sender), &config);
+        let alert = Alert {
+            kind: AlertKind::Throttling,
+            rate: 0.5,
+            threshold: 0.25,
+            requests: 40,
+            window: Duration::from_secs(60),
+        };
+        assert!(alert.summary().contains("50% of 40"), "{}", alert.summary());
+        // The failing webhook does not keep the others from being called
+        notifier.notify(&alert).await;
+    }
+}
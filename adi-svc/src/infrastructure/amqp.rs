@@ -0,0 +1,165 @@
+/// RabbitMQ / AMQP 0-9-1 lifecycle event publisher
+///
+/// Publishes persistent messages to a configured exchange with publisher
+/// confirms, so an event only counts as published once the broker has taken
+/// responsibility for it. The channel is reopened after the connection drops.
+
+use async_trait::async_trait;
+use lapin::options::{BasicPublishOptions, ConfirmSelectOptions, ExchangeDeclareOptions};
+use lapin::types::{AMQPValue, FieldTable};
+use lapin::{BasicProperties, Channel, Connection, ConnectionProperties, ExchangeKind};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::EventPublisherPort;
+use crate::domain::LifecycleEvent;
+use crate::infrastructure::config::EventsConfig;
+use crate::infrastructure::events::{render_route, EventFormat};
+use crate::infrastructure::metrics::metrics;
+
+/// Label for this transport in metrics
+const TRANSPORT: &str = "amqp";
+
+/// Longest wait for the broker to confirm a message
+const CONFIRM_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// AMQP `delivery_mode` for messages that survive a broker restart
+const PERSISTENT: u8 = 2;
+
+/// Publishes lifecycle events to an AMQP exchange
+pub struct AmqpEventPublisher {
+    url: String,
+    exchange: String,
+    routing_key: String,
+    format: EventFormat,
+    channel: Mutex<Option<Channel>>,
+}
+
+impl AmqpEventPublisher {
+    /// Connect and declare the exchange, failing fast on a bad URL or broker
+    ///
+    /// `routing_key` may contain `{event_type}`, `{tenant}` and `{model}`.
+    pub async fn connect(
+        url: impl Into<String>,
+        exchange: impl Into<String>,
+        routing_key: impl Into<String>,
+        format: EventFormat,
+    ) -> ApplicationResult<Self> {
+        let publisher = Self {
+            url: url.into(),
+            exchange: exchange.into(),
+            routing_key: routing_key.into(),
+            format,
+            channel: Mutex::new(None),
+        };
+        let channel = publisher.open_channel().await?;
+        *publisher.channel.lock().await = Some(channel);
+        info!("Publishing lifecycle events to AMQP exchange '{}'", publisher.exchange);
+        Ok(publisher)
+    }
+
+    /// Publisher for the configured broker, if AMQP publishing is enabled
+    pub async fn from_config(config: &EventsConfig) -> ApplicationResult<Option<Self>> {
+        match &config.amqp_url {
+            Some(url) => Ok(Some(
+                Self::connect(
+                    url.clone(),
+                    config.amqp_exchange.clone(),
+                    config.amqp_routing_key.clone(),
+                    config.format,
+                )
+                .await?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    async fn open_channel(&self) -> ApplicationResult<Channel> {
+        let connection = Connection::connect(&self.url, ConnectionProperties::default())
+            .await
+            .map_err(|e| amqp_error("connect to AMQP broker", e))?;
+        let channel = connection
+            .create_channel()
+            .await
+            .map_err(|e| amqp_error("open AMQP channel", e))?;
+        channel
+            .confirm_select(ConfirmSelectOptions::default())
+            .await
+            .map_err(|e| amqp_error("enable publisher confirms", e))?;
+        // The default exchange ("") always exists and cannot be declared
+        if !self.exchange.is_empty() {
+            let options = ExchangeDeclareOptions {
+                durable: true,
+                ..Default::default()
+            };
+            channel
+                .exchange_declare(&self.exchange, ExchangeKind::Topic, options, FieldTable::default())
+                .await
+                .map_err(|e| amqp_error("declare AMQP exchange", e))?;
+        }
+        Ok(channel)
+    }
+
+    async fn publish_confirmed(
+        &self,
+        channel: &Channel,
+        event: &LifecycleEvent,
+        payload: &[u8],
+    ) -> ApplicationResult<()> {
+        let mut headers = FieldTable::default();
+        headers.insert("tenant_id".into(), AMQPValue::LongString(event.tenant_id.as_str().into()));
+        headers.insert("operation_id".into(), AMQPValue::LongString(event.operation_id.as_str().into()));
+        let properties = BasicProperties::default()
+            .with_content_type(self.format.content_type().into())
+            .with_delivery_mode(PERSISTENT)
+            .with_message_id(event.event_id.as_str().into())
+            .with_type(event.event_type.as_str().into())
+            .with_timestamp(event.occurred_at.timestamp().max(0) as u64)
+            .with_headers(headers);
+
+        let routing_key = render_route(&self.routing_key, event);
+        let confirm = channel
+            .basic_publish(&self.exchange, &routing_key, BasicPublishOptions::default(), payload, properties)
+            .await
+            .map_err(|e| amqp_error("publish AMQP message", e))?;
+        let confirmation = tokio::time::timeout(CONFIRM_TIMEOUT, confirm)
+            .await
+            .map_err(|_| ApplicationError::Internal("Timed out waiting for AMQP publisher confirm".to_string()))?
+            .map_err(|e| amqp_error("confirm AMQP message", e))?;
+        if confirmation.is_nack() {
+            return Err(ApplicationError::Internal(format!("AMQP broker rejected event {}", event.event_id)));
+        }
+        debug!("Event {} confirmed on '{}' with key {}", event.event_id, self.exchange, routing_key);
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for AmqpEventPublisher {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()> {
+        let payload = self.format.encode(event)?;
+        // One publish at a time keeps confirms in order and the channel shared
+        let mut channel = self.channel.lock().await;
+        let current = match channel.take() {
+            Some(open) if open.status().connected() => open,
+            _ => {
+                warn!("AMQP channel closed; reconnecting");
+                let reopened = self.open_channel().await;
+                if reopened.is_err() {
+                    metrics().record_event_published(TRANSPORT, false);
+                }
+                reopened?
+            }
+        };
+        let outcome = self.publish_confirmed(&current, event, &payload).await;
+        metrics().record_event_published(TRANSPORT, outcome.is_ok());
+        *channel = Some(current);
+        outcome
+    }
+}
+
+fn amqp_error(action: &str, e: lapin::Error) -> ApplicationError {
+    ApplicationError::Internal(format!("Failed to {}: {}", action, e))
+}
@@ -0,0 +1,113 @@
+/// Destinations and schedule of the Parquet analytics export
+///
+/// The export is written to a local directory, e.g. a mounted lakehouse
+/// volume, or to a Blob Storage container. When scheduled, the previous UTC
+/// day is exported on every tick, so a missed run is caught up by the next.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::application::analytics::AnalyticsExportService;
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::DatasetWriterPort;
+use crate::infrastructure::azure_events::ManagedIdentityCredential;
+use crate::infrastructure::config::AnalyticsExportConfig;
+use crate::infrastructure::tasks::TaskSupervisor;
+use crate::infrastructure::training_export::BlobDatasetWriter;
+
+/// Dataset writer putting files under a local directory
+#[derive(Debug, Clone)]
+pub struct FileDatasetWriter {
+    root: PathBuf,
+}
+
+impl FileDatasetWriter {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+#[async_trait]
+impl DatasetWriterPort for FileDatasetWriter {
+    /// Written to a temporary file first, so readers never see a partial file
+    async fn put(&self, path: &str, _content_type: &str, content: Bytes) -> ApplicationResult<()> {
+        let target = self.root.join(path);
+        let failed = |e: std::io::Error| ApplicationError::Internal(format!("Failed to write {}: {}", target.display(), e));
+        if let Some(parent) = target.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(failed)?;
+        }
+        let partial = target.with_extension("partial");
+        tokio::fs::write(&partial, &content).await.map_err(failed)?;
+        tokio::fs::rename(&partial, &target).await.map_err(failed)
+    }
+}
+
+/// Writer for the configured destination, or `None` when none is configured
+pub fn analytics_export_writer(
+    config: &AnalyticsExportConfig,
+    credential: Arc<ManagedIdentityCredential>,
+) -> ApplicationResult<Option<Arc<dyn DatasetWriterPort>>> {
+    if let Some(dir) = &config.dir {
+        return Ok(Some(Arc::new(FileDatasetWriter::new(dir))));
+    }
+    config
+        .container_url
+        .as_deref()
+        .map(|url| -> ApplicationResult<Arc<dyn DatasetWriterPort>> {
+            let writer = BlobDatasetWriter::new(url, config.sas_token.as_ref(), credential, "ANALYTICS_EXPORT_CONTAINER_URL")?;
+            Ok(Arc::new(writer))
+        })
+        .transpose()
+}
+
+/// Spawn the scheduled export, exporting the previous day once per interval until shutdown
+pub fn spawn_analytics_export_task(
+    supervisor: &TaskSupervisor,
+    service: Arc<AnalyticsExportService>,
+    interval: Duration,
+) {
+    info!("Starting analytics export task (interval: {:?})", interval);
+
+    supervisor.spawn("analytics-export", move |shutdown| {
+        let service = service.clone();
+        async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = ticker.tick() => {}
+                }
+                if let Err(e) = service.export_yesterday().await {
+                    error!("Scheduled analytics export failed: {}", e);
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_writer_creates_folders() {
+        let root = tempfile::tempdir().unwrap();
+        let writer = FileDatasetWriter::new(root.path());
+        writer
+            .put("lake/date=2024-03-01/fields.parquet", "application/vnd.apache.parquet", Bytes::from_static(b"PAR1"))
+            .await
+            .unwrap();
+        writer
+            .put("lake/date=2024-03-01/fields.parquet", "application/vnd.apache.parquet", Bytes::from_static(b"PAR2"))
+            .await
+            .unwrap();
+
+        let folder = root.path().join("lake/date=2024-03-01");
+        assert_eq!(std::fs::read(folder.join("fields.parquet")).unwrap(), b"PAR2");
+        assert_eq!(std::fs::read_dir(folder).unwrap().count(), 1, "no partial file left behind");
+    }
+}
@@ -0,0 +1,167 @@
+/// AWS SQS and SNS completion notifications
+///
+/// For consumers that live in AWS. Credentials come from the default AWS
+/// provider chain, so an IAM role (EC2 instance profile, ECS task role or EKS
+/// service account) works without keys in configuration. Only completion
+/// events are sent; FIFO queues and topics group messages by operation.
+
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_sdk_sns::types::MessageAttributeValue as SnsAttribute;
+use aws_sdk_sqs::types::MessageAttributeValue as SqsAttribute;
+use base64::{Engine as _, engine::general_purpose};
+use std::collections::HashMap;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::EventPublisherPort;
+use crate::domain::{LifecycleEvent, LifecycleEventKind};
+use crate::infrastructure::config::EventsConfig;
+use crate::infrastructure::events::EventFormat;
+use crate::infrastructure::metrics::metrics;
+
+/// Suffix AWS requires on FIFO queue URLs and topic ARNs
+const FIFO_SUFFIX: &str = ".fifo";
+
+/// Publishers for the configured SQS queue and SNS topic, if any
+pub async fn aws_event_publishers(config: &EventsConfig) -> Vec<Arc<dyn EventPublisherPort>> {
+    if config.sqs_queue_url.is_none() && config.sns_topic_arn.is_none() {
+        return Vec::new();
+    }
+    let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+    let mut publishers: Vec<Arc<dyn EventPublisherPort>> = Vec::new();
+    if let Some(queue_url) = &config.sqs_queue_url {
+        info!("Sending completion notifications to SQS queue {}", queue_url);
+        publishers.push(Arc::new(SqsEventPublisher {
+            client: aws_sdk_sqs::Client::new(&sdk_config),
+            queue_url: queue_url.clone(),
+            format: config.format,
+        }));
+    }
+    if let Some(topic_arn) = &config.sns_topic_arn {
+        info!("Sending completion notifications to SNS topic {}", topic_arn);
+        publishers.push(Arc::new(SnsEventPublisher {
+            client: aws_sdk_sns::Client::new(&sdk_config),
+            topic_arn: topic_arn.clone(),
+            format: config.format,
+        }));
+    }
+    publishers
+}
+
+/// Sends completion events to an SQS queue
+pub struct SqsEventPublisher {
+    client: aws_sdk_sqs::Client,
+    queue_url: String,
+    format: EventFormat,
+}
+
+#[async_trait]
+impl EventPublisherPort for SqsEventPublisher {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()> {
+        if event.event_type == LifecycleEventKind::Created {
+            return Ok(());
+        }
+        let mut attributes = HashMap::new();
+        for (name, value) in message_attributes(event, self.format) {
+            let attribute = SqsAttribute::builder()
+                .data_type("String")
+                .string_value(value)
+                .build()
+                .map_err(|e| ApplicationError::Internal(format!("Invalid SQS attribute {}: {}", name, e)))?;
+            attributes.insert(name.to_string(), attribute);
+        }
+
+        let mut request = self
+            .client
+            .send_message()
+            .queue_url(&self.queue_url)
+            .message_body(message_body(event, self.format)?)
+            .set_message_attributes(Some(attributes));
+        if self.queue_url.ends_with(FIFO_SUFFIX) {
+            request = request
+                .message_group_id(&event.operation_id)
+                .message_deduplication_id(&event.event_id);
+        }
+
+        let sent = request.send().await;
+        metrics().record_event_published("sqs", sent.is_ok());
+        let output = sent.map_err(|e| {
+            ApplicationError::Internal(format!(
+                "Failed to send SQS message: {}",
+                aws_sdk_sqs::error::DisplayErrorContext(e)
+            ))
+        })?;
+        debug!("Event {} sent to SQS as {:?}", event.event_id, output.message_id());
+        Ok(())
+    }
+}
+
+/// Publishes completion events to an SNS topic
+pub struct SnsEventPublisher {
+    client: aws_sdk_sns::Client,
+    topic_arn: String,
+    format: EventFormat,
+}
+
+#[async_trait]
+impl EventPublisherPort for SnsEventPublisher {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()> {
+        if event.event_type == LifecycleEventKind::Created {
+            return Ok(());
+        }
+        let mut attributes = HashMap::new();
+        for (name, value) in message_attributes(event, self.format) {
+            let attribute = SnsAttribute::builder()
+                .data_type("String")
+                .string_value(value)
+                .build()
+                .map_err(|e| ApplicationError::Internal(format!("Invalid SNS attribute {}: {}", name, e)))?;
+            attributes.insert(name.to_string(), attribute);
+        }
+
+        let mut request = self
+            .client
+            .publish()
+            .topic_arn(&self.topic_arn)
+            .message(message_body(event, self.format)?)
+            .set_message_attributes(Some(attributes));
+        if self.topic_arn.ends_with(FIFO_SUFFIX) {
+            request = request
+                .message_group_id(&event.operation_id)
+                .message_deduplication_id(&event.event_id);
+        }
+
+        let published = request.send().await;
+        metrics().record_event_published("sns", published.is_ok());
+        let output = published.map_err(|e| {
+            ApplicationError::Internal(format!(
+                "Failed to publish SNS message: {}",
+                aws_sdk_sns::error::DisplayErrorContext(e)
+            ))
+        })?;
+        debug!("Event {} published to SNS as {:?}", event.event_id, output.message_id());
+        Ok(())
+    }
+}
+
+/// Message bodies are text, so protobuf payloads are base64-encoded
+fn message_body(event: &LifecycleEvent, format: EventFormat) -> ApplicationResult<String> {
+    let payload = format.encode(event)?;
+    Ok(match format {
+        EventFormat::Json => String::from_utf8(payload)
+            .map_err(|e| ApplicationError::Internal(format!("Event JSON is not UTF-8: {}", e)))?,
+        EventFormat::Protobuf => general_purpose::STANDARD.encode(payload),
+    })
+}
+
+/// Attributes subscribers can filter on without parsing the body
+fn message_attributes(event: &LifecycleEvent, format: EventFormat) -> Vec<(&'static str, String)> {
+    vec![
+        ("event_type", event.event_type.as_str().to_string()),
+        ("tenant_id", event.tenant_id.to_string()),
+        ("model", event.model.clone()),
+        ("content_type", format.content_type().to_string()),
+    ]
+}
@@ -7,93 +7,427 @@
 /// the Azure REST API.
 
 use async_trait::async_trait;
-use reqwest::{Client, StatusCode};
-use serde::{Deserialize, Serialize};
+use reqwest::{Certificate, Client, Proxy, RequestBuilder, StatusCode};
+use serde::Deserialize;
 use std::collections::HashMap;
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
 use tracing::{debug, info, warn, error};
-use base64::{Engine as _, engine::general_purpose};
 
+use crate::application::{deadline, request_id};
 use crate::application::errors::{ApplicationError, ApplicationResult};
-use crate::application::ports::DocumentIntelligencePort;
+use crate::application::ports::{DocumentIntelligencePort, HealthCheckPort};
 use crate::domain::*;
-use crate::infrastructure::config::AzureConfig;
+use crate::infrastructure::config::{AzureConfig, HttpClientConfig, Secret};
+
+/// Wait between classification polls when Azure doesn't say
+const CLASSIFY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Query parameters for the analyze options Azure accepts
+fn analyze_query(options: &AnalyzeOptions) -> Vec<(&'static str, String)> {
+    let mut query = Vec::new();
+    if let Some(locale) = &options.locale {
+        query.push(("locale", locale.as_str().to_string()));
+    }
+    if let Some(pages) = options.pages.as_ref().filter(|pages| !pages.is_empty()) {
+        query.push(("pages", pages.as_vec().join(",")));
+    }
+    if !options.features.is_empty() {
+        let features: Vec<&str> = options.features.iter().map(|f| f.as_str()).collect();
+        query.push(("features", features.join(",")));
+    }
+    if let Some(string_index_type) = &options.string_index_type {
+        query.push(("stringIndexType", string_index_type.as_str().to_string()));
+    }
+    query
+}
+
+/// A failed Azure call; timeouts at the caller's deadline are reported as such
+fn request_error(e: reqwest::Error) -> ApplicationError {
+    if e.is_timeout() && deadline::remaining().is_some_and(|remaining| remaining.is_zero()) {
+        ApplicationError::DeadlineExceeded
+    } else if e.is_connect() || e.is_timeout() {
+        ApplicationError::AzureUnavailable(format!("Request failed: {}", e))
+    } else {
+        ApplicationError::AzureService(format!("Request failed: {}", e))
+    }
+}
+
+/// Error for an unsuccessful Azure response, with the code and message from
+/// its body; throttling is told apart so callers can back off
+async fn response_error(response: reqwest::Response) -> ApplicationError {
+    let status = response.status();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+    };
+    let retry_after_secs = header("retry-after").and_then(|value| value.parse().ok());
+    let request_id = header("apim-request-id").or_else(|| header("x-ms-request-id"));
+    let error_text = response
+        .text()
+        .await
+        .unwrap_or_else(|_| "Unknown error".to_string());
+    error!("Azure API error: {} - {}", status, error_text);
+    let error = match serde_json::from_str::<AzureErrorResponse>(&error_text) {
+        Ok(body) => body.error.into_domain(Some(status.as_u16()), request_id),
+        Err(_) => AzureError {
+            status: Some(status.as_u16()),
+            code: status.canonical_reason().unwrap_or("Unknown").replace(' ', ""),
+            message: error_text,
+            target: None,
+            inner_code: None,
+            request_id,
+        },
+    };
+    if status == StatusCode::TOO_MANY_REQUESTS {
+        ApplicationError::AzureThrottled { error: Box::new(error), retry_after_secs }
+    } else {
+        ApplicationError::Azure(Box::new(error))
+    }
+}
+
+/// Tag an Azure call with the request id it serves, for support tickets
+fn with_client_request_id(request: RequestBuilder) -> RequestBuilder {
+    match request_id::current() {
+        Some(id) => request.header("x-ms-client-request-id", id),
+        None => request,
+    }
+}
+
+/// HTTP client with the configured pooling, keep-alive, proxy and trust settings
+fn http_client(http: &HttpClientConfig, timeout: Duration) -> ApplicationResult<Client> {
+    let mut builder = Client::builder()
+        .timeout(timeout)
+        .pool_max_idle_per_host(http.pool_max_idle_per_host)
+        .pool_idle_timeout(http.pool_idle_timeout_secs.map(Duration::from_secs))
+        .tcp_keepalive(http.tcp_keepalive_secs.map(Duration::from_secs));
+    if let Some(secs) = http.http2_keepalive_secs {
+        builder = builder
+            .http2_keep_alive_interval(Duration::from_secs(secs))
+            .http2_keep_alive_while_idle(true);
+    }
+    if http.http2_prior_knowledge {
+        builder = builder.http2_prior_knowledge();
+    }
+    if let Some(proxy_url) = &http.proxy_url {
+        let proxy = Proxy::all(proxy_url.expose())
+            .map_err(|e| ApplicationError::Internal(format!("Invalid proxy URL: {}", e)))?;
+        builder = builder.proxy(proxy);
+    }
+    if let Some(path) = &http.ca_cert_path {
+        let pem = std::fs::read(path)
+            .map_err(|e| ApplicationError::Internal(format!("Failed to read CA certificate {}: {}", path, e)))?;
+        let certificate = Certificate::from_pem(&pem)
+            .map_err(|e| ApplicationError::Internal(format!("Invalid CA certificate {}: {}", path, e)))?;
+        builder = builder.add_root_certificate(certificate);
+    }
+    builder
+        .build()
+        .map_err(|e| ApplicationError::Internal(format!("Failed to create HTTP client: {}", e)))
+}
+
+/// How long a resource that throttled or failed is passed over for new
+/// submissions when Azure does not say
+const FAILOVER_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// A Document Intelligence resource the adapter submits to
+struct AzureResource {
+    /// Prefix of the operation ids it issues; empty for the primary resource
+    name: String,
+    endpoint: String,
+    /// Replaced when the key is rotated
+    key: RwLock<Secret>,
+    /// Tried when Azure rejects `key`, so either key can be regenerated without an outage
+    secondary_key: Option<Secret>,
+    /// Set once Azure rejected `key` and accepted `secondary_key`
+    on_secondary: AtomicBool,
+    weight: u32,
+    /// Passed over for new submissions until then, after it throttled or failed
+    cooling_until: RwLock<Option<Instant>>,
+}
+
+impl AzureResource {
+    fn new(name: &str, endpoint: &str, key: Secret, secondary_key: Option<Secret>, weight: u32) -> Self {
+        Self {
+            name: name.to_string(),
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            key: RwLock::new(key),
+            secondary_key,
+            on_secondary: AtomicBool::new(false),
+            weight,
+            cooling_until: RwLock::new(None),
+        }
+    }
+    
+    fn label(&self) -> &str {
+        if self.name.is_empty() {
+            "primary"
+        } else {
+            &self.name
+        }
+    }
+    
+    fn key(&self) -> String {
+        self.key.read().unwrap_or_else(|e| e.into_inner()).expose().to_string()
+    }
+    
+    /// Key to send, and the other key to retry with when Azure rejects it
+    fn keys(&self) -> (String, Option<String>) {
+        let secondary = self.secondary_key.as_ref().map(|key| key.expose().to_string());
+        match secondary {
+            Some(secondary) if self.on_secondary.load(Ordering::Relaxed) => (secondary, Some(self.key())),
+            secondary => (self.key(), secondary),
+        }
+    }
+    
+    fn is_cooling(&self, now: Instant) -> bool {
+        self.cooling_until.read().unwrap_or_else(|e| e.into_inner()).is_some_and(|until| until > now)
+    }
+    
+    fn cool_down(&self, duration: Duration) {
+        *self.cooling_until.write().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now() + duration);
+    }
+    
+    /// Operation id handed out for an operation Azure created on this resource
+    fn operation_id(&self, azure_id: &str) -> String {
+        if self.name.is_empty() {
+            azure_id.to_string()
+        } else {
+            format!("{}.{}", self.name, azure_id)
+        }
+    }
+}
+
+/// Whether a failed submission is worth retrying on another resource
+fn fails_over(error: &ApplicationError) -> bool {
+    match error {
+        ApplicationError::AzureThrottled { .. } | ApplicationError::AzureUnavailable(_) => true,
+        ApplicationError::Azure(error) => error.status.is_some_and(|status| status >= 500),
+        _ => false,
+    }
+}
 
 /// Azure Document Intelligence adapter
+///
+/// Submissions are spread over the configured resources by weight and move
+/// on to the next resource when one throttles or fails; each operation id
+/// names the resource that owns it, so polls go back to that resource.
 pub struct AzureDocumentIntelligenceAdapter {
     config: AzureConfig,
+    /// The primary resource first, then `config.resources` in order
+    resources: Vec<AzureResource>,
+    /// Submissions so far, to pick the next resource by weight
+    turn: AtomicUsize,
+    /// Model each running operation was submitted to, so polls use its path
+    /// and API version; operations from before a restart are polled as `prebuilt-read`
+    operation_models: RwLock<HashMap<String, String>>,
     client: Client,
 }
 
 impl AzureDocumentIntelligenceAdapter {
-    pub fn new(config: AzureConfig) -> Self {
-        let client = Client::builder()
-            .timeout(Duration::from_secs(300))
-            .build()
-            .expect("Failed to create HTTP client");
+    /// Fails when the proxy URL or CA certificate in `config.http` is unusable
+    pub fn new(config: AzureConfig) -> ApplicationResult<Self> {
+        let client = http_client(&config.http, Duration::from_secs(config.request_timeout_secs))?;
+        let mut resources = vec![AzureResource::new(
+            "",
+            &config.endpoint,
+            config.key.clone(),
+            config.secondary_key.clone(),
+            config.weight,
+        )];
+        resources.extend(config.resources.iter().map(|resource| {
+            AzureResource::new(
+                &resource.name,
+                &resource.endpoint,
+                resource.key.clone(),
+                resource.secondary_key.clone(),
+                resource.weight,
+            )
+        }));
+        if resources.iter().all(|resource| resource.weight == 0) {
+            return Err(ApplicationError::Internal("At least one Azure resource needs a weight above 0".to_string()));
+        }
         
-        Self { config, client }
+        Ok(Self {
+            config,
+            resources,
+            turn: AtomicUsize::new(0),
+            operation_models: RwLock::new(HashMap::new()),
+            client,
+        })
     }
     
-    fn build_url(&self, path: &str) -> String {
+    fn primary(&self) -> &AzureResource {
+        &self.resources[0]
+    }
+    
+    /// Use `key` for every call to the primary resource from now on, e.g.
+    /// after rotating it in Azure; `false` when it is the key already in use
+    pub fn set_key(&self, key: Secret) -> bool {
+        let primary = self.primary();
+        let mut current = primary.key.write().unwrap_or_else(|e| e.into_inner());
+        if current.expose() == key.expose() {
+            return false;
+        }
+        *current = key;
+        primary.on_secondary.store(false, Ordering::Relaxed);
+        true
+    }
+    
+    /// Send a request built for a key; when Azure rejects the key with a 401,
+    /// retry once with the resource's other key and keep using that one if it works
+    async fn send_with_key(
+        &self,
+        resource: &AzureResource,
+        request: impl Fn(&str) -> RequestBuilder,
+    ) -> ApplicationResult<reqwest::Response> {
+        let (key, other_key) = resource.keys();
+        let response = request(&key).send().await.map_err(request_error)?;
+        let other_key = match other_key {
+            Some(other_key) if response.status() == StatusCode::UNAUTHORIZED => other_key,
+            _ => return Ok(response),
+        };
+        let retried = request(&other_key).send().await.map_err(request_error)?;
+        if retried.status() != StatusCode::UNAUTHORIZED {
+            let rejected = if resource.on_secondary.fetch_xor(true, Ordering::Relaxed) { "secondary" } else { "primary" };
+            warn!(
+                "Azure rejected the {} key of resource {}; switched to the other key. Rotate the rejected key in configuration",
+                rejected,
+                resource.label()
+            );
+            #[cfg(feature = "server")]
+            crate::infrastructure::metrics::metrics().record_azure_key_fallback(resource.label(), rejected);
+        }
+        Ok(retried)
+    }
+    
+    /// Resources to try for the next submission: the one whose turn it is by
+    /// weight, then the others in configured order, cooling ones last
+    fn submission_order(&self) -> Vec<&AzureResource> {
+        let total: usize = self.resources.iter().map(|resource| resource.weight as usize).sum();
+        let mut slot = self.turn.fetch_add(1, Ordering::Relaxed) % total;
+        let first = self
+            .resources
+            .iter()
+            .position(|resource| {
+                let picked = slot < resource.weight as usize;
+                slot = slot.saturating_sub(resource.weight as usize);
+                picked
+            })
+            .unwrap_or(0);
+        
+        let mut order: Vec<&AzureResource> = std::iter::once(&self.resources[first])
+            .chain(self.resources.iter().enumerate().filter(|(index, _)| *index != first).map(|(_, resource)| resource))
+            .collect();
+        let now = Instant::now();
+        order.sort_by_key(|resource| resource.is_cooling(now));
+        order
+    }
+    
+    /// Resource that owns an operation and Azure's id for it
+    fn resource_for<'a>(&self, operation_id: &'a str) -> (&AzureResource, &'a str) {
+        operation_id
+            .split_once('.')
+            .and_then(|(name, azure_id)| {
+                self.resources
+                    .iter()
+                    .find(|resource| !resource.name.is_empty() && resource.name == name)
+                    .map(|resource| (resource, azure_id))
+            })
+            .unwrap_or((self.primary(), operation_id))
+    }
+    
+    /// Timeout for the next Azure call: the configured one, cut short by the caller's deadline
+    fn request_timeout(&self) -> ApplicationResult<Duration> {
+        let timeout = Duration::from_secs(self.config.request_timeout_secs);
+        match deadline::remaining() {
+            Some(remaining) if remaining.is_zero() => Err(ApplicationError::DeadlineExceeded),
+            Some(remaining) => Ok(timeout.min(remaining)),
+            None => Ok(timeout),
+        }
+    }
+    
+    /// API version for a model: its override, or the global one
+    fn api_version(&self, model_id: &str) -> &str {
+        self.config
+            .model_api_versions
+            .get(model_id)
+            .unwrap_or(&self.config.api_version)
+    }
+    
+    fn build_url(&self, resource: &AzureResource, path: &str) -> String {
         format!(
             "{}/documentintelligence/documentModels/{}:analyze?api-version={}",
-            self.config.endpoint.trim_end_matches('/'),
+            resource.endpoint,
             path,
-            self.config.api_version
+            self.api_version(path)
         )
     }
     
-    fn build_result_url(&self, model_id: &str, result_id: &str) -> String {
+    fn build_result_url(&self, resource: &AzureResource, model_id: &str, result_id: &str) -> String {
         format!(
             "{}/documentintelligence/documentModels/{}/analyzeResults/{}?api-version={}",
-            self.config.endpoint.trim_end_matches('/'),
+            resource.endpoint,
             model_id,
             result_id,
-            self.config.api_version
+            self.api_version(model_id)
         )
     }
     
+    /// Submit to the resource whose turn it is, failing over to the others
     async fn submit_analysis(
         &self,
         model_id: &str,
         request: &AnalyzeDocumentRequest,
     ) -> ApplicationResult<String> {
-        let url = self.build_url(model_id);
-        debug!("Submitting analysis to: {}", url);
-        
-        let body = match &request.source {
-            DocumentSource::Url(doc_url) => {
-                AzureAnalyzeRequest::Url { url_source: doc_url.clone() }
-            }
-            DocumentSource::Bytes(bytes) => {
-                AzureAnalyzeRequest::Base64 {
-                    base64_source: general_purpose::STANDARD.encode(bytes),
+        let mut last_error = None;
+        for resource in self.submission_order() {
+            match self.submit_to(resource, model_id, request).await {
+                Ok(azure_id) => return Ok(resource.operation_id(&azure_id)),
+                Err(e) if self.resources.len() > 1 && fails_over(&e) => {
+                    let cooldown = match &e {
+                        ApplicationError::AzureThrottled { retry_after_secs: Some(secs), .. } => Duration::from_secs(*secs),
+                        _ => FAILOVER_COOLDOWN,
+                    };
+                    warn!("Azure resource {} failed, trying the next one: {}", resource.label(), e);
+                    resource.cool_down(cooldown);
+                    last_error = Some(e);
                 }
+                Err(e) => return Err(e),
             }
-        };
+        }
+        Err(last_error.unwrap_or_else(|| ApplicationError::Internal("No Azure resource configured".to_string())))
+    }
+    
+    async fn submit_to(
+        &self,
+        resource: &AzureResource,
+        model_id: &str,
+        request: &AnalyzeDocumentRequest,
+    ) -> ApplicationResult<String> {
+        let url = self.build_url(resource, model_id);
+        debug!("Submitting analysis to: {}", url);
         
+        let timeout = self.request_timeout()?;
+        let query = analyze_query(&request.options);
         let response = self
-            .client
-            .post(&url)
-            .header("Ocp-Apim-Subscription-Key", &self.config.key)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(|e| ApplicationError::AzureService(format!("Request failed: {}", e)))?;
+            .send_with_key(resource, |key| {
+                let (content_type, body) = analyze_body(&request.source);
+                with_client_request_id(self.client.post(&url))
+                    .query(&query)
+                    .header("Ocp-Apim-Subscription-Key", key)
+                    .header("Content-Type", content_type)
+                    .body(body)
+                    .timeout(timeout)
+            })
+            .await?;
         
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            error!("Azure API error: {} - {}", status, error_text);
-            return Err(ApplicationError::AzureService(format!(
-                "API returned status {}: {}",
-                status, error_text
-            )));
+            return Err(response_error(response).await);
         }
         
         // Extract operation location from headers
@@ -118,54 +452,145 @@ impl AzureDocumentIntelligenceAdapter {
             .unwrap_or(&operation_location)
             .to_string();
         
-        info!("Analysis submitted successfully: operation_id={}", operation_id);
+        info!("Analysis submitted successfully to {}: operation_id={}", resource.label(), operation_id);
         Ok(operation_id)
     }
     
+    /// Poll an operation, returning the parsed response and its raw body
     async fn poll_result(
         &self,
         model_id: &str,
         operation_id: &str,
-    ) -> ApplicationResult<AzureAnalyzeResult> {
-        let url = self.build_result_url(model_id, operation_id);
+    ) -> ApplicationResult<(AzureAnalyzeOperation, bytes::Bytes)> {
+        let (resource, azure_id) = self.resource_for(operation_id);
+        let url = self.build_result_url(resource, model_id, azure_id);
         debug!("Polling result from: {}", url);
         
+        let timeout = self.request_timeout()?;
         let response = self
-            .client
-            .get(&url)
-            .header("Ocp-Apim-Subscription-Key", &self.config.key)
-            .send()
-            .await
-            .map_err(|e| ApplicationError::AzureService(format!("Request failed: {}", e)))?;
+            .send_with_key(resource, |key| {
+                with_client_request_id(self.client.get(&url))
+                    .header("Ocp-Apim-Subscription-Key", key)
+                    .timeout(timeout)
+            })
+            .await?;
         
         if response.status() == StatusCode::NOT_FOUND {
             return Err(ApplicationError::OperationNotFound(operation_id.to_string()));
         }
         
         if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response
-                .text()
-                .await
-                .unwrap_or_else(|_| "Unknown error".to_string());
-            return Err(ApplicationError::AzureService(format!(
-                "API returned status {}: {}",
-                status, error_text
-            )));
+            return Err(response_error(response).await);
         }
         
-        let result: AzureAnalyzeResult = response
-            .json()
+        let body = response
+            .bytes()
             .await
+            .map_err(|e| ApplicationError::AzureService(format!("Failed to read response: {}", e)))?;
+        let result: AzureAnalyzeOperation = serde_json::from_slice(&body)
             .map_err(|e| ApplicationError::AzureService(format!("Failed to parse response: {}", e)))?;
         
-        Ok(result)
+        Ok((result, body))
+    }
+    
+    /// Classify a document with a custom classification model, waiting for
+    /// Azure to finish; the most confident document type found wins
+    pub async fn classify_document(
+        &self,
+        classifier_id: &str,
+        source: &DocumentSource,
+    ) -> ApplicationResult<Option<DocumentClassification>> {
+        let url = format!(
+            "{}/documentintelligence/documentClassifiers/{}:analyze?api-version={}",
+            self.primary().endpoint,
+            classifier_id,
+            self.api_version(classifier_id)
+        );
+        debug!("Submitting classification to: {}", url);
+        
+        // Classification is quick, so it is waited for within one request timeout
+        let budget = self.request_timeout()?;
+        let started = tokio::time::Instant::now();
+        let response = self
+            .send_with_key(self.primary(), |key| {
+                let (content_type, body) = analyze_body(source);
+                with_client_request_id(self.client.post(&url))
+                    .header("Ocp-Apim-Subscription-Key", key)
+                    .header("Content-Type", content_type)
+                    .body(body)
+                    .timeout(budget)
+            })
+            .await?;
+        if !response.status().is_success() {
+            return Err(response_error(response).await);
+        }
+        let result_url = response
+            .headers()
+            .get("operation-location")
+            .and_then(|value| value.to_str().ok())
+            .ok_or_else(|| {
+                ApplicationError::AzureService("No operation location in classification response".to_string())
+            })?
+            .to_string();
+        
+        loop {
+            let timeout = self.request_timeout()?;
+            let response = self
+                .send_with_key(self.primary(), |key| {
+                    with_client_request_id(self.client.get(&result_url))
+                        .header("Ocp-Apim-Subscription-Key", key)
+                        .timeout(timeout)
+                })
+                .await?;
+            if !response.status().is_success() {
+                return Err(response_error(response).await);
+            }
+            let retry_after = response
+                .headers()
+                .get("retry-after")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok())
+                .map(Duration::from_secs)
+                .unwrap_or(CLASSIFY_POLL_INTERVAL);
+            let operation: AzureAnalyzeOperation = response
+                .json()
+                .await
+                .map_err(|e| ApplicationError::AzureService(format!("Failed to parse response: {}", e)))?;
+            
+            match operation.status.as_str() {
+                "succeeded" => {
+                    let documents = operation.analyze_result.and_then(|result| result.documents).unwrap_or_default();
+                    return Ok(documents
+                        .into_iter()
+                        .map(|document| DocumentClassification {
+                            doc_type: document.doc_type,
+                            confidence: document.confidence.unwrap_or(1.0),
+                        })
+                        .max_by(|a, b| a.confidence.total_cmp(&b.confidence)));
+                }
+                "failed" => {
+                    let reason = operation
+                        .error
+                        .map(|error| format!("{}: {}", error.code, error.message))
+                        .unwrap_or_else(|| "no reason given".to_string());
+                    return Err(ApplicationError::AnalysisFailed(format!("Classification failed: {}", reason)));
+                }
+                _ => {}
+            }
+            if started.elapsed() + retry_after > budget {
+                return Err(ApplicationError::AzureUnavailable(format!(
+                    "Classification did not finish within {:?}",
+                    budget
+                )));
+            }
+            tokio::time::sleep(retry_after).await;
+        }
     }
     
-    fn convert_azure_result(&self, azure_result: AzureAnalyzeResult) -> AnalysisResult {
+    fn convert_azure_result(api_version: &str, azure_result: AzureAnalyzeResult) -> AnalysisResult {
         AnalysisResult {
             model_id: azure_result.model_id.unwrap_or_default(),
-            api_version: self.config.api_version.clone(),
+            api_version: api_version.to_string(),
             content: azure_result.content.unwrap_or_default(),
             pages: azure_result
                 .pages
@@ -191,6 +616,10 @@ impl AzureDocumentIntelligenceAdapter {
                 .into_iter()
                 .map(Self::convert_document)
                 .collect(),
+            string_index_type: azure_result
+                .string_index_type
+                .and_then(|value| StringIndexType::parse(&value).ok())
+                .unwrap_or_default(),
         }
     }
     
@@ -207,10 +636,18 @@ impl AzureDocumentIntelligenceAdapter {
         }
     }
     
+    /// Azure returns polygons as a flat `[x1, y1, x2, y2, ...]` list
+    fn convert_polygon(polygon: &[f32]) -> Vec<Point> {
+        polygon
+            .chunks_exact(2)
+            .map(|p| Point { x: p[0], y: p[1] })
+            .collect()
+    }
+    
     fn convert_word(word: AzureWord) -> DocumentWord {
         DocumentWord {
             content: word.content,
-            polygon: word.polygon.into_iter().map(|p| Point { x: p[0], y: p[1] }).collect(),
+            polygon: Self::convert_polygon(&word.polygon),
             confidence: word.confidence.unwrap_or(1.0),
             span: Span {
                 offset: word.span.offset,
@@ -222,7 +659,7 @@ impl AzureDocumentIntelligenceAdapter {
     fn convert_line(line: AzureLine) -> DocumentLine {
         DocumentLine {
             content: line.content,
-            polygon: line.polygon.into_iter().map(|p| Point { x: p[0], y: p[1] }).collect(),
+            polygon: Self::convert_polygon(&line.polygon),
             spans: line.spans.into_iter().map(|s| Span {
                 offset: s.offset,
                 length: s.length,
@@ -236,7 +673,7 @@ impl AzureDocumentIntelligenceAdapter {
                 "selected" => SelectionMarkState::Selected,
                 _ => SelectionMarkState::Unselected,
             },
-            polygon: mark.polygon.into_iter().map(|p| Point { x: p[0], y: p[1] }).collect(),
+            polygon: Self::convert_polygon(&mark.polygon),
             confidence: mark.confidence.unwrap_or(1.0),
         }
     }
@@ -296,13 +733,15 @@ impl DocumentIntelligencePort for AzureDocumentIntelligenceAdapter {
         info!("Starting analysis with model: {}", model_id);
         let operation_id = self.submit_analysis(model_id, &request).await?;
         
+        self.operation_models
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(operation_id.clone(), model_id.to_string());
+        
         let mut operation = AnalysisOperation::new(request.model_type);
         operation.operation_id = operation_id.clone();
         operation.update_status(OperationStatus::Running);
         
-        // Store the model ID with the operation for later retrieval
-        // We'll use a simple format: operation_id contains just the UUID part
-        
         Ok(operation)
     }
     
@@ -310,19 +749,76 @@ impl DocumentIntelligencePort for AzureDocumentIntelligenceAdapter {
         &self,
         operation_id: &str,
     ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)> {
-        // This is a fallback - should prefer using model type from database
-        // Try most common model only
-        warn!("get_analysis_result called without model context");
-        
-        let model_id = "prebuilt-read";
-        let model_type = ModelType::Read;
+        let (operation, result, _) = self.fetch_result(operation_id, false).await?;
+        Ok((operation, result))
+    }
+    
+    async fn get_analysis_result_raw(
+        &self,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>, Option<serde_json::Value>)> {
+        self.fetch_result(operation_id, true).await
+    }
+    
+    async fn validate_custom_model(&self, model_id: &str) -> ApplicationResult<bool> {
+        // In a full implementation, this would check if the model exists
+        // For now, we'll assume custom models exist
+        info!("Validating custom model: {}", model_id);
+        Ok(true)
+    }
+}
+
+/// Reachability of the Document Intelligence endpoint; any answer short of a
+/// server error counts, since the endpoint root itself is not an API route
+#[async_trait]
+impl HealthCheckPort for AzureDocumentIntelligenceAdapter {
+    fn dependency(&self) -> &'static str {
+        "azure"
+    }
+    
+    async fn check_health(&self) -> ApplicationResult<()> {
+        let response = with_client_request_id(self.client.head(&self.primary().endpoint))
+            .timeout(self.request_timeout()?)
+            .send()
+            .await
+            .map_err(request_error)?;
+        if response.status().is_server_error() {
+            return Err(ApplicationError::AzureUnavailable(format!(
+                "Endpoint returned status {}",
+                response.status()
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl AzureDocumentIntelligenceAdapter {
+    /// Poll and convert an operation, keeping the raw response of a
+    /// succeeded one when `keep_raw` is set
+    async fn fetch_result(
+        &self,
+        operation_id: &str,
+        keep_raw: bool,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>, Option<serde_json::Value>)> {
+        let submitted = self
+            .operation_models
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(operation_id)
+            .cloned();
+        let model_id = submitted.unwrap_or_else(|| {
+            // Submitted before a restart; the tracker knows the model but the adapter does not
+            warn!("get_analysis_result called without model context");
+            ModelType::Read.as_str().to_string()
+        });
+        let model_type = ModelType::from_string(&model_id).unwrap_or(ModelType::Custom);
         
-        match self.poll_result(model_id, operation_id).await {
-            Ok(azure_result) => {
+        match self.poll_result(&model_id, operation_id).await {
+            Ok((azure_operation, body)) => {
                 let mut operation = AnalysisOperation::new(model_type);
                 operation.operation_id = operation_id.to_string();
                 
-                let status = match azure_result.status.as_str() {
+                let status = match azure_operation.status.as_str() {
                     "succeeded" => OperationStatus::Succeeded,
                     "failed" => OperationStatus::Failed,
                     "running" => OperationStatus::Running,
@@ -330,46 +826,126 @@ impl DocumentIntelligencePort for AzureDocumentIntelligenceAdapter {
                 };
                 
                 operation.update_status(status);
+                operation.error = azure_operation.error.map(|error| error.into_domain(None, None));
+                if status != OperationStatus::Running {
+                    self.operation_models.write().unwrap_or_else(|e| e.into_inner()).remove(operation_id);
+                }
                 
                 let result = if status == OperationStatus::Succeeded {
-                    Some(self.convert_azure_result(azure_result))
+                    let api_version = self.api_version(&model_id);
+                    azure_operation
+                        .analyze_result
+                        .map(|azure_result| Self::convert_azure_result(api_version, azure_result))
+                } else {
+                    None
+                };
+                let raw = if keep_raw && result.is_some() {
+                    Some(serde_json::from_slice(&body).map_err(|e| {
+                        ApplicationError::AzureService(format!("Failed to parse response: {}", e))
+                    })?)
                 } else {
                     None
                 };
                 
-                Ok((operation, result))
+                Ok((operation, result, raw))
             }
             Err(e) => Err(e),
         }
     }
-    
-    
-    async fn validate_custom_model(&self, model_id: &str) -> ApplicationResult<bool> {
-        // In a full implementation, this would check if the model exists
-        // For now, we'll assume custom models exist
-        info!("Validating custom model: {}", model_id);
-        Ok(true)
+}
+
+/// Convert a succeeded `analyzeResults` response body, as the adapter would
+pub(crate) fn parse_analyze_response(api_version: &str, body: &[u8]) -> ApplicationResult<Option<AnalysisResult>> {
+    let operation: AzureAnalyzeOperation = serde_json::from_slice(body)
+        .map_err(|e| ApplicationError::AzureService(format!("Failed to parse response: {}", e)))?;
+    Ok(operation
+        .analyze_result
+        .map(|azure_result| AzureDocumentIntelligenceAdapter::convert_azure_result(api_version, azure_result)))
+}
+
+/// Content type and body of an analyze call
+///
+/// URLs go as JSON. Documents go as raw bytes under the content type detected
+/// from them, which Azure accepts in place of a base64 JSON field and which
+/// avoids both the third larger payload and an encoded copy of the document.
+/// Detection runs on the bytes actually sent, since preprocessing may have
+/// changed their format.
+fn analyze_body(source: &DocumentSource) -> (&'static str, reqwest::Body) {
+    match source {
+        DocumentSource::Url(url) => ("application/json", serde_json::json!({ "urlSource": url }).to_string().into()),
+        DocumentSource::Bytes(bytes) => {
+            let content_type = DocumentFormat::detect(bytes)
+                .map(|format| format.mime_type())
+                .unwrap_or("application/octet-stream");
+            (content_type, bytes.clone().into())
+        }
     }
 }
 
 // Azure API DTOs
-#[derive(Debug, Serialize)]
-#[serde(untagged)]
-enum AzureAnalyzeRequest {
-    Url { #[serde(rename = "urlSource")] url_source: String },
-    Base64 { #[serde(rename = "base64Source")] base64_source: String },
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AzureAnalyzeOperation {
+    status: String,
+    analyze_result: Option<AzureAnalyzeResult>,
+    /// Why a failed operation failed
+    #[serde(default)]
+    error: Option<AzureErrorBody>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AzureErrorResponse {
+    error: AzureErrorBody,
+}
+
+#[derive(Debug, Deserialize)]
+struct AzureErrorBody {
+    code: String,
+    message: String,
+    #[serde(default)]
+    target: Option<String>,
+    #[serde(default)]
+    innererror: Option<AzureInnerError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AzureInnerError {
+    code: String,
+    #[serde(default)]
+    innererror: Option<Box<AzureInnerError>>,
+}
+
+impl AzureErrorBody {
+    fn into_domain(self, status: Option<u16>, request_id: Option<String>) -> AzureError {
+        // Each nested innererror narrows the reason down further
+        let mut inner = self.innererror.as_ref();
+        let mut inner_code = None;
+        while let Some(error) = inner {
+            inner_code = Some(error.code.clone());
+            inner = error.innererror.as_deref();
+        }
+        AzureError {
+            status,
+            code: self.code,
+            message: self.message,
+            target: self.target,
+            inner_code,
+            request_id,
+        }
+    }
 }
 
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct AzureAnalyzeResult {
-    status: String,
     model_id: Option<String>,
     content: Option<String>,
     pages: Option<Vec<AzurePage>>,
     tables: Option<Vec<AzureTable>>,
     key_value_pairs: Option<Vec<AzureKeyValuePair>>,
     documents: Option<Vec<AzureDocument>>,
+    string_index_type: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -388,7 +964,7 @@ struct AzurePage {
 #[derive(Debug, Deserialize)]
 struct AzureWord {
     content: String,
-    polygon: Vec<[f32; 2]>,
+    polygon: Vec<f32>,
     confidence: Option<f32>,
     span: AzureSpan,
 }
@@ -396,14 +972,14 @@ struct AzureWord {
 #[derive(Debug, Deserialize)]
 struct AzureLine {
     content: String,
-    polygon: Vec<[f32; 2]>,
+    polygon: Vec<f32>,
     spans: Vec<AzureSpan>,
 }
 
 #[derive(Debug, Deserialize)]
 struct AzureSelectionMark {
     state: String,
-    polygon: Vec<[f32; 2]>,
+    polygon: Vec<f32>,
     confidence: Option<f32>,
 }
 
@@ -448,6 +1024,8 @@ struct AzureKeyValueElement {
 #[serde(rename_all = "camelCase")]
 struct AzureDocument {
     doc_type: String,
+    /// Absent from classifier results
+    #[serde(default)]
     fields: HashMap<String, AzureField>,
     confidence: Option<f32>,
 }
@@ -460,17 +1038,113 @@ struct AzureField {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::infrastructure::config::{AzureMode, AzureResourceConfig};
 
     #[test]
     fn test_azure_adapter_creation() {
         let config = AzureConfig {
             endpoint: "https://test.cognitiveservices.azure.com".to_string(),
-            key: "test-key".to_string(),
+            key: "test-key".into(),
+            secondary_key: None,
             api_version: "2024-02-29-preview".to_string(),
+            model_api_versions: HashMap::new(),
+            request_timeout_secs: 300,
+            mode: AzureMode::Live,
+            mock_delay_ms: 1500,
+            cassette_path: None,
+            http: HttpClientConfig::default(),
+            weight: 1,
+            resources: Vec::new(),
         };
         
-        let adapter = AzureDocumentIntelligenceAdapter::new(config);
-        assert!(adapter.build_url("prebuilt-read").contains("prebuilt-read"));
+        let adapter = AzureDocumentIntelligenceAdapter::new(config).unwrap();
+        assert!(adapter.build_url(adapter.primary(), "prebuilt-read").contains("prebuilt-read"));
+        assert_eq!(adapter.primary().keys().0, "test-key");
+
+        // Rotated keys are used from the next call
+        assert!(!adapter.set_key("test-key".into()));
+        assert!(adapter.set_key("rotated-key".into()));
+        assert_eq!(adapter.primary().keys().0, "rotated-key");
     }
-}
 
+    #[test]
+    fn test_resources_by_weight() {
+        let resource = |name: &str, weight| AzureResourceConfig {
+            name: name.to_string(),
+            endpoint: format!("https://{}.example.com", name),
+            key: format!("{}-key", name).into(),
+            secondary_key: None,
+            weight,
+        };
+        let config = AzureConfig {
+            endpoint: "https://primary.example.com/".to_string(),
+            key: "primary-key".into(),
+            secondary_key: None,
+            api_version: "2024-02-29-preview".to_string(),
+            model_api_versions: HashMap::new(),
+            request_timeout_secs: 300,
+            mode: AzureMode::Live,
+            mock_delay_ms: 1500,
+            cassette_path: None,
+            http: HttpClientConfig::default(),
+            weight: 1,
+            resources: vec![resource("east", 2), resource("standby", 0)],
+        };
+        let adapter = AzureDocumentIntelligenceAdapter::new(config.clone()).unwrap();
+
+        let firsts: Vec<&str> = (0..6).map(|_| adapter.submission_order()[0].label()).collect();
+        assert_eq!(firsts, ["primary", "east", "east", "primary", "east", "east"]);
+        let order: Vec<&str> = adapter.submission_order().iter().map(|resource| resource.label()).collect();
+        assert_eq!(order, ["primary", "east", "standby"]);
+
+        // Resources that just failed are tried last
+        adapter.resources[0].cool_down(Duration::from_secs(60));
+        let order: Vec<&str> = adapter.submission_order().iter().map(|resource| resource.label()).collect();
+        assert_eq!(order, ["east", "standby", "primary"]);
+
+        // Operation ids name the resource that owns them
+        assert_eq!(adapter.resources[1].operation_id("abc"), "east.abc");
+        let (owner, azure_id) = adapter.resource_for("east.abc");
+        assert_eq!((owner.label(), azure_id), ("east", "abc"));
+        let (owner, azure_id) = adapter.resource_for("abc");
+        assert_eq!((owner.label(), azure_id), ("primary", "abc"));
+        assert_eq!(owner.endpoint, "https://primary.example.com");
+
+        let mut idle = config;
+        idle.weight = 0;
+        idle.resources.truncate(0);
+        assert!(AzureDocumentIntelligenceAdapter::new(idle).is_err());
+    }
+
+    #[test]
+    fn test_analyze_body() {
+        let (content_type, body) = analyze_body(&DocumentSource::Url("https://x/a \"b\".pdf".to_string()));
+        assert_eq!(content_type, "application/json");
+        let url: serde_json::Value = serde_json::from_slice(body.as_bytes().unwrap()).unwrap();
+        assert_eq!(url["urlSource"], "https://x/a \"b\".pdf");
+
+        let data = bytes::Bytes::from_static(b"%PDF-1.7 binary\x00\xff");
+        let (content_type, body) = analyze_body(&DocumentSource::Bytes(data.clone()));
+        assert_eq!(content_type, "application/pdf");
+        assert_eq!(body.as_bytes(), Some(&data[..]));
+    }
+
+    #[test]
+    fn test_string_index_type() {
+        let options = AnalyzeOptions { string_index_type: Some(StringIndexType::Utf16CodeUnit), ..Default::default() };
+        assert!(analyze_query(&options).contains(&("stringIndexType", "utf16CodeUnit".to_string())));
+        assert!(analyze_query(&AnalyzeOptions::default()).is_empty());
+
+        let body = |string_index_type: &str| {
+            serde_json::json!({
+                "status": "succeeded",
+                "analyzeResult": { "modelId": "prebuilt-read", "stringIndexType": string_index_type }
+            })
+            .to_string()
+        };
+        let result = parse_analyze_response("2024-02-29-preview", body("utf16CodeUnit").as_bytes()).unwrap().unwrap();
+        assert_eq!(result.string_index_type, StringIndexType::Utf16CodeUnit);
+        let result = parse_analyze_response("2024-02-29-preview", body("unknown").as_bytes()).unwrap().unwrap();
+        assert_eq!(result.string_index_type, StringIndexType::TextElements);
+    }
+}
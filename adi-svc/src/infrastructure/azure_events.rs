@@ -0,0 +1,426 @@
+/// Azure Service Bus and Event Grid completion notifications
+///
+/// For consumers that live in Azure. Both adapters call the services' REST
+/// APIs. Service Bus always authenticates with the host's managed identity;
+/// Event Grid does too unless a topic access key is configured. Only
+/// completion events are sent.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, info};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::EventPublisherPort;
+use crate::domain::{LifecycleEvent, LifecycleEventKind};
+use crate::infrastructure::config::EventsConfig;
+use crate::infrastructure::events::EventFormat;
+use crate::infrastructure::metrics::metrics;
+
+/// Instance Metadata Service token endpoint on Azure VMs and AKS
+const IMDS_ENDPOINT: &str = "http://169.254.169.254/metadata/identity/oauth2/token";
+
+/// Token audience for Service Bus
+const SERVICE_BUS_RESOURCE: &str = "https://servicebus.azure.net";
+
+/// Token audience for Event Grid
+const EVENT_GRID_RESOURCE: &str = "https://eventgrid.azure.net";
+
+/// Tokens are refreshed this long before they expire
+const TOKEN_REFRESH_MARGIN_SECS: i64 = 300;
+
+/// Publishers for the configured Service Bus topic and Event Grid topic, if any
+pub fn azure_event_publishers(config: &EventsConfig) -> Vec<Arc<dyn EventPublisherPort>> {
+    let credential = Arc::new(ManagedIdentityCredential::from_env(
+        config.managed_identity_client_id.clone(),
+    ));
+    let mut publishers: Vec<Arc<dyn EventPublisherPort>> = Vec::new();
+    if let Some(namespace) = &config.service_bus_namespace {
+        let publisher = ServiceBusEventPublisher::new(
+            namespace,
+            &config.service_bus_topic,
+            credential.clone(),
+            config.format,
+        );
+        info!("Sending completion notifications to Service Bus topic {}", publisher.url);
+        publishers.push(Arc::new(publisher));
+    }
+    if let Some(endpoint) = &config.event_grid_endpoint {
+        info!("Sending completion notifications to Event Grid topic {}", endpoint);
+        let auth = match &config.event_grid_key {
+            Some(key) => EventGridAuth::Key(key.expose().to_string()),
+            None => EventGridAuth::ManagedIdentity(credential.clone()),
+        };
+        publishers.push(Arc::new(EventGridEventPublisher::new(endpoint, auth)));
+    }
+    publishers
+}
+
+/// Access tokens for the host's managed identity
+///
+/// Uses the App Service / Container Apps identity endpoint when the platform
+/// advertises one (`IDENTITY_ENDPOINT` and `IDENTITY_HEADER`), IMDS otherwise.
+/// Tokens are cached per resource until shortly before they expire.
+pub struct ManagedIdentityCredential {
+    client: Client,
+    endpoint: String,
+    identity_header: Option<String>,
+    client_id: Option<String>,
+    tokens: Mutex<HashMap<String, CachedToken>>,
+}
+
+struct CachedToken {
+    value: String,
+    expires_at: DateTime<Utc>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    /// Seconds since the epoch; IMDS sends it as a string
+    expires_on: serde_json::Value,
+}
+
+impl ManagedIdentityCredential {
+    /// `client_id` selects a user-assigned identity; the system-assigned one is used when unset
+    pub fn new(endpoint: impl Into<String>, identity_header: Option<String>, client_id: Option<String>) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.into(),
+            identity_header,
+            client_id,
+            tokens: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Credential for whichever identity endpoint this host provides
+    pub fn from_env(client_id: Option<String>) -> Self {
+        match (std::env::var("IDENTITY_ENDPOINT"), std::env::var("IDENTITY_HEADER")) {
+            (Ok(endpoint), Ok(header)) => Self::new(endpoint, Some(header), client_id),
+            _ => Self::new(IMDS_ENDPOINT, None, client_id),
+        }
+    }
+
+    /// Bearer token for `resource`
+    pub async fn token(&self, resource: &str) -> ApplicationResult<String> {
+        let mut tokens = self.tokens.lock().await;
+        let refresh_after = Utc::now() + Duration::seconds(TOKEN_REFRESH_MARGIN_SECS);
+        if let Some(token) = tokens.get(resource).filter(|token| token.expires_at > refresh_after) {
+            return Ok(token.value.clone());
+        }
+        let token = self.fetch(resource).await?;
+        let value = token.value.clone();
+        tokens.insert(resource.to_string(), token);
+        Ok(value)
+    }
+
+    async fn fetch(&self, resource: &str) -> ApplicationResult<CachedToken> {
+        let mut query = vec![("resource", resource.to_string())];
+        let request = match &self.identity_header {
+            Some(header) => {
+                query.push(("api-version", "2019-08-01".to_string()));
+                self.client.get(&self.endpoint).header("X-IDENTITY-HEADER", header)
+            }
+            None => {
+                query.push(("api-version", "2018-02-01".to_string()));
+                self.client.get(&self.endpoint).header("Metadata", "true")
+            }
+        };
+        if let Some(client_id) = &self.client_id {
+            query.push(("client_id", client_id.clone()));
+        }
+
+        let response = request
+            .query(&query)
+            .send()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to request managed identity token: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApplicationError::Internal(format!(
+                "Managed identity endpoint returned {}: {}",
+                status, body
+            )));
+        }
+        let token: TokenResponse = response
+            .json()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Invalid managed identity token response: {}", e)))?;
+
+        let expires_on = token
+            .expires_on
+            .as_i64()
+            .or_else(|| token.expires_on.as_str().and_then(|value| value.parse().ok()))
+            .and_then(|seconds| Utc.timestamp_opt(seconds, 0).single())
+            .ok_or_else(|| {
+                ApplicationError::Internal(format!("Invalid token expiry: {}", token.expires_on))
+            })?;
+        debug!("Managed identity token for {} expires at {}", resource, expires_on);
+        Ok(CachedToken {
+            value: token.access_token,
+            expires_at: expires_on,
+        })
+    }
+}
+
+/// Sends completion events to a Service Bus topic
+pub struct ServiceBusEventPublisher {
+    client: Client,
+    url: String,
+    credential: Arc<ManagedIdentityCredential>,
+    format: EventFormat,
+}
+
+impl ServiceBusEventPublisher {
+    /// `namespace` is a bare name (`contoso`), a host name or a full URL
+    pub fn new(
+        namespace: &str,
+        topic: &str,
+        credential: Arc<ManagedIdentityCredential>,
+        format: EventFormat,
+    ) -> Self {
+        let namespace = namespace.trim().trim_end_matches('/');
+        let base = if namespace.contains("://") {
+            namespace.to_string()
+        } else if namespace.contains('.') {
+            format!("https://{}", namespace)
+        } else {
+            format!("https://{}.servicebus.windows.net", namespace)
+        };
+        Self {
+            client: Client::new(),
+            url: format!("{}/{}/messages", base, topic),
+            credential,
+            format,
+        }
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for ServiceBusEventPublisher {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()> {
+        if event.event_type == LifecycleEventKind::Created {
+            return Ok(());
+        }
+        let token = self.credential.token(SERVICE_BUS_RESOURCE).await?;
+        let broker_properties = json!({
+            "MessageId": event.event_id,
+            "CorrelationId": event.operation_id,
+            "Label": event.event_type.as_str(),
+        });
+
+        // Custom properties are headers; quoted values are read as strings
+        let sent = self
+            .client
+            .post(&self.url)
+            .bearer_auth(token)
+            .header("Content-Type", self.format.content_type())
+            .header("BrokerProperties", broker_properties.to_string())
+            .header("event_type", format!("\"{}\"", event.event_type.as_str()))
+            .header("tenant_id", format!("\"{}\"", event.tenant_id))
+            .header("model", format!("\"{}\"", event.model))
+            .body(self.format.encode(event)?)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to send Service Bus message: {}", e));
+        let sent = match sent {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                Err(format!("Service Bus returned {}: {}", status, body))
+            }
+            Err(e) => Err(e),
+        };
+        metrics().record_event_published("servicebus", sent.is_ok());
+        sent.map_err(ApplicationError::Internal)?;
+        debug!("Event {} sent to Service Bus", event.event_id);
+        Ok(())
+    }
+}
+
+/// How Event Grid requests are authorized
+pub enum EventGridAuth {
+    /// Topic access key (`aeg-sas-key`)
+    Key(String),
+    ManagedIdentity(Arc<ManagedIdentityCredential>),
+}
+
+/// Event in the Event Grid schema
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct EventGridEvent<'a> {
+    id: &'a str,
+    event_type: String,
+    subject: String,
+    event_time: DateTime<Utc>,
+    data: &'a LifecycleEvent,
+    data_version: &'static str,
+}
+
+/// Publishes completion events to an Event Grid topic
+///
+/// Event Grid carries JSON, so events use the Event Grid schema with the
+/// JSON event as `data` regardless of `EVENT_FORMAT`.
+pub struct EventGridEventPublisher {
+    client: Client,
+    endpoint: String,
+    auth: EventGridAuth,
+}
+
+impl EventGridEventPublisher {
+    /// `endpoint` is the topic endpoint, e.g. `https://topic.westeurope-1.eventgrid.azure.net/api/events`
+    pub fn new(endpoint: impl Into<String>, auth: EventGridAuth) -> Self {
+        Self {
+            client: Client::new(),
+            endpoint: endpoint.into(),
+            auth,
+        }
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for EventGridEventPublisher {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()> {
+        if event.event_type == LifecycleEventKind::Created {
+            return Ok(());
+        }
+        let body = [EventGridEvent {
+            id: &event.event_id,
+            event_type: format!("adi.operation.{}", event.event_type.as_str()),
+            subject: format!("tenants/{}/operations/{}", event.tenant_id, event.operation_id),
+            event_time: event.occurred_at,
+            data: event,
+            data_version: "1.0",
+        }];
+
+        let request = self.client.post(&self.endpoint).json(&body);
+        let request = match &self.auth {
+            EventGridAuth::Key(key) => request.header("aeg-sas-key", key),
+            EventGridAuth::ManagedIdentity(credential) => {
+                request.bearer_auth(credential.token(EVENT_GRID_RESOURCE).await?)
+            }
+        };
+        let sent = match request.send().await {
+            Ok(response) if response.status().is_success() => Ok(()),
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                Err(format!("Event Grid returned {}: {}", status, body))
+            }
+            Err(e) => Err(format!("Failed to publish Event Grid event: {}", e)),
+        };
+        metrics().record_event_published("eventgrid", sent.is_ok());
+        sent.map_err(ApplicationError::Internal)?;
+        debug!("Event {} published to Event Grid", event.event_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AnalysisOperation, ModelType, OperationStatus, TenantId};
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn completed_event() -> LifecycleEvent {
+        let mut operation = AnalysisOperation::new(ModelType::Invoice);
+        operation.tenant_id = TenantId::new("acme").unwrap();
+        operation.update_status(OperationStatus::Succeeded);
+        LifecycleEvent::new(LifecycleEventKind::Succeeded, &operation)
+    }
+
+    async fn mount_token(server: &MockServer, resource: &str) {
+        let expires_on = (Utc::now() + Duration::hours(1)).timestamp().to_string();
+        Mock::given(method("GET"))
+            .and(path("/token"))
+            .and(query_param("resource", resource))
+            .and(header("Metadata", "true"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "access_token": "mi-token",
+                "expires_on": expires_on,
+            })))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    #[tokio::test]
+    async fn test_service_bus_publish() {
+        let server = MockServer::start().await;
+        mount_token(&server, SERVICE_BUS_RESOURCE).await;
+        Mock::given(method("POST"))
+            .and(path("/adi.operations/messages"))
+            .and(header("Authorization", "Bearer mi-token"))
+            .and(header("tenant_id", "\"acme\""))
+            .and(header("Content-Type", "application/json"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let credential = Arc::new(ManagedIdentityCredential::new(format!("{}/token", server.uri()), None, None));
+        let publisher = ServiceBusEventPublisher::new(&server.uri(), "adi.operations", credential, EventFormat::Json);
+        let event = completed_event();
+        // The second publish reuses the cached token
+        publisher.publish(&event).await.unwrap();
+        publisher.publish(&event).await.unwrap();
+
+        let mut operation = AnalysisOperation::new(ModelType::Invoice);
+        operation.update_status(OperationStatus::Running);
+        publisher
+            .publish(&LifecycleEvent::new(LifecycleEventKind::Created, &operation))
+            .await
+            .unwrap();
+
+        let sent = &server.received_requests().await.unwrap()[1];
+        let properties: serde_json::Value =
+            serde_json::from_slice(sent.headers.get("BrokerProperties").unwrap().as_bytes()).unwrap();
+        assert_eq!(properties["MessageId"], event.event_id);
+        assert_eq!(properties["Label"], "succeeded");
+
+        let credential = Arc::new(ManagedIdentityCredential::new(IMDS_ENDPOINT, None, None));
+        let named = ServiceBusEventPublisher::new("contoso", "adi", credential, EventFormat::Json);
+        assert_eq!(named.url, "https://contoso.servicebus.windows.net/adi/messages");
+    }
+
+    #[tokio::test]
+    async fn test_event_grid_publish() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/events"))
+            .and(header("aeg-sas-key", "topic-key"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let publisher = EventGridEventPublisher::new(
+            format!("{}/api/events", server.uri()),
+            EventGridAuth::Key("topic-key".to_string()),
+        );
+        let event = completed_event();
+        publisher.publish(&event).await.unwrap();
+
+        let body: serde_json::Value =
+            serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(body[0]["id"], event.event_id);
+        assert_eq!(body[0]["eventType"], "adi.operation.succeeded");
+        assert_eq!(body[0]["subject"], format!("tenants/acme/operations/{}", event.operation_id));
+        assert_eq!(body[0]["data"]["model"], "prebuilt-invoice");
+        assert_eq!(body[0]["dataVersion"], "1.0");
+
+        let rejected = EventGridEventPublisher::new(
+            format!("{}/api/events", server.uri()),
+            EventGridAuth::Key("wrong".to_string()),
+        );
+        assert!(rejected.publish(&event).await.is_err());
+    }
+}
@@ -0,0 +1,182 @@
+/// Azure OpenAI embeddings adapter
+///
+/// Turns content chunks into vectors with an embeddings deployment of an
+/// Azure OpenAI resource, a batch of chunks per request. Requests carry the
+/// API key when one is configured and a managed identity token otherwise.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::json;
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::EmbeddingProviderPort;
+use crate::infrastructure::azure_events::ManagedIdentityCredential;
+use crate::infrastructure::config::EmbeddingsConfig;
+use crate::infrastructure::metrics::metrics;
+
+/// Token audience for Azure OpenAI
+const OPENAI_RESOURCE: &str = "https://cognitiveservices.azure.com";
+
+/// How Azure OpenAI requests are authorized
+pub enum AzureOpenAiAuth {
+    /// Resource key (`api-key`)
+    Key(String),
+    ManagedIdentity(Arc<ManagedIdentityCredential>),
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    index: usize,
+    embedding: Vec<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingsResponse {
+    data: Vec<EmbeddingData>,
+}
+
+/// Embeds text with an Azure OpenAI embeddings deployment
+pub struct AzureOpenAiEmbeddings {
+    client: Client,
+    url: String,
+    auth: AzureOpenAiAuth,
+    batch_size: usize,
+}
+
+impl AzureOpenAiEmbeddings {
+    /// `endpoint` is the resource URL, e.g. `https://acme.openai.azure.com`
+    pub fn new(endpoint: &str, deployment: &str, api_version: &str, auth: AzureOpenAiAuth, batch_size: usize) -> Self {
+        Self {
+            client: Client::new(),
+            url: format!(
+                "{}/openai/deployments/{}/embeddings?api-version={}",
+                endpoint.trim().trim_end_matches('/'),
+                deployment,
+                api_version
+            ),
+            auth,
+            batch_size: batch_size.max(1),
+        }
+    }
+
+    /// Provider for the configured resource, or `None` when none is configured
+    ///
+    /// Without an API key, requests are authorized with `credential`.
+    pub fn from_config(config: &EmbeddingsConfig, credential: Arc<ManagedIdentityCredential>) -> Option<Self> {
+        let endpoint = config.azure_openai_endpoint.as_ref()?;
+        let auth = match &config.azure_openai_key {
+            Some(key) => AzureOpenAiAuth::Key(key.expose().to_string()),
+            None => AzureOpenAiAuth::ManagedIdentity(credential),
+        };
+        info!("Embedding succeeded operations with Azure OpenAI deployment {}", config.deployment);
+        Some(Self::new(endpoint, &config.deployment, &config.api_version, auth, config.batch_size))
+    }
+
+    async fn embed_batch(&self, texts: &[String]) -> ApplicationResult<Vec<Vec<f32>>> {
+        let request = self.client.post(&self.url).json(&json!({ "input": texts }));
+        let request = match &self.auth {
+            AzureOpenAiAuth::Key(key) => request.header("api-key", key),
+            AzureOpenAiAuth::ManagedIdentity(credential) => {
+                request.bearer_auth(credential.token(OPENAI_RESOURCE).await?)
+            }
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to request embeddings: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApplicationError::Internal(format!("Azure OpenAI returned {}: {}", status, body)));
+        }
+        let mut embeddings = response
+            .json::<EmbeddingsResponse>()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Invalid embeddings response: {}", e)))?
+            .data;
+        if embeddings.len() != texts.len() {
+            return Err(ApplicationError::Internal(format!(
+                "Azure OpenAI returned {} embeddings for {} inputs",
+                embeddings.len(),
+                texts.len()
+            )));
+        }
+        embeddings.sort_by_key(|data| data.index);
+        Ok(embeddings.into_iter().map(|data| data.embedding).collect())
+    }
+}
+
+#[async_trait]
+impl EmbeddingProviderPort for AzureOpenAiEmbeddings {
+    async fn embed(&self, texts: &[String]) -> ApplicationResult<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            let outcome = self.embed_batch(batch).await;
+            metrics().record_embedded_chunks(batch.len(), outcome.is_ok());
+            embeddings.extend(outcome?);
+        }
+        debug!("Embedded {} texts with Azure OpenAI", texts.len());
+        Ok(embeddings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_embed_in_batches() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/openai/deployments/embed/embeddings"))
+            .and(query_param("api-version", "2024-02-01"))
+            .and(header("api-key", "openai-key"))
+            .respond_with(|request: &wiremock::Request| {
+                let body: Value = serde_json::from_slice(&request.body).unwrap();
+                // Out of order, as the API does not promise to keep it
+                let data: Vec<Value> = body["input"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .map(|(index, text)| json!({ "index": index, "embedding": [text.as_str().unwrap().len() as f32, 1.0] }))
+                    .collect();
+                ResponseTemplate::new(200).set_body_json(json!({ "data": data }))
+            })
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        let embeddings = AzureOpenAiEmbeddings::new(
+            &server.uri(),
+            "embed",
+            "2024-02-01",
+            AzureOpenAiAuth::Key("openai-key".to_string()),
+            2,
+        );
+        let texts = vec!["a".to_string(), "bb".to_string(), "ccc".to_string()];
+        let vectors = embeddings.embed(&texts).await.unwrap();
+        assert_eq!(vectors, [vec![1.0, 1.0], vec![2.0, 1.0], vec![3.0, 1.0]]);
+    }
+
+    #[tokio::test]
+    async fn test_embeddings_error() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).set_body_string("rate limited"))
+            .mount(&server)
+            .await;
+
+        let embeddings =
+            AzureOpenAiEmbeddings::new(&server.uri(), "embed", "2024-02-01", AzureOpenAiAuth::Key("k".to_string()), 16);
+        let error = embeddings.embed(&["a".to_string()]).await.unwrap_err().to_string();
+        assert!(error.contains("429"));
+    }
+}
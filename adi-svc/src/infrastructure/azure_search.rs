@@ -0,0 +1,196 @@
+/// Azure AI Search indexing adapter
+///
+/// Pushes each succeeded operation into an existing Azure AI Search index
+/// through the documents REST API, shaped by the configured index mapping.
+/// Requests carry the admin key when one is configured and a managed
+/// identity token otherwise.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use tracing::{debug, info};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::SearchIndexPort;
+use crate::domain::{IndexMapping, SearchDocument};
+use crate::infrastructure::azure_events::ManagedIdentityCredential;
+use crate::infrastructure::config::SearchIndexConfig;
+use crate::infrastructure::metrics::metrics;
+
+/// Documents API version
+const API_VERSION: &str = "2023-11-01";
+
+/// Token audience for Azure AI Search
+const SEARCH_RESOURCE: &str = "https://search.azure.com";
+
+/// How Azure AI Search requests are authorized
+pub enum AzureSearchAuth {
+    /// Admin key (`api-key`)
+    Key(String),
+    ManagedIdentity(Arc<ManagedIdentityCredential>),
+}
+
+/// Per-document outcome in an indexing response
+#[derive(Debug, Deserialize)]
+struct IndexingResult {
+    key: String,
+    status: bool,
+    #[serde(rename = "errorMessage")]
+    error_message: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexingResponse {
+    value: Vec<IndexingResult>,
+}
+
+/// Merges or uploads documents into an Azure AI Search index
+pub struct AzureSearchIndexer {
+    client: Client,
+    url: String,
+    auth: AzureSearchAuth,
+    mapping: IndexMapping,
+}
+
+impl AzureSearchIndexer {
+    /// `endpoint` is the search service URL, e.g. `https://acme.search.windows.net`
+    pub fn new(endpoint: &str, index: &str, auth: AzureSearchAuth, mapping: IndexMapping) -> Self {
+        Self {
+            client: Client::new(),
+            url: format!(
+                "{}/indexes/{}/docs/index?api-version={}",
+                endpoint.trim().trim_end_matches('/'),
+                index,
+                API_VERSION
+            ),
+            auth,
+            mapping,
+        }
+    }
+
+    /// Indexer for the configured service, or `None` when none is configured
+    ///
+    /// Without an admin key, requests are authorized with `credential`.
+    pub fn from_config(
+        config: &SearchIndexConfig,
+        credential: Arc<ManagedIdentityCredential>,
+    ) -> ApplicationResult<Option<Self>> {
+        let Some(endpoint) = &config.azure_search_endpoint else {
+            return Ok(None);
+        };
+        let mapping = config.index_mapping()?;
+        let auth = match &config.azure_search_key {
+            Some(key) => AzureSearchAuth::Key(key.expose().to_string()),
+            None => AzureSearchAuth::ManagedIdentity(credential),
+        };
+        info!("Indexing succeeded operations into Azure AI Search index {}", config.azure_search_index);
+        Ok(Some(Self::new(endpoint, &config.azure_search_index, auth, mapping)))
+    }
+}
+
+#[async_trait]
+impl SearchIndexPort for AzureSearchIndexer {
+    async fn index(&self, document: &SearchDocument) -> ApplicationResult<()> {
+        let mut indexed = self.mapping.apply(document);
+        indexed.insert("@search.action".to_string(), Value::String("mergeOrUpload".to_string()));
+        let body = json!({ "value": [indexed] });
+
+        let request = self.client.post(&self.url).json(&body);
+        let request = match &self.auth {
+            AzureSearchAuth::Key(key) => request.header("api-key", key),
+            AzureSearchAuth::ManagedIdentity(credential) => {
+                request.bearer_auth(credential.token(SEARCH_RESOURCE).await?)
+            }
+        };
+        let outcome = match request.send().await {
+            // 207 means some documents of the batch failed; each carries its own status
+            Ok(response) if response.status().is_success() => match response.json::<IndexingResponse>().await {
+                Ok(indexing) => match indexing.value.into_iter().find(|result| !result.status) {
+                    Some(failed) => Err(format!(
+                        "Azure AI Search rejected document {}: {}",
+                        failed.key,
+                        failed.error_message.unwrap_or_default()
+                    )),
+                    None => Ok(()),
+                },
+                Err(e) => Err(format!("Invalid Azure AI Search indexing response: {}", e)),
+            },
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                Err(format!("Azure AI Search returned {}: {}", status, body))
+            }
+            Err(e) => Err(format!("Failed to send document to Azure AI Search: {}", e)),
+        };
+        metrics().record_search_indexed("azure_search", outcome.is_ok());
+        outcome.map_err(ApplicationError::Internal)?;
+        debug!("Operation {} indexed in Azure AI Search", document.operation_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AnalysisOperation, AnalysisResult, ModelType, OperationStatus};
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn document() -> SearchDocument {
+        let mut operation = AnalysisOperation::new(ModelType::Read);
+        operation.update_status(OperationStatus::Succeeded);
+        let result = AnalysisResult {
+            content: "Hello".to_string(),
+            ..Default::default()
+        };
+        SearchDocument::new(&operation, &result)
+    }
+
+    #[tokio::test]
+    async fn test_index_document() {
+        let server = MockServer::start().await;
+        let document = document();
+        Mock::given(method("POST"))
+            .and(path("/indexes/docs/docs/index"))
+            .and(query_param("api-version", API_VERSION))
+            .and(header("api-key", "admin-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "value": [{ "key": document.operation_id, "status": true, "errorMessage": null, "statusCode": 201 }]
+            })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mapping = IndexMapping::parse("key=operation_id,body=content").unwrap();
+        let indexer = AzureSearchIndexer::new(&server.uri(), "docs", AzureSearchAuth::Key("admin-key".to_string()), mapping);
+        indexer.index(&document).await.unwrap();
+
+        let body: Value = serde_json::from_slice(&server.received_requests().await.unwrap()[0].body).unwrap();
+        assert_eq!(body["value"][0]["@search.action"], "mergeOrUpload");
+        assert_eq!(body["value"][0]["key"], document.operation_id);
+        assert_eq!(body["value"][0]["body"], "Hello");
+    }
+
+    #[tokio::test]
+    async fn test_rejected_document() {
+        let server = MockServer::start().await;
+        let document = document();
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(207).set_body_json(json!({
+                "value": [{ "key": document.operation_id, "status": false, "errorMessage": "field 'body' is not in the index", "statusCode": 400 }]
+            })))
+            .mount(&server)
+            .await;
+
+        let indexer = AzureSearchIndexer::new(
+            &server.uri(),
+            "docs",
+            AzureSearchAuth::Key("admin-key".to_string()),
+            IndexMapping::default(),
+        );
+        let error = indexer.index(&document).await.unwrap_err().to_string();
+        assert!(error.contains("field 'body' is not in the index"));
+    }
+}
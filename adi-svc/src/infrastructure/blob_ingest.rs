@@ -0,0 +1,398 @@
+/// Azure Blob Storage ingestion
+///
+/// Lists a container (optionally under a prefix) on a schedule and submits
+/// every blob it has not analyzed yet. Blob ETags are remembered in the
+/// ingest ledger, so a blob is analyzed again only when it is replaced, and
+/// each submitted blob is tagged with the operation it started. Requests use
+/// the Blob REST API with a SAS token or the host's managed identity.
+
+use bytes::Bytes;
+use reqwest::{Client, RequestBuilder};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+use url::Url;
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::IngestLedgerPort;
+use crate::application::services::DocumentIntelligenceService;
+use crate::domain::{AnalyzeDocumentRequest, AnalyzeOptions, DocumentMetadata, DocumentSource, ModelType, TenantId};
+use crate::infrastructure::azure_events::ManagedIdentityCredential;
+use crate::infrastructure::config::BlobIngestConfig;
+use crate::infrastructure::tasks::TaskSupervisor;
+
+/// Blob REST API version; blob index tags need 2019-12-12 or later
+const STORAGE_API_VERSION: &str = "2021-08-06";
+
+/// Token audience for Blob Storage
+const STORAGE_RESOURCE: &str = "https://storage.azure.com/";
+
+/// Index tag carrying the operation a blob started
+pub const OPERATION_TAG: &str = "adi_operation_id";
+
+/// How Blob Storage requests are authorized
+pub enum BlobAuth {
+    /// SAS token appended to every request
+    Sas(String),
+    ManagedIdentity(Arc<ManagedIdentityCredential>),
+}
+
+impl BlobAuth {
+    /// URL of `container_url`, or of the blob `name` in it, with `query` and any SAS token
+    pub(crate) fn url(&self, container_url: &Url, name: Option<&str>, query: &[(&str, &str)]) -> Url {
+        let mut url = container_url.clone();
+        if let Some(name) = name {
+            if let Ok(mut segments) = url.path_segments_mut() {
+                segments.extend(name.split('/'));
+            }
+        }
+        {
+            let mut pairs = url.query_pairs_mut();
+            for (key, value) in query {
+                pairs.append_pair(key, value);
+            }
+        }
+        if let Self::Sas(sas) = self {
+            let query = match url.query() {
+                Some(query) if !query.is_empty() => format!("{}&{}", query, sas),
+                _ => sas.clone(),
+            };
+            url.set_query(Some(&query));
+        }
+        if url.query() == Some("") {
+            url.set_query(None);
+        }
+        url
+    }
+
+    /// Authorize and send a request, failing on non-success statuses
+    pub(crate) async fn send(&self, request: RequestBuilder, action: &str) -> ApplicationResult<reqwest::Response> {
+        let request = request.header("x-ms-version", STORAGE_API_VERSION);
+        let request = match self {
+            Self::Sas(_) => request,
+            Self::ManagedIdentity(credential) => request.bearer_auth(credential.token(STORAGE_RESOURCE).await?),
+        };
+        let response = request
+            .send()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to {}: {}", action, e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApplicationError::Internal(format!(
+                "Blob Storage returned {} for {}: {}",
+                status, action, body
+            )));
+        }
+        Ok(response)
+    }
+}
+
+/// One blob from a container listing
+#[derive(Debug, Clone, PartialEq)]
+struct BlobItem {
+    name: String,
+    etag: String,
+    size: u64,
+    tags: Vec<(String, String)>,
+}
+
+/// One page of a container listing
+struct BlobPage {
+    blobs: Vec<BlobItem>,
+    next_marker: Option<String>,
+}
+
+/// Submits new blobs from one container
+pub struct BlobIngestor {
+    service: Arc<DocumentIntelligenceService>,
+    ledger: Arc<dyn IngestLedgerPort>,
+    client: Client,
+    container_url: Url,
+    prefix: String,
+    auth: BlobAuth,
+    model: ModelType,
+    tenant: TenantId,
+    interval: Duration,
+}
+
+impl BlobIngestor {
+    /// Ingestor for the configured container, or `None` when none is configured
+    ///
+    /// Without a SAS token, requests are authorized with `credential`.
+    pub fn from_config(
+        service: Arc<DocumentIntelligenceService>,
+        ledger: Arc<dyn IngestLedgerPort>,
+        config: &BlobIngestConfig,
+        credential: Arc<ManagedIdentityCredential>,
+    ) -> ApplicationResult<Option<Self>> {
+        let Some(container_url) = &config.container_url else {
+            return Ok(None);
+        };
+        let container_url = Url::parse(container_url.trim_end_matches('/')).map_err(|e| {
+            ApplicationError::Configuration(format!("Invalid BLOB_INGEST_CONTAINER_URL: {}", e))
+        })?;
+        let auth = match &config.sas_token {
+            Some(sas) => BlobAuth::Sas(sas.expose().trim_start_matches('?').to_string()),
+            None => BlobAuth::ManagedIdentity(credential),
+        };
+        Ok(Some(Self {
+            service,
+            ledger,
+            client: Client::new(),
+            container_url,
+            prefix: config.prefix.clone(),
+            auth,
+            model: config.model,
+            tenant: config.tenant.clone(),
+            interval: Duration::from_secs(config.interval_secs),
+        }))
+    }
+
+    /// Ledger key for this container and prefix
+    fn source(&self) -> String {
+        format!("{}/{}", self.container_url, self.prefix)
+    }
+
+    /// Submit every blob not yet analyzed; returns how many were submitted
+    ///
+    /// A blob that cannot be submitted is skipped and tried again next time,
+    /// unless the service rejected it outright.
+    pub async fn poll(&self) -> ApplicationResult<usize> {
+        let mut submitted = 0;
+        let mut marker = None;
+        loop {
+            let page = self.list(marker.as_deref()).await?;
+            for blob in &page.blobs {
+                match self.ingest(blob).await {
+                    Ok(true) => submitted += 1,
+                    Ok(false) => {}
+                    Err(e) => warn!("Failed to ingest blob {}: {}", blob.name, e),
+                }
+            }
+            match page.next_marker {
+                Some(next) => marker = Some(next),
+                None => break,
+            }
+        }
+        if submitted > 0 {
+            info!("Submitted {} blobs from {}", submitted, self.source());
+        }
+        Ok(submitted)
+    }
+
+    /// Submit `blob` unless this version was already ingested
+    async fn ingest(&self, blob: &BlobItem) -> ApplicationResult<bool> {
+        let source = self.source();
+        if blob.size == 0 || self.ledger.ingested_version(&source, &blob.name).await?.as_deref() == Some(&blob.etag) {
+            return Ok(false);
+        }
+
+        let request = AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(self.download(&blob.name).await?),
+            model_type: self.model,
+            options: AnalyzeOptions::default(),
+            metadata: Some(DocumentMetadata::new(&blob.name, "")),
+            tenant_id: self.tenant.clone(),
+        };
+        let operation = match self.service.analyze_document(request).await {
+            Ok(operation) => operation,
+            Err(e) if e.is_transient() => return Err(e),
+            Err(e) => {
+                // Retrying won't help until the blob is replaced
+                warn!("Blob {} was rejected: {}", blob.name, e);
+                self.ledger.record_ingested(&source, &blob.name, &blob.etag, None).await?;
+                return Ok(false);
+            }
+        };
+
+        // Recorded first: a failed tag must not get the blob analyzed twice
+        self.ledger
+            .record_ingested(&source, &blob.name, &blob.etag, Some(&operation.operation_id))
+            .await?;
+        if let Err(e) = self.tag(blob, &operation.operation_id).await {
+            error!("Failed to tag blob {}: {}", blob.name, e);
+        }
+        info!("Blob {} submitted as operation {}", blob.name, operation.operation_id);
+        Ok(true)
+    }
+
+    async fn list(&self, marker: Option<&str>) -> ApplicationResult<BlobPage> {
+        let mut query = vec![("restype", "container"), ("comp", "list"), ("include", "tags")];
+        if !self.prefix.is_empty() {
+            query.push(("prefix", &self.prefix));
+        }
+        if let Some(marker) = marker {
+            query.push(("marker", marker));
+        }
+        let request = self.client.get(self.auth.url(&self.container_url, None, &query));
+        let body = self.auth.send(request, "list blobs").await?.text().await.map_err(|e| {
+            ApplicationError::Internal(format!("Failed to read blob listing: {}", e))
+        })?;
+        Ok(parse_listing(&body))
+    }
+
+    async fn download(&self, name: &str) -> ApplicationResult<Bytes> {
+        let request = self.client.get(self.auth.url(&self.container_url, Some(name), &[]));
+        self.auth
+            .send(request, "download blob")
+            .await?
+            .bytes()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to download blob {}: {}", name, e)))
+    }
+
+    /// Set the operation tag, keeping the blob's other tags
+    async fn tag(&self, blob: &BlobItem, operation_id: &str) -> ApplicationResult<()> {
+        let mut tags: Vec<(&str, &str)> = blob
+            .tags
+            .iter()
+            .filter(|(key, _)| key != OPERATION_TAG)
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+            .collect();
+        tags.push((OPERATION_TAG, operation_id));
+        let tag_set: String = tags
+            .iter()
+            .map(|(key, value)| format!("<Tag><Key>{}</Key><Value>{}</Value></Tag>", escape(key), escape(value)))
+            .collect();
+        let body = format!(
+            "<?xml version=\"1.0\" encoding=\"utf-8\"?><Tags><TagSet>{}</TagSet></Tags>",
+            tag_set
+        );
+
+        let request = self
+            .client
+            .put(self.auth.url(&self.container_url, Some(&blob.name), &[("comp", "tags")]))
+            .header("Content-Type", "application/xml")
+            .body(body);
+        self.auth.send(request, "tag blob").await?;
+        Ok(())
+    }
+}
+
+/// Poll `ingestor` until shutdown
+pub fn spawn_blob_ingest(supervisor: &TaskSupervisor, ingestor: BlobIngestor) {
+    info!(
+        "Ingesting blobs from {} every {:?} with {}",
+        ingestor.source(),
+        ingestor.interval,
+        ingestor.model.as_str()
+    );
+    let ingestor = Arc::new(ingestor);
+    supervisor.spawn("blob-ingest", move |shutdown| {
+        let ingestor = ingestor.clone();
+        async move {
+            loop {
+                if let Err(e) = ingestor.poll().await {
+                    error!("Blob ingestion failed: {}", e);
+                }
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = tokio::time::sleep(ingestor.interval) => {}
+                }
+            }
+        }
+    });
+}
+
+/// Blobs and continuation marker from a List Blobs response
+fn parse_listing(xml: &str) -> BlobPage {
+    let blobs = elements(xml, "Blob")
+        .into_iter()
+        .filter_map(|blob| {
+            let tags = elements(blob, "Tag")
+                .into_iter()
+                .filter_map(|tag| Some((element(tag, "Key")?, element(tag, "Value").unwrap_or_default())))
+                .collect();
+            Some(BlobItem {
+                name: element(blob, "Name")?,
+                etag: element(blob, "Etag")?,
+                size: element(blob, "Content-Length").and_then(|size| size.parse().ok()).unwrap_or_default(),
+                tags,
+            })
+        })
+        .collect();
+    BlobPage {
+        blobs,
+        next_marker: element(xml, "NextMarker").filter(|marker| !marker.is_empty()),
+    }
+}
+
+/// Contents of each `<tag>` element in `xml`, outermost first
+fn elements<'a>(xml: &'a str, tag: &str) -> Vec<&'a str> {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let mut found = Vec::new();
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let inner = &rest[start + open.len()..];
+        let Some(end) = inner.find(&close) else {
+            break;
+        };
+        found.push(&inner[..end]);
+        rest = &inner[end + close.len()..];
+    }
+    found
+}
+
+/// Unescaped text of the first `<tag>` element in `xml`
+fn element(xml: &str, tag: &str) -> Option<String> {
+    elements(xml, tag).first().map(|text| unescape(text))
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_listing() {
+        let xml = r#"<?xml version="1.0" encoding="utf-8"?>
+            <EnumerationResults ServiceEndpoint="https://acct.blob.core.windows.net/" ContainerName="scans">
+              <Prefix>inbox/</Prefix>
+              <Blobs>
+                <Blob>
+                  <Name>inbox/a &amp; b.pdf</Name>
+                  <Properties><Etag>0x8DC1</Etag><Content-Length>1024</Content-Length></Properties>
+                  <Tags><TagSet><Tag><Key>batch</Key><Value>7</Value></Tag></TagSet></Tags>
+                </Blob>
+                <Blob>
+                  <Name>inbox/empty/</Name>
+                  <Properties><Etag>0x8DC2</Etag><Content-Length>0</Content-Length></Properties>
+                </Blob>
+              </Blobs>
+              <NextMarker>page-2</NextMarker>
+            </EnumerationResults>"#;
+        let page = parse_listing(xml);
+        assert_eq!(page.next_marker.as_deref(), Some("page-2"));
+        assert_eq!(
+            page.blobs[0],
+            BlobItem {
+                name: "inbox/a & b.pdf".to_string(),
+                etag: "0x8DC1".to_string(),
+                size: 1024,
+                tags: vec![("batch".to_string(), "7".to_string())],
+            }
+        );
+        assert_eq!((page.blobs[1].size, page.blobs[1].tags.len()), (0, 0));
+
+        let last = parse_listing("<EnumerationResults><Blobs /><NextMarker /></EnumerationResults>");
+        assert!(last.blobs.is_empty());
+        assert!(last.next_marker.is_none());
+        assert_eq!(escape("a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+    }
+}
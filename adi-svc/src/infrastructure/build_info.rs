@@ -0,0 +1,45 @@
+/// Build information
+///
+/// What `build.rs` recorded about the running binary: its git commit, when it
+/// was built and the cargo features it was built with.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// Identity of the running build
+#[derive(Debug, Clone, Serialize)]
+pub struct BuildInfo {
+    pub version: &'static str,
+    /// Commit built from, `unknown` outside a git checkout without `GIT_SHA`
+    pub git_sha: &'static str,
+    pub build_timestamp: Option<DateTime<Utc>>,
+    pub features: Vec<&'static str>,
+}
+
+impl BuildInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION"),
+            git_sha: env!("ADI_GIT_SHA"),
+            build_timestamp: env!("ADI_BUILD_EPOCH")
+                .parse()
+                .ok()
+                .and_then(|epoch| DateTime::from_timestamp(epoch, 0)),
+            features: env!("ADI_FEATURES").split(',').filter(|feature| !feature.is_empty()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_info() {
+        let info = BuildInfo::current();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert!(!info.git_sha.is_empty());
+        assert!(info.build_timestamp.is_some());
+        assert_eq!(info.features.contains(&"server"), cfg!(feature = "server"));
+    }
+}
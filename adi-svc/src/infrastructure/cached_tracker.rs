@@ -0,0 +1,328 @@
+/// Caching operation tracker
+///
+/// Decorates another tracker, typically PostgreSQL, with a bounded in-memory
+/// cache of operations and full results. Clients polling an operation every
+/// second are then served without a database round trip or deserializing a
+/// multi-megabyte result. Writes made through the cache update it; writes by
+/// other replicas are seen once the cached copy's TTL lapses.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use lru::LruCache;
+use std::num::NonZeroUsize;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::application::errors::ApplicationResult;
+use crate::application::ports::{OperationTrackerPort, PrunedRows};
+use crate::domain::{
+    AnalysisOperation, AnalysisResult, DocumentPage, FieldMatch, FieldQuery, OperationEvent, OperationListQuery, OperationStats, OperationStatsQuery,
+    ResultFields, TenantId,
+};
+use crate::infrastructure::metrics::metrics;
+
+/// A cached value and when it stops being served
+struct Entry<T> {
+    value: T,
+    expires_at: Instant,
+}
+
+/// LRU cache whose entries also expire after a fixed TTL
+struct TtlCache<T> {
+    /// Metric label
+    name: &'static str,
+    ttl: Duration,
+    entries: Mutex<LruCache<String, Entry<T>>>,
+}
+
+impl<T: Clone> TtlCache<T> {
+    fn new(name: &'static str, capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Self {
+            name,
+            ttl,
+            entries: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<T> {
+        let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+        let value = match entries.get(key) {
+            Some(entry) if entry.expires_at > Instant::now() => Some(entry.value.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        };
+        metrics().record_tracker_cache(self.name, value.is_some());
+        value
+    }
+
+    fn put(&self, key: &str, value: T) {
+        let entry = Entry {
+            value,
+            expires_at: Instant::now() + self.ttl,
+        };
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).put(key.to_string(), entry);
+    }
+
+    /// Change a cached value in place and serve it for another TTL, if there is one
+    fn modify(&self, key: &str, change: impl FnOnce(&mut T)) {
+        if let Some(entry) = self.entries.lock().unwrap_or_else(|e| e.into_inner()).peek_mut(key) {
+            change(&mut entry.value);
+            entry.expires_at = Instant::now() + self.ttl;
+        }
+    }
+
+    fn clear(&self) {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// Operation tracker serving hot operations and results from memory
+pub struct CachedOperationTracker {
+    inner: Arc<dyn OperationTrackerPort>,
+    operations: TtlCache<AnalysisOperation>,
+    results: TtlCache<AnalysisResult>,
+}
+
+impl CachedOperationTracker {
+    /// Cache up to `capacity` operations and as many results in front of `inner`, each for `ttl`
+    pub fn new(inner: Arc<dyn OperationTrackerPort>, capacity: NonZeroUsize, ttl: Duration) -> Self {
+        Self {
+            inner,
+            operations: TtlCache::new("operation", capacity, ttl),
+            results: TtlCache::new("result", capacity, ttl),
+        }
+    }
+
+    /// The full result, from the cache or loaded into it
+    async fn result(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisResult>> {
+        if let Some(result) = self.results.get(operation_id) {
+            return Ok(Some(result));
+        }
+        let result = self.inner.get_result(operation_id).await?;
+        if let Some(ref result) = result {
+            self.results.put(operation_id, result.clone());
+        }
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl OperationTrackerPort for CachedOperationTracker {
+    async fn store_operation(&self, operation: &AnalysisOperation) -> ApplicationResult<()> {
+        self.inner.store_operation(operation).await?;
+        self.operations.put(&operation.operation_id, operation.clone());
+        Ok(())
+    }
+
+    async fn get_operation(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisOperation>> {
+        if let Some(operation) = self.operations.get(operation_id) {
+            return Ok(Some(operation));
+        }
+        let operation = self.inner.get_operation(operation_id).await?;
+        if let Some(ref operation) = operation {
+            self.operations.put(operation_id, operation.clone());
+        }
+        Ok(operation)
+    }
+
+    async fn update_operation(&self, operation: &AnalysisOperation) -> ApplicationResult<()> {
+        self.inner.update_operation(operation).await?;
+        // The caller's copy may predate a deletion, which the update leaves alone
+        self.operations.modify(&operation.operation_id, |cached| {
+            *cached = AnalysisOperation { deleted_at: cached.deleted_at, ..operation.clone() };
+        });
+        Ok(())
+    }
+
+    async fn set_deleted(&self, operation_id: &str, deleted_at: Option<DateTime<Utc>>) -> ApplicationResult<()> {
+        self.inner.set_deleted(operation_id, deleted_at).await?;
+        self.operations.modify(operation_id, |cached| cached.deleted_at = deleted_at);
+        Ok(())
+    }
+
+    async fn record_event(&self, event: &OperationEvent) -> ApplicationResult<()> {
+        self.inner.record_event(event).await
+    }
+
+    async fn list_events(&self, operation_id: &str) -> ApplicationResult<Vec<OperationEvent>> {
+        self.inner.list_events(operation_id).await
+    }
+
+    async fn store_result(&self, operation_id: &str, result: &AnalysisResult) -> ApplicationResult<()> {
+        self.inner.store_result(operation_id, result).await?;
+        self.results.put(operation_id, result.clone());
+        Ok(())
+    }
+
+    async fn get_result(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisResult>> {
+        self.result(operation_id).await
+    }
+
+    async fn store_raw_response(&self, operation_id: &str, raw: &serde_json::Value) -> ApplicationResult<()> {
+        self.inner.store_raw_response(operation_id, raw).await
+    }
+
+    async fn get_raw_response(&self, operation_id: &str) -> ApplicationResult<Option<serde_json::Value>> {
+        self.inner.get_raw_response(operation_id).await
+    }
+
+    /// Whole results are cached; partial reads are served from a cached
+    /// result when there is one and otherwise left to the inner tracker's
+    /// selective loading
+    async fn get_result_fields(
+        &self,
+        operation_id: &str,
+        fields: &ResultFields,
+    ) -> ApplicationResult<Option<AnalysisResult>> {
+        if *fields == ResultFields::all() {
+            return self.result(operation_id).await;
+        }
+        match self.results.get(operation_id) {
+            Some(result) => Ok(Some(result.project(fields))),
+            None => self.inner.get_result_fields(operation_id, fields).await,
+        }
+    }
+
+    async fn get_result_page(&self, operation_id: &str, page_number: i32) -> ApplicationResult<Option<DocumentPage>> {
+        match self.results.get(operation_id) {
+            Some(result) => Ok(result.pages.into_iter().find(|page| page.page_number == page_number)),
+            None => self.inner.get_result_page(operation_id, page_number).await,
+        }
+    }
+
+    async fn query_result_fields(
+        &self,
+        operation_id: &str,
+        query: &FieldQuery,
+    ) -> ApplicationResult<Option<Vec<FieldMatch>>> {
+        match self.results.get(operation_id) {
+            Some(result) => Ok(Some(result.query_fields(query))),
+            None => self.inner.query_result_fields(operation_id, query).await,
+        }
+    }
+
+    async fn list_operations(
+        &self,
+        tenant: &TenantId,
+        query: &OperationListQuery,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        self.inner.list_operations(tenant, query).await
+    }
+
+    async fn list_operations_created(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        before: Option<DateTime<Utc>>,
+        before_id: Option<&str>,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        self.inner.list_operations_created(from, to, before, before_id, limit).await
+    }
+
+    async fn operation_stats(
+        &self,
+        tenant: &TenantId,
+        query: &OperationStatsQuery,
+    ) -> ApplicationResult<Vec<OperationStats>> {
+        self.inner.operation_stats(tenant, query).await
+    }
+
+    async fn find_operations_by_sha256(
+        &self,
+        tenant: &TenantId,
+        sha256: &str,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        self.inner.find_operations_by_sha256(tenant, sha256, limit).await
+    }
+
+    async fn find_operation_by_document(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        include_deleted: bool,
+    ) -> ApplicationResult<Option<AnalysisOperation>> {
+        self.inner.find_operation_by_document(tenant, document_id, include_deleted).await
+    }
+
+    async fn prune_operations(&self, cutoff: DateTime<Utc>) -> ApplicationResult<PrunedRows> {
+        let pruned = self.inner.prune_operations(cutoff).await?;
+        self.operations.clear();
+        self.results.clear();
+        Ok(pruned)
+    }
+
+    async fn purge_deleted_operations(&self, cutoff: DateTime<Utc>) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let purged = self.inner.purge_deleted_operations(cutoff).await?;
+        self.operations.clear();
+        self.results.clear();
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ModelType, OperationStatus};
+    use crate::infrastructure::tracker::InMemoryOperationTracker;
+
+    fn tracker(capacity: usize, ttl: Duration) -> (Arc<InMemoryOperationTracker>, CachedOperationTracker) {
+        let inner = Arc::new(InMemoryOperationTracker::new());
+        let cached = CachedOperationTracker::new(inner.clone(), NonZeroUsize::new(capacity).unwrap(), ttl);
+        (inner, cached)
+    }
+
+    #[tokio::test]
+    async fn test_cached_operations_and_results() {
+        let (inner, cached) = tracker(10, Duration::from_secs(60));
+        let mut operation = AnalysisOperation::new(ModelType::Read);
+        let id = operation.operation_id.clone();
+        cached.store_operation(&operation).await.unwrap();
+
+        // Served from the cache, unaware of writes that bypass it
+        operation.update_status(OperationStatus::Succeeded);
+        inner.update_operation(&operation).await.unwrap();
+        assert_eq!(cached.get_operation(&id).await.unwrap().unwrap().status, OperationStatus::NotStarted);
+
+        cached.update_operation(&operation).await.unwrap();
+        assert_eq!(cached.get_operation(&id).await.unwrap().unwrap().status, OperationStatus::Succeeded);
+
+        let result = AnalysisResult {
+            content: "cached".to_string(),
+            ..Default::default()
+        };
+        inner.store_result(&id, &result).await.unwrap();
+        assert_eq!(cached.get_result(&id).await.unwrap().unwrap().content, "cached");
+        inner.store_result(&id, &AnalysisResult::default()).await.unwrap();
+        let fields = ResultFields { content: true, ..ResultFields::none() };
+        assert_eq!(cached.get_result_fields(&id, &fields).await.unwrap().unwrap().content, "cached");
+
+        // Pruning may remove anything, so the cache starts over
+        cached.prune_operations(Utc::now() - chrono::Duration::days(1)).await.unwrap();
+        assert_eq!(cached.get_result(&id).await.unwrap().unwrap().content, "");
+    }
+
+    #[tokio::test]
+    async fn test_cache_expiry_and_eviction() {
+        let (inner, cached) = tracker(10, Duration::ZERO);
+        let mut operation = AnalysisOperation::new(ModelType::Read);
+        cached.store_operation(&operation).await.unwrap();
+        operation.update_status(OperationStatus::Running);
+        inner.update_operation(&operation).await.unwrap();
+        let fresh = cached.get_operation(&operation.operation_id).await.unwrap().unwrap();
+        assert_eq!(fresh.status, OperationStatus::Running);
+
+        let (inner, cached) = tracker(1, Duration::from_secs(60));
+        let mut first = AnalysisOperation::new(ModelType::Read);
+        cached.store_operation(&first).await.unwrap();
+        cached.store_operation(&AnalysisOperation::new(ModelType::Layout)).await.unwrap();
+        first.update_status(OperationStatus::Running);
+        inner.update_operation(&first).await.unwrap();
+        let reloaded = cached.get_operation(&first.operation_id).await.unwrap().unwrap();
+        assert_eq!(reloaded.status, OperationStatus::Running);
+    }
+}
@@ -0,0 +1,140 @@
+/// ClamAV malware scanner adapter
+///
+/// Streams documents to clamd over TCP with the `INSTREAM` command and maps
+/// its reply onto a `ScanVerdict`.
+
+use async_trait::async_trait;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tracing::{debug, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::MalwareScanPort;
+use crate::domain::ScanVerdict;
+use crate::infrastructure::config::MalwareScanConfig;
+
+/// Size of each length-prefixed `INSTREAM` chunk
+const INSTREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+/// clamd client
+pub struct ClamAvScanner {
+    address: String,
+    timeout: Duration,
+}
+
+impl ClamAvScanner {
+    pub fn new(address: impl Into<String>, timeout: Duration) -> Self {
+        Self {
+            address: address.into(),
+            timeout,
+        }
+    }
+
+    /// Scanner for the configured clamd, if scanning is enabled
+    pub fn from_config(config: &MalwareScanConfig) -> Option<Self> {
+        config
+            .clamd_address
+            .as_ref()
+            .map(|address| Self::new(address.clone(), Duration::from_secs(config.timeout_secs)))
+    }
+
+    async fn instream(&self, data: &[u8]) -> std::io::Result<String> {
+        let mut stream = TcpStream::connect(&self.address).await?;
+        stream.write_all(b"zINSTREAM\0").await?;
+        for chunk in data.chunks(INSTREAM_CHUNK_BYTES) {
+            stream.write_all(&(chunk.len() as u32).to_be_bytes()).await?;
+            stream.write_all(chunk).await?;
+        }
+        stream.write_all(&0u32.to_be_bytes()).await?;
+
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await?;
+        Ok(String::from_utf8_lossy(&reply).trim_end_matches('\0').trim().to_string())
+    }
+}
+
+/// Interpret a clamd reply such as `stream: OK` or `stream: Eicar-Signature FOUND`
+fn parse_reply(reply: &str) -> ApplicationResult<ScanVerdict> {
+    let body = reply.strip_prefix("stream:").unwrap_or(reply).trim();
+    if body == "OK" {
+        Ok(ScanVerdict::Clean)
+    } else if let Some(signature) = body.strip_suffix(" FOUND") {
+        Ok(ScanVerdict::Infected {
+            signature: signature.trim().to_string(),
+        })
+    } else {
+        Err(ApplicationError::Internal(format!("clamd scan failed: {}", reply)))
+    }
+}
+
+#[async_trait]
+impl MalwareScanPort for ClamAvScanner {
+    async fn scan(&self, data: &[u8]) -> ApplicationResult<ScanVerdict> {
+        debug!("Scanning {} bytes with clamd at {}", data.len(), self.address);
+
+        let reply = tokio::time::timeout(self.timeout, self.instream(data))
+            .await
+            .map_err(|_| ApplicationError::Internal("clamd scan timed out".to_string()))?
+            .map_err(|e| ApplicationError::Internal(format!("Failed to reach clamd: {}", e)))?;
+
+        let verdict = parse_reply(&reply)?;
+        if let ScanVerdict::Infected { signature } = &verdict {
+            warn!("clamd flagged upload: {}", signature);
+        }
+        Ok(verdict)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_reply("stream: OK").unwrap(), ScanVerdict::Clean);
+        assert_eq!(
+            parse_reply("stream: Eicar-Test-Signature FOUND").unwrap(),
+            ScanVerdict::Infected {
+                signature: "Eicar-Test-Signature".to_string()
+            }
+        );
+        assert!(parse_reply("INSTREAM size limit exceeded. ERROR").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_scan_streams_chunks() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        // Minimal clamd: read the command and chunks, flag anything containing "EICAR"
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut command = [0u8; 10];
+            socket.read_exact(&mut command).await.unwrap();
+            assert_eq!(&command, b"zINSTREAM\0");
+
+            let mut received = Vec::new();
+            loop {
+                let len = socket.read_u32().await.unwrap() as usize;
+                if len == 0 {
+                    break;
+                }
+                let mut chunk = vec![0u8; len];
+                socket.read_exact(&mut chunk).await.unwrap();
+                received.extend(chunk);
+            }
+            let reply: &[u8] = if received.windows(5).any(|w| w == b"EICAR") {
+                b"stream: Eicar-Test-Signature FOUND\0"
+            } else {
+                b"stream: OK\0"
+            };
+            socket.write_all(reply).await.unwrap();
+        });
+
+        let scanner = ClamAvScanner::new(address, Duration::from_secs(5));
+        let verdict = scanner.scan(b"X5O!P%@AP EICAR test").await.unwrap();
+        assert!(matches!(verdict, ScanVerdict::Infected { .. }));
+    }
+}
@@ -0,0 +1,171 @@
+/// Document classifiers for `analyze/auto`
+///
+/// `KeywordClassifier` needs no Azure resources: it looks for telling words
+/// in the filename and, for PDFs with a text layer, the first few pages
+/// (with the `pdf` feature).
+/// `AzureDocumentClassifier` runs a custom classification model trained in
+/// Document Intelligence Studio.
+
+use async_trait::async_trait;
+#[cfg(feature = "pdf")]
+use lopdf::Document;
+use std::sync::Arc;
+use tracing::debug;
+
+#[cfg(feature = "pdf")]
+use crate::application::errors::ApplicationError;
+use crate::application::errors::ApplicationResult;
+use crate::application::ports::DocumentClassifierPort;
+#[cfg(feature = "pdf")]
+use crate::domain::DocumentFormat;
+use crate::domain::{classify_by_keywords, DocumentClassification, DocumentSource, RoutingMethod};
+use crate::infrastructure::azure::AzureDocumentIntelligenceAdapter;
+
+/// Pages of a PDF whose text is searched for keywords
+#[cfg(feature = "pdf")]
+const MAX_TEXT_PAGES: usize = 3;
+
+/// Classifier matching keywords in the filename and PDF text layer
+#[derive(Debug, Default)]
+pub struct KeywordClassifier;
+
+impl KeywordClassifier {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl DocumentClassifierPort for KeywordClassifier {
+    fn method(&self) -> RoutingMethod {
+        RoutingMethod::Heuristic
+    }
+
+    async fn classify(
+        &self,
+        source: &DocumentSource,
+        filename: Option<&str>,
+    ) -> ApplicationResult<Option<DocumentClassification>> {
+        let (filename, text) = match source {
+            #[cfg(feature = "pdf")]
+            DocumentSource::Bytes(bytes) if matches!(DocumentFormat::detect(bytes), Ok(DocumentFormat::Pdf)) => {
+                let bytes = bytes.clone();
+                // Parsing is CPU-bound
+                let text = tokio::task::spawn_blocking(move || pdf_text(&bytes))
+                    .await
+                    .map_err(|e| ApplicationError::Internal(format!("Classification task failed: {}", e)))?;
+                (filename, text)
+            }
+            DocumentSource::Bytes(_) => (filename, String::new()),
+            // The last path segment stands in for a filename
+            DocumentSource::Url(url) => {
+                let path = url.split(['?', '#']).next().unwrap_or(url);
+                (filename.or_else(|| path.rsplit('/').next()), String::new())
+            }
+        };
+        debug!("Classifying {:?} by keywords in {} chars of text", filename, text.len());
+        Ok(classify_by_keywords(filename, &text))
+    }
+}
+
+/// Text of a PDF's first pages; empty for scans, encrypted and unreadable files
+#[cfg(feature = "pdf")]
+fn pdf_text(data: &[u8]) -> String {
+    let doc = match Document::load_mem(data) {
+        Ok(doc) if !doc.is_encrypted() => doc,
+        _ => return String::new(),
+    };
+    let pages: Vec<u32> = doc.get_pages().into_keys().take(MAX_TEXT_PAGES).collect();
+    doc.extract_text(&pages).unwrap_or_default()
+}
+
+/// Classifier running a custom Azure classification model
+pub struct AzureDocumentClassifier {
+    adapter: Arc<AzureDocumentIntelligenceAdapter>,
+    classifier_id: String,
+}
+
+impl AzureDocumentClassifier {
+    pub fn new(adapter: Arc<AzureDocumentIntelligenceAdapter>, classifier_id: impl Into<String>) -> Self {
+        Self {
+            adapter,
+            classifier_id: classifier_id.into(),
+        }
+    }
+}
+
+#[async_trait]
+impl DocumentClassifierPort for AzureDocumentClassifier {
+    fn method(&self) -> RoutingMethod {
+        RoutingMethod::Classifier
+    }
+
+    async fn classify(
+        &self,
+        source: &DocumentSource,
+        _filename: Option<&str>,
+    ) -> ApplicationResult<Option<DocumentClassification>> {
+        self.adapter.classify_document(&self.classifier_id, source).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    #[cfg(feature = "pdf")]
+    use lopdf::content::{Content, Operation};
+    #[cfg(feature = "pdf")]
+    use lopdf::{dictionary, Object, Stream};
+
+    /// One-page PDF showing `text`
+    #[cfg(feature = "pdf")]
+    fn text_pdf(text: &str) -> Bytes {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! { "Type" => "Font", "Subtype" => "Type1", "BaseFont" => "Helvetica" });
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), Object::Integer(12)]),
+                Operation::new("Tj", vec![Object::string_literal(text)]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        let mut pdf = Vec::new();
+        doc.save_to(&mut pdf).unwrap();
+        pdf.into()
+    }
+
+    #[tokio::test]
+    async fn test_keyword_classifier() {
+        let classifier = KeywordClassifier::new();
+
+        #[cfg(feature = "pdf")]
+        {
+            let pdf = DocumentSource::Bytes(text_pdf("Wage and Tax Statement 2023"));
+            let classification = classifier.classify(&pdf, Some("scan.pdf")).await.unwrap().unwrap();
+            assert_eq!(classification.doc_type, "w2");
+        }
+
+        let url = DocumentSource::Url("https://example.com/mail/receipt.jpg?sig=abc".to_string());
+        let classification = classifier.classify(&url, None).await.unwrap().unwrap();
+        assert_eq!(classification.doc_type, "receipt");
+
+        let image = DocumentSource::Bytes(Bytes::from_static(b"\xff\xd8\xff\xe0 not much of a photo"));
+        assert_eq!(classifier.classify(&image, Some("IMG_0001.jpg")).await.unwrap(), None);
+    }
+}
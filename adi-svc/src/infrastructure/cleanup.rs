@@ -0,0 +1,37 @@
+/// Scheduled cleanup task
+///
+/// Periodically runs the retention service and records what it pruned.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::application::retention::RetentionService;
+use crate::infrastructure::metrics::metrics;
+use crate::infrastructure::tasks::TaskSupervisor;
+
+/// Spawn the retention loop, running one pass per interval until shutdown
+pub fn spawn_retention_task(
+    supervisor: &TaskSupervisor,
+    service: Arc<RetentionService>,
+    interval: Duration,
+) {
+    info!("Starting retention task (interval: {:?})", interval);
+
+    supervisor.spawn("retention", move |shutdown| {
+        let service = service.clone();
+        async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = ticker.tick() => {}
+                }
+                match service.prune_expired().await {
+                    Ok(report) => metrics().record_retention(&report),
+                    Err(e) => error!("Retention pass failed: {}", e),
+                }
+            }
+        }
+    });
+}
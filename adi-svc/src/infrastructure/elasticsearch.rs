@@ -0,0 +1,361 @@
+/// Elasticsearch / OpenSearch indexing adapter
+///
+/// Pushes each succeeded operation through the `_bulk` API, keyed by
+/// operation id, shaped by the same index mapping as Azure AI Search. Both
+/// engines answer `429` when their write queues are full, for a whole request
+/// or for single items; those are retried with exponential backoff. An index
+/// template can be installed at startup so a new index gets keyword, date and
+/// text types for the mapped fields instead of dynamic ones.
+
+use async_trait::async_trait;
+use reqwest::{Client, RequestBuilder, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::SearchIndexPort;
+use crate::domain::{IndexMapping, SearchDocument, SearchSource};
+use crate::infrastructure::config::SearchIndexConfig;
+use crate::infrastructure::metrics::metrics;
+
+/// Delay before the first retry of a throttled request; doubled for each further one
+const RETRY_BASE_DELAY: Duration = Duration::from_millis(500);
+
+/// How Elasticsearch requests are authenticated
+#[derive(Clone)]
+pub enum ElasticsearchAuth {
+    None,
+    Basic { username: String, password: String },
+    /// Encoded API key (`Authorization: ApiKey ...`)
+    ApiKey(String),
+}
+
+#[derive(Debug, Deserialize)]
+struct BulkResponse {
+    errors: bool,
+    items: Vec<Map<String, Value>>,
+}
+
+/// Outcome of one bulk item, under its action name
+#[derive(Debug, Deserialize)]
+struct BulkItem {
+    status: u16,
+    #[serde(default)]
+    error: Option<Value>,
+}
+
+/// Indexes documents into an Elasticsearch or OpenSearch index
+pub struct ElasticsearchIndexer {
+    client: Client,
+    base_url: String,
+    index: String,
+    auth: ElasticsearchAuth,
+    mapping: IndexMapping,
+    max_retries: u32,
+}
+
+impl ElasticsearchIndexer {
+    /// `base_url` is the cluster URL, e.g. `https://es.internal:9200`
+    pub fn new(
+        base_url: &str,
+        index: impl Into<String>,
+        auth: ElasticsearchAuth,
+        mapping: IndexMapping,
+        max_retries: u32,
+    ) -> Self {
+        Self {
+            client: Client::new(),
+            base_url: base_url.trim().trim_end_matches('/').to_string(),
+            index: index.into(),
+            auth,
+            mapping,
+            max_retries,
+        }
+    }
+
+    /// Indexer for the configured cluster, or `None` when none is configured
+    pub fn from_config(config: &SearchIndexConfig) -> ApplicationResult<Option<Self>> {
+        let Some(url) = &config.elasticsearch_url else {
+            return Ok(None);
+        };
+        let auth = match (&config.elasticsearch_api_key, &config.elasticsearch_username) {
+            (Some(api_key), _) => ElasticsearchAuth::ApiKey(api_key.expose().to_string()),
+            (None, Some(username)) => ElasticsearchAuth::Basic {
+                username: username.clone(),
+                password: config
+                    .elasticsearch_password
+                    .as_ref()
+                    .map(|password| password.expose().to_string())
+                    .unwrap_or_default(),
+            },
+            (None, None) => ElasticsearchAuth::None,
+        };
+        info!("Indexing succeeded operations into Elasticsearch index {}", config.elasticsearch_index);
+        Ok(Some(Self::new(
+            url,
+            config.elasticsearch_index.clone(),
+            auth,
+            config.index_mapping()?,
+            config.elasticsearch_max_retries,
+        )))
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.auth {
+            ElasticsearchAuth::None => request,
+            ElasticsearchAuth::Basic { username, password } => request.basic_auth(username, Some(password)),
+            ElasticsearchAuth::ApiKey(api_key) => request.header("Authorization", format!("ApiKey {}", api_key)),
+        }
+    }
+
+    /// Create or replace the index template `<index>-template` matching the index
+    ///
+    /// Mapped metadata becomes `keyword` (or `date` / `integer`), content and
+    /// field text become `text`; single fields are left to dynamic mapping.
+    /// The template only applies to indices created after it.
+    pub async fn install_template(&self) -> ApplicationResult<()> {
+        let mut properties = Map::new();
+        for (index_field, source) in self.mapping.entries() {
+            let field_type = match source {
+                SearchSource::OperationId
+                | SearchSource::TenantId
+                | SearchSource::Model
+                | SearchSource::Filename
+                | SearchSource::ContentType
+                | SearchSource::DocTypes => "keyword",
+                SearchSource::CreatedAt => "date",
+                SearchSource::PageCount => "integer",
+                SearchSource::Content | SearchSource::Fields => "text",
+                SearchSource::Field(_) => continue,
+            };
+            properties.insert(index_field.to_string(), json!({ "type": field_type }));
+        }
+        let template = json!({
+            "index_patterns": [self.index],
+            "template": { "mappings": { "properties": properties } },
+        });
+
+        let url = format!("{}/_index_template/{}-template", self.base_url, self.index);
+        let response = self
+            .authorize(self.client.put(url).json(&template))
+            .send()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to install index template: {}", e)))?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(ApplicationError::Internal(format!(
+                "Elasticsearch refused the index template with {}: {}",
+                status, body
+            )));
+        }
+        info!("Installed Elasticsearch index template for {}", self.index);
+        Ok(())
+    }
+
+    /// Send `documents` in one bulk request, retrying throttled items
+    ///
+    /// Fails with the first item that was rejected for another reason or was
+    /// still throttled after the last retry.
+    async fn bulk(&self, documents: &[SearchDocument]) -> Result<(), String> {
+        let mut pending: Vec<&SearchDocument> = documents.iter().collect();
+        let mut attempt = 0;
+        loop {
+            let mut body = String::new();
+            for document in &pending {
+                let action = json!({ "index": { "_index": self.index, "_id": document.operation_id } });
+                body.push_str(&action.to_string());
+                body.push('\n');
+                body.push_str(&Value::Object(self.mapping.apply(document)).to_string());
+                body.push('\n');
+            }
+            let request = self
+                .client
+                .post(format!("{}/_bulk", self.base_url))
+                .header("Content-Type", "application/x-ndjson")
+                .body(body);
+            let response = self
+                .authorize(request)
+                .send()
+                .await
+                .map_err(|e| format!("Failed to send bulk request to Elasticsearch: {}", e))?;
+
+            let throttled: Vec<&SearchDocument> = match response.status() {
+                StatusCode::TOO_MANY_REQUESTS => pending.clone(),
+                status if status.is_success() => {
+                    let bulk: BulkResponse = response
+                        .json()
+                        .await
+                        .map_err(|e| format!("Invalid Elasticsearch bulk response: {}", e))?;
+                    if !bulk.errors {
+                        return Ok(());
+                    }
+                    let mut throttled = Vec::new();
+                    for (document, item) in pending.iter().zip(&bulk.items) {
+                        let Some(item) = item.values().next().and_then(|item| BulkItem::deserialize(item).ok()) else {
+                            continue;
+                        };
+                        match item.status {
+                            200..=299 => {}
+                            429 => throttled.push(*document),
+                            status => {
+                                return Err(format!(
+                                    "Elasticsearch rejected document {} with {}: {}",
+                                    document.operation_id,
+                                    status,
+                                    item.error.unwrap_or_default()
+                                ));
+                            }
+                        }
+                    }
+                    throttled
+                }
+                status => {
+                    let body = response.text().await.unwrap_or_default();
+                    return Err(format!("Elasticsearch returned {}: {}", status, body));
+                }
+            };
+            if throttled.is_empty() {
+                return Ok(());
+            }
+            if attempt >= self.max_retries {
+                return Err(format!(
+                    "Elasticsearch still throttling {} documents after {} retries",
+                    throttled.len(),
+                    attempt
+                ));
+            }
+            let delay = RETRY_BASE_DELAY * 2u32.saturating_pow(attempt);
+            warn!("Elasticsearch throttled {} documents, retrying in {:?}", throttled.len(), delay);
+            tokio::time::sleep(delay).await;
+            pending = throttled;
+            attempt += 1;
+        }
+    }
+}
+
+#[async_trait]
+impl SearchIndexPort for ElasticsearchIndexer {
+    async fn index(&self, document: &SearchDocument) -> ApplicationResult<()> {
+        let outcome = self.bulk(std::slice::from_ref(document)).await;
+        metrics().record_search_indexed("elasticsearch", outcome.is_ok());
+        outcome.map_err(ApplicationError::Internal)?;
+        debug!("Operation {} indexed in Elasticsearch", document.operation_id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AnalysisOperation, AnalysisResult, ModelType};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn document() -> SearchDocument {
+        let operation = AnalysisOperation::new(ModelType::Read);
+        let result = AnalysisResult {
+            content: "Hello".to_string(),
+            ..Default::default()
+        };
+        SearchDocument::new(&operation, &result)
+    }
+
+    fn bulk_response(status: u16) -> Value {
+        json!({
+            "took": 3,
+            "errors": status >= 300,
+            "items": [{ "index": { "_index": "adi", "status": status } }],
+        })
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_bulk_retries_throttled_items() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/_bulk"))
+            .respond_with(ResponseTemplate::new(429))
+            .up_to_n_times(1)
+            .with_priority(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/_bulk"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(bulk_response(429)))
+            .up_to_n_times(1)
+            .with_priority(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/_bulk"))
+            .and(header("Authorization", "ApiKey secret"))
+            .and(header("Content-Type", "application/x-ndjson"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(bulk_response(201)))
+            .with_priority(4)
+            .mount(&server)
+            .await;
+
+        let indexer = ElasticsearchIndexer::new(
+            &server.uri(),
+            "adi",
+            ElasticsearchAuth::ApiKey("secret".to_string()),
+            IndexMapping::parse("id=operation_id,body=content").unwrap(),
+            3,
+        );
+        let document = document();
+        indexer.index(&document).await.unwrap();
+
+        let requests = server.received_requests().await.unwrap();
+        assert_eq!(requests.len(), 3);
+        let body = String::from_utf8(requests[2].body.clone()).unwrap();
+        let lines: Vec<Value> = body.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines[0]["index"]["_id"], document.operation_id);
+        assert_eq!(lines[1]["body"], "Hello");
+
+        let impatient = ElasticsearchIndexer::new(&server.uri(), "adi", ElasticsearchAuth::None, IndexMapping::default(), 0);
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429))
+            .with_priority(1)
+            .mount(&server)
+            .await;
+        assert!(impatient.index(&document).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejected_item_and_template() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/_bulk"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "errors": true,
+                "items": [{ "index": { "status": 400, "error": { "type": "mapper_parsing_exception" } } }],
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/_index_template/adi-template"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "acknowledged": true })))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let auth = ElasticsearchAuth::Basic { username: "elastic".to_string(), password: "changeme".to_string() };
+        let mapping = IndexMapping::parse("id=operation_id,created=created_at,body=content,total=field:Total").unwrap();
+        let indexer = ElasticsearchIndexer::new(&server.uri(), "adi", auth, mapping, 3);
+        let error = indexer.index(&document()).await.unwrap_err().to_string();
+        assert!(error.contains("mapper_parsing_exception"));
+
+        indexer.install_template().await.unwrap();
+        let requests = server.received_requests().await.unwrap();
+        let template: Value = serde_json::from_slice(&requests[1].body).unwrap();
+        assert_eq!(template["index_patterns"], json!(["adi"]));
+        let properties = &template["template"]["mappings"]["properties"];
+        assert_eq!(properties["id"]["type"], "keyword");
+        assert_eq!(properties["created"]["type"], "date");
+        assert_eq!(properties["body"]["type"], "text");
+        assert!(properties.get("total").is_none());
+        assert!(requests[1].headers.get("Authorization").unwrap().to_str().unwrap().starts_with("Basic "));
+    }
+}
@@ -0,0 +1,656 @@
+/// Envelope encryption of data at rest
+///
+/// Each value is sealed with AES-256-GCM under a data key of its own, and
+/// the data key is stored wrapped by a key-encryption key (KEK) from
+/// configuration or Azure Key Vault. Sealed values name the KEK that wrapped
+/// their data key, so KEKs can be rotated: new values use the current KEK,
+/// older ones open with the KEK they name until they are re-wrapped.
+///
+/// Files are sealed in segments of [`FILE_SEGMENT_BYTES`] behind a header
+/// naming their data key, so they can be written and read as streams and
+/// read from any offset.
+
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::Aes256Gcm;
+use async_trait::async_trait;
+use base64::Engine;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::infrastructure::config::{EncryptionConfig, Secret};
+
+/// Bytes of an AES-GCM nonce
+const NONCE_LEN: usize = 12;
+
+/// Bytes of an AES-GCM tag
+const TAG_LEN: usize = 16;
+
+/// First byte of a sealed value, for telling future layouts apart
+const ENVELOPE_VERSION: u8 = 1;
+
+/// First bytes of a sealed file
+const FILE_MAGIC: &[u8; 8] = b"ADISEAL1";
+
+/// Plaintext bytes in each segment of a sealed file but the last
+pub const FILE_SEGMENT_BYTES: usize = 64 * 1024;
+
+/// Bytes a segment adds to its plaintext
+pub const SEGMENT_OVERHEAD: usize = NONCE_LEN + TAG_LEN;
+
+/// Wraps data keys with a key-encryption key
+#[async_trait]
+pub trait KeyWrapper: Send + Sync {
+    /// ID of the KEK new data keys are wrapped with
+    async fn current_key_id(&self) -> ApplicationResult<String>;
+
+    /// Wrap `key` with the current KEK, returning the KEK's ID and the wrapped key
+    async fn wrap(&self, key: &[u8]) -> ApplicationResult<(String, Vec<u8>)>;
+
+    /// Unwrap a data key wrapped by the KEK `key_id`
+    async fn unwrap(&self, key_id: &str, wrapped: &[u8]) -> ApplicationResult<Vec<u8>>;
+}
+
+/// KEKs held in configuration, the first of which wraps new data keys
+pub struct LocalKeyRing {
+    current: String,
+    keys: HashMap<String, Aes256Gcm>,
+}
+
+impl std::fmt::Debug for LocalKeyRing {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LocalKeyRing").field("current", &self.current).finish_non_exhaustive()
+    }
+}
+
+impl LocalKeyRing {
+    /// Key ring of `(id, base64 key)` pairs of 32-byte keys
+    pub fn new(keys: &[(String, Secret)]) -> ApplicationResult<Self> {
+        let current = keys
+            .first()
+            .map(|(id, _)| id.clone())
+            .ok_or_else(|| ApplicationError::Configuration("ENCRYPTION_KEYS is empty".to_string()))?;
+        let keys = keys
+            .iter()
+            .map(|(id, key)| {
+                let kek = base64::engine::general_purpose::STANDARD
+                    .decode(key.expose())
+                    .ok()
+                    .and_then(|key| Aes256Gcm::new_from_slice(&key).ok())
+                    .ok_or_else(|| {
+                        ApplicationError::Configuration(format!("Encryption key {} is not 32 base64-encoded bytes", id))
+                    })?;
+                Ok((id.clone(), kek))
+            })
+            .collect::<ApplicationResult<_>>()?;
+        Ok(Self { current, keys })
+    }
+}
+
+#[async_trait]
+impl KeyWrapper for LocalKeyRing {
+    async fn current_key_id(&self) -> ApplicationResult<String> {
+        Ok(self.current.clone())
+    }
+
+    async fn wrap(&self, key: &[u8]) -> ApplicationResult<(String, Vec<u8>)> {
+        let wrapped = seal(&self.keys[&self.current], key, self.current.as_bytes())?;
+        Ok((self.current.clone(), wrapped))
+    }
+
+    async fn unwrap(&self, key_id: &str, wrapped: &[u8]) -> ApplicationResult<Vec<u8>> {
+        let kek = self.keys.get(key_id).ok_or_else(|| {
+            ApplicationError::Configuration(format!("Encryption key {} is not in ENCRYPTION_KEYS", key_id))
+        })?;
+        open(kek, wrapped, key_id.as_bytes())
+    }
+}
+
+#[cfg(feature = "server")]
+pub use key_vault::KeyVaultKeyWrapper;
+
+#[cfg(feature = "server")]
+mod key_vault {
+    use super::*;
+    use crate::infrastructure::azure_events::ManagedIdentityCredential;
+    use reqwest::Client;
+    use serde::Deserialize;
+
+    const KEY_VAULT_RESOURCE: &str = "https://vault.azure.net";
+    const KEY_VAULT_API_VERSION: &str = "7.4";
+    const WRAP_ALGORITHM: &str = "RSA-OAEP-256";
+
+    /// KEK held in Azure Key Vault, reached with the managed identity
+    ///
+    /// Wrapping uses the key's latest version; each version stays usable for
+    /// unwrapping until it is disabled in the vault.
+    pub struct KeyVaultKeyWrapper {
+        client: Client,
+        key_url: String,
+        credential: Arc<ManagedIdentityCredential>,
+    }
+
+    impl std::fmt::Debug for KeyVaultKeyWrapper {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("KeyVaultKeyWrapper").field("key_url", &self.key_url).finish_non_exhaustive()
+        }
+    }
+
+    #[derive(Deserialize)]
+    struct KeyOperationResult {
+        kid: String,
+        value: String,
+    }
+
+    #[derive(Deserialize)]
+    struct KeyBundle {
+        key: KeyProperties,
+    }
+
+    #[derive(Deserialize)]
+    struct KeyProperties {
+        kid: String,
+    }
+
+    impl KeyVaultKeyWrapper {
+        /// Wrapper for `key_url`, e.g. `https://acme.vault.azure.net/keys/results`
+        pub fn new(key_url: &str, credential: Arc<ManagedIdentityCredential>) -> ApplicationResult<Self> {
+            let key_url = key_url.trim_end_matches('/').to_string();
+            let valid = url::Url::parse(&key_url).is_ok_and(|url| url.path().starts_with("/keys/"));
+            if !valid {
+                return Err(ApplicationError::Configuration(format!(
+                    "Invalid ENCRYPTION_KEY_VAULT_KEY_URL {}; expected https://<vault>/keys/<name>",
+                    key_url
+                )));
+            }
+            Ok(Self { client: Client::new(), key_url, credential })
+        }
+
+        async fn post(&self, url: &str, value: &[u8], action: &str) -> ApplicationResult<KeyOperationResult> {
+            let body = serde_json::json!({
+                "alg": WRAP_ALGORITHM,
+                "value": base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(value),
+            });
+            let request = self.client.post(format!("{}/{}", url, action)).json(&body);
+            self.send(request, action).await
+        }
+
+        async fn send<T: DeserializeOwned>(&self, request: reqwest::RequestBuilder, action: &str) -> ApplicationResult<T> {
+            let response = request
+                .query(&[("api-version", KEY_VAULT_API_VERSION)])
+                .bearer_auth(self.credential.token(KEY_VAULT_RESOURCE).await?)
+                .send()
+                .await
+                .map_err(|e| ApplicationError::Internal(format!("Failed to {} with Key Vault: {}", action, e)))?;
+            if !response.status().is_success() {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                return Err(ApplicationError::Internal(format!("Key Vault returned {} for {}: {}", status, action, body)));
+            }
+            response
+                .json()
+                .await
+                .map_err(|e| ApplicationError::Internal(format!("Invalid Key Vault {} response: {}", action, e)))
+        }
+    }
+
+    #[async_trait]
+    impl KeyWrapper for KeyVaultKeyWrapper {
+        async fn current_key_id(&self) -> ApplicationResult<String> {
+            let bundle: KeyBundle = self.send(self.client.get(&self.key_url), "get key").await?;
+            Ok(bundle.key.kid)
+        }
+
+        async fn wrap(&self, key: &[u8]) -> ApplicationResult<(String, Vec<u8>)> {
+            let result = self.post(&self.key_url, key, "wrapkey").await?;
+            Ok((result.kid, decode_url_safe(&result.value)?))
+        }
+
+        /// Only versions of the configured key are asked, so a tampered key ID
+        /// cannot send the managed identity's token elsewhere
+        async fn unwrap(&self, key_id: &str, wrapped: &[u8]) -> ApplicationResult<Vec<u8>> {
+            let version = key_id
+                .strip_prefix(&self.key_url)
+                .filter(|rest| rest.is_empty() || (rest.starts_with('/') && !rest[1..].contains('/')));
+            if version.is_none() {
+                return Err(ApplicationError::Internal(format!("Data key was wrapped by a foreign key {}", key_id)));
+            }
+            let result = self.post(key_id, wrapped, "unwrapkey").await?;
+            decode_url_safe(&result.value)
+        }
+    }
+
+    fn decode_url_safe(value: &str) -> ApplicationResult<Vec<u8>> {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD
+            .decode(value.trim_end_matches('='))
+            .map_err(|e| ApplicationError::Internal(format!("Invalid key from Key Vault: {}", e)))
+    }
+}
+
+/// A value sealed under a data key, with the data key as wrapped by a KEK
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Envelope {
+    /// KEK that wrapped the data key
+    pub key_id: String,
+    wrapped_key: Vec<u8>,
+    /// Nonce followed by the ciphertext and tag
+    sealed: Vec<u8>,
+}
+
+impl Envelope {
+    /// Stored form: version, length-prefixed key ID and wrapped key, then the sealed value
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(5 + self.key_id.len() + self.wrapped_key.len() + self.sealed.len());
+        bytes.push(ENVELOPE_VERSION);
+        for part in [self.key_id.as_bytes(), &self.wrapped_key] {
+            bytes.extend((part.len() as u16).to_be_bytes());
+            bytes.extend(part);
+        }
+        bytes.extend(&self.sealed);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> ApplicationResult<Self> {
+        let malformed = || ApplicationError::Internal("Malformed encrypted value".to_string());
+        let (&version, mut rest) = bytes.split_first().ok_or_else(malformed)?;
+        if version != ENVELOPE_VERSION {
+            return Err(ApplicationError::Internal(format!("Unknown encrypted value version {}", version)));
+        }
+        let mut part = || -> ApplicationResult<Vec<u8>> {
+            let len = rest.get(..2).ok_or_else(malformed)?;
+            let len = u16::from_be_bytes([len[0], len[1]]) as usize;
+            let part = rest.get(2..2 + len).ok_or_else(malformed)?.to_vec();
+            rest = &rest[2 + len..];
+            Ok(part)
+        };
+        let key_id = String::from_utf8(part()?).map_err(|_| malformed())?;
+        let wrapped_key = part()?;
+        if rest.len() < NONCE_LEN {
+            return Err(malformed());
+        }
+        Ok(Self { key_id, wrapped_key, sealed: rest.to_vec() })
+    }
+}
+
+/// Seals and opens values with envelope encryption
+#[derive(Clone)]
+pub struct EnvelopeCipher {
+    wrapper: Arc<dyn KeyWrapper>,
+}
+
+impl std::fmt::Debug for EnvelopeCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EnvelopeCipher").finish_non_exhaustive()
+    }
+}
+
+impl EnvelopeCipher {
+    pub fn new(wrapper: Arc<dyn KeyWrapper>) -> Self {
+        Self { wrapper }
+    }
+
+    /// Cipher for the configured KEKs, or `None` when encryption is off
+    #[cfg(feature = "server")]
+    pub fn from_config(
+        config: &EncryptionConfig,
+        credential: Arc<crate::infrastructure::azure_events::ManagedIdentityCredential>,
+    ) -> ApplicationResult<Option<Self>> {
+        if let Some(url) = &config.key_vault_key_url {
+            return Ok(Some(Self::new(Arc::new(KeyVaultKeyWrapper::new(url, credential)?))));
+        }
+        Self::from_keys(config)
+    }
+
+    /// Cipher for the KEKs in configuration, or `None` when there are none
+    pub fn from_keys(config: &EncryptionConfig) -> ApplicationResult<Option<Self>> {
+        if config.keys.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(Self::new(Arc::new(LocalKeyRing::new(&config.keys)?))))
+    }
+
+    /// ID of the KEK new values are sealed under
+    pub async fn current_key_id(&self) -> ApplicationResult<String> {
+        self.wrapper.current_key_id().await
+    }
+
+    /// Seal `plaintext` under a new data key
+    ///
+    /// `context` is authenticated with the value, so it only opens with the
+    /// same context, e.g. the row it was stored in.
+    pub async fn seal(&self, plaintext: &[u8], context: &str) -> ApplicationResult<Envelope> {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let sealed = seal(&Aes256Gcm::new(&key), plaintext, context.as_bytes())?;
+        let (key_id, wrapped_key) = self.wrapper.wrap(&key).await?;
+        Ok(Envelope { key_id, wrapped_key, sealed })
+    }
+
+    pub async fn open(&self, envelope: &Envelope, context: &str) -> ApplicationResult<Vec<u8>> {
+        let cipher = self.data_key(envelope).await?;
+        open(&cipher, &envelope.sealed, context.as_bytes())
+    }
+
+    /// Seal `value` as JSON
+    pub async fn seal_json<T: Serialize + ?Sized>(&self, value: &T, context: &str) -> ApplicationResult<Envelope> {
+        let json = serde_json::to_vec(value)
+            .map_err(|e| ApplicationError::Internal(format!("Failed to serialize {}: {}", context, e)))?;
+        self.seal(&json, context).await
+    }
+
+    /// Open the stored form of a value sealed by [`Self::seal_json`]
+    pub async fn open_json<T: DeserializeOwned>(&self, stored: &[u8], context: &str) -> ApplicationResult<T> {
+        let json = self.open(&Envelope::from_bytes(stored)?, context).await?;
+        serde_json::from_slice(&json)
+            .map_err(|e| ApplicationError::Internal(format!("Failed to deserialize {}: {}", context, e)))
+    }
+
+    /// The envelope with its data key wrapped by the current KEK; the sealed value is untouched
+    pub async fn rewrap(&self, envelope: &Envelope) -> ApplicationResult<Envelope> {
+        let key = self.wrapper.unwrap(&envelope.key_id, &envelope.wrapped_key).await?;
+        let (key_id, wrapped_key) = self.wrapper.wrap(&key).await?;
+        Ok(Envelope { key_id, wrapped_key, sealed: envelope.sealed.clone() })
+    }
+
+    /// A new data key for a file, with the header to store it under
+    pub async fn file_key(&self) -> ApplicationResult<(FileHeader, FileKey)> {
+        let key = Aes256Gcm::generate_key(OsRng);
+        let (key_id, wrapped_key) = self.wrapper.wrap(&key).await?;
+        Ok((FileHeader { key_id, wrapped_key }, FileKey { cipher: Aes256Gcm::new(&key) }))
+    }
+
+    /// The data key a file's header names
+    pub async fn open_file_key(&self, header: &FileHeader) -> ApplicationResult<FileKey> {
+        let cipher = self.unwrap_key(&header.key_id, &header.wrapped_key).await?;
+        Ok(FileKey { cipher })
+    }
+
+    /// The header with its data key wrapped by the current KEK
+    pub async fn rewrap_file_header(&self, header: &FileHeader) -> ApplicationResult<FileHeader> {
+        let key = self.wrapper.unwrap(&header.key_id, &header.wrapped_key).await?;
+        let (key_id, wrapped_key) = self.wrapper.wrap(&key).await?;
+        Ok(FileHeader { key_id, wrapped_key })
+    }
+
+    async fn data_key(&self, envelope: &Envelope) -> ApplicationResult<Aes256Gcm> {
+        self.unwrap_key(&envelope.key_id, &envelope.wrapped_key).await
+    }
+
+    async fn unwrap_key(&self, key_id: &str, wrapped_key: &[u8]) -> ApplicationResult<Aes256Gcm> {
+        let key = self.wrapper.unwrap(key_id, wrapped_key).await?;
+        Aes256Gcm::new_from_slice(&key)
+            .map_err(|_| ApplicationError::Internal(format!("Data key wrapped by {} has the wrong length", key_id)))
+    }
+}
+
+/// Start of a sealed file, naming its data key as wrapped by a KEK
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHeader {
+    /// KEK that wrapped the data key
+    pub key_id: String,
+    wrapped_key: Vec<u8>,
+}
+
+impl FileHeader {
+    /// Magic bytes, then the length-prefixed key ID and wrapped key
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = FILE_MAGIC.to_vec();
+        for part in [self.key_id.as_bytes(), &self.wrapped_key] {
+            bytes.extend((part.len() as u16).to_be_bytes());
+            bytes.extend(part);
+        }
+        bytes
+    }
+
+    /// Bytes the header takes at the start of the file
+    pub fn encoded_len(&self) -> u64 {
+        (FILE_MAGIC.len() + 4 + self.key_id.len() + self.wrapped_key.len()) as u64
+    }
+
+    /// The header at the start of `reader`, or `None` when the file was stored unsealed
+    ///
+    /// An unsealed file has been read from when this returns `None`.
+    pub async fn read<R: AsyncRead + Unpin>(reader: &mut R) -> ApplicationResult<Option<Self>> {
+        let failed = |e: std::io::Error| ApplicationError::Internal(format!("Failed to read file header: {}", e));
+        let mut magic = [0; FILE_MAGIC.len()];
+        match reader.read_exact(&mut magic).await {
+            Ok(_) if &magic == FILE_MAGIC => {}
+            Ok(_) => return Ok(None),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(failed(e)),
+        }
+        let mut parts = Vec::with_capacity(2);
+        for _ in 0..2 {
+            let len = reader.read_u16().await.map_err(failed)?;
+            let mut part = vec![0; len as usize];
+            reader.read_exact(&mut part).await.map_err(failed)?;
+            parts.push(part);
+        }
+        let wrapped_key = parts.pop().unwrap_or_default();
+        let key_id = String::from_utf8(parts.pop().unwrap_or_default())
+            .map_err(|_| ApplicationError::Internal("Malformed file header".to_string()))?;
+        Ok(Some(Self { key_id, wrapped_key }))
+    }
+}
+
+/// Data key of one sealed file
+///
+/// Each segment has a nonce of its own and is authenticated with its index
+/// and whether it is the last, so segments cannot be reordered, dropped or
+/// cut off the end without failing to open.
+#[derive(Clone)]
+pub struct FileKey {
+    cipher: Aes256Gcm,
+}
+
+impl std::fmt::Debug for FileKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FileKey").finish_non_exhaustive()
+    }
+}
+
+impl FileKey {
+    pub fn seal_segment(&self, index: u64, last: bool, plaintext: &[u8]) -> ApplicationResult<Vec<u8>> {
+        seal(&self.cipher, plaintext, &segment_aad(index, last))
+    }
+
+    pub fn open_segment(&self, index: u64, last: bool, segment: &[u8]) -> ApplicationResult<Vec<u8>> {
+        open(&self.cipher, segment, &segment_aad(index, last))
+    }
+}
+
+fn segment_aad(index: u64, last: bool) -> [u8; 9] {
+    let mut aad = [0; 9];
+    aad[..8].copy_from_slice(&index.to_be_bytes());
+    aad[8] = last as u8;
+    aad
+}
+
+/// Segments in the `body` bytes after a sealed file's header; there is always at least one
+pub fn segment_count(body: u64) -> u64 {
+    body.div_ceil((FILE_SEGMENT_BYTES + SEGMENT_OVERHEAD) as u64).max(1)
+}
+
+/// Plaintext length of the `body` bytes after a sealed file's header
+pub fn sealed_plaintext_len(body: u64) -> u64 {
+    body.saturating_sub(segment_count(body) * SEGMENT_OVERHEAD as u64)
+}
+
+/// Seals a file's plaintext into segments as it arrives
+#[derive(Debug)]
+pub struct SegmentWriter {
+    key: FileKey,
+    index: u64,
+    pending: Vec<u8>,
+}
+
+impl SegmentWriter {
+    /// Writer continuing at segment `index` with `pending` plaintext not yet sealed
+    pub fn new(key: FileKey, index: u64, pending: Vec<u8>) -> Self {
+        Self { key, index, pending }
+    }
+
+    /// Add plaintext, returning the segments it completes
+    ///
+    /// A full segment is only sealed once more plaintext follows, since the
+    /// last segment is sealed differently.
+    pub fn push(&mut self, plaintext: &[u8]) -> ApplicationResult<Vec<u8>> {
+        self.pending.extend_from_slice(plaintext);
+        let mut sealed = Vec::new();
+        while self.pending.len() > FILE_SEGMENT_BYTES {
+            let rest = self.pending.split_off(FILE_SEGMENT_BYTES);
+            sealed.extend(self.key.seal_segment(self.index, false, &self.pending)?);
+            self.pending = rest;
+            self.index += 1;
+        }
+        Ok(sealed)
+    }
+
+    /// Seal what is left as the last segment
+    pub fn finish(self) -> ApplicationResult<Vec<u8>> {
+        self.key.seal_segment(self.index, true, &self.pending)
+    }
+}
+
+/// Nonce followed by the ciphertext and tag
+fn seal(cipher: &Aes256Gcm, plaintext: &[u8], aad: &[u8]) -> ApplicationResult<Vec<u8>> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, Payload { msg: plaintext, aad })
+        .map_err(|_| ApplicationError::Internal("Encryption failed".to_string()))?;
+    let mut sealed = nonce.to_vec();
+    sealed.extend(ciphertext);
+    Ok(sealed)
+}
+
+fn open(cipher: &Aes256Gcm, sealed: &[u8], aad: &[u8]) -> ApplicationResult<Vec<u8>> {
+    if sealed.len() < NONCE_LEN {
+        return Err(ApplicationError::Internal("Malformed encrypted value".to_string()));
+    }
+    let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+    let nonce: aes_gcm::Nonce<_> = nonce.iter().copied().collect();
+    cipher
+        .decrypt(&nonce, Payload { msg: ciphertext, aad })
+        .map_err(|_| ApplicationError::Internal("Encrypted value failed authentication".to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(keys: &[(&str, u8)]) -> EncryptionConfig {
+        EncryptionConfig {
+            keys: keys
+                .iter()
+                .map(|(id, byte)| (id.to_string(), Secret::from(base64::engine::general_purpose::STANDARD.encode([*byte; 32]))))
+                .collect(),
+            key_vault_key_url: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_seal_open_and_rotate() {
+        let old = EnvelopeCipher::from_keys(&config(&[("k1", 1)])).unwrap().unwrap();
+        let stored = old.seal_json(&serde_json::json!({"content": "secret"}), "results/op-1").await.unwrap().to_bytes();
+        assert!(!String::from_utf8_lossy(&stored).contains("secret"));
+        let value: serde_json::Value = old.open_json(&stored, "results/op-1").await.unwrap();
+        assert_eq!(value["content"], "secret");
+        assert!(old.open_json::<serde_json::Value>(&stored, "results/op-2").await.is_err(), "bound to its row");
+
+        // k2 becomes current; values under k1 still open, and re-wrap onto k2
+        let rotated = EnvelopeCipher::from_keys(&config(&[("k2", 2), ("k1", 1)])).unwrap().unwrap();
+        assert_eq!(rotated.current_key_id().await.unwrap(), "k2");
+        let envelope = Envelope::from_bytes(&stored).unwrap();
+        assert_eq!(envelope.key_id, "k1");
+        let rewrapped = rotated.rewrap(&envelope).await.unwrap();
+        assert_eq!(rewrapped.key_id, "k2");
+        let retired = EnvelopeCipher::from_keys(&config(&[("k2", 2)])).unwrap().unwrap();
+        assert!(retired.open(&envelope, "results/op-1").await.is_err());
+        assert_eq!(retired.open(&rewrapped, "results/op-1").await.unwrap(), br#"{"content":"secret"}"#);
+
+        let mut tampered = stored.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert!(old.open_json::<serde_json::Value>(&tampered, "results/op-1").await.is_err());
+        assert!(Envelope::from_bytes(&stored[..8]).is_err());
+        let short = EncryptionConfig { keys: vec![("k1".to_string(), Secret::from("c2hvcnQ="))], key_vault_key_url: None };
+        assert!(EnvelopeCipher::from_keys(&short).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sealed_file_segments() {
+        let cipher = EnvelopeCipher::from_keys(&config(&[("k1", 1)])).unwrap().unwrap();
+        let (header, key) = cipher.file_key().await.unwrap();
+        let plaintext: Vec<u8> = (0..FILE_SEGMENT_BYTES * 2 + 100).map(|i| i as u8).collect();
+        let mut writer = SegmentWriter::new(key, 0, Vec::new());
+        let mut file = header.to_bytes();
+        for chunk in plaintext.chunks(50_000) {
+            file.extend(writer.push(chunk).unwrap());
+        }
+        file.extend(writer.finish().unwrap());
+
+        let mut reader = file.as_slice();
+        let read = FileHeader::read(&mut reader).await.unwrap().unwrap();
+        assert_eq!((read.key_id.as_str(), read.encoded_len()), ("k1", header.to_bytes().len() as u64));
+        let body = &file[header.to_bytes().len()..];
+        assert_eq!((segment_count(body.len() as u64), sealed_plaintext_len(body.len() as u64)), (3, plaintext.len() as u64));
+        let key = cipher.open_file_key(&read).await.unwrap();
+        let segments: Vec<&[u8]> = body.chunks(FILE_SEGMENT_BYTES + SEGMENT_OVERHEAD).collect();
+        let opened: Vec<u8> = segments
+            .iter()
+            .enumerate()
+            .flat_map(|(index, segment)| key.open_segment(index as u64, index == 2, segment).unwrap())
+            .collect();
+        assert_eq!(opened, plaintext);
+        assert!(key.open_segment(1, true, segments[1]).is_err(), "a cut-off file does not open");
+        assert!(key.open_segment(0, false, segments[1]).is_err(), "segments cannot be reordered");
+
+        let empty = SegmentWriter::new(key, 0, Vec::new()).finish().unwrap();
+        assert_eq!((segment_count(empty.len() as u64), sealed_plaintext_len(empty.len() as u64)), (1, 0));
+        assert!(FileHeader::read(&mut b"%PDF-1.7".as_slice()).await.unwrap().is_none());
+        assert!(FileHeader::read(&mut b"%PDF".as_slice()).await.unwrap().is_none());
+    }
+
+    #[cfg(feature = "server")]
+    #[tokio::test]
+    async fn test_key_vault_wrapper() {
+        use crate::infrastructure::azure_events::ManagedIdentityCredential;
+        use wiremock::matchers::{body_partial_json, method, path, query_param};
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/identity"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "access_token": "token",
+                "expires_on": "4102444800",
+            })))
+            .mount(&server)
+            .await;
+        let kid = format!("{}/keys/results/v2", server.uri());
+        Mock::given(method("POST"))
+            .and(path("/keys/results/wrapkey"))
+            .and(query_param("api-version", "7.4"))
+            .and(body_partial_json(serde_json::json!({"alg": "RSA-OAEP-256"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"kid": kid, "value": "d3JhcHBlZA"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/keys/results/v2/unwrapkey"))
+            .and(body_partial_json(serde_json::json!({"value": "d3JhcHBlZA"})))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({"kid": kid, "value": "a2V5"})))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let credential = Arc::new(ManagedIdentityCredential::new(format!("{}/identity", server.uri()), None, None));
+        let wrapper = KeyVaultKeyWrapper::new(&format!("{}/keys/results/", server.uri()), credential).unwrap();
+        let (key_id, wrapped) = wrapper.wrap(b"key").await.unwrap();
+        assert_eq!((key_id.as_str(), wrapped.as_slice()), (kid.as_str(), b"wrapped".as_slice()));
+        assert_eq!(wrapper.unwrap(&kid, &wrapped).await.unwrap(), b"key");
+        assert!(wrapper.unwrap("https://evil.example/keys/results/v2", &wrapped).await.is_err());
+        assert!(wrapper.unwrap(&format!("{}/keys/results-other/v1", server.uri()), &wrapped).await.is_err());
+    }
+}
@@ -0,0 +1,131 @@
+/// Wire formats for published lifecycle events
+///
+/// Broker adapters share these encodings so consumers see the same payload
+/// whichever transport carries it.
+
+use async_trait::async_trait;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::EventPublisherPort;
+use crate::domain::LifecycleEvent;
+use crate::presentation::converters::lifecycle_event_to_pb;
+
+/// Payload encoding of published events
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventFormat {
+    /// The event as JSON
+    #[default]
+    Json,
+    /// `OperationLifecycleEvent` from `document_intelligence.proto`
+    Protobuf,
+}
+
+impl EventFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(Self::Json),
+            "protobuf" | "proto" => Some(Self::Protobuf),
+            _ => None,
+        }
+    }
+    
+    /// MIME type of encoded payloads
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Protobuf => "application/x-protobuf",
+        }
+    }
+    
+    pub fn encode(&self, event: &LifecycleEvent) -> ApplicationResult<Vec<u8>> {
+        match self {
+            Self::Json => serde_json::to_vec(event)
+                .map_err(|e| ApplicationError::Internal(format!("Failed to encode event: {}", e))),
+            Self::Protobuf => Ok(lifecycle_event_to_pb(event).encode_to_vec()),
+        }
+    }
+}
+
+/// Fill `{event_type}`, `{tenant}` and `{model}` in a routing key or subject template
+pub fn render_route(template: &str, event: &LifecycleEvent) -> String {
+    template
+        .replace("{event_type}", event.event_type.as_str())
+        .replace("{tenant}", event.tenant_id.as_str())
+        .replace("{model}", &event.model)
+}
+
+/// Publishes every event to each of several brokers
+///
+/// All publishers are tried; the first failure is returned after the rest ran.
+pub struct FanoutEventPublisher {
+    publishers: Vec<Arc<dyn EventPublisherPort>>,
+}
+
+impl FanoutEventPublisher {
+    pub fn new(publishers: Vec<Arc<dyn EventPublisherPort>>) -> Self {
+        Self { publishers }
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for FanoutEventPublisher {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()> {
+        let mut outcome = Ok(());
+        for publisher in &self.publishers {
+            if let Err(e) = publisher.publish(event).await {
+                if outcome.is_ok() {
+                    outcome = Err(e);
+                }
+            }
+        }
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{AnalysisOperation, AnalysisResult, LifecycleEventKind, ModelType, OperationStatus};
+    use crate::generated as pb;
+
+    #[test]
+    fn test_encode_event() {
+        let mut operation = AnalysisOperation::new(ModelType::Invoice);
+        operation.update_status(OperationStatus::Succeeded);
+        let event = LifecycleEvent::new(LifecycleEventKind::Succeeded, &operation)
+            .with_summary(&AnalysisResult::default());
+
+        let json: serde_json::Value = serde_json::from_slice(&EventFormat::Json.encode(&event).unwrap()).unwrap();
+        assert_eq!(json["event_type"], "succeeded");
+        assert_eq!(json["model"], "prebuilt-invoice");
+        assert_eq!(json["tenant_id"], "default");
+        assert_eq!(json["summary"]["pages"], 0);
+        assert!(json.get("document_id").is_none());
+
+        let bytes = EventFormat::Protobuf.encode(&event).unwrap();
+        let decoded = pb::OperationLifecycleEvent::decode(bytes.as_slice()).unwrap();
+        assert_eq!(decoded.operation_id, operation.operation_id);
+        assert_eq!(decoded.event_type, pb::LifecycleEventType::Succeeded as i32);
+        assert_eq!(decoded.status, pb::AnalysisStatus::StatusSucceeded as i32);
+        assert!(decoded.summary.is_some());
+
+        assert_eq!(EventFormat::parse("Proto"), Some(EventFormat::Protobuf));
+        assert_eq!(EventFormat::parse("avro"), None);
+    }
+
+    #[test]
+    fn test_render_route() {
+        let mut operation = AnalysisOperation::new(ModelType::Receipt);
+        operation.tenant_id = crate::domain::TenantId::new("acme").unwrap();
+        let event = LifecycleEvent::new(LifecycleEventKind::Created, &operation);
+        assert_eq!(
+            render_route("adi.{tenant}.{model}.{event_type}", &event),
+            "adi.acme.prebuilt-receipt.created"
+        );
+        assert_eq!(render_route("fixed", &event), "fixed");
+    }
+}
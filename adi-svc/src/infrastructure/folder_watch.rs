@@ -0,0 +1,262 @@
+/// Drop-folder ingestion
+///
+/// Analyzes every file that lands in a local folder with one model, then
+/// moves it to a processed folder with its result next to it, or to a failed
+/// folder with the error. Scanners write files in pieces, so a file is only
+/// picked up once it has been left alone for the settle period, and hidden
+/// or partial-download files are skipped. With the `watch` feature inotify
+/// (or the platform's equivalent) wakes the watcher as files land; the folder
+/// is also rescanned every poll interval, which is all that happens without it.
+
+use chrono::Utc;
+use serde_json::json;
+use std::fs::Metadata;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::services::DocumentIntelligenceService;
+use crate::domain::{
+    AnalysisOperation, AnalysisResult, AnalyzeDocumentRequest, AnalyzeOptions, DocumentMetadata, DocumentSource,
+    ModelType, TenantId,
+};
+use crate::infrastructure::config::FolderWatchConfig;
+use crate::infrastructure::tasks::TaskSupervisor;
+
+/// A file whose result takes longer than this is moved to the failed folder
+const RESULT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Suffixes producers use while a file is still being written
+pub(crate) const PARTIAL_SUFFIXES: &[&str] = &[".tmp", ".part", ".partial", ".crdownload"];
+
+/// Ingests the files dropped into one folder, one at a time
+pub struct FolderWatcher {
+    service: Arc<DocumentIntelligenceService>,
+    dir: PathBuf,
+    processed_dir: PathBuf,
+    failed_dir: PathBuf,
+    model: ModelType,
+    tenant: TenantId,
+    write_results: bool,
+    poll_interval: Duration,
+    settle: Duration,
+}
+
+impl FolderWatcher {
+    /// Watcher for the configured folder, or `None` when none is configured
+    pub fn from_config(service: Arc<DocumentIntelligenceService>, config: &FolderWatchConfig) -> Option<Self> {
+        let dir = PathBuf::from(config.dir.as_ref()?);
+        let processed_dir = config
+            .processed_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| dir.join("processed"));
+        let failed_dir = config
+            .failed_dir
+            .as_ref()
+            .map(PathBuf::from)
+            .unwrap_or_else(|| dir.join("failed"));
+        Some(Self {
+            service,
+            dir,
+            processed_dir,
+            failed_dir,
+            model: config.model,
+            tenant: config.tenant.clone(),
+            write_results: config.write_results,
+            poll_interval: Duration::from_millis(config.poll_interval_ms),
+            settle: Duration::from_millis(config.settle_ms),
+        })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Ingest every settled file in the folder; returns how many were picked up
+    pub async fn scan(&self) -> ApplicationResult<usize> {
+        for dir in [&self.processed_dir, &self.failed_dir] {
+            tokio::fs::create_dir_all(dir)
+                .await
+                .map_err(|e| ApplicationError::Internal(format!("Failed to create {}: {}", dir.display(), e)))?;
+        }
+
+        let mut entries = tokio::fs::read_dir(&self.dir)
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to read {}: {}", self.dir.display(), e)))?;
+        let mut ready = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to read {}: {}", self.dir.display(), e)))?
+        {
+            let path = entry.path();
+            match entry.metadata().await {
+                Ok(metadata) if self.is_ready(&path, &metadata) => ready.push(path),
+                _ => {}
+            }
+        }
+
+        ready.sort();
+        for path in &ready {
+            self.ingest(path).await;
+        }
+        Ok(ready.len())
+    }
+
+    /// Whether `path` is a finished file the watcher should take
+    fn is_ready(&self, path: &Path, metadata: &Metadata) -> bool {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        if !metadata.is_file() || name.starts_with('.') || PARTIAL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix)) {
+            return false;
+        }
+        metadata
+            .modified()
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map_or(self.settle.is_zero(), |age| age >= self.settle)
+    }
+
+    /// Analyze one file and move it out of the folder
+    async fn ingest(&self, path: &Path) {
+        let name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+        let (target_dir, sidecar) = match self.analyze(path, &name).await {
+            Ok((operation, result)) => {
+                info!("Ingested {} as operation {}", name, operation.operation_id);
+                let sidecar = self.write_results.then(|| {
+                    let body = json!({ "operation": operation, "result": result });
+                    ("json", serde_json::to_vec_pretty(&body).unwrap_or_default())
+                });
+                (&self.processed_dir, sidecar)
+            }
+            Err(e) => {
+                warn!("Failed to ingest {}: {}", name, e);
+                (&self.failed_dir, Some(("error.txt", e.to_string().into_bytes())))
+            }
+        };
+
+        let target = match move_file(path, target_dir, &name).await {
+            Ok(target) => target,
+            Err(e) => {
+                error!("Failed to move {} to {}: {}", path.display(), target_dir.display(), e);
+                return;
+            }
+        };
+        if let Some((extension, body)) = sidecar {
+            let sidecar_path = PathBuf::from(format!("{}.{}", target.display(), extension));
+            if let Err(e) = tokio::fs::write(&sidecar_path, body).await {
+                error!("Failed to write {}: {}", sidecar_path.display(), e);
+            }
+        }
+    }
+
+    /// Submit a file and wait for its result
+    async fn analyze(&self, path: &Path, name: &str) -> ApplicationResult<(AnalysisOperation, AnalysisResult)> {
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to read {}: {}", name, e)))?;
+        let request = AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(bytes.into()),
+            model_type: self.model,
+            options: AnalyzeOptions::default(),
+            metadata: Some(DocumentMetadata::new(name, "")),
+            tenant_id: self.tenant.clone(),
+        };
+        let operation_id = self.service.analyze_document(request).await?.operation_id;
+        self.service
+            .wait_for_result(&self.tenant, &operation_id, self.poll_interval, RESULT_TIMEOUT)
+            .await
+    }
+}
+
+/// Move `path` into `dir`, prefixing a timestamp when the name is taken
+async fn move_file(path: &Path, dir: &Path, name: &str) -> std::io::Result<PathBuf> {
+    let mut target = dir.join(name);
+    if tokio::fs::try_exists(&target).await? {
+        target = dir.join(format!("{}-{}", Utc::now().format("%Y%m%dT%H%M%S%.3f"), name));
+    }
+    // Rename fails across filesystems; fall back to copying
+    if tokio::fs::rename(path, &target).await.is_err() {
+        tokio::fs::copy(path, &target).await?;
+        tokio::fs::remove_file(path).await?;
+    }
+    Ok(target)
+}
+
+/// Run `watcher` until shutdown
+pub fn spawn_folder_watch(supervisor: &TaskSupervisor, watcher: FolderWatcher) {
+    info!(
+        "Watching {} for documents to analyze with {}",
+        watcher.dir.display(),
+        watcher.model.as_str()
+    );
+    let watcher = Arc::new(watcher);
+    supervisor.spawn("folder-watch", move |shutdown| {
+        let watcher = watcher.clone();
+        async move {
+            let mut changes = FolderChanges::new(&watcher.dir);
+            loop {
+                if let Err(e) = watcher.scan().await {
+                    error!("Folder scan failed: {}", e);
+                }
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = changes.changed() => {}
+                    _ = tokio::time::sleep(watcher.poll_interval) => {}
+                }
+            }
+        }
+    });
+}
+
+/// Filesystem notifications for the watched folder
+struct FolderChanges {
+    #[cfg(feature = "watch")]
+    notifications: Option<(notify::RecommendedWatcher, tokio::sync::mpsc::UnboundedReceiver<()>)>,
+}
+
+impl FolderChanges {
+    #[cfg(feature = "watch")]
+    fn new(dir: &Path) -> Self {
+        use notify::Watcher;
+
+        let (sender, receiver) = tokio::sync::mpsc::unbounded_channel();
+        let watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            if event.is_ok() {
+                let _ = sender.send(());
+            }
+        })
+        .and_then(|mut watcher| watcher.watch(dir, notify::RecursiveMode::NonRecursive).map(|_| watcher));
+        match watcher {
+            Ok(watcher) => Self {
+                notifications: Some((watcher, receiver)),
+            },
+            Err(e) => {
+                warn!("Cannot watch {}; relying on rescans: {}", dir.display(), e);
+                Self { notifications: None }
+            }
+        }
+    }
+
+    #[cfg(not(feature = "watch"))]
+    fn new(_dir: &Path) -> Self {
+        Self {}
+    }
+
+    /// Resolves once the folder has changed; never, without notifications
+    async fn changed(&mut self) {
+        #[cfg(feature = "watch")]
+        if let Some((_, receiver)) = &mut self.notifications {
+            if receiver.recv().await.is_some() {
+                // One rescan covers a burst of events
+                while receiver.try_recv().is_ok() {}
+                return;
+            }
+        }
+        std::future::pending::<()>().await
+    }
+}
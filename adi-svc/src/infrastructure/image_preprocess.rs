@@ -0,0 +1,274 @@
+/// Image preprocessing adapter
+///
+/// Cleans up phone-camera captures before OCR with the `image` crate:
+/// straightens skewed text, drops colour, caps resolution and re-encodes
+/// as JPEG to shrink the payload.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use image::imageops::FilterType;
+use image::{DynamicImage, GrayImage, ImageBuffer, ImageFormat, ImageOutputFormat, Pixel};
+use std::io::Cursor;
+use tracing::debug;
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::ImagePreprocessPort;
+use crate::domain::{DocumentFormat, DomainError, ImagePreprocessing};
+
+/// Long edge of an A4 page; `max_dpi` assumes the capture spans a page
+const PAGE_LONG_EDGE_INCHES: f32 = 11.7;
+
+/// Quality used when an edited JPEG is re-encoded without an explicit quality
+const DEFAULT_JPEG_QUALITY: u8 = 85;
+
+/// Largest skew corrected, either way
+const MAX_SKEW_DEGREES: f32 = 10.0;
+
+const SKEW_STEP_DEGREES: f32 = 0.25;
+
+/// Skew below this is left alone to avoid resampling for no gain
+const MIN_SKEW_DEGREES: f32 = 0.2;
+
+/// Long edge of the thumbnail skew is estimated on
+const SKEW_SAMPLE_EDGE: u32 = 1000;
+
+/// Luma below which a pixel counts as ink
+const INK_THRESHOLD: u8 = 128;
+
+/// Preprocessor backed by the `image` crate
+#[derive(Debug, Default)]
+pub struct ImagePreprocessor;
+
+impl ImagePreprocessor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ImagePreprocessPort for ImagePreprocessor {
+    async fn preprocess(
+        &self,
+        data: Bytes,
+        format: DocumentFormat,
+        options: &ImagePreprocessing,
+    ) -> ApplicationResult<Vec<u8>> {
+        let options = options.clone();
+        // Decoding and resampling are CPU-bound
+        tokio::task::spawn_blocking(move || process(&data, format, &options))
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Image preprocessing task failed: {}", e)))?
+    }
+}
+
+fn process(data: &[u8], format: DocumentFormat, options: &ImagePreprocessing) -> ApplicationResult<Vec<u8>> {
+    let input_format = match format {
+        DocumentFormat::Jpeg => ImageFormat::Jpeg,
+        DocumentFormat::Png => ImageFormat::Png,
+        DocumentFormat::Tiff => ImageFormat::Tiff,
+        _ => return Ok(data.to_vec()),
+    };
+    let mut image = image::load_from_memory_with_format(data, input_format).map_err(|e| {
+        ApplicationError::Domain(DomainError::InvalidDocumentFormat(format!("Failed to decode image: {}", e)))
+    })?;
+
+    if options.grayscale {
+        image = DynamicImage::ImageLuma8(image.to_luma8());
+    }
+
+    if let Some(dpi) = options.max_dpi {
+        let max_edge = (dpi as f32 * PAGE_LONG_EDGE_INCHES).round() as u32;
+        if image.width().max(image.height()) > max_edge {
+            debug!("Downscaling {}x{} image to fit {}px", image.width(), image.height(), max_edge);
+            image = image.resize(max_edge, max_edge, FilterType::Triangle);
+        }
+    }
+
+    if options.deskew {
+        let skew = estimate_skew(&image.to_luma8());
+        if skew.abs() >= MIN_SKEW_DEGREES {
+            debug!("Correcting {:.2}° skew", skew);
+            image = match image {
+                DynamicImage::ImageLuma8(gray) => DynamicImage::ImageLuma8(rotate(&gray, skew)),
+                DynamicImage::ImageRgba8(rgba) => DynamicImage::ImageRgba8(rotate(&rgba, skew)),
+                other => DynamicImage::ImageRgb8(rotate(&other.to_rgb8(), skew)),
+            };
+        }
+    }
+
+    let output_format = match (options.jpeg_quality, input_format) {
+        (Some(quality), _) => ImageOutputFormat::Jpeg(quality),
+        (None, ImageFormat::Jpeg) => ImageOutputFormat::Jpeg(DEFAULT_JPEG_QUALITY),
+        (None, other) => other.into(),
+    };
+    if matches!(output_format, ImageOutputFormat::Jpeg(_)) {
+        // JPEG has no alpha channel and only 8-bit samples
+        image = match image {
+            DynamicImage::ImageLuma8(_) | DynamicImage::ImageRgb8(_) => image,
+            other if options.grayscale => DynamicImage::ImageLuma8(other.to_luma8()),
+            other => DynamicImage::ImageRgb8(other.to_rgb8()),
+        };
+    }
+
+    let mut output = Cursor::new(Vec::new());
+    image
+        .write_to(&mut output, output_format)
+        .map_err(|e| ApplicationError::Internal(format!("Failed to encode image: {}", e)))?;
+    Ok(output.into_inner())
+}
+
+/// Skew of text lines in degrees (positive when lines fall to the right),
+/// found by maximizing the sharpness of the horizontal ink projection
+fn estimate_skew(image: &GrayImage) -> f32 {
+    let sample = if image.width().max(image.height()) > SKEW_SAMPLE_EDGE {
+        DynamicImage::ImageLuma8(image.clone())
+            .resize(SKEW_SAMPLE_EDGE, SKEW_SAMPLE_EDGE, FilterType::Triangle)
+            .to_luma8()
+    } else {
+        image.clone()
+    };
+
+    let ink: Vec<(f32, f32)> = sample
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel.0[0] < INK_THRESHOLD)
+        .map(|(x, y, _)| (x as f32, y as f32))
+        .collect();
+    if ink.is_empty() {
+        return 0.0;
+    }
+
+    let margin = (sample.width() as f32 * MAX_SKEW_DEGREES.to_radians().tan()).ceil();
+    let bins = sample.height() as usize + 2 * margin as usize + 1;
+    let score = |degrees: f32| {
+        let slope = degrees.to_radians().tan();
+        let mut profile = vec![0u64; bins];
+        for (x, y) in &ink {
+            let row = (y - x * slope + margin).round() as usize;
+            profile[row.min(bins - 1)] += 1;
+        }
+        profile.iter().map(|count| count * count).sum::<u64>()
+    };
+
+    let steps = (MAX_SKEW_DEGREES / SKEW_STEP_DEGREES) as i32;
+    let mut best = (0.0, score(0.0));
+    for step in -steps..=steps {
+        let degrees = step as f32 * SKEW_STEP_DEGREES;
+        let candidate = score(degrees);
+        if candidate > best.1 {
+            best = (degrees, candidate);
+        }
+    }
+    best.0
+}
+
+/// Rotate by `-degrees` about the centre, keeping the size and filling with white
+fn rotate<P>(image: &ImageBuffer<P, Vec<u8>>, degrees: f32) -> ImageBuffer<P, Vec<u8>>
+where
+    P: Pixel<Subpixel = u8>,
+{
+    let (width, height) = image.dimensions();
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (cx, cy) = (width as f32 / 2.0, height as f32 / 2.0);
+    let channels = P::CHANNEL_COUNT as usize;
+
+    let sample = |x: i64, y: i64, channel: usize| -> f32 {
+        if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+            255.0
+        } else {
+            image.get_pixel(x as u32, y as u32).channels()[channel] as f32
+        }
+    };
+
+    ImageBuffer::from_fn(width, height, |x, y| {
+        // Source position of this output pixel, bilinearly interpolated
+        let dx = x as f32 + 0.5 - cx;
+        let dy = y as f32 + 0.5 - cy;
+        let sx = cx + dx * cos - dy * sin - 0.5;
+        let sy = cy + dx * sin + dy * cos - 0.5;
+        let (x0, y0) = (sx.floor() as i64, sy.floor() as i64);
+        let (fx, fy) = (sx - sx.floor(), sy - sy.floor());
+
+        let mut values = [0u8; 4];
+        for (channel, value) in values.iter_mut().enumerate().take(channels) {
+            let top = sample(x0, y0, channel) * (1.0 - fx) + sample(x0 + 1, y0, channel) * fx;
+            let bottom = sample(x0, y0 + 1, channel) * (1.0 - fx) + sample(x0 + 1, y0 + 1, channel) * fx;
+            *value = (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, 255.0) as u8;
+        }
+        *P::from_slice(&values[..channels])
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Luma, Rgb, RgbImage};
+
+    /// White page with dark "text lines" falling `degrees` to the right
+    fn skewed_page(degrees: f32) -> RgbImage {
+        let slope = degrees.to_radians().tan();
+        let mut page = RgbImage::from_pixel(600, 400, Rgb([255, 255, 255]));
+        for line in 0..8 {
+            let baseline = 60.0 + line as f32 * 35.0;
+            for x in 50..550 {
+                let y = (baseline + (x as f32 - 300.0) * slope).round() as u32;
+                for thickness in 0..4 {
+                    page.put_pixel(x, y + thickness, Rgb([20, 20, 20]));
+                }
+            }
+        }
+        page
+    }
+
+    fn encode(image: DynamicImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Cursor::new(Vec::new());
+        image.write_to(&mut bytes, format).unwrap();
+        bytes.into_inner()
+    }
+
+    #[test]
+    fn test_estimate_skew() {
+        let page = DynamicImage::ImageRgb8(skewed_page(3.0)).to_luma8();
+        assert!((estimate_skew(&page) - 3.0).abs() <= SKEW_STEP_DEGREES);
+
+        let straight = DynamicImage::ImageRgb8(skewed_page(0.0)).to_luma8();
+        assert_eq!(estimate_skew(&straight), 0.0);
+        assert_eq!(estimate_skew(&GrayImage::from_pixel(10, 10, Luma([255]))), 0.0);
+    }
+
+    #[test]
+    fn test_deskew_straightens_lines() {
+        let png = encode(DynamicImage::ImageRgb8(skewed_page(-4.0)), ImageFormat::Png);
+        let options = ImagePreprocessing {
+            deskew: true,
+            ..Default::default()
+        };
+        let output = process(&png, DocumentFormat::Png, &options).unwrap();
+
+        assert_eq!(image::guess_format(&output).unwrap(), ImageFormat::Png);
+        let straightened = image::load_from_memory(&output).unwrap().to_luma8();
+        assert!(estimate_skew(&straightened).abs() <= SKEW_STEP_DEGREES);
+    }
+
+    #[test]
+    fn test_grayscale_downscale_and_reencode() {
+        let photo = RgbImage::from_pixel(2000, 1000, Rgb([200, 120, 40]));
+        let png = encode(DynamicImage::ImageRgb8(photo), ImageFormat::Png);
+        let options = ImagePreprocessing {
+            grayscale: true,
+            max_dpi: Some(ImagePreprocessing::MIN_DPI),
+            jpeg_quality: Some(60),
+            ..Default::default()
+        };
+        let output = process(&png, DocumentFormat::Png, &options).unwrap();
+
+        assert_eq!(image::guess_format(&output).unwrap(), ImageFormat::Jpeg);
+        let decoded = image::load_from_memory(&output).unwrap();
+        assert_eq!(decoded.color(), image::ColorType::L8);
+        // 72 dpi across an A4 long edge
+        assert_eq!((decoded.width(), decoded.height()), (842, 421));
+
+        // Formats the decoder does not handle pass through untouched
+        let pdf = b"%PDF-1.7 not an image".to_vec();
+        assert_eq!(process(&pdf, DocumentFormat::Pdf, &options).unwrap(), pdf);
+    }
+}
@@ -0,0 +1,615 @@
+/// Email (IMAP) attachment ingestion
+///
+/// Checks a mailbox on a schedule and analyzes the PDF and image attachments
+/// of every unseen message, the usual shape of an invoice-intake address.
+/// Each attachment is recorded in the ingest ledger once submitted, so a
+/// message that fails halfway resumes where it stopped, and the message is
+/// flagged `\Seen` once all of its attachments are in. Results are then
+/// polled to completion, which stores them and publishes the usual lifecycle
+/// events (with a result summary) for downstream systems. The client speaks
+/// just the IMAP4rev1 commands it needs, over implicit TLS.
+
+use base64::{Engine as _, engine::general_purpose};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tracing::{debug, error, info, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::IngestLedgerPort;
+use crate::application::services::DocumentIntelligenceService;
+use crate::domain::{AnalyzeDocumentRequest, AnalyzeOptions, DocumentMetadata, DocumentSource, ModelType, TenantId};
+use crate::infrastructure::config::ImapIngestConfig;
+use crate::infrastructure::tasks::TaskSupervisor;
+
+/// Limit on connecting and on each command's reply
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// An attachment whose result takes longer than this is given up on
+const RESULT_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+
+/// Largest message the client will download
+const MAX_MESSAGE_BYTES: usize = 64 * 1024 * 1024;
+
+/// Multipart nesting deeper than this is not searched for attachments
+const MAX_MIME_DEPTH: usize = 8;
+
+/// File extensions analyzed when the attachment's content type is generic
+const DOCUMENT_EXTENSIONS: &[&str] = &[".pdf", ".jpg", ".jpeg", ".png", ".tif", ".tiff", ".bmp", ".heif"];
+
+/// Submits the attachments of unseen messages in one mailbox
+pub struct ImapIngestor {
+    service: Arc<DocumentIntelligenceService>,
+    ledger: Arc<dyn IngestLedgerPort>,
+    host: String,
+    port: u16,
+    tls: bool,
+    username: String,
+    password: String,
+    mailbox: String,
+    model: ModelType,
+    tenant: TenantId,
+    interval: Duration,
+    result_poll: Duration,
+}
+
+impl ImapIngestor {
+    /// Ingestor for the configured mailbox, or `None` when no server is configured
+    pub fn from_config(
+        service: Arc<DocumentIntelligenceService>,
+        ledger: Arc<dyn IngestLedgerPort>,
+        config: &ImapIngestConfig,
+    ) -> ApplicationResult<Option<Self>> {
+        let Some(host) = &config.host else {
+            return Ok(None);
+        };
+        if config.username.is_empty() {
+            return Err(ApplicationError::Configuration(
+                "IMAP_USERNAME is required with IMAP_HOST".to_string(),
+            ));
+        }
+        Ok(Some(Self {
+            service,
+            ledger,
+            host: host.clone(),
+            port: config.port,
+            tls: config.tls,
+            username: config.username.clone(),
+            password: config.password.expose().to_string(),
+            mailbox: config.mailbox.clone(),
+            model: config.model,
+            tenant: config.tenant.clone(),
+            interval: Duration::from_secs(config.interval_secs),
+            result_poll: Duration::from_millis(config.result_poll_ms),
+        }))
+    }
+
+    /// Ledger key for this mailbox
+    fn source(&self) -> String {
+        format!("imap://{}@{}/{}", self.username, self.host, self.mailbox)
+    }
+
+    /// Submit the attachments of every unseen message and wait for their
+    /// results; returns how many attachments were submitted
+    pub async fn poll(&self) -> ApplicationResult<usize> {
+        let mut session = ImapSession::connect(&self.host, self.port, self.tls).await?;
+        session
+            .command(&format!("LOGIN {} {}", quote(&self.username), quote(&self.password)))
+            .await?;
+        let uid_validity = session.select(&self.mailbox).await?;
+
+        let mut operation_ids = Vec::new();
+        for uid in session.search_unseen().await? {
+            match self.ingest_message(&mut session, uid_validity, uid).await {
+                Ok(submitted) => operation_ids.extend(submitted),
+                Err(e) => warn!("Failed to ingest message {} from {}: {}", uid, self.source(), e),
+            }
+        }
+        // Results can take a while; don't hold the connection open for them
+        if let Err(e) = session.command("LOGOUT").await {
+            debug!("IMAP logout failed: {}", e);
+        }
+
+        for operation_id in &operation_ids {
+            match self
+                .service
+                .wait_for_result(&self.tenant, operation_id, self.result_poll, RESULT_TIMEOUT)
+                .await
+            {
+                Ok(_) => info!("Emailed document analyzed as operation {}", operation_id),
+                Err(e) => warn!("Emailed document in operation {} was not analyzed: {}", operation_id, e),
+            }
+        }
+        Ok(operation_ids.len())
+    }
+
+    /// Submit a message's attachments, flagging it seen once all are in;
+    /// returns the operations started
+    async fn ingest_message(&self, session: &mut ImapSession, uid_validity: u32, uid: u32) -> ApplicationResult<Vec<String>> {
+        let source = self.source();
+        let message = session.fetch(uid).await?;
+        let attachments = mail_attachments(&message);
+        if attachments.is_empty() {
+            info!("Message {} in {} has no documents to analyze", uid, self.mailbox);
+        }
+
+        let mut operation_ids = Vec::new();
+        let mut complete = true;
+        for (index, attachment) in attachments.into_iter().enumerate() {
+            let item = format!("{}:{}/{}", uid_validity, uid, index);
+            if self.ledger.ingested_version(&source, &item).await?.is_some() {
+                continue;
+            }
+            let filename = attachment.filename.clone();
+            let request = AnalyzeDocumentRequest {
+                source: DocumentSource::Bytes(attachment.data.into()),
+                model_type: self.model,
+                options: AnalyzeOptions::default(),
+                metadata: Some(DocumentMetadata::new(&filename, &attachment.content_type)),
+                tenant_id: self.tenant.clone(),
+            };
+            match self.service.analyze_document(request).await {
+                Ok(operation) => {
+                    self.ledger
+                        .record_ingested(&source, &item, &filename, Some(&operation.operation_id))
+                        .await?;
+                    info!("Attachment {} of message {} submitted as operation {}", filename, uid, operation.operation_id);
+                    operation_ids.push(operation.operation_id);
+                }
+                Err(e) if e.is_transient() => {
+                    warn!("Failed to submit attachment {} of message {}: {}", filename, uid, e);
+                    complete = false;
+                }
+                Err(e) => {
+                    warn!("Attachment {} of message {} was rejected: {}", filename, uid, e);
+                    self.ledger.record_ingested(&source, &item, &filename, None).await?;
+                }
+            }
+        }
+
+        if complete {
+            session.mark_seen(uid).await?;
+        }
+        Ok(operation_ids)
+    }
+}
+
+/// Poll `ingestor` until shutdown
+pub fn spawn_imap_ingest(supervisor: &TaskSupervisor, ingestor: ImapIngestor) {
+    info!(
+        "Ingesting email attachments from {} every {:?} with {}",
+        ingestor.source(),
+        ingestor.interval,
+        ingestor.model.as_str()
+    );
+    let ingestor = Arc::new(ingestor);
+    supervisor.spawn("imap-ingest", move |shutdown| {
+        let ingestor = ingestor.clone();
+        async move {
+            loop {
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    polled = ingestor.poll() => {
+                        if let Err(e) = polled {
+                            error!("Email ingestion failed: {}", e);
+                        }
+                    }
+                }
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = tokio::time::sleep(ingestor.interval) => {}
+                }
+            }
+        }
+    });
+}
+
+trait ImapStream: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> ImapStream for T {}
+
+/// One server response line, with the literals it carried
+struct ImapResponse {
+    line: String,
+    literals: Vec<Vec<u8>>,
+}
+
+/// Connection to an IMAP server
+struct ImapSession {
+    stream: BufReader<Box<dyn ImapStream>>,
+    tag: u32,
+}
+
+impl ImapSession {
+    async fn connect(host: &str, port: u16, tls: bool) -> ApplicationResult<Self> {
+        let connect = async {
+            let tcp = TcpStream::connect((host, port)).await?;
+            let stream: Box<dyn ImapStream> = if tls {
+                let connector = tokio_native_tls::native_tls::TlsConnector::new().map_err(std::io::Error::other)?;
+                let stream = tokio_native_tls::TlsConnector::from(connector)
+                    .connect(host, tcp)
+                    .await
+                    .map_err(std::io::Error::other)?;
+                Box::new(stream)
+            } else {
+                Box::new(tcp)
+            };
+            Ok::<_, std::io::Error>(stream)
+        };
+        let stream = tokio::time::timeout(COMMAND_TIMEOUT, connect)
+            .await
+            .map_err(|_| ApplicationError::Internal(format!("Timed out connecting to {}:{}", host, port)))?
+            .map_err(|e| ApplicationError::Internal(format!("Failed to connect to {}:{}: {}", host, port, e)))?;
+
+        let mut session = Self {
+            stream: BufReader::new(stream),
+            tag: 0,
+        };
+        let greeting = tokio::time::timeout(COMMAND_TIMEOUT, session.read_response())
+            .await
+            .map_err(|_| ApplicationError::Internal("Timed out waiting for the IMAP greeting".to_string()))??;
+        if !greeting.line.starts_with("* OK") && !greeting.line.starts_with("* PREAUTH") {
+            return Err(ApplicationError::Internal(format!("IMAP server refused the connection: {}", greeting.line)));
+        }
+        Ok(session)
+    }
+
+    /// Run a command, returning its untagged responses
+    async fn command(&mut self, command: &str) -> ApplicationResult<Vec<ImapResponse>> {
+        // The verb alone is used in errors, keeping credentials out of logs
+        let verb = command.split(' ').take_while(|word| !word.starts_with('"')).collect::<Vec<_>>().join(" ");
+        tokio::time::timeout(COMMAND_TIMEOUT, self.exchange(command, &verb))
+            .await
+            .map_err(|_| ApplicationError::Internal(format!("IMAP {} timed out", verb)))?
+    }
+
+    async fn exchange(&mut self, command: &str, verb: &str) -> ApplicationResult<Vec<ImapResponse>> {
+        self.tag += 1;
+        let tag = format!("A{:04}", self.tag);
+        let stream = self.stream.get_mut();
+        let sent = async {
+            stream.write_all(format!("{} {}\r\n", tag, command).as_bytes()).await?;
+            stream.flush().await
+        };
+        sent.await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to send IMAP {}: {}", verb, e)))?;
+
+        let mut untagged = Vec::new();
+        loop {
+            let response = self.read_response().await?;
+            let Some(status) = response.line.strip_prefix(&tag) else {
+                untagged.push(response);
+                continue;
+            };
+            let status = status.trim_start();
+            if status.starts_with("OK") {
+                return Ok(untagged);
+            }
+            return Err(ApplicationError::Internal(format!("IMAP {} failed: {}", verb, status)));
+        }
+    }
+
+    /// Read one response line, including any literals inside it
+    async fn read_response(&mut self) -> ApplicationResult<ImapResponse> {
+        let mut response = ImapResponse {
+            line: String::new(),
+            literals: Vec::new(),
+        };
+        loop {
+            let mut chunk = Vec::new();
+            let read = self
+                .stream
+                .read_until(b'\n', &mut chunk)
+                .await
+                .map_err(|e| ApplicationError::Internal(format!("Failed to read from the IMAP server: {}", e)))?;
+            if read == 0 {
+                return Err(ApplicationError::Internal("IMAP server closed the connection".to_string()));
+            }
+            let text = String::from_utf8_lossy(&chunk);
+            let text = text.trim_end_matches(['\r', '\n']);
+            response.line.push_str(text);
+
+            let Some(size) = literal_size(text) else {
+                return Ok(response);
+            };
+            if size > MAX_MESSAGE_BYTES {
+                return Err(ApplicationError::Internal(format!(
+                    "IMAP response of {} bytes exceeds the {} byte limit",
+                    size, MAX_MESSAGE_BYTES
+                )));
+            }
+            let mut literal = vec![0; size];
+            self.stream
+                .read_exact(&mut literal)
+                .await
+                .map_err(|e| ApplicationError::Internal(format!("Failed to read from the IMAP server: {}", e)))?;
+            response.literals.push(literal);
+        }
+    }
+
+    /// Open `mailbox`, returning its UIDVALIDITY
+    async fn select(&mut self, mailbox: &str) -> ApplicationResult<u32> {
+        let responses = self.command(&format!("SELECT {}", quote(mailbox))).await?;
+        Ok(responses
+            .iter()
+            .find_map(|response| {
+                let start = response.line.find("[UIDVALIDITY ")? + "[UIDVALIDITY ".len();
+                let rest = &response.line[start..];
+                rest[..rest.find(']')?].trim().parse().ok()
+            })
+            .unwrap_or_default())
+    }
+
+    async fn search_unseen(&mut self) -> ApplicationResult<Vec<u32>> {
+        let responses = self.command("UID SEARCH UNSEEN").await?;
+        Ok(responses
+            .iter()
+            .filter_map(|response| response.line.strip_prefix("* SEARCH"))
+            .flat_map(|uids| uids.split_whitespace().filter_map(|uid| uid.parse().ok()))
+            .collect())
+    }
+
+    /// Raw RFC 5322 message, without flagging it seen
+    async fn fetch(&mut self, uid: u32) -> ApplicationResult<Vec<u8>> {
+        let responses = self.command(&format!("UID FETCH {} BODY.PEEK[]", uid)).await?;
+        responses
+            .into_iter()
+            .find(|response| response.line.contains("FETCH") && !response.literals.is_empty())
+            .and_then(|response| response.literals.into_iter().next())
+            .ok_or_else(|| ApplicationError::Internal(format!("IMAP server returned no body for message {}", uid)))
+    }
+
+    async fn mark_seen(&mut self, uid: u32) -> ApplicationResult<()> {
+        self.command(&format!("UID STORE {} +FLAGS.SILENT (\\Seen)", uid)).await?;
+        Ok(())
+    }
+}
+
+/// Size of the literal announced at the end of a response line, e.g. `{1024}`
+fn literal_size(line: &str) -> Option<usize> {
+    let open = line.strip_suffix('}')?.rfind('{')?;
+    line[open + 1..line.len() - 1].parse().ok()
+}
+
+/// IMAP quoted string
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// A document attached to an email
+#[derive(Debug, PartialEq)]
+struct MailAttachment {
+    filename: String,
+    content_type: String,
+    data: Vec<u8>,
+}
+
+/// PDF and image attachments of a raw message, in order
+fn mail_attachments(message: &[u8]) -> Vec<MailAttachment> {
+    let mut attachments = Vec::new();
+    collect_attachments(message, 0, &mut attachments);
+    attachments
+}
+
+fn collect_attachments(part: &[u8], depth: usize, attachments: &mut Vec<MailAttachment>) {
+    let (headers, body) = split_headers(part);
+    let content_type = header(&headers, "content-type").unwrap_or("text/plain");
+    let mime_type = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+
+    if mime_type.starts_with("multipart/") {
+        if let (Some(boundary), true) = (header_param(content_type, "boundary"), depth < MAX_MIME_DEPTH) {
+            for child in multipart_parts(body, &boundary) {
+                collect_attachments(child, depth + 1, attachments);
+            }
+        }
+        return;
+    }
+
+    let filename = header(&headers, "content-disposition")
+        .and_then(|disposition| header_param(disposition, "filename"))
+        .or_else(|| header_param(content_type, "name"));
+    let Some(filename) = filename else {
+        return;
+    };
+    let lowercase = filename.to_ascii_lowercase();
+    let is_document = mime_type == "application/pdf"
+        || mime_type.starts_with("image/")
+        || DOCUMENT_EXTENSIONS.iter().any(|extension| lowercase.ends_with(extension));
+    if !is_document {
+        return;
+    }
+
+    let encoding = header(&headers, "content-transfer-encoding").unwrap_or_default().trim().to_ascii_lowercase();
+    let data = match encoding.as_str() {
+        "base64" => {
+            let encoded: Vec<u8> = body.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+            match general_purpose::STANDARD.decode(encoded) {
+                Ok(data) => data,
+                Err(e) => {
+                    warn!("Skipping attachment {} with invalid base64: {}", filename, e);
+                    return;
+                }
+            }
+        }
+        "quoted-printable" => decode_quoted_printable(body),
+        _ => body.to_vec(),
+    };
+    attachments.push(MailAttachment {
+        filename,
+        content_type: mime_type,
+        data,
+    });
+}
+
+/// Unfolded headers (lowercased names) and the body that follows them
+fn split_headers(part: &[u8]) -> (Vec<(String, String)>, &[u8]) {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    let mut offset = 0;
+    for line in part.split_inclusive(|b| *b == b'\n') {
+        offset += line.len();
+        let text = String::from_utf8_lossy(line);
+        let text = text.trim_end_matches(['\r', '\n']);
+        if text.is_empty() {
+            return (headers, &part[offset..]);
+        }
+        if text.starts_with([' ', '\t']) {
+            if let Some((_, value)) = headers.last_mut() {
+                value.push(' ');
+                value.push_str(text.trim());
+            }
+        } else if let Some((name, value)) = text.split_once(':') {
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+    }
+    (headers, &[])
+}
+
+fn header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers
+        .iter()
+        .find(|(header, _)| header == name)
+        .map(|(_, value)| value.as_str())
+}
+
+/// Parameter of a structured header, e.g. `boundary` in `multipart/mixed; boundary="x"`
+///
+/// RFC 2231 `name*=charset''value` parameters are percent-decoded.
+fn header_param(value: &str, name: &str) -> Option<String> {
+    value.split(';').skip(1).find_map(|param| {
+        let (key, raw) = param.split_once('=')?;
+        let key = key.trim().to_ascii_lowercase();
+        let raw = raw.trim().trim_matches('"');
+        if key == name {
+            Some(raw.to_string())
+        } else if key == format!("{}*", name) {
+            let encoded = raw.splitn(3, '\'').nth(2).unwrap_or(raw);
+            url::form_urlencoded::parse(format!("v={}", encoded.replace('+', "%2B")).as_bytes())
+                .next()
+                .map(|(_, decoded)| decoded.into_owned())
+        } else {
+            None
+        }
+    })
+    .filter(|param| !param.is_empty())
+}
+
+/// Bodies of the parts of a multipart body
+fn multipart_parts<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for line in body.split_inclusive(|b| *b == b'\n') {
+        let text = String::from_utf8_lossy(line);
+        if let Some(rest) = text.trim_end().strip_prefix(&delimiter) {
+            if rest.is_empty() || rest == "--" {
+                if let Some(start) = start {
+                    // The line break before a delimiter belongs to the delimiter
+                    let part = &body[start..offset];
+                    let part = part.strip_suffix(b"\n").unwrap_or(part);
+                    parts.push(part.strip_suffix(b"\r").unwrap_or(part));
+                }
+                if rest == "--" {
+                    break;
+                }
+                start = Some(offset + line.len());
+            }
+        }
+        offset += line.len();
+    }
+    parts
+}
+
+fn decode_quoted_printable(body: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(body.len());
+    let mut i = 0;
+    while i < body.len() {
+        if body[i] != b'=' {
+            decoded.push(body[i]);
+            i += 1;
+            continue;
+        }
+        match body.get(i + 1..i + 3) {
+            // Soft line break
+            Some([b'\r', b'\n']) => i += 3,
+            Some([b'\n', _]) => i += 2,
+            Some(hex) => match u8::from_str_radix(&String::from_utf8_lossy(hex), 16) {
+                Ok(byte) => {
+                    decoded.push(byte);
+                    i += 3;
+                }
+                Err(_) => {
+                    decoded.push(b'=');
+                    i += 1;
+                }
+            },
+            None => i = body.len(),
+        }
+    }
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mail_attachments() {
+        let message = concat!(
+            "From: Supplier <billing@example.com>\r\n",
+            "Subject: Invoice\r\n",
+            "Content-Type: multipart/mixed;\r\n",
+            "\tboundary=\"outer\"\r\n",
+            "\r\n",
+            "--outer\r\n",
+            "Content-Type: multipart/alternative; boundary=inner\r\n",
+            "\r\n",
+            "--inner\r\n",
+            "Content-Type: text/plain\r\n",
+            "\r\n",
+            "Please find attached.\r\n",
+            "--inner--\r\n",
+            "--outer\r\n",
+            "Content-Type: application/pdf; name=\"invoice.pdf\"\r\n",
+            "Content-Disposition: attachment; filename=\"invoice.pdf\"\r\n",
+            "Content-Transfer-Encoding: base64\r\n",
+            "\r\n",
+            "JVBERi0x\r\n",
+            "LjcK\r\n",
+            "--outer\r\n",
+            "Content-Type: application/octet-stream\r\n",
+            "Content-Disposition: attachment; filename*=UTF-8''scan%20%C3%A9.PNG\r\n",
+            "Content-Transfer-Encoding: quoted-printable\r\n",
+            "\r\n",
+            "=89PNG=\r\n",
+            "!\r\n",
+            "--outer\r\n",
+            "Content-Type: text/csv; name=\"lines.csv\"\r\n",
+            "\r\n",
+            "a,b\r\n",
+            "--outer--\r\n",
+        );
+        let attachments = mail_attachments(message.as_bytes());
+        assert_eq!(attachments.len(), 2);
+        assert_eq!(
+            attachments[0],
+            MailAttachment {
+                filename: "invoice.pdf".to_string(),
+                content_type: "application/pdf".to_string(),
+                data: b"%PDF-1.7\n".to_vec(),
+            }
+        );
+        assert_eq!(attachments[1].filename, "scan \u{e9}.PNG");
+        assert_eq!(attachments[1].data, b"\x89PNG!");
+
+        assert!(mail_attachments(b"Subject: hi\r\n\r\nNo attachments").is_empty());
+    }
+
+    #[test]
+    fn test_imap_syntax() {
+        assert_eq!(literal_size("* 1 FETCH (UID 7 BODY[] {1024}"), Some(1024));
+        assert_eq!(literal_size("* 1 FETCH (FLAGS (\\Seen))"), None);
+        assert_eq!(quote("p\"a\\ss"), "\"p\\\"a\\\\ss\"");
+    }
+}
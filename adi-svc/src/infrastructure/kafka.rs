@@ -0,0 +1,101 @@
+/// Kafka lifecycle event publisher
+///
+/// Events are keyed by operation ID, so one operation's events land on one
+/// partition in order. Publishing only enqueues into the producer; delivery
+/// outcomes are logged and counted in `adi_events_published_total`.
+
+use async_trait::async_trait;
+use rdkafka::config::ClientConfig;
+use rdkafka::message::{Header, OwnedHeaders};
+use rdkafka::producer::{FutureProducer, FutureRecord};
+use tracing::{debug, error, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::EventPublisherPort;
+use crate::domain::LifecycleEvent;
+use crate::infrastructure::config::EventsConfig;
+use crate::infrastructure::events::EventFormat;
+use crate::infrastructure::metrics::metrics;
+
+/// Label for this transport in metrics
+const TRANSPORT: &str = "kafka";
+
+/// Publishes lifecycle events to a Kafka topic
+pub struct KafkaEventPublisher {
+    producer: FutureProducer,
+    topic: String,
+    format: EventFormat,
+}
+
+impl KafkaEventPublisher {
+    pub fn new(brokers: &str, topic: impl Into<String>, format: EventFormat) -> ApplicationResult<Self> {
+        let producer = ClientConfig::new()
+            .set("bootstrap.servers", brokers)
+            .set("message.timeout.ms", "30000")
+            .set("enable.idempotence", "true")
+            .create()
+            .map_err(|e| ApplicationError::Configuration(format!("Failed to create Kafka producer: {}", e)))?;
+        Ok(Self {
+            producer,
+            topic: topic.into(),
+            format,
+        })
+    }
+
+    /// Publisher for the configured brokers, if Kafka publishing is enabled
+    pub fn from_config(config: &EventsConfig) -> ApplicationResult<Option<Self>> {
+        config
+            .kafka_brokers
+            .as_deref()
+            .map(|brokers| Self::new(brokers, config.kafka_topic.clone(), config.format))
+            .transpose()
+    }
+}
+
+#[async_trait]
+impl EventPublisherPort for KafkaEventPublisher {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()> {
+        let payload = self.format.encode(event)?;
+        let headers = OwnedHeaders::new()
+            .insert(Header {
+                key: "event_type",
+                value: Some(event.event_type.as_str()),
+            })
+            .insert(Header {
+                key: "tenant_id",
+                value: Some(event.tenant_id.as_str()),
+            })
+            .insert(Header {
+                key: "content-type",
+                value: Some(self.format.content_type()),
+            });
+        let record = FutureRecord::to(&self.topic)
+            .key(&event.operation_id)
+            .payload(&payload)
+            .headers(headers);
+
+        let delivery = self.producer.send_result(record).map_err(|(e, _)| {
+            metrics().record_event_published(TRANSPORT, false);
+            ApplicationError::Internal(format!("Failed to enqueue Kafka event: {}", e))
+        })?;
+
+        let event_id = event.event_id.clone();
+        tokio::spawn(async move {
+            match delivery.await {
+                Ok(Ok((partition, offset))) => {
+                    metrics().record_event_published(TRANSPORT, true);
+                    debug!("Event {} delivered to partition {} at offset {}", event_id, partition, offset);
+                }
+                Ok(Err((e, _))) => {
+                    metrics().record_event_published(TRANSPORT, false);
+                    error!("Kafka did not accept event {}: {}", event_id, e);
+                }
+                Err(_) => {
+                    metrics().record_event_published(TRANSPORT, false);
+                    warn!("Kafka producer shut down before delivering event {}", event_id);
+                }
+            }
+        });
+        Ok(())
+    }
+}
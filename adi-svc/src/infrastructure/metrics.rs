@@ -0,0 +1,341 @@
+/// Prometheus metrics
+///
+/// Process-wide metrics registry, rendered in the Prometheus text
+/// exposition format by the REST `/metrics` endpoint.
+
+use prometheus::{Encoder, HistogramOpts, HistogramVec, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::application::ports::OperationMetricsPort;
+use crate::application::retention::RetentionReport;
+use crate::domain::{ModelType, OperationEventKind, TenantId};
+
+/// Service metrics
+pub struct Metrics {
+    registry: Registry,
+    pub retention_pruned: IntCounterVec,
+    pub db_pool_connections: IntGaugeVec,
+    pub db_pool_max_connections: IntGauge,
+    pub events_published: IntCounterVec,
+    pub search_indexed: IntCounterVec,
+    pub embedded_chunks: IntCounterVec,
+    pub azure_key_fallbacks: IntCounterVec,
+    pub tracker_cache: IntCounterVec,
+    pub submit_duration: HistogramVec,
+    pub completion_duration: HistogramVec,
+    pub operations_completed: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let retention_pruned = IntCounterVec::new(
+            Opts::new(
+                "adi_retention_pruned_total",
+                "Rows and files removed by the retention task",
+            ),
+            &["kind"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(retention_pruned.clone()))
+            .expect("metric registered once");
+
+        let db_pool_connections = IntGaugeVec::new(
+            Opts::new(
+                "adi_db_pool_connections",
+                "Database pool connections by state",
+            ),
+            &["state"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(db_pool_connections.clone()))
+            .expect("metric registered once");
+
+        let db_pool_max_connections = IntGauge::new(
+            "adi_db_pool_max_connections",
+            "Configured database pool size",
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(db_pool_max_connections.clone()))
+            .expect("metric registered once");
+
+        let events_published = IntCounterVec::new(
+            Opts::new(
+                "adi_events_published_total",
+                "Lifecycle events handed to a message broker, by delivery outcome",
+            ),
+            &["transport", "outcome"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(events_published.clone()))
+            .expect("metric registered once");
+
+        let search_indexed = IntCounterVec::new(
+            Opts::new(
+                "adi_search_documents_indexed_total",
+                "Succeeded operations pushed to a search index, by outcome",
+            ),
+            &["backend", "outcome"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(search_indexed.clone()))
+            .expect("metric registered once");
+
+        let embedded_chunks = IntCounterVec::new(
+            Opts::new(
+                "adi_embedded_chunks_total",
+                "Content chunks sent to the embeddings provider, by outcome",
+            ),
+            &["outcome"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(embedded_chunks.clone()))
+            .expect("metric registered once");
+
+        let azure_key_fallbacks = IntCounterVec::new(
+            Opts::new(
+                "adi_azure_key_fallbacks_total",
+                "Switches to an Azure resource's other key after Azure rejected the one in use, by resource and rejected key",
+            ),
+            &["resource", "rejected"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(azure_key_fallbacks.clone()))
+            .expect("metric registered once");
+
+        let tracker_cache = IntCounterVec::new(
+            Opts::new(
+                "adi_tracker_cache_requests_total",
+                "Tracker cache lookups, by cached kind and outcome",
+            ),
+            &["cache", "outcome"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(tracker_cache.clone()))
+            .expect("metric registered once");
+
+        let submit_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "adi_analysis_submit_duration_seconds",
+                "Time taken to submit a document for analysis, by model, tenant and outcome",
+            )
+            .buckets(vec![0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0]),
+            &["model", "tenant", "outcome"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(submit_duration.clone()))
+            .expect("metric registered once");
+
+        let completion_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "adi_analysis_duration_seconds",
+                "Time from submission until an operation was seen finished, by model, tenant and status",
+            )
+            .buckets(vec![1.0, 2.5, 5.0, 10.0, 20.0, 30.0, 60.0, 120.0, 300.0, 600.0, 1800.0]),
+            &["model", "tenant", "status"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(completion_duration.clone()))
+            .expect("metric registered once");
+
+        let operations_completed = IntCounterVec::new(
+            Opts::new(
+                "adi_operations_completed_total",
+                "Operations seen finished, by model, tenant and status",
+            ),
+            &["model", "tenant", "status"],
+        )
+        .expect("valid metric definition");
+        registry
+            .register(Box::new(operations_completed.clone()))
+            .expect("metric registered once");
+
+        Self {
+            registry,
+            retention_pruned,
+            db_pool_connections,
+            db_pool_max_connections,
+            events_published,
+            search_indexed,
+            embedded_chunks,
+            azure_key_fallbacks,
+            tracker_cache,
+            submit_duration,
+            completion_duration,
+            operations_completed,
+        }
+    }
+
+    /// Record the outcome of a retention pass
+    pub fn record_retention(&self, report: &RetentionReport) {
+        self.retention_pruned
+            .with_label_values(&["operations"])
+            .inc_by(report.operations_pruned);
+        self.retention_pruned
+            .with_label_values(&["results"])
+            .inc_by(report.results_pruned);
+        self.retention_pruned
+            .with_label_values(&["documents"])
+            .inc_by(report.documents_pruned);
+        self.retention_pruned
+            .with_label_values(&["deleted_operations"])
+            .inc_by(report.deleted_purged);
+    }
+
+    /// Record a sample of database pool utilization
+    pub fn record_db_pool(&self, size: u32, idle: usize, max: u32) {
+        let idle = idle as i64;
+        self.db_pool_connections
+            .with_label_values(&["idle"])
+            .set(idle);
+        self.db_pool_connections
+            .with_label_values(&["in_use"])
+            .set((size as i64 - idle).max(0));
+        self.db_pool_max_connections.set(max as i64);
+    }
+
+    /// Record whether a broker accepted a lifecycle event
+    pub fn record_event_published(&self, transport: &str, delivered: bool) {
+        let outcome = if delivered { "delivered" } else { "failed" };
+        self.events_published
+            .with_label_values(&[transport, outcome])
+            .inc();
+    }
+
+    /// Record whether a search index accepted a document
+    pub fn record_search_indexed(&self, backend: &str, indexed: bool) {
+        let outcome = if indexed { "indexed" } else { "failed" };
+        self.search_indexed
+            .with_label_values(&[backend, outcome])
+            .inc();
+    }
+
+    /// Record a batch of chunks sent to the embeddings provider
+    pub fn record_embedded_chunks(&self, chunks: usize, embedded: bool) {
+        let outcome = if embedded { "embedded" } else { "failed" };
+        self.embedded_chunks
+            .with_label_values(&[outcome])
+            .inc_by(chunks as u64);
+    }
+
+    /// Record a switch to the other key of an Azure resource; any increase means a key needs rotating
+    pub fn record_azure_key_fallback(&self, resource: &str, rejected: &str) {
+        self.azure_key_fallbacks
+            .with_label_values(&[resource, rejected])
+            .inc();
+    }
+
+    /// Record whether a tracker cache lookup was served from memory
+    pub fn record_tracker_cache(&self, cache: &str, hit: bool) {
+        let outcome = if hit { "hit" } else { "miss" };
+        self.tracker_cache
+            .with_label_values(&[cache, outcome])
+            .inc();
+    }
+
+    /// Record a submission for analysis and whether the backend accepted it
+    pub fn record_submit(&self, model: &str, tenant: &str, latency: Duration, accepted: bool) {
+        let outcome = if accepted { "accepted" } else { "rejected" };
+        self.submit_duration
+            .with_label_values(&[model, tenant, outcome])
+            .observe(latency.as_secs_f64());
+    }
+
+    /// Record an operation seen finished in `status`
+    pub fn record_completion(&self, model: &str, tenant: &str, status: &str, elapsed: Duration) {
+        self.completion_duration
+            .with_label_values(&[model, tenant, status])
+            .observe(elapsed.as_secs_f64());
+        self.operations_completed
+            .with_label_values(&[model, tenant, status])
+            .inc();
+    }
+
+    /// Render all metrics in the Prometheus text format
+    pub fn render(&self) -> String {
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&self.registry.gather(), &mut buffer) {
+            tracing::error!("Failed to encode metrics: {}", e);
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// Global metrics instance
+pub fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// Operation metrics recorded in the global registry
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PrometheusOperationMetrics;
+
+impl OperationMetricsPort for PrometheusOperationMetrics {
+    fn record_submit(&self, tenant: &TenantId, model: ModelType, latency: Duration, accepted: bool) {
+        metrics().record_submit(model.as_str(), tenant.as_str(), latency, accepted);
+    }
+
+    fn record_completion(&self, tenant: &TenantId, model: ModelType, outcome: OperationEventKind, elapsed: Duration) {
+        metrics().record_completion(model.as_str(), tenant.as_str(), outcome.as_str(), elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_retention() {
+        let report = RetentionReport {
+            operations_pruned: 2,
+            results_pruned: 1,
+            documents_pruned: 3,
+            deleted_purged: 1,
+        };
+        metrics().record_retention(&report);
+
+        let rendered = metrics().render();
+        assert!(rendered.contains("adi_retention_pruned_total{kind=\"documents\"}"));
+    }
+
+    #[test]
+    fn test_operation_metrics() {
+        let tenant = TenantId::new("metrics-unit").unwrap();
+        PrometheusOperationMetrics.record_submit(&tenant, ModelType::Receipt, Duration::from_millis(120), true);
+        PrometheusOperationMetrics.record_completion(&tenant, ModelType::Receipt, OperationEventKind::Failed, Duration::from_secs(4));
+
+        let rendered = metrics().render();
+        assert!(rendered.contains(
+            "adi_analysis_submit_duration_seconds_count{model=\"prebuilt-receipt\",outcome=\"accepted\",tenant=\"metrics-unit\"} 1"
+        ));
+        assert!(rendered.contains(
+            "adi_analysis_duration_seconds_bucket{model=\"prebuilt-receipt\",status=\"failed\",tenant=\"metrics-unit\",le=\"5\"} 1"
+        ));
+        assert!(rendered.contains(
+            "adi_operations_completed_total{model=\"prebuilt-receipt\",status=\"failed\",tenant=\"metrics-unit\"} 1"
+        ));
+    }
+
+    #[test]
+    fn test_record_db_pool() {
+        metrics().record_db_pool(7, 3, 10);
+
+        let rendered = metrics().render();
+        assert!(rendered.contains("adi_db_pool_connections{state=\"in_use\"} 4"));
+        assert!(rendered.contains("adi_db_pool_connections{state=\"idle\"} 3"));
+        assert!(rendered.contains("adi_db_pool_max_connections 10"));
+    }
+}
@@ -1,17 +1,126 @@
 /// Infrastructure layer - External service adapters
 /// 
 /// This layer contains implementations of ports that interact with
-/// external services like Azure AI Document Intelligence.
+/// external services like Azure AI Document Intelligence. Adapters that
+/// need PostgreSQL or Prometheus come with the `server` feature, and those
+/// with heavy dependencies (IMAP TLS, image and PDF codecs, Parquet) with
+/// their own features.
 
 pub mod azure;
+pub mod build_info;
+pub mod mock_azure;
+pub mod vcr;
 pub mod storage;
 pub mod tracker;
+pub mod tiered_tracker;
+#[cfg(feature = "server")]
 pub mod postgres_tracker;
+#[cfg(feature = "server")]
+pub mod cached_tracker;
 pub mod config;
+#[cfg(feature = "server")]
+pub mod logging;
+#[cfg(feature = "server")]
+pub mod metrics;
+#[cfg(feature = "server")]
+pub mod cleanup;
+pub mod workers;
+pub mod folder_watch;
+#[cfg(feature = "server")]
+pub mod blob_ingest;
+#[cfg(feature = "server")]
+pub mod training_export;
+#[cfg(all(feature = "server", feature = "analytics"))]
+pub mod analytics_export;
+#[cfg(feature = "imap")]
+pub mod imap_ingest;
+pub mod tasks;
+pub mod url_signing;
+pub mod encryption;
+pub mod clamav;
+#[cfg(feature = "images")]
+pub mod image_preprocess;
+#[cfg(feature = "pdf")]
+pub mod pdf_redaction;
+pub mod classification;
+pub mod webhooks;
+pub mod alerting;
+pub mod events;
+#[cfg(feature = "server")]
+pub mod azure_events;
+#[cfg(feature = "server")]
+pub mod azure_search;
+#[cfg(feature = "server")]
+pub mod elasticsearch;
+#[cfg(feature = "server")]
+pub mod azure_openai;
+#[cfg(feature = "kafka")]
+pub mod kafka;
+#[cfg(feature = "amqp")]
+pub mod amqp;
+#[cfg(feature = "aws")]
+pub mod aws;
+#[cfg(feature = "textract")]
+pub mod textract;
+#[cfg(feature = "sftp")]
+pub mod sftp;
 
 pub use azure::*;
+pub use build_info::*;
+pub use mock_azure::*;
+pub use vcr::*;
 pub use storage::*;
 pub use tracker::*;
+pub use tiered_tracker::*;
+#[cfg(feature = "server")]
 pub use postgres_tracker::*;
+#[cfg(feature = "server")]
+pub use cached_tracker::*;
 pub use config::*;
+#[cfg(feature = "server")]
+pub use logging::*;
+#[cfg(feature = "server")]
+pub use metrics::*;
+#[cfg(feature = "server")]
+pub use cleanup::*;
+pub use workers::*;
+pub use folder_watch::*;
+#[cfg(feature = "server")]
+pub use blob_ingest::*;
+#[cfg(feature = "server")]
+pub use training_export::*;
+#[cfg(all(feature = "server", feature = "analytics"))]
+pub use analytics_export::*;
+#[cfg(feature = "imap")]
+pub use imap_ingest::*;
+pub use tasks::*;
+pub use url_signing::*;
+pub use encryption::*;
+pub use clamav::*;
+#[cfg(feature = "images")]
+pub use image_preprocess::*;
+#[cfg(feature = "pdf")]
+pub use pdf_redaction::*;
+pub use classification::*;
+pub use webhooks::*;
+pub use alerting::*;
+pub use events::*;
+#[cfg(feature = "server")]
+pub use azure_events::*;
+#[cfg(feature = "server")]
+pub use azure_search::*;
+#[cfg(feature = "server")]
+pub use elasticsearch::*;
+#[cfg(feature = "server")]
+pub use azure_openai::*;
+#[cfg(feature = "kafka")]
+pub use kafka::*;
+#[cfg(feature = "amqp")]
+pub use amqp::*;
+#[cfg(feature = "aws")]
+pub use aws::*;
+#[cfg(feature = "textract")]
+pub use textract::*;
+#[cfg(feature = "sftp")]
+pub use sftp::*;
 
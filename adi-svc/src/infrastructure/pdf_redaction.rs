@@ -0,0 +1,1004 @@
+/// Redacted document rendering
+///
+/// Burns redaction boxes into a copy of an analyzed document, returned as a
+/// PDF. Images are blacked out pixel by pixel and wrapped in a one-page PDF.
+/// In PDFs, glyphs drawn under a box are removed from the page's content
+/// stream, covered pixels of JPEG and 8-bit Flate images are blacked out,
+/// annotations over a box are dropped and an opaque box is painted on top.
+/// Text inside form XObjects and images in other encodings are only covered
+/// by the painted box.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use image::{DynamicImage, ImageFormat, ImageOutputFormat, Luma, Rgb};
+use lopdf::content::{Content, Operation};
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Cursor;
+use tracing::{debug, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::RedactionRenderPort;
+use crate::domain::{DocumentFormat, DomainError, RedactionBox};
+
+/// Quality redacted images are re-encoded at
+const JPEG_QUALITY: u8 = 90;
+
+/// Glyph width assumed for fonts without width metrics, in thousandths of the font size
+const DEFAULT_GLYPH_WIDTH: f32 = 500.0;
+
+/// Height of a glyph's centre above the baseline, as a fraction of the font size
+const GLYPH_CENTRE_RISE: f32 = 0.35;
+
+/// Deepest page tree walked for inherited page attributes
+const MAX_PAGE_TREE_DEPTH: usize = 32;
+
+/// Renderer backed by `lopdf` and the `image` crate
+#[derive(Debug, Default)]
+pub struct DocumentRedactor;
+
+impl DocumentRedactor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl RedactionRenderPort for DocumentRedactor {
+    async fn render_redacted(
+        &self,
+        data: Bytes,
+        format: DocumentFormat,
+        boxes: &[RedactionBox],
+    ) -> ApplicationResult<Vec<u8>> {
+        let boxes = boxes.to_vec();
+        // Parsing and re-encoding are CPU-bound
+        tokio::task::spawn_blocking(move || match format {
+            DocumentFormat::Pdf => redact_pdf(&data, &boxes),
+            DocumentFormat::Jpeg | DocumentFormat::Png | DocumentFormat::Tiff => redact_image(&data, format, &boxes),
+            other => Err(DomainError::UnsupportedDocumentType(format!(
+                "{} documents cannot be redacted",
+                other.mime_type()
+            ))
+            .into()),
+        })
+        .await
+        .map_err(|e| ApplicationError::Internal(format!("Redaction task failed: {}", e)))?
+    }
+}
+
+fn pdf_error(e: lopdf::Error) -> ApplicationError {
+    ApplicationError::Internal(format!("Failed to write redacted PDF: {}", e))
+}
+
+fn save(doc: &mut Document) -> ApplicationResult<Vec<u8>> {
+    let mut output = Vec::new();
+    doc.save_to(&mut output)
+        .map_err(|e| ApplicationError::Internal(format!("Failed to write redacted PDF: {}", e)))?;
+    Ok(output)
+}
+
+/// Black out `boxes` on an image's first page and wrap it in a PDF
+fn redact_image(data: &[u8], format: DocumentFormat, boxes: &[RedactionBox]) -> ApplicationResult<Vec<u8>> {
+    let input_format = match format {
+        DocumentFormat::Jpeg => ImageFormat::Jpeg,
+        DocumentFormat::Png => ImageFormat::Png,
+        _ => ImageFormat::Tiff,
+    };
+    // Multi-page TIFFs decode to their first page
+    let image = image::load_from_memory_with_format(data, input_format).map_err(|e| {
+        ApplicationError::Domain(DomainError::InvalidDocumentFormat(format!("Failed to decode image: {}", e)))
+    })?;
+    let mut image = match image {
+        DynamicImage::ImageLuma8(_) => image,
+        other => DynamicImage::ImageRgb8(other.to_rgb8()),
+    };
+
+    let (width, height) = (image.width(), image.height());
+    for redaction in boxes.iter().filter(|redaction| redaction.page_number == 1) {
+        let left = (redaction.left * width as f32).floor() as u32;
+        let top = (redaction.top * height as f32).floor() as u32;
+        let right = ((redaction.right * width as f32).ceil() as u32).min(width);
+        let bottom = ((redaction.bottom * height as f32).ceil() as u32).min(height);
+        black_out(&mut image, left..right, top..bottom);
+    }
+
+    let color_space = if matches!(image, DynamicImage::ImageLuma8(_)) { "DeviceGray" } else { "DeviceRGB" };
+    image_pdf(encode_jpeg(&image)?, width, height, color_space)
+}
+
+fn black_out(image: &mut DynamicImage, columns: std::ops::Range<u32>, rows: std::ops::Range<u32>) {
+    for y in rows {
+        for x in columns.clone() {
+            match image {
+                DynamicImage::ImageLuma8(gray) => gray.put_pixel(x, y, Luma([0])),
+                DynamicImage::ImageRgb8(rgb) => rgb.put_pixel(x, y, Rgb([0, 0, 0])),
+                _ => {}
+            }
+        }
+    }
+}
+
+fn encode_jpeg(image: &DynamicImage) -> ApplicationResult<Vec<u8>> {
+    let mut output = Cursor::new(Vec::new());
+    image
+        .write_to(&mut output, ImageOutputFormat::Jpeg(JPEG_QUALITY))
+        .map_err(|e| ApplicationError::Internal(format!("Failed to encode image: {}", e)))?;
+    Ok(output.into_inner())
+}
+
+/// One-page PDF showing a JPEG at one point per pixel
+fn image_pdf(jpeg: Vec<u8>, width: u32, height: u32, color_space: &str) -> ApplicationResult<Vec<u8>> {
+    let mut doc = Document::with_version("1.5");
+    let pages_id = doc.new_object_id();
+    let image = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Image",
+            "Width" => width,
+            "Height" => height,
+            "ColorSpace" => color_space,
+            "BitsPerComponent" => 8,
+            "Filter" => "DCTDecode",
+        },
+        jpeg,
+    )
+    .with_compression(false);
+    let image_id = doc.add_object(image);
+
+    let content = Content {
+        operations: vec![
+            Operation::new("q", vec![]),
+            Operation::new(
+                "cm",
+                vec![width.into(), Object::Integer(0), Object::Integer(0), height.into(), Object::Integer(0), Object::Integer(0)],
+            ),
+            Operation::new("Do", vec!["Im0".into()]),
+            Operation::new("Q", vec![]),
+        ],
+    };
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().map_err(pdf_error)?));
+    let page_id = doc.add_object(dictionary! {
+        "Type" => "Page",
+        "Parent" => pages_id,
+        "Contents" => content_id,
+        "MediaBox" => vec![Object::Integer(0), Object::Integer(0), width.into(), height.into()],
+        "Resources" => dictionary! { "XObject" => dictionary! { "Im0" => image_id } },
+    });
+    doc.objects.insert(
+        pages_id,
+        Object::Dictionary(dictionary! {
+            "Type" => "Pages",
+            "Kids" => vec![page_id.into()],
+            "Count" => 1,
+        }),
+    );
+    let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+    doc.trailer.set("Root", catalog_id);
+    doc.compress();
+    save(&mut doc)
+}
+
+/// Rectangle in a page's default user space
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Rect {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+}
+
+impl Rect {
+    fn contains(&self, (x, y): (f32, f32)) -> bool {
+        (self.x0..=self.x1).contains(&x) && (self.y0..=self.y1).contains(&y)
+    }
+
+    fn intersects(&self, other: &Rect) -> bool {
+        self.x0 < other.x1 && other.x0 < self.x1 && self.y0 < other.y1 && other.y0 < self.y1
+    }
+
+    /// Bounds of `points`
+    fn around(points: &[(f32, f32)]) -> Self {
+        points.iter().fold(
+            Rect {
+                x0: f32::INFINITY,
+                y0: f32::INFINITY,
+                x1: f32::NEG_INFINITY,
+                y1: f32::NEG_INFINITY,
+            },
+            |rect, &(x, y)| Rect {
+                x0: rect.x0.min(x),
+                y0: rect.y0.min(y),
+                x1: rect.x1.max(x),
+                y1: rect.y1.max(y),
+            },
+        )
+    }
+}
+
+/// Visible area and rotation of a page, to place boxes measured on the page as displayed
+struct PageGeometry {
+    bounds: [f32; 4],
+    rotate: i64,
+}
+
+impl PageGeometry {
+    fn of(doc: &Document, page_id: ObjectId) -> Self {
+        let bounds = inherited(doc, page_id, b"CropBox")
+            .or_else(|| inherited(doc, page_id, b"MediaBox"))
+            .and_then(|bounds| bounds.as_array().ok())
+            .and_then(|values| {
+                let values: Vec<f32> = values.iter().filter_map(|value| resolve(doc, value).as_float().ok()).collect();
+                match values[..] {
+                    [x0, y0, x1, y1] => Some([x0.min(x1), y0.min(y1), x0.max(x1), y0.max(y1)]),
+                    _ => None,
+                }
+            })
+            .unwrap_or([0.0, 0.0, 612.0, 792.0]);
+        let rotate = inherited(doc, page_id, b"Rotate")
+            .and_then(|rotate| rotate.as_i64().ok())
+            .unwrap_or(0)
+            .rem_euclid(360);
+        Self { bounds, rotate }
+    }
+
+    /// User-space point at fractions `u` across and `v` down the displayed page
+    fn point(&self, u: f32, v: f32) -> (f32, f32) {
+        let [x0, y0, x1, y1] = self.bounds;
+        let (width, height) = (x1 - x0, y1 - y0);
+        match self.rotate {
+            90 => (x0 + v * width, y0 + u * height),
+            180 => (x1 - u * width, y0 + v * height),
+            270 => (x1 - v * width, y1 - u * height),
+            _ => (x0 + u * width, y1 - v * height),
+        }
+    }
+
+    fn rect(&self, redaction: &RedactionBox) -> Rect {
+        Rect::around(&[
+            self.point(redaction.left, redaction.top),
+            self.point(redaction.right, redaction.bottom),
+        ])
+    }
+}
+
+/// A page attribute, looked up through the page tree when the page doesn't set it
+fn inherited<'a>(doc: &'a Document, page_id: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = doc.get_dictionary(page_id).ok()?;
+    for _ in 0..MAX_PAGE_TREE_DEPTH {
+        if let Ok(value) = node.get(key) {
+            return Some(resolve(doc, value));
+        }
+        node = node
+            .get(b"Parent")
+            .and_then(Object::as_reference)
+            .and_then(|id| doc.get_dictionary(id))
+            .ok()?;
+    }
+    None
+}
+
+fn resolve<'a>(doc: &'a Document, object: &'a Object) -> &'a Object {
+    doc.dereference(object).map(|(_, object)| object).unwrap_or(object)
+}
+
+/// Burn `boxes` into every page of a PDF they fall on
+fn redact_pdf(data: &[u8], boxes: &[RedactionBox]) -> ApplicationResult<Vec<u8>> {
+    let mut doc = Document::load_mem(data).map_err(|e| {
+        ApplicationError::Domain(DomainError::InvalidDocumentFormat(format!("Failed to parse PDF: {}", e)))
+    })?;
+    if doc.is_encrypted() {
+        return Err(DomainError::EncryptedDocument.into());
+    }
+
+    for (page_number, page_id) in doc.get_pages() {
+        let geometry = PageGeometry::of(&doc, page_id);
+        let rects: Vec<Rect> = boxes
+            .iter()
+            .filter(|redaction| i64::from(redaction.page_number) == i64::from(page_number))
+            .map(|redaction| geometry.rect(redaction))
+            .collect();
+        if !rects.is_empty() {
+            debug!("Redacting {} areas of page {}", rects.len(), page_number);
+            redact_page(&mut doc, page_id, &rects).map_err(|e| match e {
+                ApplicationError::Domain(DomainError::InvalidDocumentFormat(reason)) => {
+                    DomainError::InvalidDocumentFormat(format!("page {}: {}", page_number, reason)).into()
+                }
+                other => other,
+            })?;
+        }
+    }
+
+    // Replaced content streams and dropped annotations must not survive in the file
+    doc.prune_objects();
+    doc.compress();
+    save(&mut doc)
+}
+
+fn redact_page(doc: &mut Document, page_id: ObjectId, rects: &[Rect]) -> ApplicationResult<()> {
+    let content = doc
+        .get_page_content(page_id)
+        .and_then(|content| Content::decode(&content))
+        .map_err(|e| {
+            ApplicationError::Domain(DomainError::InvalidDocumentFormat(format!(
+                "Failed to parse page content: {}",
+                e
+            )))
+        })?;
+
+    let fonts: HashMap<Vec<u8>, FontMetrics> = doc
+        .get_page_fonts(page_id)
+        .into_iter()
+        .map(|(name, font)| (name, FontMetrics::of(doc, font)))
+        .collect();
+    let images = page_images(doc, page_id);
+    let mut redactor = ContentRedactor::new(rects, &fonts, &images);
+    let mut operations = vec![Operation::new("q", vec![])];
+    for operation in content.operations {
+        redactor.apply(operation, &mut operations);
+    }
+    operations.push(Operation::new("Q", vec![]));
+
+    // Paint over what was removed, and over images that could not be rewritten
+    operations.push(Operation::new("q", vec![]));
+    operations.push(Operation::new("g", vec![Object::Integer(0)]));
+    for rect in rects {
+        operations.push(Operation::new(
+            "re",
+            vec![rect.x0.into(), rect.y0.into(), (rect.x1 - rect.x0).into(), (rect.y1 - rect.y0).into()],
+        ));
+    }
+    operations.push(Operation::new("f", vec![]));
+    operations.push(Operation::new("Q", vec![]));
+
+    let placed = redactor.placed_images;
+    let content = Content { operations }.encode().map_err(pdf_error)?;
+    // A fresh stream, so pages sharing the old one keep their content
+    let content_id = doc.add_object(Stream::new(dictionary! {}, content));
+    doc.get_dictionary_mut(page_id).map_err(pdf_error)?.set("Contents", content_id);
+
+    for (image_id, placement) in placed {
+        burn_image(doc, image_id, placement, rects);
+    }
+    remove_annotations(doc, page_id, rects);
+    Ok(())
+}
+
+/// Image XObjects a page can draw, by resource name
+fn page_images(doc: &Document, page_id: ObjectId) -> HashMap<Vec<u8>, ObjectId> {
+    let (direct, inherited) = doc.get_page_resources(page_id);
+    let resources = direct
+        .into_iter()
+        .chain(inherited.into_iter().filter_map(|id| doc.get_dictionary(id).ok()));
+    let mut images = HashMap::new();
+    for resources in resources {
+        let xobjects = match resources.get(b"XObject").map(|xobjects| resolve(doc, xobjects)) {
+            Ok(Object::Dictionary(xobjects)) => xobjects,
+            _ => continue,
+        };
+        for (name, xobject) in xobjects.iter() {
+            let id = match xobject.as_reference() {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+            let is_image = doc
+                .get_object(id)
+                .and_then(Object::as_stream)
+                .is_ok_and(|stream| stream.dict.get(b"Subtype").and_then(Object::as_name).is_ok_and(|name| name == b"Image"));
+            if is_image {
+                images.entry(name.clone()).or_insert(id);
+            }
+        }
+    }
+    images
+}
+
+/// Affine transform `[a b c d e f]`, applied to row vectors as PDF does
+type Matrix = [f32; 6];
+
+const IDENTITY: Matrix = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// `first` then `second`
+fn multiply(first: &Matrix, second: &Matrix) -> Matrix {
+    let [a, b, c, d, e, f] = *first;
+    let [a2, b2, c2, d2, e2, f2] = *second;
+    [
+        a * a2 + b * c2,
+        a * b2 + b * d2,
+        c * a2 + d * c2,
+        c * b2 + d * d2,
+        e * a2 + f * c2 + e2,
+        e * b2 + f * d2 + f2,
+    ]
+}
+
+fn transform(m: &Matrix, (x, y): (f32, f32)) -> (f32, f32) {
+    (x * m[0] + y * m[2] + m[4], x * m[1] + y * m[3] + m[5])
+}
+
+fn invert(m: &Matrix) -> Option<Matrix> {
+    let [a, b, c, d, e, f] = *m;
+    let det = a * d - b * c;
+    if det.abs() < f32::EPSILON {
+        return None;
+    }
+    let (a2, b2, c2, d2) = (d / det, -b / det, -c / det, a / det);
+    Some([a2, b2, c2, d2, -(e * a2 + f * c2), -(e * b2 + f * d2)])
+}
+
+fn translation(tx: f32, ty: f32) -> Matrix {
+    [1.0, 0.0, 0.0, 1.0, tx, ty]
+}
+
+/// Glyph widths of a font, in thousandths of the font size
+struct FontMetrics {
+    /// Composite fonts, assumed to use two-byte codes as `Identity-H` does
+    two_byte: bool,
+    first_char: u32,
+    widths: Vec<f32>,
+    cid_widths: BTreeMap<u32, f32>,
+    default_width: f32,
+}
+
+impl FontMetrics {
+    fn of(doc: &Document, font: &Dictionary) -> Self {
+        let number = |object: &Object| resolve(doc, object).as_float().ok();
+        let array = |dict: &Dictionary, key: &[u8]| -> Vec<Object> {
+            dict.get(key)
+                .map(|value| resolve(doc, value))
+                .and_then(Object::as_array)
+                .cloned()
+                .unwrap_or_default()
+        };
+
+        if font.get(b"Subtype").and_then(Object::as_name).is_ok_and(|name| name == b"Type0") {
+            let descendant = array(font, b"DescendantFonts")
+                .first()
+                .and_then(|descendant| resolve(doc, descendant).as_dict().ok().cloned())
+                .unwrap_or_default();
+            let default_width = descendant.get(b"DW").ok().and_then(number).unwrap_or(1000.0);
+            let mut cid_widths = BTreeMap::new();
+            // `c [w1 w2 ...]` gives consecutive widths, `first last w` one width for a range
+            let ranges = array(&descendant, b"W");
+            let mut i = 0;
+            while i < ranges.len() {
+                let first = number(&ranges[i]).unwrap_or(0.0) as u32;
+                match ranges.get(i + 1).map(|next| resolve(doc, next)) {
+                    Some(Object::Array(widths)) => {
+                        for (offset, width) in widths.iter().enumerate() {
+                            if let Some(width) = number(width) {
+                                cid_widths.insert(first + offset as u32, width);
+                            }
+                        }
+                        i += 2;
+                    }
+                    Some(last) => {
+                        let last = last.as_float().unwrap_or(0.0) as u32;
+                        let width = ranges.get(i + 2).and_then(number).unwrap_or(default_width);
+                        for cid in first..=last.min(first.saturating_add(0xFFFF)) {
+                            cid_widths.insert(cid, width);
+                        }
+                        i += 3;
+                    }
+                    None => break,
+                }
+            }
+            return Self {
+                two_byte: true,
+                first_char: 0,
+                widths: Vec::new(),
+                cid_widths,
+                default_width,
+            };
+        }
+
+        let missing_width = font
+            .get(b"FontDescriptor")
+            .map(|descriptor| resolve(doc, descriptor))
+            .and_then(Object::as_dict)
+            .and_then(|descriptor| descriptor.get(b"MissingWidth"))
+            .ok()
+            .and_then(number);
+        Self {
+            two_byte: false,
+            first_char: font.get(b"FirstChar").ok().and_then(number).unwrap_or(0.0) as u32,
+            widths: array(font, b"Widths").iter().map(|width| number(width).unwrap_or(0.0)).collect(),
+            cid_widths: BTreeMap::new(),
+            default_width: missing_width.unwrap_or(DEFAULT_GLYPH_WIDTH),
+        }
+    }
+
+    fn width(&self, code: u32) -> f32 {
+        if self.two_byte {
+            return self.cid_widths.get(&code).copied().unwrap_or(self.default_width);
+        }
+        code.checked_sub(self.first_char)
+            .and_then(|index| self.widths.get(index as usize))
+            .copied()
+            .unwrap_or(self.default_width)
+    }
+}
+
+/// Text state parameters, saved and restored with the graphics state
+#[derive(Clone)]
+struct TextState {
+    font: Option<Vec<u8>>,
+    size: f32,
+    char_spacing: f32,
+    word_spacing: f32,
+    horizontal_scale: f32,
+    leading: f32,
+    rise: f32,
+}
+
+impl Default for TextState {
+    fn default() -> Self {
+        Self {
+            font: None,
+            size: 0.0,
+            char_spacing: 0.0,
+            word_spacing: 0.0,
+            horizontal_scale: 1.0,
+            leading: 0.0,
+            rise: 0.0,
+        }
+    }
+}
+
+/// Walks a content stream, dropping glyphs whose centre falls in a box and
+/// noting where images are drawn
+struct ContentRedactor<'a> {
+    rects: &'a [Rect],
+    fonts: &'a HashMap<Vec<u8>, FontMetrics>,
+    images: &'a HashMap<Vec<u8>, ObjectId>,
+    ctm: Matrix,
+    text: TextState,
+    saved: Vec<(Matrix, TextState)>,
+    text_matrix: Matrix,
+    line_matrix: Matrix,
+    placed_images: Vec<(ObjectId, Matrix)>,
+}
+
+impl<'a> ContentRedactor<'a> {
+    fn new(
+        rects: &'a [Rect],
+        fonts: &'a HashMap<Vec<u8>, FontMetrics>,
+        images: &'a HashMap<Vec<u8>, ObjectId>,
+    ) -> Self {
+        Self {
+            rects,
+            fonts,
+            images,
+            ctm: IDENTITY,
+            text: TextState::default(),
+            saved: Vec::new(),
+            text_matrix: IDENTITY,
+            line_matrix: IDENTITY,
+            placed_images: Vec::new(),
+        }
+    }
+
+    /// Track `operation`'s effect and push it, or its redacted replacement, to `output`
+    fn apply(&mut self, operation: Operation, output: &mut Vec<Operation>) {
+        let operand = |index: usize| operation.operands.get(index).and_then(|value| value.as_float().ok()).unwrap_or(0.0);
+        match operation.operator.as_str() {
+            "q" => self.saved.push((self.ctm, self.text.clone())),
+            "Q" => {
+                if let Some((ctm, text)) = self.saved.pop() {
+                    self.ctm = ctm;
+                    self.text = text;
+                }
+            }
+            "cm" => {
+                let m = [operand(0), operand(1), operand(2), operand(3), operand(4), operand(5)];
+                self.ctm = multiply(&m, &self.ctm);
+            }
+            "BT" => {
+                self.text_matrix = IDENTITY;
+                self.line_matrix = IDENTITY;
+            }
+            "Tf" => {
+                self.text.font = operation.operands.first().and_then(|name| name.as_name().ok()).map(<[u8]>::to_vec);
+                self.text.size = operand(1);
+            }
+            "Tc" => self.text.char_spacing = operand(0),
+            "Tw" => self.text.word_spacing = operand(0),
+            "Tz" => self.text.horizontal_scale = operand(0) / 100.0,
+            "TL" => self.text.leading = operand(0),
+            "Ts" => self.text.rise = operand(0),
+            "Td" => self.next_line(operand(0), operand(1)),
+            "TD" => {
+                self.text.leading = -operand(1);
+                self.next_line(operand(0), operand(1));
+            }
+            "Tm" => {
+                self.line_matrix = [operand(0), operand(1), operand(2), operand(3), operand(4), operand(5)];
+                self.text_matrix = self.line_matrix;
+            }
+            "T*" => self.next_line(0.0, -self.text.leading),
+            "Tj" | "TJ" | "'" | "\"" => return self.show(operation, output),
+            "Do" => {
+                let image = operation.operands.first().and_then(|name| name.as_name().ok()).and_then(|name| self.images.get(name));
+                if let Some(image) = image {
+                    self.placed_images.push((*image, self.ctm));
+                }
+            }
+            _ => {}
+        }
+        output.push(operation);
+    }
+
+    fn next_line(&mut self, tx: f32, ty: f32) {
+        self.line_matrix = multiply(&translation(tx, ty), &self.line_matrix);
+        self.text_matrix = self.line_matrix;
+    }
+
+    /// Show text, replacing glyphs under a box with the space they took up
+    fn show(&mut self, operation: Operation, output: &mut Vec<Operation>) {
+        let mut operands = operation.operands.clone();
+        let elements = match operation.operator.as_str() {
+            "'" => {
+                self.next_line(0.0, -self.text.leading);
+                operands
+            }
+            "\"" => {
+                self.text.word_spacing = operands.first().and_then(|value| value.as_float().ok()).unwrap_or(0.0);
+                self.text.char_spacing = operands.get(1).and_then(|value| value.as_float().ok()).unwrap_or(0.0);
+                self.next_line(0.0, -self.text.leading);
+                operands.split_off(2.min(operands.len()))
+            }
+            "TJ" => operands
+                .first()
+                .and_then(|elements| elements.as_array().ok())
+                .cloned()
+                .unwrap_or_default(),
+            _ => operands,
+        };
+
+        let text = self.text.clone();
+        let font = text.font.as_ref().and_then(|name| self.fonts.get(name));
+        let scale = text.size * text.horizontal_scale;
+        let mut x = 0.0;
+        let mut redacted = false;
+        let mut replacement = Vec::new();
+        for element in &elements {
+            let (bytes, format) = match element {
+                Object::String(bytes, format) => (bytes, *format),
+                number => {
+                    let adjustment = number.as_float().unwrap_or(0.0);
+                    x -= adjustment / 1000.0 * scale;
+                    replacement.push(number.clone());
+                    continue;
+                }
+            };
+
+            let code_length = if font.is_some_and(|font| font.two_byte) { 2 } else { 1 };
+            let mut kept = Vec::new();
+            for code in bytes.chunks(code_length) {
+                let value = code.iter().fold(0u32, |value, byte| value << 8 | u32::from(*byte));
+                let width = font.map_or(DEFAULT_GLYPH_WIDTH, |font| font.width(value));
+                let spacing = if code_length == 1 && value == 32 { text.word_spacing } else { 0.0 };
+                let advance = (width / 1000.0 * text.size + text.char_spacing + spacing) * text.horizontal_scale;
+                let centre = (x + advance / 2.0, text.rise + GLYPH_CENTRE_RISE * text.size);
+                let position = transform(&multiply(&self.text_matrix, &self.ctm), centre);
+                if scale != 0.0 && self.rects.iter().any(|rect| rect.contains(position)) {
+                    if !kept.is_empty() {
+                        replacement.push(Object::String(std::mem::take(&mut kept), format));
+                    }
+                    let gap = -advance * 1000.0 / scale;
+                    match replacement.last_mut() {
+                        Some(Object::Real(previous)) => *previous += gap,
+                        _ => replacement.push(Object::Real(gap)),
+                    }
+                    redacted = true;
+                } else {
+                    kept.extend_from_slice(code);
+                }
+                x += advance;
+            }
+            if !kept.is_empty() {
+                replacement.push(Object::String(kept, format));
+            }
+        }
+        self.text_matrix = multiply(&translation(x, 0.0), &self.text_matrix);
+
+        if !redacted {
+            output.push(operation);
+            return;
+        }
+        match operation.operator.as_str() {
+            "'" => output.push(Operation::new("T*", vec![])),
+            "\"" => {
+                output.push(Operation::new("Tw", vec![text.word_spacing.into()]));
+                output.push(Operation::new("Tc", vec![text.char_spacing.into()]));
+                output.push(Operation::new("T*", vec![]));
+            }
+            _ => {}
+        }
+        output.push(Operation::new("TJ", vec![Object::Array(replacement)]));
+    }
+}
+
+/// Black out the pixels of an image drawn with `placement` that fall in `rects`
+fn burn_image(doc: &mut Document, image_id: ObjectId, placement: Matrix, rects: &[Rect]) {
+    let inverse = match invert(&placement) {
+        Some(inverse) => inverse,
+        None => return,
+    };
+    let stream = match doc.get_object(image_id).and_then(Object::as_stream) {
+        Ok(stream) => stream.clone(),
+        Err(_) => return,
+    };
+    let dict = &stream.dict;
+    let integer = |key: &[u8]| dict.get(key).ok().map(|value| resolve(doc, value)).and_then(|value| value.as_i64().ok());
+    let (width, height) = match (integer(b"Width"), integer(b"Height")) {
+        (Some(width), Some(height)) if width > 0 && height > 0 => (width as u32, height as u32),
+        _ => return,
+    };
+
+    // Areas of the image under a box, in pixels from its top-left corner
+    let areas: Vec<(u32, u32, u32, u32)> = rects
+        .iter()
+        .filter_map(|rect| {
+            let corners = [(rect.x0, rect.y0), (rect.x0, rect.y1), (rect.x1, rect.y0), (rect.x1, rect.y1)]
+                .map(|corner| transform(&inverse, corner));
+            let unit = Rect::around(&corners);
+            let unit = Rect {
+                x0: unit.x0.max(0.0),
+                y0: unit.y0.max(0.0),
+                x1: unit.x1.min(1.0),
+                y1: unit.y1.min(1.0),
+            };
+            (unit.x0 < unit.x1 && unit.y0 < unit.y1).then(|| {
+                (
+                    (unit.x0 * width as f32).floor() as u32,
+                    ((1.0 - unit.y1) * height as f32).floor() as u32,
+                    ((unit.x1 * width as f32).ceil() as u32).min(width),
+                    (((1.0 - unit.y0) * height as f32).ceil() as u32).min(height),
+                )
+            })
+        })
+        .collect();
+    if areas.is_empty() {
+        return;
+    }
+
+    let color_space = dict.get(b"ColorSpace").map(|space| resolve(doc, space));
+    let components = match color_space {
+        Ok(Object::Name(name)) if name == b"DeviceGray" => Some(1),
+        Ok(Object::Name(name)) if name == b"DeviceRGB" => Some(3),
+        Ok(Object::Array(space)) if space.first().and_then(|name| name.as_name().ok()) == Some(b"ICCBased") => space
+            .get(1)
+            .map(|profile| resolve(doc, profile))
+            .and_then(|profile| profile.as_stream().ok())
+            .and_then(|profile| profile.dict.get(b"N").and_then(Object::as_i64).ok())
+            .filter(|n| *n == 1 || *n == 3),
+        _ => None,
+    };
+    let filters = stream.filters().unwrap_or_default();
+    let plain = components.is_some()
+        && integer(b"BitsPerComponent") == Some(8)
+        && !dict.has(b"Decode")
+        && !dict.has(b"ImageMask");
+    let filters: Vec<&str> = filters.iter().map(String::as_str).collect();
+    let rewritten = match (plain, &filters[..]) {
+        (true, ["DCTDecode"]) => burn_jpeg(stream, &areas),
+        (true, [] | ["FlateDecode"]) => burn_samples(stream, components.unwrap_or(3) as u32, width, height, &areas),
+        _ => None,
+    };
+    match rewritten {
+        Some(stream) => {
+            doc.objects.insert(image_id, Object::Stream(stream));
+        }
+        None => warn!("Image {:?} could not be rewritten; it is only covered by the redaction box", image_id),
+    }
+}
+
+fn burn_jpeg(mut stream: Stream, areas: &[(u32, u32, u32, u32)]) -> Option<Stream> {
+    let image = image::load_from_memory_with_format(&stream.content, ImageFormat::Jpeg).ok()?;
+    let mut image = match image {
+        DynamicImage::ImageLuma8(_) | DynamicImage::ImageRgb8(_) => image,
+        _ => return None,
+    };
+    let (width, height) = (image.width(), image.height());
+    for &(left, top, right, bottom) in areas {
+        black_out(&mut image, left..right.min(width), top..bottom.min(height));
+    }
+    stream.set_content(encode_jpeg(&image).ok()?);
+    Some(stream)
+}
+
+fn burn_samples(
+    mut stream: Stream,
+    components: u32,
+    width: u32,
+    height: u32,
+    areas: &[(u32, u32, u32, u32)],
+) -> Option<Stream> {
+    // lopdf refuses to decode image streams, though Flate is all this one uses
+    let mut probe = stream.clone();
+    probe.dict.remove(b"Subtype");
+    let mut samples = if stream.filters().unwrap_or_default().is_empty() {
+        stream.content.clone()
+    } else {
+        probe.decompressed_content().ok()?
+    };
+    let row_length = (width * components) as usize;
+    if samples.len() < row_length * height as usize {
+        return None;
+    }
+    for &(left, top, right, bottom) in areas {
+        for row in top..bottom {
+            let start = row as usize * row_length;
+            samples[start + (left * components) as usize..start + (right * components) as usize].fill(0);
+        }
+    }
+    stream.set_plain_content(samples);
+    stream.compress().ok()?;
+    Some(stream)
+}
+
+/// Drop annotations overlapping a box, and the values of form fields they show
+fn remove_annotations(doc: &mut Document, page_id: ObjectId, rects: &[Rect]) {
+    let annotations = match doc.get_dictionary(page_id).and_then(|page| page.get(b"Annots")) {
+        Ok(annotations) => resolve(doc, annotations).as_array().cloned().unwrap_or_default(),
+        Err(_) => return,
+    };
+    let mut kept = Vec::new();
+    let mut cleared_fields = Vec::new();
+    for annotation in annotations {
+        let dict = match resolve(doc, &annotation).as_dict() {
+            Ok(dict) => dict,
+            Err(_) => {
+                kept.push(annotation);
+                continue;
+            }
+        };
+        let bounds: Vec<f32> = dict
+            .get(b"Rect")
+            .map(|rect| resolve(doc, rect))
+            .and_then(Object::as_array)
+            .map(|rect| rect.iter().filter_map(|value| resolve(doc, value).as_float().ok()).collect())
+            .unwrap_or_default();
+        let covered = match bounds[..] {
+            [x0, y0, x1, y1] => {
+                let bounds = Rect::around(&[(x0, y0), (x1, y1)]);
+                rects.iter().any(|rect| rect.intersects(&bounds))
+            }
+            _ => false,
+        };
+        if !covered {
+            kept.push(annotation);
+            continue;
+        }
+        if let Ok(parent) = dict.get(b"Parent").and_then(Object::as_reference) {
+            cleared_fields.push(parent);
+        }
+        if let Ok(id) = annotation.as_reference() {
+            cleared_fields.push(id);
+        }
+    }
+
+    for id in cleared_fields {
+        if let Ok(field) = doc.get_dictionary_mut(id) {
+            field.remove(b"V");
+            field.remove(b"AP");
+        }
+    }
+    if let Ok(page) = doc.get_dictionary_mut(page_id) {
+        page.set("Annots", Object::Array(kept));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Letter-size page showing `Public Secret` in Helvetica at (72, 700)
+    fn text_pdf() -> Vec<u8> {
+        let mut doc = Document::with_version("1.5");
+        let pages_id = doc.new_object_id();
+        let font_id = doc.add_object(dictionary! {
+            "Type" => "Font",
+            "Subtype" => "Type1",
+            "BaseFont" => "Helvetica",
+        });
+        let content = Content {
+            operations: vec![
+                Operation::new("BT", vec![]),
+                Operation::new("Tf", vec!["F1".into(), Object::Integer(12)]),
+                Operation::new("Td", vec![Object::Integer(72), Object::Integer(700)]),
+                Operation::new("Tj", vec![Object::string_literal("Public")]),
+                Operation::new("Tj", vec![Object::string_literal(" Secret")]),
+                Operation::new("ET", vec![]),
+            ],
+        };
+        let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode().unwrap()));
+        let page_id = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+            "Contents" => content_id,
+            "MediaBox" => vec![Object::Integer(0), Object::Integer(0), Object::Integer(612), Object::Integer(792)],
+            "Resources" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        });
+        doc.objects.insert(
+            pages_id,
+            Object::Dictionary(dictionary! { "Type" => "Pages", "Kids" => vec![page_id.into()], "Count" => 1 }),
+        );
+        let catalog_id = doc.add_object(dictionary! { "Type" => "Catalog", "Pages" => pages_id });
+        doc.trailer.set("Root", catalog_id);
+        save(&mut doc).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_redact_pdf_text() {
+        // Glyphs default to 6pt wide, so "Secret" spans x = 114..150 above the baseline at y = 700
+        let secret = RedactionBox {
+            page_number: 1,
+            left: 115.0 / 612.0,
+            top: (792.0 - 712.0) / 792.0,
+            right: 150.0 / 612.0,
+            bottom: (792.0 - 698.0) / 792.0,
+        };
+        let redacted = DocumentRedactor::new()
+            .render_redacted(Bytes::from(text_pdf()), DocumentFormat::Pdf, &[secret])
+            .await
+            .unwrap();
+
+        let doc = Document::load_mem(&redacted).unwrap();
+        let page_id = doc.get_pages()[&1];
+        let content = Content::decode(&doc.get_page_content(page_id).unwrap()).unwrap();
+        let shown: Vec<&Operation> = content
+            .operations
+            .iter()
+            .filter(|operation| operation.operator == "Tj" || operation.operator == "TJ")
+            .collect();
+        assert_eq!(shown[0].operands, vec![Object::string_literal("Public")]);
+        // The six glyphs of "Secret" become one gap of 6 x 500 thousandths of the font size
+        let elements = shown[1].operands[0].as_array().unwrap();
+        assert_eq!(elements[0], Object::string_literal(" "));
+        assert_eq!(elements[1].as_float().unwrap(), -3000.0);
+        assert_eq!(elements.len(), 2);
+        assert!(content.operations.iter().any(|operation| operation.operator == "re"));
+
+        // The original content stream is gone from the file
+        assert!(!redacted.windows(6).any(|window| window == b"Secret"));
+    }
+
+    #[tokio::test]
+    async fn test_redact_image() {
+        let mut image = image::RgbImage::from_pixel(40, 20, Rgb([255, 255, 255]));
+        image.put_pixel(0, 0, Rgb([250, 250, 250]));
+        let mut png = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(image).write_to(&mut png, ImageOutputFormat::Png).unwrap();
+        let left_half = RedactionBox {
+            page_number: 1,
+            left: 0.0,
+            top: 0.0,
+            right: 0.5,
+            bottom: 1.0,
+        };
+        let redacted = DocumentRedactor::new()
+            .render_redacted(Bytes::from(png.into_inner()), DocumentFormat::Png, &[left_half])
+            .await
+            .unwrap();
+
+        let doc = Document::load_mem(&redacted).unwrap();
+        let jpeg = doc
+            .objects
+            .values()
+            .filter_map(|object| object.as_stream().ok())
+            .find(|stream| stream.dict.get(b"Subtype").and_then(Object::as_name).is_ok_and(|name| name == b"Image"))
+            .unwrap();
+        let image = image::load_from_memory_with_format(&jpeg.content, ImageFormat::Jpeg).unwrap().to_rgb8();
+        assert_eq!(image.dimensions(), (40, 20));
+        assert!(image.get_pixel(5, 10).0[0] < 30);
+        assert!(image.get_pixel(35, 10).0[0] > 225);
+
+        let docx = Bytes::from_static(b"PK\x03\x04word/");
+        assert!(DocumentRedactor::new()
+            .render_redacted(docx, DocumentFormat::Docx, &[left_half])
+            .await
+            .is_err());
+    }
+}
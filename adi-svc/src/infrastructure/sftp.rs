@@ -0,0 +1,301 @@
+/// SFTP ingestion
+///
+/// For partners that can only deliver documents over SFTP. Lists a remote
+/// directory on a schedule, submits every file that has been left alone for
+/// the settle period, and moves it into a remote archive directory (or a
+/// failed directory when the service rejects it). Each submitted file is
+/// recorded in the ingest ledger by size and modification time first, so a
+/// file whose archive move failed is not analyzed a second time.
+
+use async_trait::async_trait;
+use chrono::Utc;
+use russh::client;
+use russh_keys::key::PublicKey;
+use russh_sftp::client::SftpSession;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::IngestLedgerPort;
+use crate::application::services::DocumentIntelligenceService;
+use crate::domain::{AnalyzeDocumentRequest, AnalyzeOptions, DocumentMetadata, DocumentSource, ModelType, TenantId};
+use crate::infrastructure::config::SftpIngestConfig;
+use crate::infrastructure::folder_watch::PARTIAL_SUFFIXES;
+use crate::infrastructure::tasks::TaskSupervisor;
+
+/// How the client authenticates
+enum SftpAuth {
+    Password(String),
+    Key { path: String, passphrase: Option<String> },
+}
+
+/// Submits the files delivered to one remote directory
+pub struct SftpIngestor {
+    service: Arc<DocumentIntelligenceService>,
+    ledger: Arc<dyn IngestLedgerPort>,
+    host: String,
+    port: u16,
+    username: String,
+    auth: SftpAuth,
+    host_key_fingerprint: Option<String>,
+    path: String,
+    archive_path: String,
+    failed_path: String,
+    model: ModelType,
+    tenant: TenantId,
+    interval: Duration,
+    settle: Duration,
+}
+
+impl SftpIngestor {
+    /// Ingestor for the configured server, or `None` when none is configured
+    pub fn from_config(
+        service: Arc<DocumentIntelligenceService>,
+        ledger: Arc<dyn IngestLedgerPort>,
+        config: &SftpIngestConfig,
+    ) -> ApplicationResult<Option<Self>> {
+        let Some(host) = &config.host else {
+            return Ok(None);
+        };
+        if config.username.is_empty() {
+            return Err(ApplicationError::Configuration(
+                "SFTP_USERNAME is required with SFTP_HOST".to_string(),
+            ));
+        }
+        let auth = match (&config.private_key_path, &config.password) {
+            (Some(path), _) => SftpAuth::Key {
+                path: path.clone(),
+                passphrase: config.private_key_passphrase.as_ref().map(|phrase| phrase.expose().to_string()),
+            },
+            (None, Some(password)) => SftpAuth::Password(password.expose().to_string()),
+            (None, None) => {
+                return Err(ApplicationError::Configuration(
+                    "SFTP_PRIVATE_KEY or SFTP_PASSWORD is required with SFTP_HOST".to_string(),
+                ));
+            }
+        };
+        let path = config.path.clone();
+        Ok(Some(Self {
+            service,
+            ledger,
+            host: host.clone(),
+            port: config.port,
+            username: config.username.clone(),
+            auth,
+            host_key_fingerprint: config.host_key_fingerprint.clone(),
+            archive_path: config.archive_path.clone().unwrap_or_else(|| remote_path(&path, "archive")),
+            failed_path: config.failed_path.clone().unwrap_or_else(|| remote_path(&path, "failed")),
+            path,
+            model: config.model,
+            tenant: config.tenant.clone(),
+            interval: Duration::from_secs(config.interval_secs),
+            settle: Duration::from_secs(config.settle_secs),
+        }))
+    }
+
+    /// Ledger key for this directory
+    fn source(&self) -> String {
+        format!("sftp://{}@{}:{}/{}", self.username, self.host, self.port, self.path.trim_start_matches('/'))
+    }
+
+    /// Submit every settled file in the directory; returns how many were submitted
+    pub async fn poll(&self) -> ApplicationResult<usize> {
+        let sftp = self.connect().await?;
+        for dir in [&self.archive_path, &self.failed_path] {
+            if !sftp.try_exists(dir.as_str()).await.map_err(|e| sftp_error("stat", dir, e))? {
+                sftp.create_dir(dir.as_str()).await.map_err(|e| sftp_error("mkdir", dir, e))?;
+            }
+        }
+
+        let now = Utc::now().timestamp();
+        let mut ready: Vec<(String, String)> = sftp
+            .read_dir(self.path.as_str())
+            .await
+            .map_err(|e| sftp_error("list", &self.path, e))?
+            .filter(|entry| entry.file_type().is_file())
+            .filter_map(|entry| {
+                let name = entry.file_name();
+                let metadata = entry.metadata();
+                let modified = i64::from(metadata.mtime.unwrap_or_default());
+                let settled = now - modified >= self.settle.as_secs() as i64;
+                let partial = name.starts_with('.') || PARTIAL_SUFFIXES.iter().any(|suffix| name.ends_with(suffix));
+                (settled && !partial).then(|| {
+                    let version = format!("{}:{}", metadata.size.unwrap_or_default(), modified);
+                    (name, version)
+                })
+            })
+            .collect();
+        ready.sort();
+
+        let mut submitted = 0;
+        for (name, version) in &ready {
+            match self.ingest(&sftp, name, version).await {
+                Ok(true) => submitted += 1,
+                Ok(false) => {}
+                Err(e) => warn!("Failed to ingest {} from {}: {}", name, self.source(), e),
+            }
+        }
+        if let Err(e) = sftp.close().await {
+            warn!("Failed to close SFTP session: {}", e);
+        }
+        if submitted > 0 {
+            info!("Submitted {} files from {}", submitted, self.source());
+        }
+        Ok(submitted)
+    }
+
+    /// Submit one file and move it out of the directory
+    async fn ingest(&self, sftp: &SftpSession, name: &str, version: &str) -> ApplicationResult<bool> {
+        let source = self.source();
+        let remote = remote_path(&self.path, name);
+        if self.ledger.ingested_version(&source, name).await?.as_deref() == Some(version) {
+            // Submitted before, but the move to the archive failed
+            self.move_remote(sftp, &remote, &self.archive_path, name).await?;
+            return Ok(false);
+        }
+
+        let data = sftp.read(remote.as_str()).await.map_err(|e| sftp_error("read", &remote, e))?;
+        let request = AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(data.into()),
+            model_type: self.model,
+            options: AnalyzeOptions::default(),
+            metadata: Some(DocumentMetadata::new(name, "")),
+            tenant_id: self.tenant.clone(),
+        };
+        match self.service.analyze_document(request).await {
+            Ok(operation) => {
+                self.ledger
+                    .record_ingested(&source, name, version, Some(&operation.operation_id))
+                    .await?;
+                info!("{} submitted as operation {}", remote, operation.operation_id);
+                // Moved again on the next poll when this fails
+                if let Err(e) = self.move_remote(sftp, &remote, &self.archive_path, name).await {
+                    error!("Failed to archive {}: {}", remote, e);
+                }
+                Ok(true)
+            }
+            Err(e) if e.is_transient() => Err(e),
+            Err(e) => {
+                warn!("{} was rejected: {}", remote, e);
+                self.move_remote(sftp, &remote, &self.failed_path, name).await?;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Move `from` into `dir`, prefixing a timestamp when the name is taken
+    async fn move_remote(&self, sftp: &SftpSession, from: &str, dir: &str, name: &str) -> ApplicationResult<()> {
+        let mut target = remote_path(dir, name);
+        if sftp.try_exists(target.as_str()).await.map_err(|e| sftp_error("stat", &target, e))? {
+            target = remote_path(dir, &format!("{}-{}", Utc::now().format("%Y%m%dT%H%M%S%.3f"), name));
+        }
+        sftp.rename(from, target.as_str())
+            .await
+            .map_err(|e| sftp_error("rename", from, e))
+    }
+
+    /// Authenticated SFTP session
+    async fn connect(&self) -> ApplicationResult<SftpSession> {
+        let handler = HostKeyCheck {
+            host: self.host.clone(),
+            expected: self.host_key_fingerprint.clone(),
+        };
+        let mut session = client::connect(Arc::new(client::Config::default()), (self.host.as_str(), self.port), handler)
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to connect to {}:{}: {}", self.host, self.port, e)))?;
+
+        let authenticated = match &self.auth {
+            SftpAuth::Password(password) => session.authenticate_password(&self.username, password).await,
+            SftpAuth::Key { path, passphrase } => {
+                let key = russh_keys::load_secret_key(path, passphrase.as_deref())
+                    .map_err(|e| ApplicationError::Configuration(format!("Failed to load SFTP_PRIVATE_KEY: {}", e)))?;
+                session.authenticate_publickey(&self.username, Arc::new(key)).await
+            }
+        }
+        .map_err(|e| ApplicationError::Internal(format!("SFTP authentication failed: {}", e)))?;
+        if !authenticated {
+            return Err(ApplicationError::Internal(format!(
+                "SFTP server {} rejected the credentials for {}",
+                self.host, self.username
+            )));
+        }
+
+        let channel = session
+            .channel_open_session()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to open SSH channel: {}", e)))?;
+        channel
+            .request_subsystem(true, "sftp")
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to start SFTP: {}", e)))?;
+        SftpSession::new(channel.into_stream())
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to start SFTP: {}", e)))
+    }
+}
+
+/// Verifies the server key against the configured fingerprint
+struct HostKeyCheck {
+    host: String,
+    expected: Option<String>,
+}
+
+#[async_trait]
+impl client::Handler for HostKeyCheck {
+    type Error = russh::Error;
+
+    async fn check_server_key(&mut self, server_public_key: &PublicKey) -> Result<bool, Self::Error> {
+        let fingerprint = format!("SHA256:{}", server_public_key.fingerprint());
+        match &self.expected {
+            // ssh-keygen prints fingerprints without base64 padding
+            Some(expected) => {
+                let matches = expected.trim().trim_end_matches('=') == fingerprint.trim_end_matches('=');
+                if !matches {
+                    error!("SFTP server {} presented unexpected host key {}", self.host, fingerprint);
+                }
+                Ok(matches)
+            }
+            None => {
+                warn!(
+                    "Accepting SFTP host key {} for {}; set SFTP_HOST_KEY_FINGERPRINT to pin it",
+                    fingerprint, self.host
+                );
+                Ok(true)
+            }
+        }
+    }
+}
+
+/// Poll `ingestor` until shutdown
+pub fn spawn_sftp_ingest(supervisor: &TaskSupervisor, ingestor: SftpIngestor) {
+    info!(
+        "Ingesting files from {} every {:?} with {}",
+        ingestor.source(),
+        ingestor.interval,
+        ingestor.model.as_str()
+    );
+    let ingestor = Arc::new(ingestor);
+    supervisor.spawn("sftp-ingest", move |shutdown| {
+        let ingestor = ingestor.clone();
+        async move {
+            loop {
+                if let Err(e) = ingestor.poll().await {
+                    error!("SFTP ingestion failed: {}", e);
+                }
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = tokio::time::sleep(ingestor.interval) => {}
+                }
+            }
+        }
+    });
+}
+
+fn remote_path(dir: &str, name: &str) -> String {
+    format!("{}/{}", dir.trim_end_matches('/'), name)
+}
+
+fn sftp_error(action: &str, path: &str, e: russh_sftp::client::error::Error) -> ApplicationError {
+    ApplicationError::Internal(format!("SFTP {} of {} failed: {}", action, path, e))
+}
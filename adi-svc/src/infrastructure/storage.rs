@@ -1,34 +1,333 @@
 /// File storage adapter for document uploads
 /// 
 /// This adapter provides local file storage for uploaded documents.
+/// The default tenant's documents live directly in the upload directory; other
+/// tenants get their own subdirectory under `tenants/`. With encryption on,
+/// files are sealed under a data key of their own as they are written,
+/// resumable uploads included, and opened as they are read.
 
 use async_trait::async_trait;
-use std::path::PathBuf;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt};
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_util::io::ReaderStream;
 use uuid::Uuid;
 use tracing::{debug, info};
 
 use crate::application::errors::{ApplicationError, ApplicationResult};
-use crate::application::ports::DocumentStoragePort;
+use crate::application::ports::{ByteRange, DocumentStoragePort, DocumentStream, SignedUrl, UploadState};
+use crate::domain::{DocumentMetadata, DomainError, TenantId};
 use crate::infrastructure::config::StorageConfig;
+use crate::infrastructure::encryption::{
+    sealed_plaintext_len, segment_count, EnvelopeCipher, FileHeader, FileKey, SegmentWriter, FILE_SEGMENT_BYTES, SEGMENT_OVERHEAD,
+};
+use crate::infrastructure::url_signing::UrlSigner;
+
+/// Read buffer size for streamed documents
+const STREAM_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Subdirectory of the upload directory holding in-progress resumable uploads
+const PARTIAL_UPLOAD_DIR: &str = ".uploads";
+
+/// Subdirectory of the upload directory holding non-default tenants' documents
+const TENANTS_DIR: &str = "tenants";
 
 /// Local file storage adapter
 pub struct LocalFileStorageAdapter {
     config: StorageConfig,
+    /// Serializes appends to the same resumable upload
+    upload_locks: Mutex<HashMap<String, Arc<tokio::sync::Mutex<()>>>>,
+    /// Signs document links, which the REST API serves
+    url_signer: UrlSigner,
+    /// Seals files as they are written when set
+    cipher: Option<EnvelopeCipher>,
 }
 
 impl LocalFileStorageAdapter {
     pub async fn new(config: StorageConfig) -> ApplicationResult<Self> {
         // Create upload directory if it doesn't exist
-        fs::create_dir_all(&config.upload_dir)
+        fs::create_dir_all(PathBuf::from(&config.upload_dir).join(PARTIAL_UPLOAD_DIR))
             .await
             .map_err(|e| ApplicationError::Configuration(format!("Failed to create upload directory: {}", e)))?;
         
-        Ok(Self { config })
+        Ok(Self {
+            url_signer: url_signer(&config, ""),
+            config,
+            upload_locks: Mutex::new(HashMap::new()),
+            cipher: None,
+        })
+    }
+    
+    /// Encrypt documents stored from now on
+    ///
+    /// Documents stored before stay readable.
+    pub fn with_encryption(mut self, cipher: EnvelopeCipher) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+    
+    /// Header and segment writer of a new file, when encryption is on
+    async fn file_writer(&self) -> ApplicationResult<Option<(Vec<u8>, SegmentWriter)>> {
+        match &self.cipher {
+            Some(cipher) => {
+                let (header, key) = cipher.file_key().await?;
+                Ok(Some((header.to_bytes(), SegmentWriter::new(key, 0, Vec::new()))))
+            }
+            None => Ok(None),
+        }
+    }
+    
+    async fn file_key(&self, header: &FileHeader) -> ApplicationResult<FileKey> {
+        self.cipher
+            .as_ref()
+            .ok_or_else(|| ApplicationError::Configuration("Document is encrypted but no encryption key is configured".to_string()))?
+            .open_file_key(header)
+            .await
+    }
+    
+    /// Re-wrap the data keys of documents sealed under other KEKs with the
+    /// current one, returning how many were re-wrapped
+    ///
+    /// Only headers are rewritten; files keep their modification time, so
+    /// retention is unaffected. Uploads still in progress are left alone.
+    pub async fn rewrap_keys(&self) -> ApplicationResult<u64> {
+        let cipher = self
+            .cipher
+            .as_ref()
+            .ok_or_else(|| ApplicationError::Configuration("No encryption key is configured".to_string()))?;
+        let failed = |e: std::io::Error| ApplicationError::Internal(format!("Failed to re-wrap document: {}", e));
+        let current = cipher.current_key_id().await?;
+        let upload_dir = PathBuf::from(&self.config.upload_dir);
+        let mut dirs = vec![upload_dir.clone()];
+        dirs.extend(subdirectories(&upload_dir.join(TENANTS_DIR)).await?);
+        let mut rewrapped = 0;
+        for dir in dirs {
+            let mut entries = fs::read_dir(&dir).await.map_err(failed)?;
+            while let Some(entry) = entries.next_entry().await.map_err(failed)? {
+                if !entry.file_type().await.is_ok_and(|file_type| file_type.is_file()) {
+                    continue;
+                }
+                let mut file = fs::File::open(entry.path()).await.map_err(failed)?;
+                let header = match file_header(&mut file).await? {
+                    Some(header) if header.key_id != current => header,
+                    _ => continue,
+                };
+                let modified = file.metadata().await.map_err(failed)?.modified().map_err(failed)?;
+                
+                // Written beside partial uploads, then swapped in whole
+                let partial_path = dir.join(PARTIAL_UPLOAD_DIR).join(format!("{}.part", Uuid::new_v4()));
+                let mut partial = fs::File::create(&partial_path).await.map_err(failed)?;
+                let copied = async {
+                    partial.write_all(&cipher.rewrap_file_header(&header).await?.to_bytes()).await.map_err(failed)?;
+                    tokio::io::copy(&mut file, &mut partial).await.map_err(failed)?;
+                    partial.flush().await.map_err(failed)?;
+                    partial.into_std().await.set_modified(modified).map_err(failed)
+                }
+                .await;
+                if let Err(e) = copied {
+                    let _ = fs::remove_file(&partial_path).await;
+                    return Err(e);
+                }
+                fs::rename(&partial_path, entry.path()).await.map_err(failed)?;
+                rewrapped += 1;
+            }
+        }
+        info!("Re-wrapped {} document keys with {}", rewrapped, current);
+        Ok(rewrapped)
+    }
+    
+    /// Prefix signed links with the service's public URL
+    pub fn with_public_base_url(mut self, base_url: &str) -> Self {
+        self.url_signer = url_signer(&self.config, base_url);
+        self
+    }
+    
+    /// Root directory of a tenant's documents
+    fn tenant_dir(&self, tenant: &TenantId) -> PathBuf {
+        let root = PathBuf::from(&self.config.upload_dir);
+        if tenant.is_default() {
+            root
+        } else {
+            root.join(TENANTS_DIR).join(tenant.as_str())
+        }
+    }
+    
+    /// Tenant directories are created on first write
+    async fn ensure_tenant_dir(&self, tenant: &TenantId) -> ApplicationResult<()> {
+        fs::create_dir_all(self.tenant_dir(tenant).join(PARTIAL_UPLOAD_DIR))
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to create tenant directory: {}", e)))
+    }
+    
+    fn get_file_path(&self, tenant: &TenantId, document_id: &str) -> PathBuf {
+        self.tenant_dir(tenant).join(document_id)
+    }
+    
+    fn max_bytes(&self) -> u64 {
+        (self.config.max_upload_size_mb * 1024 * 1024) as u64
+    }
+    
+    /// Data and state files of a resumable upload; IDs are UUIDs we issued
+    fn upload_paths(&self, tenant: &TenantId, upload_id: &str) -> ApplicationResult<(PathBuf, PathBuf)> {
+        let id = Uuid::parse_str(upload_id)
+            .map_err(|_| ApplicationError::UploadNotFound(upload_id.to_string()))?;
+        let dir = self.tenant_dir(tenant).join(PARTIAL_UPLOAD_DIR);
+        Ok((dir.join(format!("{}.part", id)), dir.join(format!("{}.json", id))))
     }
     
-    fn get_file_path(&self, document_id: &str) -> PathBuf {
-        PathBuf::from(&self.config.upload_dir).join(document_id)
+    async fn read_upload_state(&self, tenant: &TenantId, upload_id: &str) -> ApplicationResult<UploadState> {
+        let (data_path, state_path) = self.upload_paths(tenant, upload_id)?;
+        let state = fs::read(&state_path).await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                ApplicationError::UploadNotFound(upload_id.to_string())
+            } else {
+                ApplicationError::Internal(format!("Failed to read upload state: {}", e))
+            }
+        })?;
+        let mut state: UploadState = serde_json::from_slice(&state)
+            .map_err(|e| ApplicationError::Internal(format!("Corrupt upload state: {}", e)))?;
+        let mut data = fs::File::open(&data_path)
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to read upload data: {}", e)))?;
+        state.offset = stored_size(&mut data).await?;
+        Ok(state)
+    }
+    
+    fn upload_lock(&self, upload_id: &str) -> Arc<tokio::sync::Mutex<()>> {
+        let mut locks = self.upload_locks.lock().unwrap_or_else(|e| e.into_inner());
+        locks.entry(upload_id.to_string()).or_default().clone()
+    }
+    
+    fn forget_upload_lock(&self, upload_id: &str) {
+        let mut locks = self.upload_locks.lock().unwrap_or_else(|e| e.into_inner());
+        locks.remove(upload_id);
+    }
+    
+    /// Path for a caller-supplied document ID, refusing anything outside the upload directory
+    fn checked_file_path(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<PathBuf> {
+        if document_id.is_empty()
+            || document_id.contains(['/', '\\'])
+            || document_id == "."
+            || document_id == ".."
+        {
+            return Err(ApplicationError::DocumentNotFound(document_id.to_string()));
+        }
+        Ok(self.get_file_path(tenant, document_id))
+    }
+}
+
+/// Header of a sealed file, leaving `file` just after it; `None`, with
+/// `file` rewound, for a file stored unsealed
+async fn file_header(file: &mut fs::File) -> ApplicationResult<Option<FileHeader>> {
+    let header = FileHeader::read(file).await?;
+    if header.is_none() {
+        file.seek(SeekFrom::Start(0))
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to seek file: {}", e)))?;
+    }
+    Ok(header)
+}
+
+/// Plaintext size of a stored file, sealed or not
+async fn stored_size(file: &mut fs::File) -> ApplicationResult<u64> {
+    let len = file
+        .metadata()
+        .await
+        .map_err(|e| ApplicationError::Internal(format!("Failed to read file metadata: {}", e)))?
+        .len();
+    Ok(match file_header(file).await? {
+        Some(header) => sealed_plaintext_len(len - header.encoded_len()),
+        None => len,
+    })
+}
+
+/// Plaintext of the segments following a sealed file's header
+fn open_segments(key: &FileKey, body: &[u8]) -> ApplicationResult<Vec<u8>> {
+    if body.is_empty() {
+        return Err(ApplicationError::Internal("Sealed document is truncated".to_string()));
+    }
+    let last = segment_count(body.len() as u64) - 1;
+    let mut plaintext = Vec::with_capacity(body.len());
+    for (index, segment) in body.chunks(FILE_SEGMENT_BYTES + SEGMENT_OVERHEAD).enumerate() {
+        plaintext.extend(key.open_segment(index as u64, index as u64 == last, segment)?);
+    }
+    Ok(plaintext)
+}
+
+/// Plaintext bytes `start..start + length` of a sealed file whose segments
+/// take `body_len` bytes from `body_start`, opened a segment at a time
+fn sealed_stream(
+    file: fs::File,
+    key: FileKey,
+    body_start: u64,
+    body_len: u64,
+    start: u64,
+    length: u64,
+) -> BoxStream<'static, std::io::Result<Bytes>> {
+    let segment_len = (FILE_SEGMENT_BYTES + SEGMENT_OVERHEAD) as u64;
+    let last = segment_count(body_len) - 1;
+    let first = start / FILE_SEGMENT_BYTES as u64;
+    let skip = (start % FILE_SEGMENT_BYTES as u64) as usize;
+    stream::try_unfold((file, first, skip, length), move |(mut file, index, skip, remaining)| {
+        let key = key.clone();
+        async move {
+            if remaining == 0 {
+                return Ok(None);
+            }
+            let offset = index * segment_len;
+            let mut segment = vec![0; segment_len.min(body_len - offset) as usize];
+            file.seek(SeekFrom::Start(body_start + offset)).await?;
+            file.read_exact(&mut segment).await?;
+            let plaintext = key
+                .open_segment(index, index == last, &segment)
+                .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e.to_string()))?;
+            let end = plaintext.len().min(skip + remaining as usize);
+            let chunk = Bytes::copy_from_slice(&plaintext[skip..end]);
+            let remaining = remaining - chunk.len() as u64;
+            Ok(Some((chunk, (file, index + 1, 0, remaining))))
+        }
+    })
+    .boxed()
+}
+
+fn open_error(document_id: &str, e: std::io::Error) -> ApplicationError {
+    if e.kind() == std::io::ErrorKind::NotFound {
+        ApplicationError::DocumentNotFound(document_id.to_string())
+    } else {
+        ApplicationError::Internal(format!("Failed to open file: {}", e))
+    }
+}
+
+/// Immediate subdirectories of `dir`, which may not exist
+async fn subdirectories(dir: &Path) -> ApplicationResult<Vec<PathBuf>> {
+    let mut entries = match fs::read_dir(dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(ApplicationError::Internal(format!("Failed to read upload directory: {}", e))),
+    };
+    let mut dirs = Vec::new();
+    while let Some(entry) = entries
+        .next_entry()
+        .await
+        .map_err(|e| ApplicationError::Internal(format!("Failed to read upload directory: {}", e)))?
+    {
+        if entry.file_type().await.is_ok_and(|file_type| file_type.is_dir()) {
+            dirs.push(entry.path());
+        }
+    }
+    Ok(dirs)
+}
+
+fn url_signer(config: &StorageConfig, base_url: &str) -> UrlSigner {
+    match &config.url_signing_key {
+        Some(key) => UrlSigner::new(key.expose().as_bytes(), base_url),
+        None => UrlSigner::ephemeral(base_url),
     }
 }
 
@@ -36,9 +335,10 @@ impl LocalFileStorageAdapter {
 impl DocumentStoragePort for LocalFileStorageAdapter {
     async fn store_document(
         &self,
+        tenant: &TenantId,
         filename: &str,
         _content_type: &str,
-        data: Vec<u8>,
+        data: Bytes,
     ) -> ApplicationResult<String> {
         // Check size limit
         let max_bytes = self.config.max_upload_size_mb * 1024 * 1024;
@@ -52,10 +352,20 @@ impl DocumentStoragePort for LocalFileStorageAdapter {
         
         // Generate unique ID
         let document_id = format!("{}_{}", Uuid::new_v4(), filename);
-        let file_path = self.get_file_path(&document_id);
+        self.ensure_tenant_dir(tenant).await?;
+        let file_path = self.get_file_path(tenant, &document_id);
         
         debug!("Storing document: {} ({} bytes)", document_id, data.len());
         
+        let data = match self.file_writer().await? {
+            Some((mut file, mut writer)) => {
+                file.extend(writer.push(&data)?);
+                file.extend(writer.finish()?);
+                Bytes::from(file)
+            }
+            None => data,
+        };
+        
         // Write file
         fs::write(&file_path, data)
             .await
@@ -65,18 +375,295 @@ impl DocumentStoragePort for LocalFileStorageAdapter {
         Ok(document_id)
     }
     
-    async fn retrieve_document(&self, document_id: &str) -> ApplicationResult<Vec<u8>> {
-        let file_path = self.get_file_path(document_id);
+    async fn store_stream(
+        &self,
+        tenant: &TenantId,
+        filename: &str,
+        _content_type: &str,
+        mut body: BoxStream<'_, ApplicationResult<Bytes>>,
+    ) -> ApplicationResult<String> {
+        // Written beside partial uploads, so a failed write is purged with them
+        self.ensure_tenant_dir(tenant).await?;
+        let partial_path = self
+            .tenant_dir(tenant)
+            .join(PARTIAL_UPLOAD_DIR)
+            .join(format!("{}.part", Uuid::new_v4()));
+        let mut file = fs::File::create(&partial_path)
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to create file: {}", e)))?;
+        
+        let mut written = 0;
+        let outcome = async {
+            let write_failed = |e: std::io::Error| ApplicationError::Internal(format!("Failed to write file: {}", e));
+            let mut writer = match self.file_writer().await? {
+                Some((header, writer)) => {
+                    file.write_all(&header).await.map_err(write_failed)?;
+                    Some(writer)
+                }
+                None => None,
+            };
+            while let Some(chunk) = body.next().await {
+                let chunk = chunk?;
+                match &mut writer {
+                    Some(writer) => file.write_all(&writer.push(&chunk)?).await,
+                    None => file.write_all(&chunk).await,
+                }
+                .map_err(write_failed)?;
+                written += chunk.len();
+            }
+            if let Some(writer) = writer {
+                file.write_all(&writer.finish()?).await.map_err(write_failed)?;
+            }
+            file.flush().await.map_err(write_failed)
+        }
+        .await;
+        if let Err(e) = outcome {
+            let _ = fs::remove_file(&partial_path).await;
+            return Err(e);
+        }
+        
+        let document_id = format!("{}_{}", Uuid::new_v4(), filename);
+        fs::rename(&partial_path, self.get_file_path(tenant, &document_id))
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to store file: {}", e)))?;
+        info!("Document streamed to storage: {} ({} bytes)", document_id, written);
+        Ok(document_id)
+    }
+    
+    async fn retrieve_document(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<Bytes> {
+        let file_path = self.get_file_path(tenant, document_id);
         
         debug!("Retrieving document: {}", document_id);
         
-        fs::read(&file_path)
+        let read_failed = |e: std::io::Error| ApplicationError::Internal(format!("Failed to read file: {}", e));
+        let mut file = fs::File::open(&file_path).await.map_err(read_failed)?;
+        let header = file_header(&mut file).await?;
+        let mut data = Vec::new();
+        file.read_to_end(&mut data).await.map_err(read_failed)?;
+        match header {
+            Some(header) => Ok(Bytes::from(open_segments(&self.file_key(&header).await?, &data)?)),
+            None => Ok(Bytes::from(data)),
+        }
+    }
+    
+    async fn document_size(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<u64> {
+        let file_path = self.checked_file_path(tenant, document_id)?;
+        let mut file = fs::File::open(&file_path)
+            .await
+            .map_err(|e| open_error(document_id, e))?;
+        stored_size(&mut file).await
+    }
+    
+    async fn open_document(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        range: Option<ByteRange>,
+    ) -> ApplicationResult<DocumentStream> {
+        let file_path = self.checked_file_path(tenant, document_id)?;
+        
+        debug!("Streaming document: {} (range: {:?})", document_id, range);
+        
+        let mut file = fs::File::open(&file_path)
+            .await
+            .map_err(|e| open_error(document_id, e))?;
+        let file_len = file
+            .metadata()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to read file metadata: {}", e)))?
+            .len();
+        
+        if let Some(header) = file_header(&mut file).await? {
+            let key = self.file_key(&header).await?;
+            let body_len = file_len - header.encoded_len();
+            let total_size = sealed_plaintext_len(body_len);
+            let (start, length) = match range {
+                Some(range) if range.fits(total_size) => (range.start, range.length()),
+                Some(range) => {
+                    return Err(ApplicationError::Internal(format!(
+                        "Range {}-{} outside document of {} bytes",
+                        range.start, range.end, total_size
+                    )))
+                }
+                None => (0, total_size),
+            };
+            let body = sealed_stream(file, key, header.encoded_len(), body_len, start, length);
+            return Ok(DocumentStream { total_size, range, body });
+        }
+        let total_size = file_len;
+        
+        let body = match range {
+            Some(range) if range.fits(total_size) => {
+                file.seek(SeekFrom::Start(range.start))
+                    .await
+                    .map_err(|e| ApplicationError::Internal(format!("Failed to seek file: {}", e)))?;
+                ReaderStream::with_capacity(file.take(range.length()), STREAM_CHUNK_BYTES).boxed()
+            }
+            Some(range) => {
+                return Err(ApplicationError::Internal(format!(
+                    "Range {}-{} outside document of {} bytes",
+                    range.start, range.end, total_size
+                )))
+            }
+            None => ReaderStream::with_capacity(file, STREAM_CHUNK_BYTES).boxed(),
+        };
+        
+        Ok(DocumentStream { total_size, range, body })
+    }
+    
+    async fn create_upload(
+        &self,
+        tenant: &TenantId,
+        metadata: &DocumentMetadata,
+        total_size: Option<u64>,
+    ) -> ApplicationResult<UploadState> {
+        if let Some(size) = total_size.filter(|size| *size > self.max_bytes()) {
+            return Err(ApplicationError::Domain(DomainError::DocumentTooLarge {
+                size: size as usize,
+                max: self.max_bytes() as usize,
+            }));
+        }
+        
+        let state = UploadState {
+            upload_id: Uuid::new_v4().to_string(),
+            filename: metadata.filename.clone(),
+            content_type: metadata.content_type.clone(),
+            offset: 0,
+            total_size,
+        };
+        self.ensure_tenant_dir(tenant).await?;
+        let (data_path, state_path) = self.upload_paths(tenant, &state.upload_id)?;
+        
+        let state_json = serde_json::to_vec(&state)
+            .map_err(|e| ApplicationError::Internal(format!("Failed to serialize upload state: {}", e)))?;
+        // A sealed upload starts as its header and an empty last segment
+        let data = match self.file_writer().await? {
+            Some((mut file, writer)) => {
+                file.extend(writer.finish()?);
+                file
+            }
+            None => Vec::new(),
+        };
+        fs::write(&data_path, data)
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to create upload: {}", e)))?;
+        fs::write(&state_path, state_json)
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to create upload: {}", e)))?;
+        
+        info!("Resumable upload created: {}", state.upload_id);
+        Ok(state)
+    }
+    
+    async fn upload_state(&self, tenant: &TenantId, upload_id: &str) -> ApplicationResult<UploadState> {
+        self.read_upload_state(tenant, upload_id).await
+    }
+    
+    async fn append_upload(
+        &self,
+        tenant: &TenantId,
+        upload_id: &str,
+        offset: u64,
+        data: Bytes,
+    ) -> ApplicationResult<UploadState> {
+        let lock = self.upload_lock(upload_id);
+        let _guard = lock.lock().await;
+        
+        let mut state = self.read_upload_state(tenant, upload_id).await?;
+        if offset != state.offset {
+            return Err(ApplicationError::UploadOffsetMismatch { expected: state.offset });
+        }
+        
+        let new_offset = state.offset + data.len() as u64;
+        let limit = state.total_size.unwrap_or(u64::MAX).min(self.max_bytes());
+        if new_offset > limit {
+            return Err(ApplicationError::Domain(DomainError::DocumentTooLarge {
+                size: new_offset as usize,
+                max: limit as usize,
+            }));
+        }
+        
+        let (data_path, _) = self.upload_paths(tenant, upload_id)?;
+        let write_failed = |e: std::io::Error| ApplicationError::Internal(format!("Failed to write upload: {}", e));
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&data_path)
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to open upload: {}", e)))?;
+        match file_header(&mut file).await? {
+            Some(header) => {
+                // The last segment is reopened and sealed again with the new data after it
+                let key = self.file_key(&header).await?;
+                let body_len = file.metadata().await.map_err(write_failed)?.len() - header.encoded_len();
+                let last = segment_count(body_len) - 1;
+                let last_offset = header.encoded_len() + last * (FILE_SEGMENT_BYTES + SEGMENT_OVERHEAD) as u64;
+                let mut segment = Vec::new();
+                file.seek(SeekFrom::Start(last_offset)).await.map_err(write_failed)?;
+                file.read_to_end(&mut segment).await.map_err(write_failed)?;
+                let mut writer = SegmentWriter::new(key.clone(), last, key.open_segment(last, true, &segment)?);
+                let mut sealed = writer.push(&data)?;
+                sealed.extend(writer.finish()?);
+                file.seek(SeekFrom::Start(last_offset)).await.map_err(write_failed)?;
+                file.write_all(&sealed).await.map_err(write_failed)?;
+            }
+            None => {
+                file.seek(SeekFrom::End(0)).await.map_err(write_failed)?;
+                file.write_all(&data).await.map_err(write_failed)?;
+            }
+        }
+        file.flush().await.map_err(write_failed)?;
+        
+        debug!("Upload {} advanced to {} bytes", upload_id, new_offset);
+        state.offset = new_offset;
+        Ok(state)
+    }
+    
+    async fn complete_upload(
+        &self,
+        tenant: &TenantId,
+        upload_id: &str,
+    ) -> ApplicationResult<(String, UploadState)> {
+        let lock = self.upload_lock(upload_id);
+        let _guard = lock.lock().await;
+        
+        let state = self.read_upload_state(tenant, upload_id).await?;
+        if state.total_size.is_some_and(|size| size != state.offset) || state.offset == 0 {
+            return Err(ApplicationError::Domain(DomainError::ValidationError(format!(
+                "Upload incomplete: {} of {} bytes received",
+                state.offset,
+                state.total_size.map_or("?".to_string(), |size| size.to_string())
+            ))));
+        }
+        
+        let document_id = format!("{}_{}", Uuid::new_v4(), state.filename);
+        let (data_path, state_path) = self.upload_paths(tenant, upload_id)?;
+        fs::rename(&data_path, self.get_file_path(tenant, &document_id))
             .await
-            .map_err(|e| ApplicationError::Internal(format!("Failed to read file: {}", e)))
+            .map_err(|e| ApplicationError::Internal(format!("Failed to store upload: {}", e)))?;
+        let _ = fs::remove_file(&state_path).await;
+        self.forget_upload_lock(upload_id);
+        
+        info!("Resumable upload {} stored as {}", upload_id, document_id);
+        Ok((document_id, state))
+    }
+    
+    async fn abort_upload(&self, tenant: &TenantId, upload_id: &str) -> ApplicationResult<()> {
+        let lock = self.upload_lock(upload_id);
+        let _guard = lock.lock().await;
+        
+        let (data_path, state_path) = self.upload_paths(tenant, upload_id)?;
+        self.read_upload_state(tenant, upload_id).await?;
+        let _ = fs::remove_file(&data_path).await;
+        let _ = fs::remove_file(&state_path).await;
+        self.forget_upload_lock(upload_id);
+        
+        info!("Resumable upload aborted: {}", upload_id);
+        Ok(())
     }
     
-    async fn delete_document(&self, document_id: &str) -> ApplicationResult<()> {
-        let file_path = self.get_file_path(document_id);
+    async fn delete_document(&self, tenant: &TenantId, document_id: &str) -> ApplicationResult<()> {
+        let file_path = self.get_file_path(tenant, document_id);
         
         debug!("Deleting document: {}", document_id);
         
@@ -88,11 +675,68 @@ impl DocumentStoragePort for LocalFileStorageAdapter {
         Ok(())
     }
     
-    async fn get_document_url(&self, document_id: &str) -> ApplicationResult<String> {
-        // For local storage, we return a file:// URL
-        // In production, this would be an HTTP URL to a file server
-        let file_path = self.get_file_path(document_id);
-        Ok(format!("file://{}", file_path.display()))
+    async fn get_document_url(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        expires_in: chrono::Duration,
+    ) -> ApplicationResult<SignedUrl> {
+        // Only sign links to documents that exist
+        self.document_size(tenant, document_id).await?;
+        Ok(self.url_signer.sign(tenant, document_id, Utc::now() + expires_in))
+    }
+    
+    async fn verify_document_url(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        expires: i64,
+        signature: &str,
+    ) -> ApplicationResult<()> {
+        self.url_signer.verify(tenant, document_id, expires, signature)
+    }
+    
+    async fn purge_documents(&self, cutoff: DateTime<Utc>) -> ApplicationResult<u64> {
+        // Abandoned resumable uploads expire along with stored documents
+        let upload_dir = PathBuf::from(&self.config.upload_dir);
+        let mut dirs = vec![upload_dir.clone()];
+        dirs.extend(subdirectories(&upload_dir.join(TENANTS_DIR)).await?);
+        let mut purged = 0;
+        for dir in dirs.iter().flat_map(|dir| [dir.clone(), dir.join(PARTIAL_UPLOAD_DIR)]) {
+            let mut entries = match fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(ApplicationError::Internal(format!("Failed to read upload directory: {}", e)))
+                }
+            };
+            
+            while let Some(entry) = entries
+                .next_entry()
+                .await
+                .map_err(|e| ApplicationError::Internal(format!("Failed to read upload directory: {}", e)))?
+            {
+                let metadata = match entry.metadata().await {
+                    Ok(metadata) if metadata.is_file() => metadata,
+                    _ => continue,
+                };
+                let modified: DateTime<Utc> = match metadata.modified() {
+                    Ok(modified) => modified.into(),
+                    Err(_) => continue,
+                };
+                
+                if modified < cutoff {
+                    debug!("Purging expired document: {}", entry.file_name().to_string_lossy());
+                    fs::remove_file(entry.path())
+                        .await
+                        .map_err(|e| ApplicationError::Internal(format!("Failed to delete file: {}", e)))?;
+                    purged += 1;
+                }
+            }
+        }
+        
+        info!("Purged {} expired documents", purged);
+        Ok(purged)
     }
 }
 
@@ -107,20 +751,238 @@ mod tests {
         let config = StorageConfig {
             upload_dir: temp_dir.path().to_str().unwrap().to_string(),
             max_upload_size_mb: 10,
+            url_signing_key: None,
         };
         
         let storage = LocalFileStorageAdapter::new(config).await.unwrap();
+        let tenant = TenantId::default();
         
-        let data = b"test data".to_vec();
+        let data = Bytes::from_static(b"test data");
         let doc_id = storage
-            .store_document("test.txt", "text/plain", data.clone())
+            .store_document(&tenant, "test.txt", "text/plain", data.clone())
             .await
             .unwrap();
         
-        let retrieved = storage.retrieve_document(&doc_id).await.unwrap();
+        let retrieved = storage.retrieve_document(&tenant, &doc_id).await.unwrap();
         assert_eq!(retrieved, data);
         
-        storage.delete_document(&doc_id).await.unwrap();
+        // Other tenants' namespaces don't see the document
+        let other = TenantId::new("other").unwrap();
+        assert!(storage.retrieve_document(&other, &doc_id).await.is_err());
+        assert!(matches!(
+            storage.document_size(&other, &doc_id).await,
+            Err(ApplicationError::DocumentNotFound(_))
+        ));
+        
+        storage.delete_document(&tenant, &doc_id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_store_stream() {
+        let temp_dir = tempdir().unwrap();
+        let config = StorageConfig {
+            upload_dir: temp_dir.path().to_str().unwrap().to_string(),
+            max_upload_size_mb: 10,
+            url_signing_key: None,
+        };
+        let storage = LocalFileStorageAdapter::new(config).await.unwrap();
+        let tenant = TenantId::new("acme").unwrap();
+        
+        let chunks = futures::stream::iter([Ok(Bytes::from_static(b"{\"a\":1}\n")), Ok(Bytes::from_static(b"{\"a\":2}\n"))]);
+        let doc_id = storage
+            .store_stream(&tenant, "export.ndjson", "application/x-ndjson", chunks.boxed())
+            .await
+            .unwrap();
+        assert!(doc_id.ends_with("_export.ndjson"));
+        let stored = storage.retrieve_document(&tenant, &doc_id).await.unwrap();
+        assert_eq!(stored, Bytes::from_static(b"{\"a\":1}\n{\"a\":2}\n"));
+        
+        // A failing stream leaves nothing behind
+        let failing = futures::stream::iter([
+            Ok(Bytes::from_static(b"{}\n")),
+            Err(ApplicationError::Internal("source failed".to_string())),
+        ]);
+        assert!(storage.store_stream(&tenant, "export.ndjson", "application/x-ndjson", failing.boxed()).await.is_err());
+        let partial_dir = storage.tenant_dir(&tenant).join(PARTIAL_UPLOAD_DIR);
+        assert_eq!(std::fs::read_dir(partial_dir).unwrap().count(), 0);
     }
-}
 
+    #[tokio::test]
+    async fn test_purge_documents() {
+        let temp_dir = tempdir().unwrap();
+        let config = StorageConfig {
+            upload_dir: temp_dir.path().to_str().unwrap().to_string(),
+            max_upload_size_mb: 10,
+            url_signing_key: None,
+        };
+        
+        let storage = LocalFileStorageAdapter::new(config).await.unwrap();
+        let tenant = TenantId::default();
+        let doc_id = storage
+            .store_document(&tenant, "test.txt", "text/plain", Bytes::from_static(b"test data"))
+            .await
+            .unwrap();
+        
+        let purged = storage
+            .purge_documents(Utc::now() - chrono::Duration::days(1))
+            .await
+            .unwrap();
+        assert_eq!(purged, 0);
+        
+        let purged = storage
+            .purge_documents(Utc::now() + chrono::Duration::seconds(1))
+            .await
+            .unwrap();
+        assert_eq!(purged, 1);
+        assert!(storage.retrieve_document(&tenant, &doc_id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_open_document_range() {
+        let temp_dir = tempdir().unwrap();
+        let config = StorageConfig {
+            upload_dir: temp_dir.path().to_str().unwrap().to_string(),
+            max_upload_size_mb: 10,
+            url_signing_key: None,
+        };
+        
+        let storage = LocalFileStorageAdapter::new(config).await.unwrap();
+        let tenant = TenantId::default();
+        let doc_id = storage
+            .store_document(&tenant, "test.txt", "text/plain", Bytes::from_static(b"0123456789"))
+            .await
+            .unwrap();
+        assert_eq!(storage.document_size(&tenant, &doc_id).await.unwrap(), 10);
+        
+        let stream = storage
+            .open_document(&tenant, &doc_id, Some(ByteRange { start: 2, end: 5 }))
+            .await
+            .unwrap();
+        assert_eq!(stream.total_size, 10);
+        assert_eq!(stream.content_length(), 4);
+        let chunks: Vec<_> = stream.body.collect().await;
+        let body: Vec<u8> = chunks.into_iter().flat_map(|chunk| chunk.unwrap()).collect();
+        assert_eq!(body, b"2345");
+        
+        assert!(matches!(
+            storage.open_document(&tenant, "../etc/passwd", None).await,
+            Err(ApplicationError::DocumentNotFound(_))
+        ));
+        assert!(matches!(
+            storage.document_size(&tenant, "missing").await,
+            Err(ApplicationError::DocumentNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_resumable_upload() {
+        let temp_dir = tempdir().unwrap();
+        let config = StorageConfig {
+            upload_dir: temp_dir.path().to_str().unwrap().to_string(),
+            max_upload_size_mb: 10,
+            url_signing_key: None,
+        };
+        
+        let storage = LocalFileStorageAdapter::new(config).await.unwrap();
+        let tenant = TenantId::default();
+        let metadata = DocumentMetadata::new("scan.pdf", "application/pdf");
+        let upload = storage.create_upload(&tenant, &metadata, Some(10)).await.unwrap();
+        
+        let state = storage
+            .append_upload(&tenant, &upload.upload_id, 0, Bytes::from_static(b"01234"))
+            .await
+            .unwrap();
+        assert_eq!(state.offset, 5);
+        
+        // A retried chunk at a stale offset is rejected with the current offset
+        assert!(matches!(
+            storage.append_upload(&tenant, &upload.upload_id, 0, Bytes::from_static(b"01234")).await,
+            Err(ApplicationError::UploadOffsetMismatch { expected: 5 })
+        ));
+        assert!(storage.complete_upload(&tenant, &upload.upload_id).await.is_err());
+        assert!(storage
+            .append_upload(&tenant, &upload.upload_id, 5, Bytes::from_static(b"5678901"))
+            .await
+            .is_err());
+        
+        storage
+            .append_upload(&tenant, &upload.upload_id, 5, Bytes::from_static(b"56789"))
+            .await
+            .unwrap();
+        let (document_id, state) = storage.complete_upload(&tenant, &upload.upload_id).await.unwrap();
+        assert_eq!(state.filename, "scan.pdf");
+        assert_eq!(storage.retrieve_document(&tenant, &document_id).await.unwrap(), &b"0123456789"[..]);
+        assert!(matches!(
+            storage.upload_state(&tenant, &upload.upload_id).await,
+            Err(ApplicationError::UploadNotFound(_))
+        ));
+    }
+
+    fn cipher(keys: &[(&str, u8)]) -> EnvelopeCipher {
+        use base64::Engine;
+        let config = crate::infrastructure::config::EncryptionConfig {
+            keys: keys
+                .iter()
+                .map(|(id, byte)| (id.to_string(), base64::engine::general_purpose::STANDARD.encode([*byte; 32]).into()))
+                .collect(),
+            key_vault_key_url: None,
+        };
+        EnvelopeCipher::from_keys(&config).unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_encrypted_storage() {
+        let temp_dir = tempdir().unwrap();
+        let config = StorageConfig {
+            upload_dir: temp_dir.path().to_str().unwrap().to_string(),
+            max_upload_size_mb: 10,
+            url_signing_key: None,
+        };
+        let plain = LocalFileStorageAdapter::new(config.clone()).await.unwrap();
+        let tenant = TenantId::new("acme").unwrap();
+        let legacy = plain.store_document(&tenant, "old.txt", "text/plain", Bytes::from_static(b"old")).await.unwrap();
+
+        let storage = LocalFileStorageAdapter::new(config.clone()).await.unwrap().with_encryption(cipher(&[("k1", 1)]));
+        let data: Vec<u8> = (0..FILE_SEGMENT_BYTES * 2 + 10).map(|i| (i % 251) as u8).collect();
+        let doc_id = storage.store_document(&tenant, "scan.pdf", "application/pdf", Bytes::from(data.clone())).await.unwrap();
+        let on_disk = std::fs::read(storage.get_file_path(&tenant, &doc_id)).unwrap();
+        assert!(!on_disk.windows(64).any(|window| window == &data[1000..1064]), "stored sealed");
+        assert_eq!(storage.retrieve_document(&tenant, &doc_id).await.unwrap(), data);
+        assert_eq!(storage.document_size(&tenant, &doc_id).await.unwrap(), data.len() as u64);
+        assert_eq!(storage.retrieve_document(&tenant, &legacy).await.unwrap(), &b"old"[..]);
+        assert!(plain.retrieve_document(&tenant, &doc_id).await.is_err(), "unreadable without a key");
+
+        // A range across a segment boundary is opened segment by segment
+        let start = FILE_SEGMENT_BYTES as u64 - 5;
+        let range = ByteRange { start, end: start + 9 };
+        let stream = storage.open_document(&tenant, &doc_id, Some(range)).await.unwrap();
+        assert_eq!(stream.total_size, data.len() as u64);
+        let body: Vec<u8> = stream.body.collect::<Vec<_>>().await.into_iter().flat_map(|chunk| chunk.unwrap()).collect();
+        assert_eq!(body, &data[start as usize..start as usize + 10]);
+
+        let chunks = futures::stream::iter(data.chunks(40_000).map(|chunk| Ok(Bytes::copy_from_slice(chunk))).collect::<Vec<_>>());
+        let streamed = storage.store_stream(&tenant, "export.ndjson", "application/x-ndjson", chunks.boxed()).await.unwrap();
+        assert_eq!(storage.retrieve_document(&tenant, &streamed).await.unwrap(), data);
+
+        // Resumable uploads are sealed while in progress too
+        let metadata = DocumentMetadata::new("scan.pdf", "application/pdf");
+        let upload = storage.create_upload(&tenant, &metadata, None).await.unwrap();
+        let mut offset = 0;
+        for chunk in data.chunks(50_000) {
+            offset = storage.append_upload(&tenant, &upload.upload_id, offset, Bytes::copy_from_slice(chunk)).await.unwrap().offset;
+        }
+        assert_eq!(offset, data.len() as u64);
+        let (uploaded, _) = storage.complete_upload(&tenant, &upload.upload_id).await.unwrap();
+        assert_eq!(storage.retrieve_document(&tenant, &uploaded).await.unwrap(), data);
+
+        // Rotating to k2 re-wraps each sealed document once, after which k1 can go
+        let modified = || std::fs::metadata(storage.get_file_path(&tenant, &doc_id)).unwrap().modified().unwrap();
+        let before = modified();
+        let rotated = LocalFileStorageAdapter::new(config.clone()).await.unwrap().with_encryption(cipher(&[("k2", 2), ("k1", 1)]));
+        assert_eq!(rotated.rewrap_keys().await.unwrap(), 3);
+        assert_eq!(modified(), before, "retention clock untouched");
+        assert_eq!(rotated.rewrap_keys().await.unwrap(), 0);
+        let retired = LocalFileStorageAdapter::new(config).await.unwrap().with_encryption(cipher(&[("k2", 2)]));
+        assert_eq!(retired.retrieve_document(&tenant, &doc_id).await.unwrap(), data);
+    }
+}
@@ -0,0 +1,129 @@
+/// Background task supervision
+///
+/// Every long-running background task is spawned through the supervisor so
+/// that shutdown can cancel and join them, and so that a panicking task is
+/// logged and restarted with backoff instead of silently disappearing.
+
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{error, info, warn};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Registry of supervised background tasks
+#[derive(Clone, Default)]
+pub struct TaskSupervisor {
+    tracker: TaskTracker,
+    shutdown: CancellationToken,
+}
+
+impl TaskSupervisor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token cancelled when shutdown begins
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    /// Spawn a supervised task
+    ///
+    /// `task` is invoked again after a panic, with exponential backoff.
+    /// A task that returns normally is not restarted.
+    pub fn spawn<F, Fut>(&self, name: &'static str, task: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let shutdown = self.shutdown.clone();
+
+        self.tracker.spawn(async move {
+            let mut backoff = INITIAL_BACKOFF;
+            loop {
+                info!("Starting background task: {}", name);
+                match tokio::spawn(task(shutdown.clone())).await {
+                    Ok(()) => {
+                        info!("Background task finished: {}", name);
+                        return;
+                    }
+                    Err(e) if e.is_panic() => {
+                        error!("Background task {} panicked; restarting in {:?}", name, backoff);
+                    }
+                    Err(e) => {
+                        warn!("Background task {} aborted: {}", name, e);
+                        return;
+                    }
+                }
+
+                tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = tokio::time::sleep(backoff) => {}
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        });
+    }
+
+    /// Cancel all tasks and wait for them to finish, up to `grace`
+    ///
+    /// Returns `false` if some tasks were still running when the grace
+    /// period expired.
+    pub async fn shutdown(&self, grace: Duration) -> bool {
+        info!("Stopping {} background tasks", self.tracker.len());
+        self.shutdown.cancel();
+        self.tracker.close();
+
+        match tokio::time::timeout(grace, self.tracker.wait()).await {
+            Ok(()) => true,
+            Err(_) => {
+                warn!(
+                    "{} background tasks still running after {:?} grace period",
+                    self.tracker.len(),
+                    grace
+                );
+                false
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_panicking_task_is_restarted() {
+        let supervisor = TaskSupervisor::new();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let counter = runs.clone();
+        supervisor.spawn("flaky", move |_shutdown| {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) == 0 {
+                    panic!("first run fails");
+                }
+            }
+        });
+
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+        assert!(supervisor.shutdown(Duration::from_secs(1)).await);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_shutdown_cancels_tasks() {
+        let supervisor = TaskSupervisor::new();
+        supervisor.spawn("loop", |shutdown| async move {
+            shutdown.cancelled().await;
+        });
+
+        assert!(supervisor.shutdown(Duration::from_secs(1)).await);
+    }
+}
@@ -0,0 +1,836 @@
+/// AWS Textract document intelligence adapter
+///
+/// Analyzes documents with Textract's synchronous APIs (`AZURE_MODE=textract`):
+/// the read model uses DetectDocumentText, layout uses AnalyzeDocument with
+/// tables and forms, and invoices and receipts use AnalyzeExpense. Blocks and
+/// expense fields are mapped into the same result model Azure's responses are,
+/// so the rest of the service does not know which provider ran. Textract has
+/// no counterpart for the other models, which are refused.
+///
+/// Textract answers synchronously; the call runs in the background and the
+/// operation reports running until it returns, like an Azure operation.
+
+use async_trait::async_trait;
+use aws_config::BehaviorVersion;
+use aws_sdk_textract::config::http::HttpResponse;
+use aws_sdk_textract::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+use aws_sdk_textract::primitives::Blob;
+use aws_sdk_textract::types::{
+    Block, BlockType, Document, EntityType, ExpenseDocument, ExpenseField, FeatureType, RelationshipType,
+    SelectionStatus,
+};
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::RwLock;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::DocumentIntelligencePort;
+use crate::domain::*;
+
+/// `api_version` reported on Textract results
+const TEXTRACT_API_VERSION: &str = "textract";
+
+/// How long a finished operation's result is kept for polling
+const RETENTION: Duration = Duration::from_secs(3600);
+
+/// Deepest PDF page tree searched for an inherited `MediaBox`
+const MAX_PAGE_TREE_DEPTH: usize = 32;
+
+/// Where a submitted operation is
+enum TextractState {
+    Running,
+    Succeeded(Box<AnalysisResult>),
+    Failed(AzureError),
+}
+
+struct TextractOperation {
+    model_type: ModelType,
+    state: TextractState,
+    finished_at: Option<Instant>,
+}
+
+type Operations = Arc<RwLock<HashMap<String, TextractOperation>>>;
+
+/// Document Intelligence adapter calling AWS Textract
+pub struct TextractAdapter {
+    client: aws_sdk_textract::Client,
+    http: reqwest::Client,
+    operations: Operations,
+}
+
+impl TextractAdapter {
+    pub fn new(client: aws_sdk_textract::Client) -> Self {
+        Self {
+            client,
+            http: reqwest::Client::new(),
+            operations: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Adapter with the default AWS credential chain and region
+    pub async fn from_env() -> Self {
+        let sdk_config = aws_config::load_defaults(BehaviorVersion::latest()).await;
+        info!("Analyzing documents with AWS Textract");
+        Self::new(aws_sdk_textract::Client::new(&sdk_config))
+    }
+
+    async fn finish(operations: &Operations, operation_id: &str, state: TextractState) {
+        if let Some(operation) = operations.write().await.get_mut(operation_id) {
+            operation.state = state;
+            operation.finished_at = Some(Instant::now());
+        }
+    }
+}
+
+/// Whether Textract has an API for `model_type`
+fn supported(model_type: ModelType) -> bool {
+    matches!(model_type, ModelType::Read | ModelType::Layout | ModelType::Invoice | ModelType::Receipt)
+}
+
+#[async_trait]
+impl DocumentIntelligencePort for TextractAdapter {
+    async fn analyze_document(&self, request: AnalyzeDocumentRequest) -> ApplicationResult<AnalysisOperation> {
+        if !supported(request.model_type) {
+            return Err(DomainError::ValidationError(format!(
+                "{} is not supported by Textract",
+                request.model_type.as_str()
+            ))
+            .into());
+        }
+        let mut operation = AnalysisOperation::new(request.model_type);
+        operation.update_status(OperationStatus::Running);
+
+        {
+            let mut operations = self.operations.write().await;
+            operations.retain(|_, op| op.finished_at.is_none_or(|at| at.elapsed() < RETENTION));
+            operations.insert(
+                operation.operation_id.clone(),
+                TextractOperation { model_type: request.model_type, state: TextractState::Running, finished_at: None },
+            );
+        }
+        info!(
+            "Textract analysis {} started with model: {}",
+            operation.operation_id,
+            request.model_type.as_str()
+        );
+
+        let client = self.client.clone();
+        let http = self.http.clone();
+        let operations = self.operations.clone();
+        let operation_id = operation.operation_id.clone();
+        tokio::spawn(async move {
+            let state = match analyze(&client, &http, request.model_type, request.source).await {
+                Ok(result) => TextractState::Succeeded(Box::new(result)),
+                Err(error) => {
+                    warn!("Textract analysis {} failed: {}", operation_id, error);
+                    TextractState::Failed(error)
+                }
+            };
+            Self::finish(&operations, &operation_id, state).await;
+        });
+        Ok(operation)
+    }
+
+    async fn get_analysis_result(
+        &self,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)> {
+        let operations = self.operations.read().await;
+        let stored = operations
+            .get(operation_id)
+            .ok_or_else(|| ApplicationError::OperationNotFound(operation_id.to_string()))?;
+
+        let mut operation = AnalysisOperation::new(stored.model_type);
+        operation.operation_id = operation_id.to_string();
+        match &stored.state {
+            TextractState::Running => {
+                operation.update_status(OperationStatus::Running);
+                Ok((operation, None))
+            }
+            TextractState::Succeeded(result) => {
+                operation.update_status(OperationStatus::Succeeded);
+                Ok((operation, Some(result.as_ref().clone())))
+            }
+            TextractState::Failed(error) => {
+                operation.update_status(OperationStatus::Failed);
+                operation.error = Some(error.clone());
+                Ok((operation, None))
+            }
+        }
+    }
+
+    /// Textract has no custom models
+    async fn validate_custom_model(&self, _model_id: &str) -> ApplicationResult<bool> {
+        Ok(false)
+    }
+}
+
+/// Run the Textract API for `model_type` over the document
+async fn analyze(
+    client: &aws_sdk_textract::Client,
+    http: &reqwest::Client,
+    model_type: ModelType,
+    source: DocumentSource,
+) -> Result<AnalysisResult, AzureError> {
+    let bytes = match source {
+        DocumentSource::Bytes(bytes) => bytes,
+        DocumentSource::Url(url) => download(http, &url).await?,
+    };
+    let size = PageSize::of(&bytes);
+    let document = Document::builder().bytes(Blob::new(bytes.to_vec())).build();
+
+    let mut result = match model_type {
+        ModelType::Read => {
+            let output = client.detect_document_text().document(document).send().await.map_err(sdk_error)?;
+            map_blocks(output.blocks(), &size)
+        }
+        ModelType::Layout => {
+            let output = client
+                .analyze_document()
+                .document(document)
+                .feature_types(FeatureType::Tables)
+                .feature_types(FeatureType::Forms)
+                .send()
+                .await
+                .map_err(sdk_error)?;
+            map_blocks(output.blocks(), &size)
+        }
+        _ => {
+            let output = client.analyze_expense().document(document).send().await.map_err(sdk_error)?;
+            map_expenses(model_type, output.expense_documents(), &size)
+        }
+    };
+    result.model_id = model_type.as_str().to_string();
+    result.api_version = TEXTRACT_API_VERSION.to_string();
+    Ok(result)
+}
+
+async fn download(http: &reqwest::Client, url: &str) -> Result<Bytes, AzureError> {
+    let failed = |message: String| AzureError {
+        status: None,
+        code: "InvalidContent".to_string(),
+        message,
+        target: Some("urlSource".to_string()),
+        inner_code: None,
+        request_id: None,
+    };
+    let response = http
+        .get(url)
+        .send()
+        .await
+        .map_err(|e| failed(format!("Failed to download {}: {}", url, e)))?;
+    if !response.status().is_success() {
+        return Err(AzureError {
+            status: Some(response.status().as_u16()),
+            ..failed(format!("Downloading {} returned {}", url, response.status()))
+        });
+    }
+    response.bytes().await.map_err(|e| failed(format!("Failed to download {}: {}", url, e)))
+}
+
+fn sdk_error<E: ProvideErrorMetadata + std::error::Error + 'static>(error: SdkError<E, HttpResponse>) -> AzureError {
+    let status = match &error {
+        SdkError::ServiceError(_) => error.raw_response().map(|response| response.status().as_u16()),
+        _ => None,
+    };
+    AzureError {
+        status,
+        code: error.code().unwrap_or("TextractError").to_string(),
+        message: error.message().map_or_else(|| DisplayErrorContext(&error).to_string(), str::to_string),
+        target: None,
+        inner_code: None,
+        request_id: None,
+    }
+}
+
+/// Size of the analyzed page, to turn Textract's page-relative geometry into coordinates
+///
+/// Images are measured in pixels and PDFs in inches, as Azure reports them;
+/// anything unreadable is taken as a US Letter page.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageSize {
+    width: f32,
+    height: f32,
+    unit: &'static str,
+}
+
+impl PageSize {
+    const LETTER: Self = Self { width: 8.5, height: 11.0, unit: "inch" };
+
+    fn of(bytes: &[u8]) -> Self {
+        if bytes.starts_with(b"%PDF-") {
+            return Self::pdf(bytes).unwrap_or(Self::LETTER);
+        }
+        image::io::Reader::new(std::io::Cursor::new(bytes))
+            .with_guessed_format()
+            .ok()
+            .and_then(|reader| reader.into_dimensions().ok())
+            .map_or(Self::LETTER, |(width, height)| Self {
+                width: width as f32,
+                height: height as f32,
+                unit: "pixel",
+            })
+    }
+
+    /// First page's `MediaBox`, in inches
+    fn pdf(bytes: &[u8]) -> Option<Self> {
+        let doc = lopdf::Document::load_mem(bytes).ok()?;
+        let page_id = *doc.get_pages().values().next()?;
+        let mut node = doc.get_dictionary(page_id).ok()?;
+        for _ in 0..MAX_PAGE_TREE_DEPTH {
+            if let Ok(media_box) = node.get(b"MediaBox") {
+                let values: Vec<f32> = doc
+                    .dereference(media_box)
+                    .ok()?
+                    .1
+                    .as_array()
+                    .ok()?
+                    .iter()
+                    .filter_map(|value| value.as_float().ok())
+                    .collect();
+                return match values[..] {
+                    [x0, y0, x1, y1] => Some(Self {
+                        width: (x1 - x0).abs() / 72.0,
+                        height: (y1 - y0).abs() / 72.0,
+                        unit: "inch",
+                    }),
+                    _ => None,
+                };
+            }
+            node = node.get(b"Parent").and_then(|parent| parent.as_reference()).and_then(|id| doc.get_dictionary(id)).ok()?;
+        }
+        None
+    }
+
+    fn polygon(&self, block: &Block) -> Vec<Point> {
+        block
+            .geometry()
+            .map(|geometry| {
+                geometry
+                    .polygon()
+                    .iter()
+                    .map(|point| Point { x: point.x() * self.width, y: point.y() * self.height })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+/// Textract confidence, 0–100, on Azure's 0–1 scale
+fn confidence(block: &Block) -> f32 {
+    block.confidence().unwrap_or(0.0) / 100.0
+}
+
+/// Blocks of a response, looked up by id
+struct Blocks<'a> {
+    by_id: HashMap<&'a str, &'a Block>,
+}
+
+impl<'a> Blocks<'a> {
+    fn new(blocks: &'a [Block]) -> Self {
+        Self { by_id: blocks.iter().filter_map(|block| Some((block.id()?, block))).collect() }
+    }
+
+    /// Blocks `block` points to with a relationship of `kind`
+    fn related(&self, block: &'a Block, kind: RelationshipType) -> impl Iterator<Item = &'a Block> + '_ {
+        block
+            .relationships()
+            .iter()
+            .filter(move |relationship| relationship.r#type() == Some(&kind))
+            .flat_map(|relationship| relationship.ids())
+            .filter_map(|id| self.by_id.get(id.as_str()).copied())
+    }
+
+    /// Text of a block's child words, with `:selected:` for a checked selection element
+    fn text(&self, block: &'a Block) -> String {
+        self.related(block, RelationshipType::Child)
+            .filter_map(|child| match child.block_type()? {
+                BlockType::Word => child.text().map(str::to_string),
+                BlockType::SelectionElement => {
+                    (child.selection_status() == Some(&SelectionStatus::Selected)).then(|| ":selected:".to_string())
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+}
+
+/// Content, pages, tables and key-value pairs of a DetectDocumentText or AnalyzeDocument response
+fn map_blocks(blocks: &[Block], size: &PageSize) -> AnalysisResult {
+    let index = Blocks::new(blocks);
+    let mut content = String::new();
+    let mut pages: BTreeMap<i32, DocumentPage> = BTreeMap::new();
+    let mut tables = Vec::new();
+    let mut key_value_pairs = Vec::new();
+
+    for block in blocks {
+        let page_number = block.page().unwrap_or(1);
+        let page = pages.entry(page_number).or_insert_with(|| DocumentPage {
+            page_number,
+            angle: 0.0,
+            width: size.width,
+            height: size.height,
+            unit: size.unit.to_string(),
+            words: Vec::new(),
+            lines: Vec::new(),
+            selection_marks: Vec::new(),
+        });
+        match block.block_type() {
+            Some(BlockType::Line) => {
+                let text = block.text().unwrap_or_default();
+                if !content.is_empty() {
+                    content.push('\n');
+                }
+                let offset = content.len();
+                content.push_str(text);
+                page.lines.push(DocumentLine {
+                    content: text.to_string(),
+                    polygon: size.polygon(block),
+                    spans: vec![Span { offset: offset as i32, length: text.len() as i32 }],
+                });
+
+                let mut cursor = 0;
+                for word in index.related(block, RelationshipType::Child) {
+                    let word_text = word.text().unwrap_or_default();
+                    let at = text[cursor..].find(word_text).map_or(cursor, |found| cursor + found);
+                    cursor = (at + word_text.len()).min(text.len());
+                    page.words.push(DocumentWord {
+                        content: word_text.to_string(),
+                        polygon: size.polygon(word),
+                        confidence: confidence(word),
+                        span: Span { offset: (offset + at) as i32, length: word_text.len() as i32 },
+                    });
+                }
+            }
+            Some(BlockType::SelectionElement) => page.selection_marks.push(SelectionMark {
+                state: if block.selection_status() == Some(&SelectionStatus::Selected) {
+                    SelectionMarkState::Selected
+                } else {
+                    SelectionMarkState::Unselected
+                },
+                polygon: size.polygon(block),
+                confidence: confidence(block),
+            }),
+            Some(BlockType::Table) => tables.push(map_table(&index, block)),
+            Some(BlockType::KeyValueSet) if block.entity_types().contains(&EntityType::Key) => {
+                let value = index
+                    .related(block, RelationshipType::Value)
+                    .map(|value| index.text(value))
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                key_value_pairs.push(KeyValuePair { key: index.text(block), value, confidence: confidence(block) });
+            }
+            _ => {}
+        }
+    }
+
+    AnalysisResult {
+        content,
+        pages: pages.into_values().collect(),
+        tables,
+        key_value_pairs,
+        ..Default::default()
+    }
+}
+
+/// A TABLE block's cells, with Textract's 1-based indices made 0-based
+fn map_table(index: &Blocks<'_>, table: &Block) -> DocumentTable {
+    let cells: Vec<TableCell> = index
+        .related(table, RelationshipType::Child)
+        .filter(|cell| cell.block_type() == Some(&BlockType::Cell))
+        .map(|cell| TableCell {
+            kind: if cell.entity_types().contains(&EntityType::ColumnHeader) {
+                CellKind::ColumnHeader
+            } else {
+                CellKind::Content
+            },
+            row_index: cell.row_index().unwrap_or(1) - 1,
+            column_index: cell.column_index().unwrap_or(1) - 1,
+            row_span: cell.row_span().unwrap_or(1),
+            column_span: cell.column_span().unwrap_or(1),
+            content: index.text(cell),
+        })
+        .collect();
+    DocumentTable {
+        row_count: cells.iter().map(|cell| cell.row_index + cell.row_span).max().unwrap_or(0),
+        column_count: cells.iter().map(|cell| cell.column_index + cell.column_span).max().unwrap_or(0),
+        cells,
+    }
+}
+
+/// Azure field name for a Textract summary field type
+fn summary_field_name(model_type: ModelType, field_type: &str) -> Option<&'static str> {
+    let name = match (model_type, field_type) {
+        (ModelType::Invoice, "VENDOR_NAME") => "VendorName",
+        (ModelType::Invoice, "VENDOR_ADDRESS") => "VendorAddress",
+        (ModelType::Invoice, "RECEIVER_NAME") => "CustomerName",
+        (ModelType::Invoice, "RECEIVER_ADDRESS") => "CustomerAddress",
+        (ModelType::Invoice, "INVOICE_RECEIPT_ID") => "InvoiceId",
+        (ModelType::Invoice, "INVOICE_RECEIPT_DATE") => "InvoiceDate",
+        (ModelType::Invoice, "DUE_DATE") => "DueDate",
+        (ModelType::Invoice, "PO_NUMBER") => "PurchaseOrder",
+        (ModelType::Invoice, "SUBTOTAL") => "SubTotal",
+        (ModelType::Invoice, "TAX") => "TotalTax",
+        (ModelType::Invoice, "TOTAL") => "InvoiceTotal",
+        (ModelType::Invoice, "AMOUNT_DUE") => "AmountDue",
+        (ModelType::Invoice, "PAYMENT_TERMS") => "PaymentTerm",
+        (ModelType::Receipt, "VENDOR_NAME") => "MerchantName",
+        (ModelType::Receipt, "VENDOR_ADDRESS") => "MerchantAddress",
+        (ModelType::Receipt, "VENDOR_PHONE") => "MerchantPhoneNumber",
+        (ModelType::Receipt, "INVOICE_RECEIPT_DATE") => "TransactionDate",
+        (ModelType::Receipt, "SUBTOTAL") => "Subtotal",
+        (ModelType::Receipt, "TAX") => "TotalTax",
+        (ModelType::Receipt, "GRATUITY") => "Tip",
+        (ModelType::Receipt, "TOTAL") => "Total",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Azure line item field name for a Textract line item field type
+fn item_field_name(model_type: ModelType, field_type: &str) -> Option<&'static str> {
+    let name = match field_type {
+        "ITEM" => "Description",
+        "QUANTITY" => "Quantity",
+        "UNIT_PRICE" if model_type == ModelType::Invoice => "UnitPrice",
+        "UNIT_PRICE" => "Price",
+        "PRICE" if model_type == ModelType::Invoice => "Amount",
+        "PRICE" => "TotalPrice",
+        "PRODUCT_CODE" => "ProductCode",
+        _ => return None,
+    };
+    Some(name)
+}
+
+fn field_type(field: &ExpenseField) -> &str {
+    field.r#type().and_then(|kind| kind.text()).unwrap_or_default()
+}
+
+fn field_value(field: &ExpenseField) -> Option<&str> {
+    field.value_detection().and_then(|value| value.text()).filter(|value| !value.trim().is_empty())
+}
+
+/// An AnalyzeExpense response as one extracted document per expense document
+///
+/// Summary fields Textract has no Azure name for, including `OTHER` fields,
+/// become key-value pairs under their label.
+fn map_expenses(model_type: ModelType, expenses: &[ExpenseDocument], size: &PageSize) -> AnalysisResult {
+    let mut result = AnalysisResult::default();
+    for expense in expenses {
+        let text = map_blocks(expense.blocks(), size);
+        if result.content.is_empty() {
+            result = text;
+        }
+
+        let mut fields = HashMap::new();
+        let mut confidences = Vec::new();
+        for field in expense.summary_fields() {
+            let Some(value) = field_value(field) else { continue };
+            let field_confidence = field.value_detection().and_then(|value| value.confidence()).unwrap_or(0.0) / 100.0;
+            match summary_field_name(model_type, field_type(field)) {
+                Some(name) => {
+                    if !fields.contains_key(name) {
+                        fields.insert(name.to_string(), DocumentField::String(value.to_string()));
+                        confidences.push(field_confidence);
+                    }
+                }
+                None => result.key_value_pairs.push(KeyValuePair {
+                    key: field
+                        .label_detection()
+                        .and_then(|label| label.text())
+                        .unwrap_or_else(|| field_type(field))
+                        .to_string(),
+                    value: value.to_string(),
+                    confidence: field_confidence,
+                }),
+            }
+        }
+
+        let items: Vec<DocumentField> = expense
+            .line_item_groups()
+            .iter()
+            .flat_map(|group| group.line_items())
+            .map(|item| {
+                DocumentField::Object(
+                    item.line_item_expense_fields()
+                        .iter()
+                        .filter_map(|field| {
+                            let name = item_field_name(model_type, field_type(field))?;
+                            Some((name.to_string(), DocumentField::String(field_value(field)?.to_string())))
+                        })
+                        .collect(),
+                )
+            })
+            .filter(|item| !matches!(item, DocumentField::Object(fields) if fields.is_empty()))
+            .collect();
+        if !items.is_empty() {
+            fields.insert("Items".to_string(), DocumentField::Array(items));
+        }
+
+        result.documents.push(ExtractedDocument {
+            doc_type: if model_type == ModelType::Invoice { "invoice" } else { "receipt" }.to_string(),
+            fields,
+            confidence: if confidences.is_empty() {
+                0.0
+            } else {
+                confidences.iter().sum::<f32>() / confidences.len() as f32
+            },
+        });
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aws_sdk_textract::config::{Credentials, Region};
+    use aws_sdk_textract::types::{
+        BoundingBox, ExpenseDetection, ExpenseType, Geometry, LineItemFields, LineItemGroup, Point as TextractPoint,
+        Relationship,
+    };
+    use serde_json::json;
+    use wiremock::matchers::{header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SIZE: PageSize = PageSize { width: 200.0, height: 100.0, unit: "pixel" };
+
+    fn block(kind: BlockType, id: &str, text: Option<&str>, children: &[&str]) -> Block {
+        let mut block = Block::builder()
+            .block_type(kind)
+            .id(id)
+            .confidence(90.0)
+            .geometry(
+                Geometry::builder()
+                    .bounding_box(BoundingBox::builder().left(0.1).top(0.2).width(0.5).height(0.1).build())
+                    .polygon(TextractPoint::builder().x(0.1).y(0.2).build())
+                    .polygon(TextractPoint::builder().x(0.6).y(0.3).build())
+                    .build(),
+            )
+            .set_text(text.map(str::to_string));
+        if !children.is_empty() {
+            block = block.relationships(
+                Relationship::builder()
+                    .r#type(RelationshipType::Child)
+                    .set_ids(Some(children.iter().map(|id| id.to_string()).collect()))
+                    .build(),
+            );
+        }
+        block.build()
+    }
+
+    #[test]
+    fn test_map_lines_and_words() {
+        let blocks = vec![
+            block(BlockType::Page, "page", None, &["l1", "l2"]),
+            block(BlockType::Line, "l1", Some("Invoice INV-100"), &["w1", "w2"]),
+            block(BlockType::Word, "w1", Some("Invoice"), &[]),
+            block(BlockType::Word, "w2", Some("INV-100"), &[]),
+            block(BlockType::Line, "l2", Some("Total"), &["w3"]),
+            block(BlockType::Word, "w3", Some("Total"), &[]),
+        ];
+        let result = map_blocks(&blocks, &SIZE);
+        assert_eq!(result.content, "Invoice INV-100\nTotal");
+        assert_eq!(result.pages.len(), 1);
+        let page = &result.pages[0];
+        assert_eq!((page.width, page.height, page.unit.as_str()), (200.0, 100.0, "pixel"));
+        assert_eq!(page.lines.len(), 2);
+        assert_eq!(page.lines[1].spans[0].offset, 16);
+        let words: Vec<(&str, i32)> = page.words.iter().map(|word| (word.content.as_str(), word.span.offset)).collect();
+        assert_eq!(words, [("Invoice", 0), ("INV-100", 8), ("Total", 16)]);
+        assert!((page.words[0].confidence - 0.9).abs() < 1e-6);
+        assert!((page.lines[0].polygon[1].x - 120.0).abs() < 1e-3);
+        assert!((page.lines[0].polygon[1].y - 30.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_map_tables_and_key_values() {
+        let mut header_cell = block(BlockType::Cell, "c1", None, &["w1"]);
+        header_cell.row_index = Some(1);
+        header_cell.column_index = Some(1);
+        header_cell.entity_types = Some(vec![EntityType::ColumnHeader]);
+        let mut cell = block(BlockType::Cell, "c2", None, &["w2"]);
+        cell.row_index = Some(2);
+        cell.column_index = Some(2);
+
+        let mut key = block(BlockType::KeyValueSet, "k", None, &["w1"]);
+        key.entity_types = Some(vec![EntityType::Key]);
+        key.relationships.get_or_insert_with(Vec::new).push(
+            Relationship::builder().r#type(RelationshipType::Value).ids("v").build(),
+        );
+        let mut value = block(BlockType::KeyValueSet, "v", None, &["w2"]);
+        value.entity_types = Some(vec![EntityType::Value]);
+
+        let blocks = vec![
+            block(BlockType::Table, "t", None, &["c1", "c2"]),
+            header_cell,
+            cell,
+            key,
+            value,
+            block(BlockType::Word, "w1", Some("Total"), &[]),
+            block(BlockType::Word, "w2", Some("$110.00"), &[]),
+        ];
+        let result = map_blocks(&blocks, &SIZE);
+
+        let table = &result.tables[0];
+        assert_eq!((table.row_count, table.column_count), (2, 2));
+        assert_eq!(table.cells[0].kind, CellKind::ColumnHeader);
+        assert_eq!((table.cells[1].row_index, table.cells[1].column_index), (1, 1));
+        assert_eq!(table.cells[1].content, "$110.00");
+
+        assert_eq!(result.key_value_pairs.len(), 1);
+        assert_eq!(result.key_value_pairs[0].key, "Total");
+        assert_eq!(result.key_value_pairs[0].value, "$110.00");
+    }
+
+    #[test]
+    fn test_map_expense_fields() {
+        let field = |kind: &str, label: Option<&str>, value: &str| {
+            ExpenseField::builder()
+                .r#type(ExpenseType::builder().text(kind).build())
+                .set_label_detection(label.map(|label| ExpenseDetection::builder().text(label).build()))
+                .value_detection(ExpenseDetection::builder().text(value).confidence(80.0).build())
+                .build()
+        };
+        let expense = ExpenseDocument::builder()
+            .summary_fields(field("VENDOR_NAME", None, "Contoso Ltd."))
+            .summary_fields(field("TOTAL", Some("Total"), "$110.00"))
+            .summary_fields(field("OTHER", Some("Account No"), "42"))
+            .line_item_groups(
+                LineItemGroup::builder()
+                    .line_items(
+                        LineItemFields::builder()
+                            .line_item_expense_fields(field("ITEM", None, "Consulting"))
+                            .line_item_expense_fields(field("PRICE", None, "$100.00"))
+                            .build(),
+                    )
+                    .build(),
+            )
+            .build();
+
+        let result = map_expenses(ModelType::Invoice, &[expense], &SIZE);
+        let document = &result.documents[0];
+        assert_eq!(document.doc_type, "invoice");
+        assert_eq!(document.fields["VendorName"].as_string(), Some("Contoso Ltd."));
+        assert_eq!(document.fields["InvoiceTotal"].as_string(), Some("$110.00"));
+        assert!((document.confidence - 0.8).abs() < 1e-6);
+        let DocumentField::Array(items) = &document.fields["Items"] else { panic!("items") };
+        let DocumentField::Object(item) = &items[0] else { panic!("item") };
+        assert_eq!(item["Description"].as_string(), Some("Consulting"));
+        assert_eq!(item["Amount"].as_string(), Some("$100.00"));
+        assert_eq!(result.key_value_pairs[0].key, "Account No");
+        assert_eq!(result.key_value_pairs[0].value, "42");
+    }
+
+    #[test]
+    fn test_page_size() {
+        let mut png = Vec::new();
+        image::DynamicImage::new_rgb8(40, 30)
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+            .unwrap();
+        assert_eq!(PageSize::of(&png), PageSize { width: 40.0, height: 30.0, unit: "pixel" });
+        assert_eq!(PageSize::of(b"not a document"), PageSize::LETTER);
+    }
+
+    fn adapter(server: &MockServer) -> TextractAdapter {
+        let config = aws_sdk_textract::Config::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(Credentials::new("access", "secret", None, None, "test"))
+            .endpoint_url(server.uri())
+            .build();
+        TextractAdapter::new(aws_sdk_textract::Client::from_conf(config))
+    }
+
+    async fn wait(adapter: &TextractAdapter, operation_id: &str) -> (AnalysisOperation, Option<AnalysisResult>) {
+        for _ in 0..200 {
+            let (operation, result) = adapter.get_analysis_result(operation_id).await.unwrap();
+            if operation.status != OperationStatus::Running {
+                return (operation, result);
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("operation still running");
+    }
+
+    fn request(model_type: ModelType) -> AnalyzeDocumentRequest {
+        AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(Bytes::from_static(b"document")),
+            model_type,
+            options: AnalyzeOptions::default(),
+            metadata: None,
+            tenant_id: TenantId::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_detect_document_text() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("x-amz-target", "Textract.DetectDocumentText"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/x-amz-json-1.1")
+                    .set_body_json(json!({
+                        "DocumentMetadata": { "Pages": 1 },
+                        "Blocks": [
+                            { "BlockType": "PAGE", "Id": "p", "Relationships": [{ "Type": "CHILD", "Ids": ["l"] }] },
+                            { "BlockType": "LINE", "Id": "l", "Text": "Hello world", "Confidence": 99.0,
+                              "Relationships": [{ "Type": "CHILD", "Ids": ["a", "b"] }] },
+                            { "BlockType": "WORD", "Id": "a", "Text": "Hello", "Confidence": 99.0 },
+                            { "BlockType": "WORD", "Id": "b", "Text": "world", "Confidence": 98.0 }
+                        ]
+                    })),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let adapter = adapter(&server);
+        let operation = adapter.analyze_document(request(ModelType::Read)).await.unwrap();
+        assert_eq!(operation.status, OperationStatus::Running);
+        let (done, result) = wait(&adapter, &operation.operation_id).await;
+        assert_eq!(done.status, OperationStatus::Succeeded);
+        let result = result.unwrap();
+        assert_eq!(result.model_id, "prebuilt-read");
+        assert_eq!(result.api_version, "textract");
+        assert_eq!(result.content, "Hello world");
+        assert_eq!(result.pages[0].words.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_textract_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(400)
+                    .insert_header("content-type", "application/x-amz-json-1.1")
+                    .set_body_json(json!({
+                        "__type": "UnsupportedDocumentException",
+                        "message": "Request has unsupported document format"
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let adapter = adapter(&server);
+        let operation = adapter.analyze_document(request(ModelType::Layout)).await.unwrap();
+        let (failed, result) = wait(&adapter, &operation.operation_id).await;
+        assert_eq!(failed.status, OperationStatus::Failed);
+        assert!(result.is_none());
+        let error = failed.error.unwrap();
+        assert_eq!(error.code, "UnsupportedDocumentException");
+        assert_eq!(error.status, Some(400));
+
+        assert!(adapter.analyze_document(request(ModelType::W2)).await.is_err());
+        assert!(!adapter.validate_custom_model("model").await.unwrap());
+        assert!(matches!(
+            adapter.get_analysis_result("missing").await,
+            Err(ApplicationError::OperationNotFound(_))
+        ));
+    }
+}
@@ -0,0 +1,261 @@
+/// Tiered operation tracker
+///
+/// Writes every operation and result to a durable tracker, typically
+/// PostgreSQL, and keeps a copy of recent ones in an `InMemoryOperationTracker`.
+/// Reads of those are answered from memory; anything older, or written by
+/// another replica, is read from the durable tracker. Event history, raw
+/// responses and listings always come from the durable tracker, which is the
+/// only one guaranteed to be complete.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{debug, warn};
+
+use crate::application::errors::ApplicationResult;
+use crate::application::ports::{OperationTrackerPort, PrunedRows};
+use crate::domain::{
+    AnalysisOperation, AnalysisResult, DocumentPage, FieldMatch, FieldQuery, OperationEvent, OperationListQuery, OperationStats, OperationStatsQuery,
+    ResultFields, TenantId,
+};
+use crate::infrastructure::tasks::TaskSupervisor;
+use crate::infrastructure::tracker::InMemoryOperationTracker;
+
+/// Longest wait between evictions of operations past the memory retention
+const MAX_EVICTION_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Operation tracker writing through memory to a durable tracker
+pub struct TieredOperationTracker {
+    memory: InMemoryOperationTracker,
+    durable: Arc<dyn OperationTrackerPort>,
+    /// Operations stay in memory this long after their last update
+    retention: Duration,
+}
+
+impl TieredOperationTracker {
+    pub fn new(durable: Arc<dyn OperationTrackerPort>, retention: Duration) -> Self {
+        Self {
+            memory: InMemoryOperationTracker::new(),
+            durable,
+            retention,
+        }
+    }
+
+    /// Drop operations not updated within the retention from memory; the durable copies stay
+    pub async fn evict_expired(&self) -> ApplicationResult<u64> {
+        let cutoff = chrono::Duration::from_std(self.retention)
+            .ok()
+            .and_then(|retention| Utc::now().checked_sub_signed(retention))
+            .unwrap_or(DateTime::<Utc>::MIN_UTC);
+        let evicted = self.memory.prune_operations(cutoff).await?.operations;
+        if evicted > 0 {
+            debug!("Evicted {} operations from memory", evicted);
+        }
+        Ok(evicted)
+    }
+
+    /// Evict expired operations from memory until shutdown
+    pub fn spawn_eviction_task(self: &Arc<Self>, supervisor: &TaskSupervisor) {
+        let tracker = self.clone();
+        let interval = self.retention.min(MAX_EVICTION_INTERVAL).max(Duration::from_secs(1));
+        supervisor.spawn("tracker-eviction", move |shutdown| {
+            let tracker = tracker.clone();
+            async move {
+                let mut ticker = tokio::time::interval(interval);
+                loop {
+                    tokio::select! {
+                        _ = shutdown.cancelled() => return,
+                        _ = ticker.tick() => {
+                            if let Err(e) = tracker.evict_expired().await {
+                                warn!("Failed to evict operations from memory: {}", e);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[async_trait]
+impl OperationTrackerPort for TieredOperationTracker {
+    async fn store_operation(&self, operation: &AnalysisOperation) -> ApplicationResult<()> {
+        self.durable.store_operation(operation).await?;
+        self.memory.store_operation(operation).await
+    }
+
+    async fn get_operation(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisOperation>> {
+        match self.memory.get_operation(operation_id).await? {
+            Some(operation) => Ok(Some(operation)),
+            None => self.durable.get_operation(operation_id).await,
+        }
+    }
+
+    async fn update_operation(&self, operation: &AnalysisOperation) -> ApplicationResult<()> {
+        self.durable.update_operation(operation).await?;
+        self.memory.update_operation(operation).await
+    }
+
+    async fn set_deleted(&self, operation_id: &str, deleted_at: Option<DateTime<Utc>>) -> ApplicationResult<()> {
+        self.durable.set_deleted(operation_id, deleted_at).await?;
+        self.memory.set_deleted(operation_id, deleted_at).await
+    }
+
+    async fn record_event(&self, event: &OperationEvent) -> ApplicationResult<()> {
+        self.durable.record_event(event).await
+    }
+
+    async fn list_events(&self, operation_id: &str) -> ApplicationResult<Vec<OperationEvent>> {
+        self.durable.list_events(operation_id).await
+    }
+
+    async fn store_result(&self, operation_id: &str, result: &AnalysisResult) -> ApplicationResult<()> {
+        self.durable.store_result(operation_id, result).await?;
+        // A result without its operation in memory would outlive the eviction that removes operations
+        if self.memory.get_operation(operation_id).await?.is_some() {
+            self.memory.store_result(operation_id, result).await?;
+        }
+        Ok(())
+    }
+
+    async fn get_result(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisResult>> {
+        match self.memory.get_result(operation_id).await? {
+            Some(result) => Ok(Some(result)),
+            None => self.durable.get_result(operation_id).await,
+        }
+    }
+
+    async fn store_raw_response(&self, operation_id: &str, raw: &serde_json::Value) -> ApplicationResult<()> {
+        self.durable.store_raw_response(operation_id, raw).await
+    }
+
+    async fn get_raw_response(&self, operation_id: &str) -> ApplicationResult<Option<serde_json::Value>> {
+        self.durable.get_raw_response(operation_id).await
+    }
+
+    async fn get_result_fields(
+        &self,
+        operation_id: &str,
+        fields: &ResultFields,
+    ) -> ApplicationResult<Option<AnalysisResult>> {
+        match self.memory.get_result_fields(operation_id, fields).await? {
+            Some(result) => Ok(Some(result)),
+            None => self.durable.get_result_fields(operation_id, fields).await,
+        }
+    }
+
+    async fn get_result_page(&self, operation_id: &str, page_number: i32) -> ApplicationResult<Option<DocumentPage>> {
+        match self.memory.get_result(operation_id).await? {
+            Some(result) => Ok(result.pages.into_iter().find(|page| page.page_number == page_number)),
+            None => self.durable.get_result_page(operation_id, page_number).await,
+        }
+    }
+
+    async fn query_result_fields(
+        &self,
+        operation_id: &str,
+        query: &FieldQuery,
+    ) -> ApplicationResult<Option<Vec<FieldMatch>>> {
+        match self.memory.query_result_fields(operation_id, query).await? {
+            Some(matches) => Ok(Some(matches)),
+            None => self.durable.query_result_fields(operation_id, query).await,
+        }
+    }
+
+    async fn list_operations(
+        &self,
+        tenant: &TenantId,
+        query: &OperationListQuery,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        self.durable.list_operations(tenant, query).await
+    }
+
+    async fn list_operations_created(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        before: Option<DateTime<Utc>>,
+        before_id: Option<&str>,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        self.durable.list_operations_created(from, to, before, before_id, limit).await
+    }
+
+    async fn operation_stats(
+        &self,
+        tenant: &TenantId,
+        query: &OperationStatsQuery,
+    ) -> ApplicationResult<Vec<OperationStats>> {
+        self.durable.operation_stats(tenant, query).await
+    }
+
+    async fn find_operations_by_sha256(
+        &self,
+        tenant: &TenantId,
+        sha256: &str,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        self.durable.find_operations_by_sha256(tenant, sha256, limit).await
+    }
+
+    async fn find_operation_by_document(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        include_deleted: bool,
+    ) -> ApplicationResult<Option<AnalysisOperation>> {
+        self.durable.find_operation_by_document(tenant, document_id, include_deleted).await
+    }
+
+    async fn prune_operations(&self, cutoff: DateTime<Utc>) -> ApplicationResult<PrunedRows> {
+        let pruned = self.durable.prune_operations(cutoff).await?;
+        self.memory.prune_operations(cutoff).await?;
+        Ok(pruned)
+    }
+
+    async fn purge_deleted_operations(&self, cutoff: DateTime<Utc>) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let purged = self.durable.purge_deleted_operations(cutoff).await?;
+        self.memory.purge_deleted_operations(cutoff).await?;
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::{ModelType, OperationStatus};
+
+    #[tokio::test]
+    async fn test_tiered_tracker() {
+        let durable = Arc::new(InMemoryOperationTracker::new());
+        let tiered = TieredOperationTracker::new(durable.clone(), Duration::from_secs(3600));
+        let mut operation = AnalysisOperation::new(ModelType::Read);
+        let id = operation.operation_id.clone();
+        tiered.store_operation(&operation).await.unwrap();
+        operation.update_status(OperationStatus::Succeeded);
+        tiered.update_operation(&operation).await.unwrap();
+        let result = AnalysisResult {
+            content: "recent".to_string(),
+            ..Default::default()
+        };
+        tiered.store_result(&id, &result).await.unwrap();
+
+        // Written through to the durable tracker
+        assert_eq!(durable.get_operation(&id).await.unwrap().unwrap().status, OperationStatus::Succeeded);
+        assert_eq!(durable.get_result(&id).await.unwrap().unwrap().content, "recent");
+
+        // Recent operations are read from memory
+        durable.store_result(&id, &AnalysisResult::default()).await.unwrap();
+        assert_eq!(tiered.get_result(&id).await.unwrap().unwrap().content, "recent");
+        assert_eq!(tiered.evict_expired().await.unwrap(), 0);
+
+        // Older ones from the durable tracker
+        let tiered = TieredOperationTracker::new(durable.clone(), Duration::ZERO);
+        let other = AnalysisOperation::new(ModelType::Layout);
+        tiered.store_operation(&other).await.unwrap();
+        assert_eq!(tiered.evict_expired().await.unwrap(), 1);
+        assert!(tiered.get_operation(&other.operation_id).await.unwrap().is_some());
+        assert_eq!(tiered.get_result(&id).await.unwrap().unwrap().content, "");
+    }
+}
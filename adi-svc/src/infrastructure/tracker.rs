@@ -4,19 +4,60 @@
 /// In production, this would use a database like Redis or PostgreSQL.
 
 use async_trait::async_trait;
-use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use tracing::{debug, info};
 
 use crate::application::errors::{ApplicationError, ApplicationResult};
-use crate::application::ports::OperationTrackerPort;
-use crate::domain::{AnalysisOperation, AnalysisResult};
+use crate::application::ports::{
+    AuditLogPort, EmbeddingStorePort, ExportJobPort, IngestLedgerPort, JobQueuePort, OperationTrackerPort, PipelineRunPort, PrunedRows, QuotaPort,
+    ResultRevisionPort, UsagePort, WorkQueuePort,
+};
+use crate::domain::{
+    AnalysisJob, AnalysisOperation, AnalysisResult, AuditEntry, AuditQuery, cosine_similarity, ChunkMatch, EmbeddedChunk, ExportJob, ExportStatus, JobStatus, ModelType, OperationEvent, OperationListQuery, operation_stats, OperationStats, OperationStatsQuery,
+    OperationStatus, PipelineRun, PipelineStatus, Quota, QuotaPeriod, ResultRevision, TenantId, UsageQuery, UsageRecord, WorkLease, WorkQueue,
+};
+
+/// Lease state for one (queue, operation) pair
+struct LeaseEntry {
+    lease: WorkLease,
+    tenant: TenantId,
+    completed: bool,
+}
+
+impl LeaseEntry {
+    /// Whether `worker_id`, acting for `tenant`, still holds this lease
+    fn is_live(&self, tenant: &TenantId, worker_id: &str, now: DateTime<Utc>) -> bool {
+        !self.completed && &self.tenant == tenant && self.lease.worker_id == worker_id && self.lease.expires_at > now
+    }
+}
+
+/// Usage totals are keyed by (day, tenant, model)
+type UsageKey = (chrono::NaiveDate, TenantId, ModelType);
+
+/// An operation's embedded chunks, with its tenant
+type StoredChunks = (TenantId, Vec<EmbeddedChunk>);
 
 /// In-memory operation tracker
 pub struct InMemoryOperationTracker {
     operations: Arc<RwLock<HashMap<String, AnalysisOperation>>>,
     results: Arc<RwLock<HashMap<String, AnalysisResult>>>,
+    raw_responses: Arc<RwLock<HashMap<String, serde_json::Value>>>,
+    events: Arc<RwLock<HashMap<String, Vec<OperationEvent>>>>,
+    audit_log: Arc<RwLock<Vec<AuditEntry>>>,
+    /// Daily usage totals, kept in report order
+    usage: Arc<RwLock<BTreeMap<UsageKey, UsageRecord>>>,
+    quotas: Arc<RwLock<BTreeMap<(TenantId, QuotaPeriod), Quota>>>,
+    leases: Arc<RwLock<HashMap<(WorkQueue, String), LeaseEntry>>>,
+    jobs: Arc<RwLock<HashMap<String, AnalysisJob>>>,
+    /// Ingested item versions by (source, item)
+    ingested: Arc<RwLock<HashMap<(String, String), String>>>,
+    pipeline_runs: Arc<RwLock<HashMap<String, PipelineRun>>>,
+    exports: Arc<RwLock<HashMap<String, ExportJob>>>,
+    revisions: Arc<RwLock<HashMap<String, Vec<ResultRevision>>>>,
+    chunks: Arc<RwLock<HashMap<String, StoredChunks>>>,
 }
 
 impl InMemoryOperationTracker {
@@ -24,7 +65,55 @@ impl InMemoryOperationTracker {
         Self {
             operations: Arc::new(RwLock::new(HashMap::new())),
             results: Arc::new(RwLock::new(HashMap::new())),
+            raw_responses: Arc::new(RwLock::new(HashMap::new())),
+            events: Arc::new(RwLock::new(HashMap::new())),
+            audit_log: Arc::new(RwLock::new(Vec::new())),
+            usage: Arc::new(RwLock::new(BTreeMap::new())),
+            quotas: Arc::new(RwLock::new(BTreeMap::new())),
+            leases: Arc::new(RwLock::new(HashMap::new())),
+            jobs: Arc::new(RwLock::new(HashMap::new())),
+            ingested: Arc::new(RwLock::new(HashMap::new())),
+            pipeline_runs: Arc::new(RwLock::new(HashMap::new())),
+            exports: Arc::new(RwLock::new(HashMap::new())),
+            revisions: Arc::new(RwLock::new(HashMap::new())),
+            chunks: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+    
+    /// Remove the operations passing `expired` and everything kept for them
+    async fn remove_operations(
+        &self,
+        expired: impl Fn(&AnalysisOperation) -> bool,
+    ) -> (Vec<AnalysisOperation>, PrunedRows) {
+        let mut operations = self.operations.write().await;
+        let mut results = self.results.write().await;
+        let mut raw_responses = self.raw_responses.write().await;
+        let mut events = self.events.write().await;
+        let mut leases = self.leases.write().await;
+        let mut revisions = self.revisions.write().await;
+        let mut chunks = self.chunks.write().await;
+        
+        let ids: Vec<String> = operations
+            .values()
+            .filter(|op| expired(op))
+            .map(|op| op.operation_id.clone())
+            .collect();
+        
+        let mut removed = Vec::with_capacity(ids.len());
+        let mut pruned = PrunedRows::default();
+        for operation_id in ids {
+            removed.extend(operations.remove(&operation_id));
+            pruned.operations += 1;
+            if results.remove(&operation_id).is_some() {
+                pruned.results += 1;
+            }
+            raw_responses.remove(&operation_id);
+            events.remove(&operation_id);
+            revisions.remove(&operation_id);
+            chunks.remove(&operation_id);
+            leases.retain(|(_, leased_id), _| *leased_id != operation_id);
         }
+        (removed, pruned)
     }
 }
 
@@ -53,11 +142,30 @@ impl OperationTrackerPort for InMemoryOperationTracker {
     async fn update_operation(&self, operation: &AnalysisOperation) -> ApplicationResult<()> {
         debug!("Updating operation: {}", operation.operation_id);
         let mut operations = self.operations.write().await;
-        operations.insert(operation.operation_id.clone(), operation.clone());
+        let deleted_at = operations.get(&operation.operation_id).and_then(|stored| stored.deleted_at);
+        operations.insert(operation.operation_id.clone(), AnalysisOperation { deleted_at, ..operation.clone() });
         info!("Operation updated: {}", operation.operation_id);
         Ok(())
     }
     
+    async fn set_deleted(&self, operation_id: &str, deleted_at: Option<DateTime<Utc>>) -> ApplicationResult<()> {
+        if let Some(operation) = self.operations.write().await.get_mut(operation_id) {
+            operation.deleted_at = deleted_at;
+        }
+        Ok(())
+    }
+    
+    async fn record_event(&self, event: &OperationEvent) -> ApplicationResult<()> {
+        let mut events = self.events.write().await;
+        events.entry(event.operation_id.clone()).or_default().push(event.clone());
+        Ok(())
+    }
+    
+    async fn list_events(&self, operation_id: &str) -> ApplicationResult<Vec<OperationEvent>> {
+        let events = self.events.read().await;
+        Ok(events.get(operation_id).cloned().unwrap_or_default())
+    }
+    
     async fn store_result(
         &self,
         operation_id: &str,
@@ -75,12 +183,504 @@ impl OperationTrackerPort for InMemoryOperationTracker {
         let results = self.results.read().await;
         Ok(results.get(operation_id).cloned())
     }
+    
+    async fn store_raw_response(
+        &self,
+        operation_id: &str,
+        raw: &serde_json::Value,
+    ) -> ApplicationResult<()> {
+        let mut raw_responses = self.raw_responses.write().await;
+        raw_responses.insert(operation_id.to_string(), raw.clone());
+        Ok(())
+    }
+    
+    async fn get_raw_response(&self, operation_id: &str) -> ApplicationResult<Option<serde_json::Value>> {
+        let raw_responses = self.raw_responses.read().await;
+        Ok(raw_responses.get(operation_id).cloned())
+    }
+    
+    async fn list_operations(
+        &self,
+        tenant: &TenantId,
+        query: &OperationListQuery,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let operations = self.operations.read().await;
+        let mut matches: Vec<AnalysisOperation> = operations
+            .values()
+            .filter(|op| op.tenant_id == *tenant)
+            .filter(|op| query.status.iter().all(|status| op.status == *status))
+            .filter(|op| {
+                query.before.iter().all(|before| match &query.before_id {
+                    Some(before_id) => (op.created_at, &op.operation_id) < (*before, before_id),
+                    None => op.created_at < *before,
+                })
+            })
+            .filter(|op| query.review.iter().all(|review| op.review.as_ref().map(|r| r.status) == Some(*review)))
+            .filter(|op| query.include_deleted || !op.is_deleted())
+            .cloned()
+            .collect();
+        matches.sort_by(|a, b| (b.created_at, &b.operation_id).cmp(&(a.created_at, &a.operation_id)));
+        matches.truncate(query.limit as usize);
+        Ok(matches)
+    }
+    
+    async fn list_operations_created(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        before: Option<DateTime<Utc>>,
+        before_id: Option<&str>,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let operations = self.operations.read().await;
+        let mut matches: Vec<AnalysisOperation> = operations
+            .values()
+            .filter(|op| op.created_at >= from && op.created_at < to && !op.is_deleted())
+            .filter(|op| {
+                before.iter().all(|before| match before_id {
+                    Some(before_id) => (op.created_at, op.operation_id.as_str()) < (*before, before_id),
+                    None => op.created_at < *before,
+                })
+            })
+            .cloned()
+            .collect();
+        matches.sort_by(|a, b| (b.created_at, &b.operation_id).cmp(&(a.created_at, &a.operation_id)));
+        matches.truncate(limit as usize);
+        Ok(matches)
+    }
+    
+    async fn operation_stats(
+        &self,
+        tenant: &TenantId,
+        query: &OperationStatsQuery,
+    ) -> ApplicationResult<Vec<OperationStats>> {
+        let operations = self.operations.read().await;
+        Ok(operation_stats(operations.values().filter(|op| op.tenant_id == *tenant && !op.is_deleted()), query))
+    }
+    
+    async fn find_operations_by_sha256(
+        &self,
+        tenant: &TenantId,
+        sha256: &str,
+        limit: u32,
+    ) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let operations = self.operations.read().await;
+        let mut matches: Vec<AnalysisOperation> = operations
+            .values()
+            .filter(|op| op.tenant_id == *tenant && op.content_sha256.as_deref() == Some(sha256) && !op.is_deleted())
+            .cloned()
+            .collect();
+        matches.sort_by_key(|op| std::cmp::Reverse(op.created_at));
+        matches.truncate(limit as usize);
+        Ok(matches)
+    }
+    
+    async fn find_operation_by_document(
+        &self,
+        tenant: &TenantId,
+        document_id: &str,
+        include_deleted: bool,
+    ) -> ApplicationResult<Option<AnalysisOperation>> {
+        let operations = self.operations.read().await;
+        Ok(operations
+            .values()
+            .filter(|op| op.tenant_id == *tenant && op.document_id.as_deref() == Some(document_id))
+            .filter(|op| include_deleted || !op.is_deleted())
+            .min_by_key(|op| op.created_at)
+            .cloned())
+    }
+    
+    async fn prune_operations(&self, cutoff: DateTime<Utc>) -> ApplicationResult<PrunedRows> {
+        let (_, pruned) = self.remove_operations(|op| op.last_updated < cutoff).await;
+        self.jobs
+            .write()
+            .await
+            .retain(|_, job| job.status.is_pending() || job.updated_at >= cutoff);
+        
+        info!("Pruned {} operations and {} results", pruned.operations, pruned.results);
+        Ok(pruned)
+    }
+    
+    async fn purge_deleted_operations(&self, cutoff: DateTime<Utc>) -> ApplicationResult<Vec<AnalysisOperation>> {
+        let (purged, _) = self
+            .remove_operations(|op| op.deleted_at.is_some_and(|deleted_at| deleted_at < cutoff))
+            .await;
+        info!("Purged {} deleted operations", purged.len());
+        Ok(purged)
+    }
+}
+
+#[async_trait]
+impl WorkQueuePort for InMemoryOperationTracker {
+    async fn claim(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        worker_id: &str,
+        lease: chrono::Duration,
+        limit: u32,
+    ) -> ApplicationResult<Vec<WorkLease>> {
+        let operations = self.operations.read().await;
+        let mut leases = self.leases.write().await;
+        let now = Utc::now();
+        
+        let mut candidates: Vec<&AnalysisOperation> = operations
+            .values()
+            .filter(|op| &op.tenant_id == tenant && op.status == OperationStatus::Succeeded && !op.is_deleted())
+            .filter(|op| {
+                queue
+                    .review_status()
+                    .is_none_or(|status| op.review.as_ref().is_some_and(|review| review.status == status))
+            })
+            .filter(|op| match leases.get(&(queue, op.operation_id.clone())) {
+                Some(entry) => !entry.completed && entry.lease.expires_at <= now,
+                None => true,
+            })
+            .collect();
+        candidates.sort_by_key(|op| op.last_updated);
+        
+        let claimed: Vec<WorkLease> = candidates
+            .into_iter()
+            .take(limit as usize)
+            .map(|op| WorkLease {
+                queue,
+                operation_id: op.operation_id.clone(),
+                worker_id: worker_id.to_string(),
+                claimed_at: now,
+                expires_at: now + lease,
+            })
+            .collect();
+        
+        for lease in &claimed {
+            leases.insert(
+                (queue, lease.operation_id.clone()),
+                LeaseEntry { lease: lease.clone(), tenant: tenant.clone(), completed: false },
+            );
+        }
+        
+        debug!("Worker {} claimed {} {} items", worker_id, claimed.len(), queue.as_str());
+        Ok(claimed)
+    }
+    
+    async fn heartbeat(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+        lease: chrono::Duration,
+    ) -> ApplicationResult<Option<WorkLease>> {
+        let mut leases = self.leases.write().await;
+        let now = Utc::now();
+        
+        Ok(leases
+            .get_mut(&(queue, operation_id.to_string()))
+            .filter(|entry| entry.is_live(tenant, worker_id, now))
+            .map(|entry| {
+                entry.lease.expires_at = now + lease;
+                entry.lease.clone()
+            }))
+    }
+    
+    async fn complete(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+    ) -> ApplicationResult<bool> {
+        let mut leases = self.leases.write().await;
+        
+        match leases.get_mut(&(queue, operation_id.to_string())) {
+            Some(entry) if entry.is_live(tenant, worker_id, Utc::now()) => {
+                entry.completed = true;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+    
+    async fn release(
+        &self,
+        tenant: &TenantId,
+        queue: WorkQueue,
+        operation_id: &str,
+        worker_id: &str,
+    ) -> ApplicationResult<bool> {
+        let mut leases = self.leases.write().await;
+        let key = (queue, operation_id.to_string());
+        
+        match leases.get(&key) {
+            Some(entry) if entry.is_live(tenant, worker_id, Utc::now()) => {
+                leases.remove(&key);
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+}
+
+#[async_trait]
+impl JobQueuePort for InMemoryOperationTracker {
+    async fn enqueue_job(&self, job: &AnalysisJob) -> ApplicationResult<()> {
+        let mut jobs = self.jobs.write().await;
+        jobs.insert(job.job_id.clone(), job.clone());
+        Ok(())
+    }
+    
+    async fn claim_job(&self, stale_after: chrono::Duration) -> ApplicationResult<Option<AnalysisJob>> {
+        let mut jobs = self.jobs.write().await;
+        let now = Utc::now();
+        let next = jobs
+            .values_mut()
+            .filter(|job| match job.status {
+                JobStatus::Queued => job.next_attempt_at <= now,
+                JobStatus::Running => job.updated_at + stale_after <= now,
+                _ => false,
+            })
+            .min_by_key(|job| (std::cmp::Reverse(job.priority), job.created_at));
+        Ok(next.map(|job| {
+            job.status = JobStatus::Running;
+            job.attempts += 1;
+            job.updated_at = now;
+            job.clone()
+        }))
+    }
+    
+    async fn update_job(&self, job: &AnalysisJob) -> ApplicationResult<()> {
+        let mut jobs = self.jobs.write().await;
+        jobs.insert(job.job_id.clone(), job.clone());
+        Ok(())
+    }
+    
+    async fn get_job(&self, job_id: &str) -> ApplicationResult<Option<AnalysisJob>> {
+        let jobs = self.jobs.read().await;
+        Ok(jobs.get(job_id).cloned())
+    }
+    
+    async fn job_for_operation(&self, operation_id: &str) -> ApplicationResult<Option<AnalysisJob>> {
+        let jobs = self.jobs.read().await;
+        Ok(jobs
+            .values()
+            .find(|job| job.operation_id.as_deref() == Some(operation_id))
+            .cloned())
+    }
+}
+
+#[async_trait]
+impl IngestLedgerPort for InMemoryOperationTracker {
+    async fn ingested_version(&self, source: &str, item: &str) -> ApplicationResult<Option<String>> {
+        let ingested = self.ingested.read().await;
+        Ok(ingested.get(&(source.to_string(), item.to_string())).cloned())
+    }
+    
+    async fn record_ingested(
+        &self,
+        source: &str,
+        item: &str,
+        version: &str,
+        _operation_id: Option<&str>,
+    ) -> ApplicationResult<()> {
+        let mut ingested = self.ingested.write().await;
+        ingested.insert((source.to_string(), item.to_string()), version.to_string());
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl PipelineRunPort for InMemoryOperationTracker {
+    async fn create_run(&self, run: &PipelineRun) -> ApplicationResult<()> {
+        let mut runs = self.pipeline_runs.write().await;
+        runs.insert(run.run_id.clone(), run.clone());
+        Ok(())
+    }
+    
+    async fn update_run(&self, run: &PipelineRun) -> ApplicationResult<()> {
+        let mut runs = self.pipeline_runs.write().await;
+        runs.insert(run.run_id.clone(), run.clone());
+        Ok(())
+    }
+    
+    async fn get_run(&self, run_id: &str) -> ApplicationResult<Option<PipelineRun>> {
+        let runs = self.pipeline_runs.read().await;
+        Ok(runs.get(run_id).cloned())
+    }
+    
+    async fn stale_runs(&self, stale_after: chrono::Duration) -> ApplicationResult<Vec<PipelineRun>> {
+        let now = Utc::now();
+        let runs = self.pipeline_runs.read().await;
+        Ok(runs
+            .values()
+            .filter(|run| run.status == PipelineStatus::Running && run.updated_at + stale_after <= now)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ExportJobPort for InMemoryOperationTracker {
+    async fn create_export(&self, job: &ExportJob) -> ApplicationResult<()> {
+        let mut exports = self.exports.write().await;
+        exports.insert(job.export_id.clone(), job.clone());
+        Ok(())
+    }
+    
+    async fn update_export(&self, job: &ExportJob) -> ApplicationResult<()> {
+        let mut exports = self.exports.write().await;
+        exports.insert(job.export_id.clone(), job.clone());
+        Ok(())
+    }
+    
+    async fn get_export(&self, export_id: &str) -> ApplicationResult<Option<ExportJob>> {
+        let exports = self.exports.read().await;
+        Ok(exports.get(export_id).cloned())
+    }
+    
+    async fn stale_exports(&self, stale_after: chrono::Duration) -> ApplicationResult<Vec<ExportJob>> {
+        let now = Utc::now();
+        let exports = self.exports.read().await;
+        Ok(exports
+            .values()
+            .filter(|job| job.status == ExportStatus::Running && job.updated_at + stale_after <= now)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl ResultRevisionPort for InMemoryOperationTracker {
+    async fn store_revision(&self, revision: &ResultRevision) -> ApplicationResult<()> {
+        let mut revisions = self.revisions.write().await;
+        let stored = revisions.entry(revision.operation_id.clone()).or_default();
+        if stored.iter().any(|existing| existing.revision == revision.revision) {
+            return Err(ApplicationError::RevisionConflict {
+                operation_id: revision.operation_id.clone(),
+                revision: revision.revision,
+            });
+        }
+        stored.push(revision.clone());
+        stored.sort_by_key(|stored| stored.revision);
+        Ok(())
+    }
+    
+    async fn list_revisions(&self, operation_id: &str) -> ApplicationResult<Vec<ResultRevision>> {
+        let revisions = self.revisions.read().await;
+        Ok(revisions.get(operation_id).cloned().unwrap_or_default())
+    }
+}
+
+#[async_trait]
+impl EmbeddingStorePort for InMemoryOperationTracker {
+    async fn store_chunks(&self, operation_id: &str, tenant: &TenantId, chunks: &[EmbeddedChunk]) -> ApplicationResult<()> {
+        let mut stored = self.chunks.write().await;
+        stored.insert(operation_id.to_string(), (tenant.clone(), chunks.to_vec()));
+        Ok(())
+    }
+    
+    async fn search_chunks(&self, tenant: &TenantId, embedding: &[f32], limit: u32) -> ApplicationResult<Vec<ChunkMatch>> {
+        let stored = self.chunks.read().await;
+        let mut matches: Vec<ChunkMatch> = stored
+            .iter()
+            .filter(|(_, (owner, _))| owner == tenant)
+            .flat_map(|(operation_id, (_, chunks))| {
+                chunks.iter().map(move |chunk| {
+                    let score = cosine_similarity(&chunk.embedding, embedding);
+                    ChunkMatch::new(operation_id, chunk.index, chunk.page_number, &chunk.text, score)
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| {
+            b.score
+                .total_cmp(&a.score)
+                .then_with(|| a.operation_id.cmp(&b.operation_id))
+                .then(a.chunk_index.cmp(&b.chunk_index))
+        });
+        matches.truncate(limit as usize);
+        Ok(matches)
+    }
+}
+
+#[async_trait]
+impl AuditLogPort for InMemoryOperationTracker {
+    async fn record(&self, entry: &AuditEntry) -> ApplicationResult<()> {
+        self.audit_log.write().await.push(entry.clone());
+        Ok(())
+    }
+    
+    async fn query(&self, query: &AuditQuery) -> ApplicationResult<Vec<AuditEntry>> {
+        let audit_log = self.audit_log.read().await;
+        Ok(audit_log
+            .iter()
+            .rev()
+            .filter(|entry| entry.matches(query))
+            .take(query.limit as usize)
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl UsagePort for InMemoryOperationTracker {
+    async fn record_usage(
+        &self,
+        tenant: &TenantId,
+        day: chrono::NaiveDate,
+        model: ModelType,
+        pages: u32,
+    ) -> ApplicationResult<()> {
+        let mut usage = self.usage.write().await;
+        let record = usage
+            .entry((day, tenant.clone(), model))
+            .or_insert_with(|| UsageRecord {
+                tenant_id: tenant.clone(),
+                day,
+                model,
+                operations: 0,
+                pages: 0,
+            });
+        record.operations += 1;
+        record.pages += u64::from(pages);
+        Ok(())
+    }
+    
+    async fn usage(&self, query: &UsageQuery) -> ApplicationResult<Vec<UsageRecord>> {
+        let usage = self.usage.read().await;
+        Ok(usage
+            .values()
+            .filter(|record| query.contains(record.day))
+            .filter(|record| query.tenant.iter().all(|tenant| *tenant == record.tenant_id))
+            .cloned()
+            .collect())
+    }
+}
+
+#[async_trait]
+impl QuotaPort for InMemoryOperationTracker {
+    async fn set_quota(&self, quota: &Quota) -> ApplicationResult<()> {
+        let mut quotas = self.quotas.write().await;
+        quotas.insert((quota.tenant_id.clone(), quota.period), quota.clone());
+        Ok(())
+    }
+    
+    async fn delete_quota(&self, tenant: &TenantId, period: QuotaPeriod) -> ApplicationResult<bool> {
+        let mut quotas = self.quotas.write().await;
+        Ok(quotas.remove(&(tenant.clone(), period)).is_some())
+    }
+    
+    async fn list_quotas(&self, tenant: Option<&TenantId>) -> ApplicationResult<Vec<Quota>> {
+        let quotas = self.quotas.read().await;
+        Ok(quotas
+            .values()
+            .filter(|quota| tenant.iter().all(|tenant| **tenant == quota.tenant_id))
+            .cloned()
+            .collect())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::domain::{ModelType, OperationStatus};
+    use crate::domain::{JobPriority, ModelType, OperationEventKind, OperationStatus, ReviewState};
 
     #[tokio::test]
     async fn test_store_and_get_operation() {
@@ -126,5 +726,244 @@ mod tests {
         
         assert!(retrieved.is_some());
     }
-}
 
+    #[tokio::test]
+    async fn test_prune_operations() {
+        let tracker = InMemoryOperationTracker::new();
+        let mut expired = AnalysisOperation::new(ModelType::Read);
+        expired.last_updated = Utc::now() - chrono::Duration::days(30);
+        let fresh = AnalysisOperation::new(ModelType::Read);
+        
+        tracker.store_operation(&expired).await.unwrap();
+        tracker.store_operation(&fresh).await.unwrap();
+        tracker.store_result(&expired.operation_id, &AnalysisResult::default()).await.unwrap();
+        tracker
+            .record_event(&OperationEvent::new(&expired.operation_id, OperationEventKind::Submitted, None))
+            .await
+            .unwrap();
+        
+        let pruned = tracker
+            .prune_operations(Utc::now() - chrono::Duration::days(7))
+            .await
+            .unwrap();
+        
+        assert_eq!(pruned, PrunedRows { operations: 1, results: 1 });
+        assert!(tracker.get_operation(&expired.operation_id).await.unwrap().is_none());
+        assert!(tracker.list_events(&expired.operation_id).await.unwrap().is_empty());
+        assert!(tracker.get_operation(&fresh.operation_id).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_list_operations_pages_through_ties() {
+        let tracker = InMemoryOperationTracker::new();
+        let tenant = TenantId::default();
+        let created_at = Utc::now();
+        for _ in 0..3 {
+            tracker.store_operation(&AnalysisOperation { created_at, ..AnalysisOperation::new(ModelType::Read) }).await.unwrap();
+        }
+        
+        let mut query = OperationListQuery { limit: 2, ..Default::default() };
+        let first = tracker.list_operations(&tenant, &query).await.unwrap();
+        assert_eq!(first.len(), 2);
+        query.before = Some(first[1].created_at);
+        query.before_id = Some(first[1].operation_id.clone());
+        let second = tracker.list_operations(&tenant, &query).await.unwrap();
+        assert_eq!(second.len(), 1);
+        assert!(first.iter().all(|op| op.operation_id != second[0].operation_id));
+    }
+    
+    #[tokio::test]
+    async fn test_list_operations_created_pages_through_ties() {
+        let tracker = InMemoryOperationTracker::new();
+        let created_at = Utc::now();
+        for _ in 0..3 {
+            tracker.store_operation(&AnalysisOperation { created_at, ..AnalysisOperation::new(ModelType::Read) }).await.unwrap();
+        }
+        let (from, to) = (created_at - chrono::Duration::hours(1), created_at + chrono::Duration::hours(1));
+        
+        let first = tracker.list_operations_created(from, to, None, None, 2).await.unwrap();
+        assert_eq!(first.len(), 2);
+        let second = tracker
+            .list_operations_created(from, to, Some(first[1].created_at), Some(&first[1].operation_id), 2)
+            .await
+            .unwrap();
+        assert_eq!(second.len(), 1);
+        assert!(first.iter().all(|op| op.operation_id != second[0].operation_id));
+    }
+    
+    #[tokio::test]
+    async fn test_stale_exports() {
+        let tracker = InMemoryOperationTracker::new();
+        let stale_after = chrono::Duration::minutes(15);
+        let mut stale = ExportJob::new(TenantId::default(), Default::default());
+        stale.updated_at = Utc::now() - chrono::Duration::hours(1);
+        let mut finished = stale.clone();
+        finished.export_id = "finished".to_string();
+        finished.status = ExportStatus::Succeeded;
+        tracker.create_export(&stale).await.unwrap();
+        tracker.create_export(&finished).await.unwrap();
+        tracker.create_export(&ExportJob::new(TenantId::default(), Default::default())).await.unwrap();
+        
+        let found = tracker.stale_exports(stale_after).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].export_id, stale.export_id);
+    }
+    
+    #[tokio::test]
+    async fn test_find_operation_by_document() {
+        let tracker = InMemoryOperationTracker::new();
+        let tenant = TenantId::default();
+        let mut deleted = AnalysisOperation::new(ModelType::Invoice);
+        deleted.document_id = Some("doc-1".to_string());
+        deleted.deleted_at = Some(Utc::now());
+        tracker.store_operation(&deleted).await.unwrap();
+        
+        assert!(tracker.find_operation_by_document(&tenant, "doc-1", false).await.unwrap().is_none());
+        let found = tracker.find_operation_by_document(&tenant, "doc-1", true).await.unwrap();
+        assert_eq!(found.unwrap().operation_id, deleted.operation_id);
+        
+        let live = AnalysisOperation { document_id: deleted.document_id.clone(), ..AnalysisOperation::new(ModelType::Read) };
+        tracker.store_operation(&live).await.unwrap();
+        let found = tracker.find_operation_by_document(&tenant, "doc-1", false).await.unwrap();
+        assert_eq!(found.unwrap().operation_id, live.operation_id);
+    }
+    
+    #[tokio::test]
+    async fn test_work_queue_leases() {
+        let tracker = InMemoryOperationTracker::new();
+        let tenant = TenantId::default();
+        let mut operation = AnalysisOperation::new(ModelType::Invoice);
+        operation.update_status(OperationStatus::Succeeded);
+        operation.review = Some(ReviewState::needs_review(Vec::new()));
+        tracker.store_operation(&operation).await.unwrap();
+        tracker.store_operation(&AnalysisOperation::new(ModelType::Read)).await.unwrap();
+        
+        // Succeeded operations that need no review are only exported
+        let mut unflagged = AnalysisOperation::new(ModelType::Receipt);
+        unflagged.update_status(OperationStatus::Succeeded);
+        unflagged.last_updated = operation.last_updated + chrono::Duration::seconds(1);
+        tracker.store_operation(&unflagged).await.unwrap();
+        
+        let lease = chrono::Duration::minutes(5);
+        let claimed = tracker.claim(&tenant, WorkQueue::Review, "worker-a", lease, 10).await.unwrap();
+        assert_eq!(claimed.len(), 1);
+        assert_eq!(claimed[0].operation_id, operation.operation_id);
+        
+        // Held items are not handed out again, but other queues see them
+        assert!(tracker.claim(&tenant, WorkQueue::Review, "worker-b", lease, 10).await.unwrap().is_empty());
+        assert_eq!(tracker.claim(&tenant, WorkQueue::Export, "worker-b", lease, 1).await.unwrap()[0].operation_id, operation.operation_id);
+        
+        let id = &operation.operation_id;
+        assert!(tracker.heartbeat(&tenant, WorkQueue::Review, id, "worker-b", lease).await.unwrap().is_none());
+        assert!(tracker.heartbeat(&tenant, WorkQueue::Review, id, "worker-a", lease).await.unwrap().is_some());
+        assert!(tracker.complete(&tenant, WorkQueue::Review, id, "worker-a").await.unwrap());
+        assert!(!tracker.release(&tenant, WorkQueue::Review, id, "worker-a").await.unwrap());
+        assert!(tracker.claim(&tenant, WorkQueue::Review, "worker-b", lease, 10).await.unwrap().is_empty());
+        
+        // Expired leases can be reclaimed
+        assert!(tracker.release(&tenant, WorkQueue::Export, id, "worker-b").await.unwrap());
+        tracker.claim(&tenant, WorkQueue::Export, "worker-c", chrono::Duration::zero(), 10).await.unwrap();
+        assert_eq!(tracker.claim(&tenant, WorkQueue::Export, "worker-d", lease, 10).await.unwrap().len(), 2);
+        
+        // Other tenants neither see the item nor can touch its lease
+        let acme = TenantId::new("acme").unwrap();
+        assert!(tracker.claim(&acme, WorkQueue::Review, "worker-e", lease, 10).await.unwrap().is_empty());
+        assert!(tracker.heartbeat(&acme, WorkQueue::Export, id, "worker-d", lease).await.unwrap().is_none());
+        assert!(!tracker.complete(&acme, WorkQueue::Export, id, "worker-d").await.unwrap());
+        assert!(!tracker.release(&acme, WorkQueue::Export, id, "worker-d").await.unwrap());
+        assert!(tracker.complete(&tenant, WorkQueue::Export, id, "worker-d").await.unwrap());
+    }
+    
+    #[tokio::test]
+    async fn test_usage_totals() {
+        let tracker = InMemoryOperationTracker::new();
+        let acme = TenantId::new("acme").unwrap();
+        let day = chrono::NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        tracker.record_usage(&acme, day, ModelType::Invoice, 3).await.unwrap();
+        tracker.record_usage(&acme, day, ModelType::Invoice, 2).await.unwrap();
+        tracker.record_usage(&acme, day.succ_opt().unwrap(), ModelType::Read, 1).await.unwrap();
+        tracker.record_usage(&TenantId::default(), day, ModelType::Read, 4).await.unwrap();
+        
+        let query = UsageQuery {
+            tenant: Some(acme),
+            from: day,
+            to: day,
+        };
+        let usage = tracker.usage(&query).await.unwrap();
+        assert_eq!(usage.len(), 1);
+        assert_eq!((usage[0].operations, usage[0].pages), (2, 5));
+        
+        let all = tracker
+            .usage(&UsageQuery { tenant: None, to: day.succ_opt().unwrap(), ..query })
+            .await
+            .unwrap();
+        assert_eq!(all.len(), 3);
+        assert!(all.windows(2).all(|pair| pair[0].day <= pair[1].day));
+    }
+    
+    #[tokio::test]
+    async fn test_job_claims() {
+        let tracker = InMemoryOperationTracker::new();
+        let stale_after = chrono::Duration::minutes(5);
+        let first = AnalysisJob::new(TenantId::default(), ModelType::Read, Default::default());
+        let mut second = AnalysisJob::new(TenantId::default(), ModelType::Invoice, Default::default());
+        second.created_at = first.created_at + chrono::Duration::seconds(1);
+        tracker.enqueue_job(&second).await.unwrap();
+        tracker.enqueue_job(&first).await.unwrap();
+        
+        // Oldest first, each job to one worker
+        let mut claimed = tracker.claim_job(stale_after).await.unwrap().unwrap();
+        assert_eq!(claimed.job_id, first.job_id);
+        assert_eq!((claimed.status, claimed.attempts), (JobStatus::Running, 1));
+        assert_eq!(tracker.claim_job(stale_after).await.unwrap().unwrap().job_id, second.job_id);
+        assert!(tracker.claim_job(stale_after).await.unwrap().is_none());
+        
+        claimed.submitted("op-1");
+        tracker.update_job(&claimed).await.unwrap();
+        let stored = tracker.get_job(&first.job_id).await.unwrap().unwrap();
+        assert_eq!(stored.status, JobStatus::Submitted);
+        assert_eq!(stored.operation_id.as_deref(), Some("op-1"));
+        
+        // A running job whose worker went quiet is handed out again
+        let reclaimed = tracker.claim_job(chrono::Duration::zero()).await.unwrap().unwrap();
+        assert_eq!((reclaimed.job_id, reclaimed.attempts), (second.job_id, 2));
+    }
+    
+    #[tokio::test]
+    async fn test_job_priorities() {
+        let tracker = InMemoryOperationTracker::new();
+        let stale_after = chrono::Duration::minutes(5);
+        let backfill = AnalysisJob::new(TenantId::default(), ModelType::Read, Default::default())
+            .with_priority(JobPriority::Batch);
+        let mut interactive = AnalysisJob::new(TenantId::default(), ModelType::Read, Default::default());
+        interactive.created_at = backfill.created_at + chrono::Duration::seconds(1);
+        tracker.enqueue_job(&backfill).await.unwrap();
+        tracker.enqueue_job(&interactive).await.unwrap();
+        
+        // Interactive work goes ahead of older batch work
+        assert_eq!(tracker.claim_job(stale_after).await.unwrap().unwrap().job_id, interactive.job_id);
+        assert_eq!(tracker.claim_job(stale_after).await.unwrap().unwrap().job_id, backfill.job_id);
+    }
+    
+    #[tokio::test]
+    async fn test_job_retry_schedule() {
+        let tracker = InMemoryOperationTracker::new();
+        let stale_after = chrono::Duration::minutes(5);
+        tracker
+            .enqueue_job(&AnalysisJob::new(TenantId::default(), ModelType::Read, Default::default()))
+            .await
+            .unwrap();
+        
+        // A job waiting out its backoff is not handed out
+        let mut job = tracker.claim_job(stale_after).await.unwrap().unwrap();
+        job.retry_after("throttled", chrono::Duration::minutes(1));
+        tracker.update_job(&job).await.unwrap();
+        assert!(tracker.claim_job(stale_after).await.unwrap().is_none());
+        
+        job.submitted("op-1");
+        tracker.update_job(&job).await.unwrap();
+        let found = tracker.job_for_operation("op-1").await.unwrap().unwrap();
+        assert_eq!(found.job_id, job.job_id);
+        assert!(tracker.job_for_operation("op-2").await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,107 @@
+/// Azure Blob Storage destination for training datasets and analytics exports
+///
+/// Writes each dataset file as a block blob in the configured container,
+/// authorized like blob ingestion with a SAS token or the host's managed
+/// identity.
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use reqwest::Client;
+use std::sync::Arc;
+use url::Url;
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::DatasetWriterPort;
+use crate::infrastructure::azure_events::ManagedIdentityCredential;
+use crate::infrastructure::blob_ingest::BlobAuth;
+use crate::infrastructure::config::{Secret, TrainingExportConfig};
+
+/// Dataset writer putting files into a Blob Storage container
+pub struct BlobDatasetWriter {
+    client: Client,
+    container_url: Url,
+    auth: BlobAuth,
+}
+
+impl BlobDatasetWriter {
+    /// Writer for the configured container, or `None` when none is configured
+    ///
+    /// Without a SAS token, requests are authorized with `credential`.
+    pub fn from_config(
+        config: &TrainingExportConfig,
+        credential: Arc<ManagedIdentityCredential>,
+    ) -> ApplicationResult<Option<Self>> {
+        config
+            .container_url
+            .as_deref()
+            .map(|url| Self::new(url, config.sas_token.as_ref(), credential, "TRAINING_EXPORT_CONTAINER_URL"))
+            .transpose()
+    }
+
+    /// Writer for `container_url`, named by the `setting` it was configured with in errors
+    pub fn new(
+        container_url: &str,
+        sas_token: Option<&Secret>,
+        credential: Arc<ManagedIdentityCredential>,
+        setting: &str,
+    ) -> ApplicationResult<Self> {
+        let container_url = Url::parse(container_url.trim_end_matches('/'))
+            .map_err(|e| ApplicationError::Configuration(format!("Invalid {}: {}", setting, e)))?;
+        let auth = match sas_token {
+            Some(sas) => BlobAuth::Sas(sas.expose().trim_start_matches('?').to_string()),
+            None => BlobAuth::ManagedIdentity(credential),
+        };
+        Ok(Self { client: Client::new(), container_url, auth })
+    }
+}
+
+#[async_trait]
+impl DatasetWriterPort for BlobDatasetWriter {
+    async fn put(&self, path: &str, content_type: &str, content: Bytes) -> ApplicationResult<()> {
+        let request = self
+            .client
+            .put(self.auth.url(&self.container_url, Some(path), &[]))
+            .header("x-ms-blob-type", "BlockBlob")
+            .header("Content-Type", content_type)
+            .body(content);
+        self.auth.send(request, "write dataset blob").await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_put_block_blob() {
+        let server = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/training/adi/op-1.pdf.labels.json"))
+            .and(query_param("sig", "abc"))
+            .and(header("x-ms-blob-type", "BlockBlob"))
+            .and(header("Content-Type", "application/json"))
+            .and(body_string("{}"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let config = TrainingExportConfig {
+            container_url: Some(format!("{}/training/", server.uri())),
+            sas_token: Some(Secret::from("?sig=abc")),
+            prefix: String::new(),
+        };
+        let credential = Arc::new(ManagedIdentityCredential::new(server.uri(), None, None));
+        let writer = BlobDatasetWriter::from_config(&config, credential.clone()).unwrap().unwrap();
+        writer
+            .put("adi/op-1.pdf.labels.json", "application/json", Bytes::from_static(b"{}"))
+            .await
+            .unwrap();
+
+        let unset = TrainingExportConfig { container_url: None, ..config };
+        assert!(BlobDatasetWriter::from_config(&unset, credential).unwrap().is_none());
+    }
+}
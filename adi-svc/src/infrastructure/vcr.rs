@@ -0,0 +1,342 @@
+/// Record-and-replay Document Intelligence adapter
+///
+/// `VcrAdapter::record` wraps a live adapter and writes every call and its
+/// outcome to a JSON cassette; `VcrAdapter::replay` serves a cassette back
+/// without touching the network, so integration tests can exercise result
+/// conversion and the tracker against real Azure payloads. Succeeded results
+/// are stored as raw `analyzeResults` bodies and converted on replay.
+///
+/// Cassettes are meant to be committed, so they are scrubbed before they hit
+/// disk: query strings (SAS tokens) are dropped from document URLs, uploaded
+/// documents are stored as a SHA-256 digest, and registered secrets are
+/// replaced wherever they appear.
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::DocumentIntelligencePort;
+use crate::domain::*;
+use crate::infrastructure::azure::parse_analyze_response;
+
+/// Placeholder for scrubbed secrets
+const REDACTED: &str = "<redacted>";
+
+/// Recorded calls, in the order they were made
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Cassette {
+    /// API version results are converted with
+    api_version: String,
+    interactions: Vec<Interaction>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Interaction {
+    Analyze {
+        model: ModelType,
+        source: String,
+        outcome: Recorded<String>,
+    },
+    Result {
+        operation_id: String,
+        outcome: Recorded<RecordedResult>,
+    },
+    ValidateModel {
+        model_id: String,
+        outcome: Recorded<bool>,
+    },
+}
+
+/// Outcome of a recorded call
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Recorded<T> {
+    Ok(T),
+    NotFound,
+    Error(String),
+}
+
+impl<T: Clone> Recorded<T> {
+    fn capture(result: Result<T, &ApplicationError>) -> Self {
+        match result {
+            Ok(value) => Self::Ok(value),
+            Err(ApplicationError::OperationNotFound(_)) => Self::NotFound,
+            Err(e) => Self::Error(e.to_string()),
+        }
+    }
+
+    fn replay(&self, operation_id: &str) -> ApplicationResult<T> {
+        match self {
+            Self::Ok(value) => Ok(value.clone()),
+            Self::NotFound => Err(ApplicationError::OperationNotFound(operation_id.to_string())),
+            Self::Error(message) => Err(ApplicationError::AzureService(message.clone())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct RecordedResult {
+    model_type: ModelType,
+    status: OperationStatus,
+    /// Raw `analyzeResults` body, once the operation has succeeded
+    response: Option<serde_json::Value>,
+}
+
+enum Mode {
+    Record {
+        inner: Arc<dyn DocumentIntelligencePort>,
+        path: PathBuf,
+        secrets: Vec<String>,
+    },
+    Replay {
+        /// Analyze interactions already served
+        used: Vec<bool>,
+        /// Next result interaction to serve per operation
+        cursors: HashMap<String, usize>,
+    },
+}
+
+struct State {
+    mode: Mode,
+    cassette: Cassette,
+}
+
+/// Adapter that records to, or replays from, a cassette file
+///
+/// Calls are serialized, which keeps recorded cassettes in call order.
+pub struct VcrAdapter {
+    state: Mutex<State>,
+}
+
+impl VcrAdapter {
+    /// Record calls to `inner` into `path`, which is rewritten after every call
+    pub fn record(inner: Arc<dyn DocumentIntelligencePort>, api_version: &str, path: impl Into<PathBuf>) -> Self {
+        Self {
+            state: Mutex::new(State {
+                mode: Mode::Record {
+                    inner,
+                    path: path.into(),
+                    secrets: Vec::new(),
+                },
+                cassette: Cassette {
+                    api_version: api_version.to_string(),
+                    interactions: Vec::new(),
+                },
+            }),
+        }
+    }
+
+    /// Serve the cassette at `path`
+    pub async fn replay(path: impl Into<PathBuf>) -> ApplicationResult<Self> {
+        let path = path.into();
+        let data = tokio::fs::read(&path)
+            .await
+            .map_err(|e| ApplicationError::Configuration(format!("Failed to read cassette {}: {}", path.display(), e)))?;
+        let cassette: Cassette = serde_json::from_slice(&data)
+            .map_err(|e| ApplicationError::Configuration(format!("Invalid cassette {}: {}", path.display(), e)))?;
+        info!("Replaying {} Azure interactions from {}", cassette.interactions.len(), path.display());
+        Ok(Self {
+            state: Mutex::new(State {
+                mode: Mode::Replay {
+                    used: vec![false; cassette.interactions.len()],
+                    cursors: HashMap::new(),
+                },
+                cassette,
+            }),
+        })
+    }
+
+    /// Replace `secret` wherever it appears in the recorded cassette
+    pub fn with_secret(mut self, secret: &str) -> Self {
+        if let Mode::Record { secrets, .. } = &mut self.state.get_mut().mode {
+            if !secret.is_empty() {
+                secrets.push(secret.to_string());
+            }
+        }
+        self
+    }
+
+    async fn fetch_result(
+        &self,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>, Option<serde_json::Value>)> {
+        let mut state = self.state.lock().await;
+        let State { mode, cassette } = &mut *state;
+        let recorded = match mode {
+            Mode::Record { inner, path, secrets } => {
+                let result = inner.get_analysis_result_raw(operation_id).await;
+                let recorded = result.as_ref().map(|(operation, _, raw)| RecordedResult {
+                    model_type: operation.model_type,
+                    status: operation.status,
+                    response: raw.clone(),
+                });
+                cassette.interactions.push(Interaction::Result {
+                    operation_id: operation_id.to_string(),
+                    outcome: Recorded::capture(recorded),
+                });
+                save(cassette, path, secrets).await?;
+                return result;
+            }
+            Mode::Replay { cursors, .. } => {
+                let results: Vec<&Recorded<RecordedResult>> = cassette
+                    .interactions
+                    .iter()
+                    .filter_map(|interaction| match interaction {
+                        Interaction::Result { operation_id: id, outcome } if id == operation_id => Some(outcome),
+                        _ => None,
+                    })
+                    .collect();
+                // Extra polls see the last recorded state
+                let cursor = cursors.entry(operation_id.to_string()).or_default();
+                let recorded = results.get(*cursor).or(results.last()).ok_or_else(|| {
+                    ApplicationError::Internal(format!("No recorded result for operation {}", operation_id))
+                })?;
+                *cursor += 1;
+                recorded.replay(operation_id)?
+            }
+        };
+
+        let mut operation = AnalysisOperation::new(recorded.model_type);
+        operation.operation_id = operation_id.to_string();
+        operation.update_status(recorded.status);
+        let result = match &recorded.response {
+            Some(raw) => {
+                let body = serde_json::to_vec(raw)
+                    .map_err(|e| ApplicationError::Internal(format!("Failed to encode recorded response: {}", e)))?;
+                parse_analyze_response(&cassette.api_version, &body)?
+            }
+            None => None,
+        };
+        Ok((operation, result, recorded.response))
+    }
+}
+
+#[async_trait]
+impl DocumentIntelligencePort for VcrAdapter {
+    async fn analyze_document(
+        &self,
+        request: AnalyzeDocumentRequest,
+    ) -> ApplicationResult<AnalysisOperation> {
+        let source = describe_source(&request.source);
+        let model = request.model_type;
+        let mut state = self.state.lock().await;
+        let State { mode, cassette } = &mut *state;
+        match mode {
+            Mode::Record { inner, path, secrets } => {
+                let result = inner.analyze_document(request).await;
+                cassette.interactions.push(Interaction::Analyze {
+                    model,
+                    source,
+                    outcome: Recorded::capture(result.as_ref().map(|operation| operation.operation_id.clone())),
+                });
+                save(cassette, path, secrets).await?;
+                result
+            }
+            Mode::Replay { used, .. } => {
+                let index = cassette
+                    .interactions
+                    .iter()
+                    .enumerate()
+                    .position(|(i, interaction)| {
+                        !used[i]
+                            && matches!(interaction, Interaction::Analyze { model: m, source: s, .. } if *m == model && *s == source)
+                    })
+                    .ok_or_else(|| {
+                        ApplicationError::Internal(format!("No recorded {} analysis of {}", model.as_str(), source))
+                    })?;
+                used[index] = true;
+                let Interaction::Analyze { outcome, .. } = &cassette.interactions[index] else {
+                    unreachable!("position matched an analyze interaction");
+                };
+                let operation_id = outcome.replay("")?;
+                let mut operation = AnalysisOperation::new(model);
+                operation.operation_id = operation_id;
+                operation.update_status(OperationStatus::Running);
+                Ok(operation)
+            }
+        }
+    }
+
+    async fn get_analysis_result(
+        &self,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>)> {
+        let (operation, result, _) = self.fetch_result(operation_id).await?;
+        Ok((operation, result))
+    }
+
+    async fn get_analysis_result_raw(
+        &self,
+        operation_id: &str,
+    ) -> ApplicationResult<(AnalysisOperation, Option<AnalysisResult>, Option<serde_json::Value>)> {
+        self.fetch_result(operation_id).await
+    }
+
+    async fn validate_custom_model(&self, model_id: &str) -> ApplicationResult<bool> {
+        let mut state = self.state.lock().await;
+        let State { mode, cassette } = &mut *state;
+        match mode {
+            Mode::Record { inner, path, secrets } => {
+                let result = inner.validate_custom_model(model_id).await;
+                cassette.interactions.push(Interaction::ValidateModel {
+                    model_id: model_id.to_string(),
+                    outcome: Recorded::capture(result.as_ref().copied()),
+                });
+                save(cassette, path, secrets).await?;
+                result
+            }
+            Mode::Replay { .. } => cassette
+                .interactions
+                .iter()
+                .find_map(|interaction| match interaction {
+                    Interaction::ValidateModel { model_id: id, outcome } if id == model_id => Some(outcome),
+                    _ => None,
+                })
+                .ok_or_else(|| ApplicationError::Internal(format!("No recorded validation of model {}", model_id)))?
+                .replay(model_id),
+        }
+    }
+}
+
+/// Cassette key for a document: the URL without its query, or the digest of uploaded bytes
+fn describe_source(source: &DocumentSource) -> String {
+    match source {
+        DocumentSource::Url(url) => url.split(['?', '#']).next().unwrap_or_default().to_string(),
+        DocumentSource::Bytes(bytes) => format!("sha256:{}", hex::encode(Sha256::digest(bytes))),
+    }
+}
+
+/// Write the scrubbed cassette to `path`
+async fn save(cassette: &Cassette, path: &Path, secrets: &[String]) -> ApplicationResult<()> {
+    let mut json = serde_json::to_string_pretty(cassette)
+        .map_err(|e| ApplicationError::Internal(format!("Failed to encode cassette: {}", e)))?;
+    for secret in secrets {
+        json = json.replace(secret.as_str(), REDACTED);
+    }
+    tokio::fs::write(path, json)
+        .await
+        .map_err(|e| ApplicationError::Internal(format!("Failed to write cassette {}: {}", path.display(), e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_source_scrubs_urls_and_bytes() {
+        assert_eq!(
+            describe_source(&DocumentSource::Url("https://acct.blob.core.windows.net/c/a.pdf?sv=1&sig=secret".to_string())),
+            "https://acct.blob.core.windows.net/c/a.pdf"
+        );
+        let digest = describe_source(&DocumentSource::Bytes(bytes::Bytes::from_static(b"%PDF-1.7")));
+        assert!(digest.starts_with("sha256:"));
+        assert!(!digest.contains("PDF"));
+    }
+}
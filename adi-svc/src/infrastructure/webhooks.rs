@@ -0,0 +1,148 @@
+/// Webhook delivery for pipeline `webhook` steps
+///
+/// Payloads are POSTed as JSON. With a secret configured, each request
+/// carries `X-Adi-Signature: sha256=<hex>`, an HMAC-SHA256 of the body, so
+/// receivers can tell our calls from forged ones. The timeout and secret can
+/// be replaced while running, for rotating the secret without a restart.
+
+use async_trait::async_trait;
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use sha2::Sha256;
+use std::sync::RwLock;
+use std::time::Duration;
+
+use crate::application::errors::{ApplicationError, ApplicationResult};
+use crate::application::ports::WebhookPort;
+
+/// Header carrying the body's signature
+pub const SIGNATURE_HEADER: &str = "X-Adi-Signature";
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Webhook sender over HTTP
+pub struct HttpWebhookSender {
+    client: Client,
+    settings: RwLock<WebhookSettings>,
+}
+
+#[derive(Clone, PartialEq)]
+struct WebhookSettings {
+    timeout: Duration,
+    secret: Option<Vec<u8>>,
+}
+
+impl HttpWebhookSender {
+    /// Sender giving each delivery `timeout`, signing bodies when `secret` is set
+    pub fn new(timeout: Duration, secret: Option<Vec<u8>>) -> ApplicationResult<Self> {
+        let client = Client::builder()
+            .build()
+            .map_err(|e| ApplicationError::Internal(format!("Failed to build webhook client: {}", e)))?;
+        Ok(Self { client, settings: RwLock::new(WebhookSettings { timeout, secret }) })
+    }
+
+    /// Use `timeout` and `secret` for deliveries from now on; `false` when they are already in use
+    pub fn set_settings(&self, timeout: Duration, secret: Option<Vec<u8>>) -> bool {
+        let settings = WebhookSettings { timeout, secret };
+        let mut current = self.settings.write().unwrap_or_else(|e| e.into_inner());
+        if *current == settings {
+            return false;
+        }
+        *current = settings;
+        true
+    }
+}
+
+/// `sha256=<hex>` signature of `body` under `secret`
+pub fn sign_webhook(secret: &[u8], body: &[u8]) -> String {
+    let mut mac = HmacSha256::new_from_slice(secret).expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+#[async_trait]
+impl WebhookPort for HttpWebhookSender {
+    async fn deliver(&self, url: &str, payload: &serde_json::Value) -> ApplicationResult<()> {
+        let body = serde_json::to_vec(payload)
+            .map_err(|e| ApplicationError::Internal(format!("Failed to encode webhook payload: {}", e)))?;
+        let settings = self.settings.read().unwrap_or_else(|e| e.into_inner()).clone();
+        let mut request = self
+            .client
+            .post(url)
+            .timeout(settings.timeout)
+            .header("Content-Type", "application/json");
+        if let Some(secret) = &settings.secret {
+            request = request.header(SIGNATURE_HEADER, sign_webhook(secret, &body));
+        }
+        let response = request
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| ApplicationError::Internal(format!("Failed to deliver webhook to {}: {}", url, e)))?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ApplicationError::Internal(format!("Webhook {} answered {}", url, status)));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn test_signed_delivery() {
+        let server = MockServer::start().await;
+        let payload = json!({ "run": { "run_id": "r-1" } });
+        let signature = sign_webhook(b"s3cret", &serde_json::to_vec(&payload).unwrap());
+        Mock::given(method("POST"))
+            .and(path("/hooks/ok"))
+            .and(header(SIGNATURE_HEADER, signature.as_str()))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/hooks/down"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&server)
+            .await;
+
+        let sender = HttpWebhookSender::new(Duration::from_secs(5), Some(b"s3cret".to_vec())).unwrap();
+        sender.deliver(&format!("{}/hooks/ok", server.uri()), &payload).await.unwrap();
+        let error = sender
+            .deliver(&format!("{}/hooks/down", server.uri()), &payload)
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("503"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_reloaded_settings() {
+        let server = MockServer::start().await;
+        let payload = json!({ "run": { "run_id": "r-2" } });
+        let signature = sign_webhook(b"rotated", &serde_json::to_vec(&payload).unwrap());
+        Mock::given(method("POST"))
+            .and(path("/hooks/slow"))
+            .and(header(SIGNATURE_HEADER, signature.as_str()))
+            .respond_with(ResponseTemplate::new(204).set_delay(Duration::from_millis(500)))
+            .mount(&server)
+            .await;
+        let url = format!("{}/hooks/slow", server.uri());
+
+        let sender = HttpWebhookSender::new(Duration::from_secs(5), Some(b"s3cret".to_vec())).unwrap();
+        assert!(!sender.set_settings(Duration::from_secs(5), Some(b"s3cret".to_vec())));
+        assert!(sender.deliver(&url, &payload).await.is_err());
+
+        assert!(sender.set_settings(Duration::from_millis(50), Some(b"rotated".to_vec())));
+        let error = sender.deliver(&url, &payload).await.unwrap_err();
+        assert!(error.to_string().contains("Failed to deliver"), "{}", error);
+
+        assert!(sender.set_settings(Duration::from_secs(5), Some(b"rotated".to_vec())));
+        sender.deliver(&url, &payload).await.unwrap();
+    }
+}
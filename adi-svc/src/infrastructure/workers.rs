@@ -0,0 +1,50 @@
+/// Worker pool for queued analysis jobs
+///
+/// Each worker submits one job at a time, so the pool size bounds how many
+/// submissions to the provider run concurrently in this instance.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info};
+
+use crate::application::services::DocumentIntelligenceService;
+use crate::infrastructure::tasks::TaskSupervisor;
+
+/// Spawn `workers` job workers that poll the queue every `poll_interval` while it is empty
+pub fn spawn_job_workers(
+    supervisor: &TaskSupervisor,
+    service: Arc<DocumentIntelligenceService>,
+    workers: usize,
+    poll_interval: Duration,
+) {
+    info!("Starting {} job workers (poll interval: {:?})", workers, poll_interval);
+
+    for _ in 0..workers {
+        let service = service.clone();
+        supervisor.spawn("job-worker", move |shutdown| {
+            let service = service.clone();
+            async move {
+                loop {
+                    // Drain the queue before waiting again
+                    let idle = match service.run_next_job().await {
+                        Ok(ran) => !ran,
+                        Err(e) => {
+                            error!("Job worker failed to run a job: {}", e);
+                            true
+                        }
+                    };
+                    if !idle {
+                        if shutdown.is_cancelled() {
+                            return;
+                        }
+                        continue;
+                    }
+                    tokio::select! {
+                        _ = shutdown.cancelled() => return,
+                        _ = tokio::time::sleep(poll_interval) => {}
+                    }
+                }
+            }
+        });
+    }
+}
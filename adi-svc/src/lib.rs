@@ -1,13 +1,20 @@
 /// adi-svc library
 /// 
 /// This crate provides a Rust wrapper for Azure AI Document Intelligence
-/// with both gRPC and REST interfaces.
+/// with both gRPC and REST interfaces. With `default-features = false` it
+/// builds as a library of the domain model, application service, Azure
+/// adapter and gRPC client stubs, without the servers or PostgreSQL.
 
 pub mod domain;
 pub mod application;
 pub mod infrastructure;
 pub mod presentation;
 pub mod generated;
+#[cfg(feature = "client")]
+pub mod client;
+
+#[cfg(any(test, feature = "fixtures"))]
+pub mod fixtures;
 
 pub use domain::*;
 pub use application::*;
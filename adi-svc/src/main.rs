@@ -1,106 +1,665 @@
 /// adi-svc main entry point
 /// 
-/// This starts both the gRPC and REST servers.
+/// This starts the gRPC and REST servers enabled in configuration.
 
+use std::num::NonZeroUsize;
 use std::sync::Arc;
 use tonic::transport::Server;
-use tracing::{info, error};
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+use tracing::{info, warn, error};
 
+use adi_svc::application::ports::{DocumentClassifierPort, DocumentIntelligencePort, EventPublisherPort, OperationTrackerPort};
 use adi_svc::application::services::DocumentIntelligenceService;
+use adi_svc::application::pipelines::PipelineService;
+use adi_svc::application::exports::ExportService;
+use adi_svc::application::training::TrainingExportService;
+use adi_svc::application::retention::RetentionService;
 use adi_svc::infrastructure::{
-    AzureDocumentIntelligenceAdapter, Config, PostgresOperationTracker,
-    LocalFileStorageAdapter,
+    AzureConfig, AzureDocumentClassifier, AzureDocumentIntelligenceAdapter, AzureMode, AzureOpenAiEmbeddings, AzureSearchIndexer, BlobDatasetWriter, BlobIngestor, CachedOperationTracker, ClamAvScanner, Config, DatabaseConfig, ElasticsearchIndexer, EventsConfig, FanoutEventPublisher,
+    AlertNotifier, AlertingIntelligenceAdapter, EnvelopeCipher, AzureErrorMonitor, FolderWatcher, HttpWebhookSender, TieredOperationTracker, KeywordClassifier, LogLevelControl, ManagedIdentityCredential, Redactor, MockDocumentIntelligenceAdapter,
+    PipelineConfig, PostgresOperationTracker, PrometheusOperationMetrics, LocalFileStorageAdapter, Secret, TaskSupervisor, VcrAdapter, spawn_blob_ingest,
+    init_logging, spawn_folder_watch, spawn_job_workers, spawn_retention_task,
 };
-use adi_svc::presentation::{GrpcDocumentIntelligenceService, create_rest_router};
+use adi_svc::presentation::{
+    BodyLimits, GrpcAuthInterceptor, GrpcDocumentIntelligenceService, GrpcRequestIdLayer, PublicUrls, RestOptions,
+    create_rest_router_with_options,
+};
+use adi_svc::presentation::priority::PriorityPolicy;
+use adi_svc::presentation::auth::Authenticator;
+use adi_svc::presentation::tenancy::TenantResolver;
 use adi_svc::generated::document_intelligence_service_server::DocumentIntelligenceServiceServer;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    // Initialize tracing
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "adi_svc=debug,tower_http=debug,info".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    // Load configuration, then log in its format with its secrets scrubbed
+    let config = Config::from_env()?;
+    let log_level = init_logging(config.server.log_format, Redactor::new(config.secrets()));
+    #[cfg(unix)]
+    tokio::spawn(toggle_debug_on_sigusr1(log_level.clone()));
 
     info!("Starting adi-svc...");
-
-    // Load configuration
-    let config = Config::from_env()?;
     info!("Configuration loaded");
-    info!("Azure endpoint: {}", config.azure.endpoint);
-    info!("gRPC server will listen on {}:{}", config.server.host, config.server.grpc_port);
-    info!("REST server will listen on {}:{}", config.server.host, config.server.rest_port);
+    match config.azure.mode {
+        AzureMode::Live => info!("Azure endpoint: {}", config.azure.endpoint),
+        AzureMode::Mock => warn!("AZURE_MODE=mock; serving fixture results instead of calling Azure"),
+        AzureMode::Record => info!("Azure endpoint: {} (recording)", config.azure.endpoint),
+        AzureMode::Replay => warn!("AZURE_MODE=replay; serving recorded results instead of calling Azure"),
+        AzureMode::Textract => info!("AZURE_MODE=textract; analyzing documents with AWS Textract"),
+    }
+    if config.server.enable_grpc {
+        info!("gRPC server will listen on {}:{}", config.server.host, config.server.grpc_port);
+    } else {
+        info!("gRPC server disabled");
+    }
+    if config.server.enable_rest {
+        info!("REST server will listen on {}:{}", config.server.host, config.server.rest_port);
+    } else {
+        info!("REST server disabled");
+    }
 
     // Initialize adapters
-    let azure_adapter = Arc::new(AzureDocumentIntelligenceAdapter::new(config.azure.clone()));
-    let storage_adapter = Arc::new(LocalFileStorageAdapter::new(config.storage.clone()).await?);
+    // The adapter calling Azure, if any, kept to rotate its key
+    let mut live_adapter = None;
+    let azure_adapter: Arc<dyn DocumentIntelligencePort> = match config.azure.mode {
+        AzureMode::Live => {
+            let live = Arc::new(AzureDocumentIntelligenceAdapter::new(config.azure.clone())?);
+            live_adapter = Some(live.clone());
+            live
+        }
+        AzureMode::Mock => Arc::new(MockDocumentIntelligenceAdapter::new(&config.azure)),
+        AzureMode::Record | AzureMode::Replay => {
+            let cassette = config
+                .azure
+                .cassette_path
+                .clone()
+                .ok_or("AZURE_CASSETTE is required with AZURE_MODE=record or replay")?;
+            if config.azure.mode == AzureMode::Record {
+                let live = Arc::new(AzureDocumentIntelligenceAdapter::new(config.azure.clone())?);
+                live_adapter = Some(live.clone());
+                let mut recorder =
+                    VcrAdapter::record(live, &config.azure.api_version, cassette).with_secret(config.azure.key.expose());
+                if let Some(secondary_key) = &config.azure.secondary_key {
+                    recorder = recorder.with_secret(secondary_key.expose());
+                }
+                Arc::new(recorder)
+            } else {
+                Arc::new(VcrAdapter::replay(cassette).await?)
+            }
+        }
+        AzureMode::Textract => {
+            #[cfg(feature = "textract")]
+            {
+                Arc::new(adi_svc::infrastructure::TextractAdapter::from_env().await)
+            }
+            #[cfg(not(feature = "textract"))]
+            return Err("AZURE_MODE=textract but this build lacks the `textract` feature".into());
+        }
+    };
+    // Operators are alerted when Azure calls start failing or being throttled
+    let azure_adapter: Arc<dyn DocumentIntelligencePort> = if config.alerting.enabled() {
+        info!(
+            "Alerting on Azure error rates over {}s: {} webhook(s){}",
+            config.alerting.window_secs,
+            config.alerting.webhook_urls.len(),
+            if config.alerting.pagerduty_routing_key.is_some() { " and PagerDuty" } else { "" }
+        );
+        let webhooks = HttpWebhookSender::new(std::time::Duration::from_secs(10), None)?;
+        Arc::new(AlertingIntelligenceAdapter::new(
+            azure_adapter,
+            AzureErrorMonitor::new(&config.alerting),
+            AlertNotifier::new(Arc::new(webhooks), &config.alerting),
+        ))
+    } else {
+        azure_adapter
+    };
+    let credential = Arc::new(ManagedIdentityCredential::from_env(
+        config.events.managed_identity_client_id.clone(),
+    ));
+    let cipher = EnvelopeCipher::from_config(&config.encryption, credential.clone())?;
+    
+    let mut storage = LocalFileStorageAdapter::new(config.storage.clone())
+        .await?
+        .with_public_base_url(&config.server.public_base_url());
+    if config.storage.url_signing_key.is_none() {
+        warn!("URL_SIGNING_KEY not set; signed document links will not survive a restart");
+    }
     
     // Initialize PostgreSQL tracker
     info!("Connecting to PostgreSQL database...");
-    let tracker_adapter = Arc::new(
-        PostgresOperationTracker::new(&config.database.url).await?
-    );
+    let mut tracker = PostgresOperationTracker::new(&config.database).await?;
+    if let Some(cipher) = cipher {
+        info!("Encrypting stored documents, results, raw responses, revisions and chunks");
+        storage = storage.with_encryption(cipher.clone());
+        tracker = tracker.with_encryption(cipher);
+    }
+    let storage_adapter = Arc::new(storage);
+    let tracker_adapter = Arc::new(tracker);
+
+    // Background tasks are joined on shutdown
+    let supervisor = TaskSupervisor::new();
+    let shutdown = supervisor.shutdown_token();
+
+    tracker_adapter.spawn_pool_metrics_task(&supervisor);
+
+    // Operations and results are read through memory and the cache; queues, audit and usage go to the database
+    let mut operation_tracker: Arc<dyn OperationTrackerPort> = tracker_adapter.clone();
+    if let Some(secs) = config.database.memory_retention_secs {
+        info!("Keeping operations in memory for {}s after their last update", secs);
+        let tiered = Arc::new(TieredOperationTracker::new(operation_tracker, std::time::Duration::from_secs(secs)));
+        tiered.spawn_eviction_task(&supervisor);
+        operation_tracker = tiered;
+    }
+    let operation_tracker: Arc<dyn OperationTrackerPort> = match NonZeroUsize::new(config.database.cache_capacity) {
+        Some(capacity) => {
+            info!("Tracker cache enabled: {} entries, {}s TTL", capacity, config.database.cache_ttl_secs);
+            Arc::new(CachedOperationTracker::new(
+                operation_tracker,
+                capacity,
+                std::time::Duration::from_secs(config.database.cache_ttl_secs),
+            ))
+        }
+        None => operation_tracker,
+    };
+
+    // Start retention task if a TTL is configured
+    let retention = &config.retention;
+    if retention.result_ttl_days.is_some() || retention.deleted_ttl_days.is_some() {
+        let mut retention_service = RetentionService::new(
+            Some(operation_tracker.clone()),
+            Some(storage_adapter.clone()),
+            retention.result_ttl_days,
+        );
+        if let Some(ttl_days) = retention.result_ttl_days {
+            info!("Result retention enabled: {} days", ttl_days);
+        }
+        if let Some(ttl_days) = retention.deleted_ttl_days {
+            info!("Deleted operations purged after {} days", ttl_days);
+            retention_service = retention_service.with_deleted_ttl(ttl_days);
+        }
+        spawn_retention_task(
+            &supervisor,
+            Arc::new(retention_service),
+            std::time::Duration::from_secs(retention.cleanup_interval_secs),
+        );
+    }
 
     // Initialize application service
-    let app_service = Arc::new(DocumentIntelligenceService::new(
+    let mut service = DocumentIntelligenceService::new(
         azure_adapter,
         Some(storage_adapter),
-        Some(tracker_adapter),
-    ));
-
-    // Clone for REST server
-    let app_service_rest = app_service.clone();
-
-    // Start gRPC server
-    let grpc_addr: std::net::SocketAddr = format!("{}:{}", config.server.host, config.server.grpc_port).parse()?;
-    let grpc_service = GrpcDocumentIntelligenceService::new(app_service);
+        Some(operation_tracker),
+    )
+    .with_work_queue(tracker_adapter.clone())
+    .with_job_queue(tracker_adapter.clone())
+    .with_job_retry(config.jobs.retry)
+    .with_result_revisions(tracker_adapter.clone())
+    .with_operation_metrics(Arc::new(PrometheusOperationMetrics))
+    .with_health_check(tracker_adapter.clone());
+    #[cfg(feature = "images")]
+    {
+        service = service.with_image_preprocessor(Arc::new(adi_svc::infrastructure::ImagePreprocessor::new()));
+    }
+    #[cfg(feature = "pdf")]
+    {
+        service = service.with_redaction_renderer(Arc::new(adi_svc::infrastructure::DocumentRedactor::new()));
+    }
+    if let (true, Some(live)) = (config.server.health_check_azure, &live_adapter) {
+        service = service.with_health_check(live.clone());
+    }
+    let classifier: Arc<dyn DocumentClassifierPort> = match (&config.routing.classifier_id, &live_adapter) {
+        (Some(classifier_id), Some(live)) => {
+            info!("Routing analyze/auto with classifier {}", classifier_id);
+            Arc::new(AzureDocumentClassifier::new(live.clone(), classifier_id.clone()))
+        }
+        (Some(_), None) => {
+            warn!("AUTO_CLASSIFIER_ID needs Azure (AZURE_MODE=live or record); routing analyze/auto by keywords");
+            Arc::new(KeywordClassifier::new())
+        }
+        (None, _) => Arc::new(KeywordClassifier::new()),
+    };
+    service = service.with_model_routing(classifier, config.routing.model_routes()?);
+    if let Some(policy) = config.review.policy()? {
+        info!("Review queue enabled below confidence {}", policy.min_confidence);
+        service = service.with_review_policy(policy);
+    }
+    if let Some(scanner) = ClamAvScanner::from_config(&config.malware_scan) {
+        info!("Malware scanning enabled");
+        service = service.with_malware_scanner(Arc::new(scanner));
+    }
+    if config.validation.validate_pdfs {
+        service = service.with_pdf_validation(config.validation.max_pdf_pages);
+    }
+    if config.database.store_raw_responses {
+        service = service.with_raw_responses();
+    }
+    let output_templates = config.output_templates.templates()?;
+    if !output_templates.is_empty() {
+        let names: Vec<&str> = output_templates.iter().map(|template| template.name.as_str()).collect();
+        info!("Output templates: {}", names.join(", "));
+        service = service.with_output_templates(output_templates);
+    }
+    if config.database.audit_log {
+        info!("Audit logging enabled");
+        service = service.with_audit_log(tracker_adapter.clone());
+    }
+    if config.database.usage_metering {
+        info!("Usage metering and quotas enabled");
+        service = service
+            .with_usage_meter(tracker_adapter.clone())
+            .with_quotas(tracker_adapter.clone())
+            .with_configured_quotas(config.database.quotas.clone());
+    }
+    if let Some(indexer) = AzureSearchIndexer::from_config(&config.search_index, credential.clone())? {
+        service = service.with_search_index(Arc::new(indexer));
+    }
+    if let Some(indexer) = ElasticsearchIndexer::from_config(&config.search_index)? {
+        // Without the template a new index still works, with dynamically mapped fields
+        if config.search_index.elasticsearch_template {
+            if let Err(e) = indexer.install_template().await {
+                warn!("{}", e);
+            }
+        }
+        service = service.with_search_index(Arc::new(indexer));
+    }
+    if let Some(provider) = AzureOpenAiEmbeddings::from_config(&config.embeddings, credential.clone()) {
+        service = service.with_embeddings(Arc::new(provider), tracker_adapter.clone(), config.embeddings.chunking()?);
+    }
+    let mut publishers = event_publishers(&config.events).await?;
+    let mut event_publisher = None;
+    if !publishers.is_empty() {
+        let publisher = if publishers.len() == 1 {
+            publishers.remove(0)
+        } else {
+            Arc::new(FanoutEventPublisher::new(publishers))
+        };
+        event_publisher = Some(publisher.clone());
+        service = service.with_event_publisher(publisher);
+    }
+    let app_service = Arc::new(service);
     
-    info!("Starting gRPC server on {}", grpc_addr);
-    let grpc_server = async move {
-        if let Err(e) = Server::builder()
-            .add_service(DocumentIntelligenceServiceServer::new(grpc_service))
-            .serve(grpc_addr)
-            .await
+    // Pipelines run their steps against the same service, recording progress in the database
+    let pipelines = config.pipelines.definitions()?;
+    let mut webhook_sender = None;
+    let pipeline_service = if pipelines.is_empty() {
+        None
+    } else {
+        let names: Vec<&str> = pipelines.iter().map(|pipeline| pipeline.name.as_str()).collect();
+        info!("Pipelines enabled: {}", names.join(", "));
+        let webhooks = HttpWebhookSender::new(
+            std::time::Duration::from_secs(config.pipelines.webhook_timeout_secs),
+            config.pipelines.webhook_secret.as_ref().map(|secret| secret.expose().as_bytes().to_vec()),
+        )?;
+        let webhooks = Arc::new(webhooks);
+        webhook_sender = Some(webhooks.clone());
+        let mut pipeline_service = PipelineService::new(app_service.clone(), tracker_adapter.clone(), pipelines)
+            .with_supervisor(supervisor.clone())
+            .with_webhooks(webhooks)
+            .with_polling(
+                std::time::Duration::from_millis(config.pipelines.poll_interval_ms),
+                std::time::Duration::from_secs(config.pipelines.timeout_secs),
+            );
+        if let Some(publisher) = event_publisher {
+            pipeline_service = pipeline_service.with_event_publisher(publisher);
+        }
+        let pipeline_service = Arc::new(pipeline_service);
+        pipeline_service.spawn_recovery();
+        Some(pipeline_service)
+    };
+    let reloadable = Reloadable {
+        adapter: live_adapter.clone(),
+        service: app_service.clone(),
+        webhooks: webhook_sender,
+    };
+    spawn_config_reload(&supervisor, reloadable, config.server.config_reload_interval_secs);
+    // Exports stream results into document storage, recording progress in the database
+    let export_service = Arc::new(ExportService::new(app_service.clone(), tracker_adapter.clone()).with_supervisor(supervisor.clone()));
+    export_service.spawn_recovery();
+    if config.jobs.workers > 0 {
+        spawn_job_workers(
+            &supervisor,
+            app_service.clone(),
+            config.jobs.workers,
+            std::time::Duration::from_millis(config.jobs.poll_interval_ms),
+        );
+    }
+    if let Some(watcher) = FolderWatcher::from_config(app_service.clone(), &config.folder_watch) {
+        spawn_folder_watch(&supervisor, watcher);
+    }
+    if let Some(ingestor) =
+        BlobIngestor::from_config(app_service.clone(), tracker_adapter.clone(), &config.blob_ingest, credential.clone())?
+    {
+        spawn_blob_ingest(&supervisor, ingestor);
+    }
+    let training_export = BlobDatasetWriter::from_config(&config.training_export, credential.clone())?.map(|writer| {
+        info!("Training export enabled");
+        Arc::new(TrainingExportService::new(app_service.clone(), Arc::new(writer), &config.training_export.prefix))
+    });
+    #[cfg(feature = "analytics")]
+    let analytics_export = adi_svc::infrastructure::analytics_export_writer(&config.analytics_export, credential.clone())?.map(|writer| {
+        info!("Analytics export enabled");
+        Arc::new(adi_svc::application::analytics::AnalyticsExportService::new(
+            app_service.clone(),
+            writer,
+            &config.analytics_export.prefix,
+        ))
+    });
+    #[cfg(feature = "analytics")]
+    if let (Some(export), interval @ 1..) = (&analytics_export, config.analytics_export.interval_secs) {
+        adi_svc::infrastructure::spawn_analytics_export_task(&supervisor, export.clone(), std::time::Duration::from_secs(interval));
+    }
+    #[cfg(not(feature = "analytics"))]
+    if config.analytics_export.dir.is_some() || config.analytics_export.container_url.is_some() {
+        return Err("ANALYTICS_EXPORT_DIR or ANALYTICS_EXPORT_CONTAINER_URL is set but this build lacks the `analytics` feature".into());
+    }
+    if config.imap_ingest.host.is_some() {
+        #[cfg(feature = "imap")]
+        if let Some(ingestor) =
+            adi_svc::infrastructure::ImapIngestor::from_config(app_service.clone(), tracker_adapter.clone(), &config.imap_ingest)?
+        {
+            adi_svc::infrastructure::spawn_imap_ingest(&supervisor, ingestor);
+        }
+        #[cfg(not(feature = "imap"))]
+        return Err("IMAP_HOST is set but this build lacks the `imap` feature".into());
+    }
+    if config.sftp_ingest.host.is_some() {
+        #[cfg(feature = "sftp")]
+        if let Some(ingestor) =
+            adi_svc::infrastructure::SftpIngestor::from_config(app_service.clone(), tracker_adapter.clone(), &config.sftp_ingest)?
         {
-            error!("gRPC server error: {}", e);
+            adi_svc::infrastructure::spawn_sftp_ingest(&supervisor, ingestor);
         }
+        #[cfg(not(feature = "sftp"))]
+        return Err("SFTP_HOST is set but this build lacks the `sftp` feature".into());
+    }
+    let tenants = TenantResolver::new(config.server.tenant_api_keys.clone());
+    if tenants.requires_key() {
+        info!("Tenants assigned by API key ({} keys)", config.server.tenant_api_keys.len());
+    }
+
+    // Start gRPC server
+    let grpc_handle = if config.server.enable_grpc {
+        let grpc_addr: std::net::SocketAddr = format!("{}:{}", config.server.host, config.server.grpc_port).parse()?;
+        let auth = Authenticator::new(tenants.clone()).with_admin_key(config.server.admin_api_key.as_ref().map(Secret::expose));
+        let grpc_service = GrpcDocumentIntelligenceService::new(app_service.clone())
+            .with_max_upload_bytes(config.storage.max_upload_size_mb * 1024 * 1024)
+            .with_authenticator(auth.clone());
+        
+        info!("Starting gRPC server on {}", grpc_addr);
+        let grpc_shutdown = shutdown.clone();
+        let grpc_server = async move {
+            if let Err(e) = Server::builder()
+                .layer(GrpcRequestIdLayer)
+                .add_service(DocumentIntelligenceServiceServer::with_interceptor(
+                    grpc_service,
+                    GrpcAuthInterceptor::new(auth),
+                ))
+                .serve_with_shutdown(grpc_addr, grpc_shutdown.clone().cancelled_owned())
+                .await
+            {
+                error!("gRPC server error: {}", e);
+            }
+            if !grpc_shutdown.is_cancelled() {
+                error!("gRPC server stopped unexpectedly");
+                grpc_shutdown.cancel();
+            }
+        };
+        Some(tokio::spawn(grpc_server))
+    } else {
+        None
     };
 
     // Start REST server
-    let rest_addr: std::net::SocketAddr = format!("{}:{}", config.server.host, config.server.rest_port).parse()?;
-    let rest_router = create_rest_router(app_service_rest);
-    
-    info!("Starting REST server on {}", rest_addr);
-    let rest_server = async move {
-        let listener = tokio::net::TcpListener::bind(rest_addr).await.unwrap();
-        if let Err(e) = axum::serve(listener, rest_router).await {
-            error!("REST server error: {}", e);
-        }
+    let rest_handle = if config.server.enable_rest {
+        let rest_addr: std::net::SocketAddr = format!("{}:{}", config.server.host, config.server.rest_port).parse()?;
+        let rest_options = RestOptions {
+            limits: BodyLimits::from_storage(&config.storage),
+            urls: PublicUrls::from_server(&config.server),
+            admin_api_key: config.server.admin_api_key.as_ref().map(|key| key.expose().to_string()),
+            tenants,
+            priorities: PriorityPolicy::new(config.jobs.priority_keys.clone()),
+            log_level: Some(log_level),
+            azure_api_version: Some(config.azure.api_version.clone()),
+            pipelines: pipeline_service,
+            training_export,
+            #[cfg(feature = "analytics")]
+            analytics_export,
+            exports: Some(export_service),
+        };
+        let rest_router = create_rest_router_with_options(app_service.clone(), rest_options);
+        
+        info!("Starting REST server on {}{}", rest_addr, config.server.base_path);
+        let listener = tokio::net::TcpListener::bind(rest_addr).await?;
+        let rest_shutdown = shutdown.clone();
+        let rest_server = async move {
+            if let Err(e) = axum::serve(listener, rest_router)
+                .with_graceful_shutdown(rest_shutdown.clone().cancelled_owned())
+                .await
+            {
+                error!("REST server error: {}", e);
+            }
+            if !rest_shutdown.is_cancelled() {
+                error!("REST server stopped unexpectedly");
+                rest_shutdown.cancel();
+            }
+        };
+        Some(tokio::spawn(rest_server))
+    } else {
+        None
     };
 
-    // Run both servers concurrently
     info!("adi-svc is running!");
-    info!("gRPC endpoint: {}:{}", config.server.host, config.server.grpc_port);
-    info!("REST endpoint: http://{}:{}", config.server.host, config.server.rest_port);
-    info!("Health check: http://{}:{}/health", config.server.host, config.server.rest_port);
+    if config.server.enable_grpc {
+        info!("gRPC endpoint: {}:{}", config.server.host, config.server.grpc_port);
+    }
+    if config.server.enable_rest {
+        info!("REST endpoint: http://{}:{}", config.server.host, config.server.rest_port);
+        info!("Health check: http://{}:{}/health", config.server.host, config.server.rest_port);
+    }
     
     tokio::select! {
-        _ = grpc_server => {
-            error!("gRPC server stopped unexpectedly");
-        }
-        _ = rest_server => {
-            error!("REST server stopped unexpectedly");
-        }
-        _ = tokio::signal::ctrl_c() => {
+        _ = shutdown.cancelled() => {}
+        _ = shutdown_signal() => {
             info!("Received shutdown signal");
         }
     }
 
     info!("Shutting down adi-svc...");
+    let grace = std::time::Duration::from_secs(config.server.shutdown_grace_secs);
+    let tasks_stopped = supervisor.shutdown(grace);
+    let servers_stopped = tokio::time::timeout(grace, async {
+        for handle in [grpc_handle, rest_handle].into_iter().flatten() {
+            let _ = handle.await;
+        }
+    });
+    let (tasks_stopped, servers_stopped) = tokio::join!(tasks_stopped, servers_stopped);
+    if !tasks_stopped || servers_stopped.is_err() {
+        error!("Shutdown grace period of {:?} expired", grace);
+    }
+
+    info!("adi-svc stopped");
     Ok(())
 }
 
+/// Resolve on Ctrl+C or, on Unix, SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            error!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
+
+/// Switch between debug logging and the configured filter on each SIGUSR1
+#[cfg(unix)]
+async fn toggle_debug_on_sigusr1(log_level: LogLevelControl) {
+    let mut signal = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::user_defined1()) {
+        Ok(signal) => signal,
+        Err(e) => {
+            error!("Failed to listen for SIGUSR1: {}", e);
+            return;
+        }
+    };
+    while signal.recv().await.is_some() {
+        match log_level.toggle_debug() {
+            Ok(filter) => warn!("SIGUSR1: log filter is now {}", filter),
+            Err(e) => error!("SIGUSR1: {}", e),
+        }
+    }
+}
+
+/// What a config reload updates
+#[derive(Clone)]
+struct Reloadable {
+    adapter: Option<Arc<AzureDocumentIntelligenceAdapter>>,
+    service: Arc<DocumentIntelligenceService>,
+    webhooks: Option<Arc<HttpWebhookSender>>,
+}
+
+/// Reload the reloadable settings on each SIGHUP and, if `interval_secs` is set, on that interval
+///
+/// These are the Azure key, `TENANT_QUOTAS` and the pipeline webhook timeout
+/// and secret; everything else still needs a restart.
+fn spawn_config_reload(supervisor: &TaskSupervisor, reloadable: Reloadable, interval_secs: Option<u64>) {
+    supervisor.spawn("config-reload", move |shutdown| {
+        let reloadable = reloadable.clone();
+        async move {
+            #[cfg(unix)]
+            let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                Ok(signal) => Some(signal),
+                Err(e) => {
+                    error!("Failed to listen for SIGHUP: {}", e);
+                    None
+                }
+            };
+            let mut ticker = interval_secs.map(|secs| {
+                let mut ticker = tokio::time::interval(std::time::Duration::from_secs(secs));
+                ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+                ticker
+            });
+            loop {
+                #[cfg(unix)]
+                let sighup = async {
+                    match hangup.as_mut() {
+                        Some(signal) => signal.recv().await,
+                        None => std::future::pending().await,
+                    }
+                };
+                #[cfg(not(unix))]
+                let sighup = std::future::pending::<Option<()>>();
+                let tick = async {
+                    match ticker.as_mut() {
+                        Some(ticker) => ticker.tick().await,
+                        None => std::future::pending().await,
+                    }
+                };
+                let trigger = tokio::select! {
+                    _ = shutdown.cancelled() => return,
+                    _ = sighup => "SIGHUP",
+                    _ = tick => "interval",
+                };
+                reload_config(&reloadable, trigger);
+            }
+        }
+    });
+}
+
+/// Apply each reloadable setting as its source reads now
+fn reload_config(reloadable: &Reloadable, trigger: &str) {
+    if let Some(adapter) = &reloadable.adapter {
+        match AzureConfig::reload_key() {
+            Ok(Some(key)) => {
+                if adapter.set_key(key) {
+                    warn!("{}: reloaded the Azure key", trigger);
+                }
+            }
+            Ok(None) if trigger == "SIGHUP" => {
+                warn!("SIGHUP: no AZURE_DOCUMENT_INTELLIGENCE_KEY_FILE or .env key to reload")
+            }
+            Ok(None) => {}
+            Err(e) => error!("{}: failed to reload the Azure key: {}", trigger, e),
+        }
+    }
+    match DatabaseConfig::reload_quotas() {
+        Ok(quotas) => {
+            if reloadable.service.set_configured_quotas(quotas) {
+                warn!("{}: reloaded TENANT_QUOTAS", trigger);
+            }
+        }
+        Err(e) => error!("{}: failed to reload TENANT_QUOTAS: {}", trigger, e),
+    }
+    if let Some(webhooks) = &reloadable.webhooks {
+        match PipelineConfig::reload_webhooks() {
+            Ok((timeout_secs, secret)) => {
+                let secret = secret.map(|secret| secret.expose().as_bytes().to_vec());
+                if webhooks.set_settings(std::time::Duration::from_secs(timeout_secs), secret) {
+                    warn!("{}: reloaded the pipeline webhook settings", trigger);
+                }
+            }
+            Err(e) => error!("{}: failed to reload the pipeline webhook settings: {}", trigger, e),
+        }
+    }
+}
+
+/// Publishers for every configured broker; a broker configured without its
+/// cargo feature is an error rather than silently dropped events
+async fn event_publishers(
+    config: &EventsConfig,
+) -> Result<Vec<Arc<dyn EventPublisherPort>>, Box<dyn std::error::Error>> {
+    let mut publishers: Vec<Arc<dyn EventPublisherPort>> = Vec::new();
+    
+    if let Some(brokers) = &config.kafka_brokers {
+        #[cfg(feature = "kafka")]
+        {
+            info!("Publishing lifecycle events to Kafka topic {} via {}", config.kafka_topic, brokers);
+            let publisher = adi_svc::infrastructure::KafkaEventPublisher::new(
+                brokers,
+                config.kafka_topic.clone(),
+                config.format,
+            )?;
+            publishers.push(Arc::new(publisher));
+        }
+        #[cfg(not(feature = "kafka"))]
+        return Err(format!("KAFKA_BROKERS is set ({}) but this build lacks the `kafka` feature", brokers).into());
+    }
+    
+    if config.amqp_url.is_some() {
+        #[cfg(feature = "amqp")]
+        if let Some(publisher) = adi_svc::infrastructure::AmqpEventPublisher::from_config(config).await? {
+            publishers.push(Arc::new(publisher));
+        }
+        #[cfg(not(feature = "amqp"))]
+        return Err("AMQP_URL is set but this build lacks the `amqp` feature".into());
+    }
+    
+    if config.sqs_queue_url.is_some() || config.sns_topic_arn.is_some() {
+        #[cfg(feature = "aws")]
+        publishers.extend(adi_svc::infrastructure::aws_event_publishers(config).await);
+        #[cfg(not(feature = "aws"))]
+        return Err("SQS_QUEUE_URL or SNS_TOPIC_ARN is set but this build lacks the `aws` feature".into());
+    }
+    
+    publishers.extend(adi_svc::infrastructure::azure_event_publishers(config));
+    
+    Ok(publishers)
+}
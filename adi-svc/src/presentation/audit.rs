@@ -0,0 +1,46 @@
+/// Caller identification for the audit log
+///
+/// The service does no authentication itself; callers are told apart by the
+/// API key they present (`X-Api-Key`, or `Authorization: Bearer`), which is
+/// recorded only as a fingerprint so the log never holds usable credentials.
+
+use sha2::{Digest, Sha256};
+
+/// Header (and gRPC metadata key) carrying the caller's API key
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Principal recorded for callers that present no credentials
+pub const ANONYMOUS: &str = "anonymous";
+
+/// The API key a request presents, preferring `X-Api-Key` over a bearer token
+pub fn presented_key<'a>(api_key: Option<&'a str>, authorization: Option<&'a str>) -> Option<&'a str> {
+    let bearer = authorization.and_then(|value| {
+        let (scheme, token) = value.trim().split_once(' ')?;
+        scheme.eq_ignore_ascii_case("bearer").then_some(token)
+    });
+    api_key.or(bearer).map(str::trim).filter(|key| !key.is_empty())
+}
+
+/// Audit principal for the credentials on a request
+pub fn principal(api_key: Option<&str>, authorization: Option<&str>) -> String {
+    match presented_key(api_key, authorization) {
+        Some(key) => format!("key:{}", &hex::encode(Sha256::digest(key.as_bytes()))[..16]),
+        None => ANONYMOUS.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_principal() {
+        let from_header = principal(Some("secret"), None);
+        assert!(from_header.starts_with("key:") && !from_header.contains("secret"));
+        assert_eq!(from_header.len(), 4 + 16);
+        assert_eq!(principal(None, Some("Bearer secret")), from_header);
+        assert_eq!(principal(Some("secret"), Some("Bearer other")), from_header);
+        assert_eq!(principal(None, Some("Basic dXNlcg==")), ANONYMOUS);
+        assert_eq!(principal(Some(" "), None), ANONYMOUS);
+    }
+}
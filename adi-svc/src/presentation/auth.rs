@@ -0,0 +1,89 @@
+/// Caller authentication
+///
+/// Turns the credentials on a request into a `Principal`: the tenant comes
+/// from `TenantResolver`, and the `ADMIN_API_KEY` holder is an admin acting
+/// for whichever tenant it names (the default tenant when it names none).
+
+use sha2::{Digest, Sha256};
+
+use crate::domain::{Principal, Role, TenantId};
+use super::audit::{presented_key, principal};
+use super::tenancy::{TenantRejection, TenantResolver};
+
+/// Maps request credentials to a principal
+#[derive(Debug, Clone, Default)]
+pub struct Authenticator {
+    tenants: TenantResolver,
+    /// SHA-256 of the admin key, so it is not compared directly
+    admin_key: Option<[u8; 32]>,
+}
+
+impl Authenticator {
+    pub fn new(tenants: TenantResolver) -> Self {
+        Self { tenants, admin_key: None }
+    }
+
+    /// Grant the admin role to callers presenting `admin_key`
+    pub fn with_admin_key(mut self, admin_key: Option<&str>) -> Self {
+        self.admin_key = admin_key
+            .filter(|key| !key.is_empty())
+            .map(|key| Sha256::digest(key.as_bytes()).into());
+        self
+    }
+
+    /// Whether an admin key is configured, so anyone can hold the admin role
+    pub fn admin_enabled(&self) -> bool {
+        self.admin_key.is_some()
+    }
+
+    /// Principal for a request's API key, bearer token and tenant header
+    pub fn authenticate(
+        &self,
+        api_key: Option<&str>,
+        authorization: Option<&str>,
+        tenant_header: Option<&str>,
+    ) -> Result<Principal, TenantRejection> {
+        let id = principal(api_key, authorization);
+        let is_admin = match (self.admin_key, presented_key(api_key, authorization)) {
+            (Some(expected), Some(key)) => <[u8; 32]>::from(Sha256::digest(key.as_bytes())) == expected,
+            _ => false,
+        };
+        if is_admin {
+            let tenant = tenant_header
+                .map(str::trim)
+                .filter(|tenant| !tenant.is_empty())
+                .map(|tenant| TenantId::new(tenant).map_err(|e| TenantRejection::Invalid(e.to_string())))
+                .transpose()?
+                .unwrap_or_default();
+            return Ok(Principal::new(id, tenant, vec![Role::Admin]));
+        }
+
+        let tenant = self.tenants.resolve(api_key, authorization, tenant_header)?;
+        Ok(Principal::new(id, tenant, vec![Role::Analyst]))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_authenticate() {
+        let acme = TenantId::new("acme").unwrap();
+        let auth = Authenticator::new(TenantResolver::new([("secret".to_string(), acme.clone())]))
+            .with_admin_key(Some("root"));
+
+        let analyst = auth.authenticate(Some("secret"), None, None).unwrap();
+        assert_eq!(analyst.tenant, acme);
+        assert_eq!(analyst.id, principal(Some("secret"), None));
+        assert!(analyst.has_role(Role::Analyst) && !analyst.has_role(Role::Admin));
+
+        let admin = auth.authenticate(None, Some("Bearer root"), Some("globex")).unwrap();
+        assert_eq!(admin.tenant.as_str(), "globex");
+        assert!(admin.has_role(Role::Admin) && admin.has_role(Role::Analyst));
+
+        assert_eq!(auth.authenticate(Some("other"), None, None), Err(TenantRejection::Unauthenticated));
+        let open = Authenticator::default().authenticate(Some("root"), None, None).unwrap();
+        assert_eq!(open.roles, vec![Role::Analyst]);
+    }
+}
@@ -21,18 +21,20 @@ pub fn pb_to_analyze_request(
         None => return Err("No document source provided".to_string()),
     };
     
-    let options = request.options.map(pb_to_options).unwrap_or_default();
+    let options = request.options.map(pb_to_options).transpose()?.unwrap_or_default();
     
     Ok(AnalyzeDocumentRequest {
         source,
         model_type,
         options,
+        metadata: None,
+        tenant_id: TenantId::default(),
     })
 }
 
 /// Convert protobuf AnalyzeOptions to domain AnalyzeOptions
-pub fn pb_to_options(options: pb::AnalyzeOptions) -> AnalyzeOptions {
-    AnalyzeOptions {
+pub fn pb_to_options(options: pb::AnalyzeOptions) -> Result<AnalyzeOptions, String> {
+    Ok(AnalyzeOptions {
         locale: if options.locale.is_empty() {
             None
         } else {
@@ -41,14 +43,21 @@ pub fn pb_to_options(options: pb::AnalyzeOptions) -> AnalyzeOptions {
         pages: if options.pages.is_empty() {
             None
         } else {
-            PageRange::new(options.pages).ok()
+            Some(PageRange::new(options.pages).map_err(|e| e.to_string())?)
         },
         features: options
             .features
             .into_iter()
             .filter_map(pb_to_feature)
             .collect(),
-    }
+        preprocess: options.preprocess.map(|preprocess| ImagePreprocessing {
+            deskew: preprocess.deskew,
+            grayscale: preprocess.grayscale,
+            max_dpi: Some(preprocess.max_dpi).filter(|dpi| *dpi > 0),
+            jpeg_quality: Some(preprocess.jpeg_quality.min(u8::MAX as u32) as u8).filter(|q| *q > 0),
+        }),
+        string_index_type: pb_to_string_index_type(options.string_index_type),
+    })
 }
 
 /// Convert protobuf Feature to domain AnalysisFeature
@@ -64,6 +73,44 @@ pub fn pb_to_feature(feature: i32) -> Option<AnalysisFeature> {
     }
 }
 
+/// Convert protobuf StringIndexType to domain StringIndexType, `None` when unspecified
+pub fn pb_to_string_index_type(string_index_type: i32) -> Option<StringIndexType> {
+    match pb::StringIndexType::try_from(string_index_type).ok()? {
+        pb::StringIndexType::TextElements => Some(StringIndexType::TextElements),
+        pb::StringIndexType::UnicodeCodePoint => Some(StringIndexType::UnicodeCodePoint),
+        pb::StringIndexType::Utf16CodeUnit => Some(StringIndexType::Utf16CodeUnit),
+        pb::StringIndexType::Unspecified => None,
+    }
+}
+
+/// Convert domain AnalyzeOptions to protobuf AnalyzeOptions
+pub fn options_to_pb(options: AnalyzeOptions) -> pb::AnalyzeOptions {
+    pb::AnalyzeOptions {
+        locale: options.locale.map(|locale| locale.as_str().to_string()).unwrap_or_default(),
+        pages: options.pages.map(|pages| pages.as_vec().to_vec()).unwrap_or_default(),
+        features: options.features.into_iter().map(feature_to_pb).collect(),
+        preprocess: options.preprocess.map(|preprocess| pb::ImagePreprocessing {
+            deskew: preprocess.deskew,
+            grayscale: preprocess.grayscale,
+            max_dpi: preprocess.max_dpi.unwrap_or_default(),
+            jpeg_quality: preprocess.jpeg_quality.unwrap_or_default() as u32,
+        }),
+        string_index_type: options.string_index_type.map(string_index_type_to_pb).unwrap_or_default(),
+    }
+}
+
+/// Convert domain AnalysisFeature to protobuf Feature
+pub fn feature_to_pb(feature: AnalysisFeature) -> i32 {
+    match feature {
+        AnalysisFeature::OcrHighResolution => pb::Feature::OcrHighResolution as i32,
+        AnalysisFeature::Languages => pb::Feature::Languages as i32,
+        AnalysisFeature::Barcodes => pb::Feature::Barcodes as i32,
+        AnalysisFeature::Formulas => pb::Feature::Formulas as i32,
+        AnalysisFeature::StyleFont => pb::Feature::StyleFont as i32,
+        AnalysisFeature::KeyValuePairs => pb::Feature::KeyValuePairs as i32,
+    }
+}
+
 /// Convert domain AnalysisOperation to protobuf AnalyzeResponse
 pub fn operation_to_pb_response(
     operation: AnalysisOperation,
@@ -73,7 +120,41 @@ pub fn operation_to_pb_response(
         status: operation_status_to_pb(operation.status) as i32,
         operation_id: operation.operation_id,
         result: result.map(result_to_pb),
-        error: None,
+        error: operation.error.map(azure_error_to_pb),
+        document_id: operation.document_id.unwrap_or_default(),
+        filtered: None,
+    }
+}
+
+/// Convert an Azure error to protobuf, its inner code and request id as details
+pub fn azure_error_to_pb(error: AzureError) -> pb::Error {
+    let detail = |code: String, message: &str| pb::Error {
+        code,
+        message: message.to_string(),
+        target: String::new(),
+        details: vec![],
+    };
+    let mut details = Vec::new();
+    if let Some(inner_code) = error.inner_code {
+        details.push(detail(inner_code, ""));
+    }
+    if let Some(request_id) = error.request_id {
+        details.push(detail("RequestId".to_string(), &request_id));
+    }
+    pb::Error {
+        code: error.code,
+        message: error.message,
+        target: error.target.unwrap_or_default(),
+        details,
+    }
+}
+
+/// Convert domain ConfidenceFiltered counts to protobuf
+pub fn confidence_filtered_to_pb(filtered: ConfidenceFiltered) -> pb::ConfidenceFiltered {
+    pb::ConfidenceFiltered {
+        words: filtered.words as u32,
+        key_value_pairs: filtered.key_value_pairs as u32,
+        document_fields: filtered.document_fields as u32,
     }
 }
 
@@ -88,6 +169,43 @@ pub fn operation_status_to_pb(status: OperationStatus) -> i32 {
     }
 }
 
+/// Convert a domain LifecycleEvent to its protobuf message
+pub fn lifecycle_event_to_pb(event: &LifecycleEvent) -> pb::OperationLifecycleEvent {
+    let event_type = match event.event_type {
+        LifecycleEventKind::Created => pb::LifecycleEventType::Created,
+        LifecycleEventKind::Succeeded => pb::LifecycleEventType::Succeeded,
+        LifecycleEventKind::Failed => pb::LifecycleEventType::Failed,
+        LifecycleEventKind::Processed => pb::LifecycleEventType::Processed,
+    };
+    pb::OperationLifecycleEvent {
+        event_id: event.event_id.clone(),
+        event_type: event_type as i32,
+        occurred_at_unix_ms: event.occurred_at.timestamp_millis(),
+        operation_id: event.operation_id.clone(),
+        tenant_id: event.tenant_id.to_string(),
+        model_id: event.model.clone(),
+        status: operation_status_to_pb(event.status),
+        document_id: event.document_id.clone().unwrap_or_default(),
+        summary: event.summary.as_ref().map(|summary| pb::ResultSummary {
+            pages: summary.pages,
+            tables: summary.tables,
+            key_value_pairs: summary.key_value_pairs,
+            document_types: summary.document_types.clone(),
+            content_length: summary.content_length,
+        }),
+        pipeline: event.pipeline.clone().unwrap_or_default(),
+    }
+}
+
+/// Convert domain StringIndexType to protobuf StringIndexType
+pub fn string_index_type_to_pb(string_index_type: StringIndexType) -> i32 {
+    match string_index_type {
+        StringIndexType::TextElements => pb::StringIndexType::TextElements as i32,
+        StringIndexType::UnicodeCodePoint => pb::StringIndexType::UnicodeCodePoint as i32,
+        StringIndexType::Utf16CodeUnit => pb::StringIndexType::Utf16CodeUnit as i32,
+    }
+}
+
 /// Convert domain AnalysisResult to protobuf AnalysisResult
 pub fn result_to_pb(result: AnalysisResult) -> pb::AnalysisResult {
     pb::AnalysisResult {
@@ -100,6 +218,7 @@ pub fn result_to_pb(result: AnalysisResult) -> pb::AnalysisResult {
         entities: vec![],
         styles: vec![],
         documents: result.documents.into_iter().map(document_to_pb).collect(),
+        string_index_type: string_index_type_to_pb(result.string_index_type),
     }
 }
 
@@ -0,0 +1,518 @@
+/// GraphQL query API
+///
+/// Exposes a tenant's operations and their results as a graph, so a client
+/// can fetch exactly the sections, pages, tables and fields it renders in one
+/// request. Result sections are only loaded when the query selects them.
+/// Served at `/graphql` by the REST router when built with the `graphql`
+/// feature.
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Enum, Json, Object, Schema, SimpleObject};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::application::errors::ApplicationError;
+use crate::application::services::DocumentIntelligenceService;
+use crate::domain::{self, FieldQuery, OperationListQuery, ResultFields, TenantId};
+
+/// Deepest selection a query may nest, well past the deepest path in the schema
+const MAX_QUERY_DEPTH: usize = 12;
+
+pub type GraphqlSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+/// Schema resolving against `service`; each request supplies its `TenantId`
+pub fn schema(service: Arc<DocumentIntelligenceService>) -> GraphqlSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(service)
+        .limit_depth(MAX_QUERY_DEPTH)
+        .finish()
+}
+
+fn service<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a Arc<DocumentIntelligenceService>> {
+    ctx.data::<Arc<DocumentIntelligenceService>>()
+}
+
+fn tenant<'a>(ctx: &Context<'a>) -> async_graphql::Result<&'a TenantId> {
+    ctx.data::<TenantId>()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// One of the caller's operations, refreshed from Azure while it runs
+    async fn operation(&self, ctx: &Context<'_>, id: String) -> async_graphql::Result<Option<Operation>> {
+        match service(ctx)?.get_analysis_result_fields(tenant(ctx)?, &id, &ResultFields::none()).await {
+            Ok((operation, _)) => Ok(Some(Operation(operation))),
+            Err(ApplicationError::OperationNotFound(_)) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// The caller's operations, newest first
+    async fn operations(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<OperationStatus>,
+        #[graphql(desc = "Only operations created before this instant, for paging")] before: Option<DateTime<Utc>>,
+        #[graphql(desc = "ID of the last operation of the previous page, for paging with `before`")]
+        before_id: Option<String>,
+        #[graphql(default = 50)] limit: u32,
+    ) -> async_graphql::Result<Vec<Operation>> {
+        let query = OperationListQuery {
+            status: status.map(Into::into),
+            before,
+            before_id,
+            limit,
+            ..Default::default()
+        };
+        let operations = service(ctx)?.list_operations(tenant(ctx)?, &query).await?;
+        Ok(operations.into_iter().map(Operation).collect())
+    }
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "domain::OperationStatus")]
+pub enum OperationStatus {
+    NotStarted,
+    Running,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "domain::ModelType")]
+pub enum ModelType {
+    Read,
+    Layout,
+    Invoice,
+    Receipt,
+    IdDocument,
+    BusinessCard,
+    W2,
+    Custom,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "domain::OperationEventKind")]
+pub enum OperationEventKind {
+    Submitted,
+    Running,
+    Succeeded,
+    Failed,
+    Canceled,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "domain::SelectionMarkState")]
+pub enum SelectionMarkState {
+    Selected,
+    Unselected,
+}
+
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(remote = "domain::CellKind")]
+pub enum CellKind {
+    Content,
+    RowHeader,
+    ColumnHeader,
+    StubHead,
+    Description,
+}
+
+pub struct Operation(domain::AnalysisOperation);
+
+#[Object]
+impl Operation {
+    async fn id(&self) -> &str {
+        &self.0.operation_id
+    }
+
+    async fn status(&self) -> OperationStatus {
+        self.0.status.into()
+    }
+
+    async fn model_type(&self) -> ModelType {
+        self.0.model_type.into()
+    }
+
+    async fn created_at(&self) -> DateTime<Utc> {
+        self.0.created_at
+    }
+
+    async fn last_updated(&self) -> DateTime<Utc> {
+        self.0.last_updated
+    }
+
+    async fn document_id(&self) -> Option<&str> {
+        self.0.document_id.as_deref()
+    }
+
+    async fn filename(&self) -> Option<&str> {
+        self.0.filename.as_deref()
+    }
+
+    async fn content_type(&self) -> Option<&str> {
+        self.0.content_type.as_deref()
+    }
+
+    async fn content_sha256(&self) -> Option<&str> {
+        self.0.content_sha256.as_deref()
+    }
+
+    async fn page_count(&self) -> Option<u32> {
+        self.0.page_count
+    }
+
+    /// Why Azure failed the operation, shaped as in the REST API
+    async fn error(&self) -> Option<Json<domain::AzureError>> {
+        self.0.error.clone().map(Json)
+    }
+
+    /// How `analyze/auto` chose the model, shaped as in the REST API
+    async fn routing(&self) -> Option<Json<domain::RoutingDecision>> {
+        self.0.routing.clone().map(Json)
+    }
+
+    /// The result once the operation has succeeded, with only the selected sections loaded
+    async fn result(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<AnalysisResult>> {
+        if self.0.status != domain::OperationStatus::Succeeded {
+            return Ok(None);
+        }
+        let selection = ctx.look_ahead();
+        let combined = selection.field("fields").exists() || selection.field("normalizedFields").exists();
+        let fields = ResultFields {
+            content: selection.field("content").exists(),
+            pages: selection.field("pages").exists(),
+            tables: selection.field("tables").exists(),
+            key_value_pairs: selection.field("keyValuePairs").exists() || combined,
+            documents: selection.field("documents").exists() || combined,
+        };
+        let (_, result) = service(ctx)?
+            .completed_result_fields(tenant(ctx)?, &self.0.operation_id, &fields)
+            .await?;
+        Ok(Some(AnalysisResult(result)))
+    }
+
+    /// Recorded status transitions, oldest first
+    async fn events(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<OperationEvent>> {
+        let events = service(ctx)?.operation_events(tenant(ctx)?, &self.0.operation_id).await?;
+        Ok(events
+            .into_iter()
+            .map(|event| OperationEvent {
+                kind: event.kind.into(),
+                at: event.at,
+                detail: event.detail,
+            })
+            .collect())
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct OperationEvent {
+    kind: OperationEventKind,
+    at: DateTime<Utc>,
+    detail: Option<String>,
+}
+
+pub struct AnalysisResult(domain::AnalysisResult);
+
+#[Object]
+impl AnalysisResult {
+    async fn model_id(&self) -> &str {
+        &self.0.model_id
+    }
+
+    async fn api_version(&self) -> &str {
+        &self.0.api_version
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    /// How span offsets count characters: textElements, unicodeCodePoint or utf16CodeUnit
+    async fn string_index_type(&self) -> &str {
+        self.0.string_index_type.as_str()
+    }
+
+    /// Pages, all of them or those numbered in `numbers`
+    async fn pages(&self, numbers: Option<Vec<i32>>) -> Vec<Page<'_>> {
+        self.0
+            .pages
+            .iter()
+            .filter(|page| numbers.as_ref().is_none_or(|numbers| numbers.contains(&page.page_number)))
+            .map(Page)
+            .collect()
+    }
+
+    async fn tables(&self) -> Vec<Table<'_>> {
+        self.0.tables.iter().map(Table).collect()
+    }
+
+    /// Key-value pairs, optionally with this key (case-insensitive) or at least this confidence
+    async fn key_value_pairs(
+        &self,
+        key: Option<String>,
+        min_confidence: Option<f32>,
+    ) -> async_graphql::Result<Vec<KeyValuePair>> {
+        let query = FieldQuery::new(key, min_confidence)?;
+        Ok(self
+            .0
+            .key_value_pairs
+            .iter()
+            .filter(|pair| query.matches(&pair.key, pair.confidence))
+            .map(|pair| KeyValuePair {
+                key: pair.key.clone(),
+                value: pair.value.clone(),
+                confidence: pair.confidence,
+            })
+            .collect())
+    }
+
+    /// Documents extracted by prebuilt models, optionally of one type
+    async fn documents(&self, doc_type: Option<String>) -> Vec<ExtractedDocument<'_>> {
+        self.0
+            .documents
+            .iter()
+            .filter(|document| doc_type.as_ref().is_none_or(|doc_type| document.doc_type == *doc_type))
+            .map(ExtractedDocument)
+            .collect()
+    }
+
+    /// Key-value pairs and document fields together, as `/api/v1/results/{id}/fields` returns them
+    async fn fields(&self, key: Option<String>, min_confidence: Option<f32>) -> async_graphql::Result<Vec<FieldMatch>> {
+        let query = FieldQuery::new(key, min_confidence)?;
+        Ok(self.0.query_fields(&query).into_iter().map(FieldMatch::from).collect())
+    }
+
+    /// Canonical forms of the fields that have one, keyed by field name
+    async fn normalized_fields(
+        &self,
+        key: Option<String>,
+        min_confidence: Option<f32>,
+    ) -> async_graphql::Result<Json<BTreeMap<String, domain::NormalizedValue>>> {
+        let query = FieldQuery::new(key, min_confidence)?;
+        Ok(Json(domain::normalized_fields(&self.0.query_fields(&query))))
+    }
+}
+
+pub struct Page<'a>(&'a domain::DocumentPage);
+
+#[Object]
+impl Page<'_> {
+    async fn page_number(&self) -> i32 {
+        self.0.page_number
+    }
+
+    async fn angle(&self) -> f32 {
+        self.0.angle
+    }
+
+    async fn width(&self) -> f32 {
+        self.0.width
+    }
+
+    async fn height(&self) -> f32 {
+        self.0.height
+    }
+
+    async fn unit(&self) -> &str {
+        &self.0.unit
+    }
+
+    /// Words, optionally only those with at least this confidence
+    async fn words(&self, min_confidence: Option<f32>) -> Vec<Word<'_>> {
+        self.0
+            .words
+            .iter()
+            .filter(|word| min_confidence.is_none_or(|min| word.confidence >= min))
+            .map(Word)
+            .collect()
+    }
+
+    async fn lines(&self) -> Vec<Line<'_>> {
+        self.0.lines.iter().map(Line).collect()
+    }
+
+    async fn selection_marks(&self) -> Vec<SelectionMark> {
+        self.0
+            .selection_marks
+            .iter()
+            .map(|mark| SelectionMark {
+                state: mark.state.into(),
+                confidence: mark.confidence,
+                polygon: polygon(&mark.polygon),
+            })
+            .collect()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Point {
+    x: f32,
+    y: f32,
+}
+
+fn polygon(points: &[domain::Point]) -> Vec<Point> {
+    points.iter().map(|point| Point { x: point.x, y: point.y }).collect()
+}
+
+pub struct Word<'a>(&'a domain::DocumentWord);
+
+#[Object]
+impl Word<'_> {
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn confidence(&self) -> f32 {
+        self.0.confidence
+    }
+
+    async fn polygon(&self) -> Vec<Point> {
+        polygon(&self.0.polygon)
+    }
+
+    /// Offset of the word in the result's `content`
+    async fn offset(&self) -> i32 {
+        self.0.span.offset
+    }
+
+    async fn length(&self) -> i32 {
+        self.0.span.length
+    }
+}
+
+pub struct Line<'a>(&'a domain::DocumentLine);
+
+#[Object]
+impl Line<'_> {
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn polygon(&self) -> Vec<Point> {
+        polygon(&self.0.polygon)
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct SelectionMark {
+    state: SelectionMarkState,
+    confidence: f32,
+    polygon: Vec<Point>,
+}
+
+pub struct Table<'a>(&'a domain::DocumentTable);
+
+#[Object]
+impl Table<'_> {
+    async fn row_count(&self) -> i32 {
+        self.0.row_count
+    }
+
+    async fn column_count(&self) -> i32 {
+        self.0.column_count
+    }
+
+    /// Cells, optionally only those of one kind such as `COLUMN_HEADER`
+    async fn cells(&self, kind: Option<CellKind>) -> Vec<TableCell> {
+        self.0
+            .cells
+            .iter()
+            .filter(|cell| kind.is_none_or(|kind| cell.kind == kind.into()))
+            .map(|cell| TableCell {
+                kind: cell.kind.into(),
+                row_index: cell.row_index,
+                column_index: cell.column_index,
+                row_span: cell.row_span,
+                column_span: cell.column_span,
+                content: cell.content.clone(),
+            })
+            .collect()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct TableCell {
+    kind: CellKind,
+    row_index: i32,
+    column_index: i32,
+    row_span: i32,
+    column_span: i32,
+    content: String,
+}
+
+#[derive(SimpleObject)]
+pub struct KeyValuePair {
+    key: String,
+    value: String,
+    confidence: f32,
+}
+
+pub struct ExtractedDocument<'a>(&'a domain::ExtractedDocument);
+
+#[Object]
+impl ExtractedDocument<'_> {
+    async fn doc_type(&self) -> &str {
+        &self.0.doc_type
+    }
+
+    async fn confidence(&self) -> f32 {
+        self.0.confidence
+    }
+
+    /// Fields sorted by name, all of them or those named in `names`
+    async fn fields(&self, names: Option<Vec<String>>) -> Vec<DocumentField> {
+        let mut fields: Vec<DocumentField> = self
+            .0
+            .fields
+            .iter()
+            .filter(|(name, _)| names.as_ref().is_none_or(|names| names.contains(name)))
+            .map(|(name, value)| DocumentField {
+                name: name.clone(),
+                value: Json(value.clone()),
+            })
+            .collect();
+        fields.sort_by(|a, b| a.name.cmp(&b.name));
+        fields
+    }
+}
+
+/// A named field; its value is typed as in the REST API, e.g. `{"type": "number", "value": 42.5}`
+#[derive(SimpleObject)]
+pub struct DocumentField {
+    name: String,
+    value: Json<domain::DocumentField>,
+}
+
+/// Key-value pair or document field selected by `AnalysisResult.fields`
+#[derive(SimpleObject)]
+pub struct FieldMatch {
+    /// Document type for document fields, absent for key-value pairs
+    doc_type: Option<String>,
+    name: String,
+    value: Json<domain::DocumentField>,
+    confidence: f32,
+}
+
+impl From<domain::FieldMatch> for FieldMatch {
+    fn from(field: domain::FieldMatch) -> Self {
+        match field {
+            domain::FieldMatch::KeyValuePair { key, value, confidence } => Self {
+                doc_type: None,
+                name: key,
+                value: Json(domain::DocumentField::String(value)),
+                confidence,
+            },
+            domain::FieldMatch::DocumentField { doc_type, name, value, confidence } => Self {
+                doc_type: Some(doc_type),
+                name,
+                value: Json(value),
+                confidence,
+            },
+        }
+    }
+}
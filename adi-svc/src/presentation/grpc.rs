@@ -2,51 +2,457 @@
 /// 
 /// This module implements the DocumentIntelligenceService gRPC service.
 
+use bytes::BytesMut;
 use std::sync::Arc;
-use tonic::{Request, Response, Status};
-use tracing::{info, error};
-use futures::Stream;
+use std::time::Duration;
+use tokio::time::Instant;
+use tonic::codegen::{http, BoxFuture, Context, Poll, Service};
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::service::Interceptor;
+use tonic::{Code, Request, Response, Status};
+use tracing::{info, error, Instrument};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
 use std::pin::Pin;
+use sha2::{Digest, Sha256};
 
+use crate::application::deadline::with_deadline;
+use crate::application::request_id::{self, with_request_id};
+use crate::application::errors::ApplicationError;
 use crate::application::services::DocumentIntelligenceService;
 use crate::domain::*;
 use crate::generated as pb;
 use crate::generated::document_intelligence_service_server::DocumentIntelligenceService as DocumentIntelligenceServiceTrait;
+use super::audit::API_KEY_HEADER;
+use super::auth::Authenticator;
 use super::converters::*;
+use super::rest::REQUEST_ID_HEADER;
+use super::tenancy::{TenantRejection, TenantResolver, TENANT_HEADER};
+
+/// Default cap on streamed uploads, matching the storage default of 50 MB
+const DEFAULT_MAX_UPLOAD_BYTES: usize = 50 * 1024 * 1024;
 
 /// gRPC service implementation
 pub struct GrpcDocumentIntelligenceService {
     service: Arc<DocumentIntelligenceService>,
+    max_upload_bytes: usize,
+    auth: Authenticator,
 }
 
 impl GrpcDocumentIntelligenceService {
     pub fn new(service: Arc<DocumentIntelligenceService>) -> Self {
-        Self { service }
+        Self {
+            service,
+            max_upload_bytes: DEFAULT_MAX_UPLOAD_BYTES,
+            auth: Authenticator::default(),
+        }
+    }
+    
+    /// Limit the total size of an `UploadAndAnalyze` stream
+    pub fn with_max_upload_bytes(mut self, max_upload_bytes: usize) -> Self {
+        self.max_upload_bytes = max_upload_bytes;
+        self
+    }
+    
+    /// Assign requests to tenants by API key instead of the `x-tenant-id` metadata
+    pub fn with_tenants(mut self, tenants: TenantResolver) -> Self {
+        self.auth = Authenticator::new(tenants);
+        self
+    }
+    
+    /// Authenticate requests `GrpcAuthInterceptor` has not already authenticated
+    pub fn with_authenticator(mut self, auth: Authenticator) -> Self {
+        self.auth = auth;
+        self
+    }
+    
+    /// The caller: the principal `GrpcAuthInterceptor` attached, or one
+    /// resolved from the metadata when the service runs without it
+    fn principal<T>(&self, request: &Request<T>) -> Result<Principal, TenantRejection> {
+        match request.extensions().get::<Principal>() {
+            Some(principal) => Ok(principal.clone()),
+            None => authenticate(&self.auth, request.metadata()),
+        }
+    }
+    
+    /// Submit a document to a prebuilt model
+    async fn start_prebuilt(
+        &self,
+        request: Request<pb::AnalyzeRequest>,
+        model_type: ModelType,
+    ) -> Result<AnalysisOperation, Status> {
+        let principal = self.principal(&request).map_err(tenant_status)?;
+        self.service.authorize(&principal, Role::Analyst).map_err(permission_status)?;
+        let tenant = principal.tenant;
+        let deadline = request_deadline(request.metadata());
+        let mut domain_request = pb_to_analyze_request(request.into_inner(), model_type)
+            .map_err(Status::invalid_argument)?;
+        domain_request.tenant_id = tenant;
+        
+        with_deadline(deadline, self.service.analyze_document(domain_request))
+            .await
+            .map_err(application_status)
+    }
+    
+    /// Submit a document to a custom model
+    async fn start_custom(&self, request: Request<pb::AnalyzeCustomRequest>) -> Result<AnalysisOperation, Status> {
+        let principal = self.principal(&request).map_err(tenant_status)?;
+        self.service.authorize(&principal, Role::Analyst).map_err(permission_status)?;
+        let tenant = principal.tenant;
+        let deadline = request_deadline(request.metadata());
+        let req = request.into_inner();
+        let model_id = req.model_id.clone();
+        
+        let source = match req.source {
+            Some(pb::analyze_custom_request::Source::DocumentUrl(url)) => {
+                DocumentSource::Url(url)
+            }
+            Some(pb::analyze_custom_request::Source::DocumentBytes(bytes)) => {
+                DocumentSource::Bytes(bytes)
+            }
+            None => return Err(Status::invalid_argument("No document source provided")),
+        };
+        
+        with_deadline(deadline, self.service.analyze_custom(&tenant, source, &model_id))
+            .await
+            .map_err(application_status)
     }
+    
+    /// Collect an upload stream and submit it
+    async fn start_upload(
+        &self,
+        request: Request<tonic::Streaming<pb::UploadRequest>>,
+    ) -> Result<UploadOutcome, Status> {
+        let principal = self.principal(&request).map_err(tenant_status)?;
+        self.service.authorize(&principal, Role::Analyst).map_err(permission_status)?;
+        let tenant = principal.tenant;
+        let deadline = request_deadline(request.metadata());
+        let mut stream = request.into_inner();
+        let mut metadata: Option<pb::UploadMetadata> = None;
+        let mut chunks = BytesMut::new();
+        let mut hasher = Sha256::new();
+        
+        // Collect chunks, rejecting the stream as soon as it exceeds the limit
+        while let Some(upload_req) = stream.message().await? {
+            match upload_req.data {
+                Some(pb::upload_request::Data::Metadata(meta)) => {
+                    metadata = Some(meta);
+                }
+                Some(pb::upload_request::Data::Chunk(chunk)) => {
+                    let size = chunks.len() + chunk.len();
+                    if size > self.max_upload_bytes {
+                        let err = DomainError::DocumentTooLarge {
+                            size,
+                            max: self.max_upload_bytes,
+                        };
+                        return Err(Status::resource_exhausted(err.to_string()));
+                    }
+                    hasher.update(&chunk);
+                    chunks.extend_from_slice(&chunk);
+                }
+                None => {}
+            }
+        }
+        
+        let metadata = metadata.ok_or_else(|| {
+            Status::invalid_argument("No metadata provided")
+        })?;
+        
+        let model_type = ModelType::from_string(&metadata.model_type)
+            .map_err(|e| Status::invalid_argument(e.to_string()))?;
+        
+        let expected_sha256 = metadata.expected_sha256.trim().to_ascii_lowercase();
+        if !expected_sha256.is_empty() {
+            if expected_sha256.len() != 64 || !expected_sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+                return Err(Status::invalid_argument("expected_sha256 must be 64 hex characters"));
+            }
+            let actual_sha256 = hex::encode(hasher.finalize());
+            if actual_sha256 != expected_sha256 {
+                error!("Upload checksum mismatch for {}", metadata.filename);
+                return Ok(UploadOutcome::ChecksumMismatch {
+                    expected: expected_sha256,
+                    actual: actual_sha256,
+                });
+            }
+        }
+        
+        let domain_request = AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(chunks.freeze()),
+            model_type,
+            options: Default::default(),
+            metadata: Some(DocumentMetadata::new(metadata.filename, metadata.content_type)),
+            tenant_id: tenant,
+        };
+        
+        with_deadline(deadline, self.service.analyze_document(domain_request))
+            .await
+            .map(|operation| UploadOutcome::Started(Box::new(operation)))
+            .map_err(application_status)
+    }
+    
+    /// Record a mutating RPC: the operation it started, or the code it failed with
+    async fn audit(&self, principal: &str, rpc: &str, started: Result<&AnalysisOperation, String>) {
+        if !self.service.audit_enabled() {
+            return;
+        }
+        let action = format!("grpc {}", rpc);
+        let entry = match started {
+            Ok(operation) => {
+                AuditEntry::new(principal, &action, AuditOutcome::Success, "Ok").with_operation(operation)
+            }
+            Err(code) => AuditEntry::new(principal, &action, AuditOutcome::Failure, code),
+        };
+        self.service.record_audit(&entry).await;
+    }
+}
+
+/// How an `UploadAndAnalyze` stream ended when it did not fail outright
+enum UploadOutcome {
+    Started(Box<AnalysisOperation>),
+    ChecksumMismatch { expected: String, actual: String },
+}
+
+/// Audit principal for a request, also for callers that fail authentication
+fn request_principal<T>(request: &Request<T>) -> String {
+    match request.extensions().get::<Principal>() {
+        Some(principal) => principal.id.clone(),
+        None => {
+            let value = |key| request.metadata().get(key).and_then(|value| value.to_str().ok());
+            super::audit::principal(value(API_KEY_HEADER), value("authorization"))
+        }
+    }
+}
+
+/// Principal for the API key, bearer token and `x-tenant-id` in `metadata`
+fn authenticate(auth: &Authenticator, metadata: &MetadataMap) -> Result<Principal, TenantRejection> {
+    let value = |key| metadata.get(key).and_then(|value| value.to_str().ok());
+    auth.authenticate(value(API_KEY_HEADER), value("authorization"), value(TENANT_HEADER))
+}
+
+/// Deadline the caller set with `grpc-timeout`
+///
+/// tonic also drops the handler when it passes; running the work under it
+/// lets Azure calls stop at the same moment and report `DEADLINE_EXCEEDED`.
+/// Malformed values are ignored, as tonic ignores them.
+fn request_deadline(metadata: &MetadataMap) -> Option<Instant> {
+    let timeout = metadata.get("grpc-timeout")?.to_str().ok()?;
+    parse_grpc_timeout(timeout).map(|timeout| Instant::now() + timeout)
+}
+
+/// Parse a `grpc-timeout` value: at most 8 digits and a unit (`H`, `M`, `S`, `m`, `u` or `n`)
+fn parse_grpc_timeout(value: &str) -> Option<Duration> {
+    let split = value.len().checked_sub(1)?;
+    let (amount, unit) = (value.get(..split)?, value.get(split..)?);
+    if amount.is_empty() || amount.len() > 8 || !amount.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 3600)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+/// Authenticates every RPC before it reaches the service
+///
+/// Rejects calls with missing or unknown credentials and attaches the
+/// caller's `Principal` to the request extensions, where the service reads
+/// its tenant, roles and audit identity. Rejected calls never reach the
+/// service, so they are not audited.
+#[derive(Debug, Clone)]
+pub struct GrpcAuthInterceptor {
+    auth: Arc<Authenticator>,
+}
+
+impl GrpcAuthInterceptor {
+    pub fn new(auth: Authenticator) -> Self {
+        Self { auth: Arc::new(auth) }
+    }
+}
+
+impl Interceptor for GrpcAuthInterceptor {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        let principal = authenticate(&self.auth, request.metadata()).map_err(tenant_status)?;
+        request.extensions_mut().insert(principal);
+        Ok(request)
+    }
+}
+
+/// Serves each RPC under the caller's `x-request-id` metadata or a new id
+///
+/// The id is echoed in the response metadata, also on failed calls, and
+/// recorded on the span every log line of the call carries.
+#[derive(Debug, Clone, Default)]
+pub struct GrpcRequestIdLayer;
+
+impl<S> tower::Layer<S> for GrpcRequestIdLayer {
+    type Service = GrpcRequestId<S>;
+    
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcRequestId { inner }
+    }
+}
+
+/// Service added by `GrpcRequestIdLayer`
+#[derive(Debug, Clone)]
+pub struct GrpcRequestId<S> {
+    inner: S,
+}
+
+impl<S, B, ResB> Service<http::Request<B>> for GrpcRequestId<S>
+where
+    S: Service<http::Request<B>, Response = http::Response<ResB>>,
+    S::Future: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+    
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+    
+    fn call(&mut self, mut request: http::Request<B>) -> Self::Future {
+        let presented = request.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok());
+        let id = request_id::resolve(presented);
+        let value = http::HeaderValue::from_str(&id).expect("request ids are visible ASCII");
+        request.headers_mut().insert(REQUEST_ID_HEADER, value.clone());
+        let span = tracing::info_span!("rpc", method = %request.uri().path(), request_id = %id);
+        
+        let response = self.inner.call(request);
+        Box::pin(
+            with_request_id(id, async move {
+                let mut response = response.await?;
+                response.headers_mut().insert(REQUEST_ID_HEADER, value);
+                Ok(response)
+            })
+            .instrument(span),
+        )
+    }
+}
+
+/// Map a tenant resolution failure onto a gRPC status
+fn tenant_status(rejection: TenantRejection) -> Status {
+    match rejection {
+        TenantRejection::Unauthenticated => Status::unauthenticated(rejection.to_string()),
+        TenantRejection::Mismatch => Status::permission_denied(rejection.to_string()),
+        TenantRejection::Invalid(message) => Status::invalid_argument(message),
+    }
+}
+
+/// Map an authorization failure onto a gRPC status
+fn permission_status(err: ApplicationError) -> Status {
+    Status::permission_denied(err.to_string())
+}
+
+/// gRPC code name as recorded in the audit log, e.g. `InvalidArgument`
+fn status_code_name(status: &Status) -> String {
+    format!("{:?}", status.code())
+}
+
+/// Failed response reporting a checksum mismatch on an uploaded document
+fn checksum_mismatch_response(expected: &str, actual: &str) -> pb::AnalyzeResponse {
+    pb::AnalyzeResponse {
+        status: pb::AnalysisStatus::StatusFailed as i32,
+        operation_id: String::new(),
+        result: None,
+        error: Some(pb::Error {
+            code: "ChecksumMismatch".to_string(),
+            message: format!("Expected SHA-256 {} but received {}", expected, actual),
+            target: "expected_sha256".to_string(),
+            details: vec![],
+        }),
+        document_id: String::new(),
+        filtered: None,
+    }
+}
+
+/// Map application failures onto gRPC status codes; throttling carries a
+/// `retry-after` entry (seconds) in the trailers, as the REST header does,
+/// and errors Azure reported carry it as an encoded `pb::Error` in the details
+fn application_status(err: ApplicationError) -> Status {
+    let message = err.to_string();
+    let azure_error = err.azure_error().cloned();
+    let mut retry_after_secs = None;
+    let code = match err {
+        ApplicationError::OperationNotFound(_)
+        | ApplicationError::DocumentNotFound(_)
+        | ApplicationError::UploadNotFound(_)
+        | ApplicationError::JobNotFound(_)
+        | ApplicationError::QuotaNotFound(_)
+        | ApplicationError::PageNotFound(_)
+        | ApplicationError::TableNotFound(_)
+        | ApplicationError::TemplateNotFound(_)
+        | ApplicationError::PipelineNotFound(_)
+        | ApplicationError::PipelineRunNotFound(_)
+        | ApplicationError::ExportNotFound(_) => Code::NotFound,
+        ApplicationError::Domain(_) => Code::InvalidArgument,
+        ApplicationError::PermissionDenied(_) | ApplicationError::InvalidSignature(_) => Code::PermissionDenied,
+        ApplicationError::MalwareDetected(_)
+        | ApplicationError::LeaseNotHeld(_)
+        | ApplicationError::UploadOffsetMismatch { .. }
+        | ApplicationError::JobNotRetryable(_)
+        | ApplicationError::ResultNotAvailable(_)
+        | ApplicationError::Configuration(_) => Code::FailedPrecondition,
+        ApplicationError::QuotaExceeded { resets_at, .. } => {
+            retry_after_secs = Some((resets_at - chrono::Utc::now()).num_seconds().max(0) as u64);
+            Code::ResourceExhausted
+        }
+        ApplicationError::AzureThrottled { retry_after_secs: secs, .. } => {
+            retry_after_secs = secs;
+            Code::ResourceExhausted
+        }
+        ApplicationError::RevisionConflict { .. } => Code::Aborted,
+        ApplicationError::DeadlineExceeded => Code::DeadlineExceeded,
+        // Azure refusing the document is the caller's problem; anything else is ours
+        ApplicationError::Azure(error) if error.status == Some(400) => Code::InvalidArgument,
+        ApplicationError::AzureUnavailable(_) => {
+            error!("Request failed: {}", message);
+            Code::Unavailable
+        }
+        ApplicationError::Azure(error) if error.status.is_some_and(|status| status >= 500) => {
+            error!("Request failed: {}", message);
+            Code::Unavailable
+        }
+        ApplicationError::Azure(_)
+        | ApplicationError::AzureService(_)
+        | ApplicationError::AnalysisFailed(_)
+        | ApplicationError::Internal(_) => {
+            error!("Request failed: {}", message);
+            Code::Internal
+        }
+    };
+    
+    let mut metadata = MetadataMap::new();
+    if let Some(secs) = retry_after_secs {
+        metadata.insert("retry-after", MetadataValue::from(secs));
+    }
+    let details = azure_error
+        .map(|error| prost::Message::encode_to_vec(&azure_error_to_pb(error)))
+        .unwrap_or_default();
+    Status::with_details_and_metadata(code, message, details.into(), metadata)
 }
 
 #[tonic::async_trait]
 impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
+    type DownloadDocumentStream =
+        Pin<Box<dyn Stream<Item = Result<pb::DownloadDocumentResponse, Status>> + Send>>;
+    
     async fn analyze_read(
         &self,
         request: Request<pb::AnalyzeRequest>,
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: AnalyzeRead request received");
         
-        let req = request.into_inner();
-        let domain_request = pb_to_analyze_request(req, ModelType::Read)
-            .map_err(|e| Status::invalid_argument(e))?;
-        
-        let operation = self
-            .service
-            .analyze_document(domain_request)
-            .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
+        let principal = request_principal(&request);
+        let started = self.start_prebuilt(request, ModelType::Read).await;
+        self.audit(&principal, "AnalyzeRead", started.as_ref().map_err(status_code_name)).await;
         
-        let response = operation_to_pb_response(operation, None);
+        let response = operation_to_pb_response(started?, None);
         Ok(Response::new(response))
     }
     
@@ -56,20 +462,11 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: AnalyzeLayout request received");
         
-        let req = request.into_inner();
-        let domain_request = pb_to_analyze_request(req, ModelType::Layout)
-            .map_err(|e| Status::invalid_argument(e))?;
+        let principal = request_principal(&request);
+        let started = self.start_prebuilt(request, ModelType::Layout).await;
+        self.audit(&principal, "AnalyzeLayout", started.as_ref().map_err(status_code_name)).await;
         
-        let operation = self
-            .service
-            .analyze_document(domain_request)
-            .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
-        
-        let response = operation_to_pb_response(operation, None);
+        let response = operation_to_pb_response(started?, None);
         Ok(Response::new(response))
     }
     
@@ -79,20 +476,11 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: AnalyzeInvoice request received");
         
-        let req = request.into_inner();
-        let domain_request = pb_to_analyze_request(req, ModelType::Invoice)
-            .map_err(|e| Status::invalid_argument(e))?;
+        let principal = request_principal(&request);
+        let started = self.start_prebuilt(request, ModelType::Invoice).await;
+        self.audit(&principal, "AnalyzeInvoice", started.as_ref().map_err(status_code_name)).await;
         
-        let operation = self
-            .service
-            .analyze_document(domain_request)
-            .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
-        
-        let response = operation_to_pb_response(operation, None);
+        let response = operation_to_pb_response(started?, None);
         Ok(Response::new(response))
     }
     
@@ -102,20 +490,11 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: AnalyzeReceipt request received");
         
-        let req = request.into_inner();
-        let domain_request = pb_to_analyze_request(req, ModelType::Receipt)
-            .map_err(|e| Status::invalid_argument(e))?;
+        let principal = request_principal(&request);
+        let started = self.start_prebuilt(request, ModelType::Receipt).await;
+        self.audit(&principal, "AnalyzeReceipt", started.as_ref().map_err(status_code_name)).await;
         
-        let operation = self
-            .service
-            .analyze_document(domain_request)
-            .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
-        
-        let response = operation_to_pb_response(operation, None);
+        let response = operation_to_pb_response(started?, None);
         Ok(Response::new(response))
     }
     
@@ -125,20 +504,11 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: AnalyzeIdDocument request received");
         
-        let req = request.into_inner();
-        let domain_request = pb_to_analyze_request(req, ModelType::IdDocument)
-            .map_err(|e| Status::invalid_argument(e))?;
-        
-        let operation = self
-            .service
-            .analyze_document(domain_request)
-            .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
+        let principal = request_principal(&request);
+        let started = self.start_prebuilt(request, ModelType::IdDocument).await;
+        self.audit(&principal, "AnalyzeIdDocument", started.as_ref().map_err(status_code_name)).await;
         
-        let response = operation_to_pb_response(operation, None);
+        let response = operation_to_pb_response(started?, None);
         Ok(Response::new(response))
     }
     
@@ -148,20 +518,11 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: AnalyzeBusinessCard request received");
         
-        let req = request.into_inner();
-        let domain_request = pb_to_analyze_request(req, ModelType::BusinessCard)
-            .map_err(|e| Status::invalid_argument(e))?;
+        let principal = request_principal(&request);
+        let started = self.start_prebuilt(request, ModelType::BusinessCard).await;
+        self.audit(&principal, "AnalyzeBusinessCard", started.as_ref().map_err(status_code_name)).await;
         
-        let operation = self
-            .service
-            .analyze_document(domain_request)
-            .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
-        
-        let response = operation_to_pb_response(operation, None);
+        let response = operation_to_pb_response(started?, None);
         Ok(Response::new(response))
     }
     
@@ -171,20 +532,11 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: AnalyzeW2 request received");
         
-        let req = request.into_inner();
-        let domain_request = pb_to_analyze_request(req, ModelType::W2)
-            .map_err(|e| Status::invalid_argument(e))?;
+        let principal = request_principal(&request);
+        let started = self.start_prebuilt(request, ModelType::W2).await;
+        self.audit(&principal, "AnalyzeW2", started.as_ref().map_err(status_code_name)).await;
         
-        let operation = self
-            .service
-            .analyze_document(domain_request)
-            .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
-        
-        let response = operation_to_pb_response(operation, None);
+        let response = operation_to_pb_response(started?, None);
         Ok(Response::new(response))
     }
     
@@ -194,29 +546,11 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: AnalyzeCustom request received");
         
-        let req = request.into_inner();
-        let model_id = req.model_id.clone();
-        
-        let source = match req.source {
-            Some(pb::analyze_custom_request::Source::DocumentUrl(url)) => {
-                DocumentSource::Url(url)
-            }
-            Some(pb::analyze_custom_request::Source::DocumentBytes(bytes)) => {
-                DocumentSource::Bytes(bytes)
-            }
-            None => return Err(Status::invalid_argument("No document source provided")),
-        };
-        
-        let operation = self
-            .service
-            .analyze_custom(source, &model_id)
-            .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
+        let principal = request_principal(&request);
+        let started = self.start_custom(request).await;
+        self.audit(&principal, "AnalyzeCustom", started.as_ref().map_err(status_code_name)).await;
         
-        let response = operation_to_pb_response(operation, None);
+        let response = operation_to_pb_response(started?, None);
         Ok(Response::new(response))
     }
     
@@ -224,19 +558,39 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
         &self,
         request: Request<pb::GetAnalysisResultRequest>,
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
-        let operation_id = request.into_inner().operation_id;
+        let principal = self.principal(&request).map_err(tenant_status)?;
+        self.service.authorize(&principal, Role::Analyst).map_err(permission_status)?;
+        let tenant = principal.tenant;
+        let deadline = request_deadline(request.metadata());
+        let request = request.into_inner();
+        let operation_id = request.operation_id;
         info!("gRPC: GetAnalysisResult request for operation: {}", operation_id);
         
-        let (operation, result) = self
-            .service
-            .get_analysis_result(&operation_id)
-            .await
-            .map_err(|e| {
-                error!("Failed to get result: {}", e);
-                Status::not_found(e.to_string())
-            })?;
-        
-        let response = operation_to_pb_response(operation, result);
+        let fields = match request.field_mask.filter(|mask| !mask.paths.is_empty()) {
+            Some(mask) => ResultFields::from_names(
+                mask.paths.iter().map(|path| path.strip_prefix("result.").unwrap_or(path)),
+            )
+            .map_err(|e| Status::invalid_argument(e.to_string()))?,
+            None => ResultFields::all(),
+        };
+        let min_confidence = Some(request.min_confidence).filter(|confidence| *confidence != 0.0);
+        if let Some(confidence) = min_confidence {
+            validate_min_confidence(confidence).map_err(|e| Status::invalid_argument(e.to_string()))?;
+        }
+        let (operation, mut result) =
+            with_deadline(deadline, self.service.get_analysis_result_fields(&tenant, &operation_id, &fields))
+                .await
+                .map_err(application_status)?;
+        
+        let filtered = match (min_confidence, result.as_mut()) {
+            (Some(confidence), Some(result)) => Some(result.retain_confident(confidence)),
+            _ => None,
+        };
+        if let (true, Some(result)) = (request.normalize_coordinates, result.as_mut()) {
+            result.normalize_coordinates();
+        }
+        let mut response = operation_to_pb_response(operation, result);
+        response.filtered = filtered.map(confidence_filtered_to_pb);
         Ok(Response::new(response))
     }
     
@@ -246,47 +600,59 @@ impl DocumentIntelligenceServiceTrait for GrpcDocumentIntelligenceService {
     ) -> Result<Response<pb::AnalyzeResponse>, Status> {
         info!("gRPC: UploadAndAnalyze request received");
         
-        let mut stream = request.into_inner();
-        let mut metadata: Option<pb::UploadMetadata> = None;
-        let mut chunks: Vec<u8> = Vec::new();
-        
-        // Collect chunks
-        while let Some(upload_req) = stream.message().await? {
-            match upload_req.data {
-                Some(pb::upload_request::Data::Metadata(meta)) => {
-                    metadata = Some(meta);
-                }
-                Some(pb::upload_request::Data::Chunk(chunk)) => {
-                    chunks.extend_from_slice(&chunk);
-                }
-                None => {}
-            }
-        }
-        
-        let metadata = metadata.ok_or_else(|| {
-            Status::invalid_argument("No metadata provided")
-        })?;
-        
-        let model_type = ModelType::from_string(&metadata.model_type)
-            .map_err(|e| Status::invalid_argument(e.to_string()))?;
-        
-        let domain_request = AnalyzeDocumentRequest {
-            source: DocumentSource::Bytes(chunks),
-            model_type,
-            options: Default::default(),
+        let principal = request_principal(&request);
+        let started = self.start_upload(request).await;
+        let audited = match &started {
+            Ok(UploadOutcome::Started(operation)) => Ok(operation.as_ref()),
+            Ok(UploadOutcome::ChecksumMismatch { .. }) => Err("ChecksumMismatch".to_string()),
+            Err(status) => Err(status_code_name(status)),
         };
+        self.audit(&principal, "UploadAndAnalyze", audited).await;
         
-        let operation = self
+        let response = match started? {
+            UploadOutcome::Started(operation) => operation_to_pb_response(*operation, None),
+            UploadOutcome::ChecksumMismatch { expected, actual } => checksum_mismatch_response(&expected, &actual),
+        };
+        Ok(Response::new(response))
+    }
+    
+    async fn download_document(
+        &self,
+        request: Request<pb::DownloadDocumentRequest>,
+    ) -> Result<Response<Self::DownloadDocumentStream>, Status> {
+        let principal = self.principal(&request).map_err(tenant_status)?;
+        self.service.authorize(&principal, Role::Analyst).map_err(permission_status)?;
+        let tenant = principal.tenant;
+        let document_id = request.into_inner().document_id;
+        info!("gRPC: DownloadDocument request for document: {}", document_id);
+        
+        let document = self
             .service
-            .analyze_document(domain_request)
+            .open_document(&tenant, &document_id, None)
             .await
-            .map_err(|e| {
-                error!("Analysis failed: {}", e);
-                Status::internal(e.to_string())
-            })?;
-        
-        let response = operation_to_pb_response(operation, None);
-        Ok(Response::new(response))
+            .map_err(application_status)?;
+        let metadata = self
+            .service
+            .document_metadata(&tenant, &document_id)
+            .await
+            .map_err(application_status)?;
+        
+        let info = pb::DownloadDocumentResponse {
+            data: Some(pb::download_document_response::Data::Info(pb::DocumentInfo {
+                document_id,
+                filename: metadata.filename,
+                content_type: metadata.content_type,
+                size: document.total_size,
+            })),
+        };
+        let chunks = document
+            .body
+            .map_ok(|bytes| pb::DownloadDocumentResponse {
+                data: Some(pb::download_document_response::Data::Chunk(bytes)),
+            })
+            .map_err(|e| Status::internal(format!("Failed to read document: {}", e)));
+        
+        Ok(Response::new(Box::pin(stream::once(async move { Ok(info) }).chain(chunks))))
     }
 }
 
@@ -1,13 +1,26 @@
 /// Presentation layer - gRPC and REST API servers
 /// 
 /// This layer handles incoming requests and translates them
-/// to application service calls.
+/// to application service calls. The servers need the `server` feature;
+/// the protobuf converters are always available.
 
+pub mod audit;
+pub mod auth;
+#[cfg(feature = "server")]
 pub mod grpc;
+#[cfg(feature = "server")]
 pub mod rest;
 pub mod converters;
+#[cfg(feature = "graphql")]
+pub mod graphql;
+pub mod priority;
+#[cfg(feature = "server")]
+pub mod streaming;
+pub mod tenancy;
 
+#[cfg(feature = "server")]
 pub use grpc::*;
+#[cfg(feature = "server")]
 pub use rest::*;
 pub use converters::*;
 
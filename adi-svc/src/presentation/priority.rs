@@ -0,0 +1,62 @@
+/// Job priority for queued requests
+///
+/// Callers pick a lane with `?priority=`, and `JOB_PRIORITY_KEYS` caps what a
+/// given API key may ask for, so a backfill key lands in the batch lane even
+/// when its client asks for interactive.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+
+use crate::domain::JobPriority;
+use super::audit::presented_key;
+
+/// Highest priority each API key may use
+#[derive(Debug, Clone, Default)]
+pub struct PriorityPolicy {
+    /// Caps by SHA-256 of the API key
+    caps: HashMap<[u8; 32], JobPriority>,
+}
+
+impl PriorityPolicy {
+    pub fn new(keys: impl IntoIterator<Item = (String, JobPriority)>) -> Self {
+        Self {
+            caps: keys
+                .into_iter()
+                .map(|(key, priority)| (Sha256::digest(key.as_bytes()).into(), priority))
+                .collect(),
+        }
+    }
+    
+    /// Priority for a job: what was requested (interactive by default), capped by the key's policy
+    pub fn resolve(
+        &self,
+        requested: Option<JobPriority>,
+        api_key: Option<&str>,
+        authorization: Option<&str>,
+    ) -> JobPriority {
+        let requested = requested.unwrap_or_default();
+        let cap = presented_key(api_key, authorization)
+            .and_then(|key| self.caps.get(&<[u8; 32]>::from(Sha256::digest(key.as_bytes()))));
+        match cap {
+            Some(cap) => requested.min(*cap),
+            None => requested,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_priority() {
+        let policy = PriorityPolicy::new([("backfill".to_string(), JobPriority::Batch)]);
+        assert_eq!(policy.resolve(None, None, None), JobPriority::Interactive);
+        assert_eq!(policy.resolve(Some(JobPriority::Batch), Some("other"), None), JobPriority::Batch);
+        assert_eq!(policy.resolve(None, Some("backfill"), None), JobPriority::Batch);
+        assert_eq!(
+            policy.resolve(Some(JobPriority::Interactive), None, Some("Bearer backfill")),
+            JobPriority::Batch
+        );
+    }
+}
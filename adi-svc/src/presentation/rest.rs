@@ -3,303 +3,2558 @@
 /// This module provides a RESTful HTTP API for document analysis.
 
 use axum::{
-    extract::{Path, State, Multipart},
-    http::StatusCode,
+    async_trait,
+    extract::{DefaultBodyLimit, FromRequest, FromRequestParts, MatchedPath, Path, Query, Request, State, Multipart},
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
-    routing::{get, post},
-    Json, Router,
+    routing::{delete, get, post, put},
+    Extension, Json, Router,
 };
+use bytes::Bytes;
+use chrono::Datelike;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use tower_http::cors::{Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing::{info, error};
 
+use crate::application::deadline::with_deadline;
+use crate::application::request_id::{self, with_request_id};
+use crate::application::errors::ApplicationError;
+use crate::application::ports::UploadState;
+use crate::application::pipelines::PipelineService;
 use crate::application::services::DocumentIntelligenceService;
+#[cfg(feature = "analytics")]
+use crate::application::analytics::{AnalyticsExportReport, AnalyticsExportService};
+use crate::application::exports::ExportService;
+use crate::application::training::{TrainingExportReport, TrainingExportService};
 use crate::domain::*;
+use crate::infrastructure::build_info::BuildInfo;
+use crate::infrastructure::config::{ServerConfig, StorageConfig};
+use crate::infrastructure::logging::LogLevelControl;
+use crate::infrastructure::metrics::metrics;
+use crate::infrastructure::url_signing::SIGNED_DOCUMENT_PATH;
+use super::audit::{principal, API_KEY_HEADER};
+use super::auth::Authenticator;
+use super::priority::PriorityPolicy;
+use super::tenancy::{TenantRejection, TenantResolver, TENANT_HEADER};
+use super::streaming::{inline_disposition, parse_range, range_not_satisfiable, stream_response, RangeRequest};
+
+/// Room for multipart boundaries and part headers on top of the file itself
+const MULTIPART_OVERHEAD_BYTES: usize = 64 * 1024;
+
+/// How long the caller will wait for a response, in seconds
+const REQUEST_TIMEOUT_HEADER: &str = "x-request-timeout";
+
+/// Correlates a call across the caller's logs, ours and Azure's
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
 
 /// REST API state
 #[derive(Clone)]
 pub struct RestApiState {
     pub service: Arc<DocumentIntelligenceService>,
+    pub urls: Arc<PublicUrls>,
+    /// Principals and roles, resolved as `GrpcAuthInterceptor` resolves them
+    pub auth: Arc<Authenticator>,
+    pub tenants: Arc<TenantResolver>,
+    pub priorities: Arc<PriorityPolicy>,
+    pub log_level: Option<LogLevelControl>,
+    pub azure_api_version: Option<Arc<str>>,
+    pub pipelines: Option<Arc<PipelineService>>,
+    pub training_export: Option<Arc<TrainingExportService>>,
+    #[cfg(feature = "analytics")]
+    pub analytics_export: Option<Arc<AnalyticsExportService>>,
+    pub exports: Option<Arc<ExportService>>,
+}
+
+/// Request body limits, applied per route group
+#[derive(Debug, Clone, Copy)]
+pub struct BodyLimits {
+    /// Limit for JSON analyze requests
+    pub json_bytes: usize,
+    /// Limit for multipart uploads, including multipart framing
+    pub upload_bytes: usize,
+}
+
+impl BodyLimits {
+    /// Derive upload limits from `max_upload_size_mb`
+    pub fn from_storage(config: &StorageConfig) -> Self {
+        Self {
+            upload_bytes: config.max_upload_size_mb * 1024 * 1024 + MULTIPART_OVERHEAD_BYTES,
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            json_bytes: 1024 * 1024,
+            upload_bytes: 50 * 1024 * 1024 + MULTIPART_OVERHEAD_BYTES,
+        }
+    }
+}
+
+/// How the API is addressed from outside: mount path and public origin
+#[derive(Debug, Clone, Default)]
+pub struct PublicUrls {
+    /// Prefix all routes are nested under (`""` or e.g. `/adi`)
+    pub base_path: String,
+    /// Public origin for absolute links; relative links are generated when unset
+    pub external_url: Option<String>,
+}
+
+impl PublicUrls {
+    pub fn from_server(config: &ServerConfig) -> Self {
+        Self {
+            base_path: config.base_path.clone(),
+            external_url: config.external_url.clone(),
+        }
+    }
+    
+    /// Public link for an API path such as `/api/v1/uploads/{id}`
+    pub fn url(&self, path: &str) -> String {
+        format!(
+            "{}{}{}",
+            self.external_url.as_deref().unwrap_or(""),
+            self.base_path,
+            path
+        )
+    }
+}
+
+/// Everything that shapes the REST router besides the service itself
+#[derive(Debug, Clone, Default)]
+pub struct RestOptions {
+    pub limits: BodyLimits,
+    pub urls: PublicUrls,
+    /// Key the `/api/v1/admin` endpoints require; they are refused when unset
+    pub admin_api_key: Option<String>,
+    /// How requests are assigned to tenants
+    pub tenants: TenantResolver,
+    /// Highest job priority each API key may use
+    pub priorities: PriorityPolicy,
+    /// Filter changed by `PUT /api/v1/admin/log-level`
+    pub log_level: Option<LogLevelControl>,
+    /// Azure API version reported by `/version`
+    pub azure_api_version: Option<String>,
+    /// Pipelines `/api/v1/pipelines` runs; none are found when unset
+    pub pipelines: Option<Arc<PipelineService>>,
+    /// Writer of `/api/v1/corrections/export`; the export is refused when unset
+    pub training_export: Option<Arc<TrainingExportService>>,
+    /// Writer of `/api/v1/admin/exports/parquet`; the export is refused when unset
+    #[cfg(feature = "analytics")]
+    pub analytics_export: Option<Arc<AnalyticsExportService>>,
+    /// Runner of `/api/v1/exports` jobs; exports are refused when unset
+    pub exports: Option<Arc<ExportService>>,
+}
+
+/// Create REST API router with default body limits
+pub fn create_rest_router(service: Arc<DocumentIntelligenceService>) -> Router {
+    create_rest_router_with_options(service, RestOptions::default())
+}
+
+/// Create REST API router with custom body limits
+pub fn create_rest_router_with_limits(
+    service: Arc<DocumentIntelligenceService>,
+    limits: BodyLimits,
+) -> Router {
+    create_rest_router_with_options(service, RestOptions { limits, ..RestOptions::default() })
+}
+
+/// Create REST API router
+pub fn create_rest_router_with_options(
+    service: Arc<DocumentIntelligenceService>,
+    options: RestOptions,
+) -> Router {
+    let RestOptions {
+        limits,
+        urls,
+        admin_api_key,
+        tenants,
+        priorities,
+        log_level,
+        azure_api_version,
+        pipelines,
+        training_export,
+        #[cfg(feature = "analytics")]
+        analytics_export,
+        exports,
+    } = options;
+    let base_path = urls.base_path.clone();
+    let state = RestApiState {
+        service,
+        urls: Arc::new(urls),
+        auth: Arc::new(Authenticator::new(tenants.clone()).with_admin_key(admin_api_key.as_deref())),
+        tenants: Arc::new(tenants),
+        priorities: Arc::new(priorities),
+        log_level,
+        azure_api_version: azure_api_version.map(Arc::from),
+        pipelines,
+        training_export,
+        #[cfg(feature = "analytics")]
+        analytics_export,
+        exports,
+    };
+    
+    // Analysis endpoints
+    let analyze_routes = Router::new()
+        .route("/api/v1/analyze/read", post(analyze_read))
+        .route("/api/v1/analyze/layout", post(analyze_layout))
+        .route("/api/v1/analyze/invoice", post(analyze_invoice))
+        .route("/api/v1/analyze/receipt", post(analyze_receipt))
+        .route("/api/v1/analyze/id-document", post(analyze_id_document))
+        .route("/api/v1/analyze/business-card", post(analyze_business_card))
+        .route("/api/v1/analyze/w2", post(analyze_w2))
+        .route("/api/v1/analyze/custom/:model_id", post(analyze_custom))
+        .route("/api/v1/analyze/auto", post(analyze_auto))
+        .layer(DefaultBodyLimit::max(limits.json_bytes));
+    
+    // Review/export work queues for external workers
+    let work_routes = WorkQueue::ALL
+        .into_iter()
+        .fold(Router::new(), |router, queue| {
+            router.nest(&format!("/api/v1/{}", queue.as_str()), work_queue_routes(queue))
+        })
+        .layer(DefaultBodyLimit::max(limits.json_bytes));
+    
+    // Upload endpoints
+    let upload_routes = Router::new()
+        .route("/api/v1/upload/read", post(upload_and_analyze_read))
+        .route("/api/v1/upload/layout", post(upload_and_analyze_layout))
+        .route("/api/v1/upload/invoice", post(upload_and_analyze_invoice))
+        .route("/api/v1/upload/auto", post(upload_and_analyze_auto))
+        
+        // Resumable uploads
+        .route("/api/v1/uploads", post(create_upload))
+        .route(
+            "/api/v1/uploads/:upload_id",
+            get(get_upload).patch(patch_upload).delete(delete_upload),
+        )
+        .route("/api/v1/uploads/:upload_id/analyze", post(analyze_upload))
+        
+        // Pipeline runs take a URL or uploaded files
+        .route("/api/v1/pipelines/:name/run", post(run_pipeline))
+        .layer(DefaultBodyLimit::max(limits.upload_bytes));
+    
+    let routes = Router::new()
+        // Health check
+        .route("/health", get(health_check))
+        .route("/version", get(version))
+        .route("/metrics", get(metrics_handler))
+        
+        .merge(analyze_routes)
+        .merge(upload_routes)
+        .merge(work_routes)
+        
+        // Results endpoint
+        .route("/api/v1/results/diff", get(diff_results))
+        .route("/api/v1/results/:operation_id", get(get_result))
+        .route("/api/v1/results/:operation_id/fields", get(get_result_fields))
+        .route("/api/v1/results/:operation_id/pages/:page_number", get(get_result_page))
+        .route("/api/v1/results/:operation_id/tables/:index/records", get(get_table_records))
+        .route("/api/v1/results/:operation_id/raw", get(get_raw_result))
+        .route("/api/v1/results/:operation_id/text", get(get_result_text))
+        .route("/api/v1/results/:operation_id/markdown", get(get_result_markdown))
+        .route("/api/v1/results/:operation_id/hocr", get(get_result_hocr))
+        .route("/api/v1/results/:operation_id/alto", get(get_result_alto))
+        .route("/api/v1/results/:operation_id/invoice.xml", get(get_result_invoice_xml))
+        .route("/api/v1/results/:operation_id/redacted-pdf", get(get_redacted_pdf))
+        
+        // Human review of low-confidence results
+        .route("/api/v1/review-queue", get(get_review_queue))
+        .route("/api/v1/results/:operation_id/corrections", post(correct_result))
+        .route("/api/v1/results/:operation_id/revisions", get(get_result_revisions))
+        .route("/api/v1/corrections/export", post(export_corrections))
+        
+        // Bulk NDJSON exports of results, downloaded through a signed link
+        .route("/api/v1/exports", post(create_export))
+        .route("/api/v1/exports/:export_id", get(get_export))
+        
+        // Retrieval by meaning over embedded results
+        .route("/api/v1/search/semantic", post(semantic_search))
+        
+        // The caller's operations and their status transition history
+        .route("/api/v1/operations", get(list_operations))
+        .route("/api/v1/operations/stats", get(get_operation_stats))
+        .route("/api/v1/operations/:operation_id", delete(delete_operation))
+        .route("/api/v1/operations/:operation_id/events", get(get_operation_events))
+        .route("/api/v1/operations/:operation_id/retry", post(retry_operation))
+        .route("/api/v1/operations/:operation_id/restore", post(restore_operation))
+        .route("/api/v1/operations/jobs/:job_id", get(get_job))
+        .route("/api/v1/operations/jobs/:job_id/retry", post(retry_job))
+        
+        // Configured pipelines and the progress of their runs
+        .route("/api/v1/pipelines", get(list_pipelines))
+        .route("/api/v1/pipelines/:name/runs/:run_id", get(get_pipeline_run))
+        
+        // Duplicate lookup by content hash
+        .route("/api/v1/documents/lookup", get(lookup_document))
+        
+        // Original stored document, directly or through a signed link
+        .route("/api/v1/documents/:document_id", get(download_document))
+        .route("/api/v1/documents/:document_id/url", get(create_document_url))
+        .route(&format!("{}/:document_id", SIGNED_DOCUMENT_PATH), get(signed_document))
+        
+        // Compliance audit log
+        .route("/api/v1/admin/audit", get(list_audit_entries))
+        
+        // Per-tenant quotas
+        .route("/api/v1/admin/quotas", get(list_quotas))
+        .route("/api/v1/admin/quotas/:tenant/:period", put(set_quota).delete(delete_quota))
+        
+        // Runtime log filter
+        .route("/api/v1/admin/log-level", get(get_log_level).put(set_log_level))
+        
+        // Metered usage and estimated cost, for chargeback
+        .route("/api/v1/usage", get(get_usage));
+    
+    // Operations and fields as Parquet, for analytics
+    #[cfg(feature = "analytics")]
+    let routes = routes.route("/api/v1/admin/exports/parquet", post(export_parquet));
+    
+    // Operations and results as a graph, for clients fetching exact slices
+    #[cfg(feature = "graphql")]
+    let routes = {
+        let schema = super::graphql::schema(state.service.clone());
+        routes.route(
+            "/graphql",
+            get(graphiql).post(move |tenant, request| graphql_query(schema.clone(), tenant, request)),
+        )
+    };
+    
+    let routes = routes
+        .route_layer(middleware::from_fn_with_state(state.clone(), audit_requests))
+        .with_state(state);
+    
+    // Mount under the base path when running behind a path-routing ingress
+    let routes = if base_path.is_empty() {
+        routes
+    } else {
+        Router::new().nest(&base_path, routes)
+    };
+    
+    routes
+        .layer(middleware::from_fn(request_deadline))
+        .layer(middleware::map_response(structured_payload_too_large))
+        .layer(
+            CorsLayer::new()
+                .allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any),
+        )
+        .layer(TraceLayer::new_for_http().make_span_with(|request: &Request| {
+            let request_id = request.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok());
+            tracing::info_span!(
+                "request",
+                method = %request.method(),
+                uri = %request.uri(),
+                request_id = request_id.unwrap_or_default(),
+            )
+        }))
+        .layer(middleware::from_fn(assign_request_id))
+}
+
+/// Claim, heartbeat, complete and release routes for one work queue
+fn work_queue_routes(queue: WorkQueue) -> Router<RestApiState> {
+    Router::new()
+        .route(
+            "/claim",
+            post(move |state, tenant, body| claim_work(state, tenant, queue, body)),
+        )
+        .route(
+            "/:operation_id/heartbeat",
+            post(move |state, tenant, path, body| heartbeat_work(state, tenant, queue, path, body)),
+        )
+        .route(
+            "/:operation_id/complete",
+            post(move |state, tenant, path, body| complete_work(state, tenant, queue, path, body)),
+        )
+        .route(
+            "/:operation_id/release",
+            post(move |state, tenant, path, body| release_work(state, tenant, queue, path, body)),
+        )
+}
+
+/// Tenant a request acts for, resolved from its API key or `X-Tenant-Id`
+struct Tenant(TenantId);
+
+#[async_trait]
+impl FromRequestParts<RestApiState> for Tenant {
+    type Rejection = AppError;
+    
+    async fn from_request_parts(parts: &mut Parts, state: &RestApiState) -> Result<Self, AppError> {
+        Ok(Self(resolve_tenant(state, &parts.headers)?))
+    }
+}
+
+fn resolve_tenant(state: &RestApiState, headers: &HeaderMap) -> Result<TenantId, TenantRejection> {
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    state
+        .tenants
+        .resolve(header(API_KEY_HEADER), header(header::AUTHORIZATION.as_str()), header(TENANT_HEADER))
+}
+
+/// Refuse callers whose principal lacks the admin role
+///
+/// The principal comes from the same `Authenticator` the gRPC interceptor
+/// uses, so both front ends grant the admin role alike.
+fn require_admin(state: &RestApiState, headers: &HeaderMap) -> Result<Principal, AppError> {
+    if !state.auth.admin_enabled() {
+        return Err(AppError::Forbidden("Admin API is disabled; set ADMIN_API_KEY".to_string()));
+    }
+    let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+    let principal = state
+        .auth
+        .authenticate(header(API_KEY_HEADER), header(header::AUTHORIZATION.as_str()), header(TENANT_HEADER))?;
+    state.service.authorize(&principal, Role::Admin)?;
+    Ok(principal)
+}
+
+/// Record mutating calls in the audit log, one entry per operation they started
+async fn audit_requests(
+    State(state): State<RestApiState>,
+    matched_path: MatchedPath,
+    request: Request,
+    next: Next,
+) -> Response {
+    let method = request.method().clone();
+    if !state.service.audit_enabled() || method.is_safe() {
+        return next.run(request).await;
+    }
+    let headers = request.headers();
+    let principal = principal(
+        headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok()),
+        headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()),
+    );
+    let action = format!("{} {}", method, matched_path.as_str());
+    
+    let response = next.run(request).await;
+    let status = response.status();
+    let outcome = if status.is_success() { AuditOutcome::Success } else { AuditOutcome::Failure };
+    let entry = AuditEntry::new(&principal, &action, outcome, status.as_u16().to_string());
+    match response.extensions().get::<StartedOperations>() {
+        Some(StartedOperations(operations)) if !operations.is_empty() => {
+            for operation in operations {
+                state.service.record_audit(&entry.clone().with_operation(operation)).await;
+            }
+        }
+        _ => state.service.record_audit(&entry).await,
+    }
+    response
+}
+
+/// Serve each request under the caller's `X-Request-Id` or a new one, echoing it back
+async fn assign_request_id(mut request: Request, next: Next) -> Response {
+    let presented = request.headers().get(REQUEST_ID_HEADER).and_then(|value| value.to_str().ok());
+    let id = request_id::resolve(presented);
+    let value = HeaderValue::from_str(&id).expect("request ids are visible ASCII");
+    request.headers_mut().insert(REQUEST_ID_HEADER, value.clone());
+    
+    let mut response = with_request_id(id, next.run(request)).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    response
+}
+
+/// Give up on a request, Azure calls included, once its `X-Request-Timeout` passes
+async fn request_deadline(request: Request, next: Next) -> Response {
+    let deadline = match request.headers().get(REQUEST_TIMEOUT_HEADER) {
+        Some(value) => match value.to_str().ok().and_then(parse_request_timeout) {
+            Some(timeout) => Some(tokio::time::Instant::now() + timeout),
+            None => {
+                return AppError::Validation(
+                    "X-Request-Timeout must be a positive number of seconds".to_string(),
+                )
+                .into_response();
+            }
+        },
+        None => None,
+    };
+    match with_deadline(deadline, async { Ok(next.run(request).await) }).await {
+        Ok(response) => response,
+        Err(err) => AppError::from(err).into_response(),
+    }
+}
+
+/// Parse an `X-Request-Timeout` value such as `30` or `2.5`
+fn parse_request_timeout(value: &str) -> Option<std::time::Duration> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|secs| *secs > 0.0)
+        .and_then(|secs| std::time::Duration::try_from_secs_f64(secs).ok())
+}
+
+/// Replace axum's plain-text body limit rejections with an `ErrorResponse`
+async fn structured_payload_too_large(response: Response) -> Response {
+    let is_json = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+    
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE && !is_json {
+        AppError::PayloadTooLarge("Request body exceeds the size limit".to_string()).into_response()
+    } else {
+        response
+    }
+}
+
+// DTOs for REST API
+#[derive(Debug, Deserialize, Serialize)]
+struct AnalyzeUrlRequest {
+    document_url: String,
+    #[serde(default)]
+    options: RestAnalyzeOptions,
+}
+
+#[derive(Debug, Default, Deserialize, Serialize)]
+struct RestAnalyzeOptions {
+    locale: Option<String>,
+    pages: Option<Vec<String>>,
+    #[serde(default)]
+    features: Vec<AnalysisFeature>,
+    preprocess: Option<ImagePreprocessing>,
+    string_index_type: Option<StringIndexType>,
+}
+
+impl TryFrom<RestAnalyzeOptions> for AnalyzeOptions {
+    type Error = AppError;
+
+    fn try_from(options: RestAnalyzeOptions) -> Result<Self, AppError> {
+        Ok(Self {
+            locale: options.locale.and_then(|l| Locale::new(l).ok()),
+            pages: options
+                .pages
+                .map(PageRange::new)
+                .transpose()
+                .map_err(|e| AppError::Validation(e.to_string()))?,
+            features: options.features,
+            preprocess: options.preprocess,
+            string_index_type: options.string_index_type,
+        })
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct AnalyzeResponse {
+    operation_id: String,
+    status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result: Option<RestAnalysisResult>,
+    /// How `analyze/auto` chose the model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    routing: Option<RoutingDecision>,
+    /// Human review, for results flagged by the review policy
+    #[serde(skip_serializing_if = "Option::is_none")]
+    review: Option<ReviewState>,
+    /// Why Azure failed the operation
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<AzureError>,
+}
+
+/// `?mode=` and `?priority=` on analyze and upload endpoints
+#[derive(Debug, Default, Deserialize)]
+struct SubmitQuery {
+    #[serde(default)]
+    mode: SubmitMode,
+    priority: Option<JobPriority>,
+}
+
+/// How to submit a request: the query, with priority capped by the caller's key
+#[derive(Debug, Clone, Copy)]
+struct Submission {
+    mode: SubmitMode,
+    priority: JobPriority,
+}
+
+#[async_trait]
+impl FromRequestParts<RestApiState> for Submission {
+    type Rejection = AppError;
+    
+    async fn from_request_parts(parts: &mut Parts, state: &RestApiState) -> Result<Self, AppError> {
+        let Query(query) = Query::<SubmitQuery>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| AppError::Validation(e.body_text()))?;
+        let header = |name| parts.headers.get(name).and_then(|value| value.to_str().ok());
+        let priority = state.priorities.resolve(
+            query.priority,
+            header(API_KEY_HEADER),
+            header(header::AUTHORIZATION.as_str()),
+        );
+        Ok(Self { mode: query.mode, priority })
+    }
+}
+
+impl Submission {
+    /// Refuse `mode=async` on routes whose analyses the job queue cannot carry
+    fn direct_only(self, what: &str) -> Result<(), AppError> {
+        match self.mode {
+            SubmitMode::Direct => Ok(()),
+            SubmitMode::Async => Err(AppError::Validation(format!("{} cannot be queued; omit mode=async", what))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum SubmitMode {
+    /// Submit to the provider before responding
+    #[default]
+    Direct,
+    /// Queue for the worker pool and respond straight away
+    Async,
+}
+
+/// A queued job and, once submitted, the operation it started
+#[derive(Debug, Serialize)]
+struct JobResponse {
+    job_id: String,
+    status: String,
+    priority: JobPriority,
+    model_type: String,
+    attempts: u32,
+    /// When a queued job's next attempt is due
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_attempt_at: Option<chrono::DateTime<chrono::Utc>>,
+    created_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    operation_status: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    status_url: String,
+    /// Where to poll the operation's result, once submitted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    result_url: Option<String>,
+}
+
+impl JobResponse {
+    fn new(urls: &PublicUrls, job: AnalysisJob, operation: Option<AnalysisOperation>) -> Self {
+        Self {
+            status_url: urls.url(&format!("/api/v1/operations/jobs/{}", job.job_id)),
+            result_url: job
+                .operation_id
+                .as_ref()
+                .map(|operation_id| urls.url(&format!("/api/v1/results/{}", operation_id))),
+            job_id: job.job_id,
+            status: job.status.as_str().to_string(),
+            priority: job.priority,
+            model_type: job.model_type.as_str().to_string(),
+            attempts: job.attempts,
+            next_attempt_at: (job.status == JobStatus::Queued).then_some(job.next_attempt_at),
+            created_at: job.created_at,
+            updated_at: job.updated_at,
+            filename: job.metadata.map(|metadata| metadata.filename),
+            document_id: job.document_id,
+            operation_id: job.operation_id,
+            operation_status: operation.map(|op| format!("{:?}", op.status).to_lowercase()),
+            error: job.error,
+        }
+    }
+}
+
+/// Queued jobs for an async upload; a single upload keeps the plain shape
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum QueuedUploadResponse {
+    Single(Box<JobResponse>),
+    Batch {
+        jobs: Vec<JobResponse>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        errors: Vec<FileError>,
+    },
+}
+
+/// One run per uploaded file; a single upload keeps the plain shape
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum PipelineRunResponse {
+    Single(PipelineRun),
+    Batch { runs: Vec<PipelineRun> },
+}
+
+/// One response per uploaded file; a single upload keeps the plain shape
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum UploadResponse {
+    Single(Box<AnalyzeResponse>),
+    Batch {
+        operations: Vec<AnalyzeResponse>,
+        #[serde(skip_serializing_if = "Vec::is_empty")]
+        errors: Vec<FileError>,
+    },
+}
+
+/// Sections are omitted when excluded with `?include=`
+#[derive(Debug, Serialize)]
+struct RestAnalysisResult {
+    model_id: String,
+    /// How span offsets in the full result count characters
+    string_index_type: StringIndexType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pages: Option<Vec<RestPage>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tables: Option<Vec<RestTable>>,
+    /// Entries dropped by `?min_confidence=`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filtered: Option<ConfidenceFiltered>,
+}
+
+#[derive(Debug, Serialize)]
+struct RestPage {
+    page_number: i32,
+    width: f32,
+    height: f32,
+    word_count: usize,
+    line_count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct RestTable {
+    row_count: i32,
+    column_count: i32,
+    cell_count: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct CreateUploadRequest {
+    filename: String,
+    content_type: Option<String>,
+    /// Total size in bytes, if known up front
+    size: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnalyzeUploadQuery {
+    model: String,
+}
+
+#[derive(Debug, Serialize)]
+struct UploadResponseBody {
+    upload_id: String,
+    filename: String,
+    content_type: String,
+    offset: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<u64>,
+}
+
+impl From<UploadState> for UploadResponseBody {
+    fn from(state: UploadState) -> Self {
+        Self {
+            upload_id: state.upload_id,
+            filename: state.filename,
+            content_type: state.content_type,
+            offset: state.offset,
+            size: state.total_size,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DocumentLookupQuery {
+    sha256: String,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct DocumentLookupResponse {
+    sha256: String,
+    operations: Vec<OperationSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OperationListParams {
+    status: Option<OperationStatus>,
+    /// Only operations created before this instant; pass the previous page's `next_before`
+    before: Option<chrono::DateTime<chrono::Utc>>,
+    /// With `before`, also operations created at that instant sorting before this ID;
+    /// pass the previous page's `next_before_id`
+    before_id: Option<String>,
+    limit: Option<u32>,
+    /// Also list deleted operations that can still be restored
+    #[serde(default)]
+    include_deleted: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct OperationListResponse {
+    tenant_id: TenantId,
+    operations: Vec<OperationSummary>,
+    /// Cursor for the next page, absent on the last one
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Operation the next page starts after, so pages never skip operations created at the same instant
+    #[serde(skip_serializing_if = "Option::is_none")]
+    next_before_id: Option<String>,
+}
+
+impl OperationListResponse {
+    /// A page of `limit` operations, with a cursor when it is full and may have more behind it
+    fn page(tenant_id: TenantId, operations: Vec<AnalysisOperation>, limit: u32) -> Self {
+        let last = operations.last().filter(|_| operations.len() == limit as usize);
+        let (next_before, next_before_id) = match last {
+            Some(last) => (Some(last.created_at), Some(last.operation_id.clone())),
+            None => (None, None),
+        };
+        Self {
+            tenant_id,
+            operations: operations.into_iter().map(OperationSummary::from).collect(),
+            next_before,
+            next_before_id,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OperationStatsParams {
+    #[serde(default)]
+    group_by: OperationStatsGroup,
+    /// Only operations created at or after this instant
+    from: Option<chrono::DateTime<chrono::Utc>>,
+    /// Only operations created before this instant
+    to: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+struct OperationStatsResponse {
+    tenant_id: TenantId,
+    group_by: OperationStatsGroup,
+    groups: Vec<OperationStats>,
+}
+
+#[derive(Debug, Serialize)]
+struct OperationSummary {
+    operation_id: String,
+    status: String,
+    model_type: String,
+    created_at: chrono::DateTime<chrono::Utc>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    document_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    review: Option<ReviewState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    deleted_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+impl From<AnalysisOperation> for OperationSummary {
+    fn from(op: AnalysisOperation) -> Self {
+        Self {
+            operation_id: op.operation_id,
+            status: format!("{:?}", op.status).to_lowercase(),
+            model_type: op.model_type.as_str().to_string(),
+            created_at: op.created_at,
+            filename: op.filename,
+            content_type: op.content_type,
+            document_id: op.document_id,
+            review: op.review,
+            deleted_at: op.deleted_at,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewQueueParams {
+    /// Only operations created before this instant; pass the previous page's `next_before`
+    before: Option<chrono::DateTime<chrono::Utc>>,
+    /// Pass the previous page's `next_before_id`
+    before_id: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CorrectionsRequest {
+    corrections: Vec<FieldCorrection>,
+}
+
+/// A result revision without the result itself, which `/api/v1/results/{id}` serves
+#[derive(Debug, Serialize)]
+struct RevisionSummary {
+    operation_id: String,
+    revision: u32,
+    corrections: Vec<FieldCorrection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reviewer: Option<String>,
+    created_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<ResultRevision> for RevisionSummary {
+    fn from(revision: ResultRevision) -> Self {
+        Self {
+            operation_id: revision.operation_id,
+            revision: revision.revision,
+            corrections: revision.corrections,
+            reviewer: revision.reviewer,
+            created_at: revision.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct RevisionsResponse {
+    operation_id: String,
+    revisions: Vec<RevisionSummary>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SemanticSearchRequest {
+    query: String,
+    top_k: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct SemanticSearchResponse {
+    query: String,
+    results: Vec<ChunkMatch>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultQuery {
+    /// Comma-separated result sections to return, e.g. `content,tables`
+    include: Option<String>,
+    /// Drop words, key-value pairs and documents below this confidence
+    min_confidence: Option<f32>,
+    /// Output template to map a succeeded result with, instead of the usual response
+    template: Option<String>,
+}
+
+/// A page with its lines grouped into paragraphs in reading order
+#[derive(Debug, Serialize)]
+struct RestResultPage {
+    #[serde(flatten)]
+    page: DocumentPage,
+    paragraphs: Vec<Paragraph>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultPageQuery {
+    #[serde(default)]
+    coordinates: PageCoordinates,
+}
+
+/// How a page's polygons are measured in a response
+#[derive(Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum PageCoordinates {
+    /// In the page's own unit, as Azure reported them
+    #[default]
+    Page,
+    /// As fractions of the page, on a 1 x 1 page
+    Normalized,
+}
+
+#[derive(Debug, Deserialize)]
+struct InvoiceXmlQuery {
+    /// `ubl` (default) or `zugferd`, also accepted as `factur-x`
+    #[serde(default)]
+    format: EInvoiceFormat,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultDiffQuery {
+    left: String,
+    right: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ResultDiffResponse {
+    left: String,
+    right: String,
+    #[serde(flatten)]
+    diff: ResultDiff,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultFieldsQuery {
+    key: Option<String>,
+    min_confidence: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResultFieldsResponse {
+    operation_id: String,
+    fields: Vec<FieldMatch>,
+    /// Canonical forms of the fields that have one, keyed by field name
+    normalized_fields: BTreeMap<String, NormalizedValue>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AuditLogQuery {
+    principal: Option<String>,
+    action: Option<String>,
+    operation_id: Option<String>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    until: Option<chrono::DateTime<chrono::Utc>>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct AuditLogResponse {
+    entries: Vec<AuditEntry>,
+}
+
+/// Days of creation to export, both included; `to` defaults to `from`
+#[cfg(feature = "analytics")]
+#[derive(Debug, Deserialize)]
+struct ParquetExportRequest {
+    from: chrono::NaiveDate,
+    to: Option<chrono::NaiveDate>,
+}
+
+#[derive(Debug, Deserialize)]
+struct QuotaListParams {
+    tenant: Option<String>,
+}
+
+/// Limits to set; an omitted limit is unlimited
+#[derive(Debug, Deserialize)]
+struct QuotaLimits {
+    max_operations: Option<u64>,
+    max_pages: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct QuotaListResponse {
+    quotas: Vec<QuotaView>,
+}
+
+#[derive(Debug, Serialize)]
+struct QuotaView {
+    #[serde(flatten)]
+    quota: Quota,
+    used_operations: u64,
+    used_pages: u64,
+    resets_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl From<(Quota, QuotaUsage)> for QuotaView {
+    fn from((quota, usage): (Quota, QuotaUsage)) -> Self {
+        Self {
+            quota,
+            used_operations: usage.operations,
+            used_pages: usage.pages,
+            resets_at: usage.resets_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OperationEventsResponse {
+    operation_id: String,
+    events: Vec<OperationEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageParams {
+    tenant: Option<String>,
+    /// First day, inclusive; defaults to the start of the current month
+    from: Option<chrono::NaiveDate>,
+    /// Last day, inclusive; defaults to today (UTC)
+    to: Option<chrono::NaiveDate>,
+}
+
+#[derive(Debug, Serialize)]
+struct UsageResponse {
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tenant_id: Option<TenantId>,
+    currency: &'static str,
+    usage: Vec<UsageEntry>,
+    total_operations: u64,
+    total_pages: u64,
+    total_estimated_cost: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct UsageEntry {
+    day: chrono::NaiveDate,
+    tenant_id: TenantId,
+    model: String,
+    operations: u64,
+    pages: u64,
+    estimated_cost: f64,
+}
+
+impl From<UsageRecord> for UsageEntry {
+    fn from(record: UsageRecord) -> Self {
+        Self {
+            estimated_cost: round_cents(record.estimated_cost_usd()),
+            day: record.day,
+            tenant_id: record.tenant_id,
+            model: record.model.as_str().to_string(),
+            operations: record.operations,
+            pages: record.pages,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ResultTextQuery {
+    pages: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RedactionQuery {
+    /// Comma-separated field names whose values are redacted
+    fields: Option<String>,
+    /// Comma-separated PII categories, e.g. `email,ssn`
+    pii: Option<String>,
+    /// Semicolon-separated `page:left,top,right,bottom` regions in the page's unit
+    regions: Option<String>,
+}
+
+impl RedactionQuery {
+    fn rules(&self) -> Result<RedactionRules, AppError> {
+        let list = |value: &Option<String>, separator: char| -> Vec<String> {
+            value
+                .iter()
+                .flat_map(|value| value.split(separator))
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_string)
+                .collect()
+        };
+        let pii = list(&self.pii, ',')
+            .iter()
+            .map(|category| {
+                PiiCategory::parse(category)
+                    .ok_or_else(|| AppError::Validation(format!("Unknown PII category: {}", category)))
+            })
+            .collect::<Result<_, _>>()?;
+        let regions = list(&self.regions, ';')
+            .iter()
+            .map(|region| region.parse().map_err(|e: DomainError| AppError::Validation(e.to_string())))
+            .collect::<Result<_, _>>()?;
+        Ok(RedactionRules {
+            fields: list(&self.fields, ','),
+            pii,
+            regions,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct DocumentUrlQuery {
+    /// Link lifetime in seconds
+    expires_in: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+struct DocumentUrlResponse {
+    url: String,
+    expires_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// An export job, with a link to its file once it has succeeded
+#[derive(Debug, Serialize)]
+struct ExportJobResponse {
+    #[serde(flatten)]
+    job: ExportJob,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    download_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SignedDocumentQuery {
+    expires: i64,
+    signature: String,
+    /// Present on links to a non-default tenant's documents
+    tenant: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ClaimWorkRequest {
+    worker_id: String,
+    lease_secs: Option<u64>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HeartbeatWorkRequest {
+    worker_id: String,
+    lease_secs: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkerRequest {
+    worker_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct ClaimWorkResponse {
+    items: Vec<WorkLease>,
+}
+
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    error: String,
+    /// Machine-readable code for errors clients are expected to handle
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+    /// When a quota that refused the request resets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resets_at: Option<chrono::DateTime<chrono::Utc>>,
+    /// Code, message and request id from Azure when it caused the error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    azure_error: Option<AzureError>,
+    /// The request's `X-Request-Id`, to quote in support tickets
+    #[serde(skip_serializing_if = "Option::is_none")]
+    request_id: Option<String>,
+}
+
+/// A file of a batch upload that was not submitted, with the error it would have responded with alone
+#[derive(Debug, Serialize)]
+struct FileError {
+    filename: String,
+    status: u16,
+    #[serde(flatten)]
+    error: ErrorResponse,
+}
+
+impl FileError {
+    fn new(filename: String, error: AppError) -> Self {
+        let (status, error, _) = error.parts();
+        Self { filename, status: status.as_u16(), error }
+    }
+}
+
+// Handler implementations
+/// Liveness and dependency health; `503` when any dependency fails its check
+async fn health_check(State(state): State<RestApiState>) -> impl IntoResponse {
+    let dependencies = state.service.check_health().await;
+    let healthy = dependencies.iter().all(|dependency| dependency.healthy);
+    let status = if healthy { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (
+        status,
+        Json(serde_json::json!({
+            "status": if healthy { "healthy" } else { "unhealthy" },
+            "service": "adi-svc",
+            "version": env!("CARGO_PKG_VERSION"),
+            "dependencies": dependencies,
+        })),
+    )
+}
+
+/// What is deployed: build identity and the Azure API version in use
+async fn version(State(state): State<RestApiState>) -> impl IntoResponse {
+    Json(serde_json::json!({
+        "service": "adi-svc",
+        "build": BuildInfo::current(),
+        "azure_api_version": state.azure_api_version.as_deref(),
+    }))
+}
+
+/// Run a GraphQL query for the caller's tenant
+#[cfg(feature = "graphql")]
+async fn graphql_query(
+    schema: super::graphql::GraphqlSchema,
+    Tenant(tenant): Tenant,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    Json(schema.execute(request.data(tenant)).await)
+}
+
+/// GraphiQL explorer for `/graphql`
+#[cfg(feature = "graphql")]
+async fn graphiql(State(state): State<RestApiState>) -> impl IntoResponse {
+    axum::response::Html(
+        async_graphql::http::GraphiQLSource::build()
+            .endpoint(&state.urls.url("/graphql"))
+            .finish(),
+    )
+}
+
+async fn metrics_handler() -> impl IntoResponse {
+    (
+        [(axum::http::header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics().render(),
+    )
+}
+
+async fn analyze_read(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze read request for: {}", request.document_url);
+    
+    let domain_request = create_domain_request(request, ModelType::Read, tenant)?;
+    submit(&state, domain_request, submission).await
+}
+
+async fn analyze_layout(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze layout request for: {}", request.document_url);
+    
+    let domain_request = create_domain_request(request, ModelType::Layout, tenant)?;
+    submit(&state, domain_request, submission).await
+}
+
+async fn analyze_invoice(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze invoice request for: {}", request.document_url);
+    
+    let domain_request = create_domain_request(request, ModelType::Invoice, tenant)?;
+    submit(&state, domain_request, submission).await
+}
+
+async fn analyze_receipt(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze receipt request for: {}", request.document_url);
+    
+    let domain_request = create_domain_request(request, ModelType::Receipt, tenant)?;
+    submit(&state, domain_request, submission).await
+}
+
+async fn analyze_id_document(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze ID document request for: {}", request.document_url);
+    
+    let domain_request = create_domain_request(request, ModelType::IdDocument, tenant)?;
+    submit(&state, domain_request, submission).await
+}
+
+async fn analyze_business_card(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze business card request for: {}", request.document_url);
+    
+    let domain_request = create_domain_request(request, ModelType::BusinessCard, tenant)?;
+    submit(&state, domain_request, submission).await
+}
+
+async fn analyze_w2(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze W-2 request for: {}", request.document_url);
+    
+    let domain_request = create_domain_request(request, ModelType::W2, tenant)?;
+    submit(&state, domain_request, submission).await
+}
+
+async fn analyze_custom(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(model_id): Path<String>,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze custom request with model: {}", model_id);
+    
+    submission.direct_only("Custom model analysis")?;
+    let source = DocumentSource::Url(request.document_url);
+    let operation = state.service.analyze_custom(&tenant, source, &model_id).await?;
+    
+    Ok(started_response(operation))
+}
+
+/// Classify the document, then analyze it with the model its type routes to
+async fn analyze_auto(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    Json(request): Json<AnalyzeUrlRequest>,
+) -> Result<Response, AppError> {
+    info!("REST: Analyze auto request for: {}", request.document_url);
+    
+    submission.direct_only("Auto-routed analysis")?;
+    // The model type is replaced by the routing decision
+    let domain_request = create_domain_request(request, ModelType::Layout, tenant)?;
+    Ok(started_response(state.service.analyze_auto(domain_request).await?))
+}
+
+async fn upload_and_analyze_read(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    info!("REST: Upload and analyze read request");
+    
+    upload_and_analyze(&state, tenant, &mut multipart, ModelType::Read, submission).await
+}
+
+async fn upload_and_analyze_layout(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    info!("REST: Upload and analyze layout request");
+    
+    upload_and_analyze(&state, tenant, &mut multipart, ModelType::Layout, submission).await
+}
+
+async fn upload_and_analyze_invoice(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    info!("REST: Upload and analyze invoice request");
+    
+    upload_and_analyze(&state, tenant, &mut multipart, ModelType::Invoice, submission).await
+}
+
+/// Classify each uploaded file and analyze it with the model its type routes to
+async fn upload_and_analyze_auto(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    submission: Submission,
+    mut multipart: Multipart,
+) -> Result<Response, AppError> {
+    info!("REST: Upload and analyze auto request");
+    
+    submission.direct_only("Auto-routed analysis")?;
+    let upload = extract_files_from_multipart(&mut multipart).await?;
+    let options = AnalyzeOptions::try_from(upload.options)?;
+    let single = upload.files.len() == 1;
+    
+    let mut started = Vec::with_capacity(upload.files.len());
+    let mut errors = Vec::new();
+    for (bytes, metadata) in upload.files {
+        let filename = metadata.filename.clone();
+        let request = AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(bytes),
+            model_type: ModelType::Layout,
+            options: options.clone(),
+            metadata: Some(metadata),
+            tenant_id: tenant.clone(),
+        };
+        match state.service.analyze_auto(request).await {
+            Ok(operation) => started.push(operation),
+            Err(e) if single => return Err(e.into()),
+            Err(e) => errors.push(FileError::new(filename, e.into())),
+        }
+    }
+    
+    Ok(upload_response(started, errors, single))
+}
+
+async fn get_result(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+    Query(query): Query<ResultQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    info!("REST: Get result for operation: {}", operation_id);
+    
+    if let Some(template) = &query.template {
+        let rendered = state.service.render_result(&tenant, &operation_id, template).await?;
+        let content_type = HeaderValue::from_str(&rendered.content_type)
+            .map_err(|e| AppError::Internal(format!("Invalid template content type: {}", e)))?;
+        return Ok(([(header::CONTENT_TYPE, content_type)], rendered.body).into_response());
+    }
+    
+    let fields = match query.include {
+        Some(include) => ResultFields::from_names(include.split(','))
+            .map_err(|e| AppError::Validation(e.to_string()))?,
+        None => ResultFields::all(),
+    };
+    if let Some(confidence) = query.min_confidence {
+        validate_min_confidence(confidence).map_err(|e| AppError::Validation(e.to_string()))?;
+    }
+    let (operation, mut result) = state.service.get_analysis_result_fields(&tenant, &operation_id, &fields).await?;
+    
+    let filtered = match (query.min_confidence, result.as_mut()) {
+        (Some(confidence), Some(result)) => Some(result.retain_confident(confidence)),
+        _ => None,
+    };
+    let mut response = operation_to_projected_response(operation, result, &fields);
+    if let Some(result) = response.result.as_mut() {
+        result.filtered = filtered;
+    }
+    info!("Returning result - has data: {}", response.result.is_some());
+    
+    let body = serde_json::to_vec(&response)
+        .map_err(|e| AppError::Internal(format!("Failed to serialize result: {}", e)))?;
+    let etag = result_etag(&response.status, &body);
+    let etag_header = HeaderValue::from_str(&etag)
+        .map_err(|e| AppError::Internal(format!("Invalid ETag: {}", e)))?;
+    
+    if if_none_match(&headers, &etag) {
+        return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag_header)]).into_response());
+    }
+    
+    Ok((
+        [
+            (header::ETAG, etag_header),
+            (header::CACHE_CONTROL, HeaderValue::from_static("no-cache")),
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/json")),
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Added, removed and changed fields, tables and content between two
+/// succeeded operations, e.g. two versions of a contract
+async fn diff_results(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Query(query): Query<ResultDiffQuery>,
+) -> Result<Json<ResultDiffResponse>, AppError> {
+    info!("REST: Diff results {} and {}", query.left, query.right);
+    
+    let diff = state.service.diff_results(&tenant, &query.left, &query.right).await?;
+    Ok(Json(ResultDiffResponse {
+        left: query.left,
+        right: query.right,
+        diff,
+    }))
+}
+
+/// Key-value pairs and document fields of a succeeded result, filtered by
+/// `?key=` and `?min_confidence=`
+async fn get_result_fields(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+    Query(query): Query<ResultFieldsQuery>,
+) -> Result<Json<ResultFieldsResponse>, AppError> {
+    info!("REST: Query fields of operation: {}", operation_id);
+    
+    let query = FieldQuery::new(query.key, query.min_confidence)
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let fields = state.service.query_result_fields(&tenant, &operation_id, &query).await?;
+    let normalized_fields = normalized_fields(&fields);
+    Ok(Json(ResultFieldsResponse { operation_id, fields, normalized_fields }))
+}
+
+/// The caller's operations waiting for human review, newest first
+async fn get_review_queue(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Query(params): Query<ReviewQueueParams>,
+) -> Result<Json<OperationListResponse>, AppError> {
+    let limit = params.limit.unwrap_or(OperationListQuery::DEFAULT_LIMIT);
+    let operations = state.service.review_queue(&tenant, params.before, params.before_id, limit).await?;
+    info!("REST: {} operations waiting for review for tenant {}", operations.len(), tenant);
+    
+    Ok(Json(OperationListResponse::page(tenant, operations, limit)))
+}
+
+/// Store a reviewer's field values as the result's next revision
+async fn correct_result(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+    headers: HeaderMap,
+    Json(request): Json<CorrectionsRequest>,
+) -> Result<(StatusCode, Json<RevisionSummary>), AppError> {
+    info!("REST: Correct {} fields of operation: {}", request.corrections.len(), operation_id);
+    
+    let reviewer = principal(
+        headers.get(API_KEY_HEADER).and_then(|value| value.to_str().ok()),
+        headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok()),
+    );
+    let revision = state
+        .service
+        .correct_result(&tenant, &operation_id, request.corrections, Some(reviewer))
+        .await?;
+    Ok((StatusCode::CREATED, Json(revision.into())))
+}
+
+/// Every revision of a result, oldest first; revision 0 is the extraction as analyzed
+async fn get_result_revisions(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<Json<RevisionsResponse>, AppError> {
+    info!("REST: Revisions of operation: {}", operation_id);
+    
+    let revisions = state.service.result_revisions(&tenant, &operation_id).await?;
+    Ok(Json(RevisionsResponse {
+        operation_id,
+        revisions: revisions.into_iter().map(RevisionSummary::from).collect(),
+    }))
+}
+
+/// Write the caller's reviewed results to the training container as a labeling dataset
+async fn export_corrections(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+) -> Result<Json<TrainingExportReport>, AppError> {
+    info!("REST: Export corrections of tenant {}", tenant);
+    
+    let export = state.training_export.as_ref().ok_or_else(|| {
+        ApplicationError::Configuration("Training export is not configured".to_string())
+    })?;
+    Ok(Json(export.export(&tenant).await?))
+}
+
+/// The caller's document chunks closest in meaning to a query, closest first
+async fn semantic_search(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Json(request): Json<SemanticSearchRequest>,
+) -> Result<Json<SemanticSearchResponse>, AppError> {
+    let query = SemanticQuery::new(&request.query, request.top_k).map_err(|e| AppError::Validation(e.to_string()))?;
+    let results = state.service.semantic_search(&tenant, &query).await?;
+    info!("REST: Semantic search for tenant {} matched {} chunks", tenant, results.len());
+    
+    Ok(Json(SemanticSearchResponse { query: query.query, results }))
+}
+
+/// One page of a succeeded result with its words, lines and selection marks
+async fn get_result_page(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path((operation_id, page_number)): Path<(String, i32)>,
+    Query(query): Query<ResultPageQuery>,
+) -> Result<Json<RestResultPage>, AppError> {
+    info!("REST: Get page {} of operation: {}", page_number, operation_id);
+    
+    if page_number < 1 {
+        return Err(AppError::Validation(format!("Invalid page number: {}", page_number)));
+    }
+    let mut page = state.service.result_page(&tenant, &operation_id, page_number).await?;
+    if query.coordinates == PageCoordinates::Normalized {
+        page.normalize_coordinates();
+    }
+    Ok(Json(RestResultPage { paragraphs: page_paragraphs(&page), page }))
+}
+
+/// One table of a succeeded result as records keyed by its column headers
+async fn get_table_records(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path((operation_id, index)): Path<(String, usize)>,
+) -> Result<Json<TableRecords>, AppError> {
+    info!("REST: Records of table {} of operation: {}", index, operation_id);
+    
+    Ok(Json(state.service.result_table(&tenant, &operation_id, index).await?.records()))
+}
+
+/// Azure's untouched response for a succeeded result, when raw responses are stored
+async fn get_raw_result(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<Json<serde_json::Value>, AppError> {
+    info!("REST: Raw result for operation: {}", operation_id);
+    
+    Ok(Json(state.service.raw_result(&tenant, &operation_id).await?))
+}
+
+/// The caller's operations, newest first, optionally filtered by status
+async fn list_operations(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Query(params): Query<OperationListParams>,
+) -> Result<Json<OperationListResponse>, AppError> {
+    let query = OperationListQuery {
+        status: params.status,
+        before: params.before,
+        before_id: params.before_id,
+        review: None,
+        include_deleted: params.include_deleted,
+        limit: params.limit.unwrap_or(OperationListQuery::DEFAULT_LIMIT),
+    };
+    let operations = state.service.list_operations(&tenant, &query).await?;
+    info!("REST: Listed {} operations for tenant {}", operations.len(), tenant);
+    
+    Ok(Json(OperationListResponse::page(tenant, operations, query.limit)))
+}
+
+/// Counts, median completion time and failure ratio of the tenant's operations
+/// by day, model or status, computed by the tracker rather than by paging the list
+async fn get_operation_stats(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Query(params): Query<OperationStatsParams>,
+) -> Result<Json<OperationStatsResponse>, AppError> {
+    let query = OperationStatsQuery {
+        group_by: params.group_by,
+        from: params.from,
+        to: params.to,
+    };
+    let groups = state.service.operation_stats(&tenant, &query).await?;
+    info!("REST: Operation stats for tenant {} in {} groups", tenant, groups.len());
+    
+    Ok(Json(OperationStatsResponse {
+        tenant_id: tenant,
+        group_by: query.group_by,
+        groups,
+    }))
+}
+
+/// Every recorded status transition of an operation, oldest first
+async fn get_operation_events(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<Json<OperationEventsResponse>, AppError> {
+    info!("REST: Events for operation: {}", operation_id);
+    
+    let events = state.service.operation_events(&tenant, &operation_id).await?;
+    Ok(Json(OperationEventsResponse { operation_id, events }))
+}
+
+/// Status of a job queued with `mode=async`, and of the operation it started
+async fn get_job(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(job_id): Path<String>,
+) -> Result<Json<JobResponse>, AppError> {
+    info!("REST: Get job: {}", job_id);
+    
+    let (job, operation) = state.service.job(&tenant, &job_id).await?;
+    Ok(Json(JobResponse::new(&state.urls, job, operation)))
+}
+
+/// Queue a dead-lettered job again with a fresh set of attempts
+async fn retry_job(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(job_id): Path<String>,
+) -> Result<Response, AppError> {
+    info!("REST: Retry job: {}", job_id);
+    
+    let job = state.service.retry_job(&tenant, &job_id).await?;
+    queued_response(&state, job)
+}
+
+/// Queue the job behind a failed operation again
+async fn retry_operation(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<Response, AppError> {
+    info!("REST: Retry operation: {}", operation_id);
+    
+    let job = state.service.retry_operation(&tenant, &operation_id).await?;
+    queued_response(&state, job)
+}
+
+/// Soft-delete an operation; its result is hidden until it is restored or purged
+async fn delete_operation(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    info!("REST: Delete operation: {}", operation_id);
+    
+    state.service.delete_operation(&tenant, &operation_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Undo the deletion of an operation that has not been purged yet
+async fn restore_operation(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<Json<OperationSummary>, AppError> {
+    info!("REST: Restore operation: {}", operation_id);
+    
+    let operation = state.service.restore_operation(&tenant, &operation_id).await?;
+    Ok(Json(operation.into()))
+}
+
+/// Audit log entries, newest first, filtered by principal, action, operation
+/// and time window
+async fn list_audit_entries(
+    State(state): State<RestApiState>,
+    headers: HeaderMap,
+    Query(query): Query<AuditLogQuery>,
+) -> Result<Json<AuditLogResponse>, AppError> {
+    require_admin(&state, &headers)?;
+    
+    let query = AuditQuery {
+        principal: query.principal,
+        action: query.action,
+        operation_id: query.operation_id,
+        since: query.since,
+        until: query.until,
+        limit: query.limit.unwrap_or(AuditQuery::DEFAULT_LIMIT),
+    };
+    let entries = state.service.audit_entries(&query).await?;
+    Ok(Json(AuditLogResponse { entries }))
+}
+
+/// Quotas with usage in their current window, optionally for one tenant
+async fn list_quotas(
+    State(state): State<RestApiState>,
+    headers: HeaderMap,
+    Query(params): Query<QuotaListParams>,
+) -> Result<Json<QuotaListResponse>, AppError> {
+    require_admin(&state, &headers)?;
+    
+    let tenant = params
+        .tenant
+        .map(TenantId::new)
+        .transpose()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let quotas = state.service.quotas(tenant.as_ref()).await?;
+    Ok(Json(QuotaListResponse {
+        quotas: quotas.into_iter().map(QuotaView::from).collect(),
+    }))
+}
+
+/// Create or replace a tenant's daily or monthly quota
+async fn set_quota(
+    State(state): State<RestApiState>,
+    headers: HeaderMap,
+    Path((tenant, period)): Path<(String, String)>,
+    Json(limits): Json<QuotaLimits>,
+) -> Result<Json<Quota>, AppError> {
+    require_admin(&state, &headers)?;
+    
+    let (tenant, period) = quota_key(tenant, &period)?;
+    let quota = Quota::new(tenant, period, limits.max_operations, limits.max_pages)
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    state.service.set_quota(&quota).await?;
+    Ok(Json(quota))
 }
 
-/// Create REST API router
-pub fn create_rest_router(service: Arc<DocumentIntelligenceService>) -> Router {
-    let state = RestApiState { service };
+async fn delete_quota(
+    State(state): State<RestApiState>,
+    headers: HeaderMap,
+    Path((tenant, period)): Path<(String, String)>,
+) -> Result<StatusCode, AppError> {
+    require_admin(&state, &headers)?;
     
-    Router::new()
-        // Health check
-        .route("/health", get(health_check))
-        
-        // Analysis endpoints
-        .route("/api/v1/analyze/read", post(analyze_read))
-        .route("/api/v1/analyze/layout", post(analyze_layout))
-        .route("/api/v1/analyze/invoice", post(analyze_invoice))
-        .route("/api/v1/analyze/receipt", post(analyze_receipt))
-        .route("/api/v1/analyze/id-document", post(analyze_id_document))
-        .route("/api/v1/analyze/business-card", post(analyze_business_card))
-        .route("/api/v1/analyze/w2", post(analyze_w2))
-        .route("/api/v1/analyze/custom/:model_id", post(analyze_custom))
-        
-        // Upload endpoints
-        .route("/api/v1/upload/read", post(upload_and_analyze_read))
-        .route("/api/v1/upload/layout", post(upload_and_analyze_layout))
-        .route("/api/v1/upload/invoice", post(upload_and_analyze_invoice))
-        
-        // Results endpoint
-        .route("/api/v1/results/:operation_id", get(get_result))
-        
-        .with_state(state)
-        .layer(
-            CorsLayer::new()
-                .allow_origin(Any)
-                .allow_methods(Any)
-                .allow_headers(Any),
-        )
-        .layer(TraceLayer::new_for_http())
+    let (tenant, period) = quota_key(tenant, &period)?;
+    state.service.delete_quota(&tenant, period).await?;
+    Ok(StatusCode::NO_CONTENT)
 }
 
-// DTOs for REST API
+/// A log filter in `RUST_LOG` syntax, e.g. `info,adi_svc=debug`
 #[derive(Debug, Deserialize, Serialize)]
-struct AnalyzeUrlRequest {
-    document_url: String,
-    #[serde(default)]
-    options: RestAnalyzeOptions,
+struct LogLevel {
+    filter: String,
 }
 
-#[derive(Debug, Default, Deserialize, Serialize)]
-struct RestAnalyzeOptions {
-    locale: Option<String>,
-    pages: Option<Vec<String>>,
+fn log_level_control(state: &RestApiState) -> Result<&LogLevelControl, AppError> {
+    state
+        .log_level
+        .as_ref()
+        .ok_or_else(|| AppError::Internal("Log level control is not installed".to_string()))
 }
 
-#[derive(Debug, Serialize)]
-struct AnalyzeResponse {
-    operation_id: String,
-    status: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    result: Option<RestAnalysisResult>,
+fn pipeline_service<'a>(state: &'a RestApiState, name: &str) -> Result<&'a Arc<PipelineService>, AppError> {
+    state
+        .pipelines
+        .as_ref()
+        .ok_or_else(|| ApplicationError::PipelineNotFound(name.to_string()).into())
 }
 
-#[derive(Debug, Serialize)]
-struct RestAnalysisResult {
-    model_id: String,
-    content: String,
-    pages: Vec<RestPage>,
-    tables: Vec<RestTable>,
+/// Names of the configured pipelines
+async fn list_pipelines(State(state): State<RestApiState>) -> impl IntoResponse {
+    let names = state.pipelines.as_ref().map(|pipelines| pipelines.pipeline_names()).unwrap_or_default();
+    Json(serde_json::json!({ "pipelines": names }))
 }
 
-#[derive(Debug, Serialize)]
-struct RestPage {
-    page_number: i32,
-    width: f32,
-    height: f32,
-    word_count: usize,
-    line_count: usize,
+/// Start a pipeline for a document URL (JSON) or for each uploaded file (multipart)
+async fn run_pipeline(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(name): Path<String>,
+    request: Request,
+) -> Result<Response, AppError> {
+    info!("REST: Run pipeline {}", name);
+    
+    let pipelines = pipeline_service(&state, &name)?;
+    let is_multipart = request
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("multipart/form-data"));
+    
+    let requests = if is_multipart {
+        let mut multipart = Multipart::from_request(request, &state)
+            .await
+            .map_err(|e| AppError::Validation(e.body_text()))?;
+        let upload = extract_files_from_multipart(&mut multipart).await?;
+        let options = AnalyzeOptions::try_from(upload.options)?;
+        upload
+            .files
+            .into_iter()
+            .map(|(bytes, metadata)| AnalyzeDocumentRequest {
+                source: DocumentSource::Bytes(bytes),
+                model_type: ModelType::Layout,
+                options: options.clone(),
+                metadata: Some(metadata),
+                tenant_id: tenant.clone(),
+            })
+            .collect()
+    } else {
+        let Json(body) = Json::<AnalyzeUrlRequest>::from_request(request, &state)
+            .await
+            .map_err(|e| AppError::Validation(e.body_text()))?;
+        vec![create_domain_request(body, ModelType::Layout, tenant)?]
+    };
+    
+    let single = requests.len() == 1;
+    let mut runs = Vec::with_capacity(requests.len());
+    for request in requests {
+        runs.push(pipelines.start(&name, request).await?);
+    }
+    let location = state.urls.url(&format!("/api/v1/pipelines/{}/runs/{}", name, runs[0].run_id));
+    let location = HeaderValue::from_str(&location)
+        .map_err(|e| AppError::Internal(format!("Invalid run location: {}", e)))?;
+    let body = if single {
+        PipelineRunResponse::Single(runs.remove(0))
+    } else {
+        PipelineRunResponse::Batch { runs }
+    };
+    Ok((StatusCode::ACCEPTED, [(header::LOCATION, location)], Json(body)).into_response())
 }
 
-#[derive(Debug, Serialize)]
-struct RestTable {
-    row_count: i32,
-    column_count: i32,
-    cell_count: usize,
+/// A pipeline run and the status of each of its steps
+async fn get_pipeline_run(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path((name, run_id)): Path<(String, String)>,
+) -> Result<Json<PipelineRun>, AppError> {
+    let run = pipeline_service(&state, &name)?.run(&tenant, &name, &run_id).await?;
+    Ok(Json(run))
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorResponse {
-    error: String,
+fn export_service(state: &RestApiState) -> Result<&Arc<ExportService>, AppError> {
+    state
+        .exports
+        .as_ref()
+        .ok_or_else(|| ApplicationError::Configuration("Exports are not configured".to_string()).into())
 }
 
-// Handler implementations
-async fn health_check() -> impl IntoResponse {
-    Json(serde_json::json!({
-        "status": "healthy",
-        "service": "adi-svc",
-        "version": env!("CARGO_PKG_VERSION")
+/// Start exporting the caller's results as NDJSON
+async fn create_export(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Json(filter): Json<ExportFilter>,
+) -> Result<Response, AppError> {
+    info!("REST: Export results ({:?})", filter);
+    
+    let job = export_service(&state)?.start(tenant, filter).await?;
+    let location = state.urls.url(&format!("/api/v1/exports/{}", job.export_id));
+    let location = HeaderValue::from_str(&location)
+        .map_err(|e| AppError::Internal(format!("Invalid export location: {}", e)))?;
+    let body = ExportJobResponse { job, download_url: None, expires_at: None };
+    Ok((StatusCode::ACCEPTED, [(header::LOCATION, location)], Json(body)).into_response())
+}
+
+/// An export's progress, and a signed link to its file once it has succeeded
+async fn get_export(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(export_id): Path<String>,
+    Query(query): Query<DocumentUrlQuery>,
+) -> Result<Json<ExportJobResponse>, AppError> {
+    let exports = export_service(&state)?;
+    let job = exports.job(&tenant, &export_id).await?;
+    let signed = exports.download_url(&job, url_expiry(&query)?).await?;
+    Ok(Json(ExportJobResponse {
+        job,
+        download_url: signed.as_ref().map(|signed| signed.url.clone()),
+        expires_at: signed.map(|signed| signed.expires_at),
     }))
 }
 
-async fn analyze_read(
+async fn get_log_level(
     State(state): State<RestApiState>,
-    Json(request): Json<AnalyzeUrlRequest>,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Analyze read request for: {}", request.document_url);
+    headers: HeaderMap,
+) -> Result<Json<LogLevel>, AppError> {
+    require_admin(&state, &headers)?;
     
-    let domain_request = create_domain_request(request, ModelType::Read)?;
-    let operation = state.service.analyze_document(domain_request).await?;
-    
-    Ok(Json(operation_to_response(operation, None)))
+    Ok(Json(LogLevel { filter: log_level_control(&state)?.current() }))
 }
 
-async fn analyze_layout(
+/// Change the log filter without restarting
+async fn set_log_level(
     State(state): State<RestApiState>,
-    Json(request): Json<AnalyzeUrlRequest>,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Analyze layout request for: {}", request.document_url);
+    headers: HeaderMap,
+    Json(level): Json<LogLevel>,
+) -> Result<Json<LogLevel>, AppError> {
+    require_admin(&state, &headers)?;
     
-    let domain_request = create_domain_request(request, ModelType::Layout)?;
-    let operation = state.service.analyze_document(domain_request).await?;
+    let control = log_level_control(&state)?;
+    control.set(&level.filter).map_err(AppError::Validation)?;
+    info!("Log filter changed to {}", level.filter);
+    Ok(Json(LogLevel { filter: control.current() }))
+}
+
+/// Write the operations and fields of every tenant created on the requested days as Parquet
+#[cfg(feature = "analytics")]
+async fn export_parquet(
+    State(state): State<RestApiState>,
+    headers: HeaderMap,
+    Json(request): Json<ParquetExportRequest>,
+) -> Result<Json<AnalyticsExportReport>, AppError> {
+    require_admin(&state, &headers)?;
     
-    Ok(Json(operation_to_response(operation, None)))
+    let export = state.analytics_export.as_ref().ok_or_else(|| {
+        ApplicationError::Configuration("Analytics export is not configured".to_string())
+    })?;
+    let to = request.to.unwrap_or(request.from);
+    info!("REST: Parquet export of operations created {} to {}", request.from, to);
+    Ok(Json(export.export(request.from, to).await?))
 }
 
-async fn analyze_invoice(
+fn quota_key(tenant: String, period: &str) -> Result<(TenantId, QuotaPeriod), AppError> {
+    let tenant = TenantId::new(tenant).map_err(|e| AppError::Validation(e.to_string()))?;
+    let period = QuotaPeriod::parse(period)
+        .ok_or_else(|| AppError::Validation(format!("Unknown quota period: {}; use daily or monthly", period)))?;
+    Ok((tenant, period))
+}
+
+/// Daily pages and estimated Azure cost per tenant and model
+///
+/// The admin key may report on any tenant, or all of them when `tenant` is
+/// omitted; other callers only see their own tenant.
+async fn get_usage(
     State(state): State<RestApiState>,
-    Json(request): Json<AnalyzeUrlRequest>,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Analyze invoice request for: {}", request.document_url);
+    headers: HeaderMap,
+    Query(params): Query<UsageParams>,
+) -> Result<Json<UsageResponse>, AppError> {
+    let requested = params
+        .tenant
+        .map(TenantId::new)
+        .transpose()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let tenant = if require_admin(&state, &headers).is_ok() {
+        requested
+    } else {
+        let own = resolve_tenant(&state, &headers)?;
+        if requested.as_ref().is_some_and(|requested| *requested != own) {
+            return Err(AppError::Forbidden("Usage of other tenants requires the admin API key".to_string()));
+        }
+        Some(own)
+    };
     
-    let domain_request = create_domain_request(request, ModelType::Invoice)?;
-    let operation = state.service.analyze_document(domain_request).await?;
+    let today = chrono::Utc::now().date_naive();
+    let to = params.to.unwrap_or(today);
+    let from = params.from.unwrap_or_else(|| to.with_day(1).unwrap_or(to));
+    let query = UsageQuery { tenant, from, to };
+    let records = state.service.usage_report(&query).await?;
+    info!("REST: Usage report {}..{} with {} rows", from, to, records.len());
     
-    Ok(Json(operation_to_response(operation, None)))
+    let usage: Vec<UsageEntry> = records.into_iter().map(UsageEntry::from).collect();
+    Ok(Json(UsageResponse {
+        from,
+        to,
+        tenant_id: query.tenant,
+        currency: "USD",
+        total_operations: usage.iter().map(|entry| entry.operations).sum(),
+        total_pages: usage.iter().map(|entry| entry.pages).sum(),
+        total_estimated_cost: round_cents(usage.iter().map(|entry| entry.estimated_cost).sum()),
+        usage,
+    }))
 }
 
-async fn analyze_receipt(
+/// Round a dollar amount to whole cents
+fn round_cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+/// Plain text of a succeeded result, optionally limited to `?pages=1-3,5`
+async fn get_result_text(
     State(state): State<RestApiState>,
-    Json(request): Json<AnalyzeUrlRequest>,
-    ) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Analyze receipt request for: {}", request.document_url);
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+    Query(query): Query<ResultTextQuery>,
+) -> Result<Response, AppError> {
+    info!("REST: Text export for operation: {}", operation_id);
     
-    let domain_request = create_domain_request(request, ModelType::Receipt)?;
-    let operation = state.service.analyze_document(domain_request).await?;
+    let pages = query
+        .pages
+        .map(|pages| PageRange::new(vec![pages]))
+        .transpose()
+        .map_err(|e| AppError::Validation(e.to_string()))?;
+    let (_, result) = state.service.completed_result(&tenant, &operation_id).await?;
+    Ok((
+        [(header::CONTENT_TYPE, HeaderValue::from_static("text/plain; charset=utf-8"))],
+        result.text(pages.as_ref()),
+    )
+        .into_response())
+}
+
+/// Render a succeeded result as a single Markdown document
+async fn get_result_markdown(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<Response, AppError> {
+    export_result(&state, &tenant, &operation_id, "text/markdown; charset=utf-8", render_markdown).await
+}
+
+/// Render a succeeded result's OCR layer as hOCR
+async fn get_result_hocr(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<Response, AppError> {
+    export_result(&state, &tenant, &operation_id, "application/xhtml+xml; charset=utf-8", render_hocr).await
+}
+
+/// Render a succeeded result's OCR layer as ALTO XML
+async fn get_result_alto(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+) -> Result<Response, AppError> {
+    export_result(&state, &tenant, &operation_id, "application/xml; charset=utf-8", render_alto).await
+}
+
+/// Render a succeeded invoice result as UBL or ZUGFeRD/Factur-X XML
+async fn get_result_invoice_xml(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+    Query(query): Query<InvoiceXmlQuery>,
+) -> Result<Response, AppError> {
+    info!("REST: {:?} invoice XML for operation: {}", query.format, operation_id);
     
-    Ok(Json(operation_to_response(operation, None)))
+    let invoice = state.service.result_invoice(&tenant, &operation_id).await?;
+    Ok((
+        [(header::CONTENT_TYPE, HeaderValue::from_static("application/xml; charset=utf-8"))],
+        invoice.to_xml(query.format),
+    )
+        .into_response())
 }
 
-async fn analyze_id_document(
+/// A PDF copy of an operation's document with fields, PII and regions
+/// blacked out, also stored as a new document named by `Content-Location`
+async fn get_redacted_pdf(
     State(state): State<RestApiState>,
-    Json(request): Json<AnalyzeUrlRequest>,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Analyze ID document request for: {}", request.document_url);
+    Tenant(tenant): Tenant,
+    Path(operation_id): Path<String>,
+    Query(query): Query<RedactionQuery>,
+) -> Result<Response, AppError> {
+    info!("REST: Redacted PDF for operation: {}", operation_id);
     
-    let domain_request = create_domain_request(request, ModelType::IdDocument)?;
-    let operation = state.service.analyze_document(domain_request).await?;
+    let rules = query.rules()?;
+    let (document_id, pdf) = state.service.redact_document(&tenant, &operation_id, &rules).await?;
+    let location = HeaderValue::from_str(&state.urls.url(&format!("/api/v1/documents/{}", document_id)))
+        .map_err(|e| AppError::Internal(format!("Invalid document location: {}", e)))?;
+    Ok((
+        [
+            (header::CONTENT_TYPE, HeaderValue::from_static("application/pdf")),
+            (header::CONTENT_DISPOSITION, inline_disposition(&format!("{}-redacted.pdf", operation_id))),
+            (header::CONTENT_LOCATION, location),
+        ],
+        pdf,
+    )
+        .into_response())
+}
+
+/// Serve a succeeded result rendered by `render`
+async fn export_result(
+    state: &RestApiState,
+    tenant: &TenantId,
+    operation_id: &str,
+    content_type: &'static str,
+    render: fn(&AnalysisResult) -> String,
+) -> Result<Response, AppError> {
+    info!("REST: Export {} for operation: {}", content_type, operation_id);
     
-    Ok(Json(operation_to_response(operation, None)))
+    let (_, result) = state.service.completed_result(tenant, operation_id).await?;
+    Ok((
+        [(header::CONTENT_TYPE, HeaderValue::from_static(content_type))],
+        render(&result),
+    )
+        .into_response())
 }
 
-async fn analyze_business_card(
+/// Strong ETag from the operation status and a hash of the response body
+fn result_etag(status: &str, body: &[u8]) -> String {
+    let digest = Sha256::digest(body);
+    format!("\"{}-{}\"", status, hex::encode(&digest[..16]))
+}
+
+/// Whether any entity tag in `If-None-Match` matches `etag`
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|tag| tag.trim())
+        .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+}
+
+// Resumable upload handlers
+const UPLOAD_OFFSET: &str = "upload-offset";
+const UPLOAD_LENGTH: &str = "upload-length";
+
+/// `Upload-Offset` / `Upload-Length` headers describing an upload's progress
+fn upload_headers(state: &UploadState) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    headers.insert(UPLOAD_OFFSET, HeaderValue::from(state.offset));
+    if let Some(size) = state.total_size {
+        headers.insert(UPLOAD_LENGTH, HeaderValue::from(size));
+    }
+    headers.insert(header::CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    headers
+}
+
+async fn create_upload(
     State(state): State<RestApiState>,
-    Json(request): Json<AnalyzeUrlRequest>,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Analyze business card request for: {}", request.document_url);
+    Tenant(tenant): Tenant,
+    Json(request): Json<CreateUploadRequest>,
+) -> Result<Response, AppError> {
+    let metadata = DocumentMetadata::new(request.filename, request.content_type.unwrap_or_default());
+    let upload = state.service.create_upload(&tenant, &metadata, request.size).await?;
+    info!("REST: Created resumable upload {}", upload.upload_id);
     
-    let domain_request = create_domain_request(request, ModelType::BusinessCard)?;
-    let operation = state.service.analyze_document(domain_request).await?;
+    let mut headers = upload_headers(&upload);
+    let location = HeaderValue::from_str(&state.urls.url(&format!("/api/v1/uploads/{}", upload.upload_id)))
+        .map_err(|e| AppError::Internal(format!("Invalid upload location: {}", e)))?;
+    headers.insert(header::LOCATION, location);
     
-    Ok(Json(operation_to_response(operation, None)))
+    Ok((StatusCode::CREATED, headers, Json(UploadResponseBody::from(upload))).into_response())
 }
 
-async fn analyze_w2(
+async fn get_upload(
     State(state): State<RestApiState>,
-    Json(request): Json<AnalyzeUrlRequest>,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Analyze W-2 request for: {}", request.document_url);
-    
-    let domain_request = create_domain_request(request, ModelType::W2)?;
-    let operation = state.service.analyze_document(domain_request).await?;
+    Tenant(tenant): Tenant,
+    Path(upload_id): Path<String>,
+) -> Result<Response, AppError> {
+    let upload = state.service.upload_state(&tenant, &upload_id).await?;
+    Ok((upload_headers(&upload), Json(UploadResponseBody::from(upload))).into_response())
+}
+
+async fn patch_upload(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(upload_id): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, AppError> {
+    let offset = headers
+        .get(UPLOAD_OFFSET)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok())
+        .ok_or_else(|| AppError::Validation("Upload-Offset header is required".to_string()))?;
     
-    Ok(Json(operation_to_response(operation, None)))
+    let upload = state.service.append_upload(&tenant, &upload_id, offset, body).await?;
+    Ok((StatusCode::NO_CONTENT, upload_headers(&upload)).into_response())
 }
 
-async fn analyze_custom(
+async fn delete_upload(
     State(state): State<RestApiState>,
-    Path(model_id): Path<String>,
-    Json(request): Json<AnalyzeUrlRequest>,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Analyze custom request with model: {}", model_id);
+    Tenant(tenant): Tenant,
+    Path(upload_id): Path<String>,
+) -> Result<StatusCode, AppError> {
+    state.service.abort_upload(&tenant, &upload_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn analyze_upload(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(upload_id): Path<String>,
+    Query(query): Query<AnalyzeUploadQuery>,
+    options: Option<Json<RestAnalyzeOptions>>,
+) -> Result<Response, AppError> {
+    let model_type = ModelType::from_string(&query.model)
+        .ok()
+        .filter(|model_type| *model_type != ModelType::Custom)
+        .ok_or_else(|| AppError::Validation(format!("Unsupported model: {}", query.model)))?;
+    let options = options.map(|Json(options)| options).unwrap_or_default();
     
-    let source = DocumentSource::Url(request.document_url);
-    let operation = state.service.analyze_custom(source, &model_id).await?;
+    info!("REST: Analyze resumable upload {} with {}", upload_id, model_type);
+    let operation = state
+        .service
+        .analyze_upload(&tenant, &upload_id, model_type, options.try_into()?)
+        .await?;
     
-    Ok(Json(operation_to_response(operation, None)))
+    Ok(started_response(operation))
 }
 
-async fn upload_and_analyze_read(
+async fn lookup_document(
     State(state): State<RestApiState>,
-    mut multipart: Multipart,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Upload and analyze read request");
+    Tenant(tenant): Tenant,
+    Query(query): Query<DocumentLookupQuery>,
+) -> Result<Json<DocumentLookupResponse>, AppError> {
+    let sha256 = query.sha256.trim().to_ascii_lowercase();
+    if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return Err(AppError::Validation("sha256 must be 64 hex characters".to_string()));
+    }
+    let limit = query.limit.unwrap_or(20).clamp(1, 100);
     
-    let bytes = extract_file_from_multipart(&mut multipart).await?;
-    let operation = state.service.analyze_read(DocumentSource::Bytes(bytes)).await?;
+    let operations = state.service.lookup_by_sha256(&tenant, &sha256, limit).await?;
+    info!("REST: Hash lookup matched {} operations", operations.len());
     
-    Ok(Json(operation_to_response(operation, None)))
+    Ok(Json(DocumentLookupResponse {
+        sha256,
+        operations: operations.into_iter().map(OperationSummary::from).collect(),
+    }))
 }
 
-async fn upload_and_analyze_layout(
+async fn download_document(
     State(state): State<RestApiState>,
-    mut multipart: Multipart,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Upload and analyze layout request");
+    Tenant(tenant): Tenant,
+    Path(document_id): Path<String>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    serve_document(&state, &tenant, &document_id, &headers).await
+}
+
+// Signed document links
+const DEFAULT_URL_EXPIRY_SECS: u64 = 900;
+const MAX_URL_EXPIRY_SECS: u64 = 7 * 24 * 3600;
+
+async fn create_document_url(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    Path(document_id): Path<String>,
+    Query(query): Query<DocumentUrlQuery>,
+) -> Result<Json<DocumentUrlResponse>, AppError> {
+    let signed = state
+        .service
+        .document_url(&tenant, &document_id, url_expiry(&query)?)
+        .await?;
+    Ok(Json(DocumentUrlResponse {
+        url: signed.url,
+        expires_at: signed.expires_at,
+    }))
+}
+
+fn url_expiry(query: &DocumentUrlQuery) -> Result<chrono::Duration, AppError> {
+    let expires_in = query.expires_in.unwrap_or(DEFAULT_URL_EXPIRY_SECS);
+    if expires_in == 0 || expires_in > MAX_URL_EXPIRY_SECS {
+        return Err(AppError::Validation(format!(
+            "expires_in must be between 1 and {} seconds",
+            MAX_URL_EXPIRY_SECS
+        )));
+    }
+    Ok(chrono::Duration::seconds(expires_in as i64))
+}
+
+async fn signed_document(
+    State(state): State<RestApiState>,
+    Path(document_id): Path<String>,
+    Query(query): Query<SignedDocumentQuery>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    // The link's signature covers the tenant, so it stands in for credentials
+    let tenant = query
+        .tenant
+        .map(TenantId::new)
+        .transpose()
+        .map_err(|e| AppError::Validation(e.to_string()))?
+        .unwrap_or_default();
+    state
+        .service
+        .verify_document_url(&tenant, &document_id, query.expires, &query.signature)
+        .await?;
+    serve_document(&state, &tenant, &document_id, &headers).await
+}
+
+/// Stream a stored document with its original content type, honouring `Range`
+async fn serve_document(
+    state: &RestApiState,
+    tenant: &TenantId,
+    document_id: &str,
+    headers: &HeaderMap,
+) -> Result<Response, AppError> {
+    let total_size = state.service.document_size(tenant, document_id).await?;
+    let range = match parse_range(headers, total_size) {
+        RangeRequest::Full => None,
+        RangeRequest::Partial(range) => Some(range),
+        RangeRequest::Unsatisfiable => return Ok(range_not_satisfiable(total_size)),
+    };
     
-    let bytes = extract_file_from_multipart(&mut multipart).await?;
-    let operation = state.service.analyze_layout(DocumentSource::Bytes(bytes)).await?;
+    let metadata = state.service.document_metadata(tenant, document_id).await?;
+    let stream = state.service.open_document(tenant, document_id, range).await?;
+    info!("REST: Downloading document {} ({} bytes)", document_id, stream.content_length());
     
-    Ok(Json(operation_to_response(operation, None)))
+    let mut response = stream_response(stream, &metadata.content_type);
+    let headers = response.headers_mut();
+    headers.insert(header::CONTENT_DISPOSITION, inline_disposition(&metadata.filename));
+    headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    Ok(response)
 }
 
-async fn upload_and_analyze_invoice(
+// Work queue handlers
+const DEFAULT_LEASE_SECS: u64 = 300;
+const MAX_LEASE_SECS: u64 = 3600;
+const MAX_CLAIM_LIMIT: u32 = 100;
+
+async fn claim_work(
     State(state): State<RestApiState>,
-    mut multipart: Multipart,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Upload and analyze invoice request");
-    
-    let bytes = extract_file_from_multipart(&mut multipart).await?;
-    let operation = state.service.analyze_invoice(DocumentSource::Bytes(bytes)).await?;
+    Tenant(tenant): Tenant,
+    queue: WorkQueue,
+    Json(request): Json<ClaimWorkRequest>,
+) -> Result<Json<ClaimWorkResponse>, AppError> {
+    let worker_id = validate_worker_id(&request.worker_id)?;
+    let lease = lease_duration(request.lease_secs)?;
+    let limit = request.limit.unwrap_or(1);
+    if limit == 0 || limit > MAX_CLAIM_LIMIT {
+        return Err(AppError::Validation(format!("limit must be between 1 and {}", MAX_CLAIM_LIMIT)));
+    }
     
-    Ok(Json(operation_to_response(operation, None)))
+    let items = state.service.claim_work(&tenant, queue, worker_id, lease, limit).await?;
+    Ok(Json(ClaimWorkResponse { items }))
 }
 
-async fn get_result(
+async fn heartbeat_work(
     State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    queue: WorkQueue,
     Path(operation_id): Path<String>,
-) -> Result<Json<AnalyzeResponse>, AppError> {
-    info!("REST: Get result for operation: {}", operation_id);
-    
-    let (operation, result) = state.service.get_analysis_result(&operation_id).await
-        .map_err(|e| {
-            // Handle rate limiting specially
-            if e.to_string().contains("429") {
-                error!("Rate limit hit for operation: {}", operation_id);
-                AppError::Application(e)
-            } else {
-                AppError::Application(e)
-            }
-        })?;
-    
-    let response = operation_to_response(operation, result);
-    info!("Returning result - has data: {}", response.result.is_some());
+    Json(request): Json<HeartbeatWorkRequest>,
+) -> Result<Json<WorkLease>, AppError> {
+    let worker_id = validate_worker_id(&request.worker_id)?;
+    let lease = lease_duration(request.lease_secs)?;
     
-    Ok(Json(response))
+    let lease = state.service.heartbeat_work(&tenant, queue, &operation_id, worker_id, lease).await?;
+    Ok(Json(lease))
+}
+
+async fn complete_work(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    queue: WorkQueue,
+    Path(operation_id): Path<String>,
+    Json(request): Json<WorkerRequest>,
+) -> Result<StatusCode, AppError> {
+    let worker_id = validate_worker_id(&request.worker_id)?;
+    state.service.complete_work(&tenant, queue, &operation_id, worker_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn release_work(
+    State(state): State<RestApiState>,
+    Tenant(tenant): Tenant,
+    queue: WorkQueue,
+    Path(operation_id): Path<String>,
+    Json(request): Json<WorkerRequest>,
+) -> Result<StatusCode, AppError> {
+    let worker_id = validate_worker_id(&request.worker_id)?;
+    state.service.release_work(&tenant, queue, &operation_id, worker_id).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+fn validate_worker_id(worker_id: &str) -> Result<&str, AppError> {
+    let worker_id = worker_id.trim();
+    if worker_id.is_empty() || worker_id.len() > 255 {
+        return Err(AppError::Validation("worker_id must be 1-255 characters".to_string()));
+    }
+    Ok(worker_id)
+}
+
+fn lease_duration(lease_secs: Option<u64>) -> Result<chrono::Duration, AppError> {
+    let lease_secs = lease_secs.unwrap_or(DEFAULT_LEASE_SECS);
+    if lease_secs == 0 || lease_secs > MAX_LEASE_SECS {
+        return Err(AppError::Validation(format!("lease_secs must be between 1 and {}", MAX_LEASE_SECS)));
+    }
+    Ok(chrono::Duration::seconds(lease_secs as i64))
 }
 
 // Helper functions
 fn create_domain_request(
     request: AnalyzeUrlRequest,
     model_type: ModelType,
+    tenant_id: TenantId,
 ) -> Result<AnalyzeDocumentRequest, AppError> {
     let source = DocumentSource::Url(request.document_url);
     source.validate().map_err(|e| AppError::Validation(e.to_string()))?;
     
-    let options = AnalyzeOptions {
-        locale: request.options.locale.and_then(|l| Locale::new(l).ok()),
-        pages: request.options.pages.and_then(|p| PageRange::new(p).ok()),
-        features: vec![],
-    };
-    
     Ok(AnalyzeDocumentRequest {
         source,
         model_type,
-        options,
+        options: request.options.try_into()?,
+        metadata: None,
+        tenant_id,
     })
 }
 
+/// Operations a handler started, passed to `audit_requests` as a response extension
+#[derive(Clone)]
+struct StartedOperations(Vec<AnalysisOperation>);
+
+/// Response for a newly started operation
+fn started_response(operation: AnalysisOperation) -> Response {
+    let started = StartedOperations(vec![operation.clone()]);
+    (Extension(started), Json(operation_to_response(operation, None))).into_response()
+}
+
+/// Start an analysis now, or queue it when `mode` is async
+async fn submit(
+    state: &RestApiState,
+    request: AnalyzeDocumentRequest,
+    submission: Submission,
+) -> Result<Response, AppError> {
+    match submission.mode {
+        SubmitMode::Direct => Ok(started_response(state.service.analyze_document(request).await?)),
+        SubmitMode::Async => {
+            let job = state.service.enqueue_analysis(request, submission.priority).await?;
+            queued_response(state, job)
+        }
+    }
+}
+
+/// 202 for a queued job, pointing at its status
+fn queued_response(state: &RestApiState, job: AnalysisJob) -> Result<Response, AppError> {
+    let body = JobResponse::new(&state.urls, job, None);
+    let location = HeaderValue::from_str(&body.status_url)
+        .map_err(|e| AppError::Internal(format!("Invalid job location: {}", e)))?;
+    Ok((StatusCode::ACCEPTED, [(header::LOCATION, location)], Json(body)).into_response())
+}
+
 fn operation_to_response(
     operation: AnalysisOperation,
     result: Option<AnalysisResult>,
+) -> AnalyzeResponse {
+    operation_to_projected_response(operation, result, &ResultFields::all())
+}
+
+/// Response carrying only the result sections selected by `fields`
+fn operation_to_projected_response(
+    operation: AnalysisOperation,
+    result: Option<AnalysisResult>,
+    fields: &ResultFields,
 ) -> AnalyzeResponse {
     let status = format!("{:?}", operation.status).to_lowercase();
     
@@ -316,78 +2571,307 @@ fn operation_to_response(
     AnalyzeResponse {
         operation_id: operation.operation_id,
         status,
+        filename: operation.filename,
+        content_type: operation.content_type,
+        document_id: operation.document_id,
         result: result.map(|r| {
             let rest_result = RestAnalysisResult {
                 model_id: r.model_id.clone(),
-                content: r.content.clone(),
-                pages: r.pages.iter().map(|p| RestPage {
+                string_index_type: r.string_index_type,
+                content: fields.content.then(|| r.content.clone()),
+                pages: fields.pages.then(|| r.pages.iter().map(|p| RestPage {
                     page_number: p.page_number,
                     width: p.width,
                     height: p.height,
                     word_count: p.words.len(),
                     line_count: p.lines.len(),
-                }).collect(),
-                tables: r.tables.iter().map(|t| RestTable {
+                }).collect()),
+                tables: fields.tables.then(|| r.tables.iter().map(|t| RestTable {
                     row_count: t.row_count,
                     column_count: t.column_count,
                     cell_count: t.cells.len(),
-                }).collect(),
+                }).collect()),
+                filtered: None,
             };
-            info!("Converted to REST format - content length: {}", rest_result.content.len());
+            info!("Converted to REST format - content length: {}", r.content.len());
             rest_result
         }),
+        routing: operation.routing,
+        review: operation.review,
+        error: operation.error,
     }
 }
 
-async fn extract_file_from_multipart(multipart: &mut Multipart) -> Result<Vec<u8>, AppError> {
-    let mut file_bytes = Vec::new();
+/// Files and analysis options read from a multipart upload
+struct MultipartUpload {
+    files: Vec<(Bytes, DocumentMetadata)>,
+    options: RestAnalyzeOptions,
+}
+
+/// Read every `file` part plus an optional `options` JSON part
+async fn extract_files_from_multipart(multipart: &mut Multipart) -> Result<MultipartUpload, AppError> {
+    let mut files = Vec::new();
+    let mut options = RestAnalyzeOptions::default();
     
     while let Some(field) = multipart.next_field().await.map_err(|e| {
-        AppError::Internal(format!("Failed to read multipart field: {}", e))
+        multipart_error("Failed to read multipart field", e)
     })? {
-        if field.name() == Some("file") {
-            let data = field.bytes().await.map_err(|e| {
-                AppError::Internal(format!("Failed to read file data: {}", e))
-            })?;
-            file_bytes = data.to_vec();
-            break;
+        match field.name() {
+            Some("file") => {
+                let metadata = DocumentMetadata::new(
+                    field.file_name().unwrap_or_default(),
+                    field.content_type().unwrap_or_default(),
+                );
+                let data = field.bytes().await.map_err(|e| {
+                    multipart_error("Failed to read file data", e)
+                })?;
+                if data.is_empty() {
+                    return Err(AppError::Validation(format!("Empty file: {}", metadata.filename)));
+                }
+                files.push((data, metadata));
+            }
+            Some("options") => {
+                let text = field.text().await.map_err(|e| {
+                    multipart_error("Failed to read options", e)
+                })?;
+                options = serde_json::from_str(&text)
+                    .map_err(|e| AppError::Validation(format!("Invalid options: {}", e)))?;
+            }
+            _ => {}
         }
     }
     
-    if file_bytes.is_empty() {
+    if files.is_empty() {
         return Err(AppError::Validation("No file provided".to_string()));
     }
     
-    Ok(file_bytes)
+    Ok(MultipartUpload { files, options })
+}
+
+/// Start one analysis per uploaded file
+async fn upload_and_analyze(
+    state: &RestApiState,
+    tenant: TenantId,
+    multipart: &mut Multipart,
+    model_type: ModelType,
+    submission: Submission,
+) -> Result<Response, AppError> {
+    let upload = extract_files_from_multipart(multipart).await?;
+    let options = AnalyzeOptions::try_from(upload.options)?;
+    let single = upload.files.len() == 1;
+    
+    let mut errors = Vec::new();
+    if submission.mode == SubmitMode::Async {
+        let mut jobs = Vec::with_capacity(upload.files.len());
+        for (bytes, metadata) in upload.files {
+            let filename = metadata.filename.clone();
+            let request = AnalyzeDocumentRequest {
+                source: DocumentSource::Bytes(bytes),
+                model_type,
+                options: options.clone(),
+                metadata: Some(metadata),
+                tenant_id: tenant.clone(),
+            };
+            match state.service.enqueue_analysis(request, submission.priority).await {
+                Ok(job) => jobs.push(JobResponse::new(&state.urls, job, None)),
+                Err(e) if single => return Err(e.into()),
+                Err(e) => errors.push(FileError::new(filename, e.into())),
+            }
+        }
+        let status = if errors.is_empty() { StatusCode::ACCEPTED } else { StatusCode::MULTI_STATUS };
+        let body = if single {
+            QueuedUploadResponse::Single(Box::new(jobs.remove(0)))
+        } else {
+            QueuedUploadResponse::Batch { jobs, errors }
+        };
+        return Ok((status, Json(body)).into_response());
+    }
+    
+    let mut started = Vec::with_capacity(upload.files.len());
+    for (bytes, metadata) in upload.files {
+        let filename = metadata.filename.clone();
+        let request = AnalyzeDocumentRequest {
+            source: DocumentSource::Bytes(bytes),
+            model_type,
+            options: options.clone(),
+            metadata: Some(metadata),
+            tenant_id: tenant.clone(),
+        };
+        match state.service.analyze_document(request).await {
+            Ok(operation) => started.push(operation),
+            Err(e) if single => return Err(e.into()),
+            Err(e) => errors.push(FileError::new(filename, e.into())),
+        }
+    }
+    
+    Ok(upload_response(started, errors, single))
+}
+
+/// The operations an upload started; a batch also lists the files that failed, as 207
+fn upload_response(started: Vec<AnalysisOperation>, errors: Vec<FileError>, single: bool) -> Response {
+    let mut operations: Vec<_> = started.iter().cloned().map(|operation| operation_to_response(operation, None)).collect();
+    let status = if errors.is_empty() { StatusCode::OK } else { StatusCode::MULTI_STATUS };
+    let body = if single {
+        UploadResponse::Single(Box::new(operations.remove(0)))
+    } else {
+        UploadResponse::Batch { operations, errors }
+    };
+    (status, Extension(StartedOperations(started)), Json(body)).into_response()
+}
+
+/// Map a multipart read error, keeping body limit rejections as 413
+fn multipart_error(context: &str, e: axum::extract::multipart::MultipartError) -> AppError {
+    if e.status() == StatusCode::PAYLOAD_TOO_LARGE {
+        AppError::PayloadTooLarge(format!("{}: upload exceeds the size limit", context))
+    } else {
+        AppError::Internal(format!("{}: {}", context, e))
+    }
 }
 
 // Error handling
 #[derive(Debug)]
 enum AppError {
     Validation(String),
+    PayloadTooLarge(String),
+    Unauthorized(String),
+    Forbidden(String),
     Internal(String),
-    Application(crate::application::errors::ApplicationError),
+    Application(ApplicationError),
 }
 
-impl From<crate::application::errors::ApplicationError> for AppError {
-    fn from(err: crate::application::errors::ApplicationError) -> Self {
+impl From<ApplicationError> for AppError {
+    fn from(err: ApplicationError) -> Self {
         Self::Application(err)
     }
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
+impl From<TenantRejection> for AppError {
+    fn from(rejection: TenantRejection) -> Self {
+        match rejection {
+            TenantRejection::Unauthenticated => Self::Unauthorized(rejection.to_string()),
+            TenantRejection::Mismatch => Self::Forbidden(rejection.to_string()),
+            TenantRejection::Invalid(message) => Self::Validation(message),
+        }
+    }
+}
+
+impl AppError {
+    /// Status, body and `Retry-After` seconds the error responds with
+    fn parts(self) -> (StatusCode, ErrorResponse, Option<u64>) {
+        let mut code = None;
+        let mut resets_at = None;
+        let mut retry_after = None;
+        let mut azure_error = None;
         let (status, message) = match self {
             AppError::Validation(msg) => (StatusCode::BAD_REQUEST, msg),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
             AppError::Internal(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg),
             AppError::Application(err) => {
-                error!("Application error: {}", err);
-                (StatusCode::INTERNAL_SERVER_ERROR, err.to_string())
+                azure_error = err.azure_error().cloned();
+                let status = match &err {
+                    ApplicationError::DocumentNotFound(_)
+                    | ApplicationError::OperationNotFound(_)
+                    | ApplicationError::UploadNotFound(_)
+                    | ApplicationError::QuotaNotFound(_)
+                    | ApplicationError::JobNotFound(_)
+                    | ApplicationError::PageNotFound(_)
+                    | ApplicationError::TableNotFound(_)
+                    | ApplicationError::TemplateNotFound(_)
+                    | ApplicationError::PipelineNotFound(_)
+                    | ApplicationError::PipelineRunNotFound(_)
+                    | ApplicationError::ExportNotFound(_) => StatusCode::NOT_FOUND,
+                    ApplicationError::LeaseNotHeld(_)
+                    | ApplicationError::UploadOffsetMismatch { .. }
+                    | ApplicationError::JobNotRetryable(_)
+                    | ApplicationError::ResultNotAvailable(_)
+                    | ApplicationError::RevisionConflict { .. } => StatusCode::CONFLICT,
+                    ApplicationError::InvalidSignature(_) | ApplicationError::PermissionDenied(_) => {
+                        StatusCode::FORBIDDEN
+                    }
+                    ApplicationError::DeadlineExceeded => {
+                        code = Some("deadline_exceeded");
+                        StatusCode::GATEWAY_TIMEOUT
+                    }
+                    ApplicationError::MalwareDetected(_) => {
+                        code = Some("malware_detected");
+                        StatusCode::UNPROCESSABLE_ENTITY
+                    }
+                    ApplicationError::QuotaExceeded { resets_at: at, .. } => {
+                        code = Some("quota_exceeded");
+                        resets_at = Some(*at);
+                        retry_after = Some((*at - chrono::Utc::now()).num_seconds().max(0) as u64);
+                        StatusCode::TOO_MANY_REQUESTS
+                    }
+                    ApplicationError::AzureThrottled { retry_after_secs, .. } => {
+                        code = Some("azure_throttled");
+                        retry_after = *retry_after_secs;
+                        StatusCode::TOO_MANY_REQUESTS
+                    }
+                    ApplicationError::AzureUnavailable(_) => {
+                        error!("Application error: {}", err);
+                        code = Some("azure_unavailable");
+                        StatusCode::BAD_GATEWAY
+                    }
+                    // Azure refusing the document is the caller's problem; anything else is ours
+                    ApplicationError::Azure(error) if error.status == Some(400) => {
+                        code = Some("azure_rejected");
+                        StatusCode::BAD_REQUEST
+                    }
+                    ApplicationError::Azure(error) if error.status.is_some_and(|status| status >= 500) => {
+                        error!("Application error: {}", err);
+                        code = Some("azure_unavailable");
+                        StatusCode::BAD_GATEWAY
+                    }
+                    ApplicationError::Azure(_) => {
+                        error!("Application error: {}", err);
+                        code = Some("azure_error");
+                        StatusCode::BAD_GATEWAY
+                    }
+                    ApplicationError::Domain(DomainError::DocumentTooLarge { .. }) => {
+                        StatusCode::PAYLOAD_TOO_LARGE
+                    }
+                    ApplicationError::Domain(DomainError::UnsupportedDocumentType(_)) => {
+                        StatusCode::UNSUPPORTED_MEDIA_TYPE
+                    }
+                    ApplicationError::Domain(DomainError::EncryptedDocument) => {
+                        code = Some("encrypted_document");
+                        StatusCode::UNPROCESSABLE_ENTITY
+                    }
+                    ApplicationError::Domain(DomainError::TooManyPages { .. }) => {
+                        code = Some("too_many_pages");
+                        StatusCode::UNPROCESSABLE_ENTITY
+                    }
+                    ApplicationError::Domain(_) => StatusCode::BAD_REQUEST,
+                    _ => {
+                        error!("Application error: {}", err);
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    }
+                };
+                (status, err.to_string())
             }
         };
         
-        let body = Json(ErrorResponse { error: message });
-        (status, body).into_response()
+        let body = ErrorResponse {
+            error: message,
+            code,
+            resets_at,
+            azure_error,
+            request_id: request_id::current(),
+        };
+        (status, body, retry_after)
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, body, retry_after) = self.parts();
+        let mut response = (status, Json(body)).into_response();
+        if let Some(retry_after) = retry_after {
+            response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+        }
+        response
     }
 }
 
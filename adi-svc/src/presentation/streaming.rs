@@ -0,0 +1,193 @@
+/// Streaming HTTP responses
+///
+/// Serves document bodies with `Body::from_stream` so large downloads and
+/// exports are never buffered whole, honouring single `Range: bytes=` requests.
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::application::ports::{ByteRange, DocumentStream};
+
+/// A `Range` header resolved against a known body size
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RangeRequest {
+    /// No usable range; serve the whole body
+    Full,
+    /// Serve a single byte range (206)
+    Partial(ByteRange),
+    /// The range lies outside the body (416)
+    Unsatisfiable,
+}
+
+/// Resolve the request's `Range` header against a body of `total_size` bytes
+///
+/// Only single `bytes=` ranges are supported; anything else (multiple ranges,
+/// other units, malformed values) is ignored and the full body is served.
+pub fn parse_range(headers: &HeaderMap, total_size: u64) -> RangeRequest {
+    let spec = match headers
+        .get(header::RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().strip_prefix("bytes="))
+    {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return RangeRequest::Full,
+    };
+
+    let (start, end) = match spec.split_once('-') {
+        Some(bounds) => bounds,
+        None => return RangeRequest::Full,
+    };
+
+    if start.is_empty() {
+        // Suffix range: the last N bytes
+        return match end.parse::<u64>() {
+            Ok(0) => RangeRequest::Unsatisfiable,
+            Ok(_) if total_size == 0 => RangeRequest::Unsatisfiable,
+            Ok(suffix) => RangeRequest::Partial(ByteRange {
+                start: total_size.saturating_sub(suffix),
+                end: total_size - 1,
+            }),
+            Err(_) => RangeRequest::Full,
+        };
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(start) => start,
+        Err(_) => return RangeRequest::Full,
+    };
+    let end = if end.is_empty() {
+        u64::MAX
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => end,
+            _ => return RangeRequest::Full,
+        }
+    };
+
+    if start >= total_size {
+        return RangeRequest::Unsatisfiable;
+    }
+    RangeRequest::Partial(ByteRange {
+        start,
+        end: end.min(total_size - 1),
+    })
+}
+
+/// 416 response advertising the actual body size
+pub fn range_not_satisfiable(total_size: u64) -> Response {
+    (
+        StatusCode::RANGE_NOT_SATISFIABLE,
+        [(header::CONTENT_RANGE, format!("bytes */{}", total_size))],
+    )
+        .into_response()
+}
+
+/// `Content-Disposition: inline` naming the original file
+///
+/// The plain `filename` parameter carries an ASCII-safe fallback; the exact
+/// name goes in the RFC 5987 `filename*` parameter.
+pub fn inline_disposition(filename: &str) -> HeaderValue {
+    let fallback: String = filename
+        .chars()
+        .map(|c| match c {
+            '"' | '\\' => '_',
+            c if c.is_ascii_graphic() || c == ' ' => c,
+            _ => '_',
+        })
+        .collect();
+    let encoded: String = filename
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'.' | b'-' | b'_' | b'~' => (b as char).to_string(),
+            _ => format!("%{:02X}", b),
+        })
+        .collect();
+    
+    HeaderValue::from_str(&format!("inline; filename=\"{}\"; filename*=UTF-8''{}", fallback, encoded))
+        .unwrap_or_else(|_| HeaderValue::from_static("inline"))
+}
+
+/// Stream a document body as 200, or 206 when a range was requested
+pub fn stream_response(stream: DocumentStream, content_type: &str) -> Response {
+    let content_type = HeaderValue::from_str(content_type)
+        .unwrap_or_else(|_| HeaderValue::from_static("application/octet-stream"));
+    let content_length = stream.content_length();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(header::CONTENT_TYPE, content_type);
+    headers.insert(header::CONTENT_LENGTH, HeaderValue::from(content_length));
+    headers.insert(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+
+    let status = match stream.range {
+        Some(range) => {
+            let content_range = format!("bytes {}-{}/{}", range.start, range.end, stream.total_size);
+            if let Ok(value) = HeaderValue::from_str(&content_range) {
+                headers.insert(header::CONTENT_RANGE, value);
+            }
+            StatusCode::PARTIAL_CONTENT
+        }
+        None => StatusCode::OK,
+    };
+
+    (status, headers, Body::from_stream(stream.body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use futures::stream::{self, StreamExt};
+
+    fn range_headers(value: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, HeaderValue::from_str(value).unwrap());
+        headers
+    }
+
+    #[test]
+    fn test_parse_range() {
+        let partial = |start, end| RangeRequest::Partial(ByteRange { start, end });
+
+        assert_eq!(parse_range(&HeaderMap::new(), 100), RangeRequest::Full);
+        assert_eq!(parse_range(&range_headers("bytes=0-9"), 100), partial(0, 9));
+        assert_eq!(parse_range(&range_headers("bytes=90-"), 100), partial(90, 99));
+        assert_eq!(parse_range(&range_headers("bytes=90-500"), 100), partial(90, 99));
+        assert_eq!(parse_range(&range_headers("bytes=-10"), 100), partial(90, 99));
+        assert_eq!(parse_range(&range_headers("bytes=-500"), 100), partial(0, 99));
+        assert_eq!(parse_range(&range_headers("bytes=100-"), 100), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range(&range_headers("bytes=-0"), 100), RangeRequest::Unsatisfiable);
+        assert_eq!(parse_range(&range_headers("bytes=0-1,5-6"), 100), RangeRequest::Full);
+        assert_eq!(parse_range(&range_headers("bytes=9-2"), 100), RangeRequest::Full);
+        assert_eq!(parse_range(&range_headers("items=0-9"), 100), RangeRequest::Full);
+    }
+
+    #[test]
+    fn test_inline_disposition() {
+        assert_eq!(
+            inline_disposition("scan 1.pdf"),
+            "inline; filename=\"scan 1.pdf\"; filename*=UTF-8''scan%201.pdf"
+        );
+        assert_eq!(
+            inline_disposition("re\"ç.pdf"),
+            "inline; filename=\"re__.pdf\"; filename*=UTF-8''re%22%C3%A7.pdf"
+        );
+    }
+
+    #[test]
+    fn test_stream_response_partial() {
+        let stream = DocumentStream {
+            total_size: 100,
+            range: Some(ByteRange { start: 10, end: 19 }),
+            body: stream::once(async { Ok(Bytes::from_static(b"0123456789")) }).boxed(),
+        };
+        let response = stream_response(stream, "application/pdf");
+
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "10");
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 10-19/100");
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "application/pdf");
+    }
+}
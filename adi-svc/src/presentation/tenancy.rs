@@ -0,0 +1,108 @@
+/// Tenant resolution for incoming requests
+///
+/// With `TENANT_API_KEYS` configured, every API key belongs to exactly one
+/// tenant and requests without a known key are refused. Without it the service
+/// trusts the `X-Tenant-Id` header, and callers that send neither share the
+/// default tenant.
+
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::domain::TenantId;
+use super::audit::presented_key;
+
+/// Header (and gRPC metadata key) naming the caller's tenant
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+/// Why a request could not be assigned a tenant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TenantRejection {
+    /// Keys are configured but the request presented none, or an unknown one
+    Unauthenticated,
+    /// The tenant header names a different tenant than the key belongs to
+    Mismatch,
+    /// The tenant header is not a valid tenant ID
+    Invalid(String),
+}
+
+impl fmt::Display for TenantRejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unauthenticated => write!(f, "A valid API key is required"),
+            Self::Mismatch => write!(f, "API key does not belong to the requested tenant"),
+            Self::Invalid(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+/// Maps request credentials to a tenant
+#[derive(Debug, Clone, Default)]
+pub struct TenantResolver {
+    /// Tenants by SHA-256 of their API key, so lookups don't compare raw keys
+    keys: HashMap<[u8; 32], TenantId>,
+}
+
+impl TenantResolver {
+    /// Resolver for `(api key, tenant)` pairs; empty means header-based tenancy
+    pub fn new(keys: impl IntoIterator<Item = (String, TenantId)>) -> Self {
+        Self {
+            keys: keys
+                .into_iter()
+                .map(|(key, tenant)| (Sha256::digest(key.as_bytes()).into(), tenant))
+                .collect(),
+        }
+    }
+    
+    /// Whether tenants come from API keys rather than the header
+    pub fn requires_key(&self) -> bool {
+        !self.keys.is_empty()
+    }
+    
+    /// Tenant for a request's API key, bearer token and tenant header
+    pub fn resolve(
+        &self,
+        api_key: Option<&str>,
+        authorization: Option<&str>,
+        tenant_header: Option<&str>,
+    ) -> Result<TenantId, TenantRejection> {
+        let requested = tenant_header
+            .map(str::trim)
+            .filter(|tenant| !tenant.is_empty())
+            .map(|tenant| TenantId::new(tenant).map_err(|e| TenantRejection::Invalid(e.to_string())))
+            .transpose()?;
+        
+        if !self.requires_key() {
+            return Ok(requested.unwrap_or_default());
+        }
+        
+        let tenant = presented_key(api_key, authorization)
+            .and_then(|key| self.keys.get(&<[u8; 32]>::from(Sha256::digest(key.as_bytes()))))
+            .ok_or(TenantRejection::Unauthenticated)?;
+        match requested {
+            Some(requested) if requested != *tenant => Err(TenantRejection::Mismatch),
+            _ => Ok(tenant.clone()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_tenant() {
+        let open = TenantResolver::default();
+        assert_eq!(open.resolve(None, None, None).unwrap(), TenantId::default());
+        assert_eq!(open.resolve(None, None, Some("acme")).unwrap().as_str(), "acme");
+        assert!(matches!(open.resolve(None, None, Some("../acme")), Err(TenantRejection::Invalid(_))));
+
+        let acme = TenantId::new("acme").unwrap();
+        let keyed = TenantResolver::new([("secret".to_string(), acme.clone())]);
+        assert_eq!(keyed.resolve(Some("secret"), None, None).unwrap(), acme);
+        assert_eq!(keyed.resolve(None, Some("Bearer secret"), Some("acme")).unwrap(), acme);
+        assert_eq!(keyed.resolve(Some("other"), None, None), Err(TenantRejection::Unauthenticated));
+        assert_eq!(keyed.resolve(None, None, Some("acme")), Err(TenantRejection::Unauthenticated));
+        assert_eq!(keyed.resolve(Some("secret"), None, Some("globex")), Err(TenantRejection::Mismatch));
+    }
+}
@@ -0,0 +1,545 @@
+//! Shared harness for the end-to-end suites
+//!
+//! Stands up a wiremock server that mimics the Azure Document Intelligence
+//! REST API and wires the real adapters and application service against it.
+
+#![allow(dead_code)]
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use adi_svc::application::errors::ApplicationResult;
+use adi_svc::application::ports::{
+    AuditLogPort, DatasetWriterPort, DocumentIntelligencePort, DocumentStoragePort, EmbeddingProviderPort, EmbeddingStorePort, EventPublisherPort, HealthCheckPort, JobQueuePort, MalwareScanPort, OperationMetricsPort, OperationTrackerPort,
+    QuotaPort, ResultRevisionPort, SearchIndexPort, UsagePort, WorkQueuePort,
+};
+use adi_svc::application::services::DocumentIntelligenceService;
+use adi_svc::domain::{
+    cosine_similarity, ChunkMatch, ChunkingPolicy, EmbeddedChunk, JobRetryPolicy, LifecycleEvent, ModelRoutes, OutputTemplate, ResultRevision, ReviewPolicy, ScanVerdict, SearchDocument, TenantId,
+};
+use async_trait::async_trait;
+use adi_svc::infrastructure::{
+    AzureConfig, AzureDocumentIntelligenceAdapter, AzureMode, HttpClientConfig, InMemoryOperationTracker, KeywordClassifier,
+    LocalFileStorageAdapter, StorageConfig,
+};
+use serde_json::Value;
+use tempfile::TempDir;
+use wiremock::matchers::{method, path, path_regex};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+pub const API_VERSION: &str = "2024-02-29-preview";
+
+/// Prebuilt models with a fixture, as (fixture name, Azure model id, REST route)
+pub const PREBUILT_MODELS: &[(&str, &str, &str)] = &[
+    ("read", "prebuilt-read", "read"),
+    ("layout", "prebuilt-layout", "layout"),
+    ("invoice", "prebuilt-invoice", "invoice"),
+    ("receipt", "prebuilt-receipt", "receipt"),
+    ("id_document", "prebuilt-idDocument", "id-document"),
+    ("business_card", "prebuilt-businessCard", "business-card"),
+    ("w2", "prebuilt-tax.us.w2", "w2"),
+];
+
+/// Load an Azure `analyzeResults` response fixture
+pub fn fixture(name: &str) -> Value {
+    let path = format!("{}/fixtures/azure/{}.json", env!("CARGO_MANIFEST_DIR"), name);
+    let raw = std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path, e));
+    serde_json::from_str(&raw).unwrap()
+}
+
+/// Azure stub serving one operation per model
+pub struct AzureStub {
+    pub server: MockServer,
+}
+
+impl AzureStub {
+    pub async fn start() -> Self {
+        Self { server: MockServer::start().await }
+    }
+
+    pub fn config(&self) -> AzureConfig {
+        AzureConfig {
+            endpoint: self.server.uri(),
+            key: "test-key".into(),
+            secondary_key: None,
+            api_version: API_VERSION.to_string(),
+            model_api_versions: HashMap::new(),
+            request_timeout_secs: 300,
+            mode: AzureMode::Live,
+            mock_delay_ms: 0,
+            cassette_path: None,
+            http: HttpClientConfig::default(),
+            weight: 1,
+            resources: Vec::new(),
+        }
+    }
+
+    /// Accept analyze submissions for `model_id` and serve `fixture` as the
+    /// result of `result_id`, reporting "running" on the first poll
+    pub async fn mount_model(&self, model_id: &str, fixture_name: &str, result_id: &str) {
+        let operation_location = format!(
+            "{}/documentintelligence/documentModels/{}/analyzeResults/{}?api-version={}",
+            self.server.uri(),
+            model_id,
+            result_id,
+            API_VERSION
+        );
+
+        Mock::given(method("POST"))
+            .and(path(format!("/documentintelligence/documentModels/{}:analyze", model_id)))
+            .respond_with(
+                ResponseTemplate::new(202).insert_header("operation-location", operation_location.as_str()),
+            )
+            .mount(&self.server)
+            .await;
+
+        let result_path = format!(
+            "^/documentintelligence/documentModels/[^/]+/analyzeResults/{}$",
+            regex_escape(result_id)
+        );
+
+        Mock::given(method("GET"))
+            .and(path_regex(result_path.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "status": "running",
+                "createdDateTime": "2024-05-01T12:00:00Z",
+                "lastUpdatedDateTime": "2024-05-01T12:00:01Z"
+            })))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path_regex(result_path.as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(fixture(fixture_name)))
+            .with_priority(2)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Make analyze submissions for `model_id` take `delay` to be accepted
+    pub async fn delay_model(&self, model_id: &str, delay: std::time::Duration) {
+        Mock::given(method("POST"))
+            .and(path(format!("/documentintelligence/documentModels/{}:analyze", model_id)))
+            .respond_with(ResponseTemplate::new(202).set_delay(delay))
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Make analyze submissions for `model_id` fail with `status` and `AZURE_ERROR`,
+    /// optionally asking for a `Retry-After`
+    pub async fn fail_model(&self, model_id: &str, status: u16, retry_after: Option<&str>) {
+        let mut response = ResponseTemplate::new(status)
+            .set_body_json(azure_error())
+            .insert_header("apim-request-id", AZURE_REQUEST_ID);
+        if let Some(retry_after) = retry_after {
+            response = response.insert_header("retry-after", retry_after);
+        }
+        Mock::given(method("POST"))
+            .and(path(format!("/documentintelligence/documentModels/{}:analyze", model_id)))
+            .respond_with(response)
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+    
+    /// Accept analyze submissions for `model_id` as `result_id`, which Azure then fails with `AZURE_ERROR`
+    pub async fn fail_operation(&self, model_id: &str, result_id: &str) {
+        let operation_location = format!(
+            "{}/documentintelligence/documentModels/{}/analyzeResults/{}?api-version={}",
+            self.server.uri(),
+            model_id,
+            result_id,
+            API_VERSION
+        );
+        Mock::given(method("POST"))
+            .and(path(format!("/documentintelligence/documentModels/{}:analyze", model_id)))
+            .respond_with(
+                ResponseTemplate::new(202).insert_header("operation-location", operation_location.as_str()),
+            )
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+        let mut failed = azure_error();
+        failed["status"] = "failed".into();
+        Mock::given(method("GET"))
+            .and(path_regex(format!("/analyzeResults/{}$", regex_escape(result_id)).as_str()))
+            .respond_with(ResponseTemplate::new(200).set_body_json(failed))
+            .with_priority(1)
+            .mount(&self.server)
+            .await;
+    }
+    
+    /// Mount every prebuilt model, using the fixture name as result id
+    pub async fn mount_all(&self) {
+        for (fixture_name, model_id, _) in PREBUILT_MODELS {
+            self.mount_model(model_id, fixture_name, &result_id(fixture_name)).await;
+        }
+    }
+}
+
+/// `apim-request-id` the stub sends with errors
+pub const AZURE_REQUEST_ID: &str = "stub-request-1";
+
+/// Error body the stub fails calls and operations with
+pub fn azure_error() -> Value {
+    serde_json::json!({
+        "error": {
+            "code": "InvalidRequest",
+            "message": "Invalid request.",
+            "innererror": {
+                "code": "InvalidContent",
+                "message": "The file is corrupted or format is unsupported.",
+                "innererror": { "code": "InvalidContentLength", "message": "The input is too small." }
+            }
+        }
+    })
+}
+
+/// Result id the stub assigns to a fixture's operation
+pub fn result_id(fixture_name: &str) -> String {
+    format!("result-{}", fixture_name.replace('_', "-"))
+}
+
+fn regex_escape(s: &str) -> String {
+    s.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c.to_string() } else { format!("\\{}", c) })
+        .collect()
+}
+
+/// Publisher that keeps events in memory for assertions
+#[derive(Default)]
+pub struct RecordingPublisher {
+    pub events: std::sync::Mutex<Vec<LifecycleEvent>>,
+}
+
+#[async_trait]
+impl EventPublisherPort for RecordingPublisher {
+    async fn publish(&self, event: &LifecycleEvent) -> ApplicationResult<()> {
+        self.events.lock().unwrap().push(event.clone());
+        Ok(())
+    }
+}
+
+/// Revisions in memory, where another reviewer stores the first correction's revision number first
+#[derive(Default)]
+pub struct RacingRevisions {
+    pub inner: InMemoryOperationTracker,
+    raced: std::sync::atomic::AtomicBool,
+}
+
+#[async_trait]
+impl ResultRevisionPort for RacingRevisions {
+    async fn store_revision(&self, revision: &ResultRevision) -> ApplicationResult<()> {
+        if revision.revision > 0 && !self.raced.swap(true, std::sync::atomic::Ordering::SeqCst) {
+            let rival = ResultRevision { reviewer: Some("rival".to_string()), ..revision.clone() };
+            self.inner.store_revision(&rival).await?;
+        }
+        self.inner.store_revision(revision).await
+    }
+
+    async fn list_revisions(&self, operation_id: &str) -> ApplicationResult<Vec<ResultRevision>> {
+        self.inner.list_revisions(operation_id).await
+    }
+}
+
+/// Search index that keeps pushed documents in memory, in order
+#[derive(Default)]
+pub struct RecordingIndex {
+    pub documents: std::sync::Mutex<Vec<SearchDocument>>,
+}
+
+#[async_trait]
+impl SearchIndexPort for RecordingIndex {
+    async fn index(&self, document: &SearchDocument) -> ApplicationResult<()> {
+        self.documents.lock().unwrap().push(document.clone());
+        Ok(())
+    }
+}
+
+/// Embeddings that count words into a fixed number of hashed buckets
+///
+/// Texts sharing words get similar vectors, enough for retrieval tests.
+pub struct WordEmbeddings;
+
+impl WordEmbeddings {
+    pub const DIMENSIONS: usize = 64;
+
+    pub fn vector(text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0; Self::DIMENSIONS];
+        for word in text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty()) {
+            let bucket = word
+                .to_lowercase()
+                .bytes()
+                .fold(0usize, |hash, byte| hash.wrapping_mul(31).wrapping_add(byte as usize));
+            vector[bucket % Self::DIMENSIONS] += 1.0;
+        }
+        vector
+    }
+}
+
+#[async_trait]
+impl EmbeddingProviderPort for WordEmbeddings {
+    async fn embed(&self, texts: &[String]) -> ApplicationResult<Vec<Vec<f32>>> {
+        Ok(texts.iter().map(|text| Self::vector(text)).collect())
+    }
+}
+
+/// Embedding store that keeps stored chunks in memory, by operation
+#[derive(Default)]
+pub struct RecordingChunks {
+    pub chunks: std::sync::Mutex<std::collections::BTreeMap<String, (TenantId, Vec<EmbeddedChunk>)>>,
+}
+
+#[async_trait]
+impl EmbeddingStorePort for RecordingChunks {
+    async fn store_chunks(&self, operation_id: &str, tenant: &TenantId, chunks: &[EmbeddedChunk]) -> ApplicationResult<()> {
+        self.chunks.lock().unwrap().insert(operation_id.to_string(), (tenant.clone(), chunks.to_vec()));
+        Ok(())
+    }
+
+    async fn search_chunks(&self, tenant: &TenantId, embedding: &[f32], limit: u32) -> ApplicationResult<Vec<ChunkMatch>> {
+        let stored = self.chunks.lock().unwrap();
+        let mut matches: Vec<ChunkMatch> = stored
+            .iter()
+            .filter(|(_, (owner, _))| owner == tenant)
+            .flat_map(|(operation_id, (_, chunks))| {
+                chunks.iter().map(move |chunk| {
+                    let score = cosine_similarity(&chunk.embedding, embedding);
+                    ChunkMatch::new(operation_id, chunk.index, chunk.page_number, &chunk.text, score)
+                })
+            })
+            .collect();
+        matches.sort_by(|a, b| b.score.total_cmp(&a.score));
+        matches.truncate(limit as usize);
+        Ok(matches)
+    }
+}
+
+/// Dataset writer that keeps written files in memory, by path
+#[derive(Default)]
+pub struct MemoryDatasetWriter {
+    pub files: std::sync::Mutex<std::collections::BTreeMap<String, (String, bytes::Bytes)>>,
+}
+
+#[async_trait]
+impl DatasetWriterPort for MemoryDatasetWriter {
+    async fn put(&self, path: &str, content_type: &str, content: bytes::Bytes) -> ApplicationResult<()> {
+        self.files.lock().unwrap().insert(path.to_string(), (content_type.to_string(), content));
+        Ok(())
+    }
+}
+
+/// Content the fake scanner treats as infected
+pub const EICAR_MARKER: &[u8] = b"EICAR-STANDARD-ANTIVIRUS-TEST-FILE";
+
+/// Scanner that flags any document containing `EICAR_MARKER`
+pub struct FakeScanner;
+
+#[async_trait]
+impl MalwareScanPort for FakeScanner {
+    async fn scan(&self, data: &[u8]) -> ApplicationResult<ScanVerdict> {
+        if data.windows(EICAR_MARKER.len()).any(|w| w == EICAR_MARKER) {
+            Ok(ScanVerdict::Infected {
+                signature: "Eicar-Test-Signature".to_string(),
+            })
+        } else {
+            Ok(ScanVerdict::Clean)
+        }
+    }
+}
+
+/// Well-formed PDF with `pages` empty pages and an optional extra trailer entry
+pub fn minimal_pdf(pages: u32, trailer_extra: &str) -> Vec<u8> {
+    let kids: Vec<String> = (0..pages).map(|i| format!("{} 0 R", i + 3)).collect();
+    let mut pdf = format!(
+        "%PDF-1.7\n1 0 obj\n<< /Type /Catalog /Pages 2 0 R >>\nendobj\n\
+         2 0 obj\n<< /Type /Pages /Kids [{}] /Count {} >>\nendobj\n",
+        kids.join(" "),
+        pages
+    );
+    for i in 0..pages {
+        pdf.push_str(&format!("{} 0 obj\n<< /Type /Page /Parent 2 0 R >>\nendobj\n", i + 3));
+    }
+    let xref = pdf.len();
+    pdf.push_str(&format!(
+        "xref\n0 {}\ntrailer\n<< /Size {} /Root 1 0 R{} >>\nstartxref\n{}\n%%EOF\n",
+        pages + 3,
+        pages + 3,
+        trailer_extra,
+        xref
+    ));
+    pdf.into_bytes()
+}
+
+/// Application service wired against the stub
+pub struct Harness {
+    pub stub: AzureStub,
+    pub service: Arc<DocumentIntelligenceService>,
+    pub tracker: Arc<dyn OperationTrackerPort>,
+    _upload_dir: TempDir,
+}
+
+pub type Embeddings = (Arc<dyn EmbeddingProviderPort>, Arc<dyn EmbeddingStorePort>, ChunkingPolicy);
+
+/// Optional service features a harness enables
+#[derive(Default)]
+pub struct HarnessOptions {
+    pub work_queue: Option<Arc<dyn WorkQueuePort>>,
+    pub scanner: Option<Arc<dyn MalwareScanPort>>,
+    pub max_pdf_pages: Option<u32>,
+    pub raw_responses: bool,
+    pub audit_log: Option<Arc<dyn AuditLogPort>>,
+    pub usage_meter: Option<Arc<dyn UsagePort>>,
+    pub quotas: Option<Arc<dyn QuotaPort>>,
+    pub event_publisher: Option<Arc<dyn EventPublisherPort>>,
+    pub search_index: Option<Arc<dyn SearchIndexPort>>,
+    /// Provider, store and chunking of result embeddings
+    pub embeddings: Option<Embeddings>,
+    pub job_queue: Option<Arc<dyn JobQueuePort>>,
+    pub job_retry: Option<JobRetryPolicy>,
+    /// Used instead of the adapter talking to the stub
+    pub intelligence: Option<Arc<dyn DocumentIntelligencePort>>,
+    pub health_checks: Vec<Arc<dyn HealthCheckPort>>,
+    pub review_policy: Option<ReviewPolicy>,
+    pub revisions: Option<Arc<dyn ResultRevisionPort>>,
+    pub output_templates: Vec<OutputTemplate>,
+    pub operation_metrics: Option<Arc<dyn OperationMetricsPort>>,
+}
+
+impl Harness {
+    /// Harness backed by the in-memory tracker
+    pub async fn in_memory() -> Self {
+        Self::in_memory_with(HarnessOptions::default()).await
+    }
+
+    pub async fn with_tracker(tracker: Arc<dyn OperationTrackerPort>) -> Self {
+        Self::build(tracker, HarnessOptions::default()).await
+    }
+
+    /// In-memory harness that scans uploads with `FakeScanner`
+    pub async fn with_malware_scanner() -> Self {
+        Self::in_memory_with(HarnessOptions {
+            scanner: Some(Arc::new(FakeScanner)),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// In-memory harness that validates PDFs, accepting up to `max_pages` pages
+    pub async fn with_pdf_validation(max_pages: u32) -> Self {
+        Self::in_memory_with(HarnessOptions {
+            max_pdf_pages: Some(max_pages),
+            ..Default::default()
+        })
+        .await
+    }
+
+    /// In-memory harness (which also serves the work queues and revisions) with `options`
+    pub async fn in_memory_with(mut options: HarnessOptions) -> Self {
+        let tracker = Arc::new(InMemoryOperationTracker::new());
+        options.work_queue.get_or_insert_with(|| tracker.clone());
+        options.revisions.get_or_insert_with(|| tracker.clone());
+        Self::build(tracker, options).await
+    }
+
+    async fn build(tracker: Arc<dyn OperationTrackerPort>, options: HarnessOptions) -> Self {
+        let stub = AzureStub::start().await;
+        stub.mount_all().await;
+
+        let upload_dir = tempfile::tempdir().unwrap();
+        let storage: Arc<dyn DocumentStoragePort> = Arc::new(
+            LocalFileStorageAdapter::new(StorageConfig {
+                upload_dir: upload_dir.path().to_str().unwrap().to_string(),
+                max_upload_size_mb: 10,
+                url_signing_key: None,
+            })
+            .await
+            .unwrap(),
+        );
+
+        let intelligence = options
+            .intelligence
+            .unwrap_or_else(|| Arc::new(AzureDocumentIntelligenceAdapter::new(stub.config()).unwrap()));
+        let mut service = DocumentIntelligenceService::new(
+            intelligence,
+            Some(storage),
+            Some(tracker.clone()),
+        )
+        .with_model_routing(Arc::new(KeywordClassifier::new()), ModelRoutes::default());
+        #[cfg(feature = "images")]
+        {
+            service = service.with_image_preprocessor(Arc::new(adi_svc::infrastructure::ImagePreprocessor::new()));
+        }
+        #[cfg(feature = "pdf")]
+        {
+            service = service.with_redaction_renderer(Arc::new(adi_svc::infrastructure::DocumentRedactor::new()));
+        }
+        if let Some(work_queue) = options.work_queue {
+            service = service.with_work_queue(work_queue);
+        }
+        if let Some(scanner) = options.scanner {
+            service = service.with_malware_scanner(scanner);
+        }
+        if let Some(max_pages) = options.max_pdf_pages {
+            service = service.with_pdf_validation(Some(max_pages));
+        }
+        if options.raw_responses {
+            service = service.with_raw_responses();
+        }
+        if !options.output_templates.is_empty() {
+            service = service.with_output_templates(options.output_templates);
+        }
+        if let Some(audit_log) = options.audit_log {
+            service = service.with_audit_log(audit_log);
+        }
+        if let Some(usage_meter) = options.usage_meter {
+            service = service.with_usage_meter(usage_meter);
+        }
+        if let Some(quotas) = options.quotas {
+            service = service.with_quotas(quotas);
+        }
+        if let Some(event_publisher) = options.event_publisher {
+            service = service.with_event_publisher(event_publisher);
+        }
+        if let Some(search_index) = options.search_index {
+            service = service.with_search_index(search_index);
+        }
+        if let Some((provider, store, chunking)) = options.embeddings {
+            service = service.with_embeddings(provider, store, chunking);
+        }
+        if let Some(job_queue) = options.job_queue {
+            service = service.with_job_queue(job_queue);
+        }
+        if let Some(job_retry) = options.job_retry {
+            service = service.with_job_retry(job_retry);
+        }
+        if let Some(policy) = options.review_policy {
+            service = service.with_review_policy(policy);
+        }
+        if let Some(metrics) = options.operation_metrics {
+            service = service.with_operation_metrics(metrics);
+        }
+        if let Some(revisions) = options.revisions {
+            service = service.with_result_revisions(revisions);
+        }
+        for check in options.health_checks {
+            service = service.with_health_check(check);
+        }
+        let service = Arc::new(service);
+
+        Self {
+            stub,
+            service,
+            tracker,
+            _upload_dir: upload_dir,
+        }
+    }
+}
+
+/// Expected `content` of a fixture
+pub fn fixture_content(name: &str) -> String {
+    fixture(name)["analyzeResult"]["content"].as_str().unwrap().to_string()
+}
@@ -0,0 +1,567 @@
+//! gRPC end-to-end tests: submit → poll → result against the Azure stub
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use adi_svc::application::ports::AuditLogPort;
+use adi_svc::client::{AdiClient, ClientError, PollPolicy};
+use adi_svc::domain::{AuditOutcome, AuditQuery, DocumentSource, ModelType, TenantId};
+use adi_svc::generated as pb;
+use adi_svc::generated::document_intelligence_service_client::DocumentIntelligenceServiceClient;
+use adi_svc::generated::document_intelligence_service_server::DocumentIntelligenceServiceServer;
+use adi_svc::infrastructure::InMemoryOperationTracker;
+use adi_svc::presentation::auth::Authenticator;
+use adi_svc::presentation::tenancy::TenantResolver;
+use adi_svc::presentation::{GrpcAuthInterceptor, GrpcDocumentIntelligenceService, GrpcRequestIdLayer};
+use tokio_stream::wrappers::TcpListenerStream;
+use tonic::transport::{Channel, Server};
+
+use common::{fixture_content, result_id, Harness, HarnessOptions, AZURE_REQUEST_ID};
+
+async fn start_server(harness: &Harness) -> DocumentIntelligenceServiceClient<Channel> {
+    serve(GrpcDocumentIntelligenceService::new(harness.service.clone())).await
+}
+
+async fn serve(service: GrpcDocumentIntelligenceService) -> DocumentIntelligenceServiceClient<Channel> {
+    DocumentIntelligenceServiceClient::connect(listen(service).await)
+        .await
+        .unwrap()
+}
+
+/// Serve `service` on a free port, returning its URL
+async fn listen(service: GrpcDocumentIntelligenceService) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(
+        Server::builder()
+            .layer(GrpcRequestIdLayer)
+            .add_service(DocumentIntelligenceServiceServer::new(service))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    format!("http://{}", addr)
+}
+
+/// Serve `service` behind `GrpcAuthInterceptor`, returning its URL
+async fn listen_with_auth(service: GrpcDocumentIntelligenceService, auth: Authenticator) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    tokio::spawn(
+        Server::builder()
+            .layer(GrpcRequestIdLayer)
+            .add_service(DocumentIntelligenceServiceServer::with_interceptor(
+                service,
+                GrpcAuthInterceptor::new(auth),
+            ))
+            .serve_with_incoming(TcpListenerStream::new(listener)),
+    );
+
+    format!("http://{}", addr)
+}
+
+fn url_request() -> pb::AnalyzeRequest {
+    pb::AnalyzeRequest {
+        source: Some(pb::analyze_request::Source::DocumentUrl(
+            "https://example.com/doc.pdf".to_string(),
+        )),
+        options: None,
+    }
+}
+
+async fn poll_until_done(
+    client: &mut DocumentIntelligenceServiceClient<Channel>,
+    operation_id: &str,
+) -> pb::AnalyzeResponse {
+    for _ in 0..5 {
+        let response = client
+            .get_analysis_result(pb::GetAnalysisResultRequest {
+                operation_id: operation_id.to_string(),
+                field_mask: None,
+                min_confidence: 0.0,
+                normalize_coordinates: false,
+            })
+            .await
+            .unwrap()
+            .into_inner();
+        if response.status != pb::AnalysisStatus::StatusRunning as i32 {
+            return response;
+        }
+    }
+    panic!("operation {} never completed", operation_id);
+}
+
+#[tokio::test]
+async fn test_analyze_invoice_and_poll() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    let submitted = client.analyze_invoice(url_request()).await.unwrap().into_inner();
+    assert_eq!(submitted.operation_id, result_id("invoice"));
+    assert_eq!(submitted.status, pb::AnalysisStatus::StatusRunning as i32);
+
+    let done = poll_until_done(&mut client, &submitted.operation_id).await;
+    assert_eq!(done.status, pb::AnalysisStatus::StatusSucceeded as i32);
+
+    let result = done.result.unwrap();
+    assert_eq!(result.content, fixture_content("invoice"));
+    assert_eq!(result.documents[0].doc_type, "invoice");
+    assert!(result.documents[0].fields.contains_key("InvoiceTotal"));
+}
+
+#[tokio::test]
+async fn test_grpc_deadline_cancels_azure_call() {
+    let harness = Harness::in_memory().await;
+    harness.stub.delay_model("prebuilt-read", Duration::from_secs(30)).await;
+    let mut client = start_server(&harness).await;
+
+    let mut request = tonic::Request::new(url_request());
+    request.set_timeout(Duration::from_millis(200));
+    let started = std::time::Instant::now();
+    let status = client.analyze_read(request).await.unwrap_err();
+    // tonic may drop the handler at the deadline before it reports DEADLINE_EXCEEDED itself
+    assert!(
+        matches!(status.code(), tonic::Code::DeadlineExceeded | tonic::Code::Cancelled),
+        "{:?}",
+        status
+    );
+    assert!(started.elapsed() < Duration::from_secs(10));
+
+    let mut request = tonic::Request::new(url_request());
+    request.set_timeout(Duration::from_secs(30));
+    let submitted = client.analyze_layout(request).await.unwrap().into_inner();
+    assert_eq!(submitted.operation_id, result_id("layout"));
+}
+
+#[tokio::test]
+async fn test_error_status_mapping() {
+    let harness = Harness::in_memory().await;
+    harness.stub.fail_model("prebuilt-read", 429, Some("7")).await;
+    harness.stub.fail_model("prebuilt-layout", 503, None).await;
+    let mut client = start_server(&harness).await;
+
+    let status = client.analyze_read(url_request()).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+    assert_eq!(status.metadata().get("retry-after").unwrap(), "7");
+    let status = client.analyze_layout(url_request()).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::Unavailable);
+
+    let status = client
+        .get_analysis_result(pb::GetAnalysisResultRequest {
+            operation_id: "no-such-operation".to_string(),
+            field_mask: None,
+            min_confidence: 0.0,
+            normalize_coordinates: false,
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+    let request = pb::AnalyzeRequest {
+        source: Some(pb::analyze_request::Source::DocumentUrl(String::new())),
+        options: None,
+    };
+    let status = client.analyze_invoice(request).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_azure_error_details() {
+    let harness = Harness::in_memory().await;
+    harness.stub.fail_model("prebuilt-invoice", 400, None).await;
+    harness.stub.fail_operation("prebuilt-receipt", "result-failed").await;
+    let mut client = start_server(&harness).await;
+
+    let status = client.analyze_invoice(url_request()).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+    let error = <pb::Error as prost::Message>::decode(status.details()).unwrap();
+    assert_eq!(error.code, "InvalidRequest");
+    assert_eq!(error.details[0].code, "InvalidContentLength");
+    assert_eq!(error.details[1].code, "RequestId");
+    assert_eq!(error.details[1].message, AZURE_REQUEST_ID);
+
+    let submitted = client.analyze_receipt(url_request()).await.unwrap().into_inner();
+    let failed = poll_until_done(&mut client, &submitted.operation_id).await;
+    assert_eq!(failed.status, pb::AnalysisStatus::StatusFailed as i32);
+    let error = failed.error.unwrap();
+    assert_eq!(error.code, "InvalidRequest");
+    assert_eq!(error.message, "Invalid request.");
+}
+
+#[tokio::test]
+async fn test_request_id_metadata() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    let mut request = tonic::Request::new(url_request());
+    request.metadata_mut().insert("x-request-id", "support-1234".parse().unwrap());
+    let response = client.analyze_read(request).await.unwrap();
+    assert_eq!(response.metadata().get("x-request-id").unwrap(), "support-1234");
+
+    let forwarded = harness.stub.server.received_requests().await.unwrap();
+    let submission = forwarded.iter().find(|request| request.method.as_str() == "POST").unwrap();
+    assert_eq!(submission.headers.get("x-ms-client-request-id").unwrap(), "support-1234");
+
+    let status = client
+        .get_analysis_result(pb::GetAnalysisResultRequest {
+            operation_id: "missing".to_string(),
+            field_mask: None,
+            min_confidence: 0.0,
+            normalize_coordinates: false,
+        })
+        .await
+        .unwrap_err();
+    assert!(status.metadata().get("x-request-id").is_some());
+}
+
+#[tokio::test]
+async fn test_analyze_layout_returns_tables() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    let submitted = client.analyze_layout(url_request()).await.unwrap().into_inner();
+    let done = poll_until_done(&mut client, &submitted.operation_id).await;
+
+    let result = done.result.unwrap();
+    assert_eq!(result.tables.len(), 1);
+    assert_eq!(result.tables[0].cells.len(), 6);
+    assert_eq!(result.pages[0].words[0].polygon.as_ref().unwrap().points.len(), 4);
+}
+
+#[tokio::test]
+async fn test_result_field_mask() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    let submitted = client.analyze_layout(url_request()).await.unwrap().into_inner();
+    poll_until_done(&mut client, &submitted.operation_id).await;
+
+    let masked = |paths: &[&str]| pb::GetAnalysisResultRequest {
+        operation_id: submitted.operation_id.clone(),
+        field_mask: Some(prost_types::FieldMask {
+            paths: paths.iter().map(|path| path.to_string()).collect(),
+        }),
+        min_confidence: 0.0,
+        normalize_coordinates: false,
+    };
+
+    let result = client
+        .get_analysis_result(masked(&["result.tables", "content"]))
+        .await
+        .unwrap()
+        .into_inner()
+        .result
+        .unwrap();
+    assert_eq!(result.content, fixture_content("layout"));
+    assert_eq!(result.tables.len(), 1);
+    assert!(result.pages.is_empty());
+
+    let status = client.get_analysis_result(masked(&["result.words"])).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_result_min_confidence() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    let submitted = client.analyze_layout(url_request()).await.unwrap().into_inner();
+    let unfiltered = poll_until_done(&mut client, &submitted.operation_id).await;
+    assert!(unfiltered.filtered.is_none());
+
+    let request = |min_confidence: f32| pb::GetAnalysisResultRequest {
+        operation_id: submitted.operation_id.clone(),
+        field_mask: None,
+        min_confidence,
+        normalize_coordinates: false,
+    };
+    let response = client.get_analysis_result(request(1.0)).await.unwrap().into_inner();
+    let filtered = response.filtered.unwrap();
+    let words: usize = unfiltered.result.unwrap().pages.iter().map(|page| page.words.len()).sum();
+    assert_eq!(filtered.words as usize, words);
+    assert!(response.result.unwrap().pages.iter().all(|page| page.words.is_empty()));
+
+    let status = client.get_analysis_result(request(1.5)).await.unwrap_err();
+    assert_eq!(status.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_result_normalized_coordinates() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    let submitted = client.analyze_layout(url_request()).await.unwrap().into_inner();
+    let raw = poll_until_done(&mut client, &submitted.operation_id).await.result.unwrap();
+    let response = client
+        .get_analysis_result(pb::GetAnalysisResultRequest {
+            operation_id: submitted.operation_id.clone(),
+            field_mask: None,
+            min_confidence: 0.0,
+            normalize_coordinates: true,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    let normalized = response.result.unwrap();
+
+    let (raw_page, page) = (&raw.pages[0], &normalized.pages[0]);
+    assert_eq!((page.width, page.height, page.unit.as_str()), (1.0, 1.0, "normalized"));
+    let (raw_word, word) = (raw_page.words[0].polygon.as_ref().unwrap(), page.words[0].polygon.as_ref().unwrap());
+    assert!((word.points[0].x - raw_word.points[0].x / raw_page.width).abs() < 1e-6);
+    assert!((word.points[0].y - raw_word.points[0].y / raw_page.height).abs() < 1e-6);
+}
+
+#[tokio::test]
+async fn test_streaming_upload() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    let messages = vec![
+        pb::UploadRequest {
+            data: Some(pb::upload_request::Data::Metadata(pb::UploadMetadata {
+                filename: "scan.png".to_string(),
+                content_type: "image/png".to_string(),
+                model_type: "read".to_string(),
+                expected_sha256: String::new(),
+            })),
+        },
+        pb::UploadRequest {
+            data: Some(pb::upload_request::Data::Chunk(vec![0x89, b'P', b'N', b'G'].into())),
+        },
+        pb::UploadRequest {
+            data: Some(pb::upload_request::Data::Chunk(vec![0x0d, 0x0a, 0x1a, 0x0a].into())),
+        },
+    ];
+
+    let submitted = client
+        .upload_and_analyze(tokio_stream::iter(messages))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(submitted.operation_id, result_id("read"));
+
+    let operation = harness
+        .tracker
+        .get_operation(&submitted.operation_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(operation.filename.as_deref(), Some("scan.png"));
+    assert_eq!(operation.content_type.as_deref(), Some("image/png"));
+
+    let done = poll_until_done(&mut client, &submitted.operation_id).await;
+    assert_eq!(done.result.unwrap().content, fixture_content("read"));
+}
+
+fn upload_messages(expected_sha256: &str, chunks: &[&[u8]]) -> Vec<pb::UploadRequest> {
+    let metadata = pb::UploadRequest {
+        data: Some(pb::upload_request::Data::Metadata(pb::UploadMetadata {
+            filename: "doc.pdf".to_string(),
+            content_type: "application/pdf".to_string(),
+            model_type: "read".to_string(),
+            expected_sha256: expected_sha256.to_string(),
+        })),
+    };
+    std::iter::once(metadata)
+        .chain(chunks.iter().map(|chunk| pb::UploadRequest {
+            data: Some(pb::upload_request::Data::Chunk(bytes::Bytes::copy_from_slice(chunk))),
+        }))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_streaming_upload_checksum() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    // SHA-256 of "%PDF-1.4 test"
+    let digest = "d663640088750cf16276d623c2588d7233f2b84b45f4b2e20832f47b16aa5618";
+    let submitted = client
+        .upload_and_analyze(tokio_stream::iter(upload_messages(
+            &digest.to_uppercase(),
+            &[b"%PDF-1.4", b" test"],
+        )))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(submitted.operation_id, result_id("read"));
+    assert!(submitted.error.is_none());
+
+    let mismatch = client
+        .upload_and_analyze(tokio_stream::iter(upload_messages(digest, &[b"%PDF-1.5 test"])))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(mismatch.status, pb::AnalysisStatus::StatusFailed as i32);
+    assert!(mismatch.operation_id.is_empty());
+    let error = mismatch.error.unwrap();
+    assert_eq!(error.code, "ChecksumMismatch");
+    assert_eq!(error.target, "expected_sha256");
+
+    let malformed = client
+        .upload_and_analyze(tokio_stream::iter(upload_messages("abc", &[b"%PDF-1.4 test"])))
+        .await
+        .unwrap_err();
+    assert_eq!(malformed.code(), tonic::Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_streaming_upload_size_limit() {
+    let harness = Harness::in_memory().await;
+    let service = GrpcDocumentIntelligenceService::new(harness.service.clone()).with_max_upload_bytes(16);
+    let mut client = serve(service).await;
+
+    let status = client
+        .upload_and_analyze(tokio_stream::iter(upload_messages("", &[b"%PDF-1.4 0", &[0u8; 10]])))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+    let accepted = client
+        .upload_and_analyze(tokio_stream::iter(upload_messages("", &[b"%PDF-1.4", &[0u8; 8]])))
+        .await
+        .unwrap()
+        .into_inner();
+    assert_eq!(accepted.operation_id, result_id("read"));
+}
+
+#[tokio::test]
+async fn test_download_document() {
+    let harness = Harness::in_memory().await;
+    let mut client = start_server(&harness).await;
+
+    let submitted = client
+        .upload_and_analyze(tokio_stream::iter(upload_messages("", &[b"%PDF-1.4", b" test"])))
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!submitted.document_id.is_empty());
+
+    let mut stream = client
+        .download_document(pb::DownloadDocumentRequest {
+            document_id: submitted.document_id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let mut info = None;
+    let mut content = Vec::new();
+    while let Some(message) = stream.message().await.unwrap() {
+        match message.data {
+            Some(pb::download_document_response::Data::Info(i)) => info = Some(i),
+            Some(pb::download_document_response::Data::Chunk(chunk)) => content.extend(chunk),
+            None => {}
+        }
+    }
+    let info = info.unwrap();
+    assert_eq!(info.document_id, submitted.document_id);
+    assert_eq!(info.filename, "doc.pdf");
+    assert_eq!(info.content_type, "application/pdf");
+    assert_eq!(info.size, 13);
+    assert_eq!(content, b"%PDF-1.4 test");
+
+    let status = client
+        .download_document(pb::DownloadDocumentRequest {
+            document_id: "missing.pdf".to_string(),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), tonic::Code::NotFound);
+}
+
+#[tokio::test]
+async fn test_mutating_rpcs_are_audited() {
+    let audit_log = Arc::new(InMemoryOperationTracker::new());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        audit_log: Some(audit_log.clone()),
+        ..Default::default()
+    })
+    .await;
+    let mut client = start_server(&harness).await;
+
+    let mut request = tonic::Request::new(url_request());
+    request.metadata_mut().insert("x-api-key", "client-key".parse().unwrap());
+    client.analyze_invoice(request).await.unwrap();
+    let missing_source = pb::AnalyzeRequest { source: None, options: None };
+    assert!(client.analyze_read(missing_source).await.is_err());
+    poll_until_done(&mut client, &result_id("invoice")).await;
+
+    let entries = audit_log.query(&AuditQuery::default()).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].action, "grpc AnalyzeRead");
+    assert_eq!(entries[0].outcome, AuditOutcome::Failure);
+    assert_eq!(entries[0].status, "InvalidArgument");
+    assert_eq!(entries[1].action, "grpc AnalyzeInvoice");
+    assert_eq!(entries[1].outcome, AuditOutcome::Success);
+    assert_eq!(entries[1].model.as_deref(), Some("prebuilt-invoice"));
+    assert_eq!(entries[1].operation_id, Some(result_id("invoice")));
+    assert!(entries[1].principal.starts_with("key:"));
+}
+
+#[tokio::test]
+async fn test_client_analyze_and_wait() {
+    let harness = Harness::in_memory().await;
+    let url = listen(GrpcDocumentIntelligenceService::new(harness.service.clone())).await;
+    let client = AdiClient::connect(url)
+        .await
+        .unwrap()
+        .with_tenant(&TenantId::new("sdk").unwrap())
+        .unwrap()
+        .with_poll_policy(PollPolicy {
+            initial_delay: std::time::Duration::from_millis(5),
+            ..Default::default()
+        });
+
+    let document = DocumentSource::Url("https://example.com/doc.pdf".to_string());
+    let result = client.analyze_and_wait(document.clone(), ModelType::Layout).await.unwrap();
+    assert_eq!(result.content, fixture_content("layout"));
+
+    // The operation belongs to the client's tenant
+    let tenant = TenantId::new("sdk").unwrap();
+    let operation_id = result_id("layout");
+    harness.service.get_analysis_result(&tenant, &operation_id).await.unwrap();
+    assert_eq!(client.wait(&operation_id).await.unwrap().content, fixture_content("layout"));
+
+    let unknown = client.get_result("no-such-operation").await.unwrap_err();
+    assert!(matches!(unknown, ClientError::Status(status) if status.code() == tonic::Code::NotFound));
+    assert!(matches!(
+        client.analyze(document, ModelType::Custom).await.unwrap_err(),
+        ClientError::InvalidRequest(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_auth_interceptor_assigns_principals() {
+    let harness = Harness::in_memory().await;
+    let acme = TenantId::new("acme").unwrap();
+    let auth = Authenticator::new(TenantResolver::new([("acme-key".to_string(), acme.clone())]))
+        .with_admin_key(Some("admin-key"));
+    let url = listen_with_auth(GrpcDocumentIntelligenceService::new(harness.service.clone()), auth).await;
+    let document = DocumentSource::Url("https://example.com/doc.pdf".to_string());
+    let status_code = |error: ClientError| match error {
+        ClientError::Status(status) => status.code(),
+        other => panic!("unexpected error: {}", other),
+    };
+
+    let anonymous = AdiClient::connect(url.clone()).await.unwrap();
+    let rejected = anonymous.analyze(document.clone(), ModelType::Read).await.unwrap_err();
+    assert_eq!(status_code(rejected), tonic::Code::Unauthenticated);
+
+    let analyst = AdiClient::connect(url.clone()).await.unwrap().with_api_key("acme-key").unwrap();
+    let submitted = analyst.analyze(document.clone(), ModelType::Invoice).await.unwrap();
+    harness.service.get_analysis_result(&acme, &submitted.operation_id).await.unwrap();
+
+    let mismatched = analyst.clone().with_tenant(&TenantId::new("globex").unwrap()).unwrap();
+    let denied = mismatched.get_result(&submitted.operation_id).await.unwrap_err();
+    assert_eq!(status_code(denied), tonic::Code::PermissionDenied);
+
+    // Admins act for the tenant they name
+    let admin = AdiClient::connect(url).await.unwrap().with_api_key("admin-key").unwrap();
+    let not_found = admin.get_result(&submitted.operation_id).await.unwrap_err();
+    assert_eq!(status_code(not_found), tonic::Code::NotFound);
+    let admin = admin.with_tenant(&acme).unwrap();
+    assert_eq!(admin.get_result(&submitted.operation_id).await.unwrap().operation_id, submitted.operation_id);
+}
@@ -0,0 +1,336 @@
+//! Ingestion end-to-end tests: documents picked up from drop locations
+
+mod common;
+
+use std::sync::Arc;
+
+use adi_svc::domain::{ModelType, OperationListQuery, TenantId};
+use adi_svc::infrastructure::{
+    BlobIngestConfig, BlobIngestor, FolderWatchConfig, FolderWatcher, InMemoryOperationTracker, ManagedIdentityCredential,
+};
+use serde_json::Value;
+use wiremock::matchers::{body_string_contains, method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, ResponseTemplate};
+
+use common::{fixture_content, minimal_pdf, Harness};
+
+#[tokio::test]
+async fn test_folder_watch() {
+    let harness = Harness::in_memory().await;
+    let inbox = tempfile::tempdir().unwrap();
+    let config = FolderWatchConfig {
+        dir: Some(inbox.path().to_str().unwrap().to_string()),
+        model: ModelType::Read,
+        tenant: TenantId::new("scanners").unwrap(),
+        processed_dir: None,
+        failed_dir: None,
+        write_results: true,
+        poll_interval_ms: 10,
+        settle_ms: 0,
+    };
+    let watcher = FolderWatcher::from_config(harness.service.clone(), &config).unwrap();
+
+    std::fs::write(inbox.path().join("scan.pdf"), minimal_pdf(1, "")).unwrap();
+    std::fs::write(inbox.path().join("notes.txt"), "not a document").unwrap();
+    // Hidden and half-written files are left for later
+    std::fs::write(inbox.path().join(".scan2.pdf"), minimal_pdf(1, "")).unwrap();
+    std::fs::write(inbox.path().join("scan3.pdf.part"), minimal_pdf(1, "")).unwrap();
+
+    assert_eq!(watcher.scan().await.unwrap(), 2);
+    let processed = inbox.path().join("processed");
+    assert!(processed.join("scan.pdf").exists());
+    assert!(!inbox.path().join("scan.pdf").exists());
+    let sidecar: Value =
+        serde_json::from_slice(&std::fs::read(processed.join("scan.pdf.json")).unwrap()).unwrap();
+    assert_eq!(sidecar["operation"]["status"], "succeeded");
+    assert_eq!(sidecar["operation"]["tenant_id"], "scanners");
+    assert_eq!(sidecar["result"]["content"], fixture_content("read"));
+
+    let failed = inbox.path().join("failed");
+    assert!(failed.join("notes.txt").exists());
+    assert!(!std::fs::read_to_string(failed.join("notes.txt.error.txt")).unwrap().is_empty());
+    assert!(inbox.path().join(".scan2.pdf").exists());
+    assert!(inbox.path().join("scan3.pdf.part").exists());
+
+    // A second file with a taken name keeps both
+    std::fs::write(inbox.path().join("scan.pdf"), minimal_pdf(1, "")).unwrap();
+    assert_eq!(watcher.scan().await.unwrap(), 1);
+    assert_eq!(std::fs::read_dir(&processed).unwrap().count(), 4);
+    assert_eq!(watcher.scan().await.unwrap(), 0);
+
+    // Without a folder there is nothing to watch
+    let disabled = FolderWatchConfig { dir: None, ..config };
+    assert!(FolderWatcher::from_config(harness.service.clone(), &disabled).is_none());
+}
+
+fn blob_listing(blobs: &[(&str, &str, usize)], next_marker: &str) -> String {
+    let blobs: String = blobs
+        .iter()
+        .map(|(name, etag, size)| {
+            format!(
+                "<Blob><Name>{}</Name><Properties><Etag>{}</Etag><Content-Length>{}</Content-Length></Properties>\
+                 <Tags><TagSet><Tag><Key>source</Key><Value>scanner-1</Value></Tag></TagSet></Tags></Blob>",
+                name, etag, size
+            )
+        })
+        .collect();
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?><EnumerationResults><Blobs>{}</Blobs>\
+         <NextMarker>{}</NextMarker></EnumerationResults>",
+        blobs, next_marker
+    )
+}
+
+#[tokio::test]
+async fn test_blob_ingest() {
+    let harness = Harness::in_memory().await;
+    let server = &harness.stub.server;
+    let pdf = minimal_pdf(1, "");
+
+    Mock::given(method("GET"))
+        .and(path("/scans"))
+        .and(query_param("comp", "list"))
+        .and(query_param("prefix", "inbox/"))
+        .and(query_param("sig", "secret"))
+        .and(query_param_is_missing("marker"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(blob_listing(
+            &[("inbox/a.pdf", "0x1", pdf.len()), ("inbox/folder/", "0x2", 0)],
+            "page-2",
+        )))
+        .mount(server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/scans"))
+        .and(query_param("comp", "list"))
+        .and(query_param("marker", "page-2"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(blob_listing(
+            &[("inbox/b c.pdf", "0x3", pdf.len())],
+            "",
+        )))
+        .mount(server)
+        .await;
+    for blob in ["/scans/inbox/a.pdf", "/scans/inbox/b%20c.pdf"] {
+        Mock::given(method("GET"))
+            .and(path(blob))
+            .and(query_param("sig", "secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(pdf.clone()))
+            .mount(server)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path(blob))
+            .and(query_param("comp", "tags"))
+            .and(body_string_contains("<Key>source</Key><Value>scanner-1</Value>"))
+            .and(body_string_contains("<Key>adi_operation_id</Key>"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    let config = BlobIngestConfig {
+        container_url: Some(format!("{}/scans", server.uri())),
+        prefix: "inbox/".to_string(),
+        sas_token: Some("?sig=secret".into()),
+        model: ModelType::Read,
+        tenant: TenantId::new("storage").unwrap(),
+        interval_secs: 60,
+    };
+    let credential = Arc::new(ManagedIdentityCredential::from_env(None));
+    let ingestor = BlobIngestor::from_config(
+        harness.service.clone(),
+        Arc::new(InMemoryOperationTracker::new()),
+        &config,
+        credential.clone(),
+    )
+    .unwrap()
+    .unwrap();
+
+    // Empty blobs (folder markers) are skipped
+    assert_eq!(ingestor.poll().await.unwrap(), 2);
+    let tagged: Vec<String> = server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.method.as_str() == "PUT")
+        .map(|request| String::from_utf8(request.body).unwrap())
+        .collect();
+    assert_eq!(tagged.len(), 2);
+    let operations = harness
+        .service
+        .list_operations(
+            &config.tenant,
+            &OperationListQuery {
+                limit: 10,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+    // The stub hands every read submission the same result id
+    let tag = format!("<Value>{}</Value>", operations[0].operation_id);
+    assert!(tagged.iter().all(|body| body.contains(&tag)));
+
+    // Blobs are not analyzed twice
+    assert_eq!(ingestor.poll().await.unwrap(), 0);
+
+    let disabled = BlobIngestConfig { container_url: None, ..config };
+    let ledger = Arc::new(InMemoryOperationTracker::new());
+    assert!(BlobIngestor::from_config(harness.service.clone(), ledger, &disabled, credential)
+        .unwrap()
+        .is_none());
+}
+
+#[cfg(feature = "imap")]
+mod imap {
+    use super::*;
+    use std::sync::Mutex;
+
+    use adi_svc::domain::LifecycleEventKind;
+    use adi_svc::infrastructure::{ImapIngestConfig, ImapIngestor};
+    use base64::{Engine as _, engine::general_purpose};
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::TcpListener;
+
+    use common::{HarnessOptions, RecordingPublisher};
+
+    /// Mailbox served by `FakeImapServer`, as (uid, raw message, seen)
+    type Mailbox = Arc<Mutex<Vec<(u32, Vec<u8>, bool)>>>;
+
+    /// IMAP server holding one mailbox, recording the commands it receives
+    struct FakeImapServer {
+        port: u16,
+        mailbox: Mailbox,
+        commands: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl FakeImapServer {
+        async fn start(messages: Vec<(u32, Vec<u8>)>) -> Self {
+            let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let port = listener.local_addr().unwrap().port();
+            let mailbox: Mailbox = Arc::new(Mutex::new(
+                messages.into_iter().map(|(uid, raw)| (uid, raw, false)).collect(),
+            ));
+            let commands = Arc::new(Mutex::new(Vec::new()));
+            let (served_mailbox, served_commands) = (mailbox.clone(), commands.clone());
+            tokio::spawn(async move {
+                while let Ok((socket, _)) = listener.accept().await {
+                    let (reader, mut writer) = socket.into_split();
+                    let mut lines = BufReader::new(reader).lines();
+                    writer.write_all(b"* OK fake IMAP ready\r\n").await.unwrap();
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let (tag, command) = line.split_once(' ').unwrap();
+                        served_commands.lock().unwrap().push(command.to_string());
+                        let words: Vec<&str> = command.split(' ').collect();
+                        let mut reply = Vec::new();
+                        match words.as_slice() {
+                            ["SELECT", ..] => reply.extend_from_slice(b"* OK [UIDVALIDITY 42] UIDs valid\r\n"),
+                            ["UID", "SEARCH", "UNSEEN"] => {
+                                let unseen: Vec<String> = served_mailbox
+                                    .lock()
+                                    .unwrap()
+                                    .iter()
+                                    .filter(|(_, _, seen)| !seen)
+                                    .map(|(uid, _, _)| uid.to_string())
+                                    .collect();
+                                reply.extend_from_slice(format!("* SEARCH {}\r\n", unseen.join(" ")).as_bytes());
+                            }
+                            ["UID", "FETCH", uid, "BODY.PEEK[]"] => {
+                                let mailbox = served_mailbox.lock().unwrap();
+                                let (uid, raw, _) = mailbox.iter().find(|(id, _, _)| id.to_string() == *uid).unwrap();
+                                reply.extend_from_slice(format!("* 1 FETCH (UID {} BODY[] {{{}}}\r\n", uid, raw.len()).as_bytes());
+                                reply.extend_from_slice(raw);
+                                reply.extend_from_slice(b")\r\n");
+                            }
+                            ["UID", "STORE", uid, ..] => {
+                                for (id, _, seen) in served_mailbox.lock().unwrap().iter_mut() {
+                                    if id.to_string() == *uid {
+                                        *seen = true;
+                                    }
+                                }
+                            }
+                            ["LOGOUT"] => reply.extend_from_slice(b"* BYE logging out\r\n"),
+                            _ => {}
+                        }
+                        reply.extend_from_slice(format!("{} OK done\r\n", tag).as_bytes());
+                        writer.write_all(&reply).await.unwrap();
+                    }
+                }
+            });
+            Self { port, mailbox, commands }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_imap_ingest() {
+        let publisher = Arc::new(RecordingPublisher::default());
+        let harness = Harness::in_memory_with(HarnessOptions {
+            event_publisher: Some(publisher.clone()),
+            ..Default::default()
+        })
+        .await;
+
+        let attachment = general_purpose::STANDARD.encode(minimal_pdf(1, ""));
+        let invoice = format!(
+            "From: billing@example.com\r\nSubject: Invoice 1001\r\n\
+             Content-Type: multipart/mixed; boundary=\"b1\"\r\n\r\n\
+             --b1\r\nContent-Type: text/plain\r\n\r\nInvoice attached.\r\n\
+             --b1\r\nContent-Type: application/pdf\r\n\
+             Content-Disposition: attachment; filename=\"invoice-1001.pdf\"\r\n\
+             Content-Transfer-Encoding: base64\r\n\r\n{}\r\n--b1--\r\n",
+            attachment
+        );
+        let chatter = b"From: someone@example.com\r\nSubject: Hello\r\n\r\nNo documents here.\r\n".to_vec();
+        let server = FakeImapServer::start(vec![(7, invoice.into_bytes()), (8, chatter)]).await;
+
+        let config = ImapIngestConfig {
+            host: Some("127.0.0.1".to_string()),
+            port: server.port,
+            tls: false,
+            username: "invoices@example.com".to_string(),
+            password: "p\"w".into(),
+            mailbox: "INBOX".to_string(),
+            model: ModelType::Read,
+            tenant: TenantId::new("mailroom").unwrap(),
+            interval_secs: 60,
+            result_poll_ms: 10,
+        };
+        let ledger = Arc::new(InMemoryOperationTracker::new());
+        let ingestor = ImapIngestor::from_config(harness.service.clone(), ledger.clone(), &config)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(ingestor.poll().await.unwrap(), 1);
+        assert!(server.mailbox.lock().unwrap().iter().all(|(_, _, seen)| *seen));
+        let commands = server.commands.lock().unwrap().clone();
+        assert_eq!(commands[0], r#"LOGIN "invoices@example.com" "p\"w""#);
+        assert!(commands.contains(&"UID FETCH 7 BODY.PEEK[]".to_string()));
+
+        // The result was polled to completion, announcing it downstream
+        let events = publisher.events.lock().unwrap().clone();
+        let succeeded = events
+            .iter()
+            .find(|event| event.event_type == LifecycleEventKind::Succeeded)
+            .unwrap();
+        assert_eq!(succeeded.tenant_id.as_str(), "mailroom");
+        assert!(succeeded.summary.is_some());
+        let (operation, _) = harness
+            .service
+            .get_analysis_result(&config.tenant, &succeeded.operation_id)
+            .await
+            .unwrap();
+        assert_eq!(operation.filename.as_deref(), Some("invoice-1001.pdf"));
+
+        // Seen messages are left alone
+        assert_eq!(ingestor.poll().await.unwrap(), 0);
+        let fetches = server.commands.lock().unwrap().iter().filter(|command| command.contains("FETCH")).count();
+        assert_eq!(fetches, 2);
+
+        let disabled = ImapIngestConfig { host: None, ..config.clone() };
+        assert!(ImapIngestor::from_config(harness.service.clone(), ledger.clone(), &disabled)
+            .unwrap()
+            .is_none());
+        let anonymous = ImapIngestConfig { username: String::new(), ..config };
+        assert!(ImapIngestor::from_config(harness.service.clone(), ledger, &anonymous).is_err());
+    }
+}
@@ -0,0 +1,338 @@
+//! End-to-end tests against a real PostgreSQL started with testcontainers
+//!
+//! Requires Docker. Run with: cargo test --test postgres_e2e -- --ignored
+
+mod common;
+
+use std::sync::Arc;
+
+use base64::Engine;
+
+use adi_svc::application::errors::ApplicationError;
+use adi_svc::application::ports::{AuditLogPort, ExportJobPort, HealthCheckPort, JobQueuePort, OperationTrackerPort, PipelineRunPort, QuotaPort, ResultRevisionPort, UsagePort, WorkQueuePort};
+use adi_svc::domain::{
+    AnalysisJob, AnalysisOperation, AuditEntry, AuditOutcome, AuditQuery, ExportJob, FieldQuery, ModelType, OperationEventKind, OperationListQuery, OperationStatsGroup, OperationStatsQuery,
+    JobStatus, OperationStatus, parse_pipelines, PipelineRun, Quota, QuotaPeriod, ResultFields, ResultRevision, TenantId, UsageQuery, WorkQueue,
+};
+use adi_svc::infrastructure::{DatabaseConfig, EncryptionConfig, EnvelopeCipher, PostgresOperationTracker, Secret};
+use testcontainers_modules::postgres::Postgres;
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+
+use common::{fixture_content, result_id, Harness, PREBUILT_MODELS};
+
+#[tokio::test]
+#[ignore] // Requires Docker
+async fn test_submit_poll_and_persist_all_models() {
+    let container = Postgres::default().start().await.unwrap();
+    let database_url = format!(
+        "postgresql://postgres:postgres@{}:{}/postgres",
+        container.get_host().await.unwrap(),
+        container.get_host_port_ipv4(5432).await.unwrap()
+    );
+
+    // Migrations run on connect and are safe to re-apply
+    let postgres = PostgresOperationTracker::new(&DatabaseConfig {
+        statement_timeout_ms: Some(30_000),
+        ..DatabaseConfig::new(database_url.clone())
+    })
+    .await
+    .unwrap();
+    postgres.migrate().await.unwrap();
+    postgres.check_health().await.unwrap();
+
+    let postgres = Arc::new(postgres);
+    let tracker: Arc<dyn OperationTrackerPort> = postgres.clone();
+    let harness = Harness::with_tracker(tracker.clone()).await;
+    let tenant = TenantId::new("acme").unwrap();
+
+    for (fixture_name, model_id, _) in PREBUILT_MODELS {
+        let operation = harness
+            .service
+            .analyze_document(adi_svc::domain::AnalyzeDocumentRequest {
+                source: adi_svc::domain::DocumentSource::Url("https://example.com/doc.pdf".to_string()),
+                model_type: adi_svc::domain::ModelType::from_string(model_id).unwrap(),
+                options: Default::default(),
+                metadata: None,
+                tenant_id: tenant.clone(),
+            })
+            .await
+            .unwrap();
+        assert_eq!(operation.operation_id, result_id(fixture_name));
+
+        harness.service.get_analysis_result(&tenant, &operation.operation_id).await.unwrap();
+        let (done, result) = harness
+            .service
+            .get_analysis_result(&tenant, &operation.operation_id)
+            .await
+            .unwrap();
+        assert_eq!(done.status, OperationStatus::Succeeded);
+        assert_eq!(result.unwrap().content, fixture_content(fixture_name));
+        assert_eq!(done.tenant_id, tenant);
+        assert!(done.page_count.is_some_and(|pages| pages > 0));
+        assert!(harness
+            .service
+            .get_analysis_result(&TenantId::default(), &operation.operation_id)
+            .await
+            .is_err());
+
+        let kinds: Vec<OperationEventKind> = tracker
+            .list_events(&operation.operation_id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|event| event.kind)
+            .collect();
+        assert_eq!(
+            kinds,
+            [OperationEventKind::Submitted, OperationEventKind::Running, OperationEventKind::Succeeded]
+        );
+
+        let stored = tracker.get_result(&operation.operation_id).await.unwrap().unwrap();
+        assert_eq!(stored.content, fixture_content(fixture_name));
+
+        let fields = ResultFields::from_names(["tables"]).unwrap();
+        let projected = tracker
+            .get_result_fields(&operation.operation_id, &fields)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(projected.content.is_empty() && projected.pages.is_empty());
+        assert_eq!(projected.tables.len(), stored.tables.len());
+
+        let page = tracker.get_result_page(&operation.operation_id, 1).await.unwrap().unwrap();
+        assert_eq!(page.lines.len(), stored.pages[0].lines.len());
+        assert!(tracker.get_result_page(&operation.operation_id, 999).await.unwrap().is_none());
+
+        let raw = serde_json::json!({ "status": "succeeded", "analyzeResult": { "modelId": model_id } });
+        tracker.store_raw_response(&operation.operation_id, &raw).await.unwrap();
+        assert_eq!(tracker.get_raw_response(&operation.operation_id).await.unwrap(), Some(raw));
+
+        let query = FieldQuery::new(None, Some(0.5)).unwrap();
+        let matches = tracker.query_result_fields(&operation.operation_id, &query).await.unwrap().unwrap();
+        assert_eq!(
+            serde_json::to_value(&matches).unwrap(),
+            serde_json::to_value(stored.query_fields(&query)).unwrap()
+        );
+    }
+
+    let listed = tracker.list_operations(&tenant, &OperationListQuery::default()).await.unwrap();
+    assert_eq!(listed.len(), PREBUILT_MODELS.len());
+    assert!(listed.windows(2).all(|pair| pair[0].created_at >= pair[1].created_at));
+    let succeeded = OperationListQuery {
+        status: Some(OperationStatus::Succeeded),
+        limit: 2,
+        ..Default::default()
+    };
+    assert_eq!(tracker.list_operations(&tenant, &succeeded).await.unwrap().len(), 2);
+    let mut paged = Vec::new();
+    let mut page_query = OperationListQuery { limit: 2, ..Default::default() };
+    loop {
+        let page = tracker.list_operations(&tenant, &page_query).await.unwrap();
+        paged.extend(page.iter().map(|op| op.operation_id.clone()));
+        match page.last() {
+            Some(last) if page.len() == 2 => {
+                page_query.before = Some(last.created_at);
+                page_query.before_id = Some(last.operation_id.clone());
+            }
+            _ => break,
+        }
+    }
+    assert_eq!(paged, listed.iter().map(|op| op.operation_id.clone()).collect::<Vec<_>>());
+    let created_at: chrono::DateTime<chrono::Utc> = "2020-01-01T00:00:00Z".parse().unwrap();
+    let mut tied = Vec::new();
+    for _ in 0..3 {
+        let operation = AnalysisOperation {
+            created_at,
+            tenant_id: TenantId::new("tied").unwrap(),
+            ..AnalysisOperation::new(ModelType::Read)
+        };
+        tracker.store_operation(&operation).await.unwrap();
+        tied.push(operation.operation_id);
+    }
+    tied.sort_by(|a, b| b.cmp(a));
+    let (from, to) = (created_at, created_at + chrono::Duration::days(1));
+    let first = tracker.list_operations_created(from, to, None, None, 2).await.unwrap();
+    let last = first.last().unwrap();
+    let second = tracker.list_operations_created(from, to, Some(last.created_at), Some(&last.operation_id), 2).await.unwrap();
+    assert_eq!(first.iter().chain(&second).map(|op| op.operation_id.clone()).collect::<Vec<_>>(), tied);
+
+    // Two reviewers storing the same revision at once: one wins, the other is told to reload
+    let contested = &listed[0].operation_id;
+    let result = tracker.get_result(contested).await.unwrap().unwrap();
+    let original = ResultRevision::original(contested, result);
+    let (first, second) = tokio::join!(postgres.store_revision(&original), postgres.store_revision(&original));
+    assert!(first.is_ok() != second.is_ok(), "{:?} {:?}", first, second);
+    let conflict = first.err().or(second.err());
+    assert!(matches!(conflict, Some(ApplicationError::RevisionConflict { revision: 0, .. })), "{:?}", conflict);
+    assert!(tracker
+        .list_operations(&TenantId::default(), &OperationListQuery::default())
+        .await
+        .unwrap()
+        .is_empty());
+
+    // Only exports without progress for the stale period are reported
+    let mut orphaned = ExportJob::new(tenant.clone(), Default::default());
+    orphaned.updated_at = chrono::Utc::now() - chrono::Duration::hours(1);
+    postgres.create_export(&orphaned).await.unwrap();
+    postgres.create_export(&ExportJob::new(tenant.clone(), Default::default())).await.unwrap();
+    let stale = postgres.stale_exports(chrono::Duration::minutes(15)).await.unwrap();
+    assert_eq!(stale.iter().map(|job| job.export_id.as_str()).collect::<Vec<_>>(), [orphaned.export_id.as_str()]);
+    let definitions = parse_pipelines(r#"{"read": [{"step": "analyze"}]}"#).unwrap();
+    let mut orphaned = PipelineRun::new(&definitions[0], tenant.clone());
+    orphaned.updated_at = chrono::Utc::now() - chrono::Duration::hours(1);
+    postgres.create_run(&orphaned).await.unwrap();
+    postgres.create_run(&PipelineRun::new(&definitions[0], tenant.clone())).await.unwrap();
+    let stale = postgres.stale_runs(chrono::Duration::minutes(15)).await.unwrap();
+    assert_eq!(stale.iter().map(|run| run.run_id.as_str()).collect::<Vec<_>>(), [orphaned.run_id.as_str()]);
+
+    // Work queues lease, extend and complete only the caller's operations
+    let (lease, other) = (chrono::Duration::minutes(5), TenantId::default());
+    assert!(postgres.claim(&other, WorkQueue::Export, "w", lease, 10).await.unwrap().is_empty());
+    let leases = postgres.claim(&tenant, WorkQueue::Export, "w", lease, 1).await.unwrap();
+    let leased = &leases[0].operation_id;
+    assert!(postgres.heartbeat(&other, WorkQueue::Export, leased, "w", lease).await.unwrap().is_none());
+    assert!(!postgres.complete(&other, WorkQueue::Export, leased, "w").await.unwrap());
+    assert!(!postgres.release(&other, WorkQueue::Export, leased, "w").await.unwrap());
+    assert!(postgres.heartbeat(&tenant, WorkQueue::Export, leased, "w", lease).await.unwrap().is_some());
+    assert!(postgres.complete(&tenant, WorkQueue::Export, leased, "w").await.unwrap());
+
+    let by_model = OperationStatsQuery { group_by: OperationStatsGroup::Model, ..Default::default() };
+    let stats = tracker.operation_stats(&tenant, &by_model).await.unwrap();
+    assert_eq!(stats.len(), PREBUILT_MODELS.len());
+    assert!(stats.iter().all(|group| group.key.starts_with("prebuilt-") && group.succeeded == 1));
+    assert!(stats.iter().all(|group| group.median_completion_secs.is_some() && group.failure_ratio == Some(0.0)));
+
+    // Deleted operations drop out of lists and stats until restored, and are purged after the cutoff
+    let deleted = harness.service.delete_operation(&tenant, &listed[0].operation_id).await.unwrap();
+    assert!(tracker.get_operation(&deleted.operation_id).await.unwrap().unwrap().deleted_at.is_some());
+    assert_eq!(tracker.list_operations(&tenant, &OperationListQuery::default()).await.unwrap().len(), PREBUILT_MODELS.len() - 1);
+    let with_deleted = OperationListQuery { include_deleted: true, ..Default::default() };
+    assert_eq!(tracker.list_operations(&tenant, &with_deleted).await.unwrap().len(), PREBUILT_MODELS.len());
+    assert_eq!(tracker.operation_stats(&tenant, &by_model).await.unwrap().len(), PREBUILT_MODELS.len() - 1);
+    harness.service.restore_operation(&tenant, &deleted.operation_id).await.unwrap();
+    assert!(tracker.get_operation(&deleted.operation_id).await.unwrap().unwrap().deleted_at.is_none());
+    harness.service.delete_operation(&tenant, &deleted.operation_id).await.unwrap();
+    let purged = tracker.purge_deleted_operations(chrono::Utc::now()).await.unwrap();
+    assert_eq!(purged.iter().map(|op| op.operation_id.as_str()).collect::<Vec<_>>(), [deleted.operation_id.as_str()]);
+    assert!(tracker.get_result(&deleted.operation_id).await.unwrap().is_none());
+
+    // Documents of deleted operations are only found when asked for
+    let uploaded = AnalysisOperation {
+        tenant_id: tenant.clone(),
+        document_id: Some("doc-deleted".to_string()),
+        ..AnalysisOperation::new(ModelType::Read)
+    };
+    tracker.store_operation(&uploaded).await.unwrap();
+    tracker.set_deleted(&uploaded.operation_id, Some(chrono::Utc::now())).await.unwrap();
+    // A stale copy written back, as by a poll in flight, keeps the deletion
+    tracker.update_operation(&uploaded).await.unwrap();
+    assert!(tracker.find_operation_by_document(&tenant, "doc-deleted", false).await.unwrap().is_none());
+    assert!(tracker.find_operation_by_document(&tenant, "doc-deleted", true).await.unwrap().is_some());
+
+    // With encryption on, results and raw responses leave nothing readable in their columns
+    let encrypted_with = |keys: &'static [(&'static str, u8)]| {
+        let database_url = database_url.clone();
+        async move {
+            let config = EncryptionConfig {
+                keys: keys.iter().map(|(id, byte)| (id.to_string(), Secret::from(base64::engine::general_purpose::STANDARD.encode([*byte; 32])))).collect(),
+                key_vault_key_url: None,
+            };
+            PostgresOperationTracker::new(&DatabaseConfig::new(database_url))
+                .await
+                .unwrap()
+                .with_encryption(EnvelopeCipher::from_keys(&config).unwrap().unwrap())
+        }
+    };
+    let encrypted = encrypted_with(&[("k1", 1)]).await;
+    let plaintext = tracker.get_result(&listed[1].operation_id).await.unwrap().unwrap();
+    assert_eq!(encrypted.get_result(&listed[1].operation_id).await.unwrap().unwrap().content, plaintext.content);
+    let operation = AnalysisOperation { tenant_id: tenant.clone(), ..AnalysisOperation::new(ModelType::Invoice) };
+    encrypted.store_operation(&operation).await.unwrap();
+    encrypted.store_result(&operation.operation_id, &plaintext).await.unwrap();
+    let raw = serde_json::json!({ "status": "succeeded" });
+    encrypted.store_raw_response(&operation.operation_id, &raw).await.unwrap();
+    let pool = sqlx::PgPool::connect(&database_url).await.unwrap();
+    let (content, pages, raw_column, key_id): (String, Option<serde_json::Value>, Option<serde_json::Value>, Option<String>) =
+        sqlx::query_as("SELECT content, pages_data, raw_response, encryption_key_id FROM results WHERE operation_id = $1")
+            .bind(&operation.operation_id)
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+    assert_eq!((content.as_str(), pages, raw_column, key_id.as_deref()), ("", None, None, Some("k1")));
+    let opened = encrypted.get_result(&operation.operation_id).await.unwrap().unwrap();
+    assert_eq!(serde_json::to_value(&opened).unwrap(), serde_json::to_value(&plaintext).unwrap());
+    let page = encrypted.get_result_page(&operation.operation_id, 1).await.unwrap().unwrap();
+    assert_eq!(page.lines.len(), plaintext.pages[0].lines.len());
+    let query = FieldQuery::new(None, Some(0.5)).unwrap();
+    assert_eq!(
+        encrypted.query_result_fields(&operation.operation_id, &query).await.unwrap().unwrap().len(),
+        plaintext.query_fields(&query).len()
+    );
+    assert!(tracker.get_result(&operation.operation_id).await.is_err(), "unreadable without a key");
+
+    // Rotating to k2 re-wraps the result and raw response, after which k1 can go
+    let rotated = encrypted_with(&[("k2", 2), ("k1", 1)]).await;
+    assert_eq!(rotated.rewrap_keys().await.unwrap(), 2);
+    assert_eq!(rotated.rewrap_keys().await.unwrap(), 0);
+    let retired = encrypted_with(&[("k2", 2)]).await;
+    assert_eq!(retired.get_result(&operation.operation_id).await.unwrap().unwrap().content, plaintext.content);
+    assert_eq!(retired.get_raw_response(&operation.operation_id).await.unwrap(), Some(raw));
+
+    let entry = AuditEntry::new("key:0123456789abcdef", "POST /api/v1/analyze/read", AuditOutcome::Success, "200");
+    postgres.record(&entry).await.unwrap();
+    postgres
+        .record(&AuditEntry::new("anonymous", "DELETE /api/v1/uploads/:upload_id", AuditOutcome::Failure, "404"))
+        .await
+        .unwrap();
+    let query = AuditQuery {
+        principal: Some(entry.principal.clone()),
+        ..Default::default()
+    };
+    let entries = postgres.query(&query).await.unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].action, entry.action);
+    assert_eq!(postgres.query(&AuditQuery { limit: 1, ..Default::default() }).await.unwrap()[0].status, "404");
+
+    let day = chrono::NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+    postgres.record_usage(&tenant, day, ModelType::Invoice, 3).await.unwrap();
+    postgres.record_usage(&tenant, day, ModelType::Invoice, 2).await.unwrap();
+    postgres.record_usage(&TenantId::default(), day, ModelType::Read, 1).await.unwrap();
+    let query = UsageQuery {
+        tenant: Some(tenant.clone()),
+        from: day,
+        to: day,
+    };
+    let usage = postgres.usage(&query).await.unwrap();
+    assert_eq!(usage.len(), 1);
+    assert_eq!((usage[0].operations, usage[0].pages), (2, 5));
+    assert_eq!(usage[0].model, ModelType::Invoice);
+    assert_eq!(postgres.usage(&UsageQuery { tenant: None, ..query }).await.unwrap().len(), 2);
+
+    let daily = Quota::new(tenant.clone(), QuotaPeriod::Daily, Some(100), None).unwrap();
+    postgres.set_quota(&daily).await.unwrap();
+    postgres.set_quota(&Quota { max_pages: Some(5), ..daily.clone() }).await.unwrap();
+    postgres
+        .set_quota(&Quota::new(tenant.clone(), QuotaPeriod::Monthly, None, Some(1000)).unwrap())
+        .await
+        .unwrap();
+    let quotas = postgres.list_quotas(Some(&tenant)).await.unwrap();
+    assert_eq!(quotas.len(), 2);
+    assert_eq!((quotas[0].period, quotas[0].max_pages), (QuotaPeriod::Daily, Some(5)));
+    assert!(postgres.list_quotas(Some(&TenantId::default())).await.unwrap().is_empty());
+    assert!(postgres.delete_quota(&tenant, QuotaPeriod::Daily).await.unwrap());
+    assert!(!postgres.delete_quota(&tenant, QuotaPeriod::Daily).await.unwrap());
+
+    let mut job = AnalysisJob::new(tenant.clone(), ModelType::Invoice, Default::default());
+    job.document_url = Some("https://example.com/doc.pdf".to_string());
+    postgres.enqueue_job(&job).await.unwrap();
+    let stale_after = chrono::Duration::minutes(5);
+    let mut claimed = postgres.claim_job(stale_after).await.unwrap().unwrap();
+    assert_eq!((claimed.job_id.as_str(), claimed.status, claimed.attempts), (job.job_id.as_str(), JobStatus::Running, 1));
+    assert_eq!(claimed.document_url, job.document_url);
+    assert!(postgres.claim_job(stale_after).await.unwrap().is_none());
+    claimed.submitted("op-1");
+    postgres.update_job(&claimed).await.unwrap();
+    let stored = postgres.get_job(&job.job_id).await.unwrap().unwrap();
+    assert_eq!((stored.status, stored.operation_id.as_deref()), (JobStatus::Submitted, Some("op-1")));
+    assert_eq!(postgres.job_for_operation("op-1").await.unwrap().unwrap().job_id, job.job_id);
+}
@@ -0,0 +1,3172 @@
+//! REST end-to-end tests: submit → poll → result against the Azure stub
+
+mod common;
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use adi_svc::presentation::{
+    create_rest_router, create_rest_router_with_limits, create_rest_router_with_options, BodyLimits,
+    PublicUrls, RestOptions,
+};
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use serde_json::{json, Value};
+use tower::ServiceExt;
+use tracing_subscriber::EnvFilter;
+use wiremock::matchers::{header, method, path, path_regex};
+use wiremock::{Mock, ResponseTemplate};
+
+#[cfg(feature = "analytics")]
+use adi_svc::application::analytics::AnalyticsExportService;
+use adi_svc::application::exports::ExportService;
+use adi_svc::application::ports::{ExportJobPort, PipelineRunPort};
+use adi_svc::application::pipelines::PipelineService;
+use adi_svc::application::training::TrainingExportService;
+use adi_svc::domain::{
+    parse_pipelines, AnalysisOperation, ChunkingPolicy, ExportFilter, ExportJob, JobPriority, JobRetryPolicy, LifecycleEventKind, ModelType, PipelineRun, Quota, QuotaPeriod, ReviewPolicy, ReviewState, ScanVerdict, TenantId,
+};
+use adi_svc::infrastructure::{
+    AzureDocumentIntelligenceAdapter, AzureResourceConfig, HttpWebhookSender, InMemoryOperationTracker, PrometheusOperationMetrics, TaskSupervisor, VcrAdapter,
+};
+use adi_svc::infrastructure::LogLevelControl;
+use adi_svc::presentation::priority::PriorityPolicy;
+use adi_svc::presentation::tenancy::TenantResolver;
+use common::{
+    fixture, fixture_content, minimal_pdf, result_id, AzureStub, Harness, HarnessOptions, MemoryDatasetWriter, RacingRevisions,
+    RecordingChunks, RecordingIndex, RecordingPublisher, WordEmbeddings, API_VERSION, AZURE_REQUEST_ID, EICAR_MARKER, PREBUILT_MODELS,
+};
+
+async fn send(router: &Router, request: Request<Body>) -> (StatusCode, Value) {
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let value = if body.is_empty() {
+        Value::Null
+    } else {
+        serde_json::from_slice(&body).unwrap_or(Value::Null)
+    };
+    (status, value)
+}
+
+fn post_json(uri: &str, body: Value) -> Request<Body> {
+    Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(Body::from(body.to_string()))
+        .unwrap()
+}
+
+fn get(uri: &str) -> Request<Body> {
+    Request::builder().uri(uri).body(Body::empty()).unwrap()
+}
+
+fn bodyless(method: Method, uri: &str) -> Request<Body> {
+    Request::builder().method(method).uri(uri).body(Body::empty()).unwrap()
+}
+
+#[tokio::test]
+async fn test_health() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (status, body) = send(&router, get("/health")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "healthy");
+}
+
+#[tokio::test]
+async fn test_version() {
+    let harness = Harness::in_memory().await;
+    let options = RestOptions {
+        azure_api_version: Some(API_VERSION.to_string()),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    let (status, body) = send(&router, get("/version")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["build"]["version"], env!("CARGO_PKG_VERSION"));
+    assert!(body["build"]["git_sha"].is_string());
+    assert!(body["build"]["build_timestamp"].is_string());
+    assert!(body["build"]["features"].as_array().unwrap().contains(&json!("server")));
+    assert_eq!(body["azure_api_version"], API_VERSION);
+}
+
+#[tokio::test]
+async fn test_health_checks_dependencies() {
+    let stub = AzureStub::start().await;
+    let reachable = Arc::new(AzureDocumentIntelligenceAdapter::new(stub.config()).unwrap());
+    let mut config = stub.config();
+    config.endpoint = "http://127.0.0.1:1".to_string();
+    let unreachable = Arc::new(AzureDocumentIntelligenceAdapter::new(config).unwrap());
+
+    let harness = Harness::in_memory_with(HarnessOptions {
+        health_checks: vec![reachable.clone()],
+        ..Default::default()
+    })
+    .await;
+    let (status, body) = send(&create_rest_router(harness.service.clone()), get("/health")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["dependencies"][0]["name"], "azure");
+    assert_eq!(body["dependencies"][0]["healthy"], true);
+    assert!(body["dependencies"][0]["latency_ms"].is_u64());
+
+    let harness = Harness::in_memory_with(HarnessOptions {
+        health_checks: vec![reachable, unreachable],
+        ..Default::default()
+    })
+    .await;
+    let (status, body) = send(&create_rest_router(harness.service.clone()), get("/health")).await;
+    assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(body["status"], "unhealthy");
+    assert_eq!(body["dependencies"][0]["healthy"], true);
+    assert_eq!(body["dependencies"][1]["healthy"], false);
+    assert!(body["dependencies"][1]["error"].is_string());
+}
+
+#[tokio::test]
+async fn test_analyze_and_poll_every_prebuilt_model() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    for (fixture_name, _, route) in PREBUILT_MODELS {
+        let (status, submitted) = send(
+            &router,
+            post_json(
+                &format!("/api/v1/analyze/{}", route),
+                json!({ "document_url": "https://example.com/doc.pdf" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK, "submit {}", route);
+        assert_eq!(submitted["operation_id"], result_id(fixture_name));
+        assert_eq!(submitted["status"], "running");
+
+        let results_uri = format!("/api/v1/results/{}", result_id(fixture_name));
+
+        let (_, first_poll) = send(&router, get(&results_uri)).await;
+        assert_eq!(first_poll["status"], "running", "first poll {}", route);
+        assert!(first_poll.get("result").is_none());
+
+        let (status, second_poll) = send(&router, get(&results_uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(second_poll["status"], "succeeded", "second poll {}", route);
+        assert_eq!(second_poll["result"]["content"], fixture_content(fixture_name));
+        assert_eq!(second_poll["result"]["pages"][0]["page_number"], 1);
+    }
+}
+
+#[tokio::test]
+async fn test_results_are_served_from_tracker_once_terminal() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/layout", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+
+    let results_uri = format!("/api/v1/results/{}", result_id("layout"));
+    send(&router, get(&results_uri)).await;
+    send(&router, get(&results_uri)).await;
+
+    let polls_before = harness.stub.server.received_requests().await.unwrap().len();
+    let (_, cached) = send(&router, get(&results_uri)).await;
+    let polls_after = harness.stub.server.received_requests().await.unwrap().len();
+
+    assert_eq!(cached["status"], "succeeded");
+    assert_eq!(cached["result"]["tables"][0]["cell_count"], 6);
+    assert_eq!(polls_before, polls_after);
+}
+
+#[tokio::test]
+async fn test_multipart_upload_records_document_metadata() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let boundary = "adi-boundary";
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"invoice.pdf\"\r\n\
+         Content-Type: application/pdf\r\n\r\n%PDF-1.4 test\r\n--{b}--\r\n",
+        b = boundary
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/invoice")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(body))
+        .unwrap();
+
+    let (status, submitted) = send(&router, request).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(submitted["filename"], "invoice.pdf");
+    assert_eq!(submitted["content_type"], "application/pdf");
+
+    let operation = harness
+        .tracker
+        .get_operation(&result_id("invoice"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(operation.document_id.is_some());
+}
+
+#[tokio::test]
+async fn test_azure_calls_go_through_configured_proxy() {
+    let stub = AzureStub::start().await;
+    stub.mount_all().await;
+    let mut config = stub.config();
+    config.endpoint = "http://document-intelligence.invalid".to_string();
+    config.http.proxy_url = Some(stub.server.uri().into());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        intelligence: Some(Arc::new(AzureDocumentIntelligenceAdapter::new(config.clone()).unwrap())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (status, _) = send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/inv.pdf" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let proxied = stub.server.received_requests().await.unwrap();
+    assert_eq!(proxied.len(), 1);
+    assert_eq!(proxied[0].headers.get("host").unwrap(), "document-intelligence.invalid");
+
+    config.http.ca_cert_path = Some("/nonexistent/ca.pem".to_string());
+    assert!(AzureDocumentIntelligenceAdapter::new(config).is_err());
+}
+
+#[tokio::test]
+async fn test_submissions_fail_over_to_another_resource() {
+    let primary = AzureStub::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(503))
+        .mount(&primary.server)
+        .await;
+    let backup = AzureStub::start().await;
+    backup.mount_all().await;
+    let mut config = primary.config();
+    config.resources = vec![AzureResourceConfig {
+        name: "backup".to_string(),
+        endpoint: backup.server.uri(),
+        key: "backup-key".into(),
+        secondary_key: None,
+        weight: 0,
+    }];
+    let harness = Harness::in_memory_with(HarnessOptions {
+        intelligence: Some(Arc::new(AzureDocumentIntelligenceAdapter::new(config).unwrap())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    for _ in 0..2 {
+        let (status, submitted) = send(
+            &router,
+            post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/inv.pdf" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(submitted["operation_id"], format!("backup.{}", result_id("invoice")));
+    }
+    // The failed resource is passed over while it cools down
+    assert_eq!(primary.server.received_requests().await.unwrap().len(), 1);
+
+    let results_uri = format!("/api/v1/results/backup.{}", result_id("invoice"));
+    send(&router, get(&results_uri)).await;
+    let (_, polled) = send(&router, get(&results_uri)).await;
+    assert_eq!(polled["status"], "succeeded");
+    assert_eq!(polled["result"]["content"], fixture_content("invoice"));
+    let backup_requests = backup.server.received_requests().await.unwrap();
+    assert!(backup_requests.iter().all(|request| request.headers.get("ocp-apim-subscription-key").unwrap() == "backup-key"));
+    assert_eq!(primary.server.received_requests().await.unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn test_rejected_key_falls_back_to_secondary_key() {
+    let stub = AzureStub::start().await;
+    Mock::given(method("POST"))
+        .and(header("ocp-apim-subscription-key", "test-key"))
+        .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+            "error": { "code": "401", "message": "Access denied due to invalid subscription key." }
+        })))
+        .with_priority(1)
+        .mount(&stub.server)
+        .await;
+    stub.mount_all().await;
+    let mut config = stub.config();
+    config.secondary_key = Some("second-key".into());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        intelligence: Some(Arc::new(AzureDocumentIntelligenceAdapter::new(config).unwrap())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    for _ in 0..2 {
+        let (status, _) = send(
+            &router,
+            post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/inv.pdf" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    // Once the secondary key works it is sent first
+    let keys: Vec<String> = stub
+        .server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| request.headers.get("ocp-apim-subscription-key").unwrap().to_str().unwrap().to_string())
+        .collect();
+    assert_eq!(keys, ["test-key", "second-key", "second-key"]);
+    assert!(adi_svc::infrastructure::metrics::metrics()
+        .render()
+        .contains("adi_azure_key_fallbacks_total{rejected=\"primary\",resource=\"primary\"} 1"));
+}
+
+#[tokio::test]
+async fn test_per_model_api_versions() {
+    let stub = AzureStub::start().await;
+    stub.mount_all().await;
+    let mut config = stub.config();
+    config.model_api_versions.insert("prebuilt-invoice".to_string(), "2023-07-31".to_string());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        intelligence: Some(Arc::new(AzureDocumentIntelligenceAdapter::new(config).unwrap())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    for route in ["invoice", "read"] {
+        send(
+            &router,
+            post_json(&format!("/api/v1/analyze/{}", route), json!({ "document_url": "https://example.com/doc.pdf" })),
+        )
+        .await;
+    }
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    send(&router, get(&results_uri)).await;
+    let (_, polled) = send(&router, get(&results_uri)).await;
+    assert_eq!(polled["status"], "succeeded");
+
+    let calls: Vec<(String, String)> = stub
+        .server
+        .received_requests()
+        .await
+        .unwrap()
+        .iter()
+        .map(|request| {
+            let version = request.url.query_pairs().find(|(name, _)| name == "api-version").unwrap().1;
+            (request.url.path().to_string(), version.to_string())
+        })
+        .collect();
+    let invoice_result = format!("/documentintelligence/documentModels/prebuilt-invoice/analyzeResults/{}", result_id("invoice"));
+    assert_eq!(
+        calls,
+        [
+            ("/documentintelligence/documentModels/prebuilt-invoice:analyze".to_string(), "2023-07-31".to_string()),
+            ("/documentintelligence/documentModels/prebuilt-read:analyze".to_string(), API_VERSION.to_string()),
+            (invoice_result.clone(), "2023-07-31".to_string()),
+            (invoice_result, "2023-07-31".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_page_ranges_are_validated_and_normalized() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (status, body) = send(
+        &router,
+        post_json(
+            "/api/v1/analyze/read",
+            json!({ "document_url": "https://example.com/doc.pdf", "options": { "pages": ["1-3,2"] } }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("'1-3' overlaps '2'"), "body: {}", body);
+
+    let (status, _) = send(
+        &router,
+        post_json(
+            "/api/v1/analyze/read",
+            json!({ "document_url": "https://example.com/doc.pdf", "options": { "pages": ["7-", " 1-3 ,5"] } }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let requests = harness.stub.server.received_requests().await.unwrap();
+    assert_eq!(requests.len(), 1);
+    let pages = requests[0].url.query_pairs().find(|(name, _)| name == "pages").unwrap().1.to_string();
+    assert_eq!(pages, "7-,1-3,5");
+}
+
+#[tokio::test]
+async fn test_string_index_type_is_forwarded_and_recorded() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (status, _) = send(
+        &router,
+        post_json(
+            "/api/v1/analyze/read",
+            json!({
+                "document_url": "https://example.com/doc.pdf",
+                "options": { "string_index_type": "utf16CodeUnit" }
+            }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let requests = harness.stub.server.received_requests().await.unwrap();
+    let query = requests[0].url.query().unwrap_or_default().to_string();
+    assert!(query.contains("stringIndexType=utf16CodeUnit"), "query: {}", query);
+
+    let results_uri = format!("/api/v1/results/{}", result_id("read"));
+    send(&router, get(&results_uri)).await;
+    let (_, polled) = send(&router, get(&results_uri)).await;
+    assert_eq!(polled["status"], "succeeded");
+    // The stub answers with the indexing in its fixture, as Azure reports what it used
+    assert_eq!(polled["result"]["string_index_type"], "textElements");
+
+    let (status, _) = send(
+        &router,
+        post_json(
+            "/api/v1/analyze/read",
+            json!({
+                "document_url": "https://example.com/doc.pdf",
+                "options": { "string_index_type": "bytes" }
+            }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn test_recorded_azure_calls_replay_without_azure() {
+    let cassette_dir = tempfile::tempdir().unwrap();
+    let cassette = cassette_dir.path().join("invoice.json");
+    let document_url = "https://acct.blob.core.windows.net/docs/inv.pdf?sv=2022-11-02&sig=c2VjcmV0";
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+
+    let stub = AzureStub::start().await;
+    stub.mount_all().await;
+    let recorder = VcrAdapter::record(
+        Arc::new(AzureDocumentIntelligenceAdapter::new(stub.config()).unwrap()),
+        API_VERSION,
+        &cassette,
+    )
+    .with_secret("c2VjcmV0");
+    let recording = Harness::in_memory_with(HarnessOptions {
+        intelligence: Some(Arc::new(recorder)),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(recording.service.clone());
+    send(&router, post_json("/api/v1/analyze/invoice", json!({ "document_url": document_url }))).await;
+    send(&router, get(&results_uri)).await;
+    let (_, recorded) = send(&router, get(&results_uri)).await;
+    assert_eq!(recorded["status"], "succeeded");
+
+    let written = std::fs::read_to_string(&cassette).unwrap();
+    assert!(!written.contains("c2VjcmV0"));
+    assert!(written.contains("https://acct.blob.core.windows.net/docs/inv.pdf\""));
+
+    let replaying = Harness::in_memory_with(HarnessOptions {
+        intelligence: Some(Arc::new(VcrAdapter::replay(&cassette).await.unwrap())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(replaying.service.clone());
+    let (status, submitted) = send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": document_url })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(submitted["operation_id"], result_id("invoice"));
+
+    let (_, first_poll) = send(&router, get(&results_uri)).await;
+    assert_eq!(first_poll["status"], "running");
+    let (_, replayed) = send(&router, get(&results_uri)).await;
+    assert_eq!(replayed["status"], "succeeded");
+    assert_eq!(replayed["result"]["content"], fixture_content("invoice"));
+    assert_eq!(replayed["result"], recorded["result"]);
+    assert!(replaying.stub.server.received_requests().await.unwrap().is_empty());
+
+    // Documents that were never recorded are refused rather than sent to Azure
+    let (status, _) = send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/other.pdf" })),
+    )
+    .await;
+    assert!(!status.is_success());
+}
+
+#[tokio::test]
+async fn test_results_etag_and_not_modified() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/read", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("read"));
+
+    let running = router.clone().oneshot(get(&results_uri)).await.unwrap();
+    let running_etag = running.headers()["etag"].to_str().unwrap().to_string();
+    assert!(running_etag.starts_with("\"running-"));
+
+    let done = router.clone().oneshot(get(&results_uri)).await.unwrap();
+    let done_etag = done.headers()["etag"].to_str().unwrap().to_string();
+    assert_ne!(running_etag, done_etag);
+
+    let revalidate = Request::builder()
+        .uri(&results_uri)
+        .header("if-none-match", format!("W/\"stale\", {}", done_etag))
+        .body(Body::empty())
+        .unwrap();
+    let not_modified = router.clone().oneshot(revalidate).await.unwrap();
+    assert_eq!(not_modified.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(not_modified.headers()["etag"].to_str().unwrap(), done_etag);
+    let body = axum::body::to_bytes(not_modified.into_body(), usize::MAX).await.unwrap();
+    assert!(body.is_empty());
+}
+
+#[tokio::test]
+async fn test_oversized_bodies_are_rejected_with_structured_413() {
+    let harness = Harness::in_memory().await;
+    let limits = BodyLimits { json_bytes: 256, upload_bytes: 1024 };
+    let router = create_rest_router_with_limits(harness.service.clone(), limits);
+
+    let long_url = format!("https://example.com/{}.pdf", "a".repeat(512));
+    let (status, body) = send(
+        &router,
+        post_json("/api/v1/analyze/read", json!({ "document_url": long_url })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(body["error"].is_string());
+
+    let boundary = "adi-boundary";
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"big.pdf\"\r\n\
+         Content-Type: application/pdf\r\n\r\n{data}\r\n--{b}--\r\n",
+        b = boundary,
+        data = "x".repeat(4096)
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/read")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(body))
+        .unwrap();
+    let (status, body) = send(&router, request).await;
+    assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+    assert!(body["error"].as_str().unwrap().contains("size limit"));
+
+    // Requests within the limit are unaffected
+    let (status, _) = send(
+        &router,
+        post_json("/api/v1/analyze/read", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_request_timeout_cancels_azure_call() {
+    let harness = Harness::in_memory().await;
+    harness.stub.delay_model("prebuilt-read", std::time::Duration::from_secs(30)).await;
+    let router = create_rest_router(harness.service.clone());
+    let with_timeout = |uri: &str, timeout: &str| {
+        let mut request = post_json(uri, json!({ "document_url": "https://example.com/doc.pdf" }));
+        request.headers_mut().insert("x-request-timeout", timeout.parse().unwrap());
+        request
+    };
+
+    let started = std::time::Instant::now();
+    let (status, body) = send(&router, with_timeout("/api/v1/analyze/read", "0.2")).await;
+    assert_eq!(status, StatusCode::GATEWAY_TIMEOUT);
+    assert_eq!(body["code"], "deadline_exceeded");
+    assert!(started.elapsed() < std::time::Duration::from_secs(10));
+
+    let (status, _) = send(&router, with_timeout("/api/v1/analyze/layout", "30")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    for invalid in ["soon", "0", "-1"] {
+        let (status, _) = send(&router, with_timeout("/api/v1/analyze/layout", invalid)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", invalid);
+    }
+}
+
+#[tokio::test]
+async fn test_request_id_is_echoed_and_forwarded_to_azure() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let mut request = post_json("/api/v1/analyze/read", json!({ "document_url": "https://example.com/doc.pdf" }));
+    request.headers_mut().insert("x-request-id", "support-1234".parse().unwrap());
+    let response = router.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-request-id"], "support-1234");
+
+    let forwarded = harness.stub.server.received_requests().await.unwrap();
+    let submission = forwarded.iter().find(|request| request.method.as_str() == "POST").unwrap();
+    assert_eq!(submission.headers.get("x-ms-client-request-id").unwrap(), "support-1234");
+
+    // Generated when absent, and quoted in error bodies
+    let response = router.clone().oneshot(get("/api/v1/results/missing")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let id = response.headers()["x-request-id"].to_str().unwrap().to_string();
+    assert!(!id.is_empty());
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["request_id"], id.as_str());
+}
+
+#[tokio::test]
+async fn test_error_status_mapping() {
+    let harness = Harness::in_memory().await;
+    harness.stub.fail_model("prebuilt-read", 429, Some("7")).await;
+    harness.stub.fail_model("prebuilt-layout", 503, None).await;
+    let router = create_rest_router(harness.service.clone());
+
+    let submit = |model: &str| {
+        post_json(&format!("/api/v1/analyze/{}", model), json!({ "document_url": "https://example.com/doc.pdf" }))
+    };
+    let response = router.clone().oneshot(submit("read")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(response.headers()["retry-after"], "7");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["code"], "azure_throttled");
+
+    assert_eq!(body["azure_error"]["code"], "InvalidRequest");
+
+    let (status, body) = send(&router, submit("layout")).await;
+    assert_eq!(status, StatusCode::BAD_GATEWAY);
+    assert_eq!(body["code"], "azure_unavailable");
+
+    let (status, _) = send(&router, get("/api/v1/results/no-such-operation")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&router, post_json("/api/v1/analyze/invoice", json!({ "document_url": "" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_azure_error_details() {
+    let harness = Harness::in_memory().await;
+    harness.stub.fail_model("prebuilt-invoice", 400, None).await;
+    harness.stub.fail_operation("prebuilt-receipt", "result-failed").await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (status, body) = send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "azure_rejected");
+    assert_eq!(
+        body["azure_error"],
+        json!({
+            "status": 400,
+            "code": "InvalidRequest",
+            "message": "Invalid request.",
+            "inner_code": "InvalidContentLength",
+            "request_id": AZURE_REQUEST_ID,
+        })
+    );
+
+    // Operations Azure fails report why
+    let (status, _) = send(
+        &router,
+        post_json("/api/v1/analyze/receipt", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = send(&router, get("/api/v1/results/result-failed")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "failed");
+    assert_eq!(body["error"]["code"], "InvalidRequest");
+    assert_eq!(body["error"]["inner_code"], "InvalidContentLength");
+    assert!(body["error"].get("status").is_none());
+}
+
+#[tokio::test]
+async fn test_review_queue_claim_heartbeat_complete() {
+    let policy = ReviewPolicy::new(0.95, vec!["PurchaseOrder".to_string()]).unwrap();
+    let harness = Harness::in_memory_with(HarnessOptions {
+        review_policy: Some(policy),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let operation_id = result_id("invoice");
+    let results_uri = format!("/api/v1/results/{}", operation_id);
+
+    // Nothing to claim until the operation has succeeded
+    let (_, claimed) = send(&router, post_json("/api/v1/review/claim", json!({ "worker_id": "a" }))).await;
+    assert_eq!(claimed["items"], json!([]));
+    send(&router, get(&results_uri)).await;
+    send(&router, get(&results_uri)).await;
+
+    let (status, claimed) = send(
+        &router,
+        post_json("/api/v1/review/claim", json!({ "worker_id": "a", "lease_secs": 60, "limit": 5 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(claimed["items"][0]["operation_id"], operation_id);
+    assert_eq!(claimed["items"][0]["queue"], "review");
+
+    let (_, other) = send(&router, post_json("/api/v1/review/claim", json!({ "worker_id": "b" }))).await;
+    assert_eq!(other["items"], json!([]));
+
+    let heartbeat_uri = format!("/api/v1/review/{}/heartbeat", operation_id);
+    let (status, _) = send(&router, post_json(&heartbeat_uri, json!({ "worker_id": "b" }))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    let (status, lease) = send(&router, post_json(&heartbeat_uri, json!({ "worker_id": "a" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(lease["worker_id"], "a");
+
+    let complete_uri = format!("/api/v1/review/{}/complete", operation_id);
+    let (status, _) = send(&router, post_json(&complete_uri, json!({ "worker_id": "a" }))).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+
+    let (status, _) = send(&router, post_json("/api/v1/export/claim", json!({ "worker_id": "" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (_, export) = send(&router, post_json("/api/v1/export/claim", json!({ "worker_id": "b" }))).await;
+    assert_eq!(export["items"][0]["operation_id"], operation_id);
+}
+
+#[tokio::test]
+async fn test_batch_upload_reports_files_that_failed() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        job_queue: Some(Arc::new(InMemoryOperationTracker::new())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+    let upload = |uri: &str| {
+        let boundary = "adi-boundary";
+        let body = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.pdf\"\r\n\
+             Content-Type: application/pdf\r\n\r\n%PDF-1.4 a\r\n\
+             --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"notes.txt\"\r\n\
+             Content-Type: text/plain\r\n\r\nnot a document\r\n--{b}--\r\n",
+            b = boundary
+        );
+        Request::builder()
+            .method(Method::POST)
+            .uri(uri)
+            .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    // The PDF is still analyzed; the text file is reported with the status it would have had alone
+    let (status, body) = send(&router, upload("/api/v1/upload/read")).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["operations"].as_array().unwrap().len(), 1);
+    assert_eq!(body["operations"][0]["filename"], "a.pdf");
+    assert_eq!(body["errors"][0]["filename"], "notes.txt");
+    assert_eq!(body["errors"][0]["status"], 415);
+    assert!(body["errors"][0]["error"].is_string());
+
+    let (status, body) = send(&router, upload("/api/v1/upload/read?mode=async")).await;
+    assert_eq!(status, StatusCode::MULTI_STATUS);
+    assert_eq!(body["jobs"].as_array().unwrap().len(), 1);
+    assert_eq!(body["errors"][0]["filename"], "notes.txt");
+    assert!(harness.service.run_next_job().await.unwrap());
+    assert!(!harness.service.run_next_job().await.unwrap());
+}
+
+#[tokio::test]
+async fn test_multipart_upload_with_multiple_files_and_options() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let boundary = "adi-boundary";
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"options\"\r\n\r\n\
+         {{\"locale\":\"en-US\",\"features\":[\"barcodes\"]}}\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"a.pdf\"\r\n\
+         Content-Type: application/pdf\r\n\r\n%PDF-1.4 a\r\n\
+         --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"b.tif\"\r\n\
+         Content-Type: image/tiff\r\n\r\nII*\0 b\r\n--{b}--\r\n",
+        b = boundary
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/read")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(body))
+        .unwrap();
+
+    let (status, submitted) = send(&router, request).await;
+    assert_eq!(status, StatusCode::OK);
+    let operations = submitted["operations"].as_array().unwrap();
+    assert_eq!(operations.len(), 2);
+    assert_eq!(operations[0]["filename"], "a.pdf");
+    assert_eq!(operations[1]["filename"], "b.tif");
+    assert_eq!(operations[1]["content_type"], "image/tiff");
+
+    let analyze_calls = harness
+        .stub
+        .server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .filter(|request| request.url.path().ends_with(":analyze"))
+        .collect::<Vec<_>>();
+    assert_eq!(analyze_calls.len(), 2);
+    let query = analyze_calls[0].url.query().unwrap_or_default().to_string();
+    assert!(query.contains("locale=en-US"), "query: {}", query);
+
+    let invalid = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"options\"\r\n\r\nnot json\r\n--{b}--\r\n",
+        b = boundary
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/read")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(invalid))
+        .unwrap();
+    let (status, _) = send(&router, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_lookup_prior_operations_by_sha256() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let boundary = "adi-boundary";
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"invoice.pdf\"\r\n\
+         Content-Type: application/pdf\r\n\r\n%PDF-1.4 test\r\n--{b}--\r\n",
+        b = boundary
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/invoice")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(body))
+        .unwrap();
+    send(&router, request).await;
+
+    let unknown = "0".repeat(64);
+    let (status, body) = send(&router, get(&format!("/api/v1/documents/lookup?sha256={}", unknown))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["operations"].as_array().unwrap().is_empty());
+
+    // sha256("%PDF-1.4 test"), case-insensitive
+    let sha256 = "D663640088750CF16276D623C2588D7233F2B84B45F4B2E20832F47B16AA5618";
+    let (_, body) = send(&router, get(&format!("/api/v1/documents/lookup?sha256={}", sha256))).await;
+    assert_eq!(body["sha256"], sha256.to_lowercase());
+    assert_eq!(body["operations"][0]["operation_id"], result_id("invoice"));
+    assert_eq!(body["operations"][0]["filename"], "invoice.pdf");
+
+    let (status, _) = send(&router, get("/api/v1/documents/lookup?sha256=xyz")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_resumable_upload_then_analyze() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (status, created) = send(
+        &router,
+        post_json("/api/v1/uploads", json!({ "filename": "scan.pdf", "content_type": "application/pdf", "size": 12 })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let upload_uri = format!("/api/v1/uploads/{}", created["upload_id"].as_str().unwrap());
+
+    let patch = |offset: u64, chunk: &'static [u8]| {
+        Request::builder()
+            .method(Method::PATCH)
+            .uri(&upload_uri)
+            .header("upload-offset", offset.to_string())
+            .header("content-type", "application/offset+octet-stream")
+            .body(Body::from(chunk))
+            .unwrap()
+    };
+
+    let response = router.clone().oneshot(patch(0, b"%PDF-1")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(response.headers()["upload-offset"], "6");
+
+    // Resending the same chunk after a dropped response is a conflict
+    let (status, _) = send(&router, patch(0, b"%PDF-1")).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    let (_, state) = send(&router, get(&upload_uri)).await;
+    assert_eq!(state["offset"], 6);
+
+    let (status, _) = send(&router, post_json(&format!("{}/analyze?model=read", upload_uri), json!({}))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    send(&router, patch(6, b".4 abc")).await;
+    let (status, submitted) = send(
+        &router,
+        post_json(&format!("{}/analyze?model=read", upload_uri), json!({})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(submitted["operation_id"], result_id("read"));
+    assert_eq!(submitted["filename"], "scan.pdf");
+
+    let (status, _) = send(&router, get(&upload_uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_base_path_and_external_url() {
+    let harness = Harness::in_memory().await;
+    let options = RestOptions {
+        urls: PublicUrls {
+            base_path: "/adi".to_string(),
+            external_url: Some("https://docs.example.com".to_string()),
+        },
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    let (status, _) = send(&router, get("/adi/health")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&router, get("/health")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let response = router
+        .clone()
+        .oneshot(post_json("/adi/api/v1/uploads", json!({ "filename": "doc.pdf" })))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+    assert!(location.starts_with("https://docs.example.com/adi/api/v1/uploads/"));
+
+    let path = location.trim_start_matches("https://docs.example.com");
+    let (status, body) = send(&router, get(path)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["filename"], "doc.pdf");
+}
+
+#[tokio::test]
+async fn test_download_original_document() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let boundary = "adi-boundary";
+    let body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"invoice.pdf\"\r\n\
+         Content-Type: application/pdf\r\n\r\n%PDF-1.4 test\r\n--{b}--\r\n",
+        b = boundary
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/invoice")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(body))
+        .unwrap();
+    let (_, submitted) = send(&router, request).await;
+    let document_uri = format!("/api/v1/documents/{}", submitted["document_id"].as_str().unwrap());
+
+    let response = router.clone().oneshot(get(&document_uri)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/pdf");
+    assert!(response.headers()["content-disposition"]
+        .to_str()
+        .unwrap()
+        .contains("filename=\"invoice.pdf\""));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"%PDF-1.4 test");
+
+    let ranged = Request::builder()
+        .uri(&document_uri)
+        .header("range", "bytes=0-3")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(ranged).await.unwrap();
+    assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+    assert_eq!(response.headers()["content-range"], "bytes 0-3/13");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"%PDF");
+
+    let beyond = Request::builder()
+        .uri(&document_uri)
+        .header("range", "bytes=100-")
+        .body(Body::empty())
+        .unwrap();
+    let response = router.clone().oneshot(beyond).await.unwrap();
+    assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+
+    let (status, _) = send(&router, get("/api/v1/documents/missing.pdf")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_signed_document_url() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (_, created) = send(&router, post_json("/api/v1/uploads", json!({ "filename": "scan.pdf" }))).await;
+    let upload_uri = format!("/api/v1/uploads/{}", created["upload_id"].as_str().unwrap());
+    let patch = Request::builder()
+        .method(Method::PATCH)
+        .uri(&upload_uri)
+        .header("upload-offset", "0")
+        .body(Body::from("%PDF-1.4 scan"))
+        .unwrap();
+    router.clone().oneshot(patch).await.unwrap();
+    let (_, submitted) = send(&router, post_json(&format!("{}/analyze?model=read", upload_uri), json!({}))).await;
+    let document_id = submitted["document_id"].as_str().unwrap().to_string();
+
+    let (status, signed) = send(&router, get(&format!("/api/v1/documents/{}/url?expires_in=60", document_id))).await;
+    assert_eq!(status, StatusCode::OK);
+    let url = signed["url"].as_str().unwrap();
+    assert!(url.starts_with("/api/v1/signed/documents/"));
+    assert!(signed["expires_at"].is_string());
+
+    let response = router.clone().oneshot(get(url)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(&body[..], b"%PDF-1.4 scan");
+
+    let tampered = url.replace("expires=", "expires=1");
+    let (status, _) = send(&router, get(&tampered)).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = send(&router, get(&format!("/api/v1/documents/{}/url?expires_in=0", document_id))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&router, get("/api/v1/documents/missing.pdf/url")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+fn multipart_upload(uri: &str, filename: &str, content: &[u8]) -> Request<Body> {
+    let boundary = "adi-boundary";
+    let mut body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{f}\"\r\n\
+         Content-Type: application/pdf\r\n\r\n",
+        b = boundary,
+        f = filename
+    )
+    .into_bytes();
+    body.extend_from_slice(content);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    Request::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(body))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_malware_scan_rejects_infected_uploads() {
+    let harness = Harness::with_malware_scanner().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let mut infected = b"%PDF-1.4 ".to_vec();
+    infected.extend_from_slice(EICAR_MARKER);
+    let (status, body) = send(&router, multipart_upload("/api/v1/upload/read", "bad.pdf", &infected)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "malware_detected");
+    assert!(harness.tracker.get_operation(&result_id("read")).await.unwrap().is_none());
+
+    let (status, _) = send(&router, multipart_upload("/api/v1/upload/read", "ok.pdf", b"%PDF-1.4 ok")).await;
+    assert_eq!(status, StatusCode::OK);
+    let operation = harness
+        .tracker
+        .get_operation(&result_id("read"))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(operation.scan_verdict, Some(ScanVerdict::Clean));
+}
+
+#[tokio::test]
+async fn test_upload_content_type_is_sniffed() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (status, body) = send(&router, multipart_upload("/api/v1/upload/read", "notes.pdf", b"just text")).await;
+    assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert!(body["error"].as_str().unwrap().contains("Unsupported document type"));
+
+    // Declared as PDF, but the bytes are a JPEG
+    let jpeg = [0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F'];
+    let (status, submitted) = send(&router, multipart_upload("/api/v1/upload/read", "photo.pdf", &jpeg)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(submitted["content_type"], "image/jpeg");
+}
+
+#[tokio::test]
+async fn test_pdf_pre_validation() {
+    let harness = Harness::with_pdf_validation(3).await;
+    let router = create_rest_router(harness.service.clone());
+
+    let encrypted = minimal_pdf(1, " /Encrypt 9 0 R");
+    let (status, body) = send(&router, multipart_upload("/api/v1/upload/read", "locked.pdf", &encrypted)).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "encrypted_document");
+
+    let (status, body) = send(&router, multipart_upload("/api/v1/upload/read", "long.pdf", &minimal_pdf(4, ""))).await;
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(body["code"], "too_many_pages");
+
+    let truncated = minimal_pdf(2, "");
+    let (status, body) = send(
+        &router,
+        multipart_upload("/api/v1/upload/read", "cut.pdf", &truncated[..truncated.len() / 2]),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("corrupt"));
+    assert!(harness.tracker.get_operation(&result_id("read")).await.unwrap().is_none());
+
+    let (status, _) = send(&router, multipart_upload("/api/v1/upload/read", "ok.pdf", &minimal_pdf(3, ""))).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[cfg(feature = "images")]
+#[tokio::test]
+async fn test_image_preprocessing_before_submission() {
+    use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let mut png = std::io::Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(64, 48, Rgb([180, 30, 30])))
+        .write_to(&mut png, ImageFormat::Png)
+        .unwrap();
+    let png = png.into_inner();
+
+    let upload = |options: &str| {
+        let boundary = "adi-boundary";
+        let mut body = format!(
+            "--{b}\r\nContent-Disposition: form-data; name=\"options\"\r\n\r\n{o}\r\n\
+             --{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"photo.png\"\r\n\
+             Content-Type: image/png\r\n\r\n",
+            b = boundary,
+            o = options
+        )
+        .into_bytes();
+        body.extend_from_slice(&png);
+        body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+        Request::builder()
+            .method(Method::POST)
+            .uri("/api/v1/upload/read")
+            .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+            .body(Body::from(body))
+            .unwrap()
+    };
+
+    let (status, _) = send(&router, upload(r#"{"preprocess":{"jpeg_quality":0}}"#)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, submitted) =
+        send(&router, upload(r#"{"preprocess":{"grayscale":true,"jpeg_quality":70}}"#)).await;
+    assert_eq!(status, StatusCode::OK);
+    // The stored original keeps its format
+    assert_eq!(submitted["content_type"], "image/png");
+
+    let analyze = harness
+        .stub
+        .server
+        .received_requests()
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|request| request.url.path().ends_with(":analyze"))
+        .unwrap();
+    // Sent as raw bytes under the preprocessed format
+    assert_eq!(analyze.headers.get("content-type").unwrap(), "image/jpeg");
+    let sent = analyze.body;
+    assert_eq!(image::guess_format(&sent).unwrap(), ImageFormat::Jpeg);
+    assert_eq!(image::load_from_memory(&sent).unwrap().color(), image::ColorType::L8);
+}
+
+#[tokio::test]
+async fn test_markdown_export() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/layout", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let markdown_uri = format!("/api/v1/results/{}/markdown", result_id("layout"));
+
+    // First poll reports the operation as still running
+    let (status, body) = send(&router, get(&markdown_uri)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"].as_str().unwrap().contains("running"));
+
+    let response = router.clone().oneshot(get(&markdown_uri)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/markdown; charset=utf-8");
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let markdown = String::from_utf8(body.to_vec()).unwrap();
+    assert!(markdown.starts_with("## Content\n"));
+    assert!(markdown.contains(fixture_content("layout").trim_end()));
+    assert!(markdown.contains("| Item | Qty | Price |\n| --- | --- | --- |\n| Widget | 2 | 10.00 |"));
+}
+
+#[tokio::test]
+async fn test_result_include_projection() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/layout", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("layout"));
+    send(&router, get(&results_uri)).await;
+
+    let (status, full) = send(&router, get(&results_uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(full["result"]["pages"].is_array());
+
+    let (status, projected) = send(&router, get(&format!("{}?include=content,tables", results_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(projected["result"]["content"], fixture_content("layout"));
+    assert_eq!(projected["result"]["tables"][0]["cell_count"], 6);
+    assert!(projected["result"].get("pages").is_none());
+
+    let (status, _) = send(&router, get(&format!("{}?include=words", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_result_min_confidence() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    let (_, full) = send(&router, get(&results_uri)).await;
+    assert!(full["result"].get("filtered").is_none());
+
+    let (status, filtered) = send(&router, get(&format!("{}?min_confidence=0.97", results_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        filtered["result"]["filtered"],
+        json!({ "words": 2, "key_value_pairs": 1, "document_fields": 0 })
+    );
+    assert_eq!(filtered["result"]["pages"][0]["word_count"], 3);
+
+    let (status, _) = send(&router, get(&format!("{}?min_confidence=-1", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_text_export() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/read", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("read"));
+    send(&router, get(&results_uri)).await;
+
+    let text = |uri: String| {
+        let router = router.clone();
+        async move {
+            let response = router.oneshot(get(&uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["content-type"], "text/plain; charset=utf-8");
+            let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+            String::from_utf8(body.to_vec()).unwrap()
+        }
+    };
+
+    assert_eq!(text(format!("{}/text", results_uri)).await, fixture_content("read"));
+    assert_eq!(
+        text(format!("{}/text?pages=1", results_uri)).await,
+        "Contoso Ltd. quarterly report 2024"
+    );
+    assert_eq!(text(format!("{}/text?pages=2-", results_uri)).await, "");
+}
+
+#[tokio::test]
+async fn test_result_page() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/read", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("read"));
+    send(&router, get(&results_uri)).await;
+
+    let (status, page) = send(&router, get(&format!("{}/pages/1", results_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(page["page_number"], 1);
+    assert_eq!(page["lines"][0]["content"], "Contoso Ltd. quarterly report 2024");
+    assert!(!page["words"].as_array().unwrap().is_empty());
+    assert!(page["selection_marks"].is_array());
+    assert_eq!(page["paragraphs"][0]["lines"][0], 0);
+    assert!(page["paragraphs"][0]["content"].as_str().unwrap().starts_with("Contoso Ltd. quarterly report 2024"));
+
+    let (status, normalized) =
+        send(&router, get(&format!("{}/pages/1?coordinates=normalized", results_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(normalized["unit"], "normalized");
+    assert_eq!(normalized["width"], 1.0);
+    let x = |page: &serde_json::Value| page["words"][0]["polygon"][0]["x"].as_f64().unwrap();
+    assert!((x(&normalized) - x(&page) / page["width"].as_f64().unwrap()).abs() < 1e-6);
+    let (status, _) = send(&router, get(&format!("{}/pages/1?coordinates=furlongs", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = send(&router, get(&format!("{}/pages/2", results_uri))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&router, get(&format!("{}/pages/0", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_table_records() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/layout", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("layout"));
+    send(&router, get(&results_uri)).await;
+
+    let (status, table) = send(&router, get(&format!("{}/tables/0/records", results_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(table["headers"], json!(["Item", "Qty", "Price"]));
+    assert_eq!(table["records"], json!([{ "Item": "Widget", "Qty": "2", "Price": "10.00" }]));
+
+    let (status, _) = send(&router, get(&format!("{}/tables/1/records", results_uri))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[cfg(feature = "templates")]
+#[tokio::test]
+async fn test_output_template() {
+    let templates = adi_svc::domain::parse_output_templates(
+        &json!({
+            "erp-v1": {
+                "models": ["invoice"],
+                "json_path": {
+                    "id": "$.operation_id",
+                    "vendor": "$.documents[0].fields.VendorName.value",
+                    "invoice_number": "$.documents[0].fields.InvoiceId.value"
+                }
+            }
+        })
+        .to_string(),
+    )
+    .unwrap();
+    let harness = Harness::in_memory_with(HarnessOptions {
+        output_templates: templates,
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    for model in ["invoice", "read"] {
+        send(
+            &router,
+            post_json(&format!("/api/v1/analyze/{}", model), json!({ "document_url": "https://example.com/doc.pdf" })),
+        )
+        .await;
+        send(&router, get(&format!("/api/v1/results/{}", result_id(model)))).await;
+    }
+
+    let invoice_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    let (status, mapped) = send(&router, get(&format!("{}?template=erp-v1", invoice_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        mapped,
+        json!({ "id": result_id("invoice"), "vendor": "Contoso Ltd.", "invoice_number": "INV-100" })
+    );
+
+    let (status, _) = send(&router, get(&format!("{}?template=erp-v2", invoice_uri))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let read_uri = format!("/api/v1/results/{}?template=erp-v1", result_id("read"));
+    let (status, _) = send(&router, get(&read_uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST, "template is for invoices only");
+}
+
+#[tokio::test]
+async fn test_raw_response() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        raw_responses: true,
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let raw_uri = format!("/api/v1/results/{}/raw", result_id("invoice"));
+    let (status, _) = send(&router, get(&raw_uri)).await;
+    assert_eq!(status, StatusCode::CONFLICT, "operation still running");
+    let (status, raw) = send(&router, get(&raw_uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(raw, fixture("invoice"));
+
+    // Without the flag only the converted result is kept
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    send(&router, get(&format!("/api/v1/results/{}", result_id("invoice")))).await;
+    let (status, _) = send(&router, get(&raw_uri)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+}
+
+#[tokio::test]
+async fn test_result_diff() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    for model in ["read", "layout"] {
+        send(
+            &router,
+            post_json(
+                &format!("/api/v1/analyze/{}", model),
+                json!({ "document_url": "https://example.com/doc.pdf" }),
+            ),
+        )
+        .await;
+        send(&router, get(&format!("/api/v1/results/{}", result_id(model)))).await;
+    }
+
+    let uri = format!("/api/v1/results/diff?left={}&right={}", result_id("read"), result_id("layout"));
+    let (status, diff) = send(&router, get(&uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(diff["left"], result_id("read"));
+    assert_eq!(diff["tables"][0]["kind"], "added");
+    assert_eq!(diff["tables"][0]["right_shape"], json!([2, 3]));
+    assert_eq!(diff["content"]["identical"], false);
+
+    let uri = format!("/api/v1/results/diff?left={}&right={}", result_id("read"), result_id("read"));
+    let (_, same) = send(&router, get(&uri)).await;
+    assert_eq!(same["fields"], json!([]));
+    assert_eq!(same["tables"], json!([]));
+    assert_eq!(same["content"]["identical"], true);
+}
+
+#[tokio::test]
+async fn test_result_field_query() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    send(&router, get(&results_uri)).await;
+
+    let (status, body) = send(&router, get(&format!("{}/fields?key=vendorname", results_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    let fields = body["fields"].as_array().unwrap();
+    assert_eq!(fields.len(), 2);
+    assert_eq!(fields[0]["source"], "key_value_pair");
+    assert_eq!(fields[1]["source"], "document_field");
+    assert_eq!(fields[1]["doc_type"], "invoice");
+    assert_eq!(fields[1]["name"], "VendorName");
+
+    let (_, body) = send(&router, get(&format!("{}/fields?min_confidence=0.95", results_uri))).await;
+    let fields = body["fields"].as_array().unwrap();
+    assert_eq!(fields.len(), 5);
+    assert!(fields.iter().all(|field| field["source"] == "document_field"));
+    assert_eq!(
+        body["normalized_fields"],
+        json!({
+            "InvoiceDate": { "kind": "date", "value": "2024-04-30" },
+            "InvoiceTotal": { "kind": "amount", "value": "110.00", "currency": "USD" },
+        })
+    );
+
+    let (status, _) = send(&router, get(&format!("{}/fields?min_confidence=2", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_review_queue_and_corrections() {
+    let policy = ReviewPolicy::new(0.95, vec!["InvoiceTotal".to_string(), "PurchaseOrder".to_string()]).unwrap();
+    let harness = Harness::in_memory_with(HarnessOptions {
+        review_policy: Some(policy),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    send(&router, get(&results_uri)).await;
+    let (_, body) = send(&router, get(&results_uri)).await;
+    assert_eq!(body["status"], "succeeded");
+    assert_eq!(body["review"]["status"], "needs_review");
+    assert_eq!(body["review"]["reasons"], json!(["missing field PurchaseOrder"]));
+
+    let (status, body) = send(&router, get("/api/v1/review-queue")).await;
+    assert_eq!(status, StatusCode::OK);
+    let queued = body["operations"].as_array().unwrap();
+    assert_eq!(queued.len(), 1);
+    assert_eq!(queued[0]["operation_id"], result_id("invoice"));
+
+    let corrections = json!({
+        "corrections": [
+            { "field": "PurchaseOrder", "value": "PO-7" },
+            { "field": "invoicetotal", "value": "$120.00" },
+        ]
+    });
+    let corrections_uri = format!("{}/corrections", results_uri);
+    let (status, body) = send(&router, post_json(&corrections_uri, corrections)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["revision"], 1);
+    assert_eq!(body["reviewer"], "anonymous");
+
+    // Reads see the corrected values and the operation leaves the queue
+    let (_, body) = send(&router, get(&format!("{}/fields?key=purchaseorder", results_uri))).await;
+    assert_eq!(body["fields"][0]["value"], json!({ "type": "string", "value": "PO-7" }));
+    let (_, body) = send(&router, get(&format!("{}/fields?key=InvoiceTotal", results_uri))).await;
+    assert_eq!(body["normalized_fields"]["InvoiceTotal"]["value"], "120.00");
+    let (_, body) = send(&router, get("/api/v1/review-queue")).await;
+    assert_eq!(body["operations"], json!([]));
+    let (_, body) = send(&router, post_json("/api/v1/review/claim", json!({ "worker_id": "a" }))).await;
+    assert_eq!(body["items"], json!([]));
+    let (_, body) = send(&router, get(&results_uri)).await;
+    assert_eq!(body["review"]["status"], "reviewed");
+    assert_eq!(body["review"]["revision"], 1);
+
+    let (status, body) = send(&router, get(&format!("{}/revisions", results_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    let revisions = body["revisions"].as_array().unwrap();
+    assert_eq!(revisions.len(), 2);
+    assert_eq!(revisions[0]["revision"], 0);
+    assert_eq!(revisions[0]["corrections"], json!([]));
+    assert_eq!(revisions[1]["corrections"][0]["field"], "PurchaseOrder");
+
+    let (status, _) = send(&router, post_json(&corrections_uri, json!({ "corrections": [] }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(
+        &router,
+        post_json(
+            "/api/v1/results/missing/corrections",
+            json!({ "corrections": [{ "field": "PurchaseOrder", "value": "PO-7" }] }),
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_concurrent_corrections_conflict() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        revisions: Some(Arc::new(RacingRevisions::default())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    send(&router, get(&results_uri)).await;
+    send(&router, get(&results_uri)).await;
+
+    // Another reviewer stores revision 1 between this correction reading the latest revision and storing its own
+    let corrections_uri = format!("{}/corrections", results_uri);
+    let corrections = json!({ "corrections": [{ "field": "PurchaseOrder", "value": "PO-7" }] });
+    let (status, body) = send(&router, post_json(&corrections_uri, corrections.clone())).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"].as_str().unwrap().contains("revision 1"));
+
+    // Correcting again builds on the rival's revision
+    let (status, body) = send(&router, post_json(&corrections_uri, corrections)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["revision"], 2);
+    let (_, body) = send(&router, get(&format!("{}/revisions", results_uri))).await;
+    let reviewers: Vec<_> = body["revisions"].as_array().unwrap().iter().map(|revision| revision["reviewer"].clone()).collect();
+    assert_eq!(reviewers, [Value::Null, json!("rival"), json!("anonymous")]);
+}
+
+#[tokio::test]
+async fn test_corrections_export() {
+    let harness = Harness::in_memory().await;
+    let writer = Arc::new(MemoryDatasetWriter::default());
+    let options = RestOptions {
+        training_export: Some(Arc::new(TrainingExportService::new(
+            harness.service.clone(),
+            writer.clone(),
+            "/datasets/",
+        ))),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    send(&router, multipart_upload("/api/v1/upload/invoice", "invoice.pdf", b"%PDF-1.4 test")).await;
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    send(&router, get(&results_uri)).await;
+    let (_, body) = send(&router, get(&results_uri)).await;
+    assert_eq!(body["status"], "succeeded");
+
+    // Nothing reviewed yet, so nothing is written
+    let (status, body) = send(&router, post_json("/api/v1/corrections/export", json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["documents"], 0);
+    assert!(writer.files.lock().unwrap().is_empty());
+
+    let corrections = json!({
+        "corrections": [
+            { "field": "VendorName", "value": "Contoso Ltd" },
+            { "field": "PurchaseOrder", "value": "PO-7" },
+        ]
+    });
+    let (status, _) = send(&router, post_json(&format!("{}/corrections", results_uri), corrections)).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let (status, body) = send(&router, post_json("/api/v1/corrections/export", json!({}))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["folder"], "datasets/default/");
+    assert_eq!(body["documents"], 1);
+    assert!(body["fields"].as_array().unwrap().contains(&json!("VendorName")));
+    // The fixture's page has no words for the line items
+    assert_eq!(
+        body["unlocated"],
+        json!([
+            { "operation_id": result_id("invoice"), "field": "Items" },
+            { "operation_id": result_id("invoice"), "field": "PurchaseOrder" },
+        ])
+    );
+
+    let files = writer.files.lock().unwrap().clone();
+    let document = format!("datasets/default/{}.pdf", result_id("invoice"));
+    assert_eq!(
+        files.keys().cloned().collect::<Vec<_>>(),
+        vec![
+            "datasets/default/fields.json".to_string(),
+            document.clone(),
+            format!("{}.labels.json", document),
+            format!("{}.ocr.json", document),
+        ]
+    );
+    assert_eq!(files[&document], ("application/pdf".to_string(), bytes::Bytes::from_static(b"%PDF-1.4 test")));
+    let labels: Value = serde_json::from_slice(&files[&format!("{}.labels.json", document)].1).unwrap();
+    assert_eq!(labels["document"], format!("{}.pdf", result_id("invoice")));
+    let vendor = labels["labels"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|label| label["label"] == "VendorName")
+        .unwrap();
+    // The corrected spelling isn't on the page, so it is labeled where the original reading was
+    assert_eq!(vendor["value"][0]["text"], "Contoso Ltd");
+    assert_eq!(vendor["value"][0]["page"], 1);
+    assert_eq!(vendor["value"][0]["boundingBoxes"].as_array().unwrap().len(), 2);
+    let ocr: Value = serde_json::from_slice(&files[&format!("{}.ocr.json", document)].1).unwrap();
+    assert_eq!(ocr["analyzeResult"]["pages"][0]["words"][0]["content"], "Contoso");
+
+    let router = create_rest_router(harness.service.clone());
+    let (status, _) = send(&router, post_json("/api/v1/corrections/export", json!({}))).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[cfg(feature = "analytics")]
+#[tokio::test]
+async fn test_parquet_export() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let harness = Harness::in_memory().await;
+    let writer = Arc::new(MemoryDatasetWriter::default());
+    let options = RestOptions {
+        admin_api_key: Some("admin-key".to_string()),
+        analytics_export: Some(Arc::new(AnalyticsExportService::new(harness.service.clone(), writer.clone(), "lake"))),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    send(&router, multipart_upload("/api/v1/upload/invoice", "invoice.pdf", b"%PDF-1.4 test")).await;
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    send(&router, get(&results_uri)).await;
+    let (_, body) = send(&router, get(&results_uri)).await;
+    assert_eq!(body["status"], "succeeded");
+
+    let today = chrono::Utc::now().date_naive().to_string();
+    let export = || post_json("/api/v1/admin/exports/parquet", json!({ "from": today }));
+    let (status, _) = send(&router, export()).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = send(&router, with_api_key(export(), "admin-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    let folder = format!("lake/date={}/", today);
+    assert_eq!(body["folders"], json!([folder]));
+    assert_eq!(body["operations"], 1);
+    assert!(body["fields"].as_u64().unwrap() > 0);
+    assert_eq!(body["skipped"], json!([]));
+
+    let files = writer.files.lock().unwrap().clone();
+    let rows = |name: &str| {
+        let (content_type, content) = &files[&format!("{}{}", folder, name)];
+        assert_eq!(content_type, "application/vnd.apache.parquet");
+        SerializedFileReader::new(content.clone()).unwrap().metadata().file_metadata().num_rows()
+    };
+    assert_eq!(rows("operations.parquet"), 1);
+    assert_eq!(rows("fields.parquet") as u64, body["fields"].as_u64().unwrap());
+
+    let (status, _) = send(
+        &router,
+        with_api_key(post_json("/api/v1/admin/exports/parquet", json!({ "from": today, "to": "2000-01-01" })), "admin-key"),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let options = RestOptions { admin_api_key: Some("admin-key".to_string()), ..RestOptions::default() };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+    let (status, _) = send(&router, with_api_key(export(), "admin-key")).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_ndjson_export() {
+    let harness = Harness::in_memory().await;
+    let jobs = Arc::new(InMemoryOperationTracker::new());
+    let exports = Arc::new(ExportService::new(harness.service.clone(), jobs.clone()));
+    let options = RestOptions { exports: Some(exports.clone()), ..RestOptions::default() };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    send(&router, multipart_upload("/api/v1/upload/invoice", "invoice.pdf", b"%PDF-1.4 test")).await;
+    let (_, read) = send(&router, post_json("/api/v1/analyze/read", json!({ "document_url": "https://example.com/doc.pdf" }))).await;
+    for operation_id in [result_id("invoice"), read["operation_id"].as_str().unwrap().to_string()] {
+        let results_uri = format!("/api/v1/results/{}", operation_id);
+        send(&router, get(&results_uri)).await;
+        let (_, body) = send(&router, get(&results_uri)).await;
+        assert_eq!(body["status"], "succeeded");
+    }
+
+    let (status, started) = send(&router, post_json("/api/v1/exports", json!({ "model": "invoice" }))).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(started["status"], "running");
+    assert_eq!(started["filter"]["model"], "prebuilt-invoice");
+    let export = wait_for_export(&router, started["export_id"].as_str().unwrap()).await;
+    assert_eq!(export["status"], "succeeded");
+    assert_eq!(export["results"], 1);
+
+    // The signed link needs no tenant or credentials
+    let response = router.clone().oneshot(get(export["download_url"].as_str().unwrap())).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let lines: Vec<Value> = body
+        .split(|byte| *byte == b'\n')
+        .filter(|line| !line.is_empty())
+        .map(|line| serde_json::from_slice(line).unwrap())
+        .collect();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["operation_id"], result_id("invoice"));
+    assert_eq!(lines[0]["filename"], "invoice.pdf");
+    assert_eq!(lines[0]["result"]["model_id"], "prebuilt-invoice");
+
+    let (_, started) = send(&router, post_json("/api/v1/exports", json!({}))).await;
+    let export = wait_for_export(&router, started["export_id"].as_str().unwrap()).await;
+    assert_eq!(export["results"], 2);
+    let future = (chrono::Utc::now() + chrono::Duration::hours(1)).to_rfc3339();
+    let (_, started) = send(&router, post_json("/api/v1/exports", json!({ "from": future }))).await;
+    let export = wait_for_export(&router, started["export_id"].as_str().unwrap()).await;
+    assert_eq!((export["status"].as_str(), export["results"].as_u64()), (Some("succeeded"), Some(0)));
+
+    // Other tenants don't see the export
+    let mut other = get(&format!("/api/v1/exports/{}", started["export_id"].as_str().unwrap()));
+    other.headers_mut().insert("x-tenant-id", "other".parse().unwrap());
+    let (status, _) = send(&router, other).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let range = json!({ "from": "2024-03-02T00:00:00Z", "to": "2024-03-01T00:00:00Z" });
+    let (status, _) = send(&router, post_json("/api/v1/exports", range)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // An export left running by a stopped instance is failed on recovery
+    let mut orphaned = ExportJob::new(TenantId::default(), ExportFilter::default());
+    orphaned.updated_at = chrono::Utc::now() - chrono::Duration::hours(1);
+    jobs.create_export(&orphaned).await.unwrap();
+    assert_eq!(exports.recover_interrupted().await.unwrap(), 1);
+    let (_, export) = send(&router, get(&format!("/api/v1/exports/{}", orphaned.export_id))).await;
+    assert_eq!(export["status"], "failed");
+    assert_eq!(exports.recover_interrupted().await.unwrap(), 0);
+
+    let router = create_rest_router(harness.service.clone());
+    let (status, _) = send(&router, post_json("/api/v1/exports", json!({}))).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+/// Poll an export until it finishes
+async fn wait_for_export(router: &Router, export_id: &str) -> Value {
+    let uri = format!("/api/v1/exports/{}", export_id);
+    for _ in 0..200 {
+        let (status, export) = send(router, get(&uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        if export["status"] != "running" {
+            return export;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("export {} did not finish", export_id);
+}
+
+#[tokio::test]
+async fn test_operation_pages_through_timestamp_ties() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    // A batch upload: every operation created at the same instant
+    let created_at = "2026-03-01T12:00:00Z".parse().unwrap();
+    let mut created = Vec::new();
+    for i in 0..5 {
+        let operation = AnalysisOperation {
+            created_at,
+            review: (i % 2 == 0).then(|| ReviewState::needs_review(vec!["low confidence".to_string()])),
+            ..AnalysisOperation::new(ModelType::Invoice)
+        };
+        harness.tracker.store_operation(&operation).await.unwrap();
+        created.push(operation.operation_id);
+    }
+
+    let listed = |route: &'static str| {
+        let router = router.clone();
+        async move {
+            let mut ids = Vec::new();
+            let mut uri = format!("{}?limit=2", route);
+            loop {
+                let (status, body) = send(&router, get(&uri)).await;
+                assert_eq!(status, StatusCode::OK);
+                ids.extend(body["operations"].as_array().unwrap().iter().map(|op| op["operation_id"].as_str().unwrap().to_string()));
+                match (body["next_before"].as_str(), body["next_before_id"].as_str()) {
+                    (Some(before), Some(before_id)) => {
+                        uri = format!("{}?limit=2&before={}&before_id={}", route, before, before_id)
+                    }
+                    _ => break ids,
+                }
+            }
+        }
+    };
+
+    let mut expected = created.clone();
+    expected.sort_by(|a, b| b.cmp(a));
+    assert_eq!(listed("/api/v1/operations").await, expected);
+
+    let mut expected: Vec<String> = created.iter().step_by(2).cloned().collect();
+    expected.sort_by(|a, b| b.cmp(a));
+    assert_eq!(listed("/api/v1/review-queue").await, expected);
+}
+
+#[cfg(feature = "graphql")]
+#[tokio::test]
+async fn test_graphql_operations_page_through_timestamp_ties() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let created_at = "2026-03-01T12:00:00Z".parse().unwrap();
+    let mut created = Vec::new();
+    for _ in 0..5 {
+        let operation = AnalysisOperation { created_at, ..AnalysisOperation::new(ModelType::Invoice) };
+        harness.tracker.store_operation(&operation).await.unwrap();
+        created.push(operation.operation_id);
+    }
+
+    let mut ids = Vec::new();
+    let mut cursor = String::new();
+    loop {
+        let query = format!("{{ operations(limit: 2{}) {{ id createdAt }} }}", cursor);
+        let (_, body) = send(&router, post_json("/graphql", json!({ "query": query }))).await;
+        let page = body["data"]["operations"].as_array().unwrap().clone();
+        ids.extend(page.iter().map(|op| op["id"].as_str().unwrap().to_string()));
+        match page.last() {
+            Some(last) if page.len() == 2 => {
+                cursor = format!(", before: {}, beforeId: {}", last["createdAt"], last["id"])
+            }
+            _ => break,
+        }
+    }
+    let mut expected = created;
+    expected.sort_by(|a, b| b.cmp(a));
+    assert_eq!(ids, expected);
+}
+
+#[cfg(feature = "graphql")]
+#[tokio::test]
+async fn test_graphql_query() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let query = format!(
+        r#"{{ operation(id: "{}") {{
+            id status modelType
+            result {{
+                keyValuePairs(key: "vendorname") {{ key value }}
+                documents(docType: "invoice") {{ docType fields(names: ["VendorName"]) {{ name value }} }}
+                fields(minConfidence: 0.95) {{ docType name }}
+                normalizedFields(key: "invoicetotal")
+                pages(numbers: [1]) {{ pageNumber words(minConfidence: 0.0) {{ content }} }}
+            }}
+        }} }}"#,
+        result_id("invoice")
+    );
+    let (status, body) = send(&router, post_json("/graphql", json!({ "query": query }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["operation"]["status"], "RUNNING");
+    assert_eq!(body["data"]["operation"]["result"], Value::Null);
+
+    let (_, body) = send(&router, post_json("/graphql", json!({ "query": query }))).await;
+    let operation = &body["data"]["operation"];
+    assert_eq!(operation["id"], result_id("invoice"));
+    assert_eq!(operation["status"], "SUCCEEDED");
+    assert_eq!(operation["modelType"], "INVOICE");
+    let result = &operation["result"];
+    assert_eq!(result["keyValuePairs"], json!([{ "key": "VendorName", "value": "Contoso Ltd." }]));
+    assert_eq!(result["documents"][0]["fields"][0]["name"], "VendorName");
+    assert_eq!(result["documents"][0]["fields"][0]["value"], json!({ "type": "string", "value": "Contoso Ltd." }));
+    assert_eq!(result["fields"].as_array().unwrap().len(), 5);
+    assert_eq!(
+        result["normalizedFields"],
+        json!({ "InvoiceTotal": { "kind": "amount", "value": "110.00", "currency": "USD" } })
+    );
+    assert_eq!(result["pages"][0]["pageNumber"], 1);
+    assert!(!result["pages"][0]["words"].as_array().unwrap().is_empty());
+    // Only what was selected comes back
+    assert!(result.get("content").is_none());
+
+    let (_, body) = send(
+        &router,
+        post_json("/graphql", json!({ "query": "{ operations(status: SUCCEEDED) { id } missing: operation(id: \"nope\") { id } }" })),
+    )
+    .await;
+    assert_eq!(body["data"]["operations"], json!([{ "id": result_id("invoice") }]));
+    assert_eq!(body["data"]["missing"], Value::Null);
+
+    let query = format!(r#"{{ operation(id: "{}") {{ result {{ fields(minConfidence: 2) {{ name }} }} }} }}"#, result_id("invoice"));
+    let (_, body) = send(&router, post_json("/graphql", json!({ "query": query }))).await;
+    assert!(body["errors"][0]["message"].as_str().unwrap().contains("confidence"));
+
+    let (status, _) = send(&router, get("/graphql")).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_hocr_and_alto_export() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/read", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let results_uri = format!("/api/v1/results/{}", result_id("read"));
+    send(&router, get(&results_uri)).await;
+
+    for (format, content_type, marker) in [
+        ("hocr", "application/xhtml+xml; charset=utf-8", "class=\"ocrx_word\""),
+        ("alto", "application/xml; charset=utf-8", "<String ID=\"string_1_1_1\" CONTENT=\"Contoso\""),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(get(&format!("{}/{}", results_uri, format)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", format);
+        assert_eq!(response.headers()["content-type"], content_type);
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let document = String::from_utf8(body.to_vec()).unwrap();
+        assert!(document.starts_with("<?xml"), "{}", format);
+        assert!(document.contains(marker), "{}: {}", format, document);
+    }
+}
+
+#[tokio::test]
+async fn test_invoice_xml_export() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    for model in ["invoice", "read"] {
+        send(
+            &router,
+            post_json(&format!("/api/v1/analyze/{}", model), json!({ "document_url": "https://example.com/doc.pdf" })),
+        )
+        .await;
+        send(&router, get(&format!("/api/v1/results/{}", result_id(model)))).await;
+    }
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+
+    // The fixture names no customer, which both formats require
+    let (status, body) = send(&router, get(&format!("{}/invoice.xml", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("CustomerName"), "{}", body);
+    let corrections = json!({ "corrections": [{ "field": "CustomerName", "value": "Fabrikam Inc." }] });
+    let (status, _) = send(&router, post_json(&format!("{}/corrections", results_uri), corrections)).await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    for (format, marker) in [
+        ("ubl", "<cbc:PayableAmount currencyID=\"USD\">110.00</cbc:PayableAmount>"),
+        ("zugferd", "<ram:DuePayableAmount>110.00</ram:DuePayableAmount>"),
+    ] {
+        let response = router
+            .clone()
+            .oneshot(get(&format!("{}/invoice.xml?format={}", results_uri, format)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK, "{}", format);
+        assert_eq!(response.headers()["content-type"], "application/xml; charset=utf-8");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let document = String::from_utf8(body.to_vec()).unwrap();
+        assert!(document.contains("INV-100"), "{}", format);
+        assert!(document.contains("Fabrikam Inc."), "{}", format);
+        assert!(document.contains(marker), "{}: {}", format, document);
+    }
+
+    let (status, _) = send(&router, get(&format!("{}/invoice.xml?format=pdf", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let read_uri = format!("/api/v1/results/{}/invoice.xml", result_id("read"));
+    let (status, _) = send(&router, get(&read_uri)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[cfg(feature = "pdf")]
+#[tokio::test]
+async fn test_redacted_pdf() {
+    use image::{DynamicImage, ImageFormat, Rgb, RgbImage};
+
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    // One pixel per hundredth of an inch of the fixture's 8.5 x 11 inch page
+    let mut png = std::io::Cursor::new(Vec::new());
+    DynamicImage::ImageRgb8(RgbImage::from_pixel(85, 110, Rgb([255, 255, 255])))
+        .write_to(&mut png, ImageFormat::Png)
+        .unwrap();
+    let boundary = "adi-boundary";
+    let mut body = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"scan.png\"\r\n\
+         Content-Type: image/png\r\n\r\n",
+        b = boundary
+    )
+    .into_bytes();
+    body.extend_from_slice(&png.into_inner());
+    body.extend_from_slice(format!("\r\n--{}--\r\n", boundary).as_bytes());
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/read")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(body))
+        .unwrap();
+    let (status, _) = send(&router, request).await;
+    assert_eq!(status, StatusCode::OK);
+    let results_uri = format!("/api/v1/results/{}", result_id("read"));
+    send(&router, get(&results_uri)).await;
+
+    let (status, _) = send(&router, get(&format!("{}/redacted-pdf", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, body) = send(&router, get(&format!("{}/redacted-pdf?pii=passport", results_uri))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("passport"));
+
+    let response = router
+        .clone()
+        .oneshot(get(&format!("{}/redacted-pdf?pii=email&regions=1:0,0,4.25,11", results_uri)))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "application/pdf");
+    let location = response.headers()["content-location"].to_str().unwrap().to_string();
+    assert!(location.starts_with("/api/v1/documents/"));
+    let pdf = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(pdf.starts_with(b"%PDF-"));
+
+    // The redacted copy is stored alongside the original
+    let response = router.clone().oneshot(get(&location)).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let stored = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert_eq!(stored, pdf);
+
+    // Operations on a URL have no stored document to redact
+    send(
+        &router,
+        post_json("/api/v1/analyze/layout", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let layout_uri = format!("/api/v1/results/{}", result_id("layout"));
+    send(&router, get(&layout_uri)).await;
+    send(&router, get(&layout_uri)).await;
+    let (status, body) = send(&router, get(&format!("{}/redacted-pdf?regions=1:0,0,1,1", layout_uri))).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(body["error"].as_str().unwrap().contains("no stored document"));
+}
+
+#[tokio::test]
+async fn test_analyze_auto_routes_by_classification() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let (status, submitted) =
+        send(&router, multipart_upload("/api/v1/upload/auto", "ACME invoice 0042.pdf", b"%PDF-1.4 auto")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(submitted["operation_id"], result_id("invoice"));
+    assert_eq!(submitted["routing"]["method"], "heuristic");
+    assert_eq!(submitted["routing"]["doc_type"], "invoice");
+    assert_eq!(submitted["routing"]["model_id"], "prebuilt-invoice");
+    assert_eq!(submitted["routing"]["fallback"], false);
+
+    // The decision stays on the operation through polling
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    send(&router, get(&results_uri)).await;
+    let (_, done) = send(&router, get(&results_uri)).await;
+    assert_eq!(done["status"], "succeeded");
+    assert_eq!(done["routing"]["model_id"], "prebuilt-invoice");
+
+    // Nothing recognizable takes the fallback route
+    let (status, submitted) = send(
+        &router,
+        post_json("/api/v1/analyze/auto", json!({ "document_url": "https://example.com/scans/0042.pdf" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(submitted["operation_id"], result_id("layout"));
+    assert_eq!(submitted["routing"]["model_id"], "prebuilt-layout");
+    assert_eq!(submitted["routing"]["fallback"], true);
+    assert!(submitted["routing"].get("doc_type").is_none());
+}
+
+#[tokio::test]
+async fn test_pipeline_runs() {
+    let harness = Harness::in_memory().await;
+    Mock::given(method("POST"))
+        .and(path("/hooks/invoices"))
+        .respond_with(ResponseTemplate::new(200))
+        .mount(&harness.stub.server)
+        .await;
+    let definitions = parse_pipelines(&format!(
+        r#"{{
+            "invoices": [
+                {{"step": "classify"}},
+                {{"step": "analyze"}},
+                {{"step": "validate", "required_fields": ["InvoiceTotal", "VendorName"], "min_confidence": 0.9}},
+                {{"step": "publish_event"}},
+                {{"step": "webhook", "url": "{}/hooks/invoices"}}
+            ],
+            "purchase-orders": [
+                {{"step": "analyze", "model": "prebuilt-invoice"}},
+                {{"step": "validate", "required_fields": ["PurchaseOrder"]}},
+                {{"step": "publish_event"}}
+            ]
+        }}"#,
+        harness.stub.server.uri()
+    ))
+    .unwrap();
+    let publisher = Arc::new(RecordingPublisher::default());
+    let runs = Arc::new(InMemoryOperationTracker::new());
+    let pipelines = PipelineService::new(harness.service.clone(), runs.clone(), definitions.clone())
+        .with_event_publisher(publisher.clone())
+        .with_webhooks(Arc::new(HttpWebhookSender::new(Duration::from_secs(5), None).unwrap()))
+        .with_polling(Duration::from_millis(10), Duration::from_secs(5));
+    let pipelines = Arc::new(pipelines);
+    let options = RestOptions {
+        pipelines: Some(pipelines.clone()),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    let (_, listed) = send(&router, get("/api/v1/pipelines")).await;
+    assert_eq!(listed["pipelines"], json!(["invoices", "purchase-orders"]));
+
+    let (status, started) =
+        send(&router, multipart_upload("/api/v1/pipelines/invoices/run", "invoice.pdf", b"%PDF-1.4 pipeline")).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(started["pipeline"], "invoices");
+    let run = wait_for_run(&router, "invoices", started["run_id"].as_str().unwrap()).await;
+    assert_eq!(run["status"], "succeeded", "{}", run);
+    assert_eq!(run["operation_id"], result_id("invoice"));
+    let steps: Vec<&str> = run["steps"].as_array().unwrap().iter().map(|step| step["status"].as_str().unwrap()).collect();
+    assert_eq!(steps, vec!["succeeded"; 5]);
+    assert_eq!(run["steps"][0]["detail"], "invoice (heuristic) routes to prebuilt-invoice");
+
+    let events = publisher.events.lock().unwrap().clone();
+    let processed = events.iter().find(|event| event.event_type == LifecycleEventKind::Processed).unwrap();
+    assert_eq!(processed.pipeline.as_deref(), Some("invoices"));
+    assert_eq!(processed.summary.as_ref().unwrap().document_types, vec!["invoice"]);
+    let hooks = harness.stub.server.received_requests().await.unwrap();
+    let hook = hooks.iter().find(|request| request.url.path() == "/hooks/invoices").unwrap();
+    let payload: Value = serde_json::from_slice(&hook.body).unwrap();
+    assert_eq!(payload["run"]["run_id"], run["run_id"]);
+    assert_eq!(payload["routing"]["model_id"], "prebuilt-invoice");
+
+    // A failed validation skips the remaining steps
+    let (status, started) = send(
+        &router,
+        post_json("/api/v1/pipelines/purchase-orders/run", json!({ "document_url": "https://example.com/po.pdf" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    let run = wait_for_run(&router, "purchase-orders", started["run_id"].as_str().unwrap()).await;
+    assert_eq!(run["status"], "failed");
+    assert_eq!(run["steps"][1]["status"], "failed");
+    assert!(run["steps"][1]["detail"].as_str().unwrap().contains("missing field PurchaseOrder"));
+    assert_eq!(run["steps"][2]["status"], "skipped");
+
+    let (status, _) = send(&router, post_json("/api/v1/pipelines/unknown/run", json!({ "document_url": "https://example.com/a.pdf" }))).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let uri = format!("/api/v1/pipelines/invoices/runs/{}", started["run_id"].as_str().unwrap());
+    let (status, _) = send(&router, get(&uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // A run left running by a stopped instance fails at the step it reached
+    let mut orphaned = PipelineRun::new(&definitions[0], TenantId::default());
+    orphaned.start_step(0);
+    orphaned.updated_at = chrono::Utc::now() - chrono::Duration::hours(1);
+    runs.create_run(&orphaned).await.unwrap();
+    assert_eq!(pipelines.recover_interrupted().await.unwrap(), 1);
+    let run = wait_for_run(&router, "invoices", &orphaned.run_id).await;
+    assert_eq!(run["status"], "failed");
+    assert_eq!(run["steps"][0]["status"], "failed");
+    assert_eq!(run["steps"][4]["status"], "skipped");
+    assert_eq!(pipelines.recover_interrupted().await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_pipeline_run_interrupted_by_shutdown() {
+    let harness = Harness::in_memory().await;
+    Mock::given(method("POST"))
+        .and(path("/hooks/slow"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+        .mount(&harness.stub.server)
+        .await;
+    let definitions = parse_pipelines(&format!(
+        r#"{{"slow": [{{"step": "analyze"}}, {{"step": "webhook", "url": "{}/hooks/slow"}}]}}"#,
+        harness.stub.server.uri()
+    ))
+    .unwrap();
+    let supervisor = TaskSupervisor::new();
+    let pipelines = PipelineService::new(harness.service.clone(), Arc::new(InMemoryOperationTracker::new()), definitions)
+        .with_supervisor(supervisor.clone())
+        .with_webhooks(Arc::new(HttpWebhookSender::new(Duration::from_secs(60), None).unwrap()))
+        .with_polling(Duration::from_millis(10), Duration::from_secs(5));
+    let options = RestOptions { pipelines: Some(Arc::new(pipelines)), ..RestOptions::default() };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    let (_, started) =
+        send(&router, post_json("/api/v1/pipelines/slow/run", json!({ "document_url": "https://example.com/a.pdf" }))).await;
+    let uri = format!("/api/v1/pipelines/slow/runs/{}", started["run_id"].as_str().unwrap());
+    for _ in 0..200 {
+        let (_, run) = send(&router, get(&uri)).await;
+        if run["steps"][1]["status"] == "running" {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    assert!(supervisor.shutdown(Duration::from_secs(5)).await);
+    let (_, run) = send(&router, get(&uri)).await;
+    assert_eq!(run["status"], "failed");
+    assert_eq!(run["steps"][1]["detail"], "Interrupted by shutdown");
+}
+
+/// Poll a pipeline run until it finishes
+async fn wait_for_run(router: &Router, pipeline: &str, run_id: &str) -> Value {
+    let uri = format!("/api/v1/pipelines/{}/runs/{}", pipeline, run_id);
+    for _ in 0..200 {
+        let (status, run) = send(router, get(&uri)).await;
+        assert_eq!(status, StatusCode::OK);
+        if run["status"] != "running" {
+            return run;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("pipeline run {} did not finish", run_id);
+}
+
+#[tokio::test]
+async fn test_operation_events() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    send(
+        &router,
+        post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..3 {
+        send(&router, get(&result_uri)).await;
+    }
+
+    let (status, body) = send(&router, get(&format!("/api/v1/operations/{}/events", result_id("invoice")))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["operation_id"], result_id("invoice"));
+    let kinds: Vec<&str> = body["events"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|event| event["kind"].as_str().unwrap())
+        .collect();
+    assert_eq!(kinds, ["submitted", "running", "succeeded"]);
+    assert_eq!(body["events"][0]["detail"], "prebuilt-invoice");
+
+    let (status, _) = send(&router, get("/api/v1/operations/unknown/events")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+fn with_api_key(mut request: Request<Body>, key: &str) -> Request<Body> {
+    request.headers_mut().insert("x-api-key", key.parse().unwrap());
+    request
+}
+
+#[tokio::test]
+async fn test_tenant_isolation() {
+    let harness = Harness::in_memory().await;
+    let options = RestOptions {
+        tenants: TenantResolver::new([
+            ("acme-key".to_string(), TenantId::new("acme").unwrap()),
+            ("globex-key".to_string(), TenantId::new("globex").unwrap()),
+        ]),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    let submit = post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" }));
+    let (status, _) = send(&router, submit).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let submit = post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" }));
+    let (status, _) = send(&router, with_api_key(submit, "acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..2 {
+        let (status, _) = send(&router, with_api_key(get(&result_uri), "acme-key")).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let (status, body) = send(&router, with_api_key(get(&result_uri), "acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "succeeded");
+
+    // Another tenant can neither read the result nor see it exists
+    let (status, _) = send(&router, with_api_key(get(&result_uri), "globex-key")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let events_uri = format!("/api/v1/operations/{}/events", result_id("invoice"));
+    let (status, _) = send(&router, with_api_key(get(&events_uri), "globex-key")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let mut request = with_api_key(get(&result_uri), "globex-key");
+    request.headers_mut().insert("x-tenant-id", "acme".parse().unwrap());
+    let (status, _) = send(&router, request).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = send(&router, with_api_key(get("/api/v1/operations"), "acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["tenant_id"], "acme");
+    assert_eq!(body["operations"].as_array().unwrap().len(), 1);
+    assert_eq!(body["operations"][0]["operation_id"], result_id("invoice"));
+    let (_, body) = send(&router, with_api_key(get("/api/v1/operations?status=running"), "acme-key")).await;
+    assert!(body["operations"].as_array().unwrap().is_empty());
+    let (_, body) = send(&router, with_api_key(get("/api/v1/operations"), "globex-key")).await;
+    assert!(body["operations"].as_array().unwrap().is_empty());
+    let (status, _) = send(&router, with_api_key(get("/api/v1/operations?limit=0"), "acme-key")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Work queues only lease the caller's own operations
+    let claim = || post_json("/api/v1/export/claim", json!({ "worker_id": "w" }));
+    let (_, body) = send(&router, with_api_key(claim(), "globex-key")).await;
+    assert_eq!(body["items"], json!([]));
+    let (_, body) = send(&router, with_api_key(claim(), "acme-key")).await;
+    assert_eq!(body["items"][0]["operation_id"], result_id("invoice"));
+    for action in ["heartbeat", "complete", "release"] {
+        let uri = format!("/api/v1/export/{}/{}", result_id("invoice"), action);
+        let (status, _) = send(&router, with_api_key(post_json(&uri, json!({ "worker_id": "w" })), "globex-key")).await;
+        assert_eq!(status, StatusCode::CONFLICT, "{} of another tenant's lease", action);
+    }
+    let complete_uri = format!("/api/v1/export/{}/complete", result_id("invoice"));
+    let (status, _) = send(&router, with_api_key(post_json(&complete_uri, json!({ "worker_id": "w" })), "acme-key")).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+}
+
+#[tokio::test]
+async fn test_operation_stats() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    for model in ["invoice", "layout"] {
+        let submit = post_json(&format!("/api/v1/analyze/{}", model), json!({ "document_url": "https://example.com/doc.pdf" }));
+        let (status, _) = send(&router, submit).await;
+        assert_eq!(status, StatusCode::OK);
+    }
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..3 {
+        send(&router, get(&result_uri)).await;
+    }
+
+    let (status, body) = send(&router, get("/api/v1/operations/stats?group_by=model")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["group_by"], "model");
+    let groups = body["groups"].as_array().unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0]["key"], "prebuilt-invoice");
+    assert_eq!(groups[0]["succeeded"], 1);
+    assert_eq!(groups[0]["failure_ratio"], 0.0);
+    assert!(groups[0]["median_completion_secs"].is_number());
+    assert_eq!(groups[1]["key"], "prebuilt-layout");
+    assert!(groups[1]["median_completion_secs"].is_null(), "unfinished operations are not timed");
+
+    let (_, body) = send(&router, get("/api/v1/operations/stats")).await;
+    assert_eq!(body["group_by"], "day");
+    assert_eq!(body["groups"][0]["key"], chrono::Utc::now().format("%Y-%m-%d").to_string());
+    assert_eq!(body["groups"][0]["operations"], 2);
+
+    let (_, body) = send(&router, get("/api/v1/operations/stats?group_by=status&from=2999-01-01T00:00:00Z")).await;
+    assert!(body["groups"].as_array().unwrap().is_empty());
+    let (status, _) = send(&router, get("/api/v1/operations/stats?group_by=tenant")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let range = "from=2024-03-02T00:00:00Z&to=2024-03-01T00:00:00Z";
+    let (status, _) = send(&router, get(&format!("/api/v1/operations/stats?{}", range))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_soft_delete_and_restore() {
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+
+    let submit = post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" }));
+    send(&router, submit).await;
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..3 {
+        send(&router, get(&result_uri)).await;
+    }
+    let operation_uri = format!("/api/v1/operations/{}", result_id("invoice"));
+
+    let (status, _) = send(&router, bodyless(Method::DELETE, &operation_uri)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&router, get(&result_uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&router, bodyless(Method::DELETE, &operation_uri)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (_, body) = send(&router, get("/api/v1/operations")).await;
+    assert!(body["operations"].as_array().unwrap().is_empty());
+    let (_, body) = send(&router, get("/api/v1/operations/stats")).await;
+    assert!(body["groups"].as_array().unwrap().is_empty());
+    let (_, body) = send(&router, get("/api/v1/operations?include_deleted=true")).await;
+    assert!(body["operations"][0]["deleted_at"].is_string());
+
+    let (status, body) = send(&router, bodyless(Method::POST, &format!("{}/restore", operation_uri))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["operation_id"], result_id("invoice"));
+    assert!(body.get("deleted_at").is_none());
+    let (status, body) = send(&router, get(&result_uri)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "succeeded");
+
+    let (status, _) = send(&router, bodyless(Method::POST, "/api/v1/operations/unknown/restore")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_audit_log() {
+    let audit_log = Arc::new(InMemoryOperationTracker::new());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        audit_log: Some(audit_log.clone()),
+        ..Default::default()
+    })
+    .await;
+    let options = RestOptions {
+        admin_api_key: Some("admin-key".to_string()),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    let mut request = post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" }));
+    request.headers_mut().insert("x-api-key", "client-key".parse().unwrap());
+    let (status, _) = send(&router, request).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = send(&router, post_json("/api/v1/analyze/read", json!({ "document_url": "ftp://x" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    // Reads are not audited
+    send(&router, get(&format!("/api/v1/results/{}", result_id("invoice")))).await;
+
+    let (status, _) = send(&router, get("/api/v1/admin/audit")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let admin_get = |uri: &str| {
+        Request::builder()
+            .uri(uri)
+            .header("x-api-key", "admin-key")
+            .body(Body::empty())
+            .unwrap()
+    };
+    let (status, body) = send(&router, admin_get("/api/v1/admin/audit")).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = body["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["action"], "POST /api/v1/analyze/read");
+    assert_eq!(entries[0]["outcome"], "failure");
+    assert_eq!(entries[0]["status"], "400");
+    assert_eq!(entries[0]["principal"], "anonymous");
+    assert_eq!(entries[1]["action"], "POST /api/v1/analyze/invoice");
+    assert_eq!(entries[1]["outcome"], "success");
+    assert_eq!(entries[1]["model"], "prebuilt-invoice");
+    assert_eq!(entries[1]["operation_id"], result_id("invoice"));
+    let principal = entries[1]["principal"].as_str().unwrap();
+    assert!(principal.starts_with("key:") && !principal.contains("client-key"));
+
+    let (_, body) = send(&router, admin_get(&format!("/api/v1/admin/audit?principal={}", principal))).await;
+    assert_eq!(body["entries"].as_array().unwrap().len(), 1);
+    let (status, _) = send(&router, admin_get("/api/v1/admin/audit?limit=0")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Uploads record the hash of the submitted document
+    let (status, _) = send(&router, multipart_upload("/api/v1/upload/read", "a.pdf", b"%PDF-1.4 audit")).await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = send(&router, admin_get("/api/v1/admin/audit?limit=1")).await;
+    assert_eq!(body["entries"][0]["document_sha256"].as_str().map(str::len), Some(64));
+}
+
+#[tokio::test]
+async fn test_operation_metrics() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        operation_metrics: Some(Arc::new(PrometheusOperationMetrics)),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+    let as_tenant = |mut request: Request<Body>| {
+        request.headers_mut().insert("x-tenant-id", "metrics-e2e".parse().unwrap());
+        request
+    };
+
+    send(&router, as_tenant(multipart_upload("/api/v1/upload/invoice", "invoice.pdf", b"%PDF-1.4 test"))).await;
+    let results_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    send(&router, as_tenant(get(&results_uri))).await;
+    let (_, body) = send(&router, as_tenant(get(&results_uri))).await;
+    assert_eq!(body["status"], "succeeded");
+    // Only the poll that sees the operation finish counts it
+    send(&router, as_tenant(get(&results_uri))).await;
+
+    let response = router.clone().oneshot(get("/metrics")).await.unwrap();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let metrics = String::from_utf8(body.to_vec()).unwrap();
+    let labels = "model=\"prebuilt-invoice\",outcome=\"accepted\",tenant=\"metrics-e2e\"";
+    assert!(metrics.contains(&format!("adi_analysis_submit_duration_seconds_count{{{}}} 1", labels)), "{}", metrics);
+    let labels = "model=\"prebuilt-invoice\",status=\"succeeded\",tenant=\"metrics-e2e\"";
+    assert!(metrics.contains(&format!("adi_operations_completed_total{{{}}} 1", labels)));
+    assert!(metrics.contains(&format!("adi_analysis_duration_seconds_count{{{}}} 1", labels)));
+}
+
+#[tokio::test]
+async fn test_usage_report() {
+    let meter = Arc::new(InMemoryOperationTracker::new());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        usage_meter: Some(meter),
+        ..Default::default()
+    })
+    .await;
+    let options = RestOptions {
+        admin_api_key: Some("admin-key".to_string()),
+        tenants: TenantResolver::new([
+            ("acme-key".to_string(), TenantId::new("acme").unwrap()),
+            ("globex-key".to_string(), TenantId::new("globex").unwrap()),
+        ]),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+
+    let submit = post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" }));
+    let (status, _) = send(&router, with_api_key(submit, "acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    // Terminal results are re-served from the tracker and must not be metered twice
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..4 {
+        send(&router, with_api_key(get(&result_uri), "acme-key")).await;
+    }
+
+    let (status, body) = send(&router, with_api_key(get("/api/v1/usage"), "acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["tenant_id"], "acme");
+    assert_eq!(body["currency"], "USD");
+    let usage = body["usage"].as_array().unwrap();
+    assert_eq!(usage.len(), 1);
+    assert_eq!(usage[0]["model"], "prebuilt-invoice");
+    assert_eq!(usage[0]["operations"], 1);
+    let pages = usage[0]["pages"].as_u64().unwrap();
+    assert!(pages > 0);
+    assert_eq!(body["total_pages"], pages);
+    assert!(body["total_estimated_cost"].as_f64().unwrap() > 0.0);
+
+    // Tenants only see their own usage; the admin key sees everyone's
+    let (_, body) = send(&router, with_api_key(get("/api/v1/usage"), "globex-key")).await;
+    assert!(body["usage"].as_array().unwrap().is_empty());
+    let (status, _) = send(&router, with_api_key(get("/api/v1/usage?tenant=acme"), "globex-key")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = send(&router, with_api_key(get("/api/v1/usage"), "admin-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.get("tenant_id").is_none());
+    assert_eq!(body["usage"][0]["tenant_id"], "acme");
+
+    let (status, _) = send(&router, with_api_key(get("/api/v1/usage?from=2024-02-01&to=2024-01-01"), "acme-key")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_log_level_endpoint() {
+    let harness = Harness::in_memory().await;
+    let (control, _layer) = LogLevelControl::new(EnvFilter::new("info"));
+    let options = RestOptions {
+        admin_api_key: Some("admin-key".to_string()),
+        log_level: Some(control.clone()),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+    let set = |key: &str, filter: &str| {
+        Request::builder()
+            .method(Method::PUT)
+            .uri("/api/v1/admin/log-level")
+            .header("x-api-key", key)
+            .header("content-type", "application/json")
+            .body(Body::from(json!({ "filter": filter }).to_string()))
+            .unwrap()
+    };
+
+    let (status, body) = send(&router, with_api_key(get("/api/v1/admin/log-level"), "admin-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["filter"], "info");
+
+    let (status, _) = send(&router, set("wrong-key", "debug")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(control.current(), "info");
+
+    let (status, body) = send(&router, set("admin-key", "info,adi_svc=debug")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["filter"].as_str().unwrap().contains("adi_svc=debug"));
+    assert!(control.current().contains("adi_svc=debug"));
+
+    let (status, _) = send(&router, set("admin-key", "adi_svc=loud")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_quotas() {
+    let tracker = Arc::new(InMemoryOperationTracker::new());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        usage_meter: Some(tracker.clone()),
+        quotas: Some(tracker),
+        ..Default::default()
+    })
+    .await;
+    let options = RestOptions {
+        admin_api_key: Some("admin-key".to_string()),
+        tenants: TenantResolver::new([
+            ("acme-key".to_string(), TenantId::new("acme").unwrap()),
+            ("globex-key".to_string(), TenantId::new("globex").unwrap()),
+        ]),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+    let admin = |method: Method, uri: &str, body: Option<Value>| {
+        let body = body.map(|body| Body::from(body.to_string())).unwrap_or_else(Body::empty);
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-api-key", "admin-key")
+            .header("content-type", "application/json")
+            .body(body)
+            .unwrap()
+    };
+    let submit = |key: &str| {
+        with_api_key(
+            post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" })),
+            key,
+        )
+    };
+
+    // Admin routes authenticate as gRPC does: unknown keys are unauthenticated, tenants lack the role
+    let (status, _) = send(&router, with_api_key(get("/api/v1/admin/quotas"), "stolen-key")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = send(&router, with_api_key(get("/api/v1/admin/quotas"), "acme-key")).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = send(
+        &router,
+        admin(Method::PUT, "/api/v1/admin/quotas/acme/daily", Some(json!({ "max_operations": 1 }))),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["period"], "daily");
+    assert_eq!(body["max_operations"], 1);
+    for (uri, limits) in [
+        ("/api/v1/admin/quotas/acme/weekly", json!({ "max_pages": 10 })),
+        ("/api/v1/admin/quotas/acme/monthly", json!({})),
+    ] {
+        let (status, _) = send(&router, admin(Method::PUT, uri, Some(limits))).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    // The first operation is counted once it has been metered
+    let (status, _) = send(&router, submit("acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..2 {
+        send(&router, with_api_key(get(&result_uri), "acme-key")).await;
+    }
+
+    let response = router.clone().oneshot(submit("acme-key")).await.unwrap();
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: i64 = response.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((0..=86_400).contains(&retry_after));
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(body["code"], "quota_exceeded");
+    assert!(body["resets_at"].is_string());
+    let (status, _) = send(&router, submit("globex-key")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = send(&router, admin(Method::GET, "/api/v1/admin/quotas?tenant=acme", None)).await;
+    assert_eq!(status, StatusCode::OK);
+    let quotas = body["quotas"].as_array().unwrap();
+    assert_eq!(quotas.len(), 1);
+    assert_eq!(quotas[0]["tenant_id"], "acme");
+    assert_eq!(quotas[0]["used_operations"], 1);
+    assert!(quotas[0]["used_pages"].as_u64().unwrap() > 0);
+
+    let (status, _) = send(&router, admin(Method::DELETE, "/api/v1/admin/quotas/acme/daily", None)).await;
+    assert_eq!(status, StatusCode::NO_CONTENT);
+    let (status, _) = send(&router, admin(Method::DELETE, "/api/v1/admin/quotas/acme/daily", None)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    let (status, _) = send(&router, submit("acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Quotas from configuration apply alongside the stored ones, and reload in place
+    let configured = vec![Quota::new(TenantId::new("globex").unwrap(), QuotaPeriod::Monthly, Some(0), None).unwrap()];
+    assert!(harness.service.set_configured_quotas(configured.clone()));
+    assert!(!harness.service.set_configured_quotas(configured));
+    let (status, _) = send(&router, submit("globex-key")).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    let (status, _) = send(&router, submit("acme-key")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(harness.service.set_configured_quotas(Vec::new()));
+    let (status, _) = send(&router, submit("globex-key")).await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_lifecycle_events() {
+    let publisher = Arc::new(RecordingPublisher::default());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        event_publisher: Some(publisher.clone()),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    let submit = post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" }));
+    let (status, _) = send(&router, submit).await;
+    assert_eq!(status, StatusCode::OK);
+    // Polls past completion are served from the tracker and announce nothing new
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..3 {
+        send(&router, get(&result_uri)).await;
+    }
+
+    let events = publisher.events.lock().unwrap().clone();
+    let kinds: Vec<&str> = events.iter().map(|event| event.event_type.as_str()).collect();
+    assert_eq!(kinds, ["created", "succeeded"]);
+    assert!(events.iter().all(|event| event.operation_id == result_id("invoice")));
+    assert!(events.iter().all(|event| event.model == "prebuilt-invoice" && event.tenant_id.is_default()));
+    assert!(events[0].summary.is_none());
+    let summary = events[1].summary.as_ref().unwrap();
+    assert!(summary.pages > 0);
+    assert_eq!(summary.document_types, ["invoice"]);
+    assert_ne!(events[0].event_id, events[1].event_id);
+}
+
+#[tokio::test]
+async fn test_search_indexing() {
+    let index = Arc::new(RecordingIndex::default());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        search_index: Some(index.clone()),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    let submit = post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" }));
+    let (status, _) = send(&router, submit).await;
+    assert_eq!(status, StatusCode::OK);
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..3 {
+        send(&router, get(&result_uri)).await;
+    }
+
+    // Indexed once on success, with the content and key fields
+    let documents = index.documents.lock().unwrap().clone();
+    assert_eq!(documents.len(), 1);
+    assert_eq!(documents[0].operation_id, result_id("invoice"));
+    assert_eq!(documents[0].model, "prebuilt-invoice");
+    assert_eq!(documents[0].content, fixture_content("invoice"));
+    assert_eq!(documents[0].doc_types, ["invoice"]);
+    assert!(documents[0].fields.contains_key("InvoiceTotal"));
+
+    // Corrections are pushed again
+    let corrections = json!({ "corrections": [{ "field": "PurchaseOrder", "value": "PO-7" }] });
+    let (status, _) = send(&router, post_json(&format!("{}/corrections", result_uri), corrections)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    let documents = index.documents.lock().unwrap().clone();
+    assert_eq!(documents.len(), 2);
+    assert_eq!(documents[1].fields["PurchaseOrder"], "PO-7");
+}
+
+#[tokio::test]
+async fn test_embeddings() {
+    let store = Arc::new(RecordingChunks::default());
+    let harness = Harness::in_memory_with(HarnessOptions {
+        embeddings: Some((Arc::new(WordEmbeddings), store.clone(), ChunkingPolicy::new(16, 8).unwrap())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    let submit = post_json("/api/v1/analyze/invoice", json!({ "document_url": "https://example.com/doc.pdf" }));
+    let (status, _) = send(&router, submit).await;
+    assert_eq!(status, StatusCode::OK);
+    let result_uri = format!("/api/v1/results/{}", result_id("invoice"));
+    for _ in 0..3 {
+        send(&router, get(&result_uri)).await;
+    }
+
+    // Embedded once on success, in chunks covering the content
+    let stored = store.chunks.lock().unwrap().clone();
+    assert_eq!(stored.len(), 1);
+    let (tenant, chunks) = &stored[&result_id("invoice")];
+    assert_eq!(tenant.as_str(), "default");
+    assert_eq!(fixture_content("invoice"), "Contoso Ltd. INV-100 2024-04-30 $110.00");
+    let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.text.as_str()).collect();
+    assert_eq!(texts, ["Contoso Ltd.", "Ltd. INV-100", "2024-04-30", "$110.00"]);
+    for (index, chunk) in chunks.iter().enumerate() {
+        assert_eq!(chunk.index as usize, index);
+        assert_eq!(chunk.embedding, WordEmbeddings::vector(&chunk.text));
+    }
+}
+
+#[tokio::test]
+async fn test_semantic_search() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        embeddings: Some((Arc::new(WordEmbeddings), Arc::new(InMemoryOperationTracker::new()), ChunkingPolicy::default())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+    for (name, route) in [("read", "read"), ("invoice", "invoice"), ("receipt", "receipt")] {
+        let submit = post_json(&format!("/api/v1/analyze/{}", route), json!({ "document_url": "https://example.com/doc.pdf" }));
+        let (status, _) = send(&router, submit).await;
+        assert_eq!(status, StatusCode::OK);
+        for _ in 0..2 {
+            send(&router, get(&format!("/api/v1/results/{}", result_id(name)))).await;
+        }
+    }
+
+    let search = json!({ "query": "Contoso quarterly report", "top_k": 2 });
+    let (status, body) = send(&router, post_json("/api/v1/search/semantic", search)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["query"], "Contoso quarterly report");
+    let results = body["results"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["operation_id"], result_id("read"));
+    assert_eq!(results[0]["page_number"], 1);
+    assert_eq!(results[0]["chunk_index"], 0);
+    assert_eq!(results[0]["snippet"], fixture_content("read"));
+    assert_ne!(results[1]["operation_id"], result_id("read"));
+    assert!(results[0]["score"].as_f64().unwrap() > results[1]["score"].as_f64().unwrap());
+
+    // Other tenants' chunks are not searched
+    let mut other_tenant = post_json("/api/v1/search/semantic", json!({ "query": "Contoso" }));
+    other_tenant.headers_mut().insert("x-tenant-id", "acme".parse().unwrap());
+    let (status, body) = send(&router, other_tenant).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["results"], json!([]));
+
+    let (status, _) = send(&router, post_json("/api/v1/search/semantic", json!({ "query": " " }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = send(&router, post_json("/api/v1/search/semantic", json!({ "query": "x", "top_k": 500 }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Without embeddings there is nothing to search
+    let harness = Harness::in_memory().await;
+    let router = create_rest_router(harness.service.clone());
+    let (status, _) = send(&router, post_json("/api/v1/search/semantic", json!({ "query": "Contoso" }))).await;
+    assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+#[tokio::test]
+async fn test_async_jobs() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        job_queue: Some(Arc::new(InMemoryOperationTracker::new())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+
+    let response = router
+        .clone()
+        .oneshot(post_json(
+            "/api/v1/analyze/invoice?mode=async",
+            json!({ "document_url": "https://example.com/doc.pdf" }),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let location = response.headers()["location"].to_str().unwrap().to_string();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let queued: Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(queued["status"], "queued");
+    assert_eq!(queued["model_type"], "prebuilt-invoice");
+    assert!(queued.get("operation_id").is_none());
+    assert_eq!(location, format!("/api/v1/operations/jobs/{}", queued["job_id"].as_str().unwrap()));
+
+    // Nothing reaches Azure until a worker picks the job up
+    let (status, job) = send(&router, get(&location)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(job["status"], "queued");
+    assert!(harness.stub.server.received_requests().await.unwrap().is_empty());
+
+    assert!(harness.service.run_next_job().await.unwrap());
+    assert!(!harness.service.run_next_job().await.unwrap());
+    let (_, job) = send(&router, get(&location)).await;
+    assert_eq!(job["status"], "submitted");
+    assert_eq!(job["attempts"], 1);
+    assert_eq!(job["operation_id"], result_id("invoice"));
+    assert_eq!(job["operation_status"], "running");
+    let (status, _) = send(&router, get(job["result_url"].as_str().unwrap())).await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Uploads are stored when queued and read back by the worker
+    let boundary = "adi-boundary";
+    let upload = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"scan.pdf\"\r\n\
+         Content-Type: application/pdf\r\n\r\n%PDF-1.7 test\r\n--{b}--\r\n",
+        b = boundary
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/read?mode=async")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(upload))
+        .unwrap();
+    let (status, queued) = send(&router, request).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(queued["filename"], "scan.pdf");
+    assert!(queued["document_id"].is_string());
+    assert!(harness.service.run_next_job().await.unwrap());
+    let (_, job) = send(&router, get(queued["status_url"].as_str().unwrap())).await;
+    assert_eq!(job["status"], "submitted");
+    assert_eq!(job["operation_id"], result_id("read"));
+
+    let (status, _) = send(&router, get("/api/v1/operations/jobs/no-such-job")).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_async_refused_where_jobs_cannot_carry_the_model() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        job_queue: Some(Arc::new(InMemoryOperationTracker::new())),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+    let document = json!({ "document_url": "https://example.com/doc.pdf" });
+
+    for uri in ["/api/v1/analyze/custom/my-model?mode=async", "/api/v1/analyze/auto?mode=async"] {
+        let (status, body) = send(&router, post_json(uri, document.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        assert!(body["error"].as_str().unwrap().contains("cannot be queued"));
+    }
+
+    let boundary = "adi-boundary";
+    let upload = format!(
+        "--{b}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"scan.pdf\"\r\n\
+         Content-Type: application/pdf\r\n\r\n%PDF-1.7 test\r\n--{b}--\r\n",
+        b = boundary
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/api/v1/upload/auto?mode=async")
+        .header("content-type", format!("multipart/form-data; boundary={}", boundary))
+        .body(Body::from(upload))
+        .unwrap();
+    let (status, body) = send(&router, request).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["error"].as_str().unwrap().contains("cannot be queued"));
+
+    assert!(!harness.service.run_next_job().await.unwrap());
+    assert!(harness.stub.server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_job_priorities() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        job_queue: Some(Arc::new(InMemoryOperationTracker::new())),
+        ..Default::default()
+    })
+    .await;
+    let options = RestOptions {
+        priorities: PriorityPolicy::new([("backfill-key".to_string(), JobPriority::Batch)]),
+        ..RestOptions::default()
+    };
+    let router = create_rest_router_with_options(harness.service.clone(), options);
+    let document = json!({ "document_url": "https://example.com/doc.pdf" });
+
+    let (status, batch) = send(&router, post_json("/api/v1/analyze/read?mode=async&priority=batch", document.clone())).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(batch["priority"], "batch");
+
+    // The backfill key can't jump the queue by asking
+    let mut request = post_json("/api/v1/analyze/read?mode=async&priority=interactive", document.clone());
+    request.headers_mut().insert("x-api-key", "backfill-key".parse().unwrap());
+    let (_, capped) = send(&router, request).await;
+    assert_eq!(capped["priority"], "batch");
+
+    let (_, interactive) = send(&router, post_json("/api/v1/analyze/invoice?mode=async", document.clone())).await;
+    assert_eq!(interactive["priority"], "interactive");
+
+    // The newest job runs first because it is the only interactive one
+    assert!(harness.service.run_next_job().await.unwrap());
+    let (_, job) = send(&router, get(interactive["status_url"].as_str().unwrap())).await;
+    assert_eq!(job["status"], "submitted");
+    let (_, job) = send(&router, get(batch["status_url"].as_str().unwrap())).await;
+    assert_eq!(job["status"], "queued");
+
+    let (status, _) = send(&router, post_json("/api/v1/analyze/read?mode=async&priority=urgent", document)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_job_retries_and_dead_letters() {
+    let harness = Harness::in_memory_with(HarnessOptions {
+        job_queue: Some(Arc::new(InMemoryOperationTracker::new())),
+        job_retry: Some(JobRetryPolicy {
+            max_attempts: 2,
+            base_delay_secs: 0,
+            max_delay_secs: 0,
+        }),
+        ..Default::default()
+    })
+    .await;
+    let router = create_rest_router(harness.service.clone());
+    Mock::given(method("POST"))
+        .and(path("/documentintelligence/documentModels/prebuilt-layout:analyze"))
+        .respond_with(ResponseTemplate::new(503))
+        .up_to_n_times(2)
+        .with_priority(1)
+        .mount(&harness.stub.server)
+        .await;
+
+    let (_, queued) = send(
+        &router,
+        post_json("/api/v1/analyze/layout?mode=async", json!({ "document_url": "https://example.com/doc.pdf" })),
+    )
+    .await;
+    let job_url = queued["status_url"].as_str().unwrap().to_string();
+
+    // A throttled submission is retried, then dead-lettered once attempts run out
+    assert!(harness.service.run_next_job().await.unwrap());
+    let (_, job) = send(&router, get(&job_url)).await;
+    assert_eq!((job["status"].as_str(), job["attempts"].as_u64()), (Some("queued"), Some(1)));
+    assert!(job["error"].as_str().unwrap().contains("503"));
+    assert!(job["next_attempt_at"].is_string());
+    assert!(harness.service.run_next_job().await.unwrap());
+    assert!(!harness.service.run_next_job().await.unwrap());
+    let (_, job) = send(&router, get(&job_url)).await;
+    assert_eq!((job["status"].as_str(), job["attempts"].as_u64()), (Some("dead_lettered"), Some(2)));
+
+    let (status, job) = send(&router, post_json(&format!("{}/retry", job_url), Value::Null)).await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!((job["status"].as_str(), job["attempts"].as_u64()), (Some("queued"), Some(0)));
+    assert!(harness.service.run_next_job().await.unwrap());
+    let (_, job) = send(&router, get(&job_url)).await;
+    assert_eq!(job["status"], "submitted");
+    let (status, _) = send(&router, post_json(&format!("{}/retry", job_url), Value::Null)).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+
+    // An operation that fails at Azure sends its job back to the queue
+    let operation_id = result_id("layout");
+    Mock::given(method("GET"))
+        .and(path_regex(format!("/analyzeResults/{}$", operation_id)))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({ "status": "failed" })))
+        .with_priority(1)
+        .mount(&harness.stub.server)
+        .await;
+    let result_url = format!("/api/v1/results/{}", operation_id);
+    send(&router, get(&result_url)).await;
+    send(&router, get(&result_url)).await;
+    let (_, job) = send(&router, get(&job_url)).await;
+    assert_eq!(job["status"], "queued");
+    assert_eq!(job["error"], format!("Operation {} failed", operation_id));
+
+    let (status, job) = send(
+        &router,
+        post_json(&format!("/api/v1/operations/{}/retry", operation_id), Value::Null),
+    )
+    .await;
+    assert_eq!(status, StatusCode::ACCEPTED);
+    assert_eq!(job["attempts"], 0);
+    let (status, _) = send(&router, post_json("/api/v1/operations/unknown/retry", Value::Null)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
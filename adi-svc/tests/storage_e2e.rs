@@ -0,0 +1,163 @@
+//! End-to-end tests against Blob Storage emulated by Azurite, started with testcontainers
+//!
+//! Blob Storage is the object store the service reads from and exports to.
+//! Requires Docker. Run with: cargo test --test storage_e2e -- --ignored
+
+mod common;
+
+use std::sync::Arc;
+
+use base64::{engine::general_purpose, Engine};
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use adi_svc::application::training::TrainingExportService;
+use adi_svc::domain::{FieldCorrection, ModelType, OperationStatus, TenantId};
+use adi_svc::infrastructure::{
+    BlobDatasetWriter, BlobIngestConfig, BlobIngestor, InMemoryOperationTracker, ManagedIdentityCredential, OPERATION_TAG,
+};
+use serde_json::Value;
+use testcontainers_modules::azurite::{Azurite, BLOB_PORT};
+use testcontainers_modules::testcontainers::runners::AsyncRunner;
+
+use common::{minimal_pdf, result_id, Harness};
+
+/// Azurite's well-known development account
+const ACCOUNT: &str = "devstoreaccount1";
+const ACCOUNT_KEY: &str = "Eby8vdM02xNOcqFlqUwJPLlmEtlCDXJ1OUzFT50uSRZ6IFsuFq2UVErCz4I6tq/K1SZFPTOtr/KBHBeksoGMGw==";
+const STORAGE_API_VERSION: &str = "2021-08-06";
+
+/// Account SAS over every blob resource, signed with the development key
+fn account_sas() -> String {
+    let (permissions, services, resource_types, expiry, protocol, version) =
+        ("rwdlacupt", "b", "sco", "2099-01-01T00:00:00Z", "https,http", "2019-12-12");
+    let to_sign = format!(
+        "{}\n{}\n{}\n{}\n\n{}\n\n{}\n{}\n",
+        ACCOUNT, permissions, services, resource_types, expiry, protocol, version
+    );
+    let key = general_purpose::STANDARD.decode(ACCOUNT_KEY).unwrap();
+    let mut mac = Hmac::<Sha256>::new_from_slice(&key).unwrap();
+    mac.update(to_sign.as_bytes());
+    let signature = general_purpose::STANDARD.encode(mac.finalize().into_bytes());
+    url::form_urlencoded::Serializer::new(String::new())
+        .append_pair("sv", version)
+        .append_pair("ss", services)
+        .append_pair("srt", resource_types)
+        .append_pair("sp", permissions)
+        .append_pair("se", expiry)
+        .append_pair("spr", protocol)
+        .append_pair("sig", &signature)
+        .finish()
+}
+
+/// Blob Storage requests made by the test itself, authorized with `sas`
+struct Blobs {
+    client: reqwest::Client,
+    account_url: String,
+    sas: String,
+}
+
+impl Blobs {
+    async fn send(&self, request: reqwest::RequestBuilder) -> reqwest::Response {
+        let response = request.header("x-ms-version", STORAGE_API_VERSION).send().await.unwrap();
+        assert!(response.status().is_success(), "Blob Storage returned {}", response.status());
+        response
+    }
+
+    async fn create_container(&self, container: &str) {
+        let url = format!("{}/{}?restype=container&{}", self.account_url, container, self.sas);
+        self.send(self.client.put(url)).await;
+    }
+
+    async fn put(&self, blob: &str, content: Vec<u8>) {
+        let url = format!("{}/{}?{}", self.account_url, blob, self.sas);
+        self.send(self.client.put(url).header("x-ms-blob-type", "BlockBlob").body(content)).await;
+    }
+
+    async fn get(&self, blob: &str, query: &str) -> Vec<u8> {
+        let url = format!("{}/{}?{}{}", self.account_url, blob, query, self.sas);
+        self.send(self.client.get(url)).await.bytes().await.unwrap().to_vec()
+    }
+}
+
+#[tokio::test]
+#[ignore] // Requires Docker
+async fn test_ingest_analyze_and_export_through_blob_storage() {
+    let container = Azurite::default().start().await.unwrap();
+    let blobs = Blobs {
+        client: reqwest::Client::new(),
+        account_url: format!(
+            "http://{}:{}/{}",
+            container.get_host().await.unwrap(),
+            container.get_host_port_ipv4(BLOB_PORT).await.unwrap(),
+            ACCOUNT
+        ),
+        sas: account_sas(),
+    };
+    blobs.create_container("scans").await;
+    blobs.create_container("datasets").await;
+    let pdf = minimal_pdf(1, "");
+    blobs.put("scans/inbox/invoice.pdf", pdf.clone()).await;
+
+    // Submit: the ingestor picks the blob up and tags it with its operation
+    let harness = Harness::in_memory().await;
+    let tenant = TenantId::new("storage").unwrap();
+    let config = BlobIngestConfig {
+        container_url: Some(format!("{}/scans", blobs.account_url)),
+        prefix: "inbox/".to_string(),
+        sas_token: Some(blobs.sas.clone().into()),
+        model: ModelType::Invoice,
+        tenant: tenant.clone(),
+        interval_secs: 60,
+    };
+    let credential = Arc::new(ManagedIdentityCredential::from_env(None));
+    let ingestor = BlobIngestor::from_config(
+        harness.service.clone(),
+        Arc::new(InMemoryOperationTracker::new()),
+        &config,
+        credential.clone(),
+    )
+    .unwrap()
+    .unwrap();
+    assert_eq!(ingestor.poll().await.unwrap(), 1);
+    assert_eq!(ingestor.poll().await.unwrap(), 0, "blobs are not analyzed twice");
+
+    let operation_id = result_id("invoice");
+    let tags = String::from_utf8(blobs.get("scans/inbox/invoice.pdf", "comp=tags&").await).unwrap();
+    assert!(tags.contains(&format!("<Key>{}</Key><Value>{}</Value>", OPERATION_TAG, operation_id)));
+
+    // Poll and result
+    harness.service.get_analysis_result(&tenant, &operation_id).await.unwrap();
+    let (operation, result) = harness.service.get_analysis_result(&tenant, &operation_id).await.unwrap();
+    assert_eq!(operation.status, OperationStatus::Succeeded);
+    assert!(result.is_some());
+
+    // Export: the reviewed document and its labels land in the datasets container
+    harness
+        .service
+        .correct_result(
+            &tenant,
+            &operation_id,
+            vec![FieldCorrection { field: "VendorName".to_string(), doc_type: None, value: "Contoso Ltd".to_string() }],
+            Some("reviewer".to_string()),
+        )
+        .await
+        .unwrap();
+    let writer = BlobDatasetWriter::new(
+        &format!("{}/datasets", blobs.account_url),
+        Some(&blobs.sas.clone().into()),
+        credential,
+        "TRAINING_EXPORT_CONTAINER_URL",
+    )
+    .unwrap();
+    let export = TrainingExportService::new(harness.service.clone(), Arc::new(writer), "training");
+    let report = export.export(&tenant).await.unwrap();
+    assert_eq!(report.documents, 1);
+
+    let document = format!("datasets/training/storage/{}.pdf", operation_id);
+    assert_eq!(blobs.get(&document, "").await, pdf);
+    let labels: Value = serde_json::from_slice(&blobs.get(&format!("{}.labels.json", document), "").await).unwrap();
+    assert!(labels["labels"].as_array().unwrap().iter().any(|label| label["label"] == "VendorName"));
+    let fields: Value = serde_json::from_slice(&blobs.get("datasets/training/storage/fields.json", "").await).unwrap();
+    assert!(fields.to_string().contains("VendorName"));
+}